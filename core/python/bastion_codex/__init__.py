@@ -0,0 +1,122 @@
+"""
+bastion_codex: the Rust normalization core from Python.
+
+A thin ctypes wrapper over the C ABI in core/src/ffi.rs, so notebooks run the
+exact normalization `core normalize` does instead of reimplementing it:
+
+    import bastion_codex as bc
+    items = bc.normalize("data/raw/kev.json", "data/raw/nvd/*.json")
+    hot = bc.query(items, "kev and cvss >= 9", sort="-cvss")
+    report = bc.diff(bc.read_items("data/history/last.json"), items)
+
+Build the library first (`cargo build --release` in core/). It is looked up
+at $BASTION_CODEX_LIB, next to this package, then in core/target/release and
+core/target/debug. Items are plain dicts shaped like items.json.
+"""
+from __future__ import annotations
+
+import ctypes
+import json
+import os
+import sys
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+__all__ = ["BastionError", "normalize", "read_items", "query", "diff", "version"]
+
+
+class BastionError(RuntimeError):
+    """An error reported by the Rust core (bad input, unreadable feed, invalid query...)."""
+
+
+def _lib_name() -> str:
+    if sys.platform == "win32":
+        return "bastion_codex_core.dll"
+    if sys.platform == "darwin":
+        return "libbastion_codex_core.dylib"
+    return "libbastion_codex_core.so"
+
+
+def _candidates() -> List[Path]:
+    here = Path(__file__).resolve().parent
+    core = here.parent.parent
+    found = [here / _lib_name()]
+    found += [core / "target" / profile / _lib_name() for profile in ("release", "debug")]
+    env = os.environ.get("BASTION_CODEX_LIB")
+    return ([Path(env)] if env else []) + found
+
+
+def _load() -> ctypes.CDLL:
+    for path in _candidates():
+        if path.exists():
+            lib = ctypes.CDLL(str(path))
+            break
+    else:
+        tried = ", ".join(str(p) for p in _candidates())
+        raise ImportError(
+            f"bastion_codex: native library not found (tried {tried}); run `cargo build --release` in core/"
+        )
+
+    for name, argc in (("bastion_normalize", 2), ("bastion_read_items", 1), ("bastion_query", 3), ("bastion_diff", 2)):
+        fn = getattr(lib, name)
+        fn.argtypes = [ctypes.c_char_p] * argc
+        # c_void_p, not c_char_p: the pointer has to go back to bastion_free
+        fn.restype = ctypes.c_void_p
+    lib.bastion_free.argtypes = [ctypes.c_void_p]
+    lib.bastion_free.restype = None
+    lib.bastion_version.argtypes = []
+    lib.bastion_version.restype = ctypes.c_char_p
+    return lib
+
+
+_LIB = _load()
+
+
+def _arg(value: Optional[Any]) -> Optional[bytes]:
+    if value is None:
+        return None
+    if isinstance(value, (str, os.PathLike)):
+        return os.fsencode(value) if isinstance(value, os.PathLike) else value.encode("utf-8")
+    return json.dumps(value).encode("utf-8")
+
+
+def _call(name: str, *args: Optional[Any]) -> Any:
+    ptr = getattr(_LIB, name)(*[_arg(a) for a in args])
+    if not ptr:
+        raise BastionError(f"{name} returned no result")
+    try:
+        envelope = json.loads(ctypes.string_at(ptr).decode("utf-8"))
+    finally:
+        _LIB.bastion_free(ptr)
+    if "error" in envelope:
+        raise BastionError(envelope["error"])
+    return envelope["ok"]
+
+
+def normalize(kev_path: Optional[str | os.PathLike] = None, nvd_path: Optional[str | os.PathLike] = None) -> List[Dict]:
+    """
+    Merge the KEV catalog and NVD 2.0 feed files (nvd_path may be a glob) into
+    canonical items, exactly as `core normalize --kev ... --nvd ...` with no
+    other options would write them.
+    """
+    return _call("bastion_normalize", kev_path, nvd_path)
+
+
+def read_items(path: str | os.PathLike) -> List[Dict]:
+    """Read an items file in any format normalize writes (JSON, NDJSON, .gz, .zst)."""
+    return _call("bastion_read_items", path)
+
+
+def query(items: List[Dict], expr: Optional[str] = None, sort: Optional[str] = None) -> List[Dict]:
+    """Items matching a `core query` expression, optionally sorted ("cvss", "-cvss", "published:desc")."""
+    return _call("bastion_query", items, expr, sort)
+
+
+def diff(old: List[Dict], new: List[Dict]) -> Dict:
+    """The `core diff --format json` report between two item lists."""
+    return _call("bastion_diff", old, new)
+
+
+def version() -> str:
+    """Version of the loaded native library."""
+    return _LIB.bastion_version().decode("utf-8")
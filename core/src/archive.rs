@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{codex, input};
+
+/* -------------------- Snapshot archive compression -------------------- */
+/*
+Daily codices repeat the same keys, URL prefixes and boilerplate over and over.
+A zstd dictionary trained on individual items captures that shared structure up
+front. The gain is largest for small files (deltas, per-watchlist slices, short
+windows); a full multi-MB snapshot already has enough context on its own and
+compresses about the same either way.
+
+  train-dict  items*.json -> codex.dict   (one training sample per item)
+  compress    file -> file.zst            (optionally with --dict)
+
+Every reader accepts the result directly once --zstd-dict points at the same
+dictionary (see input.rs). Keep old dictionaries around: a frame can only be
+decoded with the dictionary it was written with.
+*/
+
+/// zstd's own default dictionary size (110 KiB)
+pub const DEFAULT_DICT_SIZE: usize = 112_640;
+
+/// Train a dictionary with each canonical item (compact JSON) as one sample.
+pub fn train_dict(inputs: &[PathBuf], max_size: usize) -> Result<(Vec<u8>, usize)> {
+    let mut samples: Vec<Vec<u8>> = Vec::new();
+    for path in inputs {
+        for item in codex::read_items(path)? {
+            samples.push(serde_json::to_vec(&item)?);
+        }
+    }
+    let dict = zstd::dict::from_samples(&samples, max_size)
+        .with_context(|| format!("Failed to train dictionary from {} samples (need a few hundred items at least)", samples.len()))?;
+    Ok((dict, samples.len()))
+}
+
+/// Compress `input` (decompressing it first if already gzip/zstd) into `out`.
+/// Returns (bytes in, bytes out).
+pub fn compress_file(input_path: &Path, out: &Path, dict: Option<&[u8]>, level: i32) -> Result<(u64, u64)> {
+    let mut reader = input::open_input(input_path)?;
+    let file = File::create(out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let writer = BufWriter::new(file);
+
+    let mut enc = match dict {
+        Some(d) => zstd::stream::write::Encoder::with_dictionary(writer, level, d),
+        None => zstd::stream::write::Encoder::new(writer, level),
+    }
+    .with_context(|| "Failed to initialize zstd encoder")?;
+    enc.include_checksum(true)?;
+
+    let bytes_in = io::copy(&mut reader, &mut enc).with_context(|| format!("Failed to compress {}", input_path.display()))?;
+    enc.finish()?.flush()?;
+
+    let bytes_out = fs::metadata(out)?.len();
+    Ok((bytes_in, bytes_out))
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- ATT&CK technique mapping -------------------- */
+/*
+Maps CVEs to MITRE ATT&CK techniques so detection engineering can pivot from a
+vulnerability to the behaviour exploiting it. Fills item.attack_techniques with
+technique IDs ("T1190", "T1059.004"), sorted and deduplicated.
+
+Inputs (sniffed by content, gzip/zstd accepted):
+- CTID Mappings Explorer JSON (e.g. kev-*_attack-*.json):
+    { "mapping_objects": [ { "capability_id": "CVE-2021-44228",
+                             "attack_object_id": "T1190", "mapping_type": "exploitation_technique" } ] }
+  Entries whose status is non_mappable carry no attack_object_id and are skipped.
+- attack_to_cve CSV: "CVE ID", "Primary Impact", "Secondary Impact", "Exploitation Technique",
+  each cell a ';'-separated technique list.
+*/
+
+#[derive(Debug, Deserialize)]
+struct MappingFile {
+    #[serde(default)]
+    mapping_objects: Vec<MappingObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MappingObject {
+    #[serde(default)]
+    capability_id: Option<String>,
+    #[serde(default)]
+    attack_object_id: Option<String>,
+}
+
+type Techniques = HashMap<String, BTreeSet<String>>;
+
+// "T1059.004" or "T1190"; anything else in a cell is a label, not an ID
+fn technique_id(s: &str) -> Option<String> {
+    let s = s.trim();
+    let digits = s.strip_prefix('T')?;
+    let (main, sub) = digits.split_once('.').unwrap_or((digits, ""));
+    let ok = main.len() == 4
+        && main.bytes().all(|b| b.is_ascii_digit())
+        && (sub.is_empty() || (sub.len() == 3 && sub.bytes().all(|b| b.is_ascii_digit())));
+    ok.then(|| s.to_string())
+}
+
+fn parse_json(bytes: &[u8], path: &Path, out: &mut Techniques) -> Result<()> {
+    let file: MappingFile = serde_json::from_slice(bytes)
+        .with_context(|| format!("Failed to parse ATT&CK mappings JSON: {}", path.display()))?;
+    for m in file.mapping_objects {
+        let (Some(cve), Some(technique)) = (m.capability_id, m.attack_object_id.as_deref().and_then(technique_id)) else {
+            continue;
+        };
+        let cve = cve.trim();
+        if cve.starts_with("CVE-") {
+            out.entry(cve.to_string()).or_default().insert(technique);
+        }
+    }
+    Ok(())
+}
+
+fn parse_csv(bytes: &[u8], path: &Path, out: &mut Techniques) -> Result<()> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(bytes);
+    let headers = rdr
+        .headers()
+        .with_context(|| format!("Failed to read ATT&CK mappings CSV header: {}", path.display()))?
+        .clone();
+    let cve_col = headers
+        .iter()
+        .position(|h| h.trim().eq_ignore_ascii_case("CVE ID"))
+        .with_context(|| format!("ATT&CK mappings CSV has no \"CVE ID\" column: {}", path.display()))?;
+
+    for row in rdr.records() {
+        let row = row.with_context(|| format!("Failed to parse ATT&CK mappings CSV: {}", path.display()))?;
+        let Some(cve) = row.get(cve_col).map(str::trim).filter(|c| c.starts_with("CVE-")) else { continue; };
+        let techniques: BTreeSet<String> = row
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != cve_col)
+            .flat_map(|(_, cell)| cell.split(';').filter_map(technique_id))
+            .collect();
+        if !techniques.is_empty() {
+            out.entry(cve.to_string()).or_default().extend(techniques);
+        }
+    }
+    Ok(())
+}
+
+/// Fill `attack_techniques` from a CVE -> ATT&CK mapping dataset.
+/// Returns the number of items mapped to at least one technique.
+pub fn merge_attack(path: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let bytes = input::read_input(path)?;
+    let mut techniques = Techniques::new();
+    match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => parse_json(&bytes, path, &mut techniques)?,
+        _ => parse_csv(&bytes, path, &mut techniques)?,
+    }
+
+    let mut mapped = 0;
+    for item in items.iter_mut() {
+        let Some(found) = techniques.get(&item.id) else { continue; };
+        let merged: BTreeSet<String> = item.attack_techniques.iter().cloned().chain(found.iter().cloned()).collect();
+        item.attack_techniques = merged.into_iter().collect();
+        mapped += 1;
+    }
+    Ok(mapped)
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::{codex, ffi, input, kev, logging::Timer, nvd, source::Source};
+
+/* -------------------- Benchmark -------------------- */
+/*
+`bench --kev kev.json --nvd 'nvdcve-2.0-*.json.gz'` times the stages of a
+plain normalize over the given feeds, --iterations times (default 3), so a
+change to parsing or merging can be measured against the same inputs:
+
+  read       decompress the inputs into memory (input.rs)
+  parse      KEV and NVD sources turning feed JSON into records (source.rs)
+  normalize  the whole normalize pipeline from those bytes: parse, merge,
+             refs, KEV listings, content hashes (what ffi::normalize_feeds does)
+  serialize  write the items as pretty items.json to a temp file
+
+Each stage reports min / median / max wall time and the process's peak
+resident memory once it first finished (VmHWM; Linux only, else n/a).
+normalize includes parse, so their difference is the merge and the passes
+after it. Enrichment options (CSAF, OSV, ...) are not part of the run.
+`backend` names the JSON parser the build uses, for comparing builds.
+*/
+
+pub const DEFAULT_ITERATIONS: usize = 3;
+const BACKEND: &str = "serde_json";
+const PHASES: [&str; 4] = ["read", "parse", "normalize", "serialize"];
+
+#[derive(Debug, Serialize)]
+pub struct PhaseStats {
+    pub phase: &'static str,
+    pub min_secs: f64,
+    pub median_secs: f64,
+    pub max_secs: f64,
+    pub peak_rss_mb: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub backend: &'static str,
+    pub iterations: usize,
+    pub inputs: usize,
+    pub input_bytes: u64, // decompressed
+    pub records: usize,
+    pub items: usize,
+    pub phases: Vec<PhaseStats>,
+    pub peak_rss_mb: Option<u64>,
+}
+
+/// Peak resident set size so far, in MB (Linux /proc only).
+fn peak_rss_mb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+fn median(sorted: &[f64]) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => sorted[n / 2],
+        n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+    }
+}
+
+fn read_all(paths: &[PathBuf]) -> Result<Vec<(String, Vec<u8>)>> {
+    paths.iter().map(|p| Ok((p.display().to_string(), input::read_input(p)?))).collect()
+}
+
+/// Run the stages `iterations` times over the feeds.
+pub fn run(kev_path: Option<&Path>, nvd: &[PathBuf], iterations: usize) -> Result<BenchReport> {
+    let nvd_paths = input::expand_globs(nvd)?;
+    let iterations = iterations.max(1);
+    let out = std::env::temp_dir().join(format!("bastion-bench-{}.json", std::process::id()));
+    let output = codex::OutputOptions { format: codex::OutputFormat::Json, compress: None, compact: false };
+
+    let mut times: [Vec<f64>; 4] = Default::default();
+    let mut peaks: [Option<u64>; 4] = [None; 4];
+    let (mut input_bytes, mut records, mut items_len) = (0u64, 0usize, 0usize);
+    for iteration in 0..iterations {
+        let mut lap = |phase: usize, started: Timer| {
+            times[phase].push(started.secs());
+            if iteration == 0 {
+                peaks[phase] = peak_rss_mb();
+            }
+        };
+
+        let started = Timer::start();
+        let kev_bytes = kev_path.map(input::read_input).transpose()?;
+        let nvd_feeds = read_all(&nvd_paths)?;
+        lap(0, started);
+        let feed_bytes = nvd_feeds.iter().map(|(_, b)| b.len());
+        input_bytes = kev_bytes.iter().map(Vec::len).chain(feed_bytes).sum::<usize>() as u64;
+
+        let feeds_copy = nvd_feeds.clone();
+        let started = Timer::start();
+        let kev = kev_bytes.as_deref().map(|b| kev::KevSource::from_bytes(b, "kev")).transpose()?;
+        records = kev.as_ref().map(|k| k.items()).transpose()?.map_or(0, |r| r.len());
+        if !feeds_copy.is_empty() {
+            records += nvd::NvdSource::from_bytes(feeds_copy).items()?.len();
+        }
+        lap(1, started);
+
+        let started = Timer::start();
+        let items = ffi::normalize_feeds(kev_bytes.as_deref(), nvd_feeds)?;
+        lap(2, started);
+        items_len = items.len();
+
+        let started = Timer::start();
+        codex::write_items(&out, &items, &output)?;
+        lap(3, started);
+        std::fs::remove_file(&out).with_context(|| format!("Failed to remove {}", out.display()))?;
+    }
+
+    let phases = PHASES
+        .iter()
+        .zip(times.iter_mut())
+        .zip(peaks)
+        .map(|((phase, t), peak_rss_mb)| {
+            t.sort_by(f64::total_cmp);
+            PhaseStats {
+                phase,
+                min_secs: t.first().copied().unwrap_or_default(),
+                median_secs: median(t),
+                max_secs: t.last().copied().unwrap_or_default(),
+                peak_rss_mb,
+            }
+        })
+        .collect();
+    Ok(BenchReport {
+        backend: BACKEND,
+        iterations,
+        inputs: kev_path.iter().count() + nvd_paths.len(),
+        input_bytes,
+        records,
+        items: items_len,
+        phases,
+        peak_rss_mb: peak_rss_mb(),
+    })
+}
+
+pub fn print_table(r: &BenchReport) {
+    let mb = |v: Option<u64>| v.map_or("n/a".to_string(), |m| format!("{} MB", m));
+    println!(
+        "{} inputs, {:.1} MB decompressed, {} records -> {} items; {} iterations ({})",
+        r.inputs,
+        r.input_bytes as f64 / (1024.0 * 1024.0),
+        r.records,
+        r.items,
+        r.iterations,
+        r.backend
+    );
+    println!();
+    println!("{:<11}  {:>9}  {:>9}  {:>9}  {:>9}", "phase", "min", "median", "max", "peak rss");
+    for p in &r.phases {
+        println!(
+            "{:<11}  {:>8.3}s  {:>8.3}s  {:>8.3}s  {:>9}",
+            p.phase,
+            p.min_secs,
+            p.median_secs,
+            p.max_secs,
+            mb(p.peak_rss_mb)
+        );
+    }
+    println!();
+    println!("Peak RSS        {}", mb(r.peak_rss_mb));
+}
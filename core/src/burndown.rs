@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{collections::HashMap, io::Write};
+
+use crate::{severity, snapshot};
+
+/* -------------------- SLA burn-down -------------------- */
+/*
+`burn-down --store DIR` turns the snapshot store (see snapshot.rs) into the
+daily series behind a remediation burn-down chart: for each day from --since
+(default: the day of the first run) to --until (default: today), the last run
+taken by the end of that day (UTC), and per severity bucket
+
+  open         KEV-listed items in that run
+  within_sla   open, due date (kev_due_date, BOD 22-01) not yet passed that day
+  breached     open, due date passed
+  no_due_date  open, no parseable due date (see overdue.rs)
+
+Feeds carry no remediation state, so "open" means still listed: an item drops
+out when CISA delists it or it leaves the store's runs. Every bucket of the
+severity policy gets a row each day, zeros included, so a chart doesn't have
+gaps; buckets outside the policy follow. Days before the first run are
+skipped.
+
+--format csv (default) writes date,run,severity,open,within_sla,breached,
+no_due_date rows; json writes the same rows under {"since", "until", "days"}.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BurnDownFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BurnDownRow {
+    pub date: String,
+    pub run: String, // taken_at of the run the day is read from
+    pub severity: String,
+    pub open: usize,
+    pub within_sla: usize,
+    pub breached: usize,
+    pub no_due_date: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BurnDown {
+    pub since: String,
+    pub until: String,
+    pub days: Vec<BurnDownRow>,
+}
+
+/// A run's open (KEV-listed) items: severity bucket and due date.
+type Open = Vec<(String, Option<NaiveDate>)>;
+
+fn end_of_day(day: NaiveDate) -> DateTime<Utc> {
+    DateTime::from_naive_utc_and_offset(day.and_hms_opt(23, 59, 59).expect("valid time"), Utc)
+}
+
+/// The series over `since..=until`; None bounds default as described above.
+pub fn from_store(store: &snapshot::Store, since: Option<NaiveDate>, until: Option<NaiveDate>) -> Result<BurnDown> {
+    let runs = store.runs()?;
+    let first_day = runs.first().and_then(|r| crate::parse_iso_datetime(&r.taken_at)).map(|t| t.date_naive());
+    let until = until.unwrap_or_else(|| Utc::now().date_naive());
+    let since = since.or(first_day).unwrap_or(until);
+    let mut report = BurnDown { since: since.to_string(), until: until.to_string(), days: Vec::new() };
+    let names = severity::names();
+
+    // Item versions recur across runs: each is read once
+    let mut versions: HashMap<String, Option<(String, Option<NaiveDate>)>> = HashMap::new();
+    let mut current: Option<(snapshot::RunInfo, Open)> = None;
+    for day in since.iter_days().take_while(|d| *d <= until) {
+        let Some(run) = store.run_as_of(end_of_day(day))? else { continue };
+        if current.as_ref().is_none_or(|(r, _)| r.taken_at != run.taken_at) {
+            let mut open = Vec::new();
+            for hash in store.run_items(&run)?.into_values() {
+                if !versions.contains_key(&hash) {
+                    let item = store.object(&hash)?;
+                    let due = item.kev_due_date.as_deref();
+                    let due = due.and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok());
+                    versions.insert(hash.clone(), item.kev.then_some((item.severity_bucket, due)));
+                }
+                open.extend(versions[&hash].clone());
+            }
+            current = Some((run, open));
+        }
+        let (run, open) = current.as_ref().context("no run loaded")?;
+
+        let mut rows: Vec<BurnDownRow> = names.iter().map(|n| row(day, run, n)).collect();
+        for (bucket, due) in open {
+            let i = match rows.iter().position(|r| r.severity == *bucket) {
+                Some(i) => i,
+                None => {
+                    rows.push(row(day, run, bucket));
+                    rows.len() - 1
+                }
+            };
+            let r = &mut rows[i];
+            r.open += 1;
+            match due {
+                Some(due) if day > *due => r.breached += 1,
+                Some(_) => r.within_sla += 1,
+                None => r.no_due_date += 1,
+            }
+        }
+        report.days.extend(rows);
+    }
+    Ok(report)
+}
+
+fn row(day: NaiveDate, run: &snapshot::RunInfo, severity: &str) -> BurnDownRow {
+    BurnDownRow {
+        date: day.to_string(),
+        run: run.taken_at.clone(),
+        severity: severity.to_string(),
+        open: 0,
+        within_sla: 0,
+        breached: 0,
+        no_due_date: 0,
+    }
+}
+
+pub fn write_csv(report: &BurnDown, out: impl Write) -> Result<()> {
+    let mut w = csv::Writer::from_writer(out);
+    for row in &report.days {
+        w.serialize(row)?;
+    }
+    w.flush()?;
+    Ok(())
+}
@@ -0,0 +1,446 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::{cmp::Ordering, collections::HashMap, io::Write, path::Path};
+
+use crate::{cpe::AffectedCpe, diff::severity_rank, input, logging, CanonicalItem};
+
+/* -------------------- CI dependency check -------------------- */
+/*
+`check` answers "does this build pull in anything in the codex": components are
+read from an SBOM or lockfile and matched against each item's vulnerable CPEs
+(item.affected, from NVD configurations).
+
+Inputs, detected by content:
+- CycloneDX JSON (components[], nested components followed)
+- SPDX JSON (packages[])
+- in-toto Statement whose predicate is one of the above, bare or DSSE-wrapped
+- Cargo.lock, package-lock.json (v2/v3 "packages" map)
+
+A component matches a CPE when the CPE product equals the component name
+(case-insensitive, '-' and '_' treated alike) and the version is inside the CPE
+range, or equals the CPE's fixed version when it names one. Version comparison is
+segment-wise, numeric where both segments are numbers; prereleases (1.2.3-rc1)
+sort before their release, as in semver.
+
+A match is a violation when the item is at or above --fail-on, or is in KEV and
+--fail-on-kev is set.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CiFormat {
+    /// Plain summary
+    None,
+    /// GitHub Actions workflow commands (::error::) plus $GITHUB_STEP_SUMMARY
+    Github,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub component: String,
+    pub version: String,
+    pub id: String,
+    pub severity: String,
+    pub cvss: Option<f64>,
+    pub kev: bool,
+    pub cpe: String,
+    pub violation: bool,
+}
+
+/* ---- component extraction ---- */
+
+fn push(out: &mut Vec<Component>, name: Option<&str>, version: Option<&str>) {
+    if let (Some(name), Some(version)) = (name, version)
+        && !name.trim().is_empty()
+        && !version.trim().is_empty()
+    {
+        out.push(Component { name: name.trim().to_string(), version: version.trim().to_string() });
+    }
+}
+
+fn cyclonedx_components(list: &Value, out: &mut Vec<Component>) {
+    for c in list.as_array().into_iter().flatten() {
+        push(out, c.get("name").and_then(Value::as_str), c.get("version").and_then(Value::as_str));
+        if let Some(nested) = c.get("components") {
+            cyclonedx_components(nested, out);
+        }
+    }
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn json_components(doc: &Value, path: &Path) -> Result<Vec<Component>> {
+    // DSSE envelope around an in-toto statement
+    if let Some(payload) = doc.get("payload").and_then(Value::as_str) {
+        let bytes = base64_decode(payload).with_context(|| format!("Invalid DSSE payload in {}", path.display()))?;
+        let inner: Value = serde_json::from_slice(&bytes)
+            .with_context(|| format!("DSSE payload in {} is not JSON", path.display()))?;
+        return json_components(&inner, path);
+    }
+    // in-toto Statement: the SBOM is the predicate
+    if doc.get("_type").and_then(Value::as_str).is_some_and(|t| t.contains("in-toto.io"))
+        && let Some(predicate) = doc.get("predicate")
+    {
+        return json_components(predicate, path);
+    }
+
+    let mut out = Vec::new();
+    if doc.get("bomFormat").and_then(Value::as_str) == Some("CycloneDX") {
+        cyclonedx_components(doc.get("components").unwrap_or(&Value::Null), &mut out);
+    } else if doc.get("spdxVersion").is_some() {
+        for p in doc.get("packages").and_then(Value::as_array).into_iter().flatten() {
+            push(&mut out, p.get("name").and_then(Value::as_str), p.get("versionInfo").and_then(Value::as_str));
+        }
+    } else if let Some(packages) = doc.get("packages").and_then(Value::as_object) {
+        // package-lock.json v2/v3: "node_modules/a/node_modules/b" -> b
+        for (key, p) in packages {
+            let name = p.get("name").and_then(Value::as_str).or_else(|| key.rsplit("node_modules/").next());
+            push(&mut out, name.filter(|_| !key.is_empty()), p.get("version").and_then(Value::as_str));
+        }
+    } else {
+        bail!("{}: not a CycloneDX/SPDX SBOM, in-toto statement or package-lock.json", path.display());
+    }
+    Ok(out)
+}
+
+/// Cargo.lock: [[package]] tables with name/version lines.
+fn cargo_lock_components(text: &str) -> Vec<Component> {
+    let mut out = Vec::new();
+    let (mut name, mut version): (Option<&str>, Option<&str>) = (None, None);
+    for line in text.lines().map(str::trim).chain(["[[package]]"]) {
+        if line == "[[package]]" {
+            push(&mut out, name.take(), version.take());
+        } else if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "name" => name = Some(value),
+                "version" => version = Some(value),
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+pub fn load_components(path: &Path) -> Result<Vec<Component>> {
+    let bytes = input::read_input(path)?;
+    let mut components = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(doc) => json_components(&doc, path)?,
+        Err(_) => {
+            let text = String::from_utf8_lossy(&bytes);
+            if !text.contains("[[package]]") {
+                bail!("{}: not JSON and not a Cargo.lock", path.display());
+            }
+            cargo_lock_components(&text)
+        }
+    };
+    components.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    components.dedup();
+    Ok(components)
+}
+
+/* ---- matching ---- */
+
+fn norm(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+// Alphanumeric parts compare by letters, then trailing number: rc2 < rc10
+fn compare_part(x: &str, y: &str) -> Ordering {
+    match (x.parse::<u64>(), y.parse::<u64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        // semver: numeric identifiers rank below alphanumeric ones
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => {
+            let key = |s: &str| {
+                let letters = s.trim_end_matches(|c: char| c.is_ascii_digit());
+                (letters.to_string(), s[letters.len()..].parse::<u64>().ok())
+            };
+            key(x).cmp(&key(y)).then_with(|| x.cmp(y))
+        }
+    }
+}
+
+/// Segment-wise version order, semver-style: build metadata (+...) is ignored,
+/// a missing segment counts as 0, and a prerelease segment sorts below a missing
+/// one, so 1.2.3-rc1 < 1.2.3 < 1.2.3-1 (a Debian-style revision).
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| {
+        let v = v.trim_start_matches('v');
+        v.split('+').next().unwrap_or(v).split(['.', '-', '_']).map(str::to_string).collect::<Vec<_>>()
+    };
+    // A segment against a missing one
+    let alone = |x: &str| x.parse::<u64>().map_or(Ordering::Less, |n| n.cmp(&0));
+    let (a, b) = (split(a), split(b));
+    for i in 0..a.len().max(b.len()) {
+        let ord = match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => compare_part(x, y),
+            (Some(x), None) => alone(x),
+            (None, Some(y)) => alone(y).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+fn in_range(cpe: &AffectedCpe, cpe_version: &str, version: &str) -> bool {
+    if cpe_version != "*" && cpe_version != "-" {
+        return compare_versions(version, cpe_version) == Ordering::Equal;
+    }
+    let cmp = |bound: &Option<String>| bound.as_deref().map(|b| compare_versions(version, b));
+    cmp(&cpe.version_start_including).is_none_or(|o| o != Ordering::Less)
+        && cmp(&cpe.version_start_excluding).is_none_or(|o| o == Ordering::Greater)
+        && cmp(&cpe.version_end_including).is_none_or(|o| o != Ordering::Greater)
+        && cmp(&cpe.version_end_excluding).is_none_or(|o| o == Ordering::Less)
+}
+
+pub fn run(items: &[CanonicalItem], components: &[Component], fail_on: &str, fail_on_kev: bool) -> Vec<Finding> {
+    let threshold = severity_rank(fail_on);
+    let mut by_name: HashMap<String, Vec<&Component>> = HashMap::new();
+    for c in components {
+        by_name.entry(norm(&c.name)).or_default().push(c);
+    }
+
+    let mut findings = Vec::new();
+    for item in items {
+        for cpe in item.affected.iter().filter(|a| a.vulnerable) {
+            // cpe:2.3:part:vendor:product:version:...
+            let parts: Vec<&str> = cpe.cpe23_uri.split(':').collect();
+            let (Some(product), Some(cpe_version)) = (parts.get(4), parts.get(5)) else { continue; };
+            for c in by_name.get(&norm(product)).into_iter().flatten() {
+                if !in_range(cpe, cpe_version, &c.version) {
+                    continue;
+                }
+                let violation = (threshold > 0 && severity_rank(&item.severity_bucket) >= threshold) || (fail_on_kev && item.kev);
+                findings.push(Finding {
+                    component: c.name.clone(),
+                    version: c.version.clone(),
+                    id: item.id.clone(),
+                    severity: item.severity_bucket.clone(),
+                    cvss: item.cvss,
+                    kev: item.kev,
+                    cpe: cpe.cpe23_uri.clone(),
+                    violation,
+                });
+            }
+        }
+    }
+    // One finding per (component, version, CVE); several ranges of one CVE can match
+    findings.sort_by(|a, b| (&a.component, &a.version, &a.id).cmp(&(&b.component, &b.version, &b.id)));
+    findings.dedup_by(|a, b| (&a.component, &a.version, &a.id) == (&b.component, &b.version, &b.id));
+    findings
+}
+
+/* ---- reporting ---- */
+
+fn describe(f: &Finding) -> String {
+    let cvss = f.cvss.map_or("n/a".to_string(), |s| format!("{:.1}", s));
+    let kev = if f.kev { ", KEV" } else { "" };
+    format!("{}@{} is affected by {} ({}, CVSS {}{})", f.component, f.version, f.id, f.severity, cvss, kev)
+}
+
+/// GitHub workflow commands need %, CR and LF escaped in messages.
+pub fn gh_escape(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+pub fn report(findings: &[Finding], components: usize, source: &Path, ci: CiFormat) -> Result<()> {
+    let violations = findings.iter().filter(|f| f.violation).count();
+    for f in findings {
+        match ci {
+            CiFormat::Github => {
+                let level = if f.violation { "error" } else { "warning" };
+                println!("::{} file={},title={}::{}", level, source.display(), f.id, gh_escape(&describe(f)));
+            }
+            CiFormat::None => {
+                let tag = if f.violation { "[FAIL]" } else { "[WARN]" };
+                println!("{} {}", tag, describe(f));
+            }
+        }
+    }
+    logging::emit(
+        if violations > 0 { logging::Level::Fail } else { logging::Level::Ok },
+        format_args!("check: {} components, {} findings, {} violations", components, findings.len(), violations),
+    );
+
+    if ci == CiFormat::Github
+        && let Ok(summary_path) = std::env::var("GITHUB_STEP_SUMMARY")
+    {
+        let mut md = format!(
+            "## bastion-core check\n\n{} components, {} findings, **{} violations**\n\n",
+            components,
+            findings.len(),
+            violations
+        );
+        if !findings.is_empty() {
+            md.push_str("| Component | Version | CVE | Severity | CVSS | KEV | Violation |\n|---|---|---|---|---|---|---|\n");
+            for f in findings {
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} | {} |\n",
+                    f.component,
+                    f.version,
+                    f.id,
+                    f.severity,
+                    f.cvss.map_or(String::new(), |s| format!("{:.1}", s)),
+                    if f.kev { "yes" } else { "" },
+                    if f.violation { "yes" } else { "" }
+                ));
+            }
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&summary_path)
+            .with_context(|| format!("Failed to open GITHUB_STEP_SUMMARY: {}", summary_path))?;
+        file.write_all(md.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn component(name: &str, version: &str) -> Component {
+        Component { name: name.to_string(), version: version.to_string() }
+    }
+
+    #[test]
+    fn versions_compare_like_semver() {
+        use Ordering::*;
+        for (a, b, ord) in [
+            ("1.2.3", "1.2.3", Equal),
+            ("v1.2.3", "1.2.3.0", Equal),
+            ("1.10.0", "1.9.9", Greater),
+            ("1.2.3-rc1", "1.2.3", Less),
+            ("1.2.3-rc.1", "1.2.3", Less),
+            ("1.2.3-alpha", "1.2.3-beta", Less),
+            ("1.2.3-rc2", "1.2.3-rc10", Less),
+            ("1.2.3-1", "1.2.3-alpha", Less),
+            ("1.2.3-1", "1.2.3", Greater),
+            ("1.2.3+build.5", "1.2.3", Equal),
+            ("1.2", "1.2.0-rc1", Greater),
+        ] {
+            assert_eq!(compare_versions(a, b), ord, "{} vs {}", a, b);
+            assert_eq!(compare_versions(b, a), ord.reverse(), "{} vs {}", b, a);
+        }
+    }
+
+    fn cpe(version: &str, bounds: [Option<&str>; 4]) -> AffectedCpe {
+        let [start_in, start_ex, end_in, end_ex] = bounds.map(|b| b.map(str::to_string));
+        AffectedCpe {
+            cpe23_uri: format!("cpe:2.3:a:example:x:{}:*:*:*:*:*:*:*", version),
+            version_start_including: start_in,
+            version_start_excluding: start_ex,
+            version_end_including: end_in,
+            version_end_excluding: end_ex,
+            vulnerable: true,
+        }
+    }
+
+    #[test]
+    fn ranges_hold_their_bounds() {
+        let excluding = cpe("*", [Some("1.0.0"), None, None, Some("1.2.3")]);
+        assert!(in_range(&excluding, "*", "1.0.0"));
+        assert!(in_range(&excluding, "*", "1.2.3-rc1"));
+        assert!(!in_range(&excluding, "*", "1.2.3"));
+        assert!(!in_range(&excluding, "*", "0.9"));
+        let including = cpe("*", [None, Some("1.0"), Some("2.0"), None]);
+        assert!(!in_range(&including, "*", "1.0.0"));
+        assert!(in_range(&including, "*", "2.0.0"));
+        assert!(!in_range(&including, "*", "2.0.1"));
+        // A versioned CPE names one release
+        assert!(in_range(&cpe("1.4", [None; 4]), "1.4", "1.4.0"));
+        assert!(!in_range(&cpe("1.4", [None; 4]), "1.4", "1.4.1"));
+    }
+
+    #[test]
+    fn prereleases_are_flagged() {
+        let item = CanonicalItem {
+            id: "CVE-2099-0001".to_string(),
+            severity_bucket: "high".to_string(),
+            affected: vec![cpe("*", [None, None, None, Some("1.2.3")])],
+            ..Default::default()
+        };
+        let components = [component("x", "1.2.3-rc1"), component("x", "1.2.3")];
+        let findings = run(&[item], &components, "high", false);
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].version.as_str(), findings[0].violation), ("1.2.3-rc1", true));
+    }
+
+    fn parse(doc: Value) -> Vec<Component> {
+        json_components(&doc, Path::new("sbom.json")).unwrap()
+    }
+
+    #[test]
+    fn reads_sboms_and_lockfiles() {
+        let cyclonedx = json!({"bomFormat": "CycloneDX", "components": [
+            {"name": "a", "version": "1.0", "components": [{"name": "b", "version": "2.0"}]},
+            {"name": "no-version"},
+        ]});
+        assert_eq!(parse(cyclonedx), [component("a", "1.0"), component("b", "2.0")]);
+
+        let spdx = json!({"spdxVersion": "SPDX-2.3", "packages": [{"name": "c", "versionInfo": " 3.1 "}]});
+        assert_eq!(parse(spdx.clone()), [component("c", "3.1")]);
+        let statement = json!({"_type": "https://in-toto.io/Statement/v1", "predicate": spdx});
+        assert_eq!(parse(statement), [component("c", "3.1")]);
+
+        let dsse = json!({"payloadType": "application/vnd.in-toto+json", "payload": concat!(
+            "eyJfdHlwZSI6Imh0dHBzOi8vaW4tdG90by5pby9TdGF0ZW1lbnQvdjEiLCJwcmVkaWNhdGVUeXBlIjoiaHR0cHM6",
+            "Ly9zcGR4LmRldi9Eb2N1bWVudCIsInByZWRpY2F0ZSI6eyJzcGR4VmVyc2lvbiI6IlNQRFgtMi4zIiwicGFja2Fn",
+            "ZXMiOlt7Im5hbWUiOiJvcGVuc3NsIiwidmVyc2lvbkluZm8iOiIzLjAuNyJ9XX19",
+        )});
+        assert_eq!(parse(dsse), [component("openssl", "3.0.7")]);
+        let bad = json!({"payload": "not base64!"});
+        assert!(json_components(&bad, Path::new("x")).unwrap_err().to_string().contains("Invalid DSSE payload"));
+
+        let lock = json!({"lockfileVersion": 3, "packages": {
+            "": {"name": "app", "version": "0.1.0"},
+            "node_modules/left-pad": {"version": "1.3.0"},
+            "node_modules/a/node_modules/b": {"version": "2.1.0"},
+        }});
+        let mut npm = parse(lock);
+        npm.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(npm, [component("b", "2.1.0"), component("left-pad", "1.3.0")]);
+
+        let cargo = "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\nsource = \"registry\"\n\n\
+                     [[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\n \"serde\",\n]\n";
+        assert_eq!(cargo_lock_components(cargo), [component("serde", "1.0.200"), component("app", "0.1.0")]);
+
+        assert!(json_components(&json!({"name": "x"}), Path::new("x.json")).is_err());
+        assert_eq!(base64_decode("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(base64_decode("a*"), None);
+    }
+}
@@ -0,0 +1,672 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use serde_json::Value;
+use std::{
+    fs::File,
+    collections::{HashMap, HashSet},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{errors::Failure, input, refs, severity, source::KevListing, CanonicalItem};
+
+/* -------------------- Reading canonical outputs -------------------- */
+/*
+Every command that consumes items.json goes through read_items(), which accepts
+older layouts and upgrades them in memory. Archived snapshots keep working after
+a schema bump without a separate migration step; `core migrate --from 1 --to 2`
+rewrites one on disk for consumers that read the files directly.
+
+Layouts:
+- v1: bare JSON array of items (original bastion-core output)
+- vN: { "schema_version": N, "items": [ ... ] }; normalize writes this since v2
+- NDJSON: one item object per line (normalize --format ndjson); appendable
+
+v2 turned refs from bare URL strings into typed { url, kind, source } entries.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON array, pretty-printed unless --compact
+    Json,
+    /// One compact item per line
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// How a command writes items.json; flattened into normalize, merge and score.
+#[derive(Debug, Clone, Copy, Args)]
+pub struct OutputOptions {
+    /// Output layout: pretty JSON array or one item per line
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+    /// Compress the output; readers detect it from the bytes, so the name is up to you (items.json.gz)
+    #[arg(long, value_enum)]
+    pub compress: Option<Compression>,
+    /// Write --format json without indentation (about a third smaller)
+    #[arg(long)]
+    pub compact: bool,
+}
+
+/// Newest schema version this binary understands, and the one it writes.
+pub const SCHEMA_VERSION: u32 = 2;
+
+fn detect(root: Value) -> Result<(u32, Vec<Value>)> {
+    match root {
+        Value::Array(items) => Ok((1, items)),
+        Value::Object(mut obj) => {
+            let version = obj
+                .get("schema_version")
+                .and_then(|v| v.as_u64())
+                .context("Canonical envelope is missing schema_version")? as u32;
+            let Some(Value::Array(items)) = obj.remove("items") else {
+                bail!("Canonical envelope has no items array");
+            };
+            Ok((version, items))
+        }
+        _ => bail!("Canonical items must be an array or a versioned envelope"),
+    }
+}
+
+/// v1 items may predate `severity_bucket` being required; derive it from cvss.
+fn upgrade_v1(item: &mut Value) {
+    let Some(obj) = item.as_object_mut() else { return; };
+    let missing = obj.get("severity_bucket").and_then(|v| v.as_str()).is_none_or(str::is_empty);
+    if missing {
+        let cvss = obj.get("cvss").and_then(|v| v.as_f64());
+        let kev = obj.get("kev").and_then(|v| v.as_bool()).unwrap_or(false);
+        obj.insert("severity_bucket".to_string(), Value::String(severity::bucket(cvss, kev)));
+    }
+    if !obj.contains_key("sources") {
+        let kev = obj.get("kev").and_then(|v| v.as_bool()).unwrap_or(false);
+        let sources = if kev { vec!["nvd", "kev"] } else { vec!["nvd"] };
+        obj.insert("sources".to_string(), sources.into());
+    }
+}
+
+/// v1 -> v2: classify bare URL refs. The contributing feed wasn't recorded, so it
+/// stays "unknown". Entries that are already objects are left alone, which keeps
+/// this safe on NDJSON lines (they carry no version and are always upgraded).
+fn upgrade_v2(item: &mut Value) {
+    let Some(Value::Array(links)) = item.get_mut("refs") else { return; };
+    for link in links.iter_mut() {
+        if let Value::String(url) = link {
+            let kind = refs::classify_url(url);
+            *link = serde_json::json!({ "url": url, "kind": kind, "source": "unknown" });
+        }
+    }
+}
+
+/// Upgrade raw items from `version` to SCHEMA_VERSION, one step at a time.
+pub fn upgrade_items(items: &mut [Value], version: u32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        bail!(
+            "items use schema_version {} but this bastion-core only understands up to {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    if version <= 1 {
+        items.iter_mut().for_each(upgrade_v1);
+    }
+    if version < 2 {
+        items.iter_mut().for_each(upgrade_v2);
+    }
+    // Other fields added after v1 are #[serde(default)] and need no rewriting.
+    Ok(())
+}
+
+/// NDJSON if the first non-empty line is, on its own, a complete item object.
+/// (A pretty-printed array/envelope starts with a lone "[" or "{".)
+pub fn looks_like_ndjson(bytes: &[u8]) -> bool {
+    let Some(first) = bytes.split(|b| *b == b'\n').find(|l| !l.trim_ascii().is_empty()) else {
+        return false;
+    };
+    matches!(serde_json::from_slice::<Value>(first), Ok(Value::Object(o)) if o.contains_key("id"))
+}
+
+pub fn parse_ndjson(bytes: &[u8], path: &Path) -> Result<Vec<Value>> {
+    bytes
+        .split(|b| *b == b'\n')
+        .enumerate()
+        .filter(|(_, l)| !l.trim_ascii().is_empty())
+        .enumerate()
+        .map(|(record, (n, l))| {
+            serde_json::from_slice(l).with_context(|| {
+                Failure::new("invalid_json", format!("Invalid JSON on line {} of {}", n + 1, path.display()))
+                    .path(path)
+                    .record(record)
+                    .line(n + 1)
+            })
+        })
+        .collect()
+}
+
+/// Read canonical items from any supported layout/version.
+pub fn read_items(path: &Path) -> Result<Vec<CanonicalItem>> {
+    let bytes = input::read_input(path).with_context(|| format!("Failed to read input: {}", path.display()))?;
+    parse_items(&bytes, path)
+}
+
+/// read_items for (decompressed) bytes already in memory; `path` names them in messages.
+pub fn parse_items(bytes: &[u8], path: &Path) -> Result<Vec<CanonicalItem>> {
+    // NDJSON lines carry no version marker; items written before a bump still get upgraded
+    let (version, raw) = raw_items(bytes, path)?;
+    upgraded(raw, version.unwrap_or(1), path)
+}
+
+/// The items of a file as written, with the schema_version it declares (None: NDJSON).
+fn raw_items(bytes: &[u8], path: &Path) -> Result<(Option<u32>, Vec<Value>)> {
+    if looks_like_ndjson(bytes) {
+        return Ok((None, parse_ndjson(bytes, path)?));
+    }
+    let root: Value = serde_json::from_slice(bytes).with_context(|| {
+        Failure::new("invalid_json", format!("Failed to parse canonical items: {}", path.display())).path(path)
+    })?;
+    let (version, raw) = detect(root)?;
+    Ok((Some(version), raw))
+}
+
+/// Items of `path` upgraded from `from` (checked against what the file declares) to
+/// `to`, which must be SCHEMA_VERSION: older layouts can only be read, not written.
+/// Returns the version the file was in.
+pub fn migrate(path: &Path, from: Option<u32>, to: u32) -> Result<(u32, Vec<CanonicalItem>)> {
+    if to != SCHEMA_VERSION {
+        bail!(
+            "can't migrate to schema_version {}: this bastion-core writes schema_version {} only",
+            to,
+            SCHEMA_VERSION
+        );
+    }
+    let bytes = input::read_input(path).with_context(|| format!("Failed to read input: {}", path.display()))?;
+    let (declared, raw) = raw_items(&bytes, path)?;
+    let version = match (declared, from) {
+        (Some(declared), Some(from)) if declared != from => {
+            bail!("{} is schema_version {}, not {}", path.display(), declared, from)
+        }
+        (Some(declared), _) => declared,
+        (None, from) => from.unwrap_or(1),
+    };
+    if version > to {
+        bail!("{} is schema_version {}; downgrading to {} isn't supported", path.display(), version, to);
+    }
+    Ok((version, upgraded(raw, version, path)?))
+}
+
+fn upgraded(mut raw: Vec<Value>, version: u32, path: &Path) -> Result<Vec<CanonicalItem>> {
+    upgrade_items(&mut raw, version)?;
+    raw.into_iter().enumerate().map(|(idx, v)| to_item(v, idx, path)).collect()
+}
+
+fn to_item(v: Value, idx: usize, path: &Path) -> Result<CanonicalItem> {
+    serde_json::from_value(v).with_context(|| {
+        Failure::new("invalid_item", format!("Invalid canonical item at index {} in {}", idx, path.display()))
+            .path(path)
+            .record(idx)
+    })
+}
+
+/* -------------------- Streaming reads -------------------- */
+/*
+read_items holds the whole file; CodexReader hands items over one at a time for
+consumers that only walk them (exporters, services embedding the library):
+
+  for item in CodexReader::open(Path::new("items.json"))? {
+      let item = item?;
+      ...
+  }
+
+Same layouts and upgrades as read_items, decided from the first bytes: a bare
+array (v1), an envelope (a root object whose first key is schema_version or
+items; its array is streamed through stream.rs), otherwise NDJSON, read line by
+line. stream.rs drives a callback, so parsing runs on a worker thread at most
+READ_AHEAD items ahead of the consumer; dropping the reader stops it. An item
+that doesn't fit CanonicalItem is an Err and iteration goes on; broken JSON
+ends it after the Err.
+*/
+
+#[cfg(feature = "io")]
+const READ_AHEAD: usize = 256;
+
+#[cfg(feature = "io")]
+pub struct CodexReader {
+    rx: std::sync::mpsc::Receiver<Result<CanonicalItem>>,
+    worker: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "io")]
+impl CodexReader {
+    /// Start reading `path` (local, remote or compressed, as read_items takes).
+    pub fn open(path: &Path) -> Result<Self> {
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+        let (tx, rx) = std::sync::mpsc::sync_channel(READ_AHEAD);
+        let path = path.to_path_buf();
+        // Readers from input.rs aren't Send, so the file is opened on the worker
+        let worker = std::thread::Builder::new()
+            .name("codex-reader".to_string())
+            .spawn(move || {
+                let reader = match input::open_input(&path) {
+                    Ok(reader) => {
+                        let _ = ready_tx.send(Ok(()));
+                        reader
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.context(format!("Failed to read input: {}", path.display()))));
+                        return;
+                    }
+                };
+                let mut emit = |item| tx.send(item).map_err(|_| anyhow::anyhow!("reader dropped"));
+                if let Err(e) = stream_items(reader, &path, &mut emit) {
+                    let _ = tx.send(Err(e));
+                }
+            })
+            .context("Failed to start the codex reader thread")?;
+        ready_rx.recv().unwrap_or_else(|_| Err(anyhow::anyhow!("codex reader thread stopped")))?;
+        Ok(CodexReader { rx, worker: Some(worker) })
+    }
+}
+
+#[cfg(feature = "io")]
+impl Iterator for CodexReader {
+    type Item = Result<CanonicalItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.rx.recv() {
+            Ok(item) => Some(item),
+            // Done; unless the worker panicked, which would otherwise look like the end
+            Err(_) => match self.worker.take()?.join() {
+                Ok(()) => None,
+                Err(_) => Some(Err(anyhow::anyhow!("codex reader thread panicked"))),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+enum Layout {
+    Array,
+    Envelope,
+    Ndjson,
+}
+
+// A root object starting with one of the envelope's keys is an envelope; any other
+// object can only be the first NDJSON item
+#[cfg(feature = "io")]
+fn sniff(head: &[u8]) -> Layout {
+    let head = head.trim_ascii_start();
+    match head.first() {
+        Some(b'[') => Layout::Array,
+        Some(b'{') => {
+            let rest = head[1..].trim_ascii_start();
+            if rest.starts_with(b"\"schema_version\"") || rest.starts_with(b"\"items\"") {
+                Layout::Envelope
+            } else {
+                Layout::Ndjson
+            }
+        }
+        _ => Layout::Ndjson,
+    }
+}
+
+#[cfg(feature = "io")]
+fn stream_items(
+    reader: Box<dyn io::Read>,
+    path: &Path,
+    emit: &mut dyn FnMut(Result<CanonicalItem>) -> Result<()>,
+) -> Result<()> {
+    use io::BufRead;
+
+    let mut reader = io::BufReader::with_capacity(1 << 16, reader);
+    let head = reader.fill_buf().with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut idx = 0;
+    let mut item = |v: Value, version: u32| -> Result<()> {
+        let mut raw = [v];
+        let item = upgrade_items(&mut raw, version).and_then(|_| {
+            let [v] = raw;
+            to_item(v, idx, path)
+        });
+        idx += 1;
+        emit(item)
+    };
+    match sniff(head) {
+        Layout::Array => {
+            let mut meta = |_: &str, _: Value| Ok(());
+            crate::stream::for_each_in_root(reader, path, "items", &mut meta, |v| item(v, 1))?;
+        }
+        Layout::Envelope => {
+            // NDJSON lines have no version either and are upgraded from 1 as well
+            let version = std::cell::Cell::new(1);
+            let mut meta = |key: &str, value: Value| {
+                if key == "schema_version" {
+                    let v = value.as_u64().context("schema_version must be a number")? as u32;
+                    // upgrade_items refuses newer versions; checked here before any item
+                    upgrade_items(&mut [], v)?;
+                    version.set(v);
+                }
+                Ok(())
+            };
+            crate::stream::for_each_in_root(reader, path, "items", &mut meta, |v| item(v, version.get()))?;
+        }
+        Layout::Ndjson => {
+            let mut record = 0;
+            for (n, line) in reader.split(b'\n').enumerate() {
+                let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+                let v = serde_json::from_slice(&line).with_context(|| {
+                    Failure::new("invalid_json", format!("Invalid JSON on line {} of {}", n + 1, path.display()))
+                        .path(path)
+                        .record(record)
+                        .line(n + 1)
+                })?;
+                record += 1;
+                item(v, 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// CVE-2024-999 before CVE-2024-1000; non-CVE IDs (internal advisories) after all CVEs
+pub fn id_key(id: &str) -> (bool, u32, u64, String) {
+    let cve = id.strip_prefix("CVE-").and_then(|rest| {
+        let (year, num) = rest.split_once('-')?;
+        Some((year.parse().ok()?, num.parse().ok()?))
+    });
+    match cve {
+        Some((year, num)) => (false, year, num, String::new()),
+        None => (true, 0, 0, id.to_string()),
+    }
+}
+
+/// Canonical output order, independent of feed order and hash iteration.
+pub fn sort_by_id(items: &mut [CanonicalItem]) {
+    items.sort_by_cached_key(|i| id_key(&i.id));
+}
+
+/// The name sidecars are derived from: items.json and items.json.gz -> items.
+pub fn output_stem(out_path: &Path) -> &str {
+    let name = out_path.file_name().and_then(|s| s.to_str()).unwrap_or("items.json");
+    let name = name.strip_suffix(".gz").or_else(|| name.strip_suffix(".zst")).unwrap_or(name);
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    }
+}
+
+/* -------------------- Writing canonical outputs -------------------- */
+/*
+ItemWriter serializes one item at a time, so writing never holds more than the
+current item's bytes on top of what the caller keeps; a library user producing
+items one by one doesn't need them all in memory at once. The bytes are
+identical to serializing the whole envelope with serde_json (pretty or compact).
+
+The file is written under a temporary name next to its path, synced, and
+renamed over it by finish(), so a reader (serve, a consumer polling the file)
+sees either the old or the new version, never half. Dropping an unfinished
+writer removes the temporary file.
+*/
+
+// Compressors are finished explicitly: dropping one would swallow a failed write
+#[cfg_attr(not(feature = "io"), allow(clippy::large_enum_variant))] // one per output file
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(BufWriter<flate2::write::GzEncoder<File>>),
+    #[cfg(feature = "io")]
+    Zstd(BufWriter<zstd::stream::write::Encoder<'static, File>>),
+}
+
+impl Sink {
+    fn out(&mut self) -> &mut dyn Write {
+        match self {
+            Sink::Plain(w) => w,
+            Sink::Gzip(w) => w,
+            #[cfg(feature = "io")]
+            Sink::Zstd(w) => w,
+        }
+    }
+
+    fn finish(self) -> io::Result<File> {
+        match self {
+            Sink::Plain(w) => w.into_inner().map_err(io::IntoInnerError::into_error),
+            Sink::Gzip(w) => w.into_inner().map_err(io::IntoInnerError::into_error)?.finish(),
+            #[cfg(feature = "io")]
+            Sink::Zstd(w) => w.into_inner().map_err(io::IntoInnerError::into_error)?.finish(),
+        }
+    }
+}
+
+pub struct ItemWriter {
+    path: PathBuf,
+    partial: PathBuf,
+    sink: Option<Sink>,
+    opts: OutputOptions,
+    count: usize,
+    buf: Vec<u8>,
+}
+
+impl ItemWriter {
+    pub fn create(path: &Path, opts: &OutputOptions) -> Result<Self> {
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        let partial = path.with_file_name(format!(".{}.partial", name));
+        let file = File::create(&partial).with_context(|| format!("Failed to write output: {}", partial.display()))?;
+        let sink = match opts.compress {
+            None => Sink::Plain(BufWriter::new(file)),
+            Some(Compression::Gzip) => {
+                Sink::Gzip(BufWriter::new(flate2::write::GzEncoder::new(file, flate2::Compression::default())))
+            }
+            #[cfg(feature = "io")]
+            Some(Compression::Zstd) => Sink::Zstd(BufWriter::new(zstd::stream::write::Encoder::new(file, 0)?)),
+            #[cfg(not(feature = "io"))]
+            Some(Compression::Zstd) => bail!("zstd output needs the io feature"),
+        };
+        let mut writer =
+            ItemWriter { path: path.to_path_buf(), partial, sink: Some(sink), opts: *opts, count: 0, buf: Vec::new() };
+        let head = match (opts.format, opts.compact) {
+            (OutputFormat::Ndjson, _) => String::new(),
+            (OutputFormat::Json, true) => format!("{{\"schema_version\":{},\"items\":[", SCHEMA_VERSION),
+            (OutputFormat::Json, false) => format!("{{\n  \"schema_version\": {},\n  \"items\": [", SCHEMA_VERSION),
+        };
+        writer.write_raw(head.as_bytes())?;
+        Ok(writer)
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        let sink = self.sink.as_mut().expect("ItemWriter used after finish");
+        sink.out().write_all(bytes).with_context(|| format!("Failed to write output: {}", self.partial.display()))
+    }
+
+    pub fn write(&mut self, item: &CanonicalItem) -> Result<()> {
+        self.buf.clear();
+        match (self.opts.format, self.opts.compact) {
+            (OutputFormat::Ndjson, _) => {
+                serde_json::to_writer(&mut self.buf, item)?;
+                self.buf.push(b'\n');
+            }
+            (OutputFormat::Json, true) => {
+                if self.count > 0 {
+                    self.buf.push(b',');
+                }
+                serde_json::to_writer(&mut self.buf, item)?;
+            }
+            // Nested two levels deep in the envelope: indent every line of the item by 4
+            (OutputFormat::Json, false) => {
+                self.buf.extend_from_slice(if self.count > 0 { b",\n    " } else { b"\n    " });
+                let mut pretty = Vec::new();
+                serde_json::to_writer_pretty(&mut pretty, item)?;
+                for (i, line) in pretty.split(|&b| b == b'\n').enumerate() {
+                    if i > 0 {
+                        self.buf.extend_from_slice(b"\n    ");
+                    }
+                    self.buf.extend_from_slice(line);
+                }
+            }
+        }
+        self.count += 1;
+        let sink = self.sink.as_mut().expect("ItemWriter used after finish");
+        sink.out().write_all(&self.buf).with_context(|| format!("Failed to write output: {}", self.partial.display()))
+    }
+
+    /// Close the envelope, sync, and move the file into place.
+    pub fn finish(mut self) -> Result<()> {
+        let tail: &[u8] = match (self.opts.format, self.opts.compact) {
+            (OutputFormat::Ndjson, _) => b"",
+            (OutputFormat::Json, true) => b"]}",
+            (OutputFormat::Json, false) if self.count > 0 => b"\n  ]\n}",
+            (OutputFormat::Json, false) => b"]\n}",
+        };
+        self.write_raw(tail)?;
+        let sink = self.sink.take().expect("ItemWriter finished twice");
+        let file = sink.finish().with_context(|| format!("Failed to write output: {}", self.partial.display()))?;
+        file.sync_all().with_context(|| format!("Failed to write output: {}", self.partial.display()))?;
+        std::fs::rename(&self.partial, &self.path)
+            .with_context(|| format!("Failed to move output into place: {}", self.path.display()))
+    }
+}
+
+impl Drop for ItemWriter {
+    fn drop(&mut self) {
+        // Gone already if finish() moved it into place
+        let _ = std::fs::remove_file(&self.partial);
+    }
+}
+
+/// Write canonical items in the requested layout and compression (see ItemWriter).
+pub fn write_items(path: &Path, items: &[CanonicalItem], opts: &OutputOptions) -> Result<()> {
+    let mut writer = ItemWriter::create(path, opts)?;
+    for item in items {
+        writer.write(item)?;
+    }
+    writer.finish()
+}
+
+/* -------------------- Incremental updates -------------------- */
+
+pub struct MergeStats {
+    pub updated: usize,
+    pub added: usize,
+    pub kept: usize,
+}
+
+/// Apply freshly normalized `items` on top of `prior`, result left in `items`.
+/// A fresh record replaces the stored one only if its last_modified is newer;
+/// prior items missing from this run are preserved. (Order is settled later: normalize
+/// sorts its output by ID.) normalize folds before its enrichment passes, so kept
+/// items get this run's exploit, advisory, distro, ... data as well.
+/// With `kev_set` (this run's KEV catalog, which is always complete) KEV membership
+/// is refreshed on kept items both ways: newly listed ones gain the flag, delisted
+/// ones lose it and their KEV fields.
+pub fn merge_into_prior(
+    prior: Vec<CanonicalItem>,
+    items: &mut Vec<CanonicalItem>,
+    kev_set: Option<&HashSet<String>>,
+) -> MergeStats {
+    let mut stats = MergeStats { updated: 0, added: 0, kept: 0 };
+    let order: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+    let mut fresh: HashMap<String, CanonicalItem> = items.drain(..).map(|i| (i.id.clone(), i)).collect();
+    let mut merged: Vec<CanonicalItem> = Vec::with_capacity(prior.len() + fresh.len());
+
+    for mut old in prior {
+        match fresh.remove(&old.id) {
+            Some(new) if new.last_modified > old.last_modified => {
+                stats.updated += 1;
+                merged.push(new);
+            }
+            _ => {
+                if let Some(kev_set) = kev_set {
+                    let listed = kev_set.contains(&old.id);
+                    if listed != old.kev {
+                        old.kev = listed;
+                        if listed {
+                            old.sources.push("kev".to_string());
+                        } else {
+                            old.sources.retain(|s| s != "kev");
+                            KevListing::default().apply(&mut old);
+                        }
+                        old.severity_bucket = severity::bucket(old.cvss, old.kev);
+                    }
+                }
+                stats.kept += 1;
+                merged.push(old);
+            }
+        }
+    }
+
+    // Remaining fresh items are new IDs; keep the order this run produced them in
+    for id in order {
+        if let Some(new) = fresh.remove(&id) {
+            stats.added += 1;
+            merged.push(new);
+        }
+    }
+
+    *items = merged;
+    stats
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use super::*;
+
+    fn read_all(name: &str, text: &str) -> Vec<Result<CanonicalItem>> {
+        let path = std::env::temp_dir().join(format!("bastion-codex-reader-{}-{}", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        let items = CodexReader::open(&path).unwrap().collect();
+        let _ = std::fs::remove_file(&path);
+        items
+    }
+
+    fn ids(items: Vec<Result<CanonicalItem>>) -> Vec<String> {
+        items.into_iter().map(|i| i.unwrap().id).collect()
+    }
+
+    const ITEM: &str = r#"{"id": "CVE-2024-0001", "sources": ["nvd"], "published": null, "last_modified": null,
+        "cvss": 9.8, "severity_bucket": "critical", "kev": false, "short_desc": "", "vendor": null,
+        "product": null, "refs": [{"url": "https://example.invalid/a", "kind": "other", "source": "nvd"}]}"#;
+
+    #[test]
+    fn envelope_array_and_ndjson_layouts() {
+        let envelope = format!(r#"{{"schema_version": 2, "items": [{}, {}]}}"#, ITEM, ITEM.replace("0001", "0002"));
+        assert_eq!(ids(read_all("envelope.json", &envelope)), ["CVE-2024-0001", "CVE-2024-0002"]);
+
+        let ndjson = format!("{}\n\n{}\n", ITEM.replace('\n', " "), ITEM.replace("0001", "0003").replace('\n', " "));
+        assert_eq!(ids(read_all("items.ndjson", &ndjson)), ["CVE-2024-0001", "CVE-2024-0003"]);
+
+        // v1: a bare array, refs as plain URLs and no severity_bucket
+        let v1 = r#"[{"id": "CVE-2020-0001", "published": null, "last_modified": null, "cvss": 7.5, "kev": true,
+            "short_desc": "", "vendor": null, "product": null, "refs": ["https://github.com/o/r/commit/abc"]}]"#;
+        let items = read_all("v1.json", v1);
+        let item = items.into_iter().next().unwrap().unwrap();
+        assert_eq!(item.severity_bucket, severity::bucket(Some(7.5), true));
+        assert_eq!(item.sources, ["nvd", "kev"]);
+        assert_eq!(item.refs[0].source, "unknown");
+    }
+
+    #[test]
+    fn bad_items_and_newer_schemas_are_errors() {
+        let envelope = format!(r#"{{"schema_version": 2, "items": [{{"id": 5}}, {}]}}"#, ITEM);
+        let items = read_all("bad-item.json", &envelope);
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_err());
+        assert_eq!(items[1].as_ref().unwrap().id, "CVE-2024-0001");
+
+        let newer = format!(r#"{{"schema_version": 99, "items": [{}]}}"#, ITEM);
+        let items = read_all("newer.json", &newer);
+        assert!(items.len() == 1 && items[0].is_err());
+
+        let broken = format!(r#"{{"schema_version": 2, "items": [{}, {{"id": "#, ITEM);
+        let items = read_all("broken.json", &broken);
+        assert!(items[0].is_ok() && items.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn missing_file_fails_on_open() {
+        assert!(CodexReader::open(Path::new("/nonexistent/items.json")).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::{bail, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use serde_json::{Map, Value};
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/* -------------------- Config file -------------------- */
+/*
+`core --config bastion.toml <command> ...` reads option values from a TOML file
+(see toml.rs) instead of the command line. Top-level keys are the global
+options; each [section] holds the options of the subcommand it is named after.
+Keys are the long flag names, with '-' or '_':
+
+  severity-policy = "config/severity.json"
+  log-format = "json"
+
+  [normalize]
+  kev = "data/raw/kev.json"
+  nvd = ["data/raw/nvdcve-2.0-*.json.gz", "data/raw/nvd_api.json"]
+  overrides = "config/overrides.toml"
+  out = "data/codex/items.json"
+  with-provenance = true
+
+  [daemon]
+  interval = "6h"
+  fetch-cmd = "python orchestrator/ti_run.py --fetch"
+  notify-serve = "127.0.0.1:8080"
+
+  [notify]
+  config = "config/notify.json"
+
+A flag given on the command line wins over the file: the file's value for that
+option is dropped, including every value of a repeatable one (a single --nvd
+replaces the file's list). Booleans set a flag when true; arrays repeat a flag.
+Relative paths resolve from the working directory, as on the command line.
+Positional arguments (merge's inputs) can't be set here.
+
+`daemon` runs normalize with [normalize] as well, under the arguments after
+`--`; those may then be empty.
+
+Profiles are named sets of normalize options over the same feeds, one codex per
+team:
+
+  [profile.appsec]
+  out = "data/codex/appsec.json"
+  tag-rules = "config/appsec-tags.toml"
+
+  [profile.internal]
+  out = "data/codex/internal.json"
+  internal-advisories = "data/raw/internal.json"
+
+`normalize --profile appsec` takes [profile.appsec], then [normalize] for what
+the profile doesn't set (the command line still wins over both). `normalize
+--all-profiles` runs every profile in turn, in name order, from one parse of
+the feeds they share (see main.rs).
+
+Unknown sections and keys are errors, so a typo doesn't silently drop a setting.
+*/
+
+pub struct Config {
+    path: PathBuf,
+    root: Map<String, Value>,
+}
+
+/// The option `key` names, by long flag or its alias.
+fn find_arg<'a>(cmd: &'a Command, key: &str) -> Option<&'a Arg> {
+    let key = key.replace('_', "-");
+    cmd.get_arguments()
+        .find(|a| a.get_long_and_visible_aliases().is_some_and(|names| names.contains(&key.as_str())))
+}
+
+/// Whether `args` (up to any `--`) already set `arg`.
+fn given(arg: &Arg, args: &[OsString]) -> bool {
+    let longs = arg.get_long_and_visible_aliases().unwrap_or_default();
+    args.iter()
+        .map(|a| a.to_string_lossy())
+        .take_while(|a| a != "--")
+        .any(|a| match a.strip_prefix("--") {
+            Some(flag) => longs.contains(&flag.split('=').next().unwrap_or(flag)),
+            // -v, or -vq
+            None => arg.get_short().is_some_and(|s| a.starts_with('-') && a.len() > 1 && a[1..].contains(s)),
+        })
+}
+
+/// The value of a --profile in `args`, if one is given.
+fn profile_arg(args: &[OsString]) -> Option<String> {
+    let mut rest = args.iter().map(|a| a.to_string_lossy()).take_while(|a| a != "--");
+    while let Some(a) = rest.next() {
+        if let Some(name) = a.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+        if a == "--profile" {
+            return rest.next().map(|n| n.to_string());
+        }
+    }
+    None
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl Config {
+    // "normalize", or "profile.<name>"
+    fn section(&self, name: &str) -> Option<&Value> {
+        match name.split_once('.') {
+            Some(("profile", profile)) => self.root.get("profile")?.get(profile),
+            _ => self.root.get(name),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read config: {}", path.display()))?;
+        let root = match crate::toml::parse(&text).with_context(|| format!("Failed to parse config: {}", path.display()))? {
+            Value::Object(map) => map,
+            _ => bail!("{}: expected a table", path.display()),
+        };
+        Ok(Config { path: path.to_path_buf(), root })
+    }
+
+    /// The --config named before the subcommand in `argv`, loaded.
+    pub fn from_argv(cli: &Command, argv: &[OsString]) -> Result<Option<Self>> {
+        let mut rest = argv.iter().skip(1).map(|a| a.to_string_lossy());
+        while let Some(a) = rest.next() {
+            if let Some(path) = a.strip_prefix("--config=") {
+                return Config::load(Path::new(path)).map(Some);
+            }
+            if a == "--config" {
+                let Some(path) = rest.next() else { return Ok(None) };
+                return Config::load(Path::new(path.as_ref())).map(Some);
+            }
+            if !a.starts_with('-') || a == "--" {
+                break;
+            }
+            // Skip the value of a global option
+            if let Some(flag) = a.strip_prefix("--")
+                && !flag.contains('=')
+                && find_arg(cli, flag).is_some_and(|arg| arg.get_action().takes_values())
+            {
+                rest.next();
+            }
+        }
+        Ok(None)
+    }
+
+    /// Names of the [profile.<name>] sections.
+    pub fn profiles(&self) -> Result<Vec<String>> {
+        match self.root.get("profile") {
+            None => Ok(Vec::new()),
+            Some(Value::Object(t)) => match t.iter().find(|(_, v)| !v.is_object()) {
+                Some((name, _)) => {
+                    bail!("{}: profile.{} must be a [profile.{}] section", self.path.display(), name, name)
+                }
+                None => Ok(t.keys().cloned().collect()),
+            },
+            Some(_) => bail!("{}: profile must hold [profile.<name>] sections", self.path.display()),
+        }
+    }
+
+    /// `argv` with the global options and the subcommand's section filled in
+    /// wherever the command line doesn't set them (for normalize --profile NAME,
+    /// [profile.NAME] before [normalize]).
+    pub fn apply(&self, cli: &Command, argv: Vec<OsString>) -> Result<Vec<OsString>> {
+        let subcommand = argv.iter().enumerate().skip(1).find_map(|(i, a)| Some((i, cli.find_subcommand(a)?)));
+        for (key, value) in &self.root {
+            if value.is_object() && key != "profile" && cli.find_subcommand(key).is_none() {
+                let known: Vec<&str> = cli.get_subcommands().map(Command::get_name).filter(|n| *n != "help").collect();
+                bail!("{}: unknown section [{}] (sections are: {})", self.path.display(), key, known.join(", "));
+            }
+        }
+
+        let globals = self.args(cli, None, &argv)?;
+        let (mut out, mut tail) = (argv, Vec::new());
+        if let Some((at, sub)) = subcommand {
+            // Only what follows the subcommand: its --config isn't ours
+            let extra = self.subcommand_args(sub, &out[at + 1..])?;
+            // Before a `--`, whose arguments belong to someone else (daemon)
+            if let Some(dash) = out.iter().position(|a| a == "--") {
+                tail = out.split_off(dash);
+            }
+            out.extend(extra);
+        }
+        out.splice(1..1, globals);
+        out.extend(tail);
+        Ok(out)
+    }
+
+    /// Flags for subcommand `sub` from its section that `args` doesn't already
+    /// set; for normalize --profile NAME, [profile.NAME] first.
+    pub fn subcommand_args(&self, sub: &Command, args: &[OsString]) -> Result<Vec<OsString>> {
+        let mut sections = vec![sub.get_name().to_string()];
+        if sub.get_name() == "normalize"
+            && let Some(profile) = profile_arg(args)
+        {
+            sections.insert(0, format!("profile.{}", profile));
+        }
+        let (mut given, mut extra) = (args.to_vec(), Vec::new());
+        for section in &sections {
+            let found = self.args(sub, Some(section), &given)?;
+            given.extend(found.iter().cloned());
+            extra.extend(found);
+        }
+        Ok(extra)
+    }
+
+    /// Flags for `cmd` from `section` (None: the top-level keys) that `args`
+    /// doesn't already set.
+    pub fn args(&self, cmd: &Command, section: Option<&str>, args: &[OsString]) -> Result<Vec<OsString>> {
+        let (table, name) = match section {
+            Some(name) => match self.section(name) {
+                Some(Value::Object(t)) => (t, format!("[{}]", name)),
+                Some(_) => bail!("{}: {} must be a [section]", self.path.display(), name),
+                None if name.starts_with("profile.") => {
+                    let known = self.profiles()?.join(", ");
+                    bail!("{}: no [{}] section (profiles are: {})", self.path.display(), name, known)
+                }
+                None => return Ok(Vec::new()),
+            },
+            None => (&self.root, "top level".to_string()),
+        };
+
+        let mut out = Vec::new();
+        for (key, value) in table {
+            if section.is_none() && value.is_object() {
+                continue;
+            }
+            let arg = find_arg(cmd, key)
+                .filter(|a| a.get_id() != "config" || section.is_some())
+                .with_context(|| format!("{}: {} has no option '{}'", self.path.display(), name, key))?;
+            if given(arg, args) {
+                continue;
+            }
+            let flag = format!("--{}", arg.get_long().unwrap_or(key));
+            let values = match value {
+                Value::Array(list) if matches!(arg.get_action(), ArgAction::Append) => list.iter().collect(),
+                Value::Array(_) => bail!("{}: {} '{}' takes one value, not a list", self.path.display(), name, key),
+                v => vec![v],
+            };
+            for v in values {
+                if !arg.get_action().takes_values() {
+                    match v {
+                        Value::Bool(true) => out.push(flag.clone().into()),
+                        Value::Bool(false) => {}
+                        _ => bail!("{}: {} '{}' is a switch; use true or false", self.path.display(), name, key),
+                    }
+                    continue;
+                }
+                let s = scalar(v)
+                    .with_context(|| format!("{}: {} '{}' must be a string, number or boolean", self.path.display(), name, key))?;
+                out.push(format!("{}={}", flag, s).into());
+            }
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/* -------------------- NVD CPE configurations -------------------- */
+/*
+cve.configurations[].nodes[].cpeMatch[] flattened into one list per item.
+Node operators (AND/OR) and negation are not kept: an entry with vulnerable=false
+is a platform the vulnerable CPEs run on ("Windows on x64"), not a target itself.
+Matchers that need the full boolean tree should read the raw NVD record.
+*/
+
+#[derive(Debug, Deserialize)]
+pub struct NvdConfiguration {
+    #[serde(default)]
+    nodes: Vec<NvdNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdNode {
+    #[serde(default, rename = "cpeMatch")]
+    cpe_match: Vec<NvdCpeMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCpeMatch {
+    #[serde(default)]
+    vulnerable: bool,
+    criteria: Option<String>,
+    #[serde(default)]
+    version_start_including: Option<String>,
+    #[serde(default)]
+    version_start_excluding: Option<String>,
+    #[serde(default)]
+    version_end_including: Option<String>,
+    #[serde(default)]
+    version_end_excluding: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AffectedCpe {
+    pub cpe23_uri: String,           // cpe:2.3:a:vendor:product:version:...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_start_including: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_start_excluding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_end_including: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_end_excluding: Option<String>,
+    pub vulnerable: bool,
+}
+
+/// Flatten all cpeMatch entries, vulnerable ones first, deduplicated so output is stable.
+pub fn extract_affected(configs: &[NvdConfiguration]) -> Vec<AffectedCpe> {
+    let mut out: Vec<AffectedCpe> = configs
+        .iter()
+        .flat_map(|c| c.nodes.iter())
+        .flat_map(|n| n.cpe_match.iter())
+        .filter_map(|m| {
+            let uri = m.criteria.as_deref().map(str::trim).filter(|s| !s.is_empty())?;
+            Some(AffectedCpe {
+                cpe23_uri: uri.to_string(),
+                version_start_including: m.version_start_including.clone(),
+                version_start_excluding: m.version_start_excluding.clone(),
+                version_end_including: m.version_end_including.clone(),
+                version_end_excluding: m.version_end_excluding.clone(),
+                vulnerable: m.vulnerable,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| b.vulnerable.cmp(&a.vulnerable).then_with(|| a.cmp(b)));
+    out.dedup();
+    out
+}
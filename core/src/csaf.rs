@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{precedence, provenance, severity, CanonicalItem};
+
+/* -------------------- CSAF 2.0 advisory parsing -------------------- */
+/*
+Vendors (Red Hat, Cisco, SUSE, ...) publish one CSAF 2.0 JSON document per advisory.
+Their view often disagrees with NVD, so we keep it side by side instead of overwriting,
+unless a merge policy ranks csaf first for cvss (prefer_vendor_cvss, see precedence.rs).
+
+We target:
+- document.publisher.name
+- document.tracking { id, current_release_date }
+- document.references[] (category == "self")
+- vulnerabilities[].cve
+- vulnerabilities[].product_status.*
+- vulnerabilities[].remediations[] { category, details, url }
+- vulnerabilities[].scores[].cvss_v3 / cvss_v2 baseScore
+*/
+
+#[derive(Debug, Deserialize)]
+struct CsafDoc {
+    document: CsafDocument,
+    #[serde(default)]
+    vulnerabilities: Vec<CsafVuln>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsafDocument {
+    #[serde(default)]
+    publisher: Option<CsafPublisher>,
+    #[serde(default)]
+    tracking: Option<CsafTracking>,
+    #[serde(default)]
+    references: Vec<CsafReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsafPublisher {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsafTracking {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    current_release_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsafReference {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsafVuln {
+    #[serde(default)]
+    cve: Option<String>,
+    #[serde(default)]
+    product_status: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    remediations: Vec<CsafRemediation>,
+    #[serde(default)]
+    scores: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CsafRemediation {
+    #[serde(default)]
+    pub category: Option<String>,  // vendor_fix|workaround|mitigation|no_fix_planned|none_available
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// One vendor's view of a CVE, taken from a CSAF advisory.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VendorAdvisory {
+    pub publisher: String,             // document.publisher.name
+    pub advisory_id: Option<String>,   // document.tracking.id
+    pub released: Option<String>,      // document.tracking.current_release_date
+    pub url: Option<String>,           // "self" reference
+    pub fix_status: String,            // fixed|affected|under_investigation|not_affected|unknown
+    pub cvss: Option<f64>,             // vendor-assigned base score
+    #[serde(default)]
+    pub remediations: Vec<CsafRemediation>,
+}
+
+fn summarize_status(status: &HashMap<String, Vec<String>>) -> String {
+    let has = |keys: &[&str]| keys.iter().any(|k| status.get(*k).is_some_and(|v| !v.is_empty()));
+
+    if has(&["fixed", "first_fixed"]) {
+        "fixed".to_string()
+    } else if has(&["known_affected", "first_affected", "last_affected"]) {
+        "affected".to_string()
+    } else if has(&["under_investigation"]) {
+        "under_investigation".to_string()
+    } else if has(&["known_not_affected"]) {
+        "not_affected".to_string()
+    } else {
+        "unknown".to_string()
+    }
+}
+
+fn extract_vendor_cvss(scores: &[serde_json::Value]) -> Option<f64> {
+    // Highest score across products, preferring CVSS v3 over v2.
+    for key in ["cvss_v3", "cvss_v2"] {
+        let best = scores
+            .iter()
+            .filter_map(|s| s.get(key).and_then(|v| v.get("baseScore")).and_then(|v| v.as_f64()))
+            .fold(None, |acc: Option<f64>, s| Some(acc.map_or(s, |a| a.max(s))));
+        if best.is_some() {
+            return best;
+        }
+    }
+    None
+}
+
+fn parse_doc(bytes: &[u8], out: &mut HashMap<String, Vec<VendorAdvisory>>) {
+    // Skip non-CSAF JSON (provider metadata, indexes, ...) rather than failing the run.
+    let Ok(doc) = serde_json::from_slice::<CsafDoc>(bytes) else { return; };
+
+    let publisher = doc
+        .document
+        .publisher
+        .and_then(|p| p.name)
+        .unwrap_or_else(|| "unknown".to_string());
+    let (advisory_id, released) = match doc.document.tracking {
+        Some(t) => (t.id, t.current_release_date),
+        None => (None, None),
+    };
+    let url = doc
+        .document
+        .references
+        .iter()
+        .find(|r| r.category.as_deref() == Some("self"))
+        .and_then(|r| r.url.clone());
+
+    for v in doc.vulnerabilities {
+        let Some(cve) = v.cve.map(|c| c.trim().to_string()) else { continue; };
+        out.entry(cve).or_default().push(VendorAdvisory {
+            publisher: publisher.clone(),
+            advisory_id: advisory_id.clone(),
+            released: released.clone(),
+            url: url.clone(),
+            fix_status: summarize_status(&v.product_status),
+            cvss: extract_vendor_cvss(&v.scores),
+            remediations: v.remediations,
+        });
+    }
+}
+
+fn walk_dir(dir: &Path, out: &mut HashMap<String, Vec<VendorAdvisory>>) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read CSAF directory: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort(); // advisory order on items must not depend on the filesystem
+
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, out)?;
+            continue;
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let bytes = fs::read(&path)
+            .with_context(|| format!("Failed to read CSAF document: {}", path.display()))?;
+        parse_doc(&bytes, out);
+    }
+
+    Ok(())
+}
+
+/// Attach vendor advisories from a directory of CSAF 2.0 documents to matching items.
+///
+/// NVD-derived fields are left as-is; the vendor view lives in `vendor_advisories`.
+/// Returns the number of items that received at least one advisory.
+pub fn merge_csaf(dir: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut advisories: HashMap<String, Vec<VendorAdvisory>> = HashMap::new();
+    walk_dir(dir, &mut advisories)?;
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let Some(mut found) = advisories.remove(&item.id) else { continue; };
+
+        found.sort_by(|a, b| a.publisher.cmp(&b.publisher).then_with(|| a.advisory_id.cmp(&b.advisory_id)));
+        // A newer revision of an advisory kept from --merge-into replaces it
+        item.vendor_advisories
+            .retain(|a| !found.iter().any(|f| f.publisher == a.publisher && f.advisory_id == a.advisory_id));
+        item.vendor_advisories.extend(found);
+        if !item.sources.iter().any(|s| s == "csaf") {
+            item.sources.push("csaf".to_string());
+        }
+        merged += 1;
+    }
+
+    Ok(merged)
+}
+
+/// Replace the merged CVSS with the vendor's where `policy` ranks csaf above the
+/// source that set it. Returns the number of items changed.
+pub fn prefer_vendor_cvss(items: &mut [CanonicalItem], policy: &precedence::MergePolicy) -> usize {
+    let mut changed = 0usize;
+    for item in items.iter_mut() {
+        let Some(vendor) = item.vendor_advisories.iter().find_map(|a| a.cvss) else { continue };
+        let holder = item.provenance.get("cvss").map(|p| p.source.as_str());
+        if item.cvss == Some(vendor) || !policy.prefers("cvss", "csaf", holder) {
+            continue;
+        }
+        item.cvss = Some(vendor);
+        item.severity_bucket = severity::bucket(item.cvss, item.kev);
+        let source = provenance::FieldSource { source: "csaf".to_string(), as_of: None };
+        item.provenance.insert("cvss".to_string(), source);
+        changed += 1;
+    }
+    changed
+}
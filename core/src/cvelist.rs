@@ -0,0 +1,370 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::Read, path::Path};
+
+use crate::{
+    log_warn, refs, severity,
+    versions::{self, AffectedVersions, VersionRange},
+    CanonicalItem,
+};
+
+/* -------------------- cvelistV5 parsing (CVE JSON 5.x) -------------------- */
+/*
+MITRE's cvelistV5 repo publishes one CVE JSON 5.0/5.1 record per file
+(cves/<year>/<bucket>/CVE-YYYY-NNNN.json), usually well before NVD enriches it.
+
+We target:
+- cveMetadata.cveId / state / datePublished / dateUpdated
+- containers.cna.title, descriptions[], references[]
+- containers.cna.metrics[] (cvssV4_0 / cvssV3_1 / cvssV3_0 / cvssV2_0 baseScore)
+- containers.cna.affected[] { vendor, product, defaultStatus, versions[] }
+
+Every affected vendor/product becomes an affected_versions entry (ecosystem
+"cna", source "cvelist"): versions[] with status "affected" give exact versions,
+or ranges when lessThan / lessThanOrEqual is set (the version is then the lower
+bound, "0" meaning none); git versionType entries are skipped. The item's
+vendor / product fields take the first pair.
+
+A published record whose CVE neither NVD nor KEV has yet becomes an item of
+its own (source "cvelist"). REJECTED and RESERVED records are ignored; records
+that aren't CVE JSON are counted and skipped with a warning.
+*/
+
+#[derive(Debug, Deserialize)]
+struct CveRecord {
+    #[serde(rename = "cveMetadata")]
+    cve_metadata: CveMetadata,
+    #[serde(default)]
+    containers: Option<CveContainers>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CveMetadata {
+    cve_id: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    date_published: Option<String>,
+    #[serde(default)]
+    date_updated: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CveContainers {
+    #[serde(default)]
+    cna: Option<CnaContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CnaContainer {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    descriptions: Vec<CnaDescription>,
+    #[serde(default)]
+    references: Vec<CnaReference>,
+    #[serde(default)]
+    metrics: Vec<serde_json::Value>,
+    #[serde(default)]
+    affected: Vec<CnaAffected>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CnaDescription {
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CnaReference {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CnaAffected {
+    #[serde(default)]
+    vendor: Option<String>,
+    #[serde(default)]
+    product: Option<String>,
+    #[serde(default)]
+    default_status: Option<String>,
+    #[serde(default)]
+    versions: Vec<CnaVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CnaVersion {
+    version: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    version_type: Option<String>,
+    #[serde(default)]
+    less_than: Option<String>,
+    #[serde(default)]
+    less_than_or_equal: Option<String>,
+}
+
+/// The subset of a CNA record we merge into canonical items.
+#[derive(Debug)]
+struct CnaInfo {
+    title: Option<String>,
+    description: Option<String>,
+    published: Option<String>,
+    last_modified: Option<String>,
+    cvss: Option<f64>,
+    vendor: Option<String>,
+    product: Option<String>,
+    refs: Vec<String>,
+    affected: Vec<AffectedVersions>,
+}
+
+fn non_empty(s: &Option<String>) -> Option<String> {
+    s.as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && *v != "n/a")
+        .map(str::to_string)
+}
+
+fn extract_cna_cvss(metrics: &[serde_json::Value]) -> Option<f64> {
+    // Same idea as extract_best_cvss for NVD: newest CVSS version first.
+    let candidates = ["cvssV4_0", "cvssV3_1", "cvssV3_0", "cvssV2_0"];
+
+    for key in candidates {
+        for entry in metrics {
+            if let Some(score) = entry
+                .get(key)
+                .and_then(|v| v.get("baseScore"))
+                .and_then(|v| v.as_f64())
+            {
+                return Some(score);
+            }
+        }
+    }
+
+    None
+}
+
+fn pick_description(descs: &[CnaDescription]) -> Option<String> {
+    let english = descs.iter().find(|d| d.lang.as_deref().is_some_and(|l| l.starts_with("en")));
+    english.and_then(|d| non_empty(&d.value)).or_else(|| descs.iter().find_map(|d| non_empty(&d.value)))
+}
+
+/// One entry per affected vendor:product, merging repeated pairs.
+fn affected_versions(affected: &[CnaAffected]) -> Vec<AffectedVersions> {
+    let mut out: Vec<AffectedVersions> = Vec::new();
+    for a in affected {
+        let (Some(vendor), Some(product)) = (non_empty(&a.vendor), non_empty(&a.product)) else { continue };
+        let (mut ranges, mut exact) = (Vec::new(), Vec::new());
+        for v in a.versions.iter().filter(|v| v.status.as_deref() == Some("affected")) {
+            if v.version_type.as_deref() == Some("git") {
+                continue;
+            }
+            let from = Some(v.version.trim()).filter(|s| !s.is_empty() && !matches!(*s, "0" | "*" | "n/a"));
+            let bound = |b: &Option<String>| b.as_deref().map(str::trim).filter(|s| *s != "*").map(str::to_string);
+            if v.less_than.is_some() || v.less_than_or_equal.is_some() {
+                ranges.push(VersionRange {
+                    gte: from.map(str::to_string),
+                    lt: bound(&v.less_than),
+                    lte: bound(&v.less_than_or_equal),
+                    ..Default::default()
+                });
+            } else if let Some(version) = from {
+                exact.push(version.to_string());
+            }
+        }
+        if ranges.is_empty() && exact.is_empty() {
+            // Listed without versions: only a product affected by default counts
+            if a.default_status.as_deref() != Some("affected") {
+                continue;
+            }
+            ranges.push(VersionRange::default());
+        }
+
+        let key = format!("{}:{}", vendor.to_lowercase(), product.to_lowercase());
+        if let Some(entry) = out.iter_mut().find(|e| e.product == key) {
+            entry.ranges.extend(ranges);
+            entry.versions.extend(exact);
+            continue;
+        }
+        out.push(AffectedVersions {
+            ecosystem: "cna".to_string(),
+            product: key,
+            ranges,
+            versions: exact,
+            source: "cvelist".to_string(),
+            ..Default::default()
+        });
+    }
+    for entry in &mut out {
+        entry.ranges.sort();
+        entry.ranges.dedup();
+        entry.versions.sort();
+        entry.versions.dedup();
+        entry.scheme = versions::scheme(&entry.ranges, &entry.versions, "opaque");
+    }
+    out
+}
+
+/// Ok(None) for records with nothing to merge (rejected, reserved, no CNA container).
+fn parse_record(bytes: &[u8]) -> serde_json::Result<Option<(String, CnaInfo)>> {
+    let rec: CveRecord = serde_json::from_slice(bytes)?;
+    if matches!(rec.cve_metadata.state.as_deref(), Some("REJECTED" | "RESERVED")) {
+        return Ok(None);
+    }
+    let Some(cna) = rec.containers.and_then(|c| c.cna) else { return Ok(None) };
+
+    let first = cna.affected.iter().find(|a| non_empty(&a.vendor).is_some() || non_empty(&a.product).is_some());
+    let info = CnaInfo {
+        title: non_empty(&cna.title),
+        description: pick_description(&cna.descriptions),
+        published: rec.cve_metadata.date_published,
+        last_modified: rec.cve_metadata.date_updated,
+        cvss: extract_cna_cvss(&cna.metrics),
+        vendor: first.and_then(|a| non_empty(&a.vendor)),
+        product: first.and_then(|a| non_empty(&a.product)),
+        refs: cna.references.into_iter().map(|r| r.url.trim().to_string()).filter(|u| !u.is_empty()).collect(),
+        affected: affected_versions(&cna.affected),
+    };
+
+    Ok(Some((rec.cve_metadata.cve_id.trim().to_string(), info)))
+}
+
+fn is_record_name(name: &str) -> bool {
+    name.starts_with("CVE-") && name.ends_with(".json")
+}
+
+fn walk_dir(dir: &Path, f: &mut dyn FnMut(&[u8])) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read CVE record directory: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, f)?;
+            continue;
+        }
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if !is_record_name(name) {
+            continue;
+        }
+        let bytes = fs::read(&path)
+            .with_context(|| format!("Failed to read CVE record: {}", path.display()))?;
+        f(&bytes);
+    }
+
+    Ok(())
+}
+
+fn walk_zip(path: &Path, f: &mut dyn FnMut(&[u8])) -> Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open CVE record zip: {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("Failed to read CVE record zip: {}", path.display()))?;
+
+    for idx in 0..archive.len() {
+        let mut entry = archive.by_index(idx)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let name = entry.name()?.rsplit('/').next().unwrap_or_default().to_string();
+        if !is_record_name(&name) {
+            continue;
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        f(&bytes);
+    }
+
+    Ok(())
+}
+
+/// Call `f` with the raw bytes of every CVE-*.json record under a directory or zip.
+/// Shared by the cvelistV5 and vulnrichment sources (same record layout).
+pub fn for_each_record(path: &Path, f: &mut dyn FnMut(&[u8])) -> Result<()> {
+    if path.is_dir() {
+        walk_dir(path, f)
+    } else {
+        walk_zip(path, f)
+    }
+}
+
+/// Merge CNA-provided data from a cvelistV5 checkout (directory) or zip into `items`.
+///
+/// On items already present (from NVD, KEV or --merge-into) the title is always taken; CVSS,
+/// vendor and product only fill gaps NVD/KEV left empty, and the CNA's affected
+/// products replace earlier cvelist entries. Unknown IDs become new items.
+/// Returns (items added, items enriched).
+pub fn merge_cvelist(path: &Path, items: &mut Vec<CanonicalItem>) -> Result<(usize, usize)> {
+    let mut records: HashMap<String, CnaInfo> = HashMap::new();
+    let mut malformed = 0usize;
+    for_each_record(path, &mut |bytes| match parse_record(bytes) {
+        Ok(Some((id, info))) => {
+            records.insert(id, info);
+        }
+        Ok(None) => {}
+        Err(_) => malformed += 1,
+    })?;
+    if malformed > 0 {
+        log_warn!("cvelist: skipped {} malformed CVE records in {}", malformed, path.display());
+    }
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let Some(info) = records.remove(&item.id) else { continue; };
+
+        // The CNA is the only title source: a kept (--merge-into) item gets the current one
+        item.title = info.title;
+        if item.cvss.is_none() && info.cvss.is_some() {
+            item.cvss = info.cvss;
+            item.severity_bucket = severity::bucket(info.cvss, item.kev);
+        }
+        if item.vendor.is_none() {
+            item.vendor = info.vendor;
+        }
+        if item.product.is_none() {
+            item.product = info.product;
+        }
+        item.affected_versions.retain(|a| a.source != "cvelist");
+        item.affected_versions.extend(info.affected);
+        if !item.sources.iter().any(|s| s == "cvelist") {
+            item.sources.push("cvelist".to_string());
+        }
+        merged += 1;
+    }
+
+    // CNA-only CVEs: published in cvelistV5, not (yet) in NVD or KEV
+    let mut added: Vec<(String, CnaInfo)> = records.into_iter().collect();
+    added.sort_by(|a, b| a.0.cmp(&b.0));
+    let count = added.len();
+    for (id, info) in added {
+        let mut item_refs = Vec::new();
+        for url in &info.refs {
+            refs::push_ref(&mut item_refs, url, "cvelist");
+        }
+        items.push(CanonicalItem {
+            id,
+            sources: vec!["cvelist".to_string()],
+            published: info.published,
+            last_modified: info.last_modified,
+            cvss: info.cvss,
+            severity_bucket: severity::bucket(info.cvss, false),
+            short_desc: info.description.unwrap_or_else(|| "No description available.".to_string()),
+            title: info.title,
+            vendor: info.vendor,
+            product: info.product,
+            refs: item_refs,
+            affected_versions: info.affected,
+            ..Default::default()
+        });
+    }
+
+    Ok((count, merged))
+}
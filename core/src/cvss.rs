@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/* -------------------- CVSS metric details -------------------- */
+/*
+Everything NVD tells us about the metric that produced item.cvss: which block
+it came from (version, Primary/Secondary, who scored it), the vector string and
+its components. Component names follow CVSS v3; v2's accessVector/accessComplexity
+land in attack_vector/attack_complexity and authentication has its own field.
+
+Components are taken from cvssData and, when NVD leaves them out, decoded from
+the vector string, so they are always the spelled-out form ("NETWORK").
+*/
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct CvssDetails {
+    pub version: String,             // "3.1" | "3.0" | "2.0"
+    pub source: Option<String>,      // "nvd@nist.gov" or the CNA's address
+    pub metric_type: Option<String>, // Primary | Secondary
+    pub base_score: f64,
+    pub base_severity: Option<String>,
+    pub vector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attack_vector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attack_complexity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privileges_required: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_interaction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authentication: Option<String>, // v2 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidentiality_impact: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity_impact: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability_impact: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exploitability_score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact_score: Option<f64>,
+}
+
+/// Spell out one vector component ("AV", "N") the way cvssData does.
+fn decode(metric: &str, value: &str) -> Option<&'static str> {
+    Some(match (metric, value) {
+        ("AV", "N") => "NETWORK",
+        ("AV", "A") => "ADJACENT_NETWORK",
+        ("AV", "L") => "LOCAL",
+        ("AV", "P") => "PHYSICAL",
+        ("AC" | "PR" | "Au" | "C" | "I" | "A", "L") => "LOW",
+        ("AC" | "PR" | "C" | "I" | "A", "H") => "HIGH",
+        ("AC", "M") => "MEDIUM",
+        ("PR" | "C" | "I" | "A" | "Au", "N") => "NONE",
+        ("UI", "N") => "NONE",
+        ("UI", "R") => "REQUIRED",
+        ("S", "U") => "UNCHANGED",
+        ("S", "C") => "CHANGED",
+        ("Au", "S") => "SINGLE",
+        ("Au", "M") => "MULTIPLE",
+        ("C" | "I" | "A", "P") => "PARTIAL",
+        ("C" | "I" | "A", "C") => "COMPLETE",
+        _ => return None,
+    })
+}
+
+/// cvssData field for a vector metric, by CVSS major version.
+fn field_for(metric: &str, v2: bool) -> Option<&'static str> {
+    Some(match metric {
+        "AV" if v2 => "accessVector",
+        "AC" if v2 => "accessComplexity",
+        "AV" => "attackVector",
+        "AC" => "attackComplexity",
+        "PR" => "privilegesRequired",
+        "UI" => "userInteraction",
+        "S" => "scope",
+        "Au" => "authentication",
+        "C" => "confidentialityImpact",
+        "I" => "integrityImpact",
+        "A" => "availabilityImpact",
+        _ => return None,
+    })
+}
+
+/// Build details from one NVD metric entry (an element of cvssMetricV31 etc.).
+/// None when the entry has no baseScore.
+pub fn from_nvd_entry(entry: &Value) -> Option<CvssDetails> {
+    let data = entry.get("cvssData")?;
+    let base_score = data.get("baseScore")?.as_f64()?;
+    let str_at = |v: &Value, key: &str| v.get(key).and_then(|x| x.as_str()).map(str::to_string);
+
+    let version = str_at(data, "version").unwrap_or_default();
+    let v2 = version.starts_with('2');
+    let vector = str_at(data, "vectorString");
+
+    // Components from the vector ("CVSS:3.1/AV:N/..." or v2's bare "AV:N/...")
+    let from_vector = |metric: &str| -> Option<String> {
+        vector.as_deref()?.split('/').find_map(|part| {
+            let (m, v) = part.split_once(':')?;
+            (m == metric).then(|| decode(m, v)).flatten().map(str::to_string)
+        })
+    };
+    let component = |metric: &str| field_for(metric, v2).and_then(|f| str_at(data, f)).or_else(|| from_vector(metric));
+
+    Some(CvssDetails {
+        source: str_at(entry, "source"),
+        metric_type: str_at(entry, "type"),
+        base_score,
+        // v3 keeps baseSeverity in cvssData, v2 on the entry
+        base_severity: str_at(data, "baseSeverity").or_else(|| str_at(entry, "baseSeverity")),
+        attack_vector: component("AV"),
+        attack_complexity: component("AC"),
+        privileges_required: if v2 { None } else { component("PR") },
+        user_interaction: if v2 { None } else { component("UI") },
+        scope: if v2 { None } else { component("S") },
+        authentication: if v2 { component("Au") } else { None },
+        confidentiality_impact: component("C"),
+        integrity_impact: component("I"),
+        availability_impact: component("A"),
+        exploitability_score: entry.get("exploitabilityScore").and_then(|v| v.as_f64()),
+        impact_score: entry.get("impactScore").and_then(|v| v.as_f64()),
+        version,
+        vector,
+    })
+}
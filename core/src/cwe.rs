@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- CWE taxonomy rollup -------------------- */
+/*
+Maps item.cwes onto reporting categories (OWASP Top 10, CWE Top 25, ...) and
+stores them as "<taxonomy>/<category>" in item.cwe_categories, e.g.
+"owasp-top10-2021/A03:2021-Injection". The bundled mapping lives in
+core/mappings/cwe_categories.json; --cwe-mapping swaps in a file of the same shape:
+
+  { "taxonomies": { "<taxonomy>": { "<category>": ["CWE-79", ...] } } }
+
+A CWE may land in several categories (one per taxonomy, usually). Only listed
+CWEs are mapped; there is no ChildOf walk up the CWE hierarchy.
+*/
+
+const BUNDLED_MAPPING: &str = include_str!("../mappings/cwe_categories.json");
+
+#[derive(Debug, Deserialize)]
+struct MappingFile {
+    taxonomies: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+/// CWE id -> category labels
+pub struct CweMapping(HashMap<String, Vec<String>>);
+
+impl CweMapping {
+    fn from_file(file: MappingFile) -> Self {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (taxonomy, categories) in file.taxonomies {
+            for (category, cwes) in categories {
+                let label = format!("{}/{}", taxonomy, category);
+                for cwe in cwes {
+                    map.entry(cwe.trim().to_ascii_uppercase()).or_default().push(label.clone());
+                }
+            }
+        }
+        CweMapping(map)
+    }
+
+    pub fn bundled() -> Result<Self> {
+        let file: MappingFile = serde_json::from_str(BUNDLED_MAPPING).context("Bundled CWE mapping is invalid")?;
+        Ok(Self::from_file(file))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = input::read_input(path)?;
+        let file: MappingFile = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse CWE mapping: {}", path.display()))?;
+        Ok(Self::from_file(file))
+    }
+}
+
+/// Fill cwe_categories on every item. Returns how many items got at least one category.
+pub fn rollup(mapping: &CweMapping, items: &mut [CanonicalItem]) -> usize {
+    let mut mapped = 0;
+    for item in items.iter_mut() {
+        let categories: BTreeSet<&String> = item
+            .cwes
+            .iter()
+            .filter_map(|c| mapping.0.get(c.as_str()))
+            .flatten()
+            .collect();
+        item.cwe_categories = categories.into_iter().cloned().collect();
+        if !item.cwe_categories.is_empty() {
+            mapped += 1;
+        }
+    }
+    mapped
+}
@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+use crate::{digest, CanonicalItem};
+
+/* -------------------- CycloneDX VDR export -------------------- */
+/*
+Writes the item set as a CycloneDX 1.5 BOM holding only a vulnerabilities array
+(a Vulnerability Disclosure Report). It can sit next to our CycloneDX SBOMs and
+be uploaded to Dependency-Track as-is.
+
+Per item:
+- ratings: NVD score (method and vector from cvss_details; "other" without
+  them), plus MSRC and CSAF vendor scores when present
+- source: NVD for CVEs, the internal tracker for INT- ids
+- references/advisories: refs, exploit refs, vendor advisory URLs
+- analysis.state: exploitable (KEV, public exploit or observed in the wild),
+  resolved (a vendor advisory marks it fixed), not_affected (every vendor
+  advisory says so), else in_triage
+
+The serial number comes from a hash of the vulnerabilities, so identical inputs
+produce identical ids.
+*/
+
+fn rating(score: f64, source: &str, url: Option<&str>) -> Value {
+    let mut src = json!({ "name": source });
+    if let Some(u) = url {
+        src["url"] = json!(u);
+    }
+    json!({
+        "source": src,
+        "score": score,
+        "severity": crate::severity::standard_bucket(Some(score)),
+        "method": "other",
+    })
+}
+
+fn analysis(item: &CanonicalItem) -> Value {
+    let observed = item.observed_exploitation.as_ref().is_some_and(|o| o.observed);
+    let mut statuses = item.vendor_advisories.iter().map(|a| a.fix_status.as_str());
+
+    let (state, detail) = if item.kev {
+        ("exploitable", "Listed in CISA KEV")
+    } else if observed {
+        ("exploitable", "Exploitation observed by sensor telemetry")
+    } else if item.exploit_public {
+        ("exploitable", "Public exploit available")
+    } else if item.vendor_advisories.iter().any(|a| a.fix_status == "fixed") {
+        ("resolved", "Vendor advisory reports a fix")
+    } else if !item.vendor_advisories.is_empty() && statuses.all(|s| s == "not_affected") {
+        ("not_affected", "Vendor advisories report not affected")
+    } else {
+        ("in_triage", "No vendor determination yet")
+    };
+    json!({ "state": state, "detail": detail })
+}
+
+fn vulnerability(item: &CanonicalItem) -> Value {
+    let internal = item.id.starts_with("INT-");
+    let source = if internal {
+        json!({ "name": "internal" })
+    } else {
+        json!({ "name": "NVD", "url": format!("https://nvd.nist.gov/vuln/detail/{}", item.id) })
+    };
+
+    let mut ratings = Vec::new();
+    if let Some(score) = item.cvss {
+        let mut r = rating(score, if internal { "internal" } else { "NVD" }, None);
+        // With the NVD metric at hand the rating can name its CVSS version and vector
+        if let Some(d) = &item.cvss_details {
+            let method = match d.version.as_str() {
+                "3.1" => "CVSSv31",
+                "3.0" => "CVSSv3",
+                "2.0" => "CVSSv2",
+                _ => "other",
+            };
+            r["method"] = json!(method);
+            if let Some(v) = &d.vector {
+                r["vector"] = json!(v);
+            }
+        }
+        ratings.push(r);
+    }
+    if let Some(score) = item.msrc.as_ref().and_then(|m| m.cvss) {
+        ratings.push(rating(score, "MSRC", Some("https://msrc.microsoft.com/update-guide")));
+    }
+    for adv in &item.vendor_advisories {
+        if let Some(score) = adv.cvss {
+            ratings.push(rating(score, &adv.publisher, adv.url.as_deref()));
+        }
+    }
+
+    let mut advisories: Vec<Value> = item
+        .refs
+        .iter()
+        .map(|r| r.url.as_str())
+        .chain(item.exploit_refs.iter().map(String::as_str))
+        .map(|u| json!({ "url": u }))
+        .collect();
+    for adv in &item.vendor_advisories {
+        if let Some(url) = &adv.url {
+            let mut a = json!({ "url": url });
+            if let Some(id) = &adv.advisory_id {
+                a["title"] = json!(id);
+            }
+            advisories.push(a);
+        }
+    }
+
+    let cwes: Vec<u64> = item
+        .cwes
+        .iter()
+        .filter_map(|c| c.strip_prefix("CWE-").and_then(|n| n.parse().ok()))
+        .collect();
+
+    let mut v = json!({
+        "bom-ref": item.id,
+        "id": item.id,
+        "source": source,
+        "ratings": ratings,
+        "cwes": cwes,
+        "description": item.short_desc,
+        "advisories": advisories,
+        "analysis": analysis(item),
+    });
+    // NVD timestamps carry no zone; CycloneDX requires one
+    let timestamp = |ts: &Option<String>| ts.as_deref().and_then(crate::parse_iso_datetime).map(|dt| dt.to_rfc3339());
+    if let Some(p) = timestamp(&item.published) {
+        v["published"] = json!(p);
+    }
+    if let Some(m) = timestamp(&item.last_modified) {
+        v["updated"] = json!(m);
+    }
+    if let Some(m) = &item.msrc
+        && !m.kb_articles.is_empty()
+    {
+        v["recommendation"] = json!(format!("Apply Microsoft updates: {}", m.kb_articles.join(", ")));
+    }
+    v
+}
+
+/// "3f2a..." -> "urn:uuid:3f2a....-....-4...-8...-............" (a v4-shaped id from a hash)
+fn serial_number(hex: &str) -> String {
+    format!(
+        "urn:uuid:{}-{}-4{}-8{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
+
+pub fn write_vdr(items: &[CanonicalItem], out: &Path) -> Result<usize> {
+    let vulnerabilities: Vec<Value> = items.iter().map(vulnerability).collect();
+    let fingerprint = digest::sha256_hex(&serde_json::to_vec(&vulnerabilities).unwrap_or_default());
+
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": serial_number(&fingerprint),
+        "version": 1,
+        "metadata": {
+            "timestamp": Utc::now().to_rfc3339(),
+            "tools": { "components": [{ "type": "application", "name": "bastion-core", "version": env!("CARGO_PKG_VERSION") }] },
+        },
+        "vulnerabilities": vulnerabilities,
+    });
+    fs::write(out, serde_json::to_string_pretty(&bom)?)
+        .with_context(|| format!("Failed to write output: {}", out.display()))?;
+    Ok(items.len())
+}
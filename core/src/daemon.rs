@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{log_fail, log_ok, log_warn, metrics, watchdog};
+
+/* -------------------- Scheduled refresh -------------------- */
+/*
+`daemon --interval 6h -- <normalize args>` keeps one output fresh without cron:
+
+  1. --fetch-cmd CMD, if given, runs through the shell (sh -c, cmd /C on
+     Windows) to refresh the raw feeds, e.g. `python orchestrator/ti_run.py
+     --fetch`. A non-zero exit fails the cycle.
+  2. normalize runs with the arguments after `--`, exactly as `normalize` would
+     (globs and {date} placeholders are expanded anew each cycle). The output
+     is written under a temporary name and renamed into place (see
+     codex::write_items), so readers never see half a file. With
+     --since-state and --notify among them, each cycle also posts new KEV and
+     newly critical items to the configured webhooks (see notify.rs).
+  3. --notify-serve HOST:PORT sends POST /-/reload to a running `serve` so it
+     swaps in the new snapshot without waiting for its own --reload poll.
+
+--metrics-listen HOST:PORT serves GET /metrics for Prometheus: the dataset's
+size, buckets and KEV count, the last successful cycle's time, cycle and fetch
+failures, and how long normalize took (see metrics.rs).
+
+A failed cycle is logged and leaves the previous output in place; the next one
+runs on schedule. --max-failures N stops the daemon (exit 1) after N failed
+cycles in a row, so a supervisor can notice; 0 keeps going forever.
+
+Cycles start every --interval measured from the previous start; one that runs
+longer than the interval is followed immediately by the next. The global
+--timeout would kill the whole daemon, so it is rejected here; --max-rss-mb
+and --max-open-files still apply to the process.
+*/
+
+pub struct Schedule {
+    pub interval: Duration,
+    pub fetch_cmd: Option<String>,
+    pub notify_serve: Option<String>,
+    pub max_failures: u32,
+    pub metrics_listen: Option<String>,
+}
+
+/// "90s", "30m", "6h", "1d"; a bare number is seconds.
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let n: u64 = digits.parse().map_err(|_| format!("invalid interval '{}': expected e.g. 90s, 30m, 6h, 1d", s))?;
+    let secs = match unit {
+        "" | "s" => n,
+        "m" => n * 60,
+        "h" => n * 3600,
+        "d" => n * 86400,
+        _ => return Err(format!("invalid interval unit '{}': use s, m, h or d", unit)),
+    };
+    if secs == 0 {
+        return Err("interval must be at least 1 second".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// The interval as parse_interval reads it back (6h, 90m, 45s).
+pub fn human(d: Duration) -> String {
+    match d.as_secs() {
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+fn fetch(cmd: &str) -> Result<()> {
+    let status = if cfg!(windows) {
+        Command::new("cmd").args(["/C", cmd]).status()
+    } else {
+        Command::new("sh").args(["-c", cmd]).status()
+    }
+    .with_context(|| format!("Failed to run fetch command: {}", cmd))?;
+    if !status.success() {
+        anyhow::bail!("fetch command failed ({}): {}", status, cmd);
+    }
+    Ok(())
+}
+
+/// POST /-/reload to a running `serve`; returns its status line.
+pub fn notify_serve(addr: &str) -> Result<String> {
+    let target = addr
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve serve address: {}", addr))?
+        .next()
+        .with_context(|| format!("No address for {}", addr))?;
+    let mut stream = TcpStream::connect_timeout(&target, Duration::from_secs(5))
+        .with_context(|| format!("Failed to connect to serve at {}", addr))?;
+    // Reload parses the whole file before answering
+    stream.set_read_timeout(Some(Duration::from_secs(300)))?;
+    write!(stream, "POST /-/reload HTTP/1.1\r\nHost: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", addr)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).with_context(|| format!("No answer from serve at {}", addr))?;
+    let status = response.lines().next().unwrap_or_default().to_string();
+    if status.split_whitespace().nth(1) != Some("200") {
+        let body = response.split_once("\r\n\r\n").map(|(_, b)| b.trim()).unwrap_or_default();
+        anyhow::bail!("serve at {} did not reload: {} {}", addr, status, body);
+    }
+    Ok(status)
+}
+
+/// Run `normalize` every `schedule.interval` until --max-failures is reached.
+pub fn run(schedule: &Schedule, mut normalize: impl FnMut() -> Result<()>) -> Result<()> {
+    if let Some(addr) = &schedule.metrics_listen {
+        metrics::listen(addr)?;
+    }
+    log_ok!("daemon: refreshing every {}", human(schedule.interval));
+    let mut cycle = 0u64;
+    let mut failures = 0u32;
+    loop {
+        cycle += 1;
+        let started = Instant::now();
+        let (mut fetch_failed, mut normalize_took) = (false, None);
+        let result = (|| -> Result<()> {
+            if let Some(cmd) = &schedule.fetch_cmd {
+                watchdog::phase("daemon: fetching feeds");
+                fetch(cmd).inspect_err(|_| fetch_failed = true)?;
+            }
+            let normalize_started = Instant::now();
+            let result = normalize();
+            normalize_took = Some(normalize_started.elapsed());
+            result
+        })();
+        metrics::record_cycle(&metrics::Cycle {
+            ok: result.is_ok(),
+            fetch_failed,
+            normalize: normalize_took,
+            total: started.elapsed(),
+        });
+
+        match result {
+            Ok(()) => {
+                failures = 0;
+                log_ok!("daemon: cycle {} done in {}s", cycle, started.elapsed().as_secs());
+                if let Some(addr) = &schedule.notify_serve {
+                    match notify_serve(addr) {
+                        Ok(_) => log_ok!("daemon: serve at {} reloaded", addr),
+                        Err(e) => log_warn!("daemon: {:#}", e),
+                    }
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                log_fail!("daemon: cycle {} failed, previous output kept: {:#}", cycle, e);
+                if schedule.max_failures > 0 && failures >= schedule.max_failures {
+                    return Err(e.context(format!("daemon stopped after {} failed cycles in a row", failures)));
+                }
+            }
+        }
+
+        watchdog::phase("daemon: waiting for next cycle");
+        let elapsed = started.elapsed();
+        if elapsed >= schedule.interval {
+            log_warn!(
+                "daemon: cycle {} took {}s, longer than the interval; starting the next now",
+                cycle,
+                elapsed.as_secs()
+            );
+            continue;
+        }
+        thread::sleep(schedule.interval - elapsed);
+    }
+}
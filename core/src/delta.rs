@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{codex, digest, input, CanonicalItem};
+
+/* -------------------- Delta output -------------------- */
+/*
+Most days only a few hundred of the full set's items change. normalize can
+write items.delta.json next to the output with just those:
+
+  {
+    "schema_version": 2, "generated_at": "2025-06-02T06:15:00Z",
+    "since": "state.json", "since_generated_at": "2025-06-01T06:15:00Z",
+    "added": 12, "changed": 310, "removed": 1,
+    "changes": [
+      { "change": "added",   "id": "CVE-2025-0001", "item": { ...full item... } },
+      { "change": "changed", "id": "CVE-2025-0002", "item": { ... },
+        "was": { "content_hash": "...", "cvss": 7.5, "severity_bucket": "high", "kev": false } },
+      { "change": "removed", "id": "CVE-2024-9999", "was": { ... } }
+    ]
+  }
+
+"Changed" means the item's content_hash differs, so provenance-only updates
+don't count (see digest.rs). "was" keeps the old score, bucket and KEV flag so
+a digest (see report.rs) can say what moved without the old snapshot. Changes
+are in canonical ID order.
+
+The previous state comes from one of:
+  --baseline old-items.json   a prior canonical output (its content_hash values)
+  --since-state state.json    a small id -> `was` summary file normalize rewrites
+                              after each successful run; a missing file means
+                              "first run" and every item is added
+
+The delta is listed in the run manifest as a sidecar, so it is hashed, signed
+with the manifest and uploaded with sftp:// outputs.
+*/
+
+/// What a run remembers about each item for the next delta.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Seen {
+    pub content_hash: String,
+    #[serde(default)]
+    pub cvss: Option<f64>,
+    #[serde(default)]
+    pub severity_bucket: String,
+    #[serde(default)]
+    pub kev: bool,
+}
+
+impl Seen {
+    /// Items written before content_hash existed get theirs computed.
+    pub fn of(item: &CanonicalItem) -> Result<Self> {
+        let content_hash =
+            if item.content_hash.is_empty() { digest::item_content_hash(item)? } else { item.content_hash.clone() };
+        Ok(Seen { content_hash, cvss: item.cvss, severity_bucket: item.severity_bucket.clone(), kev: item.kev })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct State {
+    pub schema_version: u32,
+    pub generated_at: String,
+    pub items: BTreeMap<String, Seen>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// One entry of a delta: the item as it is now (not for removed ones) and as it was.
+#[derive(Serialize)]
+pub struct Change<'a> {
+    pub change: ChangeKind,
+    pub id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<&'a CanonicalItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub was: Option<&'a Seen>,
+}
+
+#[derive(Serialize)]
+struct Delta<'a> {
+    schema_version: u32,
+    generated_at: String,
+    since: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since_generated_at: Option<String>,
+    added: usize,
+    changed: usize,
+    removed: usize,
+    changes: Vec<Change<'a>>,
+}
+
+pub struct Previous {
+    pub label: String, // path as given, for the delta header
+    pub generated_at: Option<String>,
+    pub seen: HashMap<String, Seen>,
+}
+
+/// A delta file as read back (by `report`).
+#[derive(Debug, Deserialize)]
+pub struct DeltaFile {
+    pub since: String,
+    #[serde(default)]
+    pub since_generated_at: Option<String>,
+    pub changes: Vec<ChangeRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeRecord {
+    pub change: ChangeKind,
+    pub id: String,
+    #[serde(default)]
+    pub item: Option<CanonicalItem>,
+    #[serde(default)]
+    pub was: Option<Seen>,
+}
+
+#[derive(Debug, Default)]
+pub struct DeltaStats {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+}
+
+/// items.json -> items.delta.json
+pub fn delta_path(out_path: &Path) -> PathBuf {
+    let stem = codex::output_stem(out_path);
+    out_path.with_file_name(format!("{}.delta.json", stem))
+}
+
+/// Summaries of a prior canonical output.
+pub fn from_baseline(path: &Path) -> Result<Previous> {
+    from_items(path.display().to_string(), None, &codex::read_items(path)?)
+}
+
+/// Summaries of items already loaded (a snapshot-store run, for the gRPC Diff).
+pub fn from_items(label: String, generated_at: Option<String>, items: &[CanonicalItem]) -> Result<Previous> {
+    let seen = items.iter().map(|i| Ok((i.id.clone(), Seen::of(i)?))).collect::<Result<_>>()?;
+    Ok(Previous { label, generated_at, seen })
+}
+
+/// Saved state from the last run, or an empty one if the file doesn't exist yet.
+pub fn from_state(path: &Path) -> Result<Previous> {
+    let label = path.display().to_string();
+    if !path.exists() {
+        return Ok(Previous { label, generated_at: None, seen: HashMap::new() });
+    }
+    let bytes = input::read_input(path)?;
+    let state: State =
+        serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse delta state: {}", path.display()))?;
+    Ok(Previous { label, generated_at: Some(state.generated_at), seen: state.items.into_iter().collect() })
+}
+
+pub fn read(path: &Path) -> Result<DeltaFile> {
+    let bytes = input::read_input(path)?;
+    serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse delta: {}", path.display()))
+}
+
+/// What differs between `items` (content hashes stamped) and `prev`, in canonical ID order.
+pub fn changes<'a>(items: &'a [CanonicalItem], prev: &'a Previous) -> (Vec<Change<'a>>, DeltaStats) {
+    let mut stats = DeltaStats::default();
+    let mut changes = Vec::new();
+    for item in items {
+        let was = prev.seen.get(&item.id);
+        let kind = match was {
+            None => ChangeKind::Added,
+            Some(w) if w.content_hash != item.content_hash => ChangeKind::Changed,
+            Some(_) => continue,
+        };
+        match kind {
+            ChangeKind::Added => stats.added += 1,
+            _ => stats.changed += 1,
+        }
+        changes.push(Change { change: kind, id: &item.id, item: Some(item), was });
+    }
+    let current: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+    for (id, was) in prev.seen.iter().filter(|(id, _)| !current.contains(id.as_str())) {
+        stats.removed += 1;
+        changes.push(Change { change: ChangeKind::Removed, id, item: None, was: Some(was) });
+    }
+    changes.sort_by_cached_key(|c| codex::id_key(c.id));
+    (changes, stats)
+}
+
+/// Write the delta of `items` (content hashes stamped) against `prev`.
+pub fn write(path: &Path, items: &[CanonicalItem], prev: &Previous) -> Result<DeltaStats> {
+    let (changes, stats) = changes(items, prev);
+    let delta = Delta {
+        schema_version: codex::SCHEMA_VERSION,
+        generated_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        since: prev.label.clone(),
+        since_generated_at: prev.generated_at.clone(),
+        added: stats.added,
+        changed: stats.changed,
+        removed: stats.removed,
+        changes,
+    };
+    let file = fs::File::create(path).with_context(|| format!("Failed to write delta: {}", path.display()))?;
+    let mut w = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut w, &delta)?;
+    w.write_all(b"\n")?;
+    w.flush().with_context(|| format!("Failed to write delta: {}", path.display()))?;
+    Ok(stats)
+}
+
+/// Record this run's hashes for the next --since-state run.
+pub fn save_state(path: &Path, items: &[CanonicalItem]) -> Result<()> {
+    let state = State {
+        schema_version: codex::SCHEMA_VERSION,
+        generated_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        items: items.iter().map(|i| Ok((i.id.clone(), Seen::of(i)?))).collect::<Result<_>>()?,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create state dir: {}", parent.display()))?;
+    }
+    // Write-then-rename so an interrupted run leaves the old state intact
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(&state)?).with_context(|| format!("Failed to write delta state: {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to write delta state: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::CanonicalItem;
+
+/* -------------------- Snapshot diff -------------------- */
+/*
+Compares two canonical snapshots by CVE ID: added, removed, severity bucket
+changes, newly KEV-flagged items and CVSS score changes. Every list is sorted by
+ID so two runs over the same inputs produce identical reports.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeverityChange {
+    pub id: String,
+    pub old: String,
+    pub new: String,
+    pub escalation: bool, // moved to a higher bucket (unknown counts as lowest)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CvssChange {
+    pub id: String,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    pub old_count: usize,
+    pub new_count: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub severity_changes: Vec<SeverityChange>,
+    pub new_kev: Vec<String>,
+    pub cvss_changes: Vec<CvssChange>,
+}
+
+/// Rank under the active severity policy (see severity.rs); unknown counts as lowest.
+pub fn severity_rank(bucket: &str) -> u8 {
+    crate::severity::rank(bucket)
+}
+
+pub fn diff_items(old: &[CanonicalItem], new: &[CanonicalItem]) -> DiffReport {
+    let old_by_id: HashMap<&str, &CanonicalItem> = old.iter().map(|i| (i.id.as_str(), i)).collect();
+    let new_by_id: HashMap<&str, &CanonicalItem> = new.iter().map(|i| (i.id.as_str(), i)).collect();
+
+    let mut report = DiffReport {
+        old_count: old_by_id.len(),
+        new_count: new_by_id.len(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        severity_changes: Vec::new(),
+        new_kev: Vec::new(),
+        cvss_changes: Vec::new(),
+    };
+
+    for (id, n) in &new_by_id {
+        let Some(o) = old_by_id.get(id) else {
+            report.added.push(id.to_string());
+            continue;
+        };
+        if o.severity_bucket != n.severity_bucket {
+            report.severity_changes.push(SeverityChange {
+                id: id.to_string(),
+                old: o.severity_bucket.clone(),
+                new: n.severity_bucket.clone(),
+                escalation: severity_rank(&n.severity_bucket) > severity_rank(&o.severity_bucket),
+            });
+        }
+        if n.kev && !o.kev {
+            report.new_kev.push(id.to_string());
+        }
+        if o.cvss != n.cvss {
+            report.cvss_changes.push(CvssChange { id: id.to_string(), old: o.cvss, new: n.cvss });
+        }
+    }
+    report.removed = old_by_id.keys().filter(|id| !new_by_id.contains_key(*id)).map(|id| id.to_string()).collect();
+
+    report.added.sort();
+    report.removed.sort();
+    report.severity_changes.sort_by(|a, b| a.id.cmp(&b.id));
+    report.new_kev.sort();
+    report.cvss_changes.sort_by(|a, b| a.id.cmp(&b.id));
+    report
+}
+
+fn score(s: Option<f64>) -> String {
+    s.map(|v| v.to_string()).unwrap_or_else(|| "n/a".to_string())
+}
+
+pub fn render_markdown(r: &DiffReport) -> String {
+    let mut out = Vec::new();
+    out.push("# Codex diff".to_string());
+    out.push(String::new());
+    out.push(format!("- Items: {} → {}", r.old_count, r.new_count));
+    out.push(format!("- Added: {}", r.added.len()));
+    out.push(format!("- Removed: {}", r.removed.len()));
+    out.push(format!("- Severity changes: {}", r.severity_changes.len()));
+    out.push(format!("- Newly KEV-listed: {}", r.new_kev.len()));
+    out.push(format!("- CVSS changes: {}", r.cvss_changes.len()));
+
+    let mut list = |title: &str, lines: Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        out.push(String::new());
+        out.push(format!("## {}", title));
+        out.extend(lines);
+    };
+
+    list("Newly KEV-listed", r.new_kev.iter().map(|id| format!("- {}", id)).collect());
+    list(
+        "Severity changes",
+        r.severity_changes
+            .iter()
+            .map(|c| {
+                let tag = if c.escalation { " ⚠ escalation" } else { "" };
+                format!("- {}: {} → {}{}", c.id, c.old, c.new, tag)
+            })
+            .collect(),
+    );
+    list(
+        "CVSS changes",
+        r.cvss_changes.iter().map(|c| format!("- {}: {} → {}", c.id, score(c.old), score(c.new))).collect(),
+    );
+    list("Added", r.added.iter().map(|id| format!("- {}", id)).collect());
+    list("Removed", r.removed.iter().map(|id| format!("- {}", id)).collect());
+
+    out.push(String::new());
+    out.join("\n")
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::{fs::File, io::Read, path::Path};
+
+use crate::CanonicalItem;
+
+/* -------------------- Content digests -------------------- */
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Feed a file's raw bytes (no decompression) into `hasher`. Returns the byte count.
+pub fn update_from_file(hasher: &mut Sha256, path: &Path) -> Result<u64> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut buf = vec![0u8; 1 << 20];
+    let mut total = 0u64;
+    loop {
+        let n = file.read(&mut buf).with_context(|| format!("Failed to hash {}", path.display()))?;
+        if n == 0 {
+            return Ok(total);
+        }
+        hasher.update(&buf[..n]);
+        total += n as u64;
+    }
+}
+
+/// SHA-256 and size of a file's raw bytes.
+pub fn sha256_file(path: &Path) -> Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let bytes = update_from_file(&mut hasher, path)?;
+    Ok((hex(&hasher.finalize()), bytes))
+}
+
+/// SHA-256 over an item's serialized fields, excluding `content_hash` itself
+/// and the provenance map (fresh downloads of unchanged data move its timestamps).
+///
+/// Consumers syncing into their own stores compare this instead of diffing
+/// whole records to find items that actually changed between runs.
+pub fn item_content_hash(item: &CanonicalItem) -> Result<String> {
+    let mut unhashed = item.clone();
+    unhashed.content_hash = String::new();
+    unhashed.provenance.clear();
+    Ok(sha256_hex(&serde_json::to_vec(&unhashed)?))
+}
+
+/// Fill `content_hash` on every item. Call after all merges/truncation.
+pub fn stamp_content_hashes(items: &mut [CanonicalItem]) -> Result<()> {
+    for item in items.iter_mut() {
+        item.content_hash = item_content_hash(item)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::CanonicalItem;
+
+/* -------------------- Linux distro security trackers -------------------- */
+/*
+Per-release package status from distro trackers, so the canonical feed can drive
+container-image triage directly. Keys in `distro_status` are "<distro>:<release>".
+
+Supported inputs:
+- Debian security tracker JSON (security-tracker.debian.org/tracker/data/json)
+    { "<srcpkg>": { "CVE-..": { "releases": { "<codename>": { status, urgency, fixed_version } } } } }
+- Ubuntu USN database JSON (usn.ubuntu.com/usn-db/database.json)
+    { "USN-N-N": { "cves": [..], "releases": { "<codename>": { "sources": { "<pkg>": { version } } } } } }
+- Alpine secdb (secdb.alpinelinux.org/<ver>/<repo>.json)
+    { "distroversion", "packages": [ { "pkg": { "name", "secfixes": { "<ver>": ["CVE-.."] } } } ] }
+- Red Hat / SUSE OVAL definition streams (XML), keyed rhel:9, sles:15-sp5, ... (see oval.rs)
+*/
+
+/// Status of one package in one distro release.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PackageStatus {
+    pub package: String,
+    pub status: String,                // fixed|vulnerable|ignored
+    pub fixed_version: Option<String>,
+}
+
+/// Aggregate status for a distro release plus the per-package detail.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DistroStatus {
+    pub status: String,                // vulnerable if any package is, else fixed, else ignored
+    pub packages: Vec<PackageStatus>,
+}
+
+type ReleaseMap = HashMap<String, BTreeMap<String, Vec<PackageStatus>>>;
+
+fn add(out: &mut ReleaseMap, cve: &str, release_key: String, pkg: PackageStatus) {
+    out.entry(cve.trim().to_string())
+        .or_default()
+        .entry(release_key)
+        .or_default()
+        .push(pkg);
+}
+
+/* ---- Debian ---- */
+
+#[derive(Debug, Deserialize)]
+struct DebianCve {
+    #[serde(default)]
+    releases: HashMap<String, DebianRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DebianRelease {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    urgency: Option<String>,
+    #[serde(default)]
+    fixed_version: Option<String>,
+}
+
+fn parse_debian(bytes: &[u8], out: &mut ReleaseMap) -> Result<()> {
+    let root: HashMap<String, HashMap<String, DebianCve>> =
+        serde_json::from_slice(bytes).with_context(|| "Failed to parse Debian tracker JSON")?;
+
+    for (package, cves) in root {
+        for (cve, entry) in cves {
+            if !cve.starts_with("CVE-") {
+                continue; // TEMP-* placeholders
+            }
+            for (release, r) in entry.releases {
+                let urgency = r.urgency.as_deref().unwrap_or_default();
+                let status = match r.status.as_deref() {
+                    Some("resolved") => "fixed",
+                    _ if urgency == "unimportant" || urgency == "end-of-life" => "ignored",
+                    _ => "vulnerable",
+                };
+                // Debian uses fixed_version "0" for "never affected"
+                let (status, fixed_version) = match r.fixed_version.as_deref() {
+                    Some("0") => ("ignored", None),
+                    _ => (status, r.fixed_version.filter(|_| status == "fixed")),
+                };
+                add(out, &cve, format!("debian:{}", release), PackageStatus {
+                    package: package.clone(),
+                    status: status.to_string(),
+                    fixed_version,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/* ---- Ubuntu USN ---- */
+
+#[derive(Debug, Deserialize)]
+struct UsnEntry {
+    #[serde(default)]
+    cves: Vec<String>,
+    #[serde(default)]
+    releases: HashMap<String, UsnRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsnRelease {
+    #[serde(default)]
+    sources: HashMap<String, UsnSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsnSource {
+    #[serde(default)]
+    version: Option<String>,
+}
+
+fn parse_ubuntu_usn(bytes: &[u8], out: &mut ReleaseMap) -> Result<()> {
+    let root: HashMap<String, UsnEntry> =
+        serde_json::from_slice(bytes).with_context(|| "Failed to parse Ubuntu USN JSON")?;
+
+    // USNs only announce fixes, so everything here is "fixed".
+    for usn in root.into_values() {
+        for cve in usn.cves.iter().filter(|c| c.starts_with("CVE-")) {
+            for (release, r) in &usn.releases {
+                for (package, src) in &r.sources {
+                    add(out, cve, format!("ubuntu:{}", release), PackageStatus {
+                        package: package.clone(),
+                        status: "fixed".to_string(),
+                        fixed_version: src.version.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/* ---- Alpine secdb ---- */
+
+#[derive(Debug, Deserialize)]
+struct AlpineRoot {
+    #[serde(default)]
+    distroversion: Option<String>,
+    #[serde(default)]
+    packages: Vec<AlpinePackageWrap>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlpinePackageWrap {
+    pkg: AlpinePackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlpinePackage {
+    name: String,
+    #[serde(default)]
+    secfixes: HashMap<String, Vec<String>>,
+}
+
+fn parse_alpine(bytes: &[u8], out: &mut ReleaseMap) -> Result<()> {
+    let root: AlpineRoot =
+        serde_json::from_slice(bytes).with_context(|| "Failed to parse Alpine secdb JSON")?;
+    let release = root.distroversion.unwrap_or_else(|| "unknown".to_string());
+
+    for wrap in root.packages {
+        for (version, cves) in &wrap.pkg.secfixes {
+            for cve in cves.iter() {
+                // Entries can carry aliases: "CVE-2023-1234 GHSA-xxxx"
+                let Some(cve) = cve.split_whitespace().find(|c| c.starts_with("CVE-")) else { continue; };
+                let (status, fixed_version) = if version == "0" {
+                    ("ignored", None)
+                } else {
+                    ("fixed", Some(version.clone()))
+                };
+                add(out, cve, format!("alpine:{}", release), PackageStatus {
+                    package: wrap.pkg.name.clone(),
+                    status: status.to_string(),
+                    fixed_version,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/* ---- Merge ---- */
+
+fn aggregate(mut packages: Vec<PackageStatus>) -> DistroStatus {
+    packages.sort_by(|a, b| a.package.cmp(&b.package).then_with(|| a.fixed_version.cmp(&b.fixed_version)));
+    let any = |s: &str| packages.iter().any(|p| p.status == s);
+    let status = if any("vulnerable") {
+        "vulnerable"
+    } else if any("fixed") {
+        "fixed"
+    } else {
+        "ignored"
+    };
+    DistroStatus { status: status.to_string(), packages }
+}
+
+/// Distro tracker inputs for one normalize run.
+pub struct DistroInputs<'a> {
+    pub debian: Option<&'a Path>,
+    pub ubuntu_usn: Option<&'a Path>,
+    pub alpine_secdb: &'a [std::path::PathBuf],
+    pub oval: &'a [std::path::PathBuf],
+}
+
+/// Parse the provided tracker files and fill `distro_status` on matching items.
+/// Returns the number of items that received distro data.
+pub fn merge_distro(inputs: &DistroInputs, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut by_source: Vec<(&str, ReleaseMap)> = Vec::new();
+
+    let read = |p: &Path| fs::read(p).with_context(|| format!("Failed to read distro tracker: {}", p.display()));
+
+    if let Some(p) = inputs.debian {
+        let mut m = ReleaseMap::new();
+        parse_debian(&read(p)?, &mut m)?;
+        by_source.push(("debian", m));
+    }
+    if let Some(p) = inputs.ubuntu_usn {
+        let mut m = ReleaseMap::new();
+        parse_ubuntu_usn(&read(p)?, &mut m)?;
+        by_source.push(("ubuntu", m));
+    }
+    if !inputs.alpine_secdb.is_empty() {
+        let mut m = ReleaseMap::new();
+        for p in inputs.alpine_secdb {
+            parse_alpine(&read(p)?, &mut m)?;
+        }
+        by_source.push(("alpine", m));
+    }
+    if !inputs.oval.is_empty() {
+        let mut m = ReleaseMap::new();
+        for p in inputs.oval {
+            let statuses = crate::oval::parse_oval(&crate::input::read_input(p)?)
+                .with_context(|| format!("Failed to read OVAL stream: {}", p.display()))?;
+            for (cve, release, pkg) in statuses {
+                add(&mut m, &cve, release, pkg);
+            }
+        }
+        by_source.push(("oval", m));
+    }
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let mut touched = false;
+        for (source, map) in by_source.iter_mut() {
+            let Some(releases) = map.remove(&item.id) else { continue; };
+            for (release, packages) in releases {
+                item.distro_status.insert(release, aggregate(packages));
+            }
+            if !item.sources.iter().any(|s| s == source) {
+                item.sources.push(source.to_string());
+            }
+            touched = true;
+        }
+        if touched {
+            merged += 1;
+        }
+    }
+
+    Ok(merged)
+}
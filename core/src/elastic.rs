@@ -0,0 +1,238 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::{delta, digest, http, log_ok, log_warn, CanonicalItem};
+
+/* -------------------- Elasticsearch / OpenSearch export -------------------- */
+/*
+export --format elasticsearch bulk-indexes items straight into a cluster, so
+Kibana / OpenSearch Dashboards can read items.json without a Logstash hop:
+
+  export --input items.json --format elasticsearch --url https://es:9200 --index vulns
+
+- each item is one document, _id = CVE ID, sent with the bulk "index" action:
+  a rerun replaces documents in place (upsert), it never duplicates them
+- the index is created with mapping() if it doesn't exist: dates as date,
+  IDs, buckets and names as keyword (vendor/product with a .text subfield),
+  descriptions as text, refs / affected / affected_versions as nested so a
+  query can match url and kind of the same ref. distro_status, descriptions
+  and provenance are kept in _source but not indexed (their keys are open-ended)
+- --since-state FILE sends only items whose content_hash changed since the run
+  that wrote FILE, and deletes the IDs that disappeared; FILE is rewritten
+  after every batch went through (same format as normalize --since-state)
+- --out FILE writes the bulk NDJSON instead of sending it (curl it yourself)
+
+Requests go through http.rs (proxy, CA bundle, retries), with URL, headers and
+body on curl's stdin so nothing shows up in the process list. Credentials: user:pass@
+in --url, or an API key in BASTION_ES_API_KEY (Authorization: ApiKey ...).
+Works against Elasticsearch 7/8 and OpenSearch 1/2 (no mapping types).
+*/
+
+pub const DEFAULT_INDEX: &str = "vulns";
+pub const DEFAULT_BATCH: usize = 500;
+
+fn keyword() -> Value {
+    json!({ "type": "keyword" })
+}
+
+fn date() -> Value {
+    json!({ "type": "date" })
+}
+
+fn not_indexed() -> Value {
+    json!({ "type": "object", "enabled": false })
+}
+
+/// Index settings and mappings for canonical item documents.
+pub fn mapping() -> Value {
+    let name = json!({ "type": "keyword", "fields": { "text": { "type": "text" } } });
+    json!({
+        "mappings": {
+            "dynamic_templates": [
+                { "strings": {
+                    "match_mapping_type": "string",
+                    "mapping": { "type": "keyword", "ignore_above": 1024 }
+                } }
+            ],
+            "properties": {
+                "id": keyword(),
+                "sources": keyword(),
+                "published": date(),
+                "last_modified": date(),
+                "cvss": { "type": "float" },
+                "severity_bucket": keyword(),
+                "kev": { "type": "boolean" },
+                "kev_date_added": date(),
+                "kev_due_date": date(),
+                "kev_required_action": { "type": "text" },
+                "short_desc": { "type": "text" },
+                "title": { "type": "text" },
+                "descriptions": not_indexed(),
+                "vendor": name.clone(),
+                "product": name,
+                "refs": {
+                    "type": "nested",
+                    "properties": { "url": keyword(), "kind": keyword(), "source": keyword(), "dead": keyword() }
+                },
+                "fix_refs": keyword(),
+                "cwes": keyword(),
+                "affected": { "type": "nested" },
+                "affected_versions": { "type": "nested" },
+                "cwe_categories": keyword(),
+                "attack_techniques": keyword(),
+                "tags": keyword(),
+                "distro_status": not_indexed(),
+                "exploit_public": { "type": "boolean" },
+                "exploit_refs": keyword(),
+                "has_nuclei_template": { "type": "boolean" },
+                "epss": { "type": "float" },
+                "priority_score": { "type": "float" },
+                "priority_tier": keyword(),
+                "internal_notes": { "type": "text" },
+                "provenance": not_indexed(),
+                "content_hash": keyword()
+            }
+        }
+    })
+}
+
+/// `method` `url` with an optional body; returns (HTTP status, response body).
+fn request(method: &str, url: &str, content_type: &str, body: Option<&str>) -> Result<(u16, String)> {
+    let mut config = format!("url = {}\nrequest = {}\n", http::config_quote(url), http::config_quote(method));
+    config.push_str(&format!("header = {}\n", http::config_quote(&format!("Content-Type: {}", content_type))));
+    if let Ok(key) = std::env::var("BASTION_ES_API_KEY") {
+        let auth = format!("Authorization: ApiKey {}", key.trim());
+        config.push_str(&format!("header = {}\n", http::config_quote(&auth)));
+    }
+    if let Some(body) = body {
+        config.push_str(&format!("data-binary = {}\n", http::config_quote(body)));
+    }
+
+    let response = http::send_retrying(&format!("{} {}", method, url), &["--max-time", "120"], &config)?;
+    if response.status == 0 {
+        bail!("{} {}: {} (curl exit {})", method, url, response.detail(), response.code);
+    }
+    Ok((response.status, response.text()))
+}
+
+fn base(url: &str) -> &str {
+    url.trim_end_matches('/')
+}
+
+/// Create `index` with mapping() unless it exists.
+pub fn ensure_index(url: &str, index: &str) -> Result<bool> {
+    let target = format!("{}/{}", base(url), index);
+    let (status, body) = request("PUT", &target, "application/json", Some(&mapping().to_string()))?;
+    match status {
+        200..=299 => Ok(true),
+        400 if body.contains("resource_already_exists_exception") => Ok(false),
+        _ => bail!("creating index {} failed (HTTP {}): {}", index, status, body.trim()),
+    }
+}
+
+/// Bulk actions for one run: index changed items, delete `removed` IDs.
+pub fn bulk_lines(index: &str, items: &[&CanonicalItem], removed: &[String]) -> Result<Vec<String>> {
+    let mut lines = Vec::with_capacity(items.len() + removed.len());
+    for item in items {
+        let action = json!({ "index": { "_index": index, "_id": item.id } });
+        lines.push(format!("{}\n{}\n", action, serde_json::to_string(item)?));
+    }
+    for id in removed {
+        lines.push(format!("{}\n", json!({ "delete": { "_index": index, "_id": id } })));
+    }
+    Ok(lines)
+}
+
+// Failed actions in a bulk response, as "ID: reason" (first few)
+fn bulk_failures(body: &str) -> Result<(usize, Vec<String>)> {
+    let response: Value = serde_json::from_str(body).context("bulk response is not JSON")?;
+    if response.get("errors").and_then(Value::as_bool) != Some(true) {
+        return Ok((0, Vec::new()));
+    }
+    let failed: Vec<&Value> = response["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|i| i.as_object()?.values().next())
+        .filter(|r| r.get("error").is_some())
+        .collect();
+    let shown = failed
+        .iter()
+        .take(5)
+        .map(|r| {
+            let reason = r["error"].get("reason").and_then(Value::as_str).unwrap_or("unknown error");
+            format!("{}: {}", r["_id"].as_str().unwrap_or("?"), reason)
+        })
+        .collect();
+    Ok((failed.len(), shown))
+}
+
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub indexed: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+pub struct EsOptions<'a> {
+    pub url: Option<&'a str>,
+    pub index: &'a str,
+    pub batch: usize,
+    pub since_state: Option<&'a Path>,
+    pub out: Option<&'a Path>,
+}
+
+/// Send (or with `out`, write) the bulk requests for `items`.
+pub fn export(items: &mut [CanonicalItem], opts: &EsOptions) -> Result<ExportStats> {
+    digest::stamp_content_hashes(items)?;
+    let prev = opts.since_state.map(delta::from_state).transpose()?;
+    let mut stats = ExportStats::default();
+    let send: Vec<&CanonicalItem> = items
+        .iter()
+        .filter(|i| prev.as_ref().is_none_or(|p| p.seen.get(&i.id).is_none_or(|w| w.content_hash != i.content_hash)))
+        .collect();
+    stats.unchanged = items.len() - send.len();
+    let current: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+    let mut removed: Vec<String> = prev
+        .iter()
+        .flat_map(|p| p.seen.keys())
+        .filter(|id| !current.contains(id.as_str()))
+        .cloned()
+        .collect();
+    removed.sort();
+    let lines = bulk_lines(opts.index, &send, &removed)?;
+    stats.indexed = send.len();
+    stats.deleted = removed.len();
+
+    if let Some(out) = opts.out {
+        fs::write(out, lines.concat()).with_context(|| format!("Failed to write bulk file: {}", out.display()))?;
+    } else {
+        let Some(url) = opts.url else { bail!("elasticsearch export needs --url or --out") };
+        if ensure_index(url, opts.index)? {
+            log_ok!("created index {} with the canonical mapping", opts.index);
+        }
+        let endpoint = format!("{}/_bulk", base(url));
+        let batches = lines.chunks(opts.batch.max(1));
+        let total = batches.len();
+        for (n, batch) in batches.enumerate() {
+            let (status, body) = request("POST", &endpoint, "application/x-ndjson", Some(&batch.concat()))?;
+            if !(200..300).contains(&status) {
+                bail!("bulk request {}/{} failed (HTTP {}): {}", n + 1, total, status, body.trim());
+            }
+            let (failed, shown) = bulk_failures(&body)?;
+            if failed > 0 {
+                bail!("bulk request {}/{}: {} actions failed: {}", n + 1, total, failed, shown.join("; "));
+            }
+        }
+    }
+
+    if let Some(state) = opts.since_state {
+        if opts.out.is_some() {
+            log_warn!("--since-state {} not updated: the bulk file hasn't been sent yet", state.display());
+        } else {
+            delta::save_state(state, items)?;
+        }
+    }
+    Ok(stats)
+}
@@ -0,0 +1,117 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::{fmt, path::Path};
+
+/* -------------------- Structured error output -------------------- */
+/*
+With --error-format json a failed run prints one document on stderr instead of
+the anyhow chain, so wrappers can act on it without scraping context strings:
+
+  { "error": { "code": "invalid_item", "message": "...", "path": "items.json",
+               "record": 12, "line": 40, "column": 7, "details": ..., "causes": [...] } }
+
+Failure sites that know more than a message attach a `Failure` as context; the
+outermost one in the chain supplies code/path/record. JSON syntax errors add
+line/column (for NDJSON, the line in the file). Errors nobody tagged get code
+"error". Exit status is 1 unless the failure names its own (gate policies do).
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// anyhow's human-readable chain
+    Text,
+    /// One JSON document on stderr
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    pub code: &'static str,  // invalid_json|invalid_item|invalid_record|validation_failed|lint_failed|...
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<usize>,  // 0-based index into the input's items/records
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,    // 1-based line in the file, when the parser's own is relative
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>, // e.g. schema anomalies or lint findings
+    #[serde(skip)]
+    pub exit_code: Option<i32>,
+}
+
+impl Failure {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Failure { code, message: message.into(), path: None, record: None, line: None, details: None, exit_code: None }
+    }
+
+    pub fn path(mut self, path: &Path) -> Self {
+        self.path = Some(path.display().to_string());
+        self
+    }
+
+    pub fn record(mut self, record: usize) -> Self {
+        self.record = Some(record);
+        self
+    }
+
+    pub fn line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
+
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Process exit status for a failed run.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    err.downcast_ref::<Failure>().and_then(|f| f.exit_code).unwrap_or(1)
+}
+
+pub fn print_json(err: &anyhow::Error) {
+    // anyhow's downcast looks through context layers, outermost first
+    let failure = err.downcast_ref::<Failure>();
+    let syntax = err.downcast_ref::<serde_json::Error>();
+
+    let mut doc = serde_json::json!({
+        "code": failure.map_or("error", |f| f.code),
+        "message": err.to_string(),
+    });
+    if let Some(f) = failure {
+        if let Some(path) = &f.path {
+            doc["path"] = path.as_str().into();
+        }
+        if let Some(record) = f.record {
+            doc["record"] = record.into();
+        }
+        if let Some(details) = &f.details {
+            doc["details"] = details.clone();
+        }
+    }
+    if let Some(e) = syntax
+        && e.line() > 0
+    {
+        doc["line"] = failure.and_then(|f| f.line).unwrap_or(e.line()).into();
+        doc["column"] = e.column().into();
+    }
+    doc["causes"] = err.chain().skip(1).map(|e| e.to_string()).collect::<Vec<_>>().into();
+
+    eprintln!("{}", serde_json::json!({ "error": doc }));
+}
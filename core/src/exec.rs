@@ -0,0 +1,93 @@
+use anyhow::{bail, Context, Result};
+use std::process::{Command, Stdio};
+
+use crate::{
+    lenient,
+    source::{PartialItem, Source},
+};
+
+/* -------------------- External-command sources -------------------- */
+/*
+`--source exec:NAME=COMMAND` runs COMMAND through the shell (sh -c, cmd /C on
+Windows) and reads one PartialItem per line of its stdout, so a proprietary
+feed needs an adapter script, not a fork:
+
+  {"id":"CVE-2025-1234","vendor":"Acme","description":"Heap overflow in ...",
+   "refs":[{"url":"https://intel.example/a/991","kind":"advisory","source":"acme"}]}
+  {"id":"CVE-2025-5678","kev":{"date_added":"2025-06-01"}}
+
+Fields are those of PartialItem (see source.rs); only id is required and
+unknown fields are rejected, so a typo fails the run rather than dropping data.
+Blank lines are skipped. NAME is what `sources` records for the items. The
+command's stderr passes through; a non-zero exit fails the run.
+
+The command's output isn't a file, so it can't be listed in the run manifest
+or archived for replay: have the adapter write a file and `cat` it if runs must
+be reproducible.
+*/
+
+pub struct ExecSource {
+    name: String,
+    command: String,
+}
+
+impl ExecSource {
+    /// "NAME=COMMAND"
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some((name, command)) = spec.split_once('=') else {
+            bail!("invalid exec source '{}': expected NAME=COMMAND", spec);
+        };
+        let (name, command) = (name.trim(), command.trim());
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("invalid exec source name '{}': use letters, digits, '-' and '_'", name);
+        }
+        if command.is_empty() {
+            bail!("exec source {} has an empty command", name);
+        }
+        Ok(ExecSource { name: name.to_string(), command: command.to_string() })
+    }
+}
+
+impl Source for ExecSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn items(&self) -> Result<Vec<PartialItem>> {
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(["/C", &self.command]).stdin(Stdio::null()).stderr(Stdio::inherit()).output()
+        } else {
+            Command::new("sh").args(["-c", &self.command]).stdin(Stdio::null()).stderr(Stdio::inherit()).output()
+        }
+        .with_context(|| format!("Failed to run {} source command: {}", self.name, self.command))?;
+        if !output.status.success() {
+            bail!("{} source command failed ({}): {}", self.name, output.status, self.command);
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .with_context(|| format!("{} source command wrote non-UTF-8 output", self.name))?;
+        let mut items = Vec::new();
+        for (n, line) in stdout.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = serde_json::from_str::<PartialItem>(line).map_err(anyhow::Error::from).and_then(|mut item| {
+                item.id = item.id.trim().to_string();
+                if item.id.is_empty() {
+                    bail!("item without an id");
+                }
+                Ok(item)
+            });
+            match parsed {
+                Ok(item) => items.push(item),
+                // --lenient: report the line and go on (see lenient.rs)
+                Err(e) if lenient::enabled() => {
+                    let record = serde_json::from_str(line).unwrap_or_default();
+                    lenient::skip(&self.name, &self.command, n + 1, &record, "/id", format!("{:#}", e));
+                }
+                Err(e) => return Err(e.context(format!("{} source: line {}: invalid item", self.name, n + 1))),
+            }
+        }
+        Ok(items)
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    io::BufRead,
+    path::{Path, PathBuf},
+};
+
+use crate::{input, log_ok, CanonicalItem};
+
+/* -------------------- Exploited-in-the-wild evidence -------------------- */
+/*
+`kev` is CISA's catalog only. VulnCheck KEV and community trackers list a lot
+more exploitation, so item.exploited collects every listing:
+
+  "exploited": { "cisa_kev": true, "other_sources": [
+    { "source": "vulncheck-kev", "date_added": "2024-01-03", "references": ["https://..."] } ] }
+
+- cisa_kev mirrors `kev` (set after --merge-into and the KEV pass, by sync_kev)
+- other_sources comes from --exploited-feed [NAME=]PATH, repeatable. NAME is
+  the source recorded on the item; without it a VulnCheck KEV file is
+  "vulncheck-kev" and anything else is named after the file
+- items neither CISA nor a feed lists have no `exploited`
+
+Feed formats, told apart by content:
+- VulnCheck KEV (API `data` or the backup's JSON array): cve[],
+  date_added, knownRansomwareCampaignUse, vulncheck_reported_exploitation[].url
+- generic JSON (an array, { "data": [...] }, { "vulnerabilities": [...] } as in
+  CISA-shaped mirrors, or JSON lines): one object per listing with the CVE in
+  cve / cveID / cve_id / id (string or list), optional date_added / dateAdded,
+  and reference URLs in references / urls / url
+- CSV with a header naming a cve column, optional date_added and url columns
+*/
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Exploited {
+    pub cisa_kev: bool,
+    #[serde(default)]
+    pub other_sources: Vec<ExploitedListing>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ExploitedListing {
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_added: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ransomware_known: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+}
+
+/// One --exploited-feed value.
+#[derive(Debug, Clone)]
+pub struct ExploitedFeed {
+    pub name: Option<String>,
+    pub path: PathBuf,
+}
+
+/// NAME=PATH or PATH (a clap value parser).
+pub fn parse_feed(spec: &str) -> Result<ExploitedFeed, String> {
+    let feed = match spec.split_once('=') {
+        Some((name, path))
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            ExploitedFeed { name: Some(name.to_string()), path: PathBuf::from(path) }
+        }
+        _ => ExploitedFeed { name: None, path: PathBuf::from(spec) },
+    };
+    if feed.path.as_os_str().is_empty() {
+        return Err(format!("exploited feed '{}' has no path", spec));
+    }
+    Ok(feed)
+}
+
+fn strings(v: Option<&Value>) -> Vec<String> {
+    match v {
+        Some(Value::String(s)) => vec![s.trim().to_string()],
+        Some(Value::Array(a)) => a.iter().filter_map(Value::as_str).map(|s| s.trim().to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn first_str<'a>(rec: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|k| rec.get(k).and_then(Value::as_str)).map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn is_vulncheck(rec: &Value) -> bool {
+    rec.get("vulncheck_xdb").is_some() || rec.get("vulncheck_reported_exploitation").is_some()
+}
+
+// (CVE IDs, listing without its source name)
+fn listing(rec: &Value) -> (Vec<String>, ExploitedListing) {
+    let cves = ["cve", "cveID", "cve_id", "id"]
+        .iter()
+        .flat_map(|k| strings(rec.get(k)))
+        .map(|c| c.to_uppercase())
+        .filter(|c| c.starts_with("CVE-"))
+        .collect();
+    let mut references: Vec<String> = match rec.get("vulncheck_reported_exploitation") {
+        Some(Value::Array(reports)) => {
+            reports.iter().filter_map(|r| first_str(r, &["url"])).map(str::to_string).collect()
+        }
+        _ => ["references", "urls", "url"].iter().flat_map(|k| strings(rec.get(k))).collect(),
+    };
+    references.retain(|r| !r.is_empty());
+    references.dedup();
+    let ransomware_known = first_str(rec, &["knownRansomwareCampaignUse"]).map(|s| s.eq_ignore_ascii_case("known"));
+    let date_added = first_str(rec, &["date_added", "dateAdded"]).map(str::to_string);
+    (cves, ExploitedListing { date_added, ransomware_known, references, ..Default::default() })
+}
+
+fn json_records(bytes: &[u8], path: &Path) -> Result<Vec<Value>> {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(Value::Array(records)) => Ok(records),
+        Ok(Value::Object(mut doc)) => match doc.remove("data").or_else(|| doc.remove("vulnerabilities")) {
+            Some(Value::Array(records)) => Ok(records),
+            _ => Ok(vec![Value::Object(doc)]),
+        },
+        Ok(_) => bail!("{}: expected an array of listings", path.display()),
+        Err(_) => {
+            let mut records = Vec::new();
+            for (n, line) in bytes.lines().enumerate() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    records.push(
+                        serde_json::from_str(&line)
+                            .with_context(|| format!("{}: invalid JSON on line {}", path.display(), n + 1))?,
+                    );
+                }
+            }
+            Ok(records)
+        }
+    }
+}
+
+fn csv_records(bytes: &[u8], path: &Path) -> Result<Vec<Value>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(bytes);
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_lowercase()).collect();
+    if !headers.iter().any(|h| h == "cve" || h == "cve_id" || h == "cveid") {
+        bail!("{}: CSV feed needs a cve column", path.display());
+    }
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.with_context(|| format!("Failed to read exploited feed: {}", path.display()))?;
+        let rec: serde_json::Map<String, Value> = headers
+            .iter()
+            .zip(row.iter())
+            .map(|(h, v)| (if h == "cveid" { "cve".to_string() } else { h.clone() }, Value::String(v.to_string())))
+            .collect();
+        records.push(Value::Object(rec));
+    }
+    Ok(records)
+}
+
+/// Listings by CVE from one feed file, and the source name they go under.
+pub fn load_feed(feed: &ExploitedFeed) -> Result<(String, HashMap<String, ExploitedListing>)> {
+    let path = &feed.path;
+    let bytes = input::read_input(path)?;
+    let json = bytes.iter().find(|b| !b.is_ascii_whitespace()).is_some_and(|b| matches!(b, b'[' | b'{'));
+    let records = if json { json_records(&bytes, path)? } else { csv_records(&bytes, path)? };
+    let name = feed.name.clone().unwrap_or_else(|| {
+        if records.iter().any(is_vulncheck) {
+            "vulncheck-kev".to_string()
+        } else {
+            let stem = path.file_name().and_then(|n| n.to_str()).unwrap_or("exploited");
+            stem.split('.').next().unwrap_or(stem).to_string()
+        }
+    });
+    let mut out: HashMap<String, ExploitedListing> = HashMap::new();
+    for rec in &records {
+        let (cves, found) = listing(rec);
+        for cve in cves {
+            // A CVE listed twice keeps the earliest date and every reference
+            let entry =
+                out.entry(cve).or_insert_with(|| ExploitedListing { source: name.clone(), ..Default::default() });
+            if let Some(d) = &found.date_added
+                && entry.date_added.as_ref().is_none_or(|e| d < e)
+            {
+                entry.date_added = Some(d.clone());
+            }
+            entry.ransomware_known = entry.ransomware_known.or(found.ransomware_known);
+            for r in &found.references {
+                if !entry.references.contains(r) {
+                    entry.references.push(r.clone());
+                }
+            }
+        }
+    }
+    Ok((name, out))
+}
+
+/// Add each feed's listings to item.exploited. Returns the number of items listed by any feed.
+pub fn merge_exploited(feeds: &[ExploitedFeed], items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut loaded = Vec::new();
+    for feed in feeds {
+        let (name, listings) = load_feed(feed)
+            .with_context(|| format!("Failed to load exploited feed: {}", feed.path.display()))?;
+        log_ok!("exploited feed {}: {} CVEs from {}", name, listings.len(), feed.path.display());
+        loaded.push((name, listings));
+    }
+    let mut listed = 0usize;
+    for item in items.iter_mut() {
+        let mut any = false;
+        for (name, listings) in loaded.iter_mut() {
+            let Some(found) = listings.remove(&item.id) else { continue };
+            let exploited = item.exploited.get_or_insert_with(Exploited::default);
+            exploited.other_sources.retain(|l| &l.source != name);
+            exploited.other_sources.push(found);
+            if !item.sources.iter().any(|s| s == name) {
+                item.sources.push(name.clone());
+            }
+            any = true;
+        }
+        if any {
+            listed += 1;
+        }
+    }
+    Ok(listed)
+}
+
+/// Set exploited.cisa_kev from `kev`, creating or dropping `exploited` as needed.
+pub fn sync_kev(items: &mut [CanonicalItem]) {
+    for item in items.iter_mut() {
+        match &mut item.exploited {
+            Some(e) => {
+                e.cisa_kev = item.kev;
+                if !e.cisa_kev && e.other_sources.is_empty() {
+                    item.exploited = None;
+                }
+            }
+            None if item.kev => item.exploited = Some(Exploited { cisa_kev: true, other_sources: Vec::new() }),
+            None => {}
+        }
+    }
+}
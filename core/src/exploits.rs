@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::CanonicalItem;
+
+/* -------------------- Exploit availability enrichment -------------------- */
+/*
+KEV says "exploited in the wild"; this says "public exploit code exists".
+
+Inputs:
+- Exploit-DB index CSV (files_exploits.csv): id, ..., codes ("CVE-2021-44228;OSVDB-..."), ...
+- Metasploit modules_metadata_base.json: { "<module>": { path, references: ["CVE-2021-44228", ...] } }
+
+`enrich` refreshes this on an existing items.json: clear_exploits drops what an
+earlier run took from the given indexes (recognized by link prefix), then
+merge_exploits adds the current ones.
+*/
+
+const EXPLOITDB_LINK: &str = "https://www.exploit-db.com/exploits/";
+const METASPLOIT_LINKS: [&str; 2] = ["https://github.com/rapid7/metasploit-framework/", "metasploit:"];
+
+#[derive(Debug, Deserialize)]
+struct ExploitDbRow {
+    id: String,
+    #[serde(default)]
+    codes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MsfModule {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    fullname: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+type ExploitRefs = HashMap<String, BTreeSet<String>>;
+
+fn parse_exploitdb(path: &Path, out: &mut ExploitRefs) -> Result<()> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Failed to open Exploit-DB CSV: {}", path.display()))?;
+
+    for row in rdr.deserialize::<ExploitDbRow>() {
+        let row = row.with_context(|| format!("Failed to parse Exploit-DB CSV: {}", path.display()))?;
+        let Some(codes) = row.codes else { continue; };
+        for code in codes.split(';').map(str::trim).filter(|c| c.starts_with("CVE-")) {
+            out.entry(code.to_string())
+                .or_default()
+                .insert(format!("{}{}", EXPLOITDB_LINK, row.id.trim()));
+        }
+    }
+    Ok(())
+}
+
+fn parse_metasploit(path: &Path, out: &mut ExploitRefs) -> Result<()> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("Failed to read Metasploit metadata: {}", path.display()))?;
+    let modules: HashMap<String, MsfModule> = serde_json::from_slice(&bytes)
+        .with_context(|| "Failed to parse Metasploit module metadata JSON")?;
+
+    for (key, m) in modules {
+        let link = match &m.path {
+            Some(p) => format!("{}blob/master{}", METASPLOIT_LINKS[0], p),
+            None => format!("{}{}", METASPLOIT_LINKS[1], m.fullname.as_deref().unwrap_or(&key)),
+        };
+        for r in m.references.iter().map(|r| r.trim()).filter(|r| r.starts_with("CVE-")) {
+            out.entry(r.to_string()).or_default().insert(link.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Remove the exploit refs (and `sources` entries) an earlier merge took from these indexes.
+pub fn clear_exploits(exploitdb: bool, metasploit: bool, items: &mut [CanonicalItem]) {
+    let mut prefixes: Vec<&str> = Vec::new();
+    if exploitdb {
+        prefixes.push(EXPLOITDB_LINK);
+    }
+    if metasploit {
+        prefixes.extend(METASPLOIT_LINKS);
+    }
+    for item in items.iter_mut() {
+        item.exploit_refs.retain(|l| !prefixes.iter().any(|p| l.starts_with(p)));
+        item.sources.retain(|s| !(exploitdb && s == "exploitdb" || metasploit && s == "metasploit"));
+        item.exploit_public = !item.exploit_refs.is_empty();
+    }
+}
+
+/// Set `exploit_public` / `exploit_refs` from the Exploit-DB and Metasploit indexes.
+/// Returns the number of items with at least one public exploit.
+pub fn merge_exploits(exploitdb: Option<&Path>, metasploit: Option<&Path>, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut by_source: Vec<(&str, ExploitRefs)> = Vec::new();
+    if let Some(p) = exploitdb {
+        let mut m = ExploitRefs::new();
+        parse_exploitdb(p, &mut m)?;
+        by_source.push(("exploitdb", m));
+    }
+    if let Some(p) = metasploit {
+        let mut m = ExploitRefs::new();
+        parse_metasploit(p, &mut m)?;
+        by_source.push(("metasploit", m));
+    }
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        for (source, map) in by_source.iter_mut() {
+            let Some(links) = map.remove(&item.id) else { continue; };
+            for l in links {
+                if !item.exploit_refs.contains(&l) {
+                    item.exploit_refs.push(l);
+                }
+            }
+            if !item.sources.iter().any(|s| s == source) {
+                item.sources.push(source.to_string());
+            }
+        }
+        if !item.exploit_refs.is_empty() {
+            item.exploit_public = true;
+            merged += 1;
+        }
+    }
+
+    Ok(merged)
+}
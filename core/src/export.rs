@@ -0,0 +1,323 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::CanonicalItem;
+
+/* -------------------- Knowledge-base export -------------------- */
+/*
+md-cards: one Markdown file per item in a flat directory (Obsidian and friends).
+cyclonedx-vdr: CycloneDX 1.5 vulnerabilities BOM (see cyclonedx.rs).
+csv: one row per item for spreadsheets; --columns picks fields (dotted paths
+     allowed), list fields are joined with --list-delimiter.
+hugo / zola: a complete content tree ready to drop into a site's content/ dir:
+
+  cves/_index.md
+  cves/<year>/_index.md
+  cves/<year>/<severity>/_index.md
+  cves/<year>/<severity>/<CVE>.md
+
+Year is taken from the CVE ID so paths never move when dates are revised.
+Pages carry taxonomy terms for vendors, cwes and severity. Hugo uses YAML
+front-matter; Zola uses TOML and needs those taxonomies declared in config.toml.
+
+Front-matter is always generated so it stays parseable. The body is rendered
+from a template, replaceable with --template; placeholders are {{name}}:
+
+  id, title, title_suffix (": <title>" or empty), description, published,
+  last_modified, cvss, severity, kev,
+  vendor, product, sources, cwes, refs (Markdown bullet list)
+
+Missing values render as empty strings.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    MdCards,
+    Hugo,
+    Zola,
+    Csv,
+    /// CycloneDX 1.5 VDR (see cyclonedx.rs)
+    CyclonedxVdr,
+    /// Bulk-index into Elasticsearch / OpenSearch (see elastic.rs)
+    Elasticsearch,
+}
+
+pub const DEFAULT_CSV_COLUMNS: [&str; 6] = ["id", "cvss", "severity_bucket", "kev", "vendor", "product"];
+
+pub struct CsvOptions {
+    pub columns: Vec<String>,
+    pub list_delimiter: String,
+}
+
+const DEFAULT_CARD_TEMPLATE: &str = "\
+# {{id}}{{title_suffix}}
+
+**Severity:** {{severity}} | **CVSS:** {{cvss}} | **KEV:** {{kev}}
+
+{{description}}
+
+## References
+
+{{refs}}
+";
+
+pub const TAXONOMIES: [&str; 3] = ["vendors", "cwes", "severity"];
+
+/// JSON string literals are valid YAML double-quoted scalars and TOML basic strings.
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn quote_list<'a>(values: impl IntoIterator<Item = &'a String>) -> String {
+    let quoted: Vec<String> = values.into_iter().map(|s| quote(s)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+fn vendor_terms(item: &CanonicalItem) -> Vec<String> {
+    item.vendor.iter().map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect()
+}
+
+fn front_matter_yaml(item: &CanonicalItem, taxonomies: bool) -> String {
+    let mut fm = String::from("---\n");
+    fm.push_str(&format!("id: {}\n", quote(&item.id)));
+    fm.push_str(&format!("title: {}\n", quote(item.title.as_deref().unwrap_or(&item.id))));
+    if let Some(p) = &item.published {
+        fm.push_str(&format!("published: {}\n", quote(p)));
+    }
+    if let Some(m) = &item.last_modified {
+        fm.push_str(&format!("last_modified: {}\n", quote(m)));
+    }
+    if let Some(c) = item.cvss {
+        fm.push_str(&format!("cvss: {}\n", c));
+    }
+    if !taxonomies {
+        fm.push_str(&format!("severity: {}\n", quote(&item.severity_bucket)));
+    }
+    fm.push_str(&format!("kev: {}\n", item.kev));
+    fm.push_str(&format!("exploit_public: {}\n", item.exploit_public));
+    if let Some(v) = &item.vendor {
+        fm.push_str(&format!("vendor: {}\n", quote(v)));
+    }
+    if let Some(p) = &item.product {
+        fm.push_str(&format!("product: {}\n", quote(p)));
+    }
+    fm.push_str(&format!("sources: {}\n", quote_list(&item.sources)));
+    if taxonomies {
+        fm.push_str(&format!("vendors: {}\n", quote_list(&vendor_terms(item))));
+        fm.push_str(&format!("cwes: {}\n", quote_list(&item.cwes)));
+        fm.push_str(&format!("severity: {}\n", quote_list([&item.severity_bucket])));
+    } else if !item.cwes.is_empty() {
+        fm.push_str(&format!("cwes: {}\n", quote_list(&item.cwes)));
+    }
+    fm.push_str("---\n\n");
+    fm
+}
+
+/// Bare TOML date literal from an ISO8601 timestamp; None if it doesn't start with a real date.
+fn toml_date(ts: Option<&str>) -> Option<&str> {
+    let day = ts?.get(..10)?;
+    chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?;
+    Some(day)
+}
+
+/// Zola: known keys at top level, everything else under [extra], terms under [taxonomies].
+fn front_matter_toml(item: &CanonicalItem) -> String {
+    let mut fm = String::from("+++\n");
+    fm.push_str(&format!("title = {}\n", quote(item.title.as_deref().unwrap_or(&item.id))));
+    if let Some(p) = toml_date(item.published.as_deref()) {
+        fm.push_str(&format!("date = {}\n", p));
+    }
+    if let Some(m) = toml_date(item.last_modified.as_deref()) {
+        fm.push_str(&format!("updated = {}\n", m));
+    }
+    fm.push_str("\n[taxonomies]\n");
+    fm.push_str(&format!("vendors = {}\n", quote_list(&vendor_terms(item))));
+    fm.push_str(&format!("cwes = {}\n", quote_list(&item.cwes)));
+    fm.push_str(&format!("severity = {}\n", quote_list([&item.severity_bucket])));
+    fm.push_str("\n[extra]\n");
+    fm.push_str(&format!("id = {}\n", quote(&item.id)));
+    if let Some(c) = item.cvss {
+        fm.push_str(&format!("cvss = {:?}\n", c));
+    }
+    fm.push_str(&format!("kev = {}\n", item.kev));
+    fm.push_str(&format!("exploit_public = {}\n", item.exploit_public));
+    if let Some(p) = &item.product {
+        fm.push_str(&format!("product = {}\n", quote(p)));
+    }
+    fm.push_str(&format!("sources = {}\n", quote_list(&item.sources)));
+    fm.push_str("+++\n\n");
+    fm
+}
+
+fn render_body(item: &CanonicalItem, template: &str) -> String {
+    let refs = item
+        .refs
+        .iter()
+        .map(|r| format!("- <{}> ({})", r.url, r.kind.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let title_suffix = item.title.as_ref().map(|t| format!(": {}", t)).unwrap_or_default();
+
+    let vars: [(&str, String); 14] = [
+        ("id", item.id.clone()),
+        ("title", item.title.clone().unwrap_or_default()),
+        ("title_suffix", title_suffix),
+        ("description", item.short_desc.clone()),
+        ("published", item.published.clone().unwrap_or_default()),
+        ("last_modified", item.last_modified.clone().unwrap_or_default()),
+        ("cvss", item.cvss.map(|c| c.to_string()).unwrap_or_else(|| "n/a".to_string())),
+        ("severity", item.severity_bucket.clone()),
+        ("kev", if item.kev { "yes" } else { "no" }.to_string()),
+        ("vendor", item.vendor.clone().unwrap_or_default()),
+        ("product", item.product.clone().unwrap_or_default()),
+        ("sources", item.sources.join(", ")),
+        ("cwes", item.cwes.join(", ")),
+        ("refs", refs),
+    ];
+
+    let mut body = template.to_string();
+    for (name, value) in &vars {
+        body = body.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    body
+}
+
+pub fn render_card(item: &CanonicalItem, template: &str) -> String {
+    format!("{}{}", front_matter_yaml(item, false), render_body(item, template))
+}
+
+/// "CVE-2024-1234" is already filesystem-safe; anything else gets sanitized.
+pub fn card_filename(id: &str) -> String {
+    let safe: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}.md", safe)
+}
+
+fn load_template(template: Option<&Path>) -> Result<String> {
+    match template {
+        Some(p) => fs::read_to_string(p).with_context(|| format!("Failed to read template: {}", p.display())),
+        None => Ok(DEFAULT_CARD_TEMPLATE.to_string()),
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(path, contents).with_context(|| format!("Failed to write: {}", path.display()))
+}
+
+pub fn write_md_cards(items: &[CanonicalItem], outdir: &Path, template: Option<&Path>) -> Result<usize> {
+    let template = load_template(template)?;
+
+    fs::create_dir_all(outdir).with_context(|| format!("Failed to create outdir: {}", outdir.display()))?;
+    for item in items {
+        write_file(&outdir.join(card_filename(&item.id)), &render_card(item, &template))?;
+    }
+    Ok(items.len())
+}
+
+/// "CVE-2024-1234" -> "2024"; IDs without a year land in "other".
+fn id_year(id: &str) -> String {
+    id.split('-')
+        .nth(1)
+        .filter(|y| y.len() == 4 && y.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or("other")
+        .to_string()
+}
+
+fn section_index(format: ExportFormat, title: &str, weight: usize) -> String {
+    match format {
+        ExportFormat::Zola => format!(
+            "+++\ntitle = {}\nsort_by = \"title\"\nweight = {}\n+++\n",
+            quote(title),
+            weight
+        ),
+        _ => format!("---\ntitle: {}\nweight: {}\n---\n", quote(title), weight),
+    }
+}
+
+/// Write a Hugo or Zola content tree under `outdir`. Returns pages written.
+pub fn write_site(items: &[CanonicalItem], outdir: &Path, format: ExportFormat, template: Option<&Path>) -> Result<usize> {
+    let template = load_template(template)?;
+    let root = outdir.join("cves");
+
+    // year -> severities present (sorted, so section files are stable)
+    let mut sections: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for item in items {
+        let year = id_year(&item.id);
+        let severity = item.severity_bucket.clone();
+        let dir: PathBuf = root.join(&year).join(&severity);
+
+        let front = match format {
+            ExportFormat::Zola => front_matter_toml(item),
+            _ => front_matter_yaml(item, true),
+        };
+        write_file(&dir.join(card_filename(&item.id)), &format!("{}{}", front, render_body(item, &template)))?;
+        sections.entry(year).or_default().insert(severity);
+    }
+
+    write_file(&root.join("_index.md"), &section_index(format, "CVEs", 0))?;
+    // Newest year first
+    for (weight, (year, severities)) in sections.iter().rev().enumerate() {
+        write_file(&root.join(year).join("_index.md"), &section_index(format, year, weight))?;
+        for severity in severities {
+            let weight = severity_weight(severity);
+            write_file(
+                &root.join(year).join(severity).join("_index.md"),
+                &section_index(format, &format!("{} {}", year, severity), weight),
+            )?;
+        }
+    }
+
+    Ok(items.len())
+}
+
+/// Most severe first; names outside the policy sort last.
+fn severity_weight(bucket: &str) -> usize {
+    let names = crate::severity::names();
+    names.iter().position(|n| *n == bucket).unwrap_or(names.len())
+}
+
+fn csv_cell(value: Option<&serde_json::Value>, list_delimiter: &str) -> String {
+    use serde_json::Value;
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(list_delimiter),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Write one CSV row per item with the given columns. `out` is a file path.
+pub fn write_csv(items: &[CanonicalItem], out: &Path, columns: &[String], list_delimiter: &str) -> Result<usize> {
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    let mut w = csv::Writer::from_path(out).with_context(|| format!("Failed to create CSV: {}", out.display()))?;
+    w.write_record(columns)?;
+    for item in items {
+        let value = serde_json::to_value(item)?;
+        let row: Vec<String> = columns
+            .iter()
+            .map(|c| csv_cell(crate::query::lookup(&value, c), list_delimiter))
+            .collect();
+        w.write_record(&row)?;
+    }
+    w.flush().with_context(|| format!("Failed to write CSV: {}", out.display()))?;
+    Ok(items.len())
+}
@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use crate::{codex, diff, digest, input, kev, nvd, overrides, query, refs, CanonicalItem, Normalizer};
+
+/* -------------------- C ABI (Python bindings) -------------------- */
+/*
+The library is also built as a cdylib (libbastion_codex_core.so / .dylib /
+bastion_codex_core.dll) so other languages get the same normalization, not a
+reimplementation. core/python/bastion_codex wraps it with ctypes:
+
+  import bastion_codex as bc
+  items = bc.normalize("data/raw/kev.json", "data/raw/nvd-*.json")   # list[dict]
+  hot = bc.query(items, 'kev and cvss >= 9', sort="-cvss")
+  changes = bc.diff(bc.read_items("last_week.json"), items)
+
+Every function takes UTF-8 C strings (NULL for an absent optional) and
+returns a new JSON string, {"ok": result} or {"error": message}, that the
+caller hands back to bastion_free. Items cross the boundary as JSON, the same
+shape items.json has (schemas/canonical_items.schema.json). Panics are caught
+at the boundary and come back as errors.
+
+normalize() is the default pipeline: what `core normalize --kev K --nvd N`
+writes with no other options (merge, refs ordering, KEV fields, ID order,
+content hashes). Enrichment and filtering options stay CLI-only for now.
+
+Without the io feature the same exports build for wasm32-unknown-unknown,
+where there are no files: a browser viewer hands over feed contents
+(bastion_normalize_feeds) or an items file it read (bastion_parse_items) and
+gets the JSON envelope back. Strings go in through the module's own memory:
+
+  ptr = bastion_alloc(n + 1)       copy n UTF-8 bytes and a NUL there
+  out = bastion_parse_items(ptr)   read up to the NUL, then bastion_free(out)
+  bastion_dealloc(ptr, n + 1)
+*/
+
+/// `core normalize --kev KEV --nvd NVD...` without other options, returned rather than written.
+pub fn normalize(kev_path: Option<&Path>, nvd: &[PathBuf]) -> Result<Vec<CanonicalItem>> {
+    let nvd_paths = input::expand_globs(nvd)?;
+    let kev = kev_path.map(kev::KevSource::load).transpose()?;
+    let nvd = (!nvd_paths.is_empty()).then(|| nvd::NvdSource::new(nvd_paths));
+    pipeline(kev, nvd)
+}
+
+/// normalize over feed contents instead of files (gzip/zstd are decoded as in files).
+pub fn normalize_feeds(kev: Option<&[u8]>, nvd: Vec<(String, Vec<u8>)>) -> Result<Vec<CanonicalItem>> {
+    let kev = kev.map(|bytes| kev::KevSource::from_bytes(bytes, "kev")).transpose()?;
+    let nvd = (!nvd.is_empty()).then(|| nvd::NvdSource::from_bytes(nvd));
+    pipeline(kev, nvd)
+}
+
+fn pipeline(kev: Option<kev::KevSource>, nvd: Option<nvd::NvdSource>) -> Result<Vec<CanonicalItem>> {
+    let mut normalizer = Normalizer::new();
+    if let Some(nvd) = &nvd {
+        normalizer = normalizer.source(nvd);
+    }
+    if let Some(kev) = &kev {
+        normalizer = normalizer.source(kev);
+    }
+    let mut items = normalizer.normalize()?;
+    refs::order_refs(&mut items);
+    refs::mine_fix_refs(&mut items);
+    if let Some(kev) = &kev {
+        for item in items.iter_mut() {
+            if let Some(listing) = kev.listing(&item.id) {
+                listing.apply(item);
+            }
+        }
+    }
+    overrides::revert_all(&mut items);
+    codex::sort_by_id(&mut items);
+    digest::stamp_content_hashes(&mut items)?;
+    Ok(items)
+}
+
+/// Items matching `expr` (query.rs syntax; None: all), optionally sorted as `query --sort`.
+pub fn query_values(items: Vec<Value>, expr: Option<&str>, sort: Option<&str>) -> Result<Vec<Value>> {
+    let expr = expr.filter(|e| !e.trim().is_empty()).map(query::parse).transpose()?;
+    let mut matched: Vec<Value> =
+        items.into_iter().filter(|v| expr.as_ref().is_none_or(|e| query::eval(e, v))).collect();
+    if let Some(spec) = sort {
+        query::sort_values(&mut matched, spec);
+    }
+    Ok(matched)
+}
+
+fn arg<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: the caller passes NUL-terminated strings that outlive the call
+    let s = unsafe { CStr::from_ptr(ptr) };
+    s.to_str().map(Some).with_context(|| format!("{} is not UTF-8", name))
+}
+
+fn required<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    arg(ptr, name)?.with_context(|| format!("{} is required", name))
+}
+
+fn items_arg(ptr: *const c_char, name: &str) -> Result<Vec<CanonicalItem>> {
+    serde_json::from_str(required(ptr, name)?).with_context(|| format!("{} is not a list of canonical items", name))
+}
+
+/// Run `f` and wrap its result (or error, or panic) as the JSON envelope.
+fn call<T: Serialize>(f: impl FnOnce() -> Result<T>) -> *mut c_char {
+    let envelope = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => serde_json::to_string(&json!({ "ok": value })),
+        Ok(Err(e)) => serde_json::to_string(&json!({ "error": format!("{:#}", e) })),
+        Err(_) => serde_json::to_string(&json!({ "error": "internal error (panic); see stderr" })),
+    };
+    let text = envelope.unwrap_or_else(|e| json!({ "error": e.to_string() }).to_string());
+    // JSON escapes control characters, so there is no interior NUL
+    CString::new(text).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// normalize(kev_path, nvd_path): nvd_path may be a glob; either may be NULL.
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_normalize(kev_path: *const c_char, nvd_path: *const c_char) -> *mut c_char {
+    call(|| {
+        let kev = arg(kev_path, "kev_path")?.map(Path::new);
+        let nvd: Vec<PathBuf> = arg(nvd_path, "nvd_path")?.map(PathBuf::from).into_iter().collect();
+        normalize(kev, &nvd)
+    })
+}
+
+/// normalize_feeds(kev_text, nvd_texts, nvd_count): feed contents; kev_text may be NULL.
+///
+/// # Safety
+/// `nvd_texts` must point at `nvd_count` string pointers (it may be NULL when that is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bastion_normalize_feeds(
+    kev_text: *const c_char,
+    nvd_texts: *const *const c_char,
+    nvd_count: usize,
+) -> *mut c_char {
+    call(|| {
+        let kev = arg(kev_text, "kev_text")?.map(str::as_bytes);
+        let mut nvd = Vec::with_capacity(nvd_count);
+        for i in 0..nvd_count {
+            // SAFETY: per the contract above
+            let ptr = unsafe { *nvd_texts.add(i) };
+            let name = format!("nvd[{}]", i);
+            let text = required(ptr, &name)?;
+            nvd.push((name, text.as_bytes().to_vec()));
+        }
+        normalize_feeds(kev, nvd)
+    })
+}
+
+/// read_items(path): an items file in any format normalize writes.
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_read_items(path: *const c_char) -> *mut c_char {
+    call(|| codex::read_items(Path::new(required(path, "path")?)))
+}
+
+/// parse_items(text): read_items over the contents of an items file (JSON or NDJSON).
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_parse_items(text: *const c_char) -> *mut c_char {
+    call(|| codex::parse_items(required(text, "text")?.as_bytes(), Path::new("items")))
+}
+
+/// query(items_json, expr, sort): expr and sort may be NULL.
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_query(items: *const c_char, expr: *const c_char, sort: *const c_char) -> *mut c_char {
+    call(|| {
+        let items: Vec<Value> = serde_json::from_str(required(items, "items")?).context("items is not a JSON list")?;
+        query_values(items, arg(expr, "expr")?, arg(sort, "sort")?)
+    })
+}
+
+/// diff(old_json, new_json): the `diff --format json` report.
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_diff(old: *const c_char, new: *const c_char) -> *mut c_char {
+    call(|| Ok(diff::diff_items(&items_arg(old, "old")?, &items_arg(new, "new")?)))
+}
+
+/// This library's version, as a plain static string (not to be freed).
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Release a string returned by any of the functions above.
+///
+/// # Safety
+/// `ptr` must come from one of them and not have been freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bastion_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        // SAFETY: per the contract above, ptr came from CString::into_raw
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+/// `len` bytes of this library's memory, for hosts (wasm) that cannot pass their own.
+#[unsafe(no_mangle)]
+pub extern "C" fn bastion_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Release memory from bastion_alloc.
+///
+/// # Safety
+/// `ptr` must come from bastion_alloc(`len`) and not have been released already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bastion_dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        // SAFETY: per the contract above, ptr is a Vec buffer of capacity len
+        drop(unsafe { Vec::from_raw_parts(ptr, 0, len) });
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::Args;
+
+use crate::{parse_iso_datetime, severity, CanonicalItem};
+
+/* -------------------- Output filters -------------------- */
+/*
+normalize can write a subset instead of the whole dataset:
+
+  normalize ... --published-after 90d --severity critical,high --out recent.json
+
+Every filter given must match. Dates are YYYY-MM-DD (start of that day, UTC),
+an ISO timestamp, or Nd for N days before now; an item whose date is unknown
+doesn't match a date filter, nor an unscored one --min-cvss. The filters run on
+the final items, after enrichment, --merge-into and overrides, so they see the
+values that get written (an overridden CVSS, a KEV listing kept from a prior
+run). A --baseline delta then reports items that fell out of the filter as
+removed.
+*/
+
+/// Flattened into normalize; empty unless a flag is given.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ItemFilter {
+    /// Keep items published on or after DATE (YYYY-MM-DD, ISO timestamp, or Nd for the last N days)
+    #[arg(long, value_name = "DATE")]
+    pub published_after: Option<String>,
+    /// Keep items last modified on or after DATE (same forms as --published-after)
+    #[arg(long, value_name = "DATE")]
+    pub modified_after: Option<String>,
+    /// Keep items with a CVSS base score of at least N
+    #[arg(long, value_name = "N")]
+    pub min_cvss: Option<f64>,
+    /// Keep KEV-listed items only
+    #[arg(long)]
+    pub kev_only: bool,
+    /// Keep items in these severity buckets (e.g. critical,high)
+    #[arg(long, value_delimiter = ',', value_name = "BUCKET,...")]
+    pub severity: Vec<String>,
+}
+
+/// DATE as described in the module comment, relative to `now`.
+pub fn parse_cutoff(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+    if let Some(days) = s.strip_suffix('d')
+        && let Ok(days) = days.parse::<i64>()
+    {
+        return Duration::try_days(days).map(|d| now - d).with_context(|| format!("{} is too far back", s));
+    }
+    if let Ok(day) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(day.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    parse_iso_datetime(s).with_context(|| format!("Invalid date '{}': use YYYY-MM-DD, an ISO timestamp or e.g. 90d", s))
+}
+
+fn on_or_after(date: Option<&str>, cutoff: Option<DateTime<Utc>>) -> bool {
+    cutoff.is_none_or(|c| date.and_then(parse_iso_datetime).is_some_and(|d| d >= c))
+}
+
+impl ItemFilter {
+    pub fn is_empty(&self) -> bool {
+        self.published_after.is_none()
+            && self.modified_after.is_none()
+            && self.min_cvss.is_none()
+            && !self.kev_only
+            && self.severity.is_empty()
+    }
+
+    /// The test items must pass; dates and bucket names are checked here, before any work.
+    pub fn matcher(&self) -> Result<impl Fn(&CanonicalItem) -> bool + '_> {
+        let now = Utc::now();
+        let published = self.published_after.as_deref().map(|s| parse_cutoff(s, now)).transpose()?;
+        let modified = self.modified_after.as_deref().map(|s| parse_cutoff(s, now)).transpose()?;
+        let known = severity::names();
+        if let Some(bad) = self.severity.iter().find(|b| !known.contains(&b.as_str())) {
+            bail!("unknown severity bucket '{}' (buckets: {})", bad, known.join(", "));
+        }
+        Ok(move |item: &CanonicalItem| {
+            on_or_after(item.published.as_deref(), published)
+                && on_or_after(item.last_modified.as_deref(), modified)
+                && self.min_cvss.is_none_or(|min| item.cvss.is_some_and(|c| c >= min))
+                && (!self.kev_only || item.kev)
+                && (self.severity.is_empty() || self.severity.contains(&item.severity_bucket))
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+/* -------------------- Synthetic source fixtures -------------------- */
+/*
+Generates structurally valid KEV / NVD 2.0 / OSV inputs so pipelines can be tested
+without shipping real feed dumps. Output is deterministic for a given seed.
+
+Edge cases injected at `edge_rate`:
+- NVD: missing metrics, non-English-only descriptions, blank descriptions,
+       huge reference lists, duplicate refs, naive vs offset timestamps
+- KEV: missing optional fields, KEV-only IDs absent from NVD
+- OSV: missing severity, no fixed event, empty references
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FixtureFormat {
+    Kev,
+    Nvd,
+    Osv,
+}
+
+pub struct FixtureSpec {
+    pub count: usize,
+    pub edge_rate: f64,
+    pub seed: u64,
+    pub formats: Vec<FixtureFormat>,
+}
+
+/// xorshift64*: tiny, dependency-free, and stable across platforms.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+    fn chance(&mut self, p: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 <= p
+    }
+    fn pick<'a>(&mut self, xs: &'a [&'a str]) -> &'a str {
+        xs[self.below(xs.len() as u64) as usize]
+    }
+}
+
+const VENDORS: [&str; 6] = ["Microsoft", "Fortinet", "Apache", "Cisco", "Ivanti", "Acme"];
+const PRODUCTS: [&str; 6] = ["Windows", "FortiOS", "Struts", "IOS XE", "Connect Secure", "Widget"];
+const WEIRD_LANGS: [&str; 4] = ["es", "zh", "ja", "de"];
+
+fn cve_id(i: usize) -> String {
+    format!("CVE-2099-{:05}", 10000 + i)
+}
+
+fn timestamp(rng: &mut Rng, edge: bool) -> String {
+    let day = 1 + rng.below(28);
+    let hour = rng.below(24);
+    if edge {
+        // RFC3339 with offset instead of NVD's naive form
+        format!("2099-01-{:02}T{:02}:00:00+02:00", day, hour)
+    } else {
+        format!("2099-01-{:02}T{:02}:15:00.000", day, hour)
+    }
+}
+
+fn nvd_record(rng: &mut Rng, i: usize, edge_rate: f64) -> Value {
+    let id = cve_id(i);
+    let score = (rng.below(100) as f64) / 10.0;
+
+    let descriptions = if rng.chance(edge_rate) {
+        // non-English only
+        json!([{ "lang": rng.pick(&WEIRD_LANGS), "value": format!("Vulnerabilidad sintética {}", id) }])
+    } else if rng.chance(edge_rate) {
+        json!([{ "lang": "en", "value": "   " }])
+    } else {
+        json!([{ "lang": "en", "value": format!("Synthetic vulnerability {} in {}.", id, rng.pick(&PRODUCTS)) }])
+    };
+
+    let metrics = if rng.chance(edge_rate) {
+        json!({})
+    } else {
+        json!({ "cvssMetricV31": [{
+            "source": "nvd@nist.gov",
+            "type": "Primary",
+            "cvssData": { "version": "3.1", "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", "baseScore": score }
+        }]})
+    };
+
+    let n_refs = if rng.chance(edge_rate) { 2000 } else { 1 + rng.below(4) as usize };
+    let mut refs: Vec<Value> = (0..n_refs)
+        .map(|r| json!({ "url": format!("https://example.invalid/{}/ref/{}", id, r) }))
+        .collect();
+    if rng.chance(edge_rate) {
+        refs.push(refs[0].clone()); // duplicate
+    }
+
+    let offset_ts = rng.chance(edge_rate);
+    let published = timestamp(rng, offset_ts);
+    json!({ "cve": {
+        "id": id,
+        "sourceIdentifier": "fixtures@bastion.invalid",
+        "published": published,
+        "lastModified": published,
+        "vulnStatus": "Analyzed",
+        "descriptions": descriptions,
+        "metrics": metrics,
+        "references": refs,
+    }})
+}
+
+fn kev_record(rng: &mut Rng, id: String, edge_rate: f64) -> Value {
+    let vendor = rng.pick(&VENDORS);
+    let product = rng.pick(&PRODUCTS);
+    if rng.chance(edge_rate) {
+        return json!({ "cveID": id });
+    }
+    json!({
+        "cveID": id,
+        "vendorProject": vendor,
+        "product": product,
+        "vulnerabilityName": format!("{} {} synthetic flaw", vendor, product),
+        "dateAdded": format!("2099-01-{:02}", 1 + rng.below(28)),
+        "shortDescription": format!("Synthetic KEV entry for {} {}.", vendor, product),
+        "requiredAction": "Apply mitigations per vendor instructions or discontinue use.",
+        "dueDate": format!("2099-02-{:02}", 1 + rng.below(28)),
+        "knownRansomwareCampaignUse": if rng.chance(0.2) { "Known" } else { "Unknown" },
+        "notes": "",
+    })
+}
+
+fn osv_record(rng: &mut Rng, i: usize, edge_rate: f64) -> Value {
+    let id = format!("OSV-2099-{:05}", i);
+    let mut events = vec![json!({ "introduced": "0" })];
+    if !rng.chance(edge_rate) {
+        events.push(json!({ "fixed": format!("1.{}.{}", rng.below(10), rng.below(10)) }));
+    }
+    let mut rec = json!({
+        "schema_version": "1.6.0",
+        "id": id,
+        "modified": timestamp(rng, false) + "Z",
+        "aliases": [cve_id(i)],
+        "summary": format!("Synthetic advisory {}", id),
+        "affected": [{
+            "package": { "ecosystem": "PyPI", "name": format!("synthetic-pkg-{}", i % 17) },
+            "ranges": [{ "type": "ECOSYSTEM", "events": events }],
+        }],
+        "references": if rng.chance(edge_rate) { json!([]) } else { json!([{ "type": "ADVISORY", "url": format!("https://example.invalid/osv/{}", id) }]) },
+    });
+    if !rng.chance(edge_rate) {
+        rec["severity"] = json!([{ "type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H" }]);
+    }
+    rec
+}
+
+/// Write the requested fixture files into `outdir`.
+pub fn write_fixtures(outdir: &Path, spec: &FixtureSpec) -> Result<Vec<String>> {
+    fs::create_dir_all(outdir)
+        .with_context(|| format!("Failed to create fixtures dir: {}", outdir.display()))?;
+
+    let mut rng = Rng::new(spec.seed);
+    let mut written = Vec::new();
+
+    if spec.formats.contains(&FixtureFormat::Nvd) {
+        let vulns: Vec<Value> = (0..spec.count).map(|i| nvd_record(&mut rng, i, spec.edge_rate)).collect();
+        let root = json!({
+            "resultsPerPage": vulns.len(),
+            "startIndex": 0,
+            "totalResults": vulns.len(),
+            "format": "NVD_CVE",
+            "version": "2.0",
+            "timestamp": "2099-02-01T00:00:00.000",
+            "vulnerabilities": vulns,
+        });
+        let path = outdir.join("nvd.json");
+        fs::write(&path, serde_json::to_string_pretty(&root)?)?;
+        written.push(path.display().to_string());
+    }
+
+    if spec.formats.contains(&FixtureFormat::Kev) {
+        // Roughly a tenth of NVD IDs are KEV-listed, plus KEV-only edge cases.
+        let mut vulns: Vec<Value> = Vec::new();
+        for i in (0..spec.count).step_by(10) {
+            vulns.push(kev_record(&mut rng, cve_id(i), spec.edge_rate));
+        }
+        let kev_only = ((spec.count as f64 / 10.0) * spec.edge_rate).ceil() as usize;
+        for i in 0..kev_only {
+            vulns.push(kev_record(&mut rng, cve_id(spec.count + i), spec.edge_rate));
+        }
+        let root = json!({
+            "title": "Synthetic KEV Catalog",
+            "catalogVersion": "2099.01.01",
+            "dateReleased": "2099-01-01T00:00:00.000Z",
+            "count": vulns.len(),
+            "vulnerabilities": vulns,
+        });
+        let path = outdir.join("kev.json");
+        fs::write(&path, serde_json::to_string_pretty(&root)?)?;
+        written.push(path.display().to_string());
+    }
+
+    if spec.formats.contains(&FixtureFormat::Osv) {
+        let dir = outdir.join("osv");
+        fs::create_dir_all(&dir)?;
+        for i in 0..spec.count {
+            let rec = osv_record(&mut rng, i, spec.edge_rate);
+            let name = format!("{}.json", rec["id"].as_str().unwrap_or("OSV"));
+            fs::write(dir.join(name), serde_json::to_string_pretty(&rec)?)?;
+        }
+        written.push(dir.display().to_string());
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,400 @@
+use anyhow::Result;
+use std::path::Path;
+
+#[cfg(target_os = "linux")]
+use anyhow::{bail, Context};
+#[cfg(target_os = "linux")]
+use std::collections::BTreeMap;
+
+use crate::{log_ok, CanonicalItem};
+
+/* -------------------- Read-only FUSE view -------------------- */
+/*
+`mount --input items.json DIR` exposes a snapshot as plain files for shell users and
+legacy tools:
+
+  DIR/by-id/CVE-2024-3400/item.json          pretty JSON, one item
+  DIR/by-severity/critical/CVE-2024-3400  -> ../../by-id/CVE-2024-3400
+  DIR/kev/CVE-2024-3400                   -> ../by-id/CVE-2024-3400
+
+The tree is built once from the snapshot; item.json bodies are rendered when
+read. It speaks the kernel FUSE protocol on /dev/fuse directly (no libfuse), and
+mounts with mount(2), so it needs root or CAP_SYS_ADMIN; there is no fusermount
+fallback. Runs in the foreground until `umount DIR` or Ctrl-C. Linux only.
+*/
+
+#[cfg(target_os = "linux")]
+enum Kind {
+    Dir(BTreeMap<String, u64>),
+    File(usize), // index into items
+    Link(String),
+}
+
+#[cfg(target_os = "linux")]
+struct Node {
+    parent: u64,
+    kind: Kind,
+}
+
+#[cfg(target_os = "linux")]
+struct Tree {
+    nodes: Vec<Node>, // inode n lives at nodes[n - 1]; 1 is the root
+}
+
+#[cfg(target_os = "linux")]
+impl Tree {
+    fn node(&self, ino: u64) -> Option<&Node> {
+        self.nodes.get(ino.checked_sub(1)? as usize)
+    }
+
+    fn add(&mut self, parent: u64, name: &str, kind: Kind) -> u64 {
+        self.nodes.push(Node { parent, kind });
+        let ino = self.nodes.len() as u64;
+        if let Kind::Dir(children) = &mut self.nodes[parent as usize - 1].kind {
+            children.insert(name.to_string(), ino);
+        }
+        ino
+    }
+
+    fn dir(&mut self, parent: u64, name: &str) -> u64 {
+        if let Some(Node { kind: Kind::Dir(children), .. }) = self.node(parent)
+            && let Some(&ino) = children.get(name)
+        {
+            return ino;
+        }
+        self.add(parent, name, Kind::Dir(BTreeMap::new()))
+    }
+
+    fn build(items: &[CanonicalItem]) -> Tree {
+        let mut t = Tree { nodes: vec![Node { parent: 1, kind: Kind::Dir(BTreeMap::new()) }] };
+        let by_id = t.dir(1, "by-id");
+        let by_sev = t.dir(1, "by-severity");
+        let kev = t.dir(1, "kev");
+
+        for (n, item) in items.iter().enumerate() {
+            // IDs become path components; anything path-like is flattened
+            let name = item.id.replace(['/', '\0'], "_");
+            if name.is_empty() || name == "." || name == ".." {
+                continue;
+            }
+            let dir = t.dir(by_id, &name);
+            t.add(dir, "item.json", Kind::File(n));
+
+            let bucket = item.severity_bucket.replace(['/', '\0'], "_");
+            let bucket = if bucket.is_empty() { "unknown".to_string() } else { bucket };
+            let sev = t.dir(by_sev, &bucket);
+            t.add(sev, &name, Kind::Link(format!("../../by-id/{}", name)));
+            if item.kev {
+                t.add(kev, &name, Kind::Link(format!("../by-id/{}", name)));
+            }
+        }
+        t
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn render(item: &CanonicalItem) -> Vec<u8> {
+    let mut body = serde_json::to_vec_pretty(item).unwrap_or_default();
+    body.push(b'\n');
+    body
+}
+
+/* ---- kernel ABI (include/uapi/linux/fuse.h), little-endian, 64-bit aligned ---- */
+
+#[cfg(target_os = "linux")]
+mod abi {
+    pub const LOOKUP: u32 = 1;
+    pub const FORGET: u32 = 2;
+    pub const GETATTR: u32 = 3;
+    pub const READLINK: u32 = 5;
+    pub const OPEN: u32 = 14;
+    pub const READ: u32 = 15;
+    pub const STATFS: u32 = 17;
+    pub const RELEASE: u32 = 18;
+    pub const FLUSH: u32 = 25;
+    pub const INIT: u32 = 26;
+    pub const OPENDIR: u32 = 27;
+    pub const READDIR: u32 = 28;
+    pub const RELEASEDIR: u32 = 29;
+    pub const ACCESS: u32 = 34;
+    pub const INTERRUPT: u32 = 36;
+    pub const DESTROY: u32 = 38;
+    pub const BATCH_FORGET: u32 = 42;
+
+    pub const IN_HEADER: usize = 40;
+    pub const MAX_WRITE: u32 = 128 * 1024;
+    pub const FOPEN_KEEP_CACHE: u32 = 1 << 1;
+    pub const TTL_SECS: u64 = 3600; // the snapshot never changes under the mount
+
+    pub fn u32_at(b: &[u8], off: usize) -> u32 {
+        b.get(off..off + 4).map_or(0, |s| u32::from_le_bytes(s.try_into().unwrap_or_default()))
+    }
+
+    pub fn u64_at(b: &[u8], off: usize) -> u64 {
+        b.get(off..off + 8).map_or(0, |s| u64::from_le_bytes(s.try_into().unwrap_or_default()))
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Fs<'a> {
+    items: &'a [CanonicalItem],
+    tree: Tree,
+    uid: u32,
+    gid: u32,
+    mtime: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl Fs<'_> {
+    fn attr(&self, ino: u64) -> Option<Vec<u8>> {
+        let node = self.tree.node(ino)?;
+        let (mode, nlink, size) = match &node.kind {
+            Kind::Dir(children) => {
+                let subdirs = children.values().filter(|c| matches!(self.tree.node(**c).map(|n| &n.kind), Some(Kind::Dir(_))));
+                (libc::S_IFDIR | 0o555, 2 + subdirs.count() as u32, 0)
+            }
+            Kind::File(i) => (libc::S_IFREG | 0o444, 1, render(&self.items[*i]).len() as u64),
+            Kind::Link(target) => (libc::S_IFLNK | 0o777, 1, target.len() as u64),
+        };
+        let mut a = Vec::with_capacity(88);
+        for v in [ino, size, size.div_ceil(512), self.mtime, self.mtime, self.mtime] {
+            a.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0u32, 0, 0, mode, nlink, self.uid, self.gid, 0, 4096, 0] {
+            a.extend_from_slice(&v.to_le_bytes());
+        }
+        Some(a)
+    }
+
+    fn entry(&self, ino: u64) -> Option<Vec<u8>> {
+        let mut out = Vec::with_capacity(128);
+        for v in [ino, 0, abi::TTL_SECS, abi::TTL_SECS] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&[0u8; 8]); // entry/attr valid nsec
+        out.extend_from_slice(&self.attr(ino)?);
+        Some(out)
+    }
+
+    fn readdir(&self, ino: u64, offset: u64, size: usize) -> Option<Vec<u8>> {
+        let node = self.tree.node(ino)?;
+        let Kind::Dir(children) = &node.kind else { return None };
+        let dots = [(".".to_string(), ino), ("..".to_string(), node.parent)];
+        let entries = dots.iter().map(|(n, i)| (n.as_str(), *i)).chain(children.iter().map(|(n, i)| (n.as_str(), *i)));
+
+        let mut out = Vec::new();
+        for (n, (name, child)) in entries.enumerate().skip(offset as usize) {
+            let dtype = match self.tree.node(child).map(|c| &c.kind) {
+                Some(Kind::Dir(_)) => libc::DT_DIR,
+                Some(Kind::Link(_)) => libc::DT_LNK,
+                _ => libc::DT_REG,
+            };
+            let len = 24 + name.len();
+            let padded = len.div_ceil(8) * 8;
+            if out.len() + padded > size {
+                break;
+            }
+            out.extend_from_slice(&child.to_le_bytes());
+            out.extend_from_slice(&(n as u64 + 1).to_le_bytes()); // offset of the next entry
+            out.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(dtype as u32).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len() + padded - len, 0);
+        }
+        Some(out)
+    }
+
+    // Ok(None): no reply is sent for this opcode
+    fn handle(&self, opcode: u32, ino: u64, body: &[u8]) -> std::result::Result<Option<Vec<u8>>, i32> {
+        match opcode {
+            abi::INIT => {
+                let mut out = Vec::with_capacity(64);
+                for v in [7u32, 31, abi::u32_at(body, 8), 0] {
+                    out.extend_from_slice(&v.to_le_bytes()); // major, minor, max_readahead, flags
+                }
+                out.extend_from_slice(&16u16.to_le_bytes()); // max_background
+                out.extend_from_slice(&12u16.to_le_bytes()); // congestion_threshold
+                out.extend_from_slice(&abi::MAX_WRITE.to_le_bytes());
+                out.extend_from_slice(&1u32.to_le_bytes()); // time_gran
+                out.resize(64, 0);
+                Ok(Some(out))
+            }
+            abi::LOOKUP => {
+                let name = body.split(|b| *b == 0).next().unwrap_or_default();
+                let name = std::str::from_utf8(name).map_err(|_| libc::ENOENT)?;
+                let Some(Node { kind: Kind::Dir(children), .. }) = self.tree.node(ino) else { return Err(libc::ENOTDIR) };
+                let child = *children.get(name).ok_or(libc::ENOENT)?;
+                self.entry(child).map(Some).ok_or(libc::ENOENT)
+            }
+            abi::GETATTR => {
+                let attr = self.attr(ino).ok_or(libc::ENOENT)?;
+                let mut out = Vec::with_capacity(104);
+                out.extend_from_slice(&abi::TTL_SECS.to_le_bytes());
+                out.extend_from_slice(&[0u8; 8]); // attr_valid_nsec, dummy
+                out.extend_from_slice(&attr);
+                Ok(Some(out))
+            }
+            abi::READLINK => match self.tree.node(ino).map(|n| &n.kind) {
+                Some(Kind::Link(target)) => Ok(Some(target.as_bytes().to_vec())),
+                _ => Err(libc::EINVAL),
+            },
+            abi::OPEN => {
+                // Read-only: any O_WRONLY/O_RDWR open is refused
+                if abi::u32_at(body, 0) & libc::O_ACCMODE as u32 != libc::O_RDONLY as u32 {
+                    return Err(libc::EROFS);
+                }
+                match self.tree.node(ino).map(|n| &n.kind) {
+                    Some(Kind::File(_)) => Ok(Some(open_out())),
+                    Some(Kind::Dir(_)) => Err(libc::EISDIR),
+                    _ => Err(libc::ENOENT),
+                }
+            }
+            abi::OPENDIR => match self.tree.node(ino).map(|n| &n.kind) {
+                Some(Kind::Dir(_)) => Ok(Some(open_out())),
+                Some(_) => Err(libc::ENOTDIR),
+                None => Err(libc::ENOENT),
+            },
+            abi::READ => {
+                let Some(Kind::File(i)) = self.tree.node(ino).map(|n| &n.kind) else { return Err(libc::EISDIR) };
+                let (offset, size) = (abi::u64_at(body, 8) as usize, abi::u32_at(body, 16) as usize);
+                let data = render(&self.items[*i]);
+                let start = offset.min(data.len());
+                Ok(Some(data[start..(start + size).min(data.len())].to_vec()))
+            }
+            abi::READDIR => {
+                let (offset, size) = (abi::u64_at(body, 8), abi::u32_at(body, 16) as usize);
+                self.readdir(ino, offset, size).map(Some).ok_or(libc::ENOTDIR)
+            }
+            abi::STATFS => {
+                let mut out = Vec::with_capacity(80);
+                for v in [0u64, 0, 0, self.tree.nodes.len() as u64, 0] {
+                    out.extend_from_slice(&v.to_le_bytes()); // blocks, bfree, bavail, files, ffree
+                }
+                for v in [4096u32, 255, 4096] {
+                    out.extend_from_slice(&v.to_le_bytes()); // bsize, namelen, frsize
+                }
+                out.resize(80, 0);
+                Ok(Some(out))
+            }
+            abi::ACCESS => {
+                if abi::u32_at(body, 0) & libc::W_OK as u32 != 0 {
+                    return Err(libc::EROFS);
+                }
+                Ok(Some(Vec::new()))
+            }
+            abi::RELEASE | abi::RELEASEDIR | abi::FLUSH | abi::DESTROY => Ok(Some(Vec::new())),
+            abi::FORGET | abi::BATCH_FORGET | abi::INTERRUPT => Ok(None),
+            _ => Err(libc::ENOSYS),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_out() -> Vec<u8> {
+    let mut out = vec![0u8; 8]; // fh: unused, everything is addressed by inode
+    out.extend_from_slice(&abi::FOPEN_KEEP_CACHE.to_le_bytes());
+    out.extend_from_slice(&[0u8; 4]);
+    out
+}
+
+#[cfg(target_os = "linux")]
+static MOUNTPOINT: std::sync::OnceLock<std::ffi::CString> = std::sync::OnceLock::new();
+
+#[cfg(target_os = "linux")]
+extern "C" fn unmount_on_signal(_: libc::c_int) {
+    if let Some(dir) = MOUNTPOINT.get() {
+        // SAFETY: umount2 is async-signal-safe; the path outlives the process.
+        unsafe { libc::umount2(dir.as_ptr(), libc::MNT_DETACH) };
+    }
+}
+
+/// Mount `items` read-only at `dir` and serve requests until unmounted.
+#[cfg(target_os = "linux")]
+pub fn mount(items: &[CanonicalItem], source: &Path, dir: &Path, allow_other: bool) -> Result<()> {
+    use std::{ffi::CString, fs::OpenOptions, io::{Read, Write}, os::fd::AsRawFd, os::unix::ffi::OsStrExt};
+
+    let mtime = std::fs::metadata(source)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    // SAFETY: getuid/getgid cannot fail.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let fs = Fs { items, tree: Tree::build(items), uid, gid, mtime };
+
+    let mut dev = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/fuse")
+        .with_context(|| "Failed to open /dev/fuse (is the fuse module loaded?)")?;
+
+    let target = CString::new(dir.as_os_str().as_bytes()).with_context(|| format!("Invalid mount point: {}", dir.display()))?;
+    let mut opts = format!("fd={},rootmode=40000,user_id={},group_id={},default_permissions", dev.as_raw_fd(), uid, gid);
+    if allow_other {
+        opts.push_str(",allow_other");
+    }
+    let opts = CString::new(opts)?;
+    let flags = libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC;
+    // SAFETY: all pointers are valid NUL-terminated strings for the duration of the call.
+    let rc = unsafe {
+        libc::mount(c"bastion-codex".as_ptr(), target.as_ptr(), c"fuse.bastion-codex".as_ptr(), flags, opts.as_ptr().cast())
+    };
+    if rc != 0 {
+        let e = std::io::Error::last_os_error();
+        bail!("Failed to mount {}: {} (mount needs root or CAP_SYS_ADMIN)", dir.display(), e);
+    }
+    let _ = MOUNTPOINT.set(target);
+    let handler = unmount_on_signal as extern "C" fn(libc::c_int);
+    // SAFETY: the handler only calls umount2.
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+    }
+    log_ok!("mounted {} items at {} (umount {} or Ctrl-C to stop)", items.len(), dir.display(), dir.display());
+
+    let mut buf = vec![0u8; abi::MAX_WRITE as usize + 4096];
+    loop {
+        let n = match dev.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) => match e.raw_os_error() {
+                // Interrupted request or signal: keep serving
+                Some(libc::ENOENT | libc::EINTR | libc::EAGAIN) => continue,
+                // Unmounted
+                Some(libc::ENODEV) => break,
+                _ => return Err(e).with_context(|| "Failed to read from /dev/fuse"),
+            },
+        };
+        if n < abi::IN_HEADER {
+            continue;
+        }
+        let req = &buf[..n];
+        let (opcode, unique, ino) = (abi::u32_at(req, 4), abi::u64_at(req, 8), abi::u64_at(req, 16));
+
+        let (error, payload) = match fs.handle(opcode, ino, &req[abi::IN_HEADER..]) {
+            Ok(None) => continue,
+            Ok(Some(payload)) => (0i32, payload),
+            Err(errno) => (-errno, Vec::new()),
+        };
+        let mut reply = Vec::with_capacity(16 + payload.len());
+        reply.extend_from_slice(&((16 + payload.len()) as u32).to_le_bytes());
+        reply.extend_from_slice(&error.to_le_bytes());
+        reply.extend_from_slice(&unique.to_le_bytes());
+        reply.extend_from_slice(&payload);
+        // ENOENT here means the request was interrupted meanwhile
+        if let Err(e) = dev.write_all(&reply)
+            && e.raw_os_error() != Some(libc::ENOENT)
+        {
+            return Err(e).with_context(|| "Failed to write to /dev/fuse");
+        }
+        if opcode == abi::DESTROY {
+            break;
+        }
+    }
+    log_ok!("unmounted {}", dir.display());
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mount(_items: &[CanonicalItem], _source: &Path, _dir: &Path, _allow_other: bool) -> Result<()> {
+    anyhow::bail!("mount is only supported on Linux")
+}
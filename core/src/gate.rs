@@ -0,0 +1,247 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::{check, input, logging, query, severity, vex, CanonicalItem};
+
+/* -------------------- CI policy gate -------------------- */
+/*
+`gate --input items.json --policy policy.toml` fails a pipeline step when the
+codex holds something the policy forbids, without a wrapper script. Each rule
+selects items; a rule fails when more than `max` items match (default 0, so
+any match fails):
+
+  [[rule]]
+  name = "KEV in our products"
+  kev = true
+  products_file = "products.txt"   # vex.rs product list, relative to the policy
+  exit_code = 2
+
+  [[rule]]
+  name = "critical surge"
+  min_severity = "critical"
+  newer_than_days = 7              # published within 7 days of --as-of (default today)
+  max = 5
+  exit_code = 3
+
+  [[rule]]
+  name = "edge RCE"
+  filter = 'cvss >= 9.0 && "rce" in tags'
+
+Selectors, all optional and ANDed: kev (true|false), min_severity, vendors
+(exact, case-insensitive), products (inline entries matched like a vex.rs
+product list), products_file, tags (item carries any), newer_than_days, filter
+(query expression, see query.rs). The policy may also be JSON of the same shape
+({"rule": [...]}).
+
+Every rule is evaluated and reported; the exit status is the highest exit_code
+(default 1) among failed rules, 0 if none failed.
+*/
+
+const SHOWN_IDS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    name: String,
+    #[serde(default)]
+    kev: Option<bool>,
+    #[serde(default)]
+    min_severity: Option<String>,
+    #[serde(default)]
+    vendors: Vec<String>,
+    #[serde(default)]
+    products: Vec<String>,
+    #[serde(default)]
+    products_file: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    newer_than_days: Option<i64>,
+    #[serde(default)]
+    filter: Option<String>,
+    #[serde(default)]
+    max: usize,
+    #[serde(default = "default_exit_code")]
+    exit_code: i32,
+}
+
+fn default_exit_code() -> i32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleSpec>,
+}
+
+pub struct Rule {
+    name: String,
+    kev: Option<bool>,
+    min_rank: Option<u8>,
+    vendors: Vec<String>,
+    products: Vec<vex::Product>,
+    tags: Vec<String>,
+    newer_than_days: Option<i64>,
+    filter: Option<query::Expr>,
+    max: usize,
+    exit_code: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleResult {
+    pub rule: String,
+    pub matched: usize,
+    pub max: usize,
+    pub failed: bool,
+    pub exit_code: i32,
+    pub ids: Vec<String>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<Rule>> {
+    let bytes = input::read_input(path)?;
+    let text = String::from_utf8(bytes).with_context(|| format!("Gate policy is not UTF-8: {}", path.display()))?;
+    let doc: Value = if text.trim_start().starts_with('{') {
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse gate policy JSON: {}", path.display()))?
+    } else {
+        crate::toml::parse(&text).with_context(|| format!("Failed to parse gate policy TOML: {}", path.display()))?
+    };
+    let file: PolicyFile =
+        serde_json::from_value(doc).with_context(|| format!("Invalid gate policy: {}", path.display()))?;
+    if file.rules.is_empty() {
+        bail!("{}: gate policy has no [[rule]] entries", path.display());
+    }
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut rules = Vec::new();
+    for spec in file.rules {
+        let name = spec.name.trim().to_string();
+        if name.is_empty() {
+            bail!("{}: gate rule with an empty name", path.display());
+        }
+        let min_rank = match &spec.min_severity {
+            Some(s) if severity::rank(s) == 0 => {
+                bail!("{}: rule \"{}\": unknown severity '{}' (known: {})", path.display(), name, s, severity::names().join(", "))
+            }
+            Some(s) => Some(severity::rank(s)),
+            None => None,
+        };
+        if !(1..=255).contains(&spec.exit_code) {
+            bail!("{}: rule \"{}\": exit_code must be 1-255 (got {})", path.display(), name, spec.exit_code);
+        }
+        let mut products: Vec<vex::Product> = spec
+            .products
+            .iter()
+            .map(|p| vex::Product { id: p.trim().to_string(), status: "affected".to_string(), justification: None })
+            .collect();
+        if let Some(file) = &spec.products_file {
+            products.extend(vex::load_products(&base.join(file))?);
+        }
+        let filter = match &spec.filter {
+            Some(f) => Some(query::parse(f).with_context(|| format!("{}: rule \"{}\": invalid filter", path.display(), name))?),
+            None => None,
+        };
+        rules.push(Rule {
+            name,
+            kev: spec.kev,
+            min_rank,
+            vendors: spec.vendors,
+            products,
+            tags: spec.tags,
+            newer_than_days: spec.newer_than_days,
+            filter,
+            max: spec.max,
+            exit_code: spec.exit_code,
+        });
+    }
+    Ok(rules)
+}
+
+impl Rule {
+    fn matches(&self, item: &CanonicalItem, as_of: NaiveDate) -> Result<bool> {
+        let eq = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+        if self.kev.is_some_and(|k| k != item.kev) {
+            return Ok(false);
+        }
+        if self.min_rank.is_some_and(|r| severity::rank(&item.severity_bucket) < r) {
+            return Ok(false);
+        }
+        if !self.vendors.is_empty() && !item.vendor.as_deref().is_some_and(|v| self.vendors.iter().any(|w| eq(v, w))) {
+            return Ok(false);
+        }
+        if !self.products.is_empty() && !self.products.iter().any(|p| vex::matches(p, item)) {
+            return Ok(false);
+        }
+        if !self.tags.is_empty() && !item.tags.iter().any(|t| self.tags.iter().any(|w| eq(t, w))) {
+            return Ok(false);
+        }
+        if let Some(days) = self.newer_than_days {
+            let cutoff = (as_of - chrono::Duration::days(days)).format("%Y-%m-%d").to_string();
+            // ISO8601 dates compare lexicographically
+            if item.published.as_deref().is_none_or(|p| p.get(..10).unwrap_or(p) < cutoff.as_str()) {
+                return Ok(false);
+            }
+        }
+        if let Some(expr) = &self.filter {
+            return Ok(query::eval(expr, &serde_json::to_value(item)?));
+        }
+        Ok(true)
+    }
+}
+
+pub fn evaluate(items: &[CanonicalItem], rules: &[Rule], as_of: NaiveDate) -> Result<Vec<RuleResult>> {
+    let mut results = Vec::new();
+    for rule in rules {
+        let mut ids = Vec::new();
+        for item in items {
+            if rule.matches(item, as_of)? {
+                ids.push(item.id.clone());
+            }
+        }
+        results.push(RuleResult {
+            rule: rule.name.clone(),
+            matched: ids.len(),
+            max: rule.max,
+            failed: ids.len() > rule.max,
+            exit_code: rule.exit_code,
+            ids,
+        });
+    }
+    Ok(results)
+}
+
+/// The exit status the policy asks for: highest exit_code among failed rules.
+pub fn exit_code(results: &[RuleResult]) -> i32 {
+    results.iter().filter(|r| r.failed).map(|r| r.exit_code).max().unwrap_or(0)
+}
+
+fn describe(r: &RuleResult) -> String {
+    let mut ids = r.ids.iter().take(SHOWN_IDS).cloned().collect::<Vec<_>>().join(", ");
+    if r.ids.len() > SHOWN_IDS {
+        ids.push_str(&format!(", ... and {} more", r.ids.len() - SHOWN_IDS));
+    }
+    let mut line = format!("gate \"{}\": {} matching items (max {})", r.rule, r.matched, r.max);
+    if !ids.is_empty() {
+        line.push_str(&format!(": {}", ids));
+    }
+    line
+}
+
+pub fn report(results: &[RuleResult], policy: &Path, ci: check::CiFormat) {
+    for r in results {
+        match ci {
+            check::CiFormat::Github if r.failed => {
+                println!("::error file={},title=gate {}::{}", policy.display(), r.rule, check::gh_escape(&describe(r)));
+            }
+            _ => println!("{} {}", if r.failed { "[FAIL]" } else { "[OK]" }, describe(r)),
+        }
+    }
+    let failed = results.iter().filter(|r| r.failed).count();
+    logging::emit(
+        if failed > 0 { logging::Level::Fail } else { logging::Level::Ok },
+        format_args!("gate: {} rules, {} failed", results.len(), failed),
+    );
+}
@@ -0,0 +1,1405 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+
+use crate::{delta, log_debug, log_ok, log_warn, query, serve, snapshot, stats, CanonicalItem};
+
+/* -------------------- gRPC API -------------------- */
+/*
+`serve --grpc-listen HOST:PORT` answers the Codex service of proto/bastion.proto
+next to the REST API, from the same in-memory index (reloads included):
+
+  GetItem      one item by ID, case-insensitive     NOT_FOUND if unknown
+  QueryItems   the GET /items filters (see serve.rs), one message per item;
+               limit 0 streams every match           INVALID_ARGUMENT on a bad filter
+  GetStats     the `stats --json` counts, top N vendors/products (default 10)
+  Diff         added/changed/removed since the snapshot-store run at or before
+               `since` (YYYY-MM-DD: end of that day), as delta.rs computes
+               them; needs serve --snapshot-store     FAILED_PRECONDITION without,
+                                                      NOT_FOUND if no run that early
+
+Items carry the fields clients usually route on as typed fields, plus `json`
+with the whole canonical item, so the .proto doesn't have to follow every
+field the schema gains. Diff answers in one message, so a wide window is a
+big one: clients may need a higher max receive size. The last run Diff loaded
+stays in memory, so polling against the same run only reads the store once.
+
+There is no Watch stream: pushing changes as they land would hold a stream
+open for its lifetime, and this server answers a connection's calls in turn,
+so a Watch would block every other call on the channel. `daemon
+--notify-serve` reloads the index after each run; clients poll Diff instead.
+
+Like the REST server this is std only: HTTP/2 over cleartext with prior
+knowledge (what gRPC clients speak to a plaintext target), HPACK decoding with
+its static table, dynamic table and Huffman code, flow control for the
+streaming call, and protobuf by hand for these few messages. Calls on one
+connection are answered in turn; a client wanting them in parallel opens
+more channels. No TLS, auth, compression or reflection: same deployment
+advice as serve.rs, and clients generate stubs from the .proto.
+*/
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const MAX_FRAME: usize = 16_384; // SETTINGS_MAX_FRAME_SIZE, left at the default
+const MAX_HEADER_BLOCK: usize = 64 * 1024;
+const MAX_MESSAGE: usize = 4 << 20;
+const DEFAULT_WINDOW: i64 = 65_535;
+const HEADER_TABLE_SIZE: usize = 4096;
+
+// Frame types and flags (RFC 9113)
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+const PRIORITY: u8 = 0x20;
+const PROTOCOL_ERROR: u32 = 0x1;
+
+// gRPC status codes
+const OK: u32 = 0;
+const INVALID_ARGUMENT: u32 = 3;
+const NOT_FOUND: u32 = 5;
+const RESOURCE_EXHAUSTED: u32 = 8;
+const FAILED_PRECONDITION: u32 = 9;
+const UNIMPLEMENTED: u32 = 12;
+const INTERNAL: u32 = 13;
+
+/* ---- HPACK (RFC 7541) ---- */
+
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// (code, bits) per symbol 0..=256 (256 is EOS), Appendix B.
+const HUFFMAN: [(u32, u8); 257] = [
+    (0x1ff8, 13), (0x7fffd8, 23), (0xfffffe2, 28), (0xfffffe3, 28), (0xfffffe4, 28), (0xfffffe5, 28),
+    (0xfffffe6, 28), (0xfffffe7, 28), (0xfffffe8, 28), (0xffffea, 24), (0x3ffffffc, 30), (0xfffffe9, 28),
+    (0xfffffea, 28), (0x3ffffffd, 30), (0xfffffeb, 28), (0xfffffec, 28), (0xfffffed, 28), (0xfffffee, 28),
+    (0xfffffef, 28), (0xffffff0, 28), (0xffffff1, 28), (0xffffff2, 28), (0x3ffffffe, 30), (0xffffff3, 28),
+    (0xffffff4, 28), (0xffffff5, 28), (0xffffff6, 28), (0xffffff7, 28), (0xffffff8, 28), (0xffffff9, 28),
+    (0xffffffa, 28), (0xffffffb, 28), (0x14, 6), (0x3f8, 10), (0x3f9, 10), (0xffa, 12), (0x1ff9, 13), (0x15, 6),
+    (0xf8, 8), (0x7fa, 11), (0x3fa, 10), (0x3fb, 10), (0xf9, 8), (0x7fb, 11), (0xfa, 8), (0x16, 6), (0x17, 6),
+    (0x18, 6), (0x0, 5), (0x1, 5), (0x2, 5), (0x19, 6), (0x1a, 6), (0x1b, 6), (0x1c, 6), (0x1d, 6), (0x1e, 6),
+    (0x1f, 6), (0x5c, 7), (0xfb, 8), (0x7ffc, 15), (0x20, 6), (0xffb, 12), (0x3fc, 10), (0x1ffa, 13), (0x21, 6),
+    (0x5d, 7), (0x5e, 7), (0x5f, 7), (0x60, 7), (0x61, 7), (0x62, 7), (0x63, 7), (0x64, 7), (0x65, 7), (0x66, 7),
+    (0x67, 7), (0x68, 7), (0x69, 7), (0x6a, 7), (0x6b, 7), (0x6c, 7), (0x6d, 7), (0x6e, 7), (0x6f, 7), (0x70, 7),
+    (0x71, 7), (0x72, 7), (0xfc, 8), (0x73, 7), (0xfd, 8), (0x1ffb, 13), (0x7fff0, 19), (0x1ffc, 13), (0x3ffc, 14),
+    (0x22, 6), (0x7ffd, 15), (0x3, 5), (0x23, 6), (0x4, 5), (0x24, 6), (0x5, 5), (0x25, 6), (0x26, 6), (0x27, 6),
+    (0x6, 5), (0x74, 7), (0x75, 7), (0x28, 6), (0x29, 6), (0x2a, 6), (0x7, 5), (0x2b, 6), (0x76, 7), (0x2c, 6),
+    (0x8, 5), (0x9, 5), (0x2d, 6), (0x77, 7), (0x78, 7), (0x79, 7), (0x7a, 7), (0x7b, 7), (0x7ffe, 15),
+    (0x7fc, 11), (0x3ffd, 14), (0x1ffd, 13), (0xffffffc, 28), (0xfffe6, 20), (0x3fffd2, 22), (0xfffe7, 20),
+    (0xfffe8, 20), (0x3fffd3, 22), (0x3fffd4, 22), (0x3fffd5, 22), (0x7fffd9, 23), (0x3fffd6, 22), (0x7fffda, 23),
+    (0x7fffdb, 23), (0x7fffdc, 23), (0x7fffdd, 23), (0x7fffde, 23), (0xffffeb, 24), (0x7fffdf, 23), (0xffffec, 24),
+    (0xffffed, 24), (0x3fffd7, 22), (0x7fffe0, 23), (0xffffee, 24), (0x7fffe1, 23), (0x7fffe2, 23), (0x7fffe3, 23),
+    (0x7fffe4, 23), (0x1fffdc, 21), (0x3fffd8, 22), (0x7fffe5, 23), (0x3fffd9, 22), (0x7fffe6, 23), (0x7fffe7, 23),
+    (0xffffef, 24), (0x3fffda, 22), (0x1fffdd, 21), (0xfffe9, 20), (0x3fffdb, 22), (0x3fffdc, 22), (0x7fffe8, 23),
+    (0x7fffe9, 23), (0x1fffde, 21), (0x7fffea, 23), (0x3fffdd, 22), (0x3fffde, 22), (0xfffff0, 24), (0x1fffdf, 21),
+    (0x3fffdf, 22), (0x7fffeb, 23), (0x7fffec, 23), (0x1fffe0, 21), (0x1fffe1, 21), (0x3fffe0, 22), (0x1fffe2, 21),
+    (0x7fffed, 23), (0x3fffe1, 22), (0x7fffee, 23), (0x7fffef, 23), (0xfffea, 20), (0x3fffe2, 22), (0x3fffe3, 22),
+    (0x3fffe4, 22), (0x7ffff0, 23), (0x3fffe5, 22), (0x3fffe6, 22), (0x7ffff1, 23), (0x3ffffe0, 26),
+    (0x3ffffe1, 26), (0xfffeb, 20), (0x7fff1, 19), (0x3fffe7, 22), (0x7ffff2, 23), (0x3fffe8, 22), (0x1ffffec, 25),
+    (0x3ffffe2, 26), (0x3ffffe3, 26), (0x3ffffe4, 26), (0x7ffffde, 27), (0x7ffffdf, 27), (0x3ffffe5, 26),
+    (0xfffff1, 24), (0x1ffffed, 25), (0x7fff2, 19), (0x1fffe3, 21), (0x3ffffe6, 26), (0x7ffffe0, 27),
+    (0x7ffffe1, 27), (0x3ffffe7, 26), (0x7ffffe2, 27), (0xfffff2, 24), (0x1fffe4, 21), (0x1fffe5, 21),
+    (0x3ffffe8, 26), (0x3ffffe9, 26), (0xffffffd, 28), (0x7ffffe3, 27), (0x7ffffe4, 27), (0x7ffffe5, 27),
+    (0xfffec, 20), (0xfffff3, 24), (0xfffed, 20), (0x1fffe6, 21), (0x3fffe9, 22), (0x1fffe7, 21), (0x1fffe8, 21),
+    (0x7ffff3, 23), (0x3fffea, 22), (0x3fffeb, 22), (0x1ffffee, 25), (0x1ffffef, 25), (0xfffff4, 24),
+    (0xfffff5, 24), (0x3ffffea, 26), (0x7ffff4, 23), (0x3ffffeb, 26), (0x7ffffe6, 27), (0x3ffffec, 26),
+    (0x3ffffed, 26), (0x7ffffe7, 27), (0x7ffffe8, 27), (0x7ffffe9, 27), (0x7ffffea, 27), (0x7ffffeb, 27),
+    (0xffffffe, 28), (0x7ffffec, 27), (0x7ffffed, 27), (0x7ffffee, 27), (0x7ffffef, 27), (0x7fffff0, 27),
+    (0x3ffffee, 26), (0x3fffffff, 30),
+];
+
+const LEAF: u16 = 0x8000;
+
+/// The code as a binary tree: node 0 is the root, children >= LEAF are symbols.
+fn huffman_tree() -> &'static [[u16; 2]] {
+    static TREE: OnceLock<Vec<[u16; 2]>> = OnceLock::new();
+    TREE.get_or_init(|| {
+        let mut tree = vec![[0u16; 2]];
+        for (sym, &(code, bits)) in HUFFMAN.iter().enumerate() {
+            let mut node = 0;
+            for i in (0..bits).rev() {
+                let bit = ((code >> i) & 1) as usize;
+                if i == 0 {
+                    tree[node][bit] = LEAF + sym as u16;
+                } else {
+                    if tree[node][bit] == 0 {
+                        tree.push([0, 0]);
+                        tree[node][bit] = (tree.len() - 1) as u16;
+                    }
+                    node = tree[node][bit] as usize;
+                }
+            }
+        }
+        tree
+    })
+}
+
+fn huffman_decode(raw: &[u8]) -> Result<Vec<u8>> {
+    let tree = huffman_tree();
+    let mut out = Vec::with_capacity(raw.len() * 8 / 5);
+    let (mut node, mut depth, mut ones) = (0usize, 0u32, true);
+    for byte in raw {
+        for i in (0..8).rev() {
+            let bit = (byte >> i) & 1;
+            let next = tree[node][bit as usize];
+            if next >= LEAF {
+                if next - LEAF == 256 {
+                    bail!("EOS inside a Huffman-coded string");
+                }
+                out.push((next - LEAF) as u8);
+                (node, depth, ones) = (0, 0, true);
+            } else {
+                (node, depth, ones) = (next as usize, depth + 1, ones && bit == 1);
+            }
+        }
+    }
+    // What is left must be padding: under a byte of EOS's leading ones
+    if depth > 7 || !ones {
+        bail!("invalid Huffman padding");
+    }
+    Ok(out)
+}
+
+fn hpack_integer(buf: &[u8], pos: &mut usize, prefix: u8) -> Result<usize> {
+    let mask = (1u8 << prefix) - 1;
+    let first = *buf.get(*pos).context("truncated HPACK integer")? & mask;
+    *pos += 1;
+    if first < mask {
+        return Ok(first as usize);
+    }
+    let (mut value, mut shift) = (mask as usize, 0);
+    loop {
+        let b = *buf.get(*pos).context("truncated HPACK integer")?;
+        *pos += 1;
+        if shift > 28 {
+            bail!("HPACK integer too large");
+        }
+        value += ((b & 0x7f) as usize) << shift;
+        shift += 7;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+fn hpack_string(buf: &[u8], pos: &mut usize) -> Result<String> {
+    let huffman = buf.get(*pos).is_some_and(|b| b & 0x80 != 0);
+    let len = hpack_integer(buf, pos, 7)?;
+    let raw = buf.get(*pos..*pos + len).context("truncated HPACK string")?;
+    *pos += len;
+    let bytes = if huffman { huffman_decode(raw)? } else { raw.to_vec() };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Header block decoder; one per connection, since the dynamic table spans requests.
+struct Decoder {
+    table: VecDeque<(String, String)>,
+    size: usize,
+    max: usize,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        Decoder { table: VecDeque::new(), size: 0, max: HEADER_TABLE_SIZE }
+    }
+
+    fn entry(&self, index: usize) -> Result<(String, String)> {
+        match index {
+            0 => bail!("HPACK index 0"),
+            i if i <= STATIC_TABLE.len() => {
+                let (name, value) = STATIC_TABLE[i - 1];
+                Ok((name.to_string(), value.to_string()))
+            }
+            i => self.table.get(i - STATIC_TABLE.len() - 1).cloned().context("HPACK index out of range"),
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max
+            && let Some((name, value)) = self.table.pop_back()
+        {
+            self.size -= name.len() + value.len() + 32;
+        }
+    }
+
+    fn literal(&self, block: &[u8], pos: &mut usize, prefix: u8) -> Result<(String, String)> {
+        let name = match hpack_integer(block, pos, prefix)? {
+            0 => hpack_string(block, pos)?,
+            index => self.entry(index)?.0,
+        };
+        Ok((name, hpack_string(block, pos)?))
+    }
+
+    fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+        while let Some(&b) = block.get(pos) {
+            if b & 0x80 != 0 {
+                let index = hpack_integer(block, &mut pos, 7)?;
+                headers.push(self.entry(index)?);
+            } else if b & 0x40 != 0 {
+                let (name, value) = self.literal(block, &mut pos, 6)?;
+                self.size += name.len() + value.len() + 32;
+                self.table.push_front((name.clone(), value.clone()));
+                self.evict();
+                headers.push((name, value));
+            } else if b & 0x20 != 0 {
+                let max = hpack_integer(block, &mut pos, 5)?;
+                if max > HEADER_TABLE_SIZE {
+                    bail!("HPACK table size {} over the {} allowed", max, HEADER_TABLE_SIZE);
+                }
+                self.max = max;
+                self.evict();
+            } else {
+                // Without indexing and never indexed look the same to a decoder
+                headers.push(self.literal(block, &mut pos, 4)?);
+            }
+        }
+        Ok(headers)
+    }
+}
+
+fn put_integer(out: &mut Vec<u8>, mut value: usize, prefix: u8, first: u8) {
+    let mask = (1usize << prefix) - 1;
+    if value < mask {
+        out.push(first | value as u8);
+        return;
+    }
+    out.push(first | mask as u8);
+    value -= mask;
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Our headers go out as literals without indexing, so the client's table never changes.
+fn encode_headers(headers: &[(&str, &str)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (name, value) in headers {
+        if (*name, *value) == (":status", "200") {
+            out.push(0x88);
+            continue;
+        }
+        out.push(0x00);
+        for s in [name, value] {
+            put_integer(&mut out, s.len(), 7, 0x00);
+            out.extend_from_slice(s.as_bytes());
+        }
+    }
+    out
+}
+
+/* ---- Protobuf ---- */
+
+#[derive(Default)]
+struct Pb(Vec<u8>);
+
+impl Pb {
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn key(&mut self, field: u32, wire: u8) {
+        self.varint(((field as u64) << 3) | wire as u64);
+    }
+
+    fn uint(&mut self, field: u32, v: u64) {
+        if v != 0 {
+            self.key(field, 0);
+            self.varint(v);
+        }
+    }
+
+    fn opt_bool(&mut self, field: u32, v: Option<bool>) {
+        if let Some(v) = v {
+            self.key(field, 0);
+            self.varint(v as u64);
+        }
+    }
+
+    fn opt_double(&mut self, field: u32, v: Option<f64>) {
+        if let Some(v) = v {
+            self.key(field, 1);
+            self.0.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    fn bytes(&mut self, field: u32, b: &[u8]) {
+        self.key(field, 2);
+        self.varint(b.len() as u64);
+        self.0.extend_from_slice(b);
+    }
+
+    /// Empty strings are proto3's default and stay off the wire.
+    fn string(&mut self, field: u32, s: &str) {
+        if !s.is_empty() {
+            self.bytes(field, s.as_bytes());
+        }
+    }
+
+    fn opt_string(&mut self, field: u32, s: &Option<String>) {
+        if let Some(s) = s {
+            self.string(field, s);
+        }
+    }
+
+    fn repeated(&mut self, field: u32, list: &[String]) {
+        for s in list {
+            self.bytes(field, s.as_bytes());
+        }
+    }
+
+    fn message(&mut self, field: u32, m: Pb) {
+        self.bytes(field, &m.0);
+    }
+
+    /// map<string, uint64> and repeated Count share the {1: name, 2: count} layout.
+    fn counts<'a>(&mut self, field: u32, counts: impl IntoIterator<Item = (&'a String, &'a usize)>) {
+        for (name, count) in counts {
+            let mut entry = Pb::default();
+            entry.string(1, name);
+            entry.uint(2, *count as u64);
+            self.message(field, entry);
+        }
+    }
+}
+
+enum Wire<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *buf.get(*pos).context("truncated varint")?;
+        *pos += 1;
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("varint too long")
+}
+
+/// A message's fields in wire order; fixed-width values are skipped over.
+fn fields(buf: &[u8]) -> Result<Vec<(u32, Wire<'_>)>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let skip = |pos: &mut usize, n: usize| -> Result<()> {
+            *pos = pos.checked_add(n).filter(|&end| end <= buf.len()).context("truncated field")?;
+            Ok(())
+        };
+        let value = match key & 7 {
+            0 => Wire::Varint(read_varint(buf, &mut pos)?),
+            1 => {
+                skip(&mut pos, 8)?;
+                Wire::Fixed
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let start = pos;
+                skip(&mut pos, len)?;
+                Wire::Bytes(&buf[start..pos])
+            }
+            5 => {
+                skip(&mut pos, 4)?;
+                Wire::Fixed
+            }
+            wire => bail!("unsupported wire type {}", wire),
+        };
+        out.push(((key >> 3) as u32, value));
+    }
+    Ok(out)
+}
+
+fn text(value: &Wire) -> Result<String> {
+    match value {
+        Wire::Bytes(b) => String::from_utf8(b.to_vec()).context("string field is not UTF-8"),
+        _ => bail!("expected a string field"),
+    }
+}
+
+fn number(value: &Wire) -> Result<u64> {
+    match value {
+        Wire::Varint(v) => Ok(*v),
+        _ => bail!("expected a varint field"),
+    }
+}
+
+/* ---- The Codex service ---- */
+
+struct Status(u32, String);
+
+enum Reply<'a> {
+    One(Vec<u8>),
+    Stream(serve::Selected<'a>),
+}
+
+fn item_message(item: &CanonicalItem) -> Vec<u8> {
+    let mut m = Pb::default();
+    m.string(1, &item.id);
+    m.repeated(2, &item.sources);
+    m.opt_string(3, &item.published);
+    m.opt_string(4, &item.last_modified);
+    m.opt_double(5, item.cvss);
+    m.string(6, &item.severity_bucket);
+    m.uint(7, item.kev as u64);
+    m.opt_string(8, &item.kev_date_added);
+    m.opt_string(9, &item.kev_due_date);
+    m.opt_string(10, &item.kev_required_action);
+    m.opt_bool(11, item.ransomware_known);
+    m.string(12, &item.short_desc);
+    m.opt_string(13, &item.title);
+    m.opt_string(14, &item.vendor);
+    m.opt_string(15, &item.product);
+    for r in &item.refs {
+        let mut rm = Pb::default();
+        rm.string(1, &r.url);
+        rm.string(2, r.kind.as_str());
+        rm.string(3, &r.source);
+        m.message(16, rm);
+    }
+    m.repeated(17, &item.cwes);
+    m.repeated(18, &item.tags);
+    m.opt_double(19, item.priority_score);
+    m.opt_string(20, &item.priority_tier);
+    m.string(21, &item.content_hash);
+    m.string(100, &serde_json::to_string(item).unwrap_or_default());
+    m.0
+}
+
+fn stats_message(s: &stats::Stats) -> Vec<u8> {
+    let mut m = Pb::default();
+    m.uint(1, s.total as u64);
+    m.uint(2, s.kev as u64);
+    m.uint(3, s.non_kev as u64);
+    m.counts(4, &s.by_severity);
+    m.uint(5, s.with_cvss as u64);
+    m.opt_double(6, s.avg_cvss);
+    m.counts(7, s.top_vendors.iter().map(|(k, v)| (k, v)));
+    m.counts(8, s.top_products.iter().map(|(k, v)| (k, v)));
+    m.counts(9, &s.by_month);
+    m.uint(10, s.no_published as u64);
+    m.0
+}
+
+/// The snapshot store Diff compares against, with the last run it loaded.
+struct Snapshots {
+    store: snapshot::Store,
+    last: Mutex<Option<Arc<delta::Previous>>>,
+}
+
+impl Snapshots {
+    /// The last run taken at or before `at`, summarized. None: no run that early.
+    fn previous(&self, at: chrono::DateTime<chrono::Utc>) -> Result<Option<Arc<delta::Previous>>> {
+        let Some(run) = self.store.run_as_of(at)? else { return Ok(None) };
+        let mut last = self.last.lock().map_err(|_| anyhow::anyhow!("snapshot cache lock poisoned"))?;
+        if let Some(prev) = last.as_ref().filter(|p| p.generated_at.as_ref() == Some(&run.taken_at)) {
+            return Ok(Some(Arc::clone(prev)));
+        }
+        let items = self.store.items(&run)?;
+        let label = format!("snapshot run {}", run.taken_at);
+        let prev = Arc::new(delta::from_items(label, Some(run.taken_at.clone()), &items)?);
+        *last = Some(Arc::clone(&prev));
+        Ok(Some(prev))
+    }
+}
+
+fn diff_message(items: &[CanonicalItem], prev: &delta::Previous) -> Vec<u8> {
+    let (changes, counts) = delta::changes(items, prev);
+    let mut m = Pb::default();
+    m.opt_string(1, &prev.generated_at);
+    m.uint(2, counts.added as u64);
+    m.uint(3, counts.changed as u64);
+    m.uint(4, counts.removed as u64);
+    for change in changes {
+        let mut cm = Pb::default();
+        let kind = match change.change {
+            delta::ChangeKind::Added => 1,
+            delta::ChangeKind::Changed => 2,
+            delta::ChangeKind::Removed => 3,
+        };
+        cm.uint(1, kind);
+        cm.string(2, change.id);
+        if let Some(item) = change.item {
+            cm.bytes(3, &item_message(item));
+        }
+        if let Some(was) = change.was {
+            let mut wm = Pb::default();
+            wm.string(1, &was.content_hash);
+            wm.opt_double(2, was.cvss);
+            wm.string(3, &was.severity_bucket);
+            wm.uint(4, was.kev as u64);
+            cm.message(4, wm);
+        }
+        m.message(5, cm);
+    }
+    m.0
+}
+
+/// QueryRequest as the /items filters; unset (empty) fields match everything.
+fn query_request(body: &[u8]) -> Result<(serve::Selection, usize)> {
+    let mut sel = serve::Selection::default();
+    let mut severities = Vec::new();
+    let mut limit = 0;
+    let set = |value: &Wire| -> Result<Option<String>> { Ok(Some(text(value)?).filter(|s| !s.is_empty())) };
+    for (field, value) in fields(body)? {
+        match field {
+            1 => severities.push(text(&value)?),
+            2 => sel.kev = Some(number(&value)? != 0),
+            3 => sel.vendor = set(&value)?,
+            4 => sel.product = set(&value)?,
+            5 => sel.tag = set(&value)?,
+            6 => sel.cwe = set(&value)?,
+            7 => sel.since = set(&value)?,
+            8 => {
+                if let Some(expr) = set(&value)? {
+                    sel.filter = Some(query::parse(&expr).context("Invalid filter")?);
+                }
+            }
+            9 => sel.sort = set(&value)?,
+            10 => limit = number(&value)? as usize,
+            _ => {}
+        }
+    }
+    if !severities.is_empty() {
+        sel.severities = Some(severities);
+    }
+    Ok((sel, if limit == 0 { usize::MAX } else { limit }))
+}
+
+fn answer<'a>(
+    index: &'a serve::Index,
+    snapshots: Option<&Snapshots>,
+    path: &str,
+    body: &[u8],
+) -> Result<Reply<'a>, Status> {
+    let invalid = |e: anyhow::Error| Status(INVALID_ARGUMENT, format!("{:#}", e));
+    match path {
+        "/bastion.v1.Codex/GetItem" => {
+            let mut id = String::new();
+            for (field, value) in fields(body).map_err(invalid)? {
+                if field == 1 {
+                    id = text(&value).map_err(invalid)?.to_ascii_uppercase();
+                }
+            }
+            match index.by_id.get(&id) {
+                Some(&i) => Ok(Reply::One(item_message(&index.items[i]))),
+                None => Err(Status(NOT_FOUND, format!("No item {}", id))),
+            }
+        }
+        "/bastion.v1.Codex/QueryItems" => {
+            let (sel, limit) = query_request(body).map_err(invalid)?;
+            Ok(Reply::Stream(sel.select(index, 0, limit).1))
+        }
+        "/bastion.v1.Codex/GetStats" => {
+            let mut top = serve::STATS_TOP;
+            for (field, value) in fields(body).map_err(invalid)? {
+                if field == 1 && number(&value).map_err(invalid)? > 0 {
+                    top = number(&value).map_err(invalid)? as usize;
+                }
+            }
+            Ok(Reply::One(stats_message(&stats::compute(&index.items, top))))
+        }
+        "/bastion.v1.Codex/Diff" => {
+            let mut since = String::new();
+            for (field, value) in fields(body).map_err(invalid)? {
+                if field == 1 {
+                    since = text(&value).map_err(invalid)?;
+                }
+            }
+            let snapshots = snapshots
+                .ok_or_else(|| Status(FAILED_PRECONDITION, "Diff needs serve --snapshot-store".to_string()))?;
+            let at = snapshot::parse_as_of(&since).map_err(invalid)?;
+            match snapshots.previous(at) {
+                Ok(Some(prev)) => Ok(Reply::One(diff_message(&index.items, &prev))),
+                Ok(None) => Err(Status(NOT_FOUND, format!("No snapshot run at or before {}", since))),
+                Err(e) => Err(Status(INTERNAL, format!("{:#}", e))),
+            }
+        }
+        _ => Err(Status(UNIMPLEMENTED, format!("No method {}", path))),
+    }
+}
+
+/// grpc-message is percent-encoded outside printable ASCII.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::new();
+    for b in s.bytes() {
+        match b {
+            b' '..=b'~' if b != b'%' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/* ---- HTTP/2 connection ---- */
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream: u32,
+    payload: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Request {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    too_large: bool,
+}
+
+struct Conn {
+    stream: TcpStream,
+    shared: serve::Shared,
+    snapshots: Option<Arc<Snapshots>>,
+    hpack: Decoder,
+    peer_max_frame: usize,
+    peer_initial_window: i64,
+    send_window: i64,
+    // Send windows of the streams we still owe an answer
+    windows: HashMap<u32, i64>,
+    receiving: HashMap<u32, Request>,
+    ready: VecDeque<(u32, Request)>,
+    last_stream: u32,
+    goaway: bool,
+}
+
+fn strip_padding(flags: u8, payload: &mut Vec<u8>) -> Result<()> {
+    if flags & PADDED != 0 {
+        let pad = *payload.first().context("empty padded frame")? as usize;
+        if pad + 1 > payload.len() {
+            bail!("padding longer than the frame");
+        }
+        payload.truncate(payload.len() - pad);
+        payload.remove(0);
+    }
+    Ok(())
+}
+
+impl Conn {
+    fn read_frame(&mut self) -> Result<Option<Frame>> {
+        let mut head = [0u8; 9];
+        match self.stream.read_exact(&mut head) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            r => r.context("Failed to read frame")?,
+        }
+        let len = u32::from_be_bytes([0, head[0], head[1], head[2]]) as usize;
+        if len > MAX_FRAME {
+            bail!("{}-byte frame over SETTINGS_MAX_FRAME_SIZE", len);
+        }
+        let mut payload = vec![0; len];
+        self.stream.read_exact(&mut payload).context("Failed to read frame")?;
+        let stream = u32::from_be_bytes([head[5], head[6], head[7], head[8]]) & 0x7fff_ffff;
+        Ok(Some(Frame { kind: head[3], flags: head[4], stream, payload }))
+    }
+
+    fn write_frame(&mut self, kind: u8, flags: u8, stream: u32, payload: &[u8]) -> Result<()> {
+        let mut buf = Vec::with_capacity(9 + payload.len());
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+        buf.push(kind);
+        buf.push(flags);
+        buf.extend_from_slice(&stream.to_be_bytes());
+        buf.extend_from_slice(payload);
+        self.stream.write_all(&buf).context("Failed to write frame")
+    }
+
+    fn on_frame(&mut self, frame: Frame) -> Result<()> {
+        match frame.kind {
+            SETTINGS if frame.flags & ACK == 0 => {
+                for s in frame.payload.chunks_exact(6) {
+                    let value = u32::from_be_bytes([s[2], s[3], s[4], s[5]]);
+                    match u16::from_be_bytes([s[0], s[1]]) {
+                        0x4 => {
+                            // INITIAL_WINDOW_SIZE moves every open stream's window by the difference
+                            let delta = value as i64 - self.peer_initial_window;
+                            self.windows.values_mut().for_each(|w| *w += delta);
+                            self.peer_initial_window = value as i64;
+                        }
+                        0x5 => self.peer_max_frame = (value as usize).clamp(MAX_FRAME, (1 << 24) - 1),
+                        _ => {}
+                    }
+                }
+                self.write_frame(SETTINGS, ACK, 0, &[])?;
+            }
+            PING if frame.flags & ACK == 0 => self.write_frame(PING, ACK, 0, &frame.payload)?,
+            WINDOW_UPDATE => {
+                let b = frame.payload.get(..4).context("short WINDOW_UPDATE")?;
+                let increment = (u32::from_be_bytes([b[0], b[1], b[2], b[3]]) & 0x7fff_ffff) as i64;
+                match frame.stream {
+                    0 => self.send_window += increment,
+                    id => {
+                        if let Some(w) = self.windows.get_mut(&id) {
+                            *w += increment;
+                        }
+                    }
+                }
+            }
+            RST_STREAM => {
+                self.receiving.remove(&frame.stream);
+                self.windows.remove(&frame.stream);
+            }
+            GOAWAY => self.goaway = true,
+            HEADERS => self.on_headers(frame)?,
+            DATA => self.on_data(frame)?,
+            CONTINUATION => bail!("CONTINUATION outside a header block"),
+            // PRIORITY, acks, unknown extension frames
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_headers(&mut self, frame: Frame) -> Result<()> {
+        let Frame { flags, stream, mut payload, .. } = frame;
+        strip_padding(flags, &mut payload)?;
+        if flags & PRIORITY != 0 {
+            if payload.len() < 5 {
+                bail!("short HEADERS priority");
+            }
+            payload.drain(..5);
+        }
+        let mut end_headers = flags & END_HEADERS != 0;
+        while !end_headers {
+            let next = self.read_frame()?.context("connection closed inside a header block")?;
+            if next.kind != CONTINUATION || next.stream != stream {
+                bail!("expected CONTINUATION on stream {}", stream);
+            }
+            if payload.len() + next.payload.len() > MAX_HEADER_BLOCK {
+                bail!("header block over {} bytes", MAX_HEADER_BLOCK);
+            }
+            payload.extend_from_slice(&next.payload);
+            end_headers = next.flags & END_HEADERS != 0;
+        }
+        // Decoded even when unwanted: the dynamic table must stay in step with the client's
+        let headers = self.hpack.decode(&payload)?;
+        if stream % 2 == 0 {
+            bail!("HEADERS on stream {}", stream);
+        }
+        if !self.receiving.contains_key(&stream) {
+            if stream <= self.last_stream {
+                // Trailers or stray headers for a stream we are done with
+                return Ok(());
+            }
+            self.last_stream = stream;
+            self.receiving.insert(stream, Request { headers, ..Default::default() });
+            self.windows.insert(stream, self.peer_initial_window);
+        }
+        if flags & END_STREAM != 0
+            && let Some(request) = self.receiving.remove(&stream)
+        {
+            self.ready.push_back((stream, request));
+        }
+        Ok(())
+    }
+
+    fn on_data(&mut self, frame: Frame) -> Result<()> {
+        let Frame { flags, stream, mut payload, .. } = frame;
+        // Flow-control credit goes straight back; request size is capped below instead
+        let credit = (payload.len() as u32).to_be_bytes();
+        if !payload.is_empty() {
+            self.write_frame(WINDOW_UPDATE, 0, 0, &credit)?;
+        }
+        strip_padding(flags, &mut payload)?;
+        let Some(request) = self.receiving.get_mut(&stream) else { return Ok(()) };
+        if request.body.len() + payload.len() > MAX_MESSAGE + 5 {
+            request.too_large = true;
+            request.body.clear();
+        } else if !request.too_large {
+            request.body.extend_from_slice(&payload);
+        }
+        if flags & END_STREAM != 0 {
+            if let Some(request) = self.receiving.remove(&stream) {
+                self.ready.push_back((stream, request));
+            }
+        } else if credit != [0; 4] {
+            self.write_frame(WINDOW_UPDATE, 0, stream, &credit)?;
+        }
+        Ok(())
+    }
+
+    /// Send one length-prefixed message as DATA; false if the client reset the stream.
+    fn send_message(&mut self, stream: u32, message: &[u8]) -> Result<bool> {
+        let mut framed = Vec::with_capacity(5 + message.len());
+        framed.push(0);
+        framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        framed.extend_from_slice(message);
+        let mut rest = framed.as_slice();
+        while !rest.is_empty() {
+            let Some(&window) = self.windows.get(&stream) else { return Ok(false) };
+            let room = window.min(self.send_window).min(self.peer_max_frame as i64);
+            if room <= 0 {
+                // Out of credit: handle the client's frames until it grants more
+                let frame = self.read_frame()?.context("connection closed mid-stream")?;
+                self.on_frame(frame)?;
+                continue;
+            }
+            let n = (room as usize).min(rest.len());
+            self.write_frame(DATA, 0, stream, &rest[..n])?;
+            self.send_window -= n as i64;
+            if let Some(w) = self.windows.get_mut(&stream) {
+                *w -= n as i64;
+            }
+            rest = &rest[n..];
+        }
+        Ok(true)
+    }
+
+    fn send_all(&mut self, stream: u32, messages: impl Iterator<Item = Vec<u8>>) -> Result<bool> {
+        for message in messages {
+            if !self.send_message(stream, &message)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Trailers (or, with `headers`, a trailers-only response) ending the call.
+    fn send_status(&mut self, stream: u32, status: &Status, headers: bool) -> Result<()> {
+        let (code, message) = (status.0.to_string(), percent_encode(&status.1));
+        let mut fields = Vec::new();
+        if headers {
+            fields.extend([(":status", "200"), ("content-type", "application/grpc")]);
+        }
+        fields.push(("grpc-status", code.as_str()));
+        if !message.is_empty() {
+            fields.push(("grpc-message", message.as_str()));
+        }
+        self.write_frame(HEADERS, END_HEADERS | END_STREAM, stream, &encode_headers(&fields))
+    }
+
+    fn respond(&mut self, stream: u32, request: Request) -> Result<()> {
+        let header = |name: &str| request.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        let path = header(":path").unwrap_or("").to_string();
+        let grpc = header("content-type").is_some_and(|c| c.starts_with("application/grpc"));
+        if header(":method") != Some("POST") || !grpc {
+            let status = if grpc { "405" } else { "415" };
+            self.write_frame(HEADERS, END_HEADERS | END_STREAM, stream, &encode_headers(&[(":status", status)]))?;
+            self.windows.remove(&stream);
+            return Ok(());
+        }
+        log_debug!("grpc: stream {} {}", stream, path);
+
+        let index = self.shared.read().map(|g| Arc::clone(&g)).map_err(|_| anyhow::anyhow!("index lock poisoned"))?;
+        let body = &request.body;
+        let declared = body.get(1..5).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize);
+        let reply = if request.too_large {
+            Err(Status(RESOURCE_EXHAUSTED, format!("request over {} bytes", MAX_MESSAGE)))
+        } else if declared != Some(body.len().saturating_sub(5)) {
+            Err(Status(INTERNAL, "request is not one length-prefixed message".to_string()))
+        } else if body[0] != 0 {
+            Err(Status(UNIMPLEMENTED, "compressed requests are not supported".to_string()))
+        } else {
+            answer(&index, self.snapshots.as_deref(), &path, &body[5..])
+        };
+
+        let done = match reply {
+            Err(status) => {
+                self.send_status(stream, &status, true)?;
+                false
+            }
+            Ok(reply) => {
+                let block = encode_headers(&[(":status", "200"), ("content-type", "application/grpc")]);
+                self.write_frame(HEADERS, END_HEADERS, stream, &block)?;
+                match reply {
+                    Reply::One(message) => self.send_message(stream, &message)?,
+                    Reply::Stream(serve::Selected::Items(items)) => {
+                        self.send_all(stream, items.into_iter().map(item_message))?
+                    }
+                    Reply::Stream(serve::Selected::Values(values)) => {
+                        let items = values.into_iter().filter_map(|v| serde_json::from_value::<CanonicalItem>(v).ok());
+                        self.send_all(stream, items.map(|item| item_message(&item)))?
+                    }
+                }
+            }
+        };
+        // A stream the client reset gets nothing more
+        if done {
+            self.send_status(stream, &Status(OK, String::new()), false)?;
+        }
+        self.windows.remove(&stream);
+        Ok(())
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let mut preface = [0u8; 24];
+        self.stream.read_exact(&mut preface).context("Failed to read the connection preface")?;
+        if preface != PREFACE {
+            bail!("not an HTTP/2 prior-knowledge client (gRPC here is plaintext h2c)");
+        }
+        self.write_frame(SETTINGS, 0, 0, &[])?;
+        loop {
+            while let Some((stream, request)) = self.ready.pop_front() {
+                self.respond(stream, request)?;
+            }
+            if self.goaway && self.receiving.is_empty() {
+                return Ok(());
+            }
+            let Some(frame) = self.read_frame()? else { return Ok(()) };
+            self.on_frame(frame)?;
+        }
+    }
+}
+
+fn connection(stream: TcpStream, shared: serve::Shared, snapshots: Option<Arc<Snapshots>>) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let mut conn = Conn {
+        stream,
+        shared,
+        snapshots,
+        hpack: Decoder::new(),
+        peer_max_frame: MAX_FRAME,
+        peer_initial_window: DEFAULT_WINDOW,
+        send_window: DEFAULT_WINDOW,
+        windows: HashMap::new(),
+        receiving: HashMap::new(),
+        ready: VecDeque::new(),
+        last_stream: 0,
+        goaway: false,
+    };
+    let result = conn.run();
+    if result.is_err() {
+        let mut payload = conn.last_stream.to_be_bytes().to_vec();
+        payload.extend_from_slice(&PROTOCOL_ERROR.to_be_bytes());
+        let _ = conn.write_frame(GOAWAY, 0, 0, &payload);
+    }
+    result
+}
+
+/// Answer gRPC on `listen` from background threads, out of serve's index;
+/// Diff compares it against runs of `store`.
+pub fn listen(listen: &str, shared: serve::Shared, store: Option<snapshot::Store>) -> Result<()> {
+    let listener = TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    log_ok!("serve: gRPC (bastion.v1.Codex) on {}", listener.local_addr()?);
+    let snapshots = store.map(|store| Arc::new(Snapshots { store, last: Mutex::new(None) }));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let (shared, snapshots) = (Arc::clone(&shared), snapshots.clone());
+            thread::spawn(move || {
+                if let Err(e) = connection(stream, shared, snapshots) {
+                    log_warn!("grpc: connection failed: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn pairs(headers: &[(String, String)]) -> Vec<(&str, &str)> {
+        headers.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect()
+    }
+
+    // RFC 7541 C.4: Huffman-coded requests sharing one dynamic table
+    #[test]
+    fn hpack_decodes_rfc_examples() {
+        let mut hpack = Decoder::new();
+        let first = hpack.decode(&hex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff")).unwrap();
+        let request = [(":method", "GET"), (":scheme", "http"), (":path", "/"), (":authority", "www.example.com")];
+        assert_eq!(pairs(&first), request);
+        let second = hpack.decode(&hex("8286 84be 5886 a8eb 1064 9cbf")).unwrap();
+        assert_eq!(pairs(&second)[3..], [(":authority", "www.example.com"), ("cache-control", "no-cache")]);
+        let third = hpack.decode(&hex("8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf")).unwrap();
+        assert_eq!(pairs(&third)[1..3], [(":scheme", "https"), (":path", "/index.html")]);
+        assert_eq!(pairs(&third)[4], ("custom-key", "custom-value"));
+        assert_eq!(hpack.table.len(), 3);
+        assert_eq!(hpack.size, 164);
+    }
+
+    #[test]
+    fn hpack_round_trips_our_headers() {
+        let long = "x".repeat(300); // length past the 7-bit prefix
+        let headers = [(":status", "200"), ("content-type", "application/grpc"), ("grpc-message", long.as_str())];
+        let block = encode_headers(&headers);
+        assert_eq!(block[0], 0x88);
+        assert_eq!(pairs(&Decoder::new().decode(&block).unwrap()), headers);
+
+        for value in [0, 14, 15, 127, 128, 1337, 1 << 20] {
+            for prefix in [4, 5, 7] {
+                let mut buf = Vec::new();
+                put_integer(&mut buf, value, prefix, 0);
+                let decoded = hpack_integer(&buf, &mut 0, prefix).unwrap();
+                assert_eq!(decoded, value, "{} with a {}-bit prefix", value, prefix);
+            }
+        }
+    }
+
+    #[test]
+    fn hpack_rejects_bad_blocks() {
+        let mut hpack = Decoder::new();
+        assert!(hpack.decode(&[0x80]).is_err()); // index 0
+        assert!(hpack.decode(&[0xbe]).is_err()); // empty dynamic table
+        assert!(hpack.decode(&[0x3f, 0xe2, 0x1f]).is_err()); // table size over 4096
+        assert!(hpack.decode(&[0x00, 0x05, b'a']).is_err()); // truncated string
+        assert!(hpack.decode(&[0x1f, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).is_err()); // integer overflow
+        assert!(huffman_decode(&[0x00]).is_err()); // padding of zeros
+        assert!(huffman_decode(&[0xff, 0xff, 0xff, 0xff]).is_err()); // EOS
+    }
+
+    #[test]
+    fn protobuf_round_trips_fields() {
+        let mut m = Pb::default();
+        m.uint(1, 0); // proto3 default: not written
+        m.uint(2, u64::MAX);
+        m.opt_double(3, Some(9.8));
+        m.string(4, "");
+        m.string(5, "CVE-2099-0001");
+        m.opt_bool(6, Some(false));
+        m.repeated(7, &["a".to_string(), "b".to_string()]);
+        let decoded = fields(&m.0).unwrap();
+        let numbers: Vec<u32> = decoded.iter().map(|(f, _)| *f).collect();
+        assert_eq!(numbers, [2, 3, 5, 6, 7, 7]);
+        assert_eq!(number(&decoded[0].1).unwrap(), u64::MAX);
+        assert!(matches!(decoded[1].1, Wire::Fixed));
+        assert_eq!(text(&decoded[2].1).unwrap(), "CVE-2099-0001");
+        assert_eq!(number(&decoded[3].1).unwrap(), 0);
+        assert_eq!(text(&decoded[5].1).unwrap(), "b");
+        assert!(text(&decoded[0].1).is_err());
+
+        assert!(fields(&[0x0a, 0x05, b'a']).is_err()); // length past the end
+        assert!(fields(&[0x08]).is_err()); // truncated varint
+        assert!(fields(&[0x0b]).is_err()); // group wire type
+        assert!(fields(&[0x09, 1, 2, 3]).is_err()); // short fixed64
+    }
+
+    #[test]
+    fn query_request_maps_to_a_selection() {
+        let mut m = Pb::default();
+        m.repeated(1, &["high".to_string(), "critical".to_string()]);
+        m.uint(2, 1);
+        m.string(3, "fortinet");
+        m.string(8, "cvss >= 9");
+        m.uint(10, 5);
+        let (sel, limit) = query_request(&m.0).unwrap();
+        assert_eq!(sel.severities, Some(vec!["high".to_string(), "critical".to_string()]));
+        assert_eq!((sel.kev, sel.vendor.as_deref(), limit), (Some(true), Some("fortinet"), 5));
+        assert!(sel.filter.is_some());
+        assert_eq!(query_request(&[]).unwrap().1, usize::MAX);
+
+        let mut bad = Pb::default();
+        bad.string(8, "cvss >=");
+        assert!(query_request(&bad.0).is_err());
+    }
+
+    fn item(id: &str, cvss: f64, kev: bool) -> CanonicalItem {
+        CanonicalItem {
+            id: id.to_string(),
+            sources: vec!["nvd".to_string()],
+            cvss: Some(cvss),
+            severity_bucket: "critical".to_string(),
+            kev,
+            short_desc: format!("Synthetic {}", id),
+            content_hash: format!("hash-{}", id),
+            ..Default::default()
+        }
+    }
+
+    fn json_field(message: &[u8]) -> CanonicalItem {
+        let json = fields(message).unwrap().into_iter().find(|(f, _)| *f == 100).unwrap().1;
+        serde_json::from_str(&text(&json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn item_message_carries_the_item() {
+        let it = item("CVE-2099-0001", 9.8, true);
+        let message = item_message(&it);
+        let decoded = fields(&message).unwrap();
+        let field = |n: u32| decoded.iter().find(|(f, _)| *f == n).map(|(_, v)| v);
+        assert_eq!(text(field(1).unwrap()).unwrap(), "CVE-2099-0001");
+        assert_eq!(number(field(7).unwrap()).unwrap(), 1);
+        assert!(field(3).is_none()); // no published date
+        assert_eq!(json_field(&message).content_hash, "hash-CVE-2099-0001");
+
+        let prev = delta::from_items("test".to_string(), Some("2099-01-01T00:00:00Z".to_string()), &[
+            item("CVE-2099-0001", 7.5, false),
+            item("CVE-2099-0002", 5.0, false),
+        ])
+        .unwrap();
+        let mut now = item("CVE-2099-0001", 9.8, true);
+        now.content_hash = "hash-changed".to_string();
+        let diff = diff_message(&[now, item("CVE-2099-0003", 8.0, false)], &prev);
+        let decoded = fields(&diff).unwrap();
+        let counts = decoded.iter().filter(|(f, _)| (2..=4).contains(f)).map(|(_, v)| number(v).unwrap());
+        let counts: Vec<u64> = counts.collect();
+        assert_eq!(counts, [1, 1, 1]);
+        assert_eq!(text(&decoded[0].1).unwrap(), "2099-01-01T00:00:00Z");
+        let kinds: Vec<(u64, String)> = decoded
+            .iter()
+            .filter(|(f, _)| *f == 5)
+            .map(|(_, v)| {
+                let Wire::Bytes(b) = v else { panic!("Change is a message") };
+                let change = fields(b).unwrap();
+                (number(&change[0].1).unwrap(), text(&change[1].1).unwrap())
+            })
+            .collect();
+        assert!(kinds.contains(&(1, "CVE-2099-0003".to_string())));
+        assert!(kinds.contains(&(2, "CVE-2099-0001".to_string())));
+        assert!(kinds.contains(&(3, "CVE-2099-0002".to_string())));
+    }
+
+    /* ---- Calls over a loopback connection ---- */
+
+    struct Client {
+        stream: TcpStream,
+        hpack: Decoder,
+    }
+
+    /// What came back on one stream: response headers, DATA bytes, trailers.
+    #[derive(Default)]
+    struct Response {
+        headers: Vec<(String, String)>,
+        data: Vec<u8>,
+        trailers: Vec<(String, String)>,
+    }
+
+    impl Response {
+        fn header(&self, name: &str) -> Option<&str> {
+            let all = self.headers.iter().chain(&self.trailers);
+            all.filter(|(n, _)| n == name).map(|(_, v)| v.as_str()).next_back()
+        }
+
+        /// The length-prefixed messages in the DATA frames.
+        fn messages(&self) -> Vec<Vec<u8>> {
+            let (mut out, mut rest) = (Vec::new(), self.data.as_slice());
+            while !rest.is_empty() {
+                assert_eq!(rest[0], 0, "uncompressed");
+                let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+                out.push(rest[5..5 + len].to_vec());
+                rest = &rest[5 + len..];
+            }
+            out
+        }
+    }
+
+    impl Client {
+        fn start(items: Vec<CanonicalItem>) -> Client {
+            let shared: serve::Shared = Arc::new(RwLock::new(Arc::new(serve::Index::new(items, None).unwrap())));
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let _ = connection(stream, shared, None);
+            });
+            let mut client = Client { stream: TcpStream::connect(addr).unwrap(), hpack: Decoder::new() };
+            client.stream.set_nodelay(true).unwrap();
+            client.stream.write_all(PREFACE).unwrap();
+            // INITIAL_WINDOW_SIZE 256: replies must wait on our WINDOW_UPDATEs
+            client.frame(SETTINGS, 0, 0, &[0, 4, 0, 0, 1, 0]);
+            let settings = client.read();
+            assert_eq!((settings.kind, settings.flags), (SETTINGS, 0));
+            let ack = client.read();
+            assert_eq!((ack.kind, ack.flags), (SETTINGS, ACK));
+            client
+        }
+
+        fn frame(&mut self, kind: u8, flags: u8, stream: u32, payload: &[u8]) {
+            let mut buf = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+            buf.extend([kind, flags]);
+            buf.extend(stream.to_be_bytes());
+            buf.extend(payload);
+            self.stream.write_all(&buf).unwrap();
+        }
+
+        fn read(&mut self) -> Frame {
+            let mut head = [0u8; 9];
+            self.stream.read_exact(&mut head).unwrap();
+            let mut payload = vec![0; u32::from_be_bytes([0, head[0], head[1], head[2]]) as usize];
+            self.stream.read_exact(&mut payload).unwrap();
+            let stream = u32::from_be_bytes([head[5], head[6], head[7], head[8]]);
+            Frame { kind: head[3], flags: head[4], stream, payload }
+        }
+
+        fn request_headers(path: &str) -> Vec<u8> {
+            let headers =
+                [(":method", "POST"), (":scheme", "http"), (":path", path), ("content-type", "application/grpc")];
+            let mut block = Vec::new();
+            for (name, value) in headers {
+                // Incremental indexing, so later calls exercise the dynamic table
+                block.push(0x40);
+                for s in [name, value] {
+                    put_integer(&mut block, s.len(), 7, 0x00);
+                    block.extend_from_slice(s.as_bytes());
+                }
+            }
+            block
+        }
+
+        /// Send `body` as DATA frames of at most `chunk` bytes, then read the answer,
+        /// granting window as DATA arrives.
+        fn call(&mut self, stream: u32, block: &[u8], body: &[u8], chunk: usize) -> Response {
+            let (head, rest) = block.split_at(block.len() / 2);
+            self.frame(HEADERS, if body.is_empty() { END_STREAM } else { 0 }, stream, head);
+            self.frame(CONTINUATION, END_HEADERS, stream, rest);
+            let chunks: Vec<&[u8]> = body.chunks(chunk).collect();
+            for (i, part) in chunks.iter().enumerate() {
+                self.frame(DATA, if i + 1 == chunks.len() { END_STREAM } else { 0 }, stream, part);
+            }
+            let mut response = Response::default();
+            loop {
+                let frame = self.read();
+                match frame.kind {
+                    WINDOW_UPDATE | SETTINGS => continue,
+                    HEADERS => {
+                        assert_eq!(frame.stream, stream);
+                        let headers = self.hpack.decode(&frame.payload).unwrap();
+                        if response.headers.is_empty() {
+                            response.headers = headers;
+                        } else {
+                            response.trailers = headers;
+                        }
+                        if frame.flags & END_STREAM != 0 {
+                            return response;
+                        }
+                    }
+                    DATA => {
+                        assert!(frame.payload.len() <= 256, "DATA past the stream window");
+                        response.data.extend(&frame.payload);
+                        let credit = (frame.payload.len() as u32).to_be_bytes();
+                        self.frame(WINDOW_UPDATE, 0, 0, &credit);
+                        self.frame(WINDOW_UPDATE, 0, stream, &credit);
+                    }
+                    kind => panic!("unexpected frame type {}", kind),
+                }
+            }
+        }
+    }
+
+    fn framed(message: &[u8]) -> Vec<u8> {
+        let mut out = vec![0];
+        out.extend((message.len() as u32).to_be_bytes());
+        out.extend(message);
+        out
+    }
+
+    #[test]
+    fn calls_round_trip_over_http2() {
+        let mut client = Client::start(vec![item("CVE-2099-0001", 9.8, true), item("CVE-2099-0002", 5.0, false)]);
+
+        // One message split over DATA frames, length prefix included
+        let mut get = Pb::default();
+        get.string(1, "cve-2099-0001");
+        let block = Client::request_headers("/bastion.v1.Codex/GetItem");
+        let response = client.call(1, &block, &framed(&get.0), 3);
+        assert_eq!(response.header(":status"), Some("200"));
+        assert_eq!(response.header("content-type"), Some("application/grpc"));
+        assert_eq!(response.header("grpc-status"), Some("0"));
+        let messages = response.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(json_field(&messages[0]).id, "CVE-2099-0001");
+
+        let block = Client::request_headers("/bastion.v1.Codex/QueryItems");
+        let response = client.call(3, &block, &framed(&[]), 64);
+        assert_eq!(response.header("grpc-status"), Some("0"));
+        let ids: Vec<String> = response.messages().iter().map(|m| json_field(m).id).collect();
+        assert_eq!(ids, ["CVE-2099-0001", "CVE-2099-0002"]);
+
+        // The first call's headers by dynamic table index: POST, http, GetItem, application/grpc
+        let indexed = [0xc1, 0xc0, 0xc3, 0xbe];
+        let mut missing = Pb::default();
+        missing.string(1, "CVE-2099-9999");
+        let response = client.call(5, &indexed, &framed(&missing.0), 64);
+        assert_eq!(response.header(":status"), Some("200"));
+        assert_eq!(response.header("grpc-status"), Some("5"));
+        assert_eq!(response.header("grpc-message"), Some("No item CVE-2099-9999"));
+        assert!(response.trailers.is_empty() && response.data.is_empty()); // trailers-only
+
+        let response = client.call(7, &indexed, &framed(&get.0)[..6], 64);
+        assert_eq!(response.header("grpc-status"), Some("13"));
+        let block = Client::request_headers("/bastion.v1.Codex/Watch");
+        assert_eq!(client.call(9, &block, &framed(&[]), 64).header("grpc-status"), Some("12"));
+        // GET from the static table
+        assert_eq!(client.call(11, &[0x82, 0xc0, 0xc3, 0xbe], &[], 64).header(":status"), Some("405"));
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+use crate::{severity, CanonicalItem};
+
+/* -------------------- Static HTML report -------------------- */
+/*
+report --format html renders items into one self-contained page (inline CSS
+and a few lines of JS, no external requests) that can be mailed or dropped on
+a file share:
+
+  - summary counts per severity bucket and KEV
+  - one table row per item: ID (linked to NVD), severity, CVSS, KEV badge with
+    due date, vendor, product, title or description, dates, tags
+  - click a column header to sort, type in the box to filter rows
+
+Severity colours follow the active policy's order: the most severe bucket is
+red, then orange, yellow, green; further buckets and unscored are grey.
+Rows arrive in canonical ID order.
+*/
+
+const STYLE: &str = "\
+body{font-family:system-ui,-apple-system,Segoe UI,sans-serif;margin:2rem;color:#1f2328}
+h1{font-size:1.5rem;margin-bottom:.25rem}
+.meta{color:#59636e;font-size:.85rem;margin-bottom:1rem}
+.summary span{display:inline-block;margin:0 .5rem .5rem 0}
+#q{padding:.35rem .5rem;width:20rem;margin-bottom:.75rem}
+table{border-collapse:collapse;width:100%;font-size:.85rem}
+th,td{border-bottom:1px solid #d1d9e0;padding:.35rem .5rem;text-align:left;vertical-align:top}
+th{background:#f6f8fa;cursor:pointer;white-space:nowrap;position:sticky;top:0}
+th.asc::after{content:' \\25B2'}th.desc::after{content:' \\25BC'}
+td.num{text-align:right;font-variant-numeric:tabular-nums}
+.badge{display:inline-block;border-radius:.75rem;padding:.05rem .5rem;font-size:.75rem;font-weight:600;white-space:nowrap}
+.sev-0{background:#d1242f;color:#fff}.sev-1{background:#e16f24;color:#fff}
+.sev-2{background:#d4a72c;color:#1f2328}.sev-3{background:#2da44e;color:#fff}
+.sev-x{background:#afb8c1;color:#1f2328}
+.kev{background:#6639ba;color:#fff}
+.due{color:#59636e;font-size:.75rem}
+.tag{background:#ddf4ff;color:#0969da;margin-right:.25rem}
+";
+
+// Sort by data-sort (else the cell text); numeric when both sides parse
+const SCRIPT: &str = "\
+document.querySelectorAll('th').forEach((th,col)=>th.addEventListener('click',()=>{
+  const body=document.querySelector('tbody'),rows=[...body.rows];
+  const dir=th.classList.contains('asc')?-1:1;
+  document.querySelectorAll('th').forEach(h=>h.classList.remove('asc','desc'));
+  th.classList.add(dir>0?'asc':'desc');
+  const key=r=>{const c=r.cells[col];return c.dataset.sort??c.textContent.trim()};
+  rows.sort((a,b)=>{const x=key(a),y=key(b),nx=parseFloat(x),ny=parseFloat(y);
+    return dir*(!isNaN(nx)&&!isNaN(ny)?nx-ny:x.localeCompare(y,undefined,{numeric:true}))});
+  rows.forEach(r=>body.appendChild(r));
+}));
+document.getElementById('q').addEventListener('input',e=>{
+  const q=e.target.value.toLowerCase();
+  document.querySelectorAll('tbody tr').forEach(r=>r.hidden=q&&!r.textContent.toLowerCase().includes(q));
+});
+";
+
+pub struct Page<'a> {
+    pub title: &'a str,
+    pub source: &'a str,      // file(s) the items came from, shown under the title
+    pub change: Option<&'a BTreeMap<String, &'static str>>, // id -> added|changed, for digests
+}
+
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn severity_class(bucket: &str) -> String {
+    match severity::names().iter().position(|n| *n == bucket) {
+        Some(i) if i < 4 && i < severity::policy().buckets.len() => format!("sev-{}", i),
+        _ => "sev-x".to_string(),
+    }
+}
+
+fn row(item: &CanonicalItem, change: Option<&str>) -> String {
+    let mut cells = Vec::new();
+    if let Some(change) = change {
+        cells.push(format!("<td>{}</td>", change));
+    }
+    cells.push(format!(
+        "<td><a href=\"https://nvd.nist.gov/vuln/detail/{}\">{}</a></td>",
+        escape(&item.id),
+        escape(&item.id)
+    ));
+    cells.push(format!(
+        "<td data-sort=\"{}\"><span class=\"badge {}\">{}</span></td>",
+        severity::rank(&item.severity_bucket),
+        severity_class(&item.severity_bucket),
+        escape(&item.severity_bucket)
+    ));
+    cells.push(match item.cvss {
+        Some(c) => format!("<td class=\"num\">{}</td>", c),
+        None => "<td class=\"num\" data-sort=\"-1\">n/a</td>".to_string(),
+    });
+    cells.push(if item.kev {
+        let due = item
+            .kev_due_date
+            .as_deref()
+            .map(|d| format!(" <span class=\"due\">due {}</span>", escape(d)))
+            .unwrap_or_default();
+        // Non-KEV rows sort first, KEV rows by due date
+        format!(
+            "<td data-sort=\"kev {}\"><span class=\"badge kev\">KEV</span>{}</td>",
+            escape(item.kev_due_date.as_deref().unwrap_or_default()),
+            due
+        )
+    } else {
+        "<td data-sort=\"\"></td>".to_string()
+    });
+    cells.push(format!("<td>{}</td>", escape(item.vendor.as_deref().unwrap_or_default())));
+    cells.push(format!("<td>{}</td>", escape(item.product.as_deref().unwrap_or_default())));
+    let text = item.title.as_deref().unwrap_or(&item.short_desc);
+    cells.push(format!("<td title=\"{}\">{}</td>", escape(&item.short_desc), escape(text)));
+    let day = |d: &Option<String>| escape(d.as_deref().map(|d| d.get(..10).unwrap_or(d)).unwrap_or_default());
+    cells.push(format!("<td>{}</td>", day(&item.published)));
+    cells.push(format!("<td>{}</td>", day(&item.last_modified)));
+    let tags: String =
+        item.tags.iter().map(|t| format!("<span class=\"badge tag\">{}</span>", escape(t))).collect();
+    cells.push(format!("<td>{}</td>", tags));
+    format!("<tr>{}</tr>", cells.concat())
+}
+
+pub fn render(items: &[&CanonicalItem], page: &Page) -> String {
+    let mut by_bucket: BTreeMap<u8, (&str, usize)> = BTreeMap::new();
+    for item in items {
+        by_bucket.entry(severity::rank(&item.severity_bucket)).or_insert((&item.severity_bucket, 0)).1 += 1;
+    }
+    let mut summary: Vec<String> = by_bucket
+        .values()
+        .rev()
+        .map(|(bucket, n)| format!("<span class=\"badge {}\">{} {}</span>", severity_class(bucket), n, escape(bucket)))
+        .collect();
+    let kev = items.iter().filter(|i| i.kev).count();
+    summary.push(format!("<span class=\"badge kev\">{} KEV</span>", kev));
+
+    let mut headers = Vec::new();
+    if page.change.is_some() {
+        headers.push("Change");
+    }
+    headers.extend(["ID", "Severity", "CVSS", "KEV", "Vendor", "Product", "Title", "Published", "Modified", "Tags"]);
+    let head: String = headers.iter().map(|h| format!("<th>{}</th>", h)).collect();
+    let rows: Vec<String> = items
+        .iter()
+        .map(|i| row(i, page.change.map(|c| c.get(&i.id).copied().unwrap_or(""))))
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n\
+         <div class=\"meta\">{count} items from {source} · generated {generated}</div>\n\
+         <div class=\"summary\">{summary}</div>\n\
+         <input id=\"q\" type=\"search\" placeholder=\"Filter rows\" aria-label=\"Filter rows\">\n\
+         <table>\n<thead><tr>{head}</tr></thead>\n<tbody>\n{rows}\n</tbody>\n</table>\n\
+         <script>\n{script}</script>\n</body>\n</html>\n",
+        title = escape(page.title),
+        style = STYLE,
+        count = items.len(),
+        source = escape(page.source),
+        generated = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        summary = summary.join(""),
+        head = head,
+        rows = rows.join("\n"),
+        script = SCRIPT,
+    )
+}
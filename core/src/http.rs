@@ -0,0 +1,289 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::log_warn;
+
+/* -------------------- HTTP client layer -------------------- */
+/*
+Everything that talks HTTP (webhooks in notify.rs, --check-refs, the
+elasticsearch export, http(s):// inputs) goes through send() here, so egress
+settings are made once:
+
+  --proxy URL          proxy for every request; otherwise curl's own
+                       HTTPS_PROXY / HTTP_PROXY / ALL_PROXY / NO_PROXY apply
+  --cacert FILE        PEM bundle for a TLS-inspecting corporate proxy or a
+                       private CA (curl also reads CURL_CA_BUNDLE)
+  --http-retries N     transient failures are retried N times (default 3),
+  --http-retry-delay-ms  waiting 1s, 2s, 4s, ... (plus jitter, at most 60s)
+
+Transient means no answer (DNS, connect, TLS handshake, timeout, reset) or
+HTTP 408, 429, 500, 502, 503, 504; a 4xx answer is final. Proxy URL and
+credentials go in the curl config on stdin, so they don't show in ps.
+
+http(s):// inputs (--nvd https://.../nvdcve-2.0-2024.json.gz) are downloaded
+before parsing. The download lands in <file>.partial and is renamed once
+complete; an interrupted attempt or run resumes it with a range request
+instead of starting over (a server that ignores ranges gets a fresh download).
+Resuming is conditional on the ETag / Last-Modified saved with the partial, so
+a file regenerated upstream since (NVD's feeds are, daily) is fetched anew
+rather than spliced onto yesterday's bytes.
+
+Requests shell out to curl; BASTION_CURL overrides the binary. The
+orchestrator's Python fetchers (orchestrator/fetchers/http.py) follow the same
+rules, and ti_run.py takes the same four flags and passes them on to core.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub proxy: Option<String>,
+    pub cacert: Option<PathBuf>,
+    pub retries: u32,
+    pub retry_delay_ms: u64,
+}
+
+pub const DEFAULT_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+const MAX_DELAY_MS: u64 = 60_000;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { proxy: None, cacert: None, retries: DEFAULT_RETRIES, retry_delay_ms: DEFAULT_RETRY_DELAY_MS }
+    }
+}
+
+// Set once from the command line, before any request
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+pub fn configure(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+pub fn curl() -> String {
+    std::env::var("BASTION_CURL").unwrap_or_else(|_| "curl".to_string())
+}
+
+pub fn is_url(path: &Path) -> bool {
+    let s = path.to_string_lossy();
+    s.starts_with("https://") || s.starts_with("http://")
+}
+
+/// curl config syntax: a quoted string with backslash escapes (\n keeps a body on one line).
+pub fn config_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub code: i32,   // curl's exit code
+    pub status: u16, // final HTTP status, 0 without a response
+    pub body: Vec<u8>,
+    pub stderr: String,
+}
+
+impl Response {
+    pub fn ok(&self) -> bool {
+        self.code == 0 && (200..300).contains(&self.status)
+    }
+
+    pub fn transient(&self) -> bool {
+        // resolve, connect, partial file, timeout, TLS connect, empty reply, send / receive errors
+        matches!(self.code, 5 | 6 | 7 | 18 | 28 | 35 | 52 | 55 | 56)
+            || matches!(self.status, 408 | 429 | 500 | 502..=504)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).trim().to_string()
+    }
+
+    /// stderr and body joined, for error messages.
+    pub fn detail(&self) -> String {
+        let mut parts = vec![self.stderr.trim().to_string(), self.text()];
+        if self.status != 0 {
+            parts.insert(0, format!("HTTP {}", self.status));
+        }
+        parts.retain(|p| !p.is_empty());
+        parts.join(": ")
+    }
+}
+
+/// One curl run with the shared settings: `config` lines (url = ..., header = ...) go on
+/// stdin after the proxy / CA lines, `args` on the command line. Err only when curl can't run.
+pub fn send(args: &[&str], config: &str) -> io::Result<Response> {
+    let s = settings();
+    let mut full = String::new();
+    if let Some(proxy) = &s.proxy {
+        full.push_str(&format!("proxy = {}\n", config_quote(proxy)));
+    }
+    if let Some(cacert) = &s.cacert {
+        full.push_str(&format!("cacert = {}\n", config_quote(&cacert.to_string_lossy())));
+    }
+    full.push_str(config);
+
+    let mut child = Command::new(curl())
+        .args(["-sS", "-w", "\n%{http_code}", "-K", "-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(full.as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    let mut body = out.stdout;
+    let split = body.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+    let status = String::from_utf8_lossy(&body[split..]).trim().parse().unwrap_or(0);
+    body.truncate(split);
+    let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+    Ok(Response { code: out.status.code().unwrap_or(-1), status, body, stderr })
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let base = settings().retry_delay_ms.saturating_mul(1 << attempt.min(16)).min(MAX_DELAY_MS);
+    // Up to a quarter more, so parallel runs don't retry in lockstep
+    let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+    Duration::from_millis(base + u64::from(nanos) % (base / 4 + 1))
+}
+
+/// send(), retrying transient failures with backoff; `what` names the request in log lines.
+pub fn send_retrying(what: &str, args: &[&str], config: &str) -> Result<Response> {
+    let retries = settings().retries;
+    let mut attempt = 0;
+    loop {
+        let response = send(args, config).with_context(|| format!("Failed to run {} (set BASTION_CURL)", curl()))?;
+        if !response.transient() || attempt >= retries {
+            return Ok(response);
+        }
+        let wait = backoff(attempt);
+        attempt += 1;
+        log_warn!("{}: {}; retry {}/{} in {:.1}s", what, response.detail(), attempt, retries, wait.as_secs_f64());
+        thread::sleep(wait);
+    }
+}
+
+fn partial_path(dest: &Path, suffix: &str) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    dest.with_file_name(format!("{}{}", name, suffix))
+}
+
+/// What an If-Range can carry from the last response of a `curl -D` dump (redirects
+/// come first): a strong ETag, else Last-Modified.
+fn validator(headers: &str) -> Option<String> {
+    let (mut etag, mut modified) = (None, None);
+    for line in headers.lines() {
+        if line.starts_with("HTTP/") {
+            (etag, modified) = (None, None);
+        } else if let Some((name, value)) = line.split_once(':') {
+            let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+            match name.trim().to_ascii_lowercase().as_str() {
+                "etag" => etag = value.filter(|v| !v.starts_with("W/")),
+                "last-modified" => modified = value,
+                _ => {}
+            }
+        }
+    }
+    etag.or(modified)
+}
+
+/// Download `url` to `dest`, resuming what an earlier attempt left in `dest`.partial.
+/// The partial's validator is kept in `dest`.partial.validator: a resume sends it as
+/// If-Range and starts over when the file changed since; a partial without one is
+/// only resumed within this call.
+pub fn download(url: &str, dest: &Path) -> Result<()> {
+    let (partial, validator_path) = (partial_path(dest, ".partial"), partial_path(dest, ".partial.validator"));
+    let headers_path = partial_path(dest, ".partial.headers");
+    let partial_arg = partial.to_string_lossy().to_string();
+    let headers_arg = headers_path.to_string_lossy().to_string();
+    let discard = || {
+        let _ = fs::remove_file(&partial);
+        let _ = fs::remove_file(&validator_path);
+    };
+    let mut stored = fs::read_to_string(&validator_path).ok().filter(|v| !v.trim().is_empty());
+    if stored.is_none() {
+        // From an older run, or a server that sent no validator: nothing to check it against
+        discard();
+    }
+    let retries = settings().retries;
+    let mut attempt = 0;
+    loop {
+        let resume = partial.exists();
+        let mut config = format!("url = {}\n", config_quote(url));
+        if resume && let Some(v) = &stored {
+            config.push_str(&format!("header = {}\n", config_quote(&format!("If-Range: {}", v.trim()))));
+        }
+        let mut args = vec!["-L", "--fail", "-o", &partial_arg, "-D", &headers_arg];
+        if resume {
+            args.extend(["-C", "-"]);
+        }
+        let response = send(&args, &config).with_context(|| format!("Failed to run {} (set BASTION_CURL)", curl()))?;
+        let current = fs::read_to_string(&headers_path).ok().and_then(|h| validator(&h));
+        let _ = fs::remove_file(&headers_path);
+        // A server ignoring If-Range answers 206 for a changed file: the partial is a splice
+        if resume && response.status == 206 && stored.is_some() && current.is_some() && current != stored {
+            log_warn!("download {}: changed since the partial was saved; starting over", url);
+            discard();
+            stored = None;
+            continue;
+        }
+        if response.ok() {
+            fs::rename(&partial, dest).with_context(|| format!("Failed to move download to {}", dest.display()))?;
+            let _ = fs::remove_file(&validator_path);
+            return Ok(());
+        }
+        // 33: no range support, or If-Range told the server the file changed;
+        // 416: the partial doesn't fit the file (any more). Start over
+        if resume && (response.code == 33 || response.status == 416) {
+            discard();
+            stored = None;
+            continue;
+        }
+        if !resume && let Some(v) = &current {
+            fs::write(&validator_path, v).with_context(|| format!("Failed to write {}", validator_path.display()))?;
+            stored = current.clone();
+        }
+        if !response.transient() || attempt >= retries {
+            bail!("download failed for {}: {}", url, response.detail());
+        }
+        let wait = backoff(attempt);
+        attempt += 1;
+        let have = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+        let resuming = if have > 0 { format!("resuming at {} bytes, ", have) } else { String::new() };
+        log_warn!(
+            "download {}: {}; {}retry {}/{} in {:.1}s",
+            url,
+            response.detail(),
+            resuming,
+            attempt,
+            retries,
+            wait.as_secs_f64()
+        );
+        thread::sleep(wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validator_comes_from_the_last_response() {
+        let redirected = "HTTP/1.1 302 Found\r\nETag: \"old\"\r\nLocation: /b\r\n\r\n\
+                          HTTP/1.1 200 OK\r\nLast-Modified: Tue, 13 Oct 2026 08:00:00 GMT\r\netag: \"abc\"\r\n\r\n";
+        assert_eq!(validator(redirected).as_deref(), Some("\"abc\""));
+        let weak = "HTTP/2 200\r\netag: W/\"abc\"\r\nlast-modified: Tue, 13 Oct 2026 08:00:00 GMT\r\n\r\n";
+        assert_eq!(validator(weak).as_deref(), Some("Tue, 13 Oct 2026 08:00:00 GMT"));
+        assert_eq!(validator("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/* -------------------- Input decompression -------------------- */
+/*
+NVD ships .json.gz and mirrors often recompress with zstd. Inputs are sniffed by
+magic bytes (not extension) and decompressed on the fly. sftp://, object
+storage (s3://, ...) and http(s):// inputs are fetched to a temp file first (see
+remote.rs; http.rs resumes interrupted downloads). zstd and remote inputs need
+the io feature; gzip is pure Rust and always there.
+*/
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[cfg(feature = "io")]
+pub use crate::remote::is_remote;
+
+/// Without the io feature there are no transfers, so every path is local.
+#[cfg(not(feature = "io"))]
+pub fn is_remote(_path: &Path) -> bool {
+    false
+}
+
+// Trained dictionary for archived snapshots (see archive.rs), set once from --zstd-dict
+static ZSTD_DICT: OnceLock<Vec<u8>> = OnceLock::new();
+
+pub fn set_zstd_dictionary(path: &Path) -> Result<()> {
+    let dict = std::fs::read(path).with_context(|| format!("Failed to read zstd dictionary: {}", path.display()))?;
+    let _ = ZSTD_DICT.set(dict);
+    Ok(())
+}
+
+/// Open `path` for reading, transparently decompressing gzip or zstd.
+pub fn open_input(path: &Path) -> Result<Box<dyn Read>> {
+    #[cfg(feature = "io")]
+    let fetched;
+    #[cfg(feature = "io")]
+    let path = if is_remote(path) {
+        fetched = crate::remote::fetch(path)?;
+        fetched.as_path()
+    } else {
+        path
+    };
+    let file = File::open(path)
+        .with_context(|| crate::errors::Failure::new("io", format!("Failed to open {}", path.display())).path(path))?;
+    decode(BufReader::with_capacity(1 << 20, file), path)
+}
+
+/// The same for an input already in memory (a browser upload, say); `name` is for messages.
+pub fn open_bytes<'a>(bytes: &'a [u8], name: &str) -> Result<Box<dyn Read + 'a>> {
+    decode(bytes, Path::new(name))
+}
+
+fn decode<'a>(mut reader: impl BufRead + 'a, path: &Path) -> Result<Box<dyn Read + 'a>> {
+    let head = reader
+        .fill_buf()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    if head.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))))
+    } else if head.starts_with(&ZSTD_MAGIC) {
+        zstd_decoder(reader, path)
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+#[cfg(feature = "io")]
+fn zstd_decoder<'a>(mut reader: impl BufRead + 'a, path: &Path) -> Result<Box<dyn Read + 'a>> {
+    // Frames record the dictionary they were compressed with; plain frames say 0
+    let dict_id = zstd::zstd_safe::get_dict_id_from_frame(reader.fill_buf()?);
+    let dec = match (dict_id, ZSTD_DICT.get()) {
+        (Some(_), Some(dict)) => zstd::stream::read::Decoder::with_dictionary(reader, dict),
+        (Some(id), None) => {
+            anyhow::bail!("{} was compressed with zstd dictionary {}; pass --zstd-dict", path.display(), id)
+        }
+        (None, _) => zstd::stream::read::Decoder::with_buffer(reader),
+    }
+    .with_context(|| format!("Failed to open zstd stream: {}", path.display()))?;
+    Ok(Box::new(BufReader::new(dec)))
+}
+
+#[cfg(not(feature = "io"))]
+fn zstd_decoder<'a>(_reader: impl BufRead + 'a, path: &Path) -> Result<Box<dyn Read + 'a>> {
+    anyhow::bail!("{} is zstd-compressed; this build has no zstd (the io feature)", path.display())
+}
+
+/// Read a whole (possibly compressed) input into memory.
+pub fn read_input(path: &Path) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open_input(path)?
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(bytes)
+}
+
+/// Expand glob patterns (e.g. "feeds/nvdcve-2.0-*.json.gz") for shells that pass
+/// them through quoted. Plain paths are kept as-is; a pattern matching nothing is an error.
+pub fn expand_globs(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for p in paths {
+        let s = p.to_string_lossy();
+        if !s.contains(['*', '?', '[']) || is_remote(p) {
+            out.push(p.clone());
+            continue;
+        }
+        let mut matched: Vec<_> = glob::glob(&s)
+            .with_context(|| format!("Invalid glob pattern: {}", s))?
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Failed to expand glob: {}", s))?;
+        if matched.is_empty() {
+            anyhow::bail!("Glob pattern matched no files: {}", s);
+        }
+        matched.sort();
+        out.extend(matched);
+    }
+    Ok(out)
+}
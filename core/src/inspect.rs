@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{codex, input, log_fail, log_ok};
+
+/* -------------------- Raw source inspection -------------------- */
+/*
+Validates raw feed files against schemas embedded in the binary (core/schemas/)
+and reports structural anomalies, so "why did parsing fail" can be answered
+without reading serde errors. The same machinery checks our canonical output
+against canonical_items.schema.json.
+*/
+
+const KEV_SCHEMA: &str = include_str!("../schemas/kev.schema.json");
+const NVD_SCHEMA: &str = include_str!("../schemas/nvd_cve_2.0.schema.json");
+/// Published schema for our own output (items.json), used by `validate`.
+pub const CANONICAL_SCHEMA: &str = include_str!("../schemas/canonical_items.schema.json");
+
+#[derive(Debug, Serialize)]
+pub struct Anomaly {
+    pub path: String,     // JSON pointer into the input
+    pub keyword: String,  // failing schema keyword (required, type, pattern, ...)
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InspectReport {
+    pub source: String,
+    pub file: String,
+    pub records: usize,
+    pub anomaly_count: usize,
+    pub by_location: BTreeMap<String, usize>, // "/vulnerabilities/*/dueDate" -> count
+    pub anomalies: Vec<Anomaly>,              // first --max-errors only
+}
+
+/// "/vulnerabilities/12/dueDate" -> "/vulnerabilities/*/dueDate"
+fn generic_path(path: &str) -> String {
+    path.split('/')
+        .map(|seg| if !seg.is_empty() && seg.chars().all(|c| c.is_ascii_digit()) { "*" } else { seg })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn inspect_file(source: &str, path: &Path, max_errors: usize) -> Result<InspectReport> {
+    let schema_text = match source {
+        "kev" => KEV_SCHEMA,
+        "nvd" => NVD_SCHEMA,
+        "canonical" => CANONICAL_SCHEMA,
+        other => return Err(anyhow!("No embedded schema for source: {}", other)),
+    };
+    let mut schema: serde_json::Value = serde_json::from_str(schema_text)?;
+    if source == "canonical" {
+        // The published enum is the standard scale; check against the active policy instead
+        schema["definitions"]["item"]["properties"]["severity_bucket"]["enum"] = crate::severity::names().into();
+    }
+    let validator = jsonschema::options()
+        .should_validate_formats(true)
+        .build(&schema)
+        .map_err(|e| anyhow!("Embedded {} schema is invalid: {}", source, e))?;
+
+    let bytes = input::read_input(path)?;
+    let instance: serde_json::Value = if source == "canonical" && codex::looks_like_ndjson(&bytes) {
+        // Validated as an array; anomaly indexes are then 0-based record numbers
+        serde_json::Value::Array(codex::parse_ndjson(&bytes, path)?)
+    } else {
+        serde_json::from_slice(&bytes).with_context(|| format!("{} is not valid JSON", path.display()))?
+    };
+
+    let records = match &instance {
+        serde_json::Value::Array(items) => items.len(),
+        other => other
+            .get("vulnerabilities")
+            .or_else(|| other.get("items"))
+            .and_then(|v| v.as_array())
+            .map_or(0, |a| a.len()),
+    };
+
+    let mut report = InspectReport {
+        source: source.to_string(),
+        file: path.display().to_string(),
+        records,
+        anomaly_count: 0,
+        by_location: BTreeMap::new(),
+        anomalies: Vec::new(),
+    };
+
+    for err in validator.iter_errors(&instance) {
+        let at = err.instance_path().to_string();
+        report.anomaly_count += 1;
+        *report.by_location.entry(generic_path(&at)).or_insert(0) += 1;
+        if report.anomalies.len() < max_errors {
+            report.anomalies.push(Anomaly {
+                path: if at.is_empty() { "/".to_string() } else { at },
+                keyword: err.kind().keyword().to_string(),
+                message: err.to_string(),
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn print_report(r: &InspectReport) {
+    if r.anomaly_count == 0 {
+        log_ok!("{} {}: {} records, no structural anomalies", r.source, r.file, r.records);
+        return;
+    }
+
+    // One entry, so --log-format json keeps the breakdown with its headline
+    let mut msg = format!("{} {}: {} records, {} structural anomalies", r.source, r.file, r.records, r.anomaly_count);
+    msg.push_str("\n  By location:");
+    for (loc, count) in &r.by_location {
+        msg.push_str(&format!("\n    {:>6}  {}", count, loc));
+    }
+    msg.push_str(&format!("\n  First {} anomalies:", r.anomalies.len()));
+    for a in &r.anomalies {
+        msg.push_str(&format!("\n    {} [{}] {}", a.path, a.keyword, a.message));
+    }
+    log_fail!("{}", msg);
+}
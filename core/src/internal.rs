@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{input, log_warn, refs, severity, CanonicalItem};
+
+/* -------------------- Internal (private) advisories -------------------- */
+/*
+Our own advisories: issues found internally, vendor pre-notifications, or CVEs
+still RESERVED upstream. They are merged like any other source ("internal"),
+but may carry `embargoed_until`; redact.rs drops embargoed items from every
+shareable output.
+
+Input: a JSON file (array, or { "advisories": [...] }) or a directory of them.
+  { "id": "CVE-2026-12345" | "INT-2026-007", "title": "...", "description": "...",
+    "cvss": 8.1, "vendor": "...", "product": "...", "refs": ["https://..."],
+    "embargoed_until": "2026-11-01T00:00:00Z" }
+
+Advisories without a CVE use INT-... IDs (the canonical schema allows both).
+If the ID is already public (came in from another source), a non-embargoed
+advisory only gap-fills it; an embargoed one is skipped with a warning so its
+details can't reach shareable outputs through the public item.
+*/
+
+#[derive(Debug, Deserialize)]
+struct InternalAdvisory {
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    cvss: Option<f64>,
+    #[serde(default)]
+    vendor: Option<String>,
+    #[serde(default)]
+    product: Option<String>,
+    #[serde(default)]
+    refs: Vec<String>,
+    #[serde(default)]
+    embargoed_until: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AdvisoryFile {
+    List(Vec<InternalAdvisory>),
+    Wrapped { advisories: Vec<InternalAdvisory> },
+}
+
+fn walk(path: &Path, out: &mut Vec<InternalAdvisory>) -> Result<()> {
+    if path.is_file() {
+        let bytes = input::read_input(path)?;
+        let file: AdvisoryFile = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse internal advisories: {}", path.display()))?;
+        match file {
+            AdvisoryFile::List(list) | AdvisoryFile::Wrapped { advisories: list } => out.extend(list),
+        }
+        return Ok(());
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(path)
+        .with_context(|| format!("Failed to read internal advisory directory: {}", path.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() || p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    entries.sort();
+    for p in entries {
+        walk(&p, out)?;
+    }
+    Ok(())
+}
+
+/// Merge internal advisories; unknown IDs become new items.
+/// Returns (items added, public items gap-filled).
+pub fn merge_internal(path: &Path, items: &mut Vec<CanonicalItem>) -> Result<(usize, usize)> {
+    let mut advisories = Vec::new();
+    walk(path, &mut advisories)?;
+
+    let (mut added, mut filled) = (0usize, 0usize);
+    for adv in advisories {
+        let id = adv.id.trim().to_string();
+        if let Some(item) = items.iter_mut().find(|i| i.id == id) {
+            if adv.embargoed_until.is_some() {
+                // Don't leak embargoed details onto a public item
+                log_warn!("internal advisory {} is embargoed but already public; skipping it", id);
+                continue;
+            }
+            if item.title.is_none() {
+                item.title = adv.title;
+            }
+            if item.vendor.is_none() {
+                item.vendor = adv.vendor;
+            }
+            if item.product.is_none() {
+                item.product = adv.product;
+            }
+            if !item.sources.iter().any(|s| s == "internal") {
+                item.sources.push("internal".to_string());
+            }
+            filled += 1;
+            continue;
+        }
+
+        let mut refs = Vec::new();
+        for url in &adv.refs {
+            refs::push_ref(&mut refs, url, "internal");
+        }
+        items.push(CanonicalItem {
+            id,
+            sources: vec!["internal".to_string()],
+            cvss: adv.cvss,
+            severity_bucket: severity::bucket(adv.cvss, false),
+            short_desc: adv.description.unwrap_or_else(|| "Internal advisory.".to_string()),
+            title: adv.title,
+            vendor: adv.vendor,
+            product: adv.product,
+            refs,
+            embargoed_until: adv.embargoed_until,
+            ..Default::default()
+        });
+        added += 1;
+    }
+    Ok((added, filled))
+}
@@ -0,0 +1,211 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    errors, input, lenient, refs, stream,
+    source::{KevListing, PartialItem, Source},
+};
+
+/* -------------------- KEV parsing -------------------- */
+/*
+The CISA Known Exploited Vulnerabilities catalog. Each entry contributes the KEV
+flag and BOD 22-01 fields, vendor/product, the advisory links found in notes and
+requiredAction, and a description for items NVD has none for. IDs missing from
+the NVD feeds still become items, with a KEV placeholder description.
+*/
+
+const PLACEHOLDER: &str = "KEV-listed vulnerability (details not in current NVD modified feed).";
+
+#[derive(Debug, Deserialize)]
+struct KevRoot {
+    #[serde(default, rename = "dateReleased")]
+    date_released: Option<String>,
+    #[serde(default)]
+    vulnerabilities: Vec<KevVuln>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KevVuln {
+    #[serde(rename = "cveID")]
+    cve_id: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    product: Option<String>,
+    #[serde(default, rename = "vendorProject")]
+    vendor_project: Option<String>,
+    #[serde(default, rename = "dateAdded")]
+    date_added: Option<String>,
+    #[serde(default, rename = "dueDate")]
+    due_date: Option<String>,
+    #[serde(default, rename = "knownRansomwareCampaignUse")]
+    known_ransomware_campaign_use: Option<String>,
+    #[serde(default, rename = "shortDescription")]
+    short_description: Option<String>,
+    #[serde(default, rename = "requiredAction")]
+    required_action: Option<String>,
+}
+
+struct KevEntry {
+    id: String,
+    note: Option<String>,
+    vendor: Option<String>,
+    product: Option<String>,
+    links: Vec<String>,
+    listing: KevListing,
+}
+
+pub struct KevSource {
+    path: Option<PathBuf>,
+    date_released: Option<String>,
+    entries: Vec<KevEntry>,
+    index: HashMap<String, usize>,
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+    s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn listing(v: &KevVuln) -> KevListing {
+    KevListing {
+        date_added: non_empty(v.date_added.clone()),
+        due_date: non_empty(v.due_date.clone()),
+        required_action: non_empty(v.required_action.clone()),
+        // "Known" | "Unknown"; anything else is treated as not stated
+        ransomware_known: match v.known_ransomware_campaign_use.as_deref().map(str::trim) {
+            Some(s) if s.eq_ignore_ascii_case("known") => Some(true),
+            Some(s) if s.eq_ignore_ascii_case("unknown") => Some(false),
+            _ => None,
+        },
+    }
+}
+
+impl KevSource {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            input::read_input(path).with_context(|| format!("Failed to read KEV file: {}", path.display()))?;
+        let mut source = Self::parse(&bytes, path)?;
+        source.path = Some(path.to_path_buf());
+        Ok(source)
+    }
+
+    /// A catalog already in memory (plain or gzip); `name` is for messages. It lists no inputs.
+    pub fn from_bytes(bytes: &[u8], name: &str) -> Result<Self> {
+        let mut decoded = Vec::new();
+        input::open_bytes(bytes, name)?
+            .read_to_end(&mut decoded)
+            .with_context(|| format!("Failed to read KEV catalog: {}", name))?;
+        Self::parse(&decoded, Path::new(name))
+    }
+
+    fn parse(bytes: &[u8], path: &Path) -> Result<Self> {
+        let invalid = || errors::Failure::new("invalid_json", "Failed to parse KEV JSON").path(path);
+        // --lenient: records are parsed one by one, broken ones skipped (see stream.rs)
+        let root: KevRoot = if lenient::enabled() {
+            let mut root = KevRoot { date_released: None, vulnerabilities: Vec::new() };
+            let mut meta = |key: &str, value: serde_json::Value| {
+                if key == "dateReleased" {
+                    root.date_released = serde_json::from_value(value)?;
+                }
+                Ok(())
+            };
+            let mut vulnerabilities = Vec::new();
+            let source = ("kev", "/cveID");
+            stream::for_each_resynced(bytes, path, "vulnerabilities", source, &mut meta, |v: KevVuln| {
+                vulnerabilities.push(v);
+                Ok(())
+            })
+            .with_context(invalid)?;
+            root.vulnerabilities = vulnerabilities;
+            root
+        } else {
+            serde_json::from_slice(bytes).with_context(invalid)?
+        };
+
+        let mut entries = Vec::with_capacity(root.vulnerabilities.len());
+        let mut index = HashMap::new();
+        for v in root.vulnerabilities {
+            let listing = listing(&v);
+            // notes / requiredAction carry the vendor's advisory and mitigation links
+            let links = refs::extract_urls(v.notes.iter().chain(&v.required_action));
+            let entry = KevEntry {
+                id: v.cve_id.trim().to_string(),
+                note: non_empty(v.short_description.or(v.notes)),
+                vendor: non_empty(v.vendor_project),
+                product: non_empty(v.product),
+                links,
+                listing,
+            };
+            // A repeated ID replaces the earlier entry
+            match index.get(&entry.id) {
+                Some(&idx) => entries[idx] = entry,
+                None => {
+                    index.insert(entry.id.clone(), entries.len());
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(KevSource { path: None, date_released: root.date_released, entries, index })
+    }
+
+    /// The catalog's dateReleased, the as_of for KEV fields.
+    pub fn date_released(&self) -> Option<&str> {
+        self.date_released.as_deref()
+    }
+
+    pub fn ids(&self) -> HashSet<String> {
+        self.index.keys().cloned().collect()
+    }
+
+    /// The catalog's description of `id`, if it has one.
+    pub fn note(&self, id: &str) -> Option<&str> {
+        self.entry(id)?.note.as_deref()
+    }
+
+    pub fn listing(&self, id: &str) -> Option<&KevListing> {
+        Some(&self.entry(id)?.listing)
+    }
+
+    fn entry(&self, id: &str) -> Option<&KevEntry> {
+        self.index.get(id).map(|&idx| &self.entries[idx])
+    }
+}
+
+impl Source for KevSource {
+    fn name(&self) -> &str {
+        "kev"
+    }
+
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.path.iter().cloned().collect()
+    }
+
+    fn items(&self) -> Result<Vec<PartialItem>> {
+        Ok(self
+            .entries
+            .iter()
+            .map(|e| {
+                let mut refs =
+                    vec![refs::Reference::new(&format!("https://nvd.nist.gov/vuln/detail/{}", e.id), &[], "kev")];
+                for url in &e.links {
+                    refs::push_ref(&mut refs, url, "kev");
+                }
+                PartialItem {
+                    id: e.id.clone(),
+                    description: e.note.clone(),
+                    placeholder: Some(PLACEHOLDER.to_string()),
+                    vendor: e.vendor.clone(),
+                    product: e.product.clone(),
+                    refs,
+                    kev: Some(e.listing.clone()),
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+}
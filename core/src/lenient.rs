@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{codex, log_debug};
+
+/* -------------------- Tolerant parsing -------------------- */
+/*
+normalize --lenient keeps one bad record from blocking the refresh. A record
+that is valid JSON but doesn't fit the feed's shape (a missing cveID, a number
+where a string belongs) is skipped and reported; every other record goes
+through as usual. Applies to NVD and KEV records and to exec source lines.
+
+The skipped records are listed in <out>.errors.json next to the output (and in
+the run manifest as a sidecar), written on every lenient run so a clean run
+leaves an empty list rather than yesterday's:
+
+  { "generated_at": "2025-06-02T06:00:11Z",
+    "skipped": 1,
+    "errors": [ { "source": "nvd", "file": "nvdcve-2.0-2024.json.gz",
+                  "record": 18211, "id": "CVE-2024-38112",
+                  "error": "invalid type: integer `3`, expected a string" } ] }
+
+`record` is the index in the feed's array (the line number for exec sources);
+`id` is whatever CVE ID could be read from it. A record that isn't valid JSON
+at all is skipped the same way, with the first CVE ID in its text: NVD and KEV
+records are found by brackets and quotes first and parsed one at a time (see
+stream.rs). What that can't get past still fails the run: a record whose
+brackets or quotes don't balance, as in a truncated file, and broken syntax
+outside the record array. (exec lines stand alone, so any of them is skipped.)
+*/
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SKIPPED: Mutex<Vec<SkippedRecord>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedRecord {
+    pub source: String,
+    pub file: String,
+    pub record: usize,
+    pub id: Option<String>,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    generated_at: String,
+    skipped: usize,
+    errors: &'a [SkippedRecord],
+}
+
+/// Start a run with leniency on or off, forgetting records an earlier
+/// (failed) run skipped.
+pub fn begin(on: bool) {
+    ENABLED.store(on, Ordering::Relaxed);
+    take();
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Note a record the parser skipped. `id_pointer` locates the CVE ID in it.
+pub fn skip(source: &str, file: &str, record: usize, value: &Value, id_pointer: &str, error: impl ToString) {
+    let id = value.pointer(id_pointer).and_then(Value::as_str).map(|s| s.trim().to_string());
+    push(source, file, record, id, error.to_string());
+}
+
+/// Note a record that isn't valid JSON (see stream.rs); the first CVE ID in its
+/// text stands in for the one a pointer would find.
+pub fn skip_broken(source: &str, file: &str, record: usize, raw: &[u8], error: impl ToString) {
+    let text = String::from_utf8_lossy(raw);
+    let id = text.match_indices("CVE-").find_map(|(at, _)| {
+        let rest = &text[at + 4..];
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(rest.len());
+        let (year, number) = rest[..end].split_once('-')?;
+        (year.len() == 4 && number.len() >= 4 && !number.contains('-')).then(|| format!("CVE-{}", &rest[..end]))
+    });
+    push(source, file, record, id, format!("invalid JSON: {}", error.to_string()));
+}
+
+fn push(source: &str, file: &str, record: usize, id: Option<String>, error: String) {
+    log_debug!("lenient: skipped {} record {} in {} ({}): {}", source, record, file, id.as_deref().unwrap_or("no id"), error);
+    let rec = SkippedRecord { source: source.to_string(), file: file.to_string(), record, id, error };
+    SKIPPED.lock().unwrap_or_else(|e| e.into_inner()).push(rec);
+}
+
+/// Everything skipped since the last call.
+pub fn take() -> Vec<SkippedRecord> {
+    std::mem::take(&mut *SKIPPED.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub fn report_path(out_path: &Path) -> PathBuf {
+    let stem = codex::output_stem(out_path);
+    out_path.with_file_name(format!("{}.errors.json", stem))
+}
+
+pub fn write_report(path: &Path, skipped: &[SkippedRecord]) -> Result<()> {
+    let report = Report {
+        generated_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        skipped: skipped.len(),
+        errors: skipped,
+    };
+    fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+        .with_context(|| format!("Failed to write error report: {}", path.display()))
+}
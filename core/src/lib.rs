@@ -0,0 +1,248 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/* -------------------- bastion-codex-core -------------------- */
+/*
+The normalization engine behind the `core` binary, usable from other Rust
+tools: parse feeds with a Source (nvd::NvdSource, kev::KevSource, or your own),
+merge them into CanonicalItems with a Normalizer (see source.rs), then apply
+the enrichment passes and writers the modules below provide. The binary in
+main.rs is the CLI over the same functions.
+
+The `io` feature (default) adds what only makes sense next to a filesystem or
+network: serve/grpc/metrics, daemon, the snapshot store, trends and burn-down, search,
+archive, inspect, tui and mount, remote inputs/outputs and notify (which shell out to
+curl, sftp and the object storage CLIs), plus zstd. Without it the parse/merge/query
+core still builds, including for wasm32-unknown-unknown, where feeds and
+items come in as bytes (NvdSource::from_bytes, KevSource::from_bytes,
+codex::parse_items) through the C ABI in ffi.rs:
+
+  cargo build --release --lib --target wasm32-unknown-unknown \
+      --no-default-features --features nvd,kev
+*/
+
+#[cfg(feature = "io")]
+pub mod archive;
+pub mod attack;
+#[cfg(all(feature = "nvd", feature = "kev"))]
+pub mod bench;
+#[cfg(feature = "io")]
+pub mod burndown;
+pub mod check;
+pub mod codex;
+pub mod config;
+pub mod cpe;
+pub mod csaf;
+pub mod cvelist;
+pub mod cvss;
+pub mod cwe;
+pub mod cyclonedx;
+#[cfg(feature = "io")]
+pub mod daemon;
+pub mod delta;
+pub mod diff;
+pub mod digest;
+pub mod distro;
+#[cfg(feature = "io")]
+pub mod elastic;
+pub mod errors;
+#[cfg(feature = "exec")]
+pub mod exec;
+pub mod exploited;
+pub mod exploits;
+pub mod export;
+#[cfg(all(feature = "nvd", feature = "kev"))]
+pub mod ffi;
+pub mod filter;
+pub mod fixtures;
+#[cfg(feature = "io")]
+pub mod fusefs;
+pub mod gate;
+#[cfg(feature = "io")]
+pub mod grpc;
+pub mod html;
+#[cfg(feature = "io")]
+pub mod http;
+pub mod input;
+#[cfg(feature = "io")]
+pub mod inspect;
+pub mod internal;
+#[cfg(feature = "kev")]
+pub mod kev;
+pub mod lenient;
+pub mod limits;
+#[cfg(feature = "io")]
+pub mod linkcheck;
+pub mod lint;
+pub mod logging;
+pub mod manifest;
+pub mod merge;
+#[cfg(feature = "io")]
+pub mod metrics;
+pub mod msrc;
+#[cfg(feature = "io")]
+pub mod notify;
+pub mod nuclei;
+#[cfg(feature = "nvd")]
+pub mod nvd;
+#[cfg(feature = "io")]
+pub mod objstore;
+pub mod osv;
+pub mod outname;
+pub mod oval;
+pub mod overdue;
+pub mod overrides;
+pub mod precedence;
+pub mod priority;
+pub mod provenance;
+pub mod query;
+pub mod redact;
+pub mod refs;
+#[cfg(feature = "io")]
+pub mod remote;
+#[cfg(feature = "io")]
+pub mod replay;
+pub mod report;
+#[cfg(feature = "io")]
+pub mod search;
+#[cfg(feature = "io")]
+pub mod serve;
+#[cfg(feature = "io")]
+pub mod service;
+pub mod severity;
+#[cfg(feature = "io")]
+pub mod sign;
+#[cfg(feature = "io")]
+pub mod snapshot;
+pub mod source;
+pub mod stats;
+pub mod stream;
+pub mod tags;
+pub mod telemetry;
+pub mod toml;
+#[cfg(feature = "io")]
+pub mod trends;
+#[cfg(feature = "io")]
+pub mod tui;
+pub mod vendors;
+pub mod versions;
+pub mod vex;
+pub mod vulnrichment;
+pub mod watchdog;
+pub mod watchlist;
+pub mod xml;
+
+pub use source::{Normalizer, PartialItem, Registry, Source};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CanonicalItem {
+    pub id: String,                      // CVE-YYYY-NNNN
+    pub sources: Vec<String>,            // ["kev","nvd"]
+    pub published: Option<String>,       // ISO8601
+    pub last_modified: Option<String>,   // ISO8601
+    pub cvss: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_details: Option<cvss::CvssDetails>, // NVD metric behind `cvss`: version, source, vector
+    pub severity_bucket: String,         // low|medium|high|critical|unknown
+    pub kev: bool,
+    #[serde(default)]
+    pub kev_date_added: Option<String>,  // YYYY-MM-DD, KEV only
+    #[serde(default)]
+    pub kev_due_date: Option<String>,    // BOD 22-01 remediation deadline
+    #[serde(default)]
+    pub kev_required_action: Option<String>,
+    #[serde(default)]
+    pub ransomware_known: Option<bool>,  // knownRansomwareCampaignUse: Known -> true, Unknown -> false
+    pub short_desc: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>, // lang -> text, with --all-descriptions (see nvd.rs)
+    #[serde(default)]
+    pub title: Option<String>,           // CNA-provided title (cvelistV5)
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub refs: Vec<refs::Reference>,      // typed links: {url, kind, source} (see refs.rs)
+    #[serde(default)]
+    pub fix_refs: Vec<String>,           // commit / PR / MR links from refs (see refs.rs)
+    #[serde(default)]
+    pub cwes: Vec<String>,               // ["CWE-79"], from NVD weaknesses
+    #[serde(default)]
+    pub affected: Vec<cpe::AffectedCpe>, // NVD cpeMatch entries with version ranges
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affected_versions: Vec<versions::AffectedVersions>, // matcher-ready ranges from CPEs and OSV
+    #[serde(default)]
+    pub cwe_categories: Vec<String>,     // "owasp-top10-2021/A03:2021-Injection", see cwe.rs
+    #[serde(default)]
+    pub attack_techniques: Vec<String>,  // ATT&CK technique IDs ("T1190"), see attack.rs
+    #[serde(default)]
+    pub tags: Vec<String>,               // routing labels from --tag-rules, see tags.rs
+    #[serde(default)]
+    pub vendor_advisories: Vec<csaf::VendorAdvisory>, // CSAF vendor views, kept alongside NVD
+    #[serde(default)]
+    pub distro_status: BTreeMap<String, distro::DistroStatus>, // "debian:bookworm" -> status
+    #[serde(default)]
+    pub msrc: Option<msrc::MsrcInfo>,    // Microsoft severity, KBs, affected products
+    #[serde(default)]
+    pub exploit_public: bool,            // public PoC/module exists (Exploit-DB, Metasploit)
+    #[serde(default)]
+    pub exploit_refs: Vec<String>,
+    #[serde(default)]
+    pub has_nuclei_template: bool,       // a nuclei detection template exists (see nuclei.rs)
+    #[serde(default)]
+    pub ssvc: Option<vulnrichment::Ssvc>, // CISA SSVC decision points
+    #[serde(default)]
+    pub observed_exploitation: Option<telemetry::ObservedExploitation>, // GreyNoise/Shodan sensor data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exploited: Option<exploited::Exploited>, // in-the-wild listings: CISA KEV and --exploited-feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epss: Option<f64>,               // FIRST EPSS probability, kept by `score --epss` (see priority.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_score: Option<f64>,     // 0-100 composite, set by `score` (see priority.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_tier: Option<String>,   // act|attend|track*|track unless the policy renames them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub internal_notes: Vec<String>,     // from --overrides; stripped by --shareable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<overrides::AppliedOverride>, // fields pinned by --overrides, with originals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embargoed_until: Option<String>, // internal advisories only; see redact.rs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<String>,          // list fields cut by --max-item-bytes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provenance: BTreeMap<String, provenance::FieldSource>, // field -> source, with --with-provenance
+    #[serde(default)]
+    pub content_hash: String,            // sha256 over all other fields (see digest.rs)
+}
+
+pub fn parse_iso_datetime(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+
+    // 1) RFC3339 with timezone (preferred)
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    // 2) Naive ISO8601 without timezone -> assume UTC
+    // Try with fractional seconds first, then without.
+    use chrono::NaiveDateTime;
+
+    let fmts = [
+        "%Y-%m-%dT%H:%M:%S%.f", // supports .123, .123456, etc.
+        "%Y-%m-%dT%H:%M:%S",
+    ];
+
+    for fmt in fmts {
+        if let Ok(ndt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
+        }
+    }
+
+    None
+}
+
+pub fn top_n_counts(map: &HashMap<String, usize>, n: usize) -> Vec<(String, usize)> {
+    let mut v: Vec<(String, usize)> = map.iter().map(|(k, c)| (k.clone(), *c)).collect();
+    v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    v.truncate(n);
+    v
+}
@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{codex, CanonicalItem};
+
+/* -------------------- Per-item size limits -------------------- */
+/*
+A handful of CVEs carry thousands of list entries (refs, advisories, ...) and
+serialize to multiple MB. Downstream consumers choke on those, so normalize can
+cap list fields per item and either drop the overflow or move it to a sidecar.
+
+The sidecar always matches its output: every run with --max-item-bytes rewrites
+it (write-then-rename) or, with nothing to move, removes the one an earlier run
+left. Items kept unchanged through --merge-into were trimmed by the run that
+wrote them, so their entries are carried over from that run's sidecar.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TruncateStrategy {
+    /// Drop entries beyond the cap
+    Cap,
+    /// Move entries beyond the cap into <out>.overflow.json
+    Sidecar,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SizeLimits {
+    pub max_item_bytes: usize,
+    pub max_list_len: usize,
+    pub strategy: TruncateStrategy,
+}
+
+/// Overflow entries removed from one item, keyed by field name.
+#[derive(Debug, Serialize, Deserialize)]
+struct Overflow {
+    id: String,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+// List fields eligible for truncation. Add new list-valued fields here.
+const LIST_FIELDS: [&str; 4] = ["refs", "vendor_advisories", "affected", "affected_versions"];
+
+fn list_len(item: &CanonicalItem, field: &str) -> usize {
+    match field {
+        "refs" => item.refs.len(),
+        "vendor_advisories" => item.vendor_advisories.len(),
+        "affected" => item.affected.len(),
+        "affected_versions" => item.affected_versions.len(),
+        _ => 0,
+    }
+}
+
+fn split_list(item: &mut CanonicalItem, field: &str, keep: usize) -> Result<serde_json::Value> {
+    let removed = match field {
+        "refs" => serde_json::to_value(item.refs.split_off(keep))?,
+        "vendor_advisories" => serde_json::to_value(item.vendor_advisories.split_off(keep))?,
+        "affected" => serde_json::to_value(item.affected.split_off(keep))?,
+        "affected_versions" => serde_json::to_value(item.affected_versions.split_off(keep))?,
+        _ => serde_json::Value::Array(Vec::new()),
+    };
+    Ok(removed)
+}
+
+fn item_size(item: &CanonicalItem) -> Result<usize> {
+    Ok(serde_json::to_vec(item)?.len())
+}
+
+fn push_overflow(fields: &mut serde_json::Map<String, serde_json::Value>, field: &str, removed: serde_json::Value) {
+    let serde_json::Value::Array(mut removed) = removed else { return; };
+    match fields.get_mut(field) {
+        // Earlier (tail) removals go after the newly removed slice to keep original order
+        Some(serde_json::Value::Array(existing)) => {
+            removed.append(existing);
+            *existing = removed;
+        }
+        _ => {
+            fields.insert(field.to_string(), serde_json::Value::Array(removed));
+        }
+    }
+}
+
+fn shrink_item(item: &mut CanonicalItem, limits: &SizeLimits) -> Result<Option<Overflow>> {
+    if item_size(item)? <= limits.max_item_bytes {
+        return Ok(None);
+    }
+
+    let mut fields = serde_json::Map::new();
+
+    // 1) Cap every list at max_list_len
+    for field in LIST_FIELDS {
+        if list_len(item, field) > limits.max_list_len {
+            let removed = split_list(item, field, limits.max_list_len)?;
+            push_overflow(&mut fields, field, removed);
+        }
+    }
+
+    // 2) Still too large: keep halving the longest list
+    while item_size(item)? > limits.max_item_bytes {
+        let Some(field) = LIST_FIELDS
+            .iter()
+            .copied()
+            .filter(|f| list_len(item, f) > 1)
+            .max_by_key(|f| list_len(item, f))
+        else {
+            break; // nothing left to trim; scalar fields alone exceed the limit
+        };
+        let keep = list_len(item, field) / 2;
+        let removed = split_list(item, field, keep)?;
+        push_overflow(&mut fields, field, removed);
+    }
+
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    item.truncated = fields.keys().cloned().collect();
+    Ok(Some(Overflow { id: item.id.clone(), fields }))
+}
+
+/// Sidecar path for a given output: items.json -> items.overflow.json
+pub fn sidecar_path(out_path: &Path) -> PathBuf {
+    let stem = codex::output_stem(out_path);
+    out_path.with_file_name(format!("{}.overflow.json", stem))
+}
+
+/// What enforce_limits did: items truncated this run, and the sidecar if one was written.
+#[derive(Debug)]
+pub struct Enforced {
+    pub truncated: usize,
+    pub sidecar: Option<PathBuf>,
+}
+
+fn read_sidecar(path: &Path) -> Result<HashMap<String, Overflow>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read overflow sidecar: {}", path.display()))?;
+    let entries: Vec<Overflow> =
+        serde_json::from_str(&text).with_context(|| format!("Invalid overflow sidecar: {}", path.display()))?;
+    Ok(entries.into_iter().map(|o| (o.id.clone(), o)).collect())
+}
+
+/// Enforce per-item size limits in place and write or remove the sidecar at `out_path`.
+/// `prior_sidecar`: the --merge-into input's, for the items kept from it.
+pub fn enforce_limits(
+    items: &mut [CanonicalItem],
+    limits: &SizeLimits,
+    out_path: &Path,
+    prior_sidecar: Option<&Path>,
+) -> Result<Enforced> {
+    let sidecar = limits.strategy == TruncateStrategy::Sidecar;
+    let mut prior = match prior_sidecar {
+        Some(path) if sidecar => read_sidecar(path)?,
+        _ => HashMap::new(),
+    };
+    let mut overflow: Vec<Overflow> = Vec::new();
+    let mut truncated = 0;
+    for item in items.iter_mut() {
+        // Trimmed before: now-removed entries go first, the earlier tail after them
+        let earlier = if item.truncated.is_empty() { None } else { prior.remove(&item.id) };
+        let now = shrink_item(item, limits)?;
+        truncated += now.is_some() as usize;
+        let kept = std::mem::take(&mut item.truncated);
+        let entry = match (now, earlier) {
+            (Some(mut now), Some(earlier)) => {
+                for (field, removed) in earlier.fields {
+                    let serde_json::Value::Array(removed) = removed else { continue };
+                    if let Some(serde_json::Value::Array(list)) = now.fields.get_mut(&field) {
+                        list.extend(removed);
+                    } else {
+                        now.fields.insert(field, serde_json::Value::Array(removed));
+                    }
+                }
+                Some(now)
+            }
+            (now, earlier) => now.or(earlier),
+        };
+        item.truncated = kept;
+        if let Some(entry) = entry {
+            for field in entry.fields.keys() {
+                if !item.truncated.contains(field) {
+                    item.truncated.push(field.clone());
+                }
+            }
+            item.truncated.sort();
+            overflow.push(entry);
+        }
+    }
+
+    let path = sidecar_path(out_path);
+    if sidecar && !overflow.is_empty() {
+        // Write-then-rename so an interrupted run leaves the old sidecar intact
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&overflow)?)
+            .with_context(|| format!("Failed to write overflow sidecar: {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to write overflow sidecar: {}", path.display()))?;
+        return Ok(Enforced { truncated, sidecar: Some(path) });
+    }
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove stale sidecar: {}", path.display()))?;
+    }
+    Ok(Enforced { truncated, sidecar: None })
+}
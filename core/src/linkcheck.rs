@@ -0,0 +1,202 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use crate::{http, log_ok, log_warn, refs, CanonicalItem};
+
+/* -------------------- Reference link checking -------------------- */
+/*
+normalize --check-refs annotate|drop probes every distinct http(s) link in
+item.refs once, after all sources have added theirs:
+
+- HEAD with redirects followed, then a one-byte GET if the server refuses HEAD
+  (405, 501), through http.rs (proxy, CA bundle) but without its retries:
+  a failed probe already counts as alive.
+- dead: HTTP 404 or 410, or a host that doesn't resolve or refuses the
+  connection. Timeouts, TLS errors, 403, 429 and 5xx count as alive, so a flaky
+  or bot-shy server doesn't lose its links.
+- annotate sets ref.dead to the reason ("HTTP 404"); drop removes the ref, and
+  with it any fix_refs entry.
+
+Up to --check-refs-concurrency requests run at once, with at least
+--check-refs-host-delay-ms between two requests to one host. A full NVD dataset
+links millions of URLs, so keep a --check-refs-cache file between runs: results
+younger than CACHE_DAYS are reused instead of probed again.
+*/
+
+const CACHE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DeadRefs {
+    /// Keep dead refs, with `dead` set to the reason
+    Annotate,
+    /// Remove dead refs
+    Drop,
+}
+
+/// Flattened into normalize.
+#[derive(Debug, Clone, Args)]
+pub struct CheckOptions {
+    /// Probe reference URLs and mark or remove the dead ones (see linkcheck.rs)
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub check_refs: Option<DeadRefs>,
+    /// Probes in flight at once for --check-refs
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub check_refs_concurrency: usize,
+    /// Minimum gap between two probes of the same host, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    pub check_refs_host_delay_ms: u64,
+    /// Probe results kept between runs (JSON; created if missing)
+    #[arg(long, value_name = "FILE")]
+    pub check_refs_cache: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Checked {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dead: Option<String>,
+    checked: String,
+}
+
+type Cache = BTreeMap<String, Checked>;
+
+fn load_cache(path: &Path) -> Result<Cache> {
+    if !path.exists() {
+        return Ok(Cache::new());
+    }
+    let bytes = fs::read(path).with_context(|| format!("Failed to read ref check cache: {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse ref check cache: {}", path.display()))
+}
+
+// curl's exit code and the final HTTP status (0 when there was no response);
+// Err when curl couldn't be run at all
+fn request(url: &str, head: bool) -> std::io::Result<(i32, u16)> {
+    let null = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let mut args = vec!["-L", "--max-redirs", "10", "--max-time", "20", "-o", null];
+    if head {
+        args.push("-I");
+    } else {
+        args.extend(["-r", "0-0"]);
+    }
+    let response = http::send(&args, &format!("url = {}\n", http::config_quote(url)))?;
+    Ok((response.code, response.status))
+}
+
+/// Why `url` is dead, or None if it answers (or might).
+fn probe(url: &str) -> std::io::Result<Option<String>> {
+    let (mut code, mut status) = request(url, true)?;
+    if code == 0 && matches!(status, 405 | 501) {
+        (code, status) = request(url, false)?;
+    }
+    Ok(match (code, status) {
+        (6, _) => Some("host not found".to_string()),
+        (7, _) => Some("connection refused".to_string()),
+        (_, 404 | 410) => Some(format!("HTTP {}", status)),
+        _ => None,
+    })
+}
+
+fn host(url: &str) -> String {
+    let key = refs::url_key(url);
+    key.split(['/', '?', '#']).next().unwrap_or("").to_string()
+}
+
+/// Probe every distinct http(s) ref and annotate or drop the dead ones.
+pub fn check_refs(items: &mut [CanonicalItem], mode: DeadRefs, opts: &CheckOptions) -> Result<()> {
+    let now = Utc::now();
+    let mut cache = opts.check_refs_cache.as_deref().map(load_cache).transpose()?.unwrap_or_default();
+    let max_age = chrono::Duration::days(CACHE_DAYS);
+    let fresh = |c: &Checked| DateTime::parse_from_rfc3339(&c.checked).is_ok_and(|t| now - t.to_utc() < max_age);
+
+    let mut by_host: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut queued = HashSet::new();
+    for r in items.iter().flat_map(|i| &i.refs) {
+        let web = r.url.starts_with("https://") || r.url.starts_with("http://");
+        if web && !cache.get(&r.url).is_some_and(fresh) && queued.insert(r.url.clone()) {
+            by_host.entry(host(&r.url)).or_default().push(r.url.clone());
+        }
+    }
+    let reused = items.iter().flat_map(|i| &i.refs).filter(|r| cache.get(&r.url).is_some_and(fresh)).count();
+    log_ok!("check-refs: probing {} URLs on {} hosts ({} refs from cache)", queued.len(), by_host.len(), reused);
+
+    // One host per worker at a time, so the per-host delay needs no coordination
+    let hosts: Mutex<VecDeque<Vec<String>>> = Mutex::new(by_host.into_values().collect());
+    let results: Mutex<HashMap<String, Option<String>>> = Mutex::new(HashMap::new());
+    let delay = Duration::from_millis(opts.check_refs_host_delay_ms);
+    let workers = opts.check_refs_concurrency.max(1);
+    let spawn_error: Mutex<Option<std::io::Error>> = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                // The guard is dropped inside and_then, so other workers can take hosts meanwhile
+                while let Some(urls) = hosts.lock().ok().and_then(|mut h| h.pop_front()) {
+                    for (n, url) in urls.into_iter().enumerate() {
+                        if n > 0 {
+                            thread::sleep(delay);
+                        }
+                        match probe(&url) {
+                            Ok(dead) => {
+                                if let Ok(mut r) = results.lock() {
+                                    r.insert(url, dead);
+                                }
+                            }
+                            // curl itself is missing: nothing else will work either
+                            Err(e) => {
+                                if let Ok(mut s) = spawn_error.lock() {
+                                    s.get_or_insert(e);
+                                }
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+    });
+    if let Some(e) = spawn_error.into_inner().ok().flatten() {
+        bail!("check-refs: failed to run {} (set BASTION_CURL): {}", http::curl(), e);
+    }
+
+    let checked = now.to_rfc3339();
+    for (url, dead) in results.into_inner().unwrap_or_default() {
+        cache.insert(url, Checked { dead, checked: checked.clone() });
+    }
+    let dead_of = |url: &str| cache.get(url).and_then(|c| c.dead.clone());
+    let mut dead = 0usize;
+    let mut dropped = Vec::new();
+    for item in items.iter_mut() {
+        for r in item.refs.iter_mut() {
+            r.dead = dead_of(&r.url);
+            dead += r.dead.is_some() as usize;
+        }
+        if mode == DeadRefs::Drop {
+            dropped.extend(item.refs.iter().filter(|r| r.dead.is_some()).map(|r| r.url.clone()));
+            item.refs.retain(|r| r.dead.is_none());
+        }
+    }
+    let action = if mode == DeadRefs::Drop { "dropped" } else { "marked" };
+    log_ok!("check-refs: {} {} dead refs", action, dead);
+
+    if let Some(path) = &opts.check_refs_cache {
+        // Links nobody carries any more would only grow the file; with drop, the
+        // dead ones are still worth remembering
+        let carried = items.iter().flat_map(|i| &i.refs).map(|r| r.url.as_str());
+        let linked: HashSet<&str> = carried.chain(dropped.iter().map(String::as_str)).collect();
+        let kept: Cache = cache.into_iter().filter(|(url, _)| linked.contains(url.as_str())).collect();
+        match serde_json::to_vec_pretty(&kept) {
+            Ok(bytes) => fs::write(path, bytes)
+                .with_context(|| format!("Failed to write ref check cache: {}", path.display()))?,
+            Err(e) => log_warn!("check-refs: cache not written: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+use crate::{input, log_ok, query, CanonicalItem};
+
+/* -------------------- Codex lint rules -------------------- */
+/*
+Data-quality checks over canonical items. A rule is a query expression (see
+query.rs); every item it matches is a finding.
+
+Custom rules live in a directory (--rules rules.d/) of JSON files, each holding
+one rule or an array of them:
+  { "id": "msrc-without-kb", "level": "warning",
+    "description": "MSRC item without KB articles",
+    "when": "msrc && !msrc.kb_articles" }
+
+level: error | warning | note (default warning). A custom rule with the same id
+as a built-in replaces it.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LintFormat {
+    Text,
+    Sarif,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_level")]
+    pub level: String,
+    pub when: String,
+}
+
+fn default_level() -> String {
+    "warning".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RuleFile {
+    One(Rule),
+    Many(Vec<Rule>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub rule: String,
+    pub level: String,
+    pub item: String,
+    pub message: String,
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    let rule = |id: &str, level: &str, description: &str, when: &str| Rule {
+        id: id.to_string(),
+        level: level.to_string(),
+        description: description.to_string(),
+        when: when.to_string(),
+    };
+    vec![
+        rule("kev-missing-vendor", "warning", "KEV-listed item has no vendor", "kev && !vendor"),
+        rule("kev-missing-due-date", "warning", "KEV-listed item has no remediation due date", "kev && !kev_due_date"),
+        rule("critical-without-refs", "error", "Critical item has no references", "severity_bucket == \"critical\" && !refs"),
+        rule("unknown-severity-with-cvss", "error", "Item has a CVSS score but severity_bucket is unknown", "cvss != null && severity_bucket == \"unknown\""),
+    ]
+}
+
+pub fn load_rules(dir: &Path) -> Result<Vec<Rule>> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read rules directory: {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+
+    let mut rules = Vec::new();
+    for path in files {
+        let bytes = input::read_input(&path)?;
+        let file: RuleFile = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse lint rules: {}", path.display()))?;
+        match file {
+            RuleFile::One(r) => rules.push(r),
+            RuleFile::Many(rs) => rules.extend(rs),
+        }
+    }
+    Ok(rules)
+}
+
+/// Built-ins (unless disabled) overridden/extended by custom rules, in a stable order.
+pub fn resolve_rules(custom: Vec<Rule>, builtins: bool) -> Vec<Rule> {
+    let mut rules = if builtins { builtin_rules() } else { Vec::new() };
+    for r in custom {
+        match rules.iter_mut().find(|b| b.id == r.id) {
+            Some(existing) => *existing = r,
+            None => rules.push(r),
+        }
+    }
+    rules
+}
+
+pub fn run(items: &[CanonicalItem], rules: &[Rule]) -> Result<Vec<Finding>> {
+    let mut compiled = Vec::new();
+    for r in rules {
+        if !matches!(r.level.as_str(), "error" | "warning" | "note") {
+            bail!("Lint rule {} has invalid level '{}' (error|warning|note)", r.id, r.level);
+        }
+        let expr = query::parse(&r.when).with_context(|| format!("Lint rule {} has an invalid expression", r.id))?;
+        compiled.push((r, expr));
+    }
+
+    let mut findings = Vec::new();
+    for item in items {
+        let value = serde_json::to_value(item)?;
+        for (rule, expr) in &compiled {
+            if query::eval(expr, &value) {
+                findings.push(Finding {
+                    rule: rule.id.clone(),
+                    level: rule.level.clone(),
+                    item: item.id.clone(),
+                    message: if rule.description.is_empty() { rule.when.clone() } else { rule.description.clone() },
+                });
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// SARIF 2.1.0 log; items are reported as logical locations inside the codex file.
+pub fn to_sarif(findings: &[Finding], rules: &[Rule], input_path: &Path) -> Value {
+    let uri = input_path.to_string_lossy().replace('\\', "/");
+    let rule_meta: Vec<Value> = rules
+        .iter()
+        .map(|r| {
+            json!({
+                "id": r.id,
+                "shortDescription": { "text": if r.description.is_empty() { &r.when } else { &r.description } },
+                "fullDescription": { "text": r.when },
+                "defaultConfiguration": { "level": r.level },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "ruleId": f.rule,
+                "level": f.level,
+                "message": { "text": format!("{}: {}", f.item, f.message) },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": { "uri": uri } },
+                    "logicalLocations": [{ "name": f.item, "kind": "object" }],
+                }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": { "driver": { "name": "bastion-core lint", "rules": rule_meta } },
+            "results": results,
+        }],
+    })
+}
+
+pub fn print_text(findings: &[Finding], rules: &[Rule], total_items: usize) {
+    for r in rules {
+        let hits: Vec<&Finding> = findings.iter().filter(|f| f.rule == r.id).collect();
+        if hits.is_empty() {
+            continue;
+        }
+        println!("[{}] {} ({} items): {}", r.level.to_uppercase(), r.id, hits.len(), hits[0].message);
+        for f in hits.iter().take(20) {
+            println!("    {}", f.item);
+        }
+        if hits.len() > 20 {
+            println!("    ... and {} more", hits.len() - 20);
+        }
+    }
+    log_ok!("lint checked {} items against {} rules: {} findings", total_items, rules.len(), findings.len());
+}
@@ -0,0 +1,174 @@
+use chrono::Utc;
+use clap::ValueEnum;
+use std::{
+    fmt,
+    io::{IsTerminal, Write},
+    sync::atomic::{AtomicU8, Ordering},
+    time::{Duration, Instant},
+};
+
+/* -------------------- Run log -------------------- */
+/*
+Status lines go to stderr, one per event, tagged by level:
+
+  [DEBUG]  --verbose only: per-phase timings, items dropped and why
+  [OK]     what a step did (counts, files written)
+  [WARN]   something was skipped or ignored; the run carries on
+  [FAIL]   a check failed; the command exits non-zero
+
+--quiet keeps only WARN and FAIL. --log-format json writes each line as an
+object for log shippers:
+
+  {"level":"ok","msg":"source nvd: 1203 records in 0.8s","ts":"2025-06-02T06:00:11.204Z"}
+
+Parsing a large feed shows a record counter while it runs, only when stderr is
+a terminal in text mode, so cron mail and CI logs never see it.
+
+The library defaults to text at the normal level; the CLI sets both from its
+global flags.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Level {
+    fn tag(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Ok => "OK",
+            Level::Warn => "WARN",
+            Level::Fail => "FAIL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+// Lowest level shown, and the format (0 = text, 1 = json)
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(Level::Ok as u8);
+static FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Set from --verbose / --quiet / --log-format.
+pub fn init(verbose: bool, quiet: bool, format: LogFormat) {
+    let min = if verbose {
+        Level::Debug
+    } else if quiet {
+        Level::Warn
+    } else {
+        Level::Ok
+    };
+    MIN_LEVEL.store(min as u8, Ordering::Relaxed);
+    FORMAT.store((format == LogFormat::Json) as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 >= MIN_LEVEL.load(Ordering::Relaxed)
+}
+
+fn json() -> bool {
+    FORMAT.load(Ordering::Relaxed) == 1
+}
+
+/// Write one status line; use the log_* macros.
+pub fn emit(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    if json() {
+        let line = serde_json::json!({
+            "ts": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "level": level.tag().to_ascii_lowercase(),
+            "msg": args.to_string(),
+        });
+        eprintln!("{}", line);
+    } else {
+        eprintln!("[{}] {}", level.tag(), args);
+    }
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Level::Debug, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_ok {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Level::Ok, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Level::Warn, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_fail {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Level::Fail, format_args!($($arg)*)) };
+}
+
+/// Elapsed time for "... in 0.8s" lines. wasm32-unknown-unknown has no clock
+/// (Instant::now panics there), so it reads zero.
+pub struct Timer(Option<Instant>);
+
+impl Timer {
+    pub fn start() -> Self {
+        Timer((!cfg!(target_arch = "wasm32")).then(Instant::now))
+    }
+
+    pub fn secs(&self) -> f64 {
+        self.0.map_or(0.0, |t| t.elapsed().as_secs_f64())
+    }
+}
+
+/* -------------------- Progress -------------------- */
+
+const PROGRESS_EVERY: Duration = Duration::from_millis(250);
+
+/// A "label: N records" counter redrawn in place while a feed is parsed.
+pub struct Progress {
+    label: String,
+    count: usize,
+    last_draw: Option<Instant>,
+    live: bool,
+}
+
+impl Progress {
+    pub fn new(label: impl Into<String>) -> Self {
+        let live = !json() && enabled(Level::Ok) && std::io::stderr().is_terminal();
+        Progress { label: label.into(), count: 0, last_draw: None, live }
+    }
+
+    pub fn tick(&mut self) {
+        self.count += 1;
+        // Checking the clock every record would cost more than the parse
+        if !self.live || !self.count.is_multiple_of(1024) {
+            return;
+        }
+        let now = Instant::now();
+        if self.last_draw.is_none_or(|t| now - t >= PROGRESS_EVERY) {
+            self.last_draw = Some(now);
+            let mut err = std::io::stderr().lock();
+            let _ = write!(err, "\r\x1b[K  {}: {} records", self.label, self.count);
+            let _ = err.flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.last_draw.is_some() {
+            let _ = write!(std::io::stderr(), "\r\x1b[K");
+        }
+    }
+}
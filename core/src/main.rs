@@ -1,30 +1,76 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand};
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use std::{collections::{HashMap, HashSet}, fs, path::PathBuf};
+use serde::Serialize;
+use std::{collections::HashMap, ffi::OsString, fs, path::{Path, PathBuf}};
+
+use bastion_codex_core::{
+    archive, attack, bench, burndown, check, codex, config, csaf, cvelist, cwe, cyclonedx, daemon, delta, diff, digest,
+    distro, elastic, errors, exploited, exploits, export, filter, fixtures, fusefs, gate, html, http, input,
+    inspect, internal, kev, lenient, limits, linkcheck, lint, logging, manifest, merge, metrics, msrc, notify,
+    nuclei, nvd, objstore, osv, outname, overdue, overrides, precedence, priority, provenance, query, redact, refs,
+    remote, replay, report, search, serve, service, severity, sign, snapshot, stats, tags, telemetry, trends, tui,
+    vendors, versions, vex, vulnrichment, watchdog, watchlist, log_fail, log_ok, log_warn, parse_iso_datetime,
+    top_n_counts, CanonicalItem, Normalizer, Registry, Source,
+};
 
 #[derive(Parser)]
 #[command(name = "bastion-core", version, about = "Bastion Codex Truth Engine (v1)")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Read option values from a bastion.toml; flags on the command line win (see config.rs)
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Abort the run after this many seconds (exit 124)
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+    /// Abort if resident memory exceeds this many MB (exit 125)
+    #[arg(long, global = true, value_name = "MB")]
+    max_rss_mb: Option<u64>,
+    /// Soft limit on open file descriptors for this process
+    #[arg(long, global = true, value_name = "N")]
+    max_open_files: Option<u64>,
+    /// zstd dictionary for reading dictionary-compressed inputs (see train-dict)
+    #[arg(long, global = true, value_name = "FILE")]
+    zstd_dict: Option<PathBuf>,
+    /// How a failed run reports its error on stderr (see errors.rs)
+    #[arg(long, global = true, value_enum, default_value_t = errors::ErrorFormat::Text)]
+    error_format: errors::ErrorFormat,
+    /// Severity bucket policy JSON (see severity.rs); default is the standard CVSS scale
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "severity_thresholds")]
+    severity_policy: Option<PathBuf>,
+    /// Inline bucket thresholds, most severe first, e.g. "urgent=9.0+kev,critical=9.0,high=7.0,medium=4.0,low=0"
+    #[arg(long, global = true, value_name = "LIST")]
+    severity_thresholds: Option<String>,
+    /// Also log per-phase timings and every item dropped, with the reason
+    #[arg(long, short, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only log warnings and failures
+    #[arg(long, short, global = true)]
+    quiet: bool,
+    /// Status lines on stderr as text or one JSON object per line (see logging.rs)
+    #[arg(long, global = true, value_enum, default_value_t = logging::LogFormat::Text)]
+    log_format: logging::LogFormat,
+    /// Proxy for every HTTP request (default: curl's HTTPS_PROXY / ALL_PROXY / NO_PROXY; see http.rs)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+    /// CA bundle (PEM) for TLS, e.g. a corporate inspecting proxy's root
+    #[arg(long, global = true, value_name = "FILE")]
+    cacert: Option<PathBuf>,
+    /// Retries for transient HTTP failures (no answer, 408, 429, 5xx)
+    #[arg(long, global = true, value_name = "N", default_value_t = http::DEFAULT_RETRIES)]
+    http_retries: u32,
+    /// First retry delay; each further retry waits twice as long (at most 60s)
+    #[arg(long, global = true, value_name = "MS", default_value_t = http::DEFAULT_RETRY_DELAY_MS)]
+    http_retry_delay_ms: u64,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // parsed once at startup
 enum Commands {
     /// Normalize KEV + NVD into canonical items.json
-    Normalize {
-        /// Path to KEV JSON (known_exploited_vulnerabilities.json)
-        #[arg(long)]
-        kev: PathBuf,
-        /// Path to NVD modified JSON (nvdcve-2.0-modified.json)
-        #[arg(long)]
-        nvd: PathBuf,
-        /// Output path for canonical items.json
-        #[arg(long)]
-        out: PathBuf,
-    },
+    Normalize(NormalizeArgs),
         /// Derive priority items and trend summaries from canonical items.json
     Derive {
         /// Input canonical items.json
@@ -36,380 +82,2808 @@ enum Commands {
         /// CVSS threshold for priority inclusion (default: 8.0)
         #[arg(long, default_value_t = 8.0)]
         cvss_threshold: f64,
+        /// Output leaves the team: withhold embargoed items
+        #[arg(long)]
+        shareable: bool,
+    },
+    /// Compare two canonical items.json snapshots
+    Diff {
+        /// Older snapshot
+        #[arg(long, value_name = "FILE")]
+        old: PathBuf,
+        /// Newer snapshot
+        #[arg(long, value_name = "FILE")]
+        new: PathBuf,
+        /// Report format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = diff::DiffFormat::Markdown)]
+        format: diff::DiffFormat,
+    },
+    /// Re-triage triggers over a window: CVSS jumps, newly KEV-listed, newly scored items
+    Trends {
+        /// Snapshot store to walk (see snapshot.rs)
+        #[arg(long, value_name = "DIR", required_unless_present = "old", conflicts_with = "old")]
+        store: Option<PathBuf>,
+        /// How far back from --until to look: 12h, 7d, 30d
+        #[arg(long, default_value = "7d", value_parser = daemon::parse_interval)]
+        window: std::time::Duration,
+        /// End of the window: YYYY-MM-DD (end of that day, UTC) or an ISO timestamp; default now
+        #[arg(long, value_name = "DATE")]
+        until: Option<String>,
+        /// Older snapshot, instead of --store
+        #[arg(long, value_name = "FILE", requires = "new")]
+        old: Option<PathBuf>,
+        /// Newer snapshot
+        #[arg(long, value_name = "FILE", requires = "old")]
+        new: Option<PathBuf>,
+        /// Smallest CVSS increase reported
+        #[arg(long, default_value_t = 2.0)]
+        min_cvss_jump: f64,
+        /// Report format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = trends::TrendsFormat::Markdown)]
+        format: trends::TrendsFormat,
+    },
+    /// Daily open KEV items per severity, within SLA or breached, from the snapshot store (see burndown.rs)
+    BurnDown {
+        /// Snapshot store to read (see snapshot.rs)
+        #[arg(long, value_name = "DIR")]
+        store: PathBuf,
+        /// First day, YYYY-MM-DD (default: the day of the first run)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        /// Last day, YYYY-MM-DD (default: today, UTC)
+        #[arg(long, value_name = "DATE")]
+        until: Option<String>,
+        #[arg(long, value_enum, default_value_t = burndown::BurnDownFormat::Csv)]
+        format: burndown::BurnDownFormat,
+        /// Write the series here instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Union several canonical items.json files (e.g. per-region pipelines) into one
+    Merge {
+        /// Inputs, highest priority first
+        #[arg(required = true, value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+        /// Merged items.json
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Which copy of an ID found in several inputs is kept
+        #[arg(long, value_enum, default_value_t = merge::Prefer::Newest)]
+        prefer: merge::Prefer,
+        #[command(flatten)]
+        output: codex::OutputOptions,
+    },
+    /// Digest of what changed (new KEV, newly critical, CVSS moves) as Markdown, or an HTML page
+    Report {
+        /// Older snapshot (with --new)
+        #[arg(long, value_name = "FILE", requires = "new", conflicts_with = "delta")]
+        old: Option<PathBuf>,
+        /// Newer snapshot (with --old)
+        #[arg(long, value_name = "FILE", requires = "old")]
+        new: Option<PathBuf>,
+        /// Delta file written by normalize --baseline/--since-state
+        #[arg(long, value_name = "FILE", required_unless_present_any = ["old", "input"])]
+        delta: Option<PathBuf>,
+        /// Whole snapshot to render (--format html only)
+        #[arg(long, visible_alias = "in", value_name = "FILE", conflicts_with_all = ["old", "delta"])]
+        input: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = report::ReportFormat::Markdown)]
+        format: report::ReportFormat,
+        /// Only items matching this query expression (see query.rs)
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+        /// Page title for --format html
+        #[arg(long)]
+        title: Option<String>,
+        /// Digest template with {{placeholders}} (see report.rs); built-in default if omitted
+        #[arg(long, value_name = "FILE")]
+        template: Option<PathBuf>,
+        /// Lowest severity bucket listed under "newly <severity>"
+        #[arg(long, default_value = "critical")]
+        severity: String,
+        /// Smallest CVSS score change worth listing
+        #[arg(long, default_value_t = 1.0)]
+        min_cvss_change: f64,
+        /// Max entries per list
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        /// Write the digest here instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// Output leaves the team: withhold embargoed items
+        #[arg(long)]
+        shareable: bool,
+    },
+    /// KEV items past (or close to) their remediation due date, by vendor
+    Overdue {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "items", value_name = "FILE")]
+        input: PathBuf,
+        /// Reference day (YYYY-MM-DD); defaults to today (UTC)
+        #[arg(long, value_name = "DATE")]
+        as_of: Option<chrono::NaiveDate>,
+        /// Also list items due within this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 0)]
+        within: i64,
+        /// Report format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = overdue::OverdueFormat::Markdown)]
+        format: overdue::OverdueFormat,
+    },
+    /// Write per-watchlist JSON Feeds and webhook digests
+    Feeds {
+        /// Input canonical items.json
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+        /// Watchlist definitions (see watchlist.rs)
+        #[arg(long, value_name = "FILE")]
+        watchlists: PathBuf,
+        /// Previous snapshot; only new/changed items are included when given
+        #[arg(long, value_name = "FILE")]
+        old: Option<PathBuf>,
+        /// Output directory (writes <slug>.feed.json and <slug>.digest.json)
+        #[arg(long, value_name = "DIR")]
+        outdir: PathBuf,
+        /// Max items per feed
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+        /// Output leaves the team: withhold embargoed items
+        #[arg(long)]
+        shareable: bool,
+    },
+    /// Filter, project and sort canonical items with a query expression
+    Query {
+        /// Input canonical items.json
+        #[arg(long, value_name = "FILE", required_unless_present = "as_of", conflicts_with = "as_of")]
+        input: Option<PathBuf>,
+        /// Query the snapshot store's last run on or before this date instead (YYYY-MM-DD or ISO timestamp)
+        #[arg(long, value_name = "DATE", requires = "store")]
+        as_of: Option<String>,
+        /// Snapshot store directory for --as-of (see snapshot.rs)
+        #[arg(long, value_name = "DIR")]
+        store: Option<PathBuf>,
+        /// Filter expression, e.g. 'kev == true && severity_bucket in ["critical","high"]'
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+        /// Fields to keep (comma-separated, dotted paths allowed)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+        /// Sort key: field (ascending) or -field / field:desc
+        #[arg(long, value_name = "FIELD", allow_hyphen_values = true)]
+        sort: Option<String>,
+        /// Max results
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Output format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = query::QueryFormat::Json)]
+        format: query::QueryFormat,
+    },
+    /// Build a full-text index over descriptions, vendors, products and refs for `search`
+    Index {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Index file to write (zstd-compressed JSON)
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+    },
+    /// Ranked full-text search over an index built by `index`
+    Search {
+        /// Words, "quoted phrases", field:word (desc, vendor, product, refs, id), -word to exclude
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+        /// Index built by `index`
+        #[arg(long, value_name = "FILE")]
+        index: PathBuf,
+        /// Only KEV-listed items
+        #[arg(long)]
+        kev: bool,
+        /// Only items at least this severe (a severity bucket name)
+        #[arg(long, value_name = "BUCKET")]
+        severity: Option<String>,
+        /// Only items from this vendor (case-insensitive)
+        #[arg(long)]
+        vendor: Option<String>,
+        /// Max results
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Output format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = search::SearchFormat::Text)]
+        format: search::SearchFormat,
+    },
+    /// Browse items.json in the terminal: filterable, sortable list with a detail pane
+    Tui {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+    },
+    /// Archive runs in a deduplicated history store and look items up as of a date
+    Snapshot {
+        /// Snapshot store directory (created if missing, see snapshot.rs)
+        #[arg(long, global = true, value_name = "DIR")]
+        store: Option<PathBuf>,
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Compute priority_score / priority_tier from CVSS, EPSS, KEV, exploits and asset criticality
+    Score {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Weights, asset criticality and tiers (JSON, see priority.rs); defaults otherwise
+        #[arg(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
+        /// FIRST EPSS scores CSV (epss_scores-YYYY-MM-DD.csv.gz), kept on items as `epss`; without it the
+        /// stored scores are used
+        #[arg(long, value_name = "FILE")]
+        epss: Option<PathBuf>,
+        /// Output scored items.json
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        #[command(flatten)]
+        output: codex::OutputOptions,
+    },
+    /// Re-apply enrichment sources to an existing items.json without re-normalizing
+    #[command(group(ArgGroup::new("enrichment").required(true).multiple(true)
+        .args(["epss", "exploitdb", "metasploit", "nuclei_templates", "overrides"])))]
+    Enrich {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// FIRST EPSS scores CSV: recompute priority_score / priority_tier with them
+        #[arg(long, value_name = "FILE")]
+        epss: Option<PathBuf>,
+        /// Priority policy for --epss (see priority.rs); defaults otherwise
+        #[arg(long, value_name = "FILE", requires = "epss")]
+        policy: Option<PathBuf>,
+        /// Exploit-DB index CSV (files_exploits.csv): replaces exploit refs from an earlier index
+        #[arg(long, value_name = "FILE")]
+        exploitdb: Option<PathBuf>,
+        /// Metasploit modules_metadata_base.json: replaces exploit refs from an earlier index
+        #[arg(long, value_name = "FILE")]
+        metasploit: Option<PathBuf>,
+        /// nuclei-templates checkout or cves.json: replaces template refs from an earlier index
+        #[arg(long, value_name = "DIR|FILE")]
+        nuclei_templates: Option<PathBuf>,
+        /// Local overrides (TOML or JSON); pins from an earlier run are undone first.
+        /// Items an earlier run suppressed stay out
+        #[arg(long, value_name = "FILE")]
+        overrides: Option<PathBuf>,
+        /// Output items.json; default: rewrite --input
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        #[command(flatten)]
+        output: codex::OutputOptions,
+    },
+    /// Summary counts over canonical items (severity, KEV, vendors, months, CVSS)
+    Stats {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Rows in the top vendors/products lists
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validate canonical items.json against the published canonical schema
+    Validate {
+        /// Canonical items.json to check
+        #[arg(long, value_name = "FILE", required_unless_present = "print_schema")]
+        input: Option<PathBuf>,
+        /// Print the canonical JSON Schema on stdout and exit
+        #[arg(long)]
+        print_schema: bool,
+        /// Max individual errors listed
+        #[arg(long, default_value_t = 50)]
+        max_errors: usize,
+        /// Print the report as JSON on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rewrite an archived items file in the current schema version
+    Migrate {
+        /// Items file to upgrade (any layout read_items accepts)
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Schema version the file must be in; NDJSON declares none and defaults to 1
+        #[arg(long, value_name = "N")]
+        from: Option<u32>,
+        /// Schema version to write; only the current one is supported
+        #[arg(long, value_name = "N", default_value_t = codex::SCHEMA_VERSION)]
+        to: u32,
+        /// Output items.json; default: rewrite --input
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        #[command(flatten)]
+        output: codex::OutputOptions,
+    },
+    /// Run data-quality lint rules over canonical items
+    Lint {
+        /// Input canonical items.json
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+        /// Directory of custom rule files (*.json, see lint.rs)
+        #[arg(long, value_name = "DIR")]
+        rules: Option<PathBuf>,
+        /// Skip the built-in rules
+        #[arg(long)]
+        no_builtin: bool,
+        /// Report format (printed on stdout)
+        #[arg(long, value_enum, default_value_t = lint::LintFormat::Text)]
+        format: lint::LintFormat,
+    },
+    /// CI gate: match SBOM/lockfile components against the codex, exit nonzero on violations
+    Check {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// CycloneDX/SPDX JSON SBOM, in-toto statement, Cargo.lock or package-lock.json
+        #[arg(long, value_name = "FILE")]
+        sbom: PathBuf,
+        /// Lowest severity bucket that fails the check ("none" to only report)
+        #[arg(long, default_value = "high")]
+        fail_on: String,
+        /// Also fail on any KEV-listed match regardless of severity
+        #[arg(long)]
+        fail_on_kev: bool,
+        /// Annotation format for CI systems
+        #[arg(long, value_enum, default_value_t = check::CiFormat::None)]
+        ci: check::CiFormat,
+        /// Print findings as JSON instead of annotations
+        #[arg(long)]
+        json: bool,
+    },
+    /// Fail a pipeline step when items match a policy's rules (exit code per rule)
+    Gate {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "items", value_name = "FILE")]
+        input: PathBuf,
+        /// Gate rules, TOML or JSON (see gate.rs)
+        #[arg(long, value_name = "FILE")]
+        policy: PathBuf,
+        /// Reference day for newer_than_days (YYYY-MM-DD); defaults to today (UTC)
+        #[arg(long, value_name = "DATE")]
+        as_of: Option<chrono::NaiveDate>,
+        /// Annotation format for CI systems
+        #[arg(long, value_enum, default_value_t = check::CiFormat::None)]
+        ci: check::CiFormat,
+        /// Print rule results as JSON instead of annotations
+        #[arg(long)]
+        json: bool,
+    },
+    /// Emit OpenVEX statements for a product list
+    Vex {
+        /// Product list, one per line (see vex.rs)
+        #[arg(long, value_name = "FILE")]
+        products: PathBuf,
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Output OpenVEX document
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Document author
+        #[arg(long, default_value = "Bastion Codex")]
+        author: String,
+        /// Output leaves the team: withhold embargoed items
+        #[arg(long)]
+        shareable: bool,
+    },
+    /// Validate raw source files against embedded upstream schemas
+    Inspect {
+        /// KEV JSON to inspect
+        #[arg(long, value_name = "FILE")]
+        kev: Option<PathBuf>,
+        /// NVD 2.0 JSON to inspect
+        #[arg(long, value_name = "FILE")]
+        nvd: Option<PathBuf>,
+        /// Max individual anomalies listed per file
+        #[arg(long, default_value_t = 50)]
+        max_errors: usize,
+        /// Print the report as JSON on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Time read / parse / normalize / serialize over feed files, with peak memory (see bench.rs)
+    #[command(group(ArgGroup::new("feeds").required(true).multiple(true).args(["kev", "nvd"])))]
+    Bench {
+        /// KEV JSON
+        #[arg(long, value_name = "FILE")]
+        kev: Option<PathBuf>,
+        /// NVD 2.0 JSON feed files (repeatable, globs allowed)
+        #[arg(long, value_name = "FILE")]
+        nvd: Vec<PathBuf>,
+        /// Runs over the same inputs; min / median / max are reported
+        #[arg(long, default_value_t = bench::DEFAULT_ITERATIONS)]
+        iterations: usize,
+        /// Print the report as JSON on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export canonical items for knowledge-base generators
+    Export {
+        /// Input canonical items.json
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+        /// Export format
+        #[arg(long, value_enum)]
+        format: export::ExportFormat,
+        /// Output directory (hugo/zola: the site's content/ dir; csv, cyclonedx-vdr: output file;
+        /// elasticsearch: write the bulk NDJSON here instead of sending it)
+        #[arg(long, value_name = "PATH", required_unless_present = "url")]
+        out: Option<PathBuf>,
+        /// CSV columns (comma-separated, dotted paths allowed)
+        #[arg(long, value_delimiter = ',', default_values_t = export::DEFAULT_CSV_COLUMNS.map(String::from))]
+        columns: Vec<String>,
+        /// Separator used when a CSV column holds a list (refs, sources, ...)
+        #[arg(long, default_value = "; ")]
+        list_delimiter: String,
+        /// Card body template with {{placeholders}} (see export.rs); built-in default if omitted
+        #[arg(long, value_name = "FILE")]
+        template: Option<PathBuf>,
+        /// Output leaves the team: withhold embargoed items
+        #[arg(long)]
+        shareable: bool,
+        /// elasticsearch: cluster URL (credentials as user:pass@ or BASTION_ES_API_KEY)
+        #[arg(long, value_name = "URL")]
+        url: Option<String>,
+        /// elasticsearch: index name (created with the canonical mapping if missing)
+        #[arg(long, default_value = elastic::DEFAULT_INDEX)]
+        index: String,
+        /// elasticsearch: send only items changed since this state file, delete removed ones; rewritten on success
+        #[arg(long, value_name = "FILE")]
+        since_state: Option<PathBuf>,
+        /// elasticsearch: bulk actions per request
+        #[arg(long, default_value_t = elastic::DEFAULT_BATCH)]
+        batch_size: usize,
+    },
+    /// Train a zstd dictionary over canonical items for snapshot archives
+    TrainDict {
+        /// Canonical items.json snapshots to sample (repeatable)
+        #[arg(long, value_name = "FILE", required = true)]
+        input: Vec<PathBuf>,
+        /// Output dictionary file
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Maximum dictionary size in bytes
+        #[arg(long, default_value_t = archive::DEFAULT_DICT_SIZE)]
+        max_size: usize,
+    },
+    /// zstd-compress a snapshot, optionally with a trained dictionary
+    Compress {
+        /// File to compress (gzip/zstd inputs are recompressed)
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+        /// Output .zst path
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Dictionary from train-dict
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+        /// zstd compression level (1-22)
+        #[arg(long, default_value_t = 19)]
+        level: i32,
+    },
+    /// Generate synthetic KEV/NVD/OSV source files for pipeline testing
+    Fixtures {
+        /// Output directory (writes kev.json, nvd.json, osv/)
+        #[arg(long, value_name = "DIR")]
+        outdir: PathBuf,
+        /// Number of NVD/OSV records to generate
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+        /// Fraction of records carrying an edge case (0.0-1.0)
+        #[arg(long, default_value_t = 0.1)]
+        edge_rate: f64,
+        /// PRNG seed; same seed, same files
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Formats to generate
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [fixtures::FixtureFormat::Kev, fixtures::FixtureFormat::Nvd])]
+        formats: Vec<fixtures::FixtureFormat>,
+    },
+    /// Mount a snapshot read-only as a FUSE filesystem (by-id/, by-severity/, kev/)
+    Mount {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Mount point (an existing, empty directory)
+        #[arg(long, value_name = "DIR")]
+        dir: PathBuf,
+        /// Let other users browse the mount (needs user_allow_other or root)
+        #[arg(long)]
+        allow_other: bool,
+    },
+    /// Re-run an archived bundle and check outputs against its replay.json hashes
+    Replay {
+        /// Bundle directory (inputs plus replay.json, see replay.rs)
+        #[arg(long, value_name = "DIR")]
+        bundle: PathBuf,
+        /// Write this binary's output hashes into the manifest instead of checking
+        #[arg(long)]
+        record: bool,
+        /// Keep outputs in DIR (default: a temp dir removed afterwards)
+        #[arg(long, value_name = "DIR")]
+        keep: Option<PathBuf>,
+    },
+    /// Serve a snapshot over a read-only HTTP/JSON API (/items, /items/{id}, /stats)
+    Serve {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Re-read the file when it changes, checking every SECS seconds
+        #[arg(long, value_name = "SECS")]
+        reload: Option<u64>,
+        /// Also answer gRPC (proto/bastion.proto) on this address
+        #[arg(long, value_name = "HOST:PORT")]
+        grpc_listen: Option<String>,
+        /// Snapshot store (see snapshot.rs) the gRPC Diff call compares the served items against
+        #[arg(long, value_name = "DIR", requires = "grpc_listen")]
+        snapshot_store: Option<PathBuf>,
+    },
+    /// Post new KEV entries and newly critical items to webhooks / Slack (see notify.rs)
+    Notify {
+        /// Targets and their triggers (notify.json)
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+        /// Older snapshot (with --new)
+        #[arg(long, value_name = "FILE", requires = "new", conflicts_with = "delta")]
+        old: Option<PathBuf>,
+        /// Newer snapshot (with --old)
+        #[arg(long, value_name = "FILE", requires = "old")]
+        new: Option<PathBuf>,
+        /// Delta file written by normalize --baseline/--since-state
+        #[arg(long, value_name = "FILE", required_unless_present = "old")]
+        delta: Option<PathBuf>,
+        /// Print the payloads instead of posting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Re-run normalize on a schedule, optionally fetching feeds first and reloading a running serve
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+        #[command(flatten)]
+        args: DaemonArgs,
+    },
+    /// Check a signed output: minisign signatures on it and its manifest, then the manifest digests
+    Verify {
+        /// Canonical items.json (its .minisig and manifest are looked up next to it)
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Trusted minisign public key; repeat to accept any of several (key rotation)
+        #[arg(long, value_name = "FILE", required_unless_present = "keyring")]
+        pubkey: Vec<PathBuf>,
+        /// JSON file of trusted keys with not_before / not_after validity windows (see sign.rs)
+        #[arg(long, value_name = "FILE")]
+        keyring: Option<PathBuf>,
+        /// Run manifest (default: items.manifest.json next to --input)
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Archive a canonical items.json as one run
+    Add {
+        /// Input canonical items.json
+        #[arg(long, visible_alias = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// When the run was taken (default: its manifest's generated_at, else the file's mtime)
+        #[arg(long, value_name = "TIMESTAMP")]
+        taken_at: Option<String>,
+    },
+    /// List archived runs, oldest first
+    List {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print an item as the last run on or before a date had it
+    Show {
+        /// Item ID (CVE-YYYY-NNNN or an internal advisory ID)
+        #[arg(long)]
+        id: String,
+        /// YYYY-MM-DD (end of that day, UTC) or an ISO timestamp
+        #[arg(long, value_name = "DATE")]
+        as_of: String,
+    },
+    /// List the runs in which an item appeared, changed or disappeared
+    History {
+        /// Item ID (CVE-YYYY-NNNN or an internal advisory ID)
+        #[arg(long)]
+        id: String,
+        /// Only changes to this field (repeatable), e.g. severity_bucket
+        #[arg(long = "field", value_name = "FIELD")]
+        fields: Vec<String>,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// List an item's EPSS score at each run where it moved (runs scored with --epss)
+    EpssTrend {
+        /// Item ID (CVE-YYYY-NNNN or an internal advisory ID)
+        #[arg(long)]
+        id: String,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apply a retention policy: drop superseded and old runs, and objects no kept run uses
+    Prune {
+        /// Keep the newest run per day for this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 90)]
+        daily_days: i64,
+        /// Then the newest run per ISO week up to this age; older runs are dropped
+        #[arg(long, value_name = "DAYS", default_value_t = 730)]
+        weekly_days: i64,
+        /// List what would be dropped
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Register the daemon as a systemd unit or a Windows boot task that restarts on failure (see service.rs)
+    Install {
+        #[command(flatten)]
+        args: DaemonArgs,
+        /// Service manager (default: systemd, or windows on Windows)
+        #[arg(long, value_enum)]
+        kind: Option<service::Kind>,
+        /// Unit / task name
+        #[arg(long, default_value = service::DEFAULT_NAME)]
+        name: String,
+        /// systemd user unit (~/.config/systemd/user) instead of a system one
+        #[arg(long)]
+        user: bool,
+        /// Directory the daemon runs in (default: the current one)
+        #[arg(long, value_name = "DIR")]
+        working_dir: Option<PathBuf>,
+        /// Seconds to wait before a restart after the daemon exits with an error
+        #[arg(long, value_name = "SECS", default_value_t = service::DEFAULT_RESTART_SECS)]
+        restart_sec: u64,
+        /// Register without starting it now
+        #[arg(long)]
+        no_start: bool,
+        /// Print the unit / task XML instead of installing it
+        #[arg(long)]
+        print: bool,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct CanonicalItem {
-    id: String,                      // CVE-YYYY-NNNN
-    sources: Vec<String>,            // ["kev","nvd"]
-    published: Option<String>,       // ISO8601
-    last_modified: Option<String>,   // ISO8601
-    cvss: Option<f64>,
-    severity_bucket: String,         // low|medium|high|critical|unknown
-    kev: bool,
-    short_desc: String,
-    vendor: Option<String>,
-    product: Option<String>,
-    refs: Vec<String>,
-}
-
-fn bucket_cvss(cvss: Option<f64>) -> String {
-    match cvss {
-        None => "unknown".to_string(),
-        Some(s) if s >= 9.0 => "critical".to_string(),
-        Some(s) if s >= 7.0 => "high".to_string(),
-        Some(s) if s >= 4.0 => "medium".to_string(),
-        Some(_) => "low".to_string(),
-    }
-}
-
-/* -------------------- KEV parsing -------------------- */
-
-#[derive(Debug, Deserialize)]
-struct KevRoot {
-    #[serde(default)]
-    vulnerabilities: Vec<KevVuln>,
-}
-
-#[derive(Debug, Deserialize)]
-struct KevVuln {
-    #[serde(rename = "cveID")]
-    cve_id: String,
-    #[serde(default)]
-    notes: Option<String>,
-    #[serde(default)]
-    product: Option<String>,
-    #[serde(default)]
-    vendorProject: Option<String>,
-    #[serde(default)]
-    dateAdded: Option<String>,
-    #[serde(default)]
-    dueDate: Option<String>,
-    #[serde(default)]
-    knownRansomwareCampaignUse: Option<String>,
-    #[serde(default)]
-    shortDescription: Option<String>,
-    #[serde(default)]
-    requiredAction: Option<String>,
-}
-
-/* -------------------- NVD parsing (minimal, tolerant) -------------------- */
-/*
-NVD 2.0 feed format can evolve; we parse only what we need.
-
-We target:
-- vulnerabilities[].cve.id
-- vulnerabilities[].cve.published
-- vulnerabilities[].cve.lastModified
-- vulnerabilities[].cve.descriptions[] { lang, value }
-- vulnerabilities[].cve.metrics.* (extract best available baseScore)
-- vulnerabilities[].cve.references[] { url }
-*/
-
-#[derive(Debug, Deserialize)]
-struct NvdRoot {
-    #[serde(default)]
-    vulnerabilities: Vec<NvdVulnWrap>,
-}
-
-#[derive(Debug, Deserialize)]
-struct NvdVulnWrap {
-    cve: NvdCve,
-}
-
-#[derive(Debug, Deserialize)]
-struct NvdCve {
-    id: String,
-    #[serde(default)]
-    published: Option<String>,
-    #[serde(default)]
-    lastModified: Option<String>,
-    #[serde(default)]
-    descriptions: Vec<NvdLangValue>,
-    #[serde(default)]
-    references: Vec<NvdRef>,
-    #[serde(default)]
-    metrics: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Deserialize)]
-struct NvdLangValue {
-    #[serde(default)]
-    lang: Option<String>,
-    #[serde(default)]
-    value: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct NvdRef {
-    #[serde(default)]
-    url: Option<String>,
-}
-
-fn pick_english_description(descs: &[NvdLangValue]) -> String {
-    // prefer lang == "en"
-    for d in descs {
-        if d.lang.as_deref() == Some("en") {
-            if let Some(v) = &d.value {
-                if !v.trim().is_empty() {
-                    return v.trim().to_string();
-                }
-            }
+#[derive(Args)]
+struct DaemonArgs {
+    /// Time between cycle starts, e.g. 90s, 30m, 6h, 1d
+    #[arg(long, value_parser = daemon::parse_interval, required = true)]
+    interval: Option<std::time::Duration>,
+    /// Shell command that refreshes the raw feeds before each normalize
+    #[arg(long, value_name = "CMD")]
+    fetch_cmd: Option<String>,
+    /// After each successful cycle, ask the serve at HOST:PORT to reload
+    #[arg(long, value_name = "HOST:PORT")]
+    notify_serve: Option<String>,
+    /// Stop after this many failed cycles in a row (0 = never)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    max_failures: u32,
+    /// Serve Prometheus metrics at http://HOST:PORT/metrics (see metrics.rs)
+    #[arg(long, value_name = "HOST:PORT")]
+    metrics_listen: Option<String>,
+    /// normalize arguments, after `--` (added to the config's [normalize], if any)
+    #[arg(last = true, value_name = "NORMALIZE_ARGS")]
+    normalize: Vec<String>,
+}
+
+impl DaemonArgs {
+    /// The daemon flags as a command line, for a service definition.
+    fn argv(&self, interval: std::time::Duration) -> Vec<String> {
+        let mut argv = vec!["daemon".to_string(), "--interval".to_string(), daemon::human(interval)];
+        let flags = [("--fetch-cmd", &self.fetch_cmd), ("--notify-serve", &self.notify_serve)];
+        for (flag, value) in flags.into_iter().chain([("--metrics-listen", &self.metrics_listen)]) {
+            if let Some(value) = value {
+                argv.extend([flag.to_string(), value.clone()]);
+            }
+        }
+        argv.extend(["--max-failures".to_string(), self.max_failures.to_string()]);
+        if !self.normalize.is_empty() {
+            argv.push("--".to_string());
+            argv.extend(self.normalize.iter().cloned());
+        }
+        argv
+    }
+}
+
+/// `daemon -- <args>` re-parses its normalize arguments each cycle.
+#[derive(Parser)]
+#[command(name = "normalize")]
+struct NormalizeCli {
+    #[command(flatten)]
+    args: NormalizeArgs,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("feeds").required(true).multiple(true).args(["kev", "nvd", "sources", "all_profiles"])))]
+struct NormalizeArgs {
+    /// Path to KEV JSON (known_exploited_vulnerabilities.json; gzip/zstd accepted).
+    /// Any subset of --kev, --nvd and --source will do; items carry what those feeds know
+    #[arg(long)]
+    kev: Option<PathBuf>,
+    /// NVD 2.0 JSON feed(s): repeat or use globs (nvdcve-2.0-*.json[.gz|.zst]).
+    /// Duplicate CVEs keep the record with the newest lastModified.
+    #[arg(long)]
+    nvd: Vec<PathBuf>,
+    /// Preferred NVD description languages, in order (e.g. es,en); falls back to the first available
+    #[arg(long, value_delimiter = ',', value_name = "LANG,...", default_value = "en")]
+    lang: Vec<String>,
+    /// Also keep every NVD description variant in item.descriptions (lang -> text)
+    #[arg(long)]
+    all_descriptions: bool,
+    /// Extra feed as KIND:ARG, merged after NVD and KEV in the order given; repeatable.
+    /// exec:NAME=COMMAND reads items as JSON lines from a command (see exec.rs)
+    #[arg(long = "source", value_name = "KIND:ARG")]
+    sources: Vec<String>,
+    /// Output path for canonical items.json (or sftp://[user@]host[:port]/path, s3://bucket/key,
+    /// gs://bucket/key, az://account/container/key); may contain {date}, {datetime}, {source_hash},
+    /// {source_hash_short} (see outname.rs)
+    #[arg(long, required_unless_present = "all_profiles")]
+    out: Option<PathBuf>,
+    /// Stable path to point at the written output (e.g. snapshots/items-latest.json); remote
+    /// with a remote --out, where it is a copy promoted after every upload (see objstore.rs)
+    #[arg(long, value_name = "FILE")]
+    latest: Option<PathBuf>,
+    /// How --latest refers to the output
+    #[arg(long, value_enum, default_value_t = outname::LatestMode::Symlink)]
+    latest_mode: outname::LatestMode,
+    /// Server-side encryption for object storage outputs
+    #[arg(long, value_enum)]
+    sse: Option<objstore::Sse>,
+    /// KMS key for --sse kms (S3 key ID/ARN, GCS CMEK key name, Azure encryption scope)
+    #[arg(long, value_name = "KEY", requires = "sse")]
+    sse_kms_key: Option<String>,
+    /// Source order for scalar fields, highest first (e.g. kev,nvd); unlisted sources follow
+    /// in feed order (see precedence.rs)
+    #[arg(long, value_name = "SOURCE,...")]
+    source_priority: Option<String>,
+    /// Source order for one field, replacing --source-priority there (e.g. cvss=csaf,nvd); repeatable
+    #[arg(long, value_name = "FIELD=SOURCE,...")]
+    field_priority: Vec<String>,
+    /// Record which source (and source timestamp) set each field, in item.provenance
+    #[arg(long)]
+    with_provenance: bool,
+    /// Skip feed records that don't parse, even as JSON, and list them in <out>.errors.json; an unbalanced
+    /// bracket or quote (a truncated file) still fails the run (see lenient.rs)
+    #[arg(long)]
+    lenient: bool,
+    /// Optional cvelistV5 checkout directory (or zip) for CNA titles/scores/affected products; adds CNA-only CVEs
+    #[arg(long, value_name = "DIR|ZIP")]
+    cvelist: Option<PathBuf>,
+    /// Optional directory of CSAF 2.0 vendor advisories
+    #[arg(long, value_name = "DIR")]
+    csaf: Option<PathBuf>,
+    /// Optional CISA vulnrichment checkout directory (or zip) for SSVC decision points
+    #[arg(long, value_name = "DIR|ZIP")]
+    vulnrichment: Option<PathBuf>,
+    /// Optional Debian security tracker JSON
+    #[arg(long, value_name = "FILE")]
+    debian: Option<PathBuf>,
+    /// Optional Ubuntu USN database JSON
+    #[arg(long, value_name = "FILE")]
+    ubuntu_usn: Option<PathBuf>,
+    /// Optional Alpine secdb JSON (repeat per release/repo)
+    #[arg(long, value_name = "FILE")]
+    alpine_secdb: Vec<PathBuf>,
+    /// Optional Red Hat / SUSE OVAL definitions, XML or .xml.gz (repeat per stream)
+    #[arg(long, value_name = "FILE")]
+    oval: Vec<PathBuf>,
+    /// Optional OSV records (directory, zip or file) for package version ranges in affected_versions
+    #[arg(long, value_name = "DIR|ZIP|FILE")]
+    osv: Option<PathBuf>,
+    /// Optional MSRC CVRF JSON document (or directory of monthly documents)
+    #[arg(long, value_name = "FILE|DIR")]
+    msrc: Option<PathBuf>,
+    /// Optional Exploit-DB index CSV (files_exploits.csv)
+    #[arg(long, value_name = "FILE")]
+    exploitdb: Option<PathBuf>,
+    /// Optional Metasploit modules_metadata_base.json
+    #[arg(long, value_name = "FILE")]
+    metasploit: Option<PathBuf>,
+    /// Optional nuclei-templates checkout (or its cves.json index): flags has_nuclei_template
+    #[arg(long, value_name = "DIR|FILE")]
+    nuclei_templates: Option<PathBuf>,
+    /// Optional internal advisories (JSON file or directory); may carry embargoed_until
+    #[arg(long, value_name = "FILE|DIR")]
+    internal_advisories: Option<PathBuf>,
+    /// Optional exploited-in-the-wild feed (VulnCheck KEV, community list; JSON or CSV), as
+    /// [NAME=]PATH; repeatable. Listings go to item.exploited (see exploited.rs)
+    #[arg(long, value_name = "[NAME=]PATH", value_parser = exploited::parse_feed)]
+    exploited_feed: Vec<exploited::ExploitedFeed>,
+    /// Optional GreyNoise GNQL export (JSON or JSON lines) for observed exploitation
+    #[arg(long, value_name = "FILE")]
+    greynoise: Option<PathBuf>,
+    /// Optional Shodan download export (JSON lines, gzip accepted) for exposure counts
+    #[arg(long, value_name = "FILE")]
+    shodan: Option<PathBuf>,
+    /// Roll CWEs up to OWASP Top 10 / CWE Top 25 categories (bundled mapping)
+    #[arg(long)]
+    cwe_rollup: bool,
+    /// Custom CWE -> category mapping for the rollup (see cwe.rs); implies --cwe-rollup
+    #[arg(long, value_name = "FILE")]
+    cwe_mapping: Option<PathBuf>,
+    /// Optional CVE -> ATT&CK mapping dataset (Mappings Explorer JSON or attack_to_cve CSV)
+    #[arg(long, value_name = "FILE")]
+    attack_mappings: Option<PathBuf>,
+    /// Map free-text vendor/product names onto canonical ones (bundled alias dictionary)
+    #[arg(long)]
+    normalize_vendors: bool,
+    /// Extra vendor/product aliases layered over the bundled set (see vendors.rs); implies --normalize-vendors
+    #[arg(long, value_name = "FILE")]
+    vendor_aliases: Option<PathBuf>,
+    /// NVD CPE dictionary (JSON or XML) to cross-check normalized vendors against; implies --normalize-vendors
+    #[arg(long, value_name = "FILE")]
+    cpe_dictionary: Option<PathBuf>,
+    /// Local overrides (TOML or JSON): pin fields, add internal notes, suppress items
+    #[arg(long, value_name = "FILE")]
+    overrides: Option<PathBuf>,
+    /// Tagging rules (TOML or JSON): regex/keyword patterns over text fields -> item.tags
+    #[arg(long, value_name = "FILE")]
+    tag_rules: Option<PathBuf>,
+    #[command(flatten)]
+    filter: filter::ItemFilter,
+    #[command(flatten)]
+    check: linkcheck::CheckOptions,
+    #[command(flatten)]
+    output: codex::OutputOptions,
+    /// Prior canonical items.json to update incrementally: records newer than the stored
+    /// last_modified replace it, everything else (incl. items outside the feed window) is kept;
+    /// this run's enrichment inputs and KEV catalog apply to kept items too
+    #[arg(long, value_name = "FILE")]
+    merge_into: Option<PathBuf>,
+    /// Also write <out>.delta.json with items added/changed/removed since this prior output
+    #[arg(long, value_name = "FILE", conflicts_with = "since_state", group = "previous")]
+    baseline: Option<PathBuf>,
+    /// Like --baseline, from a small id -> content_hash state file that is rewritten after the run
+    #[arg(long, value_name = "FILE", group = "previous")]
+    since_state: Option<PathBuf>,
+    /// Post new KEV / newly critical items from the delta to the webhooks in FILE (see notify.rs)
+    #[arg(long, value_name = "FILE", requires = "previous")]
+    notify: Option<PathBuf>,
+    /// Worker threads for NVD record conversion (0 = one per core)
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+    /// Per-item serialized size limit in bytes (oversized items get list fields truncated)
+    #[arg(long, value_name = "BYTES")]
+    max_item_bytes: Option<usize>,
+    /// Max entries kept per list field when an item exceeds --max-item-bytes
+    #[arg(long, default_value_t = 200)]
+    max_list_len: usize,
+    /// What to do with truncated entries
+    #[arg(long, value_enum, default_value_t = limits::TruncateStrategy::Cap)]
+    truncate: limits::TruncateStrategy,
+    /// minisign secret key: write detached .minisig signatures for the output and its manifest
+    #[arg(long, value_name = "FILE")]
+    sign_key: Option<PathBuf>,
+    /// Archive this run's items into the snapshot store in DIR (see snapshot.rs)
+    #[arg(long, value_name = "DIR")]
+    snapshot_store: Option<PathBuf>,
+    /// Take options from [profile.NAME] in the --config file, then from [normalize] (see config.rs)
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Run once per [profile.<name>] in the --config file; profiles reading the same feeds share one parse
+    #[arg(long, conflicts_with = "profile")]
+    all_profiles: bool,
+}
+
+/* -------------------- Main normalize logic -------------------- */
+
+fn main() -> Result<()> {
+    let raw: Vec<OsString> = std::env::args_os().collect();
+    let mut command = Cli::command();
+    command.build();
+    let mut argv = raw.clone();
+    if let Some(config) = config::Config::from_argv(&command, &argv)? {
+        argv = config.apply(&command, argv)?;
+    }
+    let cli = Cli::parse_from(argv);
+    let error_format = cli.error_format;
+    let result = run(cli, &raw);
+    if let Err(err) = &result {
+        match error_format {
+            errors::ErrorFormat::Json => errors::print_json(err),
+            errors::ErrorFormat::Text if errors::exit_code(err) != 1 => eprintln!("Error: {:?}", err),
+            errors::ErrorFormat::Text => return result,
+        }
+        std::process::exit(errors::exit_code(err));
+    }
+    result
+}
+
+/// `raw` is the command line before --config filled it in, for --all-profiles.
+fn run(cli: Cli, raw: &[OsString]) -> Result<()> {
+    logging::init(cli.verbose, cli.quiet, cli.log_format);
+    if cli.timeout.is_some() && matches!(cli.command, Commands::Daemon { action: None, .. }) {
+        anyhow::bail!("--timeout would stop the whole daemon; it applies to one-shot commands only");
+    }
+    watchdog::install(watchdog::RunLimits {
+        timeout: cli.timeout.map(std::time::Duration::from_secs),
+        max_rss_mb: cli.max_rss_mb,
+        max_open_files: cli.max_open_files,
+    })?;
+    if let Some(dict) = &cli.zstd_dict {
+        input::set_zstd_dictionary(dict)?;
+    }
+    http::configure(http::Settings {
+        proxy: cli.proxy.clone(),
+        cacert: cli.cacert.clone(),
+        retries: cli.http_retries,
+        retry_delay_ms: cli.http_retry_delay_ms,
+    });
+    if let Some(path) = &cli.severity_policy {
+        severity::set_policy(severity::SeverityPolicy::load(path)?);
+    } else if let Some(spec) = &cli.severity_thresholds {
+        severity::set_policy(severity::SeverityPolicy::parse_thresholds(spec)?);
+    }
+
+    let result = match cli.command {
+        Commands::Normalize(args) if args.all_profiles => {
+            let config = cli.config.as_deref().map(config::Config::load).transpose()?;
+            let config = config.context("--all-profiles needs a --config file with [profile.<name>] sections")?;
+            let mut command = Cli::command();
+            command.build();
+            all_profiles_cmd(&config, true, |name| {
+                let argv = with_profile(raw, name);
+                match Cli::try_parse_from(config.apply(&command, argv)?)?.command {
+                    Commands::Normalize(args) => Ok(args),
+                    _ => unreachable!("normalize command line"),
+                }
+            })
+        }
+        Commands::Normalize(args) if args.profile.is_some() && cli.config.is_none() => {
+            anyhow::bail!("--profile needs a --config file with [profile.<name>] sections")
+        }
+        Commands::Normalize(args) => normalize_cmd(args),
+        Commands::Derive { input, outdir, cvss_threshold, shareable } => {
+            derive_cmd(input, outdir, cvss_threshold, shareable)
+        }
+        Commands::Diff { old, new, format } => diff_cmd(old, new, format),
+        Commands::Trends { store, window, until, old, new, min_cvss_jump, format } => {
+            trends_cmd(store, window, until, old.zip(new), min_cvss_jump, format)
+        }
+        Commands::BurnDown { store, since, until, format, out } => burn_down_cmd(store, since, until, format, out),
+        Commands::Merge { inputs, out, prefer, output } => merge_cmd(inputs, out, prefer, output),
+        Commands::Report {
+            old,
+            new,
+            delta,
+            input,
+            format,
+            filter,
+            title,
+            template,
+            severity,
+            min_cvss_change,
+            limit,
+            out,
+            shareable,
+        } => {
+            let opts = report::ReportOptions { format, filter, title, template, severity, min_cvss_change, limit };
+            let source = ReportSource { snapshots: old.zip(new), delta, input };
+            report_cmd(source, opts, out, shareable)
+        }
+        Commands::Overdue { input, as_of, within, format } => overdue_cmd(input, as_of, within, format),
+        Commands::Feeds { input, watchlists, old, outdir, limit, shareable } => {
+            feeds_cmd(input, watchlists, old, outdir, limit, shareable)
+        }
+        Commands::Index { input, out } => index_cmd(input, out),
+        Commands::Search { query, index, kev, severity, vendor, limit, format } => {
+            search_cmd(index, &query, search::SearchFilter { kev, min_severity: severity, vendor }, limit, format)
+        }
+        Commands::Tui { input } => tui_cmd(input),
+        Commands::Snapshot { store, action } => snapshot_cmd(store, action),
+        Commands::Query { input, as_of, store, filter, fields, sort, limit, format } => {
+            let from = match (input, as_of, store) {
+                (Some(path), _, _) => QueryInput::File(path),
+                (None, Some(as_of), Some(store)) => QueryInput::AsOf { store, as_of },
+                _ => anyhow::bail!("query needs --input FILE or --store DIR --as-of DATE"),
+            };
+            query_cmd(from, filter, fields, sort, limit, format)
+        }
+        Commands::Score { input, policy, epss, out, output } => score_cmd(input, policy, epss, out, output),
+        Commands::Enrich {
+            input,
+            epss,
+            policy,
+            exploitdb,
+            metasploit,
+            nuclei_templates,
+            overrides,
+            out,
+            output,
+        } => {
+            let sources = EnrichSources { epss, policy, exploitdb, metasploit, nuclei_templates, overrides };
+            enrich_cmd(input, sources, out, output)
+        }
+        Commands::Stats { input, top, json } => stats_cmd(input, top, json),
+        Commands::Validate { input, print_schema, max_errors, json } => {
+            validate_cmd(input, print_schema, max_errors, json)
+        }
+        Commands::Migrate { input, from, to, out, output } => migrate_cmd(input, from, to, out, output),
+        Commands::Lint { input, rules, no_builtin, format } => lint_cmd(input, rules, no_builtin, format),
+        Commands::Gate { input, policy, as_of, ci, json } => gate_cmd(input, policy, as_of, ci, json),
+        Commands::Check { input, sbom, fail_on, fail_on_kev, ci, json } => {
+            check_cmd(input, sbom, fail_on, fail_on_kev, ci, json)
+        }
+        Commands::Vex { products, input, out, author, shareable } => vex_cmd(products, input, out, author, shareable),
+        Commands::Inspect { kev, nvd, max_errors, json } => inspect_cmd(kev, nvd, max_errors, json),
+        Commands::Bench { kev, nvd, iterations, json } => bench_cmd(kev, nvd, iterations, json),
+        Commands::Export {
+            input,
+            format,
+            out,
+            columns,
+            list_delimiter,
+            template,
+            shareable,
+            url,
+            index,
+            since_state,
+            batch_size,
+        } => {
+            let csv = export::CsvOptions { columns, list_delimiter };
+            if format == export::ExportFormat::Elasticsearch {
+                let es = elastic::EsOptions {
+                    url: url.as_deref(),
+                    index: &index,
+                    batch: batch_size,
+                    since_state: since_state.as_deref(),
+                    out: out.as_deref(),
+                };
+                return elasticsearch_export_cmd(input, &es, shareable);
+            }
+            let Some(out) = out else { anyhow::bail!("--out is required unless --format elasticsearch") };
+            export_cmd(input, format, out, csv, template, shareable)
+        }
+        Commands::TrainDict { input, out, max_size } => train_dict_cmd(input, out, max_size),
+        Commands::Compress { input, out, dict, level } => compress_cmd(input, out, dict, level),
+        Commands::Fixtures { outdir, count, edge_rate, seed, formats } => {
+            fixtures_cmd(outdir, fixtures::FixtureSpec { count, edge_rate, seed, formats })
+        }
+        Commands::Replay { bundle, record, keep } => replay_cmd(bundle, record, keep),
+        Commands::Serve { input, listen, reload, grpc_listen, snapshot_store } => {
+            serve_cmd(input, listen, reload, grpc_listen, snapshot_store)
+        }
+        Commands::Notify { config, old, new, delta, dry_run } => {
+            notify_cmd(config, old.zip(new), delta, dry_run)
+        }
+        Commands::Daemon { action: None, args } => {
+            let config = cli.config.as_deref().map(config::Config::load).transpose()?;
+            let DaemonArgs { interval, fetch_cmd, notify_serve, max_failures, metrics_listen, normalize } = args;
+            let interval = interval.context("--interval is required")?;
+            let schedule = daemon::Schedule { interval, fetch_cmd, notify_serve, max_failures, metrics_listen };
+            daemon_cmd(schedule, normalize, config)
+        }
+        Commands::Daemon {
+            action: Some(DaemonAction::Install { args, kind, name, user, working_dir, restart_sec, no_start, print }),
+            ..
+        } => {
+            let kind = kind.unwrap_or_else(service::Kind::native);
+            let options = (name, user, working_dir, restart_sec);
+            daemon_install_cmd(args, cli.config.as_deref(), kind, options, !no_start, print)
+        }
+        Commands::Verify { input, pubkey, keyring, manifest } => verify_cmd(input, pubkey, keyring, manifest),
+        Commands::Mount { input, dir, allow_other } => mount_cmd(input, dir, allow_other),
+    };
+    // Closes the last phase's timing
+    watchdog::phase("done");
+    result
+}
+
+/// Every file normalize read, in flag order, for the run manifest.
+fn normalize_inputs(
+    args: &NormalizeArgs,
+    nvd_paths: &[PathBuf],
+    extra: &[Box<dyn Source>],
+) -> Result<Vec<manifest::InputDigest>> {
+    let mut inputs: Vec<(&str, &PathBuf)> = args.kev.iter().map(|p| ("kev", p)).collect();
+    inputs.extend(nvd_paths.iter().map(|p| ("nvd", p)));
+    let extra_inputs: Vec<(&str, Vec<PathBuf>)> = extra.iter().map(|s| (s.name(), s.inputs())).collect();
+    for (name, paths) in &extra_inputs {
+        inputs.extend(paths.iter().map(|p| (*name, p)));
+    }
+    let optional = [
+        ("cvelist", &args.cvelist),
+        ("csaf", &args.csaf),
+        ("vulnrichment", &args.vulnrichment),
+        ("debian", &args.debian),
+        ("ubuntu-usn", &args.ubuntu_usn),
+        ("osv", &args.osv),
+        ("msrc", &args.msrc),
+        ("exploitdb", &args.exploitdb),
+        ("metasploit", &args.metasploit),
+        ("nuclei-templates", &args.nuclei_templates),
+        ("internal-advisories", &args.internal_advisories),
+        ("greynoise", &args.greynoise),
+        ("shodan", &args.shodan),
+        ("cwe-mapping", &args.cwe_mapping),
+        ("attack-mappings", &args.attack_mappings),
+        ("vendor-aliases", &args.vendor_aliases),
+        ("cpe-dictionary", &args.cpe_dictionary),
+        ("overrides", &args.overrides),
+        ("tag-rules", &args.tag_rules),
+        ("merge-into", &args.merge_into),
+        ("baseline", &args.baseline),
+    ];
+    for (role, path) in optional {
+        if let Some(path) = path {
+            inputs.push((role, path));
+        }
+    }
+    inputs.extend(args.alpine_secdb.iter().map(|p| ("alpine-secdb", p)));
+    inputs.extend(args.oval.iter().map(|p| ("oval", p)));
+    inputs.extend(args.exploited_feed.iter().map(|f| ("exploited-feed", &f.path)));
+    // Absent on the first run
+    if let Some(state) = args.since_state.as_ref().filter(|p| p.exists()) {
+        inputs.push(("since-state", state));
+    }
+    inputs.into_iter().map(|(role, path)| manifest::input(role, path)).collect()
+}
+
+fn normalize_cmd(args: NormalizeArgs) -> Result<()> {
+    if args.threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global()
+            .with_context(|| "Failed to configure worker threads")?;
+    }
+
+    let out_path = args.out.as_ref().context("--out is required")?;
+    // Checked up front so a bad config fails before the feeds are parsed
+    let notify_targets = args.notify.as_deref().map(notify::load).transpose()?;
+    let merge_policy = precedence::MergePolicy::parse(args.source_priority.as_deref(), &args.field_priority)?;
+    let keep = (!args.filter.is_empty()).then(|| args.filter.matcher()).transpose()?;
+    let nvd_paths = input::expand_globs(&args.nvd)?;
+    lenient::begin(args.lenient);
+    watchdog::phase("normalize: reading KEV");
+    // Air-gapped mirrors may carry only some feeds; a missing one just contributes nothing
+    let kev = args.kev.as_deref().map(kev::KevSource::load).transpose()?;
+    let registry = Registry::builtin();
+    let extra: Vec<Box<dyn Source>> = args.sources.iter().map(|spec| registry.create(spec)).collect::<Result<_>>()?;
+
+    // NVD first: its dates, CVSS and description win; KEV adds its flag, fields and
+    // the items NVD's snapshot doesn't have yet; --source feeds fill what's left
+    watchdog::phase("normalize: parsing NVD");
+    let feeds = &(&nvd_paths, &args.kev, &args.lang, args.all_descriptions, &args.sources, args.lenient);
+    let shared_key = format!("{:?} {:?} {:?}", feeds, args.source_priority, args.field_priority);
+    let (mut items, skipped) = match shared_sources_get(&shared_key) {
+        Some(shared) => {
+            log_ok!("sources: reusing the previous profile's {} merged items", shared.0.len());
+            lenient::take();
+            shared
+        }
+        None => {
+            let nvd = (!nvd_paths.is_empty())
+                .then(|| nvd::NvdSource::new(nvd_paths.clone()).languages(args.lang.clone(), args.all_descriptions));
+            let mut normalizer = Normalizer::new();
+            if let Some(policy) = &merge_policy {
+                normalizer = normalizer.policy(policy);
+            }
+            if let Some(nvd) = &nvd {
+                normalizer = normalizer.source(nvd);
+            }
+            if let Some(kev) = &kev {
+                normalizer = normalizer.source(kev);
+            }
+            for source in &extra {
+                normalizer = normalizer.source(source.as_ref());
+            }
+            let merged = (normalizer.normalize()?, lenient::take());
+            shared_sources_put(&shared_key, &merged);
+            merged
+        }
+    };
+    if !skipped.is_empty() {
+        log_warn!("lenient: skipped {} records that failed to parse", skipped.len());
+    }
+
+    // Per-field sources from here on; NVD/KEV attribution is known as items are built
+    let mut prov = None;
+    if args.with_provenance {
+        let kev_as_of = kev.as_ref().and_then(|k| k.date_released());
+        provenance::seed(&mut items, kev_as_of, |i| {
+            kev.as_ref().and_then(|k| k.note(&i.id)) == Some(i.short_desc.as_str())
+        });
+        prov = Some(provenance::Tracker::new(&items));
+    }
+    let file_time = |path: &PathBuf| provenance::input_time([path.as_path()]);
+
+    // Incremental update: fold this run into the prior canonical output. Before the
+    // enrichment passes, so kept items get this run's exploits, advisories, feeds, ...
+    if let Some(prior_path) = &args.merge_into {
+        watchdog::phase("normalize: merging into prior items");
+        let prior = codex::read_items(prior_path)?;
+        let kev_ids = kev.as_ref().map(|k| k.ids());
+        let stats = codex::merge_into_prior(prior, &mut items, kev_ids.as_ref());
+        log_ok!(
+            "merge-into {}: {} updated, {} added, {} kept unchanged",
+            prior_path.display(),
+            stats.updated,
+            stats.added,
+            stats.kept
+        );
+        if let Some(tracker) = &mut prov {
+            tracker.rebase(&mut items, "merge-into", file_time(prior_path));
+        }
+    }
+    watchdog::phase("normalize: merging enrichment sources");
+
+    // Fill gaps from CNA records (cvelistV5) for items NVD hasn't enriched yet, and add the ones it lacks
+    if let Some(path) = &args.cvelist {
+        let (added, merged) = cvelist::merge_cvelist(path, &mut items)?;
+        log_ok!("cvelist: {} added, {} enriched from {}", added, merged, path.display());
+        provenance::stage(&mut prov, &mut items, "cvelist", file_time(path));
+    }
+
+    // CISA SSVC decision points from vulnrichment ADP containers
+    if let Some(path) = &args.vulnrichment {
+        let merged = vulnrichment::merge_vulnrichment(path, &mut items)?;
+        log_ok!("vulnrichment enriched {} items from {}", merged, path.display());
+        provenance::stage(&mut prov, &mut items, "vulnrichment", file_time(path));
+    }
+
+    // Vendor CSAF advisories are kept next to the NVD view, not merged over it
+    if let Some(dir) = &args.csaf {
+        let merged = csaf::merge_csaf(dir, &mut items)?;
+        log_ok!("csaf attached advisories to {} items from {}", merged, dir.display());
+        if let Some(policy) = &merge_policy {
+            let changed = csaf::prefer_vendor_cvss(&mut items, policy);
+            log_ok!("csaf vendor CVSS preferred on {} items", changed);
+        }
+        provenance::stage(&mut prov, &mut items, "csaf", file_time(dir));
+    }
+
+    // Per-release package status from distro security trackers
+    let trackers = args.debian.iter().chain(&args.ubuntu_usn).chain(&args.alpine_secdb).chain(&args.oval);
+    if trackers.clone().next().is_some() {
+        let inputs = distro::DistroInputs {
+            debian: args.debian.as_deref(),
+            ubuntu_usn: args.ubuntu_usn.as_deref(),
+            alpine_secdb: &args.alpine_secdb,
+            oval: &args.oval,
+        };
+        let merged = distro::merge_distro(&inputs, &mut items)?;
+        log_ok!("distro trackers enriched {} items", merged);
+        let inputs = trackers.map(PathBuf::as_path);
+        provenance::stage(&mut prov, &mut items, "distro", provenance::input_time(inputs));
+    }
+
+    // Ecosystem package ranges (PyPI, npm, Go, ...) that NVD's CPEs don't name
+    if let Some(path) = &args.osv {
+        let merged = osv::merge_osv(path, &mut items)?;
+        log_ok!("osv added package ranges to {} items from {}", merged, path.display());
+        provenance::stage(&mut prov, &mut items, "osv", file_time(path));
+    }
+
+    // Patch Tuesday data straight from MSRC (NVD often lags by days)
+    if let Some(path) = &args.msrc {
+        let merged = msrc::merge_msrc(path, &mut items)?;
+        log_ok!("msrc enriched {} items from {}", merged, path.display());
+        provenance::stage(&mut prov, &mut items, "msrc", file_time(path));
+    }
+
+    // Public exploit availability (complements KEV's in-the-wild signal)
+    if args.exploitdb.is_some() || args.metasploit.is_some() {
+        let merged = exploits::merge_exploits(args.exploitdb.as_deref(), args.metasploit.as_deref(), &mut items)?;
+        log_ok!("exploit enrichment flagged {} items with public exploits", merged);
+        let inputs = args.exploitdb.iter().chain(&args.metasploit).map(PathBuf::as_path);
+        provenance::stage(&mut prov, &mut items, "exploits", provenance::input_time(inputs));
+    }
+
+    // Detection templates: what external scanning can verify right away
+    if let Some(path) = &args.nuclei_templates {
+        let flagged = nuclei::merge_nuclei(path, &mut items)?;
+        log_ok!("nuclei templates cover {} items", flagged);
+        provenance::stage(&mut prov, &mut items, "nuclei", file_time(path));
+    }
+
+    // Private advisories; embargoed ones are withheld from shareable outputs later
+    if let Some(path) = &args.internal_advisories {
+        let (added, filled) = internal::merge_internal(path, &mut items)?;
+        log_ok!("internal advisories: {} added, {} matched public items", added, filled);
+        provenance::stage(&mut prov, &mut items, "internal", file_time(path));
+    }
+
+    // Sensor telemetry: is it being scanned for / exploited right now
+    if args.greynoise.is_some() || args.shodan.is_some() {
+        let observed = telemetry::merge_telemetry(args.greynoise.as_deref(), args.shodan.as_deref(), &mut items)?;
+        log_ok!("telemetry marked {} items with observed exploitation", observed);
+        let inputs = args.greynoise.iter().chain(&args.shodan).map(PathBuf::as_path);
+        provenance::stage(&mut prov, &mut items, "telemetry", provenance::input_time(inputs));
+    }
+
+    // Exploitation CISA hasn't catalogued (VulnCheck KEV, community trackers)
+    if !args.exploited_feed.is_empty() {
+        let listed = exploited::merge_exploited(&args.exploited_feed, &mut items)?;
+        log_ok!("exploited feeds listed {} items", listed);
+        let inputs = args.exploited_feed.iter().map(|f| f.path.as_path());
+        provenance::stage(&mut prov, &mut items, "exploited-feeds", provenance::input_time(inputs));
+    }
+
+    // Weakness classes for AppSec reporting; after merge-into so kept items are mapped too
+    if args.cwe_rollup || args.cwe_mapping.is_some() {
+        let mapping = match &args.cwe_mapping {
+            Some(path) => cwe::CweMapping::load(path)?,
+            None => cwe::CweMapping::bundled()?,
+        };
+        let mapped = cwe::rollup(&mapping, &mut items);
+        log_ok!("cwe rollup mapped {} items to categories", mapped);
+        provenance::stage(&mut prov, &mut items, "cwe-rollup", None);
+    }
+
+    // Technique pivots for detection engineering; also after merge-into
+    if let Some(path) = &args.attack_mappings {
+        let mapped = attack::merge_attack(path, &mut items)?;
+        log_ok!("attack mappings linked {} items to techniques from {}", mapped, path.display());
+        provenance::stage(&mut prov, &mut items, "attack", file_time(path));
+    }
+
+    // One spelling per vendor so by-vendor grouping works; kept items are renamed too
+    if args.normalize_vendors || args.vendor_aliases.is_some() || args.cpe_dictionary.is_some() {
+        let mut aliases = vendors::VendorAliases::bundled()?;
+        if let Some(path) = &args.vendor_aliases {
+            aliases.extend(path)?;
+        }
+        let stats = vendors::normalize(&mut items, &aliases);
+        log_ok!(
+            "vendor normalization renamed the vendor on {} items and the product on {} ({} distinct vendors)",
+            stats.vendors_changed, stats.products_changed, stats.vendor_groups
+        );
+        if let Some(path) = &args.cpe_dictionary {
+            let cpe_vendors = vendors::load_cpe_vendors(path)?;
+            let unknown = vendors::cross_check(&items, &aliases, &cpe_vendors);
+            if unknown.is_empty() {
+                log_ok!("all vendors found in CPE dictionary {}", path.display());
+            } else {
+                let listed: Vec<String> = unknown
+                    .iter()
+                    .take(10)
+                    .map(|u| match &u.cpe_hint {
+                        Some(hint) => format!("{} ({} items, CPEs use \"{}\")", u.vendor, u.items, hint),
+                        None => format!("{} ({} items)", u.vendor, u.items),
+                    })
+                    .collect();
+                log_warn!(
+                    "{} vendors not in CPE dictionary {}: {}{}",
+                    unknown.len(),
+                    path.display(),
+                    listed.join(", "),
+                    if unknown.len() > 10 { ", ..." } else { "" }
+                );
+            }
+        }
+        provenance::stage(&mut prov, &mut items, "vendor-aliases", None);
+    }
+
+    // Matcher-ready version ranges from the CPEs, including ones kept from --merge-into
+    let with_versions = versions::derive(&mut items);
+    log_ok!("affected versions on {} items", with_versions);
+    provenance::stage(&mut prov, &mut items, "nvd", None);
+
+    // exploited.cisa_kev follows the final KEV flag (merge-into can set it on kept items)
+    exploited::sync_kev(&mut items);
+    provenance::stage(&mut prov, &mut items, "kev", None);
+
+    // Useful links first, and fix commits/PRs for patch tooling; after every source that contributes refs
+    refs::order_refs(&mut items);
+    if let Some(mode) = args.check.check_refs {
+        linkcheck::check_refs(&mut items, mode, &args.check)?;
+    }
+    let with_fixes = refs::mine_fix_refs(&mut items);
+    log_ok!("fix refs found on {} items", with_fixes);
+    provenance::stage(&mut prov, &mut items, "refs", None);
+
+    // KEV is authoritative for its own fields, including on items kept from --merge-into
+    // (without --kev, kept items keep the KEV fields they were stored with)
+    if let Some(kev) = &kev {
+        for item in items.iter_mut() {
+            if let Some(listing) = kev.listing(&item.id) {
+                listing.apply(item);
+            }
+        }
+        provenance::stage(&mut prov, &mut items, "kev", kev.date_released().map(str::to_string));
+    }
+
+    // Local assessments win over every feed; pins from an earlier run are undone first
+    overrides::revert_all(&mut items);
+    if args.merge_into.is_some() {
+        // Values restored from an earlier run's pins are the stored ones
+        provenance::stage(&mut prov, &mut items, "merge-into", None);
+    }
+    if let Some(path) = &args.overrides {
+        let rules = overrides::load(path)?;
+        let stats = overrides::apply(&mut items, &rules, path)?;
+        log_ok!(
+            "overrides from {}: {} applied, {} suppressed, {} expired",
+            path.display(),
+            stats.applied,
+            stats.suppressed.len(),
+            stats.expired
+        );
+        if !stats.suppressed.is_empty() {
+            log_ok!("suppressed: {}", stats.suppressed.join(", "));
+        }
+        if !stats.unmatched.is_empty() {
+            log_warn!("overrides matched no item: {}", stats.unmatched.join(", "));
+        }
+    }
+    let overrides_time = args.overrides.as_ref().and_then(file_time);
+    provenance::stage(&mut prov, &mut items, "overrides", overrides_time);
+
+    // Routing labels; last, so rules see pinned and normalized names
+    if let Some(path) = &args.tag_rules {
+        let rules = tags::load(path)?;
+        let counts = tags::apply(&mut items, &rules);
+        let summary: Vec<String> = counts.iter().map(|(tag, n)| format!("{}={}", tag, n)).collect();
+        log_ok!("tag rules from {}: {}", path.display(), summary.join(", "));
+        provenance::stage(&mut prov, &mut items, "tag-rules", None);
+    }
+
+    // On the final values, so an overridden CVSS or a kept KEV listing counts (see filter.rs)
+    if let Some(keep) = &keep {
+        let before = items.len();
+        items.retain(|item| keep(item));
+        log_ok!("filters kept {} of {} items", items.len(), before);
+    }
+
+    // Only now: --source-priority passes (csaf) read who set a field from the map
+    if prov.is_none() {
+        for item in items.iter_mut() {
+            item.provenance.clear();
+        }
+    }
+
+    // Same inputs, same bytes: output order comes from IDs, not feed order or hashing
+    codex::sort_by_id(&mut items);
+
+    // Write output; inputs are hashed first since --merge-into may be the output itself
+    watchdog::phase("normalize: writing output");
+    let inputs = normalize_inputs(&args, &nvd_paths, &extra)?;
+    let dest = if outname::is_template(out_path) {
+        let inputs: Vec<PathBuf> = args.kev.iter().chain(&nvd_paths).cloned().collect();
+        outname::expand(out_path, Utc::now(), &inputs)?
+    } else {
+        out_path.clone()
+    };
+    let remote_out = remote::is_remote(&dest);
+    if let Some(latest) = &args.latest
+        && remote::is_remote(latest) != remote_out
+    {
+        anyhow::bail!("--latest must be remote when --out is, and local when it isn't");
+    }
+    let staged;
+    let out_path = if remote_out {
+        staged = remote::staging_path(&dest)?;
+        staged.as_path()
+    } else {
+        dest.as_path()
+    };
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
+    }
+
+    // Protect downstream consumers from multi-MB single records
+    let mut wrote_sidecar = false;
+    if let Some(max_item_bytes) = args.max_item_bytes {
+        let size_limits = limits::SizeLimits {
+            max_item_bytes,
+            max_list_len: args.max_list_len,
+            strategy: args.truncate,
+        };
+        // Items kept through --merge-into bring their overflow from the prior run's sidecar
+        let prior_sidecar = args.merge_into.as_deref().map(limits::sidecar_path);
+        let enforced = limits::enforce_limits(&mut items, &size_limits, out_path, prior_sidecar.as_deref())?;
+        wrote_sidecar = enforced.sidecar.is_some();
+        let truncated = enforced.truncated;
+        if truncated > 0 {
+            log_ok!("truncated {} oversized items (limit {} bytes)", truncated, max_item_bytes);
+        }
+    }
+
+    // Per-record hashes so consumers can cheaply detect changed items
+    digest::stamp_content_hashes(&mut items)?;
+
+    // Read before writing: the baseline may be the file being replaced
+    let previous = match (&args.baseline, &args.since_state) {
+        (Some(path), _) => Some(delta::from_baseline(path)?),
+        (None, Some(path)) => Some(delta::from_state(path)?),
+        (None, None) => None,
+    };
+
+    codex::write_items(out_path, &items, &args.output)?;
+
+    let mut sidecars: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut delta_written = None;
+    if wrote_sidecar {
+        sidecars.push((limits::sidecar_path(out_path), limits::sidecar_path(&dest)));
+    }
+    if args.lenient {
+        let report = lenient::report_path(out_path);
+        lenient::write_report(&report, &skipped)?;
+        log_ok!("error report written to {}", lenient::report_path(&dest).display());
+        sidecars.push((report, lenient::report_path(&dest)));
+    }
+    if let Some(prev) = &previous {
+        let delta_file = delta::delta_path(out_path);
+        let stats = delta::write(&delta_file, &items, prev)?;
+        log_ok!(
+            "delta since {}: {} added, {} changed, {} removed -> {}",
+            prev.label,
+            stats.added,
+            stats.changed,
+            stats.removed,
+            delta::delta_path(&dest).display()
+        );
+        sidecars.push((delta_file.clone(), delta::delta_path(&dest)));
+        delta_written = Some(delta_file);
+    }
+
+    // What went in and what came out, for reproducing and verifying the run
+    let manifest_file = manifest::write(out_path, &dest, items.len(), inputs, &sidecars)?;
+    log_ok!("manifest written to {}", manifest_file.display());
+
+    // Detached signatures over the output and the manifest (which covers sidecars)
+    let mut signatures = Vec::new();
+    if let Some(key) = &args.sign_key {
+        watchdog::phase("normalize: signing output");
+        let comment = format!(
+            "bastion-core {} {} ({} items) {}",
+            env!("CARGO_PKG_VERSION"),
+            dest.file_name().map(|n| n.to_string_lossy()).unwrap_or_default(),
+            items.len(),
+            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        );
+        let sigs = sign::sign(&[out_path, manifest_file.as_path()], key, &comment)?;
+        log_ok!("signed {} and its manifest with {}", dest.display(), key.display());
+        signatures = sigs
+            .into_iter()
+            .zip([sign::signature_path(&dest), sign::signature_path(&manifest::manifest_path(&dest))])
+            .collect();
+    }
+
+    if remote_out {
+        let mut files = vec![(out_path.to_path_buf(), dest.clone())];
+        files.extend(sidecars);
+        files.push((manifest_file, manifest::manifest_path(&dest)));
+        files.extend(signatures);
+        let enc = objstore::Encryption { sse: args.sse, kms_key: args.sse_kms_key.clone() };
+        remote::upload(&files, &enc)?;
+        // Promoted last, so the pointer never names an output that isn't fully there
+        if let Some(latest) = &args.latest {
+            remote::upload(&[(out_path.to_path_buf(), latest.clone())], &enc)?;
+            log_ok!("{} -> {}", latest.display(), dest.display());
+        }
+    } else if let Some(latest) = &args.latest {
+        outname::update_latest(latest, &dest, args.latest_mode)?;
+        log_ok!("{} -> {}", latest.display(), dest.display());
+    }
+    // Only once the output is in place, so a failed run is retried against the old state
+    if let Some(state) = &args.since_state {
+        delta::save_state(state, &items)?;
+        log_ok!("delta state saved to {}", state.display());
+    }
+    if let Some(dir) = &args.snapshot_store {
+        watchdog::phase("normalize: archiving snapshot");
+        let run = snapshot::Store::open(dir)?.add(&items, Utc::now(), &dest.display().to_string())?;
+        log_ok!("snapshot {} archived in {} ({} new item versions)", run.taken_at, dir.display(), run.new_objects);
+    }
+    // A hook that is down must not fail the run (or block the next state)
+    if let (Some(targets), Some(delta_file)) = (&notify_targets, &delta_written) {
+        watchdog::phase("normalize: notifying");
+        let outcome = notify::send(targets, &report::from_delta(delta_file)?, false)?;
+        if outcome.failed > 0 {
+            log_warn!("{} notify targets failed; see above", outcome.failed);
+        }
+    }
+
+    metrics::set_dataset(metrics::Dataset::of(&items));
+    let now: DateTime<Utc> = Utc::now();
+    log_ok!(
+        "normalize wrote {} items to {} at {}",
+        items.len(),
+        dest.display(),
+        now.to_rfc3339(),
+    );
+
+    Ok(())
+}
+
+fn merge_cmd(inputs: Vec<PathBuf>, out: PathBuf, prefer: merge::Prefer, output: codex::OutputOptions) -> Result<()> {
+    watchdog::phase("merge: reading inputs");
+    let sets = inputs.iter().map(|p| codex::read_items(p)).collect::<Result<Vec<_>>>()?;
+    // Hashed before writing: an input may be the file being replaced
+    let digests = inputs.iter().map(|p| manifest::input("merge", p)).collect::<Result<Vec<_>>>()?;
+
+    let (mut items, summary) = merge::merge(sets, prefer);
+    codex::sort_by_id(&mut items);
+    digest::stamp_content_hashes(&mut items)?;
+
+    watchdog::phase("merge: writing output");
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
+    }
+    codex::write_items(&out, &items, &output)?;
+    let manifest_file = manifest::write(&out, &out, items.len(), digests, &[])?;
+    log_ok!("manifest written to {}", manifest_file.display());
+    log_ok!(
+        "merge wrote {} items from {} inputs ({} read, {} conflicts, {} won by a later input) to {}",
+        items.len(),
+        inputs.len(),
+        summary.read,
+        summary.conflicts,
+        summary.replaced,
+        out.display()
+    );
+    Ok(())
+}
+
+fn diff_cmd(old_path: PathBuf, new_path: PathBuf, format: diff::DiffFormat) -> Result<()> {
+    watchdog::phase("diff: reading snapshots");
+    let old = codex::read_items(&old_path)?;
+    let new = codex::read_items(&new_path)?;
+
+    let report = diff::diff_items(&old, &new);
+    match format {
+        diff::DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        diff::DiffFormat::Markdown => print!("{}", diff::render_markdown(&report)),
+    }
+    Ok(())
+}
+
+fn trends_cmd(
+    store: Option<PathBuf>,
+    window: std::time::Duration,
+    until: Option<String>,
+    snapshots: Option<(PathBuf, PathBuf)>,
+    min_cvss_jump: f64,
+    format: trends::TrendsFormat,
+) -> Result<()> {
+    let report = match (store, snapshots) {
+        (_, Some((old_path, new_path))) => {
+            watchdog::phase("trends: reading snapshots");
+            let old = codex::read_items(&old_path)?;
+            let new = codex::read_items(&new_path)?;
+            let labels = (old_path.display().to_string(), new_path.display().to_string());
+            trends::from_snapshots(&old, &new, (&labels.0, &labels.1), min_cvss_jump)
+        }
+        (Some(dir), None) => {
+            let until = until.as_deref().map(snapshot::parse_as_of).transpose()?.unwrap_or_else(Utc::now);
+            let since = until - chrono::Duration::from_std(window).with_context(|| "--window is too long")?;
+            watchdog::phase("trends: reading runs");
+            trends::from_store(&snapshot::Store::open(&dir)?, since, until, min_cvss_jump)?
+        }
+        (None, None) => anyhow::bail!("trends needs --store or --old/--new"),
+    };
+    match format {
+        trends::TrendsFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        trends::TrendsFormat::Markdown => print!("{}", trends::render_markdown(&report)),
+    }
+    log_ok!(
+        "trends over {} runs: {} CVSS jumps, {} newly KEV-listed, {} newly scored",
+        report.runs,
+        report.cvss_jumps.len(),
+        report.kev_added.len(),
+        report.newly_scored.len()
+    );
+    Ok(())
+}
+
+fn burn_down_cmd(
+    dir: PathBuf,
+    since: Option<String>,
+    until: Option<String>,
+    format: burndown::BurnDownFormat,
+    out: Option<PathBuf>,
+) -> Result<()> {
+    let day = |flag: &str, s: &str| {
+        chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .with_context(|| format!("Invalid {} '{}': use YYYY-MM-DD", flag, s))
+    };
+    let since = since.as_deref().map(|s| day("--since", s)).transpose()?;
+    let until = until.as_deref().map(|s| day("--until", s)).transpose()?;
+    if let (Some(since), Some(until)) = (since, until)
+        && since > until
+    {
+        anyhow::bail!("--since {} is after --until {}", since, until);
+    }
+
+    watchdog::phase("burn-down: reading runs");
+    let report = burndown::from_store(&snapshot::Store::open(&dir)?, since, until)?;
+    let mut bytes = Vec::new();
+    match format {
+        burndown::BurnDownFormat::Csv => burndown::write_csv(&report, &mut bytes)?,
+        burndown::BurnDownFormat::Json => {
+            serde_json::to_writer_pretty(&mut bytes, &report)?;
+            bytes.push(b'\n');
         }
     }
-    // fallback: first non-empty
-    for d in descs {
-        if let Some(v) = &d.value {
-            if !v.trim().is_empty() {
-                return v.trim().to_string();
+    match &out {
+        Some(path) => fs::write(path, &bytes).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => std::io::Write::write_all(&mut std::io::stdout(), &bytes)?,
+    }
+    let breached = report.days.iter().filter(|r| r.date == report.until).map(|r| r.breached).sum::<usize>();
+    log_ok!(
+        "burn-down {} .. {}: {} rows, {} breached on the last day",
+        report.since,
+        report.until,
+        report.days.len(),
+        breached
+    );
+    Ok(())
+}
+
+struct ReportSource {
+    snapshots: Option<(PathBuf, PathBuf)>,
+    delta: Option<PathBuf>,
+    input: Option<PathBuf>,
+}
+
+fn report_cmd(source: ReportSource, opts: report::ReportOptions, out: Option<PathBuf>, shareable: bool) -> Result<()> {
+    if severity::rank(&opts.severity) == 0 {
+        let names: Vec<&str> = severity::policy().buckets.iter().map(|b| b.name.as_str()).collect();
+        anyhow::bail!("--severity must be one of {} (got '{}')", names.join(", "), opts.severity);
+    }
+    let html = opts.format == report::ReportFormat::Html;
+    if html && opts.template.is_some() {
+        anyhow::bail!("--template only applies to --format markdown");
+    }
+    if !html && source.input.is_some() {
+        anyhow::bail!("--input needs --format html; the Markdown digest compares two runs (--old/--new or --delta)");
+    }
+    let template = report::load_template(opts.template.as_deref())?;
+    let expr = opts.filter.as_deref().map(query::parse).transpose()?;
+
+    watchdog::phase("report: reading items");
+    let rendered = if let Some(input_path) = &source.input {
+        let mut items = codex::read_items(input_path)?;
+        if shareable {
+            redact::shareable(&mut items);
+        }
+        let mut matched = Vec::new();
+        for item in &items {
+            let keep = match &expr {
+                Some(e) => query::eval(e, &serde_json::to_value(item)?),
+                None => true,
+            };
+            if keep {
+                matched.push(item);
             }
         }
+        let title = opts.title.clone().unwrap_or_else(|| "Vulnerability report".to_string());
+        let page = html::Page { title: &title, source: &input_path.display().to_string(), change: None };
+        log_ok!("report rendering {} of {} items", matched.len(), items.len());
+        html::render(&matched, &page)
+    } else {
+        let mut changes = match (source.snapshots, source.delta) {
+            (Some((old_path, new_path)), _) => {
+                let old = codex::read_items(&old_path)?;
+                let new = codex::read_items(&new_path)?;
+                report::from_snapshots(&old, new, &old_path.display().to_string())?
+            }
+            (None, Some(path)) => report::from_delta(&path)?,
+            (None, None) => anyhow::bail!("report needs --old/--new, --delta or --input"),
+        };
+        if shareable {
+            let withheld = report::withhold_embargoed(&mut changes);
+            if withheld > 0 {
+                log_ok!("redaction withheld {} embargoed items", withheld);
+            }
+        }
+        if let Some(e) = &expr {
+            report::filter(&mut changes, e)?;
+        }
+        log_ok!("report on {} changed items", changes.entries.len());
+        if html {
+            let title = opts.title.clone().unwrap_or_else(|| format!("Vulnerability digest {}", Utc::now().format("%Y-%m-%d")));
+            report::render_html(&changes, &title)
+        } else {
+            report::render(&changes, &opts, &template)
+        }
+    };
+
+    match &out {
+        Some(path) => {
+            fs::write(path, &rendered).with_context(|| format!("Failed to write report: {}", path.display()))?;
+            log_ok!("report written to {}", path.display());
+        }
+        None => print!("{}", rendered),
     }
-    "No description available.".to_string()
+    Ok(())
 }
 
-fn extract_best_cvss(metrics: &Option<serde_json::Value>) -> Option<f64> {
-    let m = metrics.as_ref()?;
+fn overdue_cmd(
+    input_path: PathBuf,
+    as_of: Option<chrono::NaiveDate>,
+    within: i64,
+    format: overdue::OverdueFormat,
+) -> Result<()> {
+    watchdog::phase("overdue: reading items");
+    let items = codex::read_items(&input_path)?;
+
+    let as_of = as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let report = overdue::compute(&items, as_of, within.max(0));
+    match format {
+        overdue::OverdueFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        overdue::OverdueFormat::Markdown => print!("{}", overdue::render_markdown(&report)),
+    }
+    log_ok!("overdue: {} overdue, {} due soon as of {}", report.overdue, report.due_soon, report.as_of);
+    Ok(())
+}
 
-    // Try common NVD metric structures in preferred order (v3.1, v3.0, v2)
-    // We look for something like:
-    // metrics.cvssMetricV31[0].cvssData.baseScore
-    // metrics.cvssMetricV30[0].cvssData.baseScore
-    // metrics.cvssMetricV2[0].cvssData.baseScore
-    let candidates = ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"];
+fn feeds_cmd(
+    input_path: PathBuf,
+    watchlists_path: PathBuf,
+    old_path: Option<PathBuf>,
+    outdir: PathBuf,
+    limit: usize,
+    shareable: bool,
+) -> Result<()> {
+    watchdog::phase("feeds: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    if shareable {
+        redact::shareable(&mut items);
+    }
+    let old = old_path.as_deref().map(codex::read_items).transpose()?;
+    let watchlists = watchlist::load_watchlists(&watchlists_path)?;
 
-    for key in candidates {
-        if let Some(arr) = m.get(key).and_then(|v| v.as_array()) {
-            for entry in arr {
-                if let Some(score) = entry
-                    .get("cvssData")
-                    .and_then(|v| v.get("baseScore"))
-                    .and_then(|v| v.as_f64())
-                {
-                    return Some(score);
-                }
+    watchdog::phase("feeds: writing");
+    let summary = watchlist::write_feeds(&items, old.as_deref(), &watchlists, &outdir, limit)?;
+    for (name, count) in &summary {
+        log_ok!("feeds: {} -> {} items", name, count);
+    }
+    log_ok!("feeds wrote {} watchlists to {}", summary.len(), outdir.display());
+    Ok(())
+}
+
+enum QueryInput {
+    File(PathBuf),
+    AsOf { store: PathBuf, as_of: String },
+}
+
+fn query_cmd(
+    from: QueryInput,
+    filter: Option<String>,
+    fields: Vec<String>,
+    sort: Option<String>,
+    limit: Option<usize>,
+    format: query::QueryFormat,
+) -> Result<()> {
+    // Parse first so a typo fails before loading a large file
+    let expr = filter.as_deref().map(query::parse).transpose()?;
+
+    watchdog::phase("query: reading items");
+    let items = match &from {
+        QueryInput::File(path) => codex::read_items(path)?,
+        QueryInput::AsOf { store, as_of } => {
+            let at = snapshot::parse_as_of(as_of)?;
+            let snapshots = snapshot::Store::open(store)?;
+            let Some(run) = snapshots.run_as_of(at)? else {
+                anyhow::bail!("{} has no run on or before {}", store.display(), as_of)
+            };
+            log_ok!("query as of {}: the run taken at {} ({} items)", as_of, run.taken_at, run.items);
+            snapshots.items(&run)?
+        }
+    };
+
+    let mut matched: Vec<serde_json::Value> = Vec::new();
+    for item in &items {
+        let value = serde_json::to_value(item)?;
+        if expr.as_ref().is_none_or(|e| query::eval(e, &value)) {
+            matched.push(value);
+        }
+    }
+    if let Some(spec) = &sort {
+        query::sort_values(&mut matched, spec);
+    }
+    if let Some(n) = limit {
+        matched.truncate(n);
+    }
+    if !fields.is_empty() {
+        matched = matched.iter().map(|v| query::project(v, &fields)).collect();
+    }
+
+    match format {
+        query::QueryFormat::Json => println!("{}", serde_json::to_string_pretty(&matched)?),
+        query::QueryFormat::Jsonl => {
+            for v in &matched {
+                println!("{}", serde_json::to_string(v)?);
             }
         }
     }
+    log_ok!("query matched {} of {} items", matched.len(), items.len());
+    Ok(())
+}
 
-    None
+fn index_cmd(input_path: PathBuf, out: PathBuf) -> Result<()> {
+    watchdog::phase("index: reading items");
+    let items = codex::read_items(&input_path)?;
+    watchdog::phase("index: building");
+    let index = search::build(&items, &input_path)?;
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
+    }
+    search::write(&index, &out)?;
+    log_ok!("index wrote {} items to {}", index.docs.len(), out.display());
+    Ok(())
 }
 
-/* -------------------- Main normalize logic -------------------- */
+fn search_cmd(
+    index_path: PathBuf,
+    query: &str,
+    filter: search::SearchFilter,
+    limit: usize,
+    format: search::SearchFormat,
+) -> Result<()> {
+    watchdog::phase("search: loading index");
+    let index = search::load(&index_path)?;
+    watchdog::phase("search: searching");
+    let hits = index.search(query, &filter, limit)?;
+    match format {
+        search::SearchFormat::Text => {
+            for h in &hits {
+                let kev = if h.doc.kev { " KEV" } else { "" };
+                println!("{:>7.2}  {:<16} {}{}  {}", h.score, h.doc.id, h.doc.severity_bucket, kev, h.doc.short_desc);
+            }
+        }
+        search::SearchFormat::Json => println!("{}", serde_json::to_string_pretty(&hits)?),
+    }
+    log_ok!(
+        "search: {} results from {} items (index of {} built {})",
+        hits.len(),
+        index.docs.len(),
+        index.source,
+        index.generated_at
+    );
+    Ok(())
+}
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    match cli.command {
-        Commands::Normalize { kev, nvd, out } => normalize_cmd(kev, nvd, out),
-                Commands::Derive { input, outdir, cvss_threshold } => derive_cmd(input, outdir, cvss_threshold),
-    }
-}
-
-fn normalize_cmd(kev_path: PathBuf, nvd_path: PathBuf, out_path: PathBuf) -> Result<()> {
-    let kev_bytes = fs::read(&kev_path)
-        .with_context(|| format!("Failed to read KEV file: {}", kev_path.display()))?;
-    let nvd_bytes = fs::read(&nvd_path)
-        .with_context(|| format!("Failed to read NVD file: {}", nvd_path.display()))?;
-
-    let kev_root: KevRoot = serde_json::from_slice(&kev_bytes)
-        .with_context(|| "Failed to parse KEV JSON")?;
-    let nvd_root: NvdRoot = serde_json::from_slice(&nvd_bytes)
-        .with_context(|| "Failed to parse NVD JSON")?;
-
-    // Build KEV set + small metadata map
-    let mut kev_set: HashSet<String> = HashSet::new();
-    let mut kev_notes: HashMap<String, String> = HashMap::new();
-    let mut kev_vendor: HashMap<String, String> = HashMap::new();
-    let mut kev_product: HashMap<String, String> = HashMap::new();
-
-    for v in kev_root.vulnerabilities {
-        let id = v.cve_id.trim().to_string();
-        kev_set.insert(id.clone());
-        if let Some(s) = v.shortDescription.or(v.notes) {
-            let s = s.trim().to_string();
-            if !s.is_empty() {
-                kev_notes.insert(id.clone(), s);
+fn tui_cmd(input_path: PathBuf) -> Result<()> {
+    watchdog::phase("tui: reading items");
+    let items = codex::read_items(&input_path)?;
+    watchdog::phase("tui: browsing");
+    tui::run(&items)
+}
+
+fn snapshot_cmd(store: Option<PathBuf>, action: SnapshotAction) -> Result<()> {
+    let Some(dir) = store else { anyhow::bail!("snapshot needs --store DIR") };
+    let store = snapshot::Store::open(&dir)?;
+    match action {
+        SnapshotAction::Add { input, taken_at } => {
+            watchdog::phase("snapshot: reading items");
+            let items = codex::read_items(&input)?;
+            let at = match &taken_at {
+                Some(ts) => parse_iso_datetime(ts).with_context(|| format!("Invalid --taken-at '{}'", ts))?,
+                None => snapshot::output_time(&input)?,
+            };
+            watchdog::phase("snapshot: archiving");
+            let run = store.add(&items, at, &input.display().to_string())?;
+            log_ok!(
+                "snapshot {} archived {} items from {} ({} new item versions)",
+                run.taken_at,
+                run.items,
+                input.display(),
+                run.new_objects
+            );
+        }
+        SnapshotAction::List { json } => {
+            let runs = store.runs()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&runs)?);
+            } else {
+                for r in &runs {
+                    println!("{}  {:>7} items  {:>6} new  {}", r.taken_at, r.items, r.new_objects, r.label);
+                }
+            }
+            log_ok!("{} runs in {}", runs.len(), dir.display());
+        }
+        SnapshotAction::Show { id, as_of } => {
+            let at = snapshot::parse_as_of(&as_of)?;
+            match store.as_of(&id, at)? {
+                None => anyhow::bail!("{} has no run on or before {}", dir.display(), as_of),
+                Some((run, None)) => anyhow::bail!("{} is not in the run taken at {}", id, run.taken_at),
+                Some((run, Some(item))) => {
+                    println!("{}", serde_json::to_string_pretty(&item)?);
+                    log_ok!("{} as of {}: from the run taken at {}", id, as_of, run.taken_at);
+                }
+            }
+        }
+        SnapshotAction::History { id, fields, json } => {
+            watchdog::phase("snapshot: reading runs");
+            let changes = store.history(&id, &fields)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                for c in &changes {
+                    match c.change {
+                        snapshot::ChangeKind::Added => println!("{}  added", c.taken_at),
+                        snapshot::ChangeKind::Removed => println!("{}  removed", c.taken_at),
+                        snapshot::ChangeKind::Changed => {
+                            // Lists and nested objects can be long; the JSON output has them whole
+                            let short = |v: &serde_json::Value| {
+                                let s = v.to_string();
+                                if s.chars().count() > 80 { format!("{}...", s.chars().take(77).collect::<String>()) } else { s }
+                            };
+                            for f in &c.fields {
+                                println!("{}  {}: {} -> {}", c.taken_at, f.field, short(&f.was), short(&f.now));
+                            }
+                        }
+                    }
+                }
             }
+            log_ok!("{}: {} changes across {} runs", id, changes.len(), store.runs()?.len());
         }
-        if let Some(vendor) = v.vendorProject {
-            let vendor = vendor.trim().to_string();
-            if !vendor.is_empty() {
-                kev_vendor.insert(id.clone(), vendor);
+        SnapshotAction::EpssTrend { id, json } => {
+            watchdog::phase("snapshot: reading runs");
+            let points = store.epss_trend(&id)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&points)?);
+            } else {
+                for p in &points {
+                    match p.epss {
+                        Some(epss) => println!("{}  {:.5}", p.taken_at, epss),
+                        None => println!("{}  (no score)", p.taken_at),
+                    }
+                }
+            }
+            let scored: Vec<f64> = points.iter().filter_map(|p| p.epss).collect();
+            match (scored.first(), scored.last()) {
+                (Some(first), Some(last)) => {
+                    let (moved, changes) = (last - first, points.len());
+                    log_ok!("{}: epss {:.5} -> {:.5} ({:+.5}) over {} changes", id, first, last, moved, changes)
+                }
+                _ => log_warn!("{} has no EPSS score in {}: archive runs scored with --epss", id, dir.display()),
             }
         }
-        if let Some(prod) = v.product {
-            let prod = prod.trim().to_string();
-            if !prod.is_empty() {
-                kev_product.insert(id.clone(), prod);
+        SnapshotAction::Prune { daily_days, weekly_days, dry_run } => {
+            let plan = snapshot::plan_retention(&store.runs()?, Utc::now(), daily_days, weekly_days);
+            for (run, reason) in &plan {
+                println!("{}  {}  {}", if dry_run { "would drop" } else { "dropping" }, run.taken_at, reason);
             }
+            if dry_run || plan.is_empty() {
+                log_ok!("retention: {} of {} runs to drop in {}", plan.len(), store.runs()?.len(), dir.display());
+                return Ok(());
+            }
+            watchdog::phase("snapshot: pruning");
+            let runs: Vec<snapshot::RunInfo> = plan.into_iter().map(|(run, _)| run).collect();
+            let stats = store.prune(&runs)?;
+            log_ok!(
+                "retention: dropped {} runs and {} unused item versions from {} (daily {}d, weekly {}d)",
+                stats.runs,
+                stats.objects,
+                dir.display(),
+                daily_days,
+                weekly_days
+            );
         }
     }
+    Ok(())
+}
 
-    // Normalize NVD items
-    let mut items: Vec<CanonicalItem> = Vec::with_capacity(nvd_root.vulnerabilities.len());
+fn score_cmd(
+    input_path: PathBuf,
+    policy_path: Option<PathBuf>,
+    epss_path: Option<PathBuf>,
+    out: PathBuf,
+    output: codex::OutputOptions,
+) -> Result<()> {
+    watchdog::phase("score: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    let policy = match &policy_path {
+        Some(path) => priority::PriorityPolicy::load(path)?,
+        None => priority::PriorityPolicy::default(),
+    };
+    let epss = epss_path.as_deref().map(priority::load_epss).transpose()?;
+    if let (Some(path), Some(epss)) = (&epss_path, &epss) {
+        log_ok!("loaded {} EPSS scores from {}", epss.len(), path.display());
+    }
 
-    for wrap in nvd_root.vulnerabilities {
-        let cve = wrap.cve;
-        let id = cve.id.trim().to_string();
+    watchdog::phase("score: scoring");
+    let counts = priority::score(&mut items, &policy, epss.as_ref());
+    digest::stamp_content_hashes(&mut items)?;
 
-        let cvss = extract_best_cvss(&cve.metrics);
-        let mut refs: Vec<String> = cve.references.iter()
-            .filter_map(|r| r.url.as_ref().map(|u| u.trim().to_string()))
-            .filter(|u| !u.is_empty())
-            .collect();
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
+    }
+    codex::write_items(&out, &items, &output)?;
 
-        // Always include the NVD detail page as a ref
-        refs.push(format!("https://nvd.nist.gov/vuln/detail/{}", id));
+    let summary: Vec<String> = counts.iter().map(|(tier, n)| format!("{} {}", tier, n)).collect();
+    log_ok!("score wrote {} items to {} ({})", items.len(), out.display(), summary.join(", "));
+    Ok(())
+}
 
-        // Deduplicate refs
-        let mut seen = HashSet::new();
-        refs.retain(|r| seen.insert(r.clone()));
+struct EnrichSources {
+    epss: Option<PathBuf>,
+    policy: Option<PathBuf>,
+    exploitdb: Option<PathBuf>,
+    metasploit: Option<PathBuf>,
+    nuclei_templates: Option<PathBuf>,
+    overrides: Option<PathBuf>,
+}
 
-        // Prefer NVD description; fall back to KEV note if empty
-        let mut desc = pick_english_description(&cve.descriptions);
-        if desc == "No description available." {
-            if let Some(k) = kev_notes.get(&id) {
-                desc = k.clone();
-            }
-        }
+/// The given enrichment steps of normalize, in normalize's order, over items it already wrote.
+fn enrich_cmd(
+    input_path: PathBuf,
+    sources: EnrichSources,
+    out: Option<PathBuf>,
+    output: codex::OutputOptions,
+) -> Result<()> {
+    watchdog::phase("enrich: reading items");
+    let mut items = codex::read_items(&input_path)?;
+
+    if sources.exploitdb.is_some() || sources.metasploit.is_some() {
+        watchdog::phase("enrich: exploits");
+        let (exploitdb, metasploit) = (sources.exploitdb.as_deref(), sources.metasploit.as_deref());
+        exploits::clear_exploits(exploitdb.is_some(), metasploit.is_some(), &mut items);
+        let merged = exploits::merge_exploits(exploitdb, metasploit, &mut items)?;
+        log_ok!("exploit enrichment flagged {} items with public exploits", merged);
+    }
 
-        let is_kev = kev_set.contains(&id);
-        let mut sources = vec!["nvd".to_string()];
-        if is_kev {
-            sources.push("kev".to_string());
-        }
+    if let Some(path) = &sources.nuclei_templates {
+        watchdog::phase("enrich: nuclei templates");
+        nuclei::clear_nuclei(&mut items);
+        let flagged = nuclei::merge_nuclei(path, &mut items)?;
+        log_ok!("nuclei templates cover {} items", flagged);
+    }
 
-        let vendor = kev_vendor.get(&id).cloned();
-        let product = kev_product.get(&id).cloned();
+    if let Some(path) = &sources.overrides {
+        watchdog::phase("enrich: overrides");
+        overrides::revert_all(&mut items);
+        let rules = overrides::load(path)?;
+        let stats = overrides::apply(&mut items, &rules, path)?;
+        log_ok!(
+            "overrides from {}: {} applied, {} suppressed, {} expired",
+            path.display(),
+            stats.applied,
+            stats.suppressed.len(),
+            stats.expired
+        );
+        if !stats.unmatched.is_empty() {
+            log_warn!("overrides matched no item: {}", stats.unmatched.join(", "));
+        }
+    }
 
-        let item = CanonicalItem {
-            id,
-            sources,
-            published: cve.published,
-            last_modified: cve.lastModified,
-            cvss,
-            severity_bucket: bucket_cvss(cvss),
-            kev: is_kev,
-            short_desc: desc,
-            vendor,
-            product,
-            refs,
+    // Scores read what the steps above set (exploits, pinned CVSS), so they come last
+    if let Some(path) = &sources.epss {
+        watchdog::phase("enrich: scoring");
+        let policy = match &sources.policy {
+            Some(path) => priority::PriorityPolicy::load(path)?,
+            None => priority::PriorityPolicy::default(),
         };
+        let epss = priority::load_epss(path)?;
+        log_ok!("loaded {} EPSS scores from {}", epss.len(), path.display());
+        let counts = priority::score(&mut items, &policy, Some(&epss));
+        let summary: Vec<String> = counts.iter().map(|(tier, n)| format!("{} {}", tier, n)).collect();
+        log_ok!("rescored {} items ({})", items.len(), summary.join(", "));
+    }
 
-        items.push(item);
-    }
-
-    // Also include KEV-only items that might not appear in NVD modified feed snapshot
-    // (rare, but keeps completeness)
-    let existing: HashSet<String> = items.iter().map(|i| i.id.clone()).collect();
-    for id in kev_set {
-        if !existing.contains(&id) {
-            let mut refs = vec![format!("https://nvd.nist.gov/vuln/detail/{}", id)];
-            let mut seen = HashSet::new();
-            refs.retain(|r| seen.insert(r.clone()));
-
-            items.push(CanonicalItem {
-                id: id.clone(),
-                sources: vec!["kev".to_string()],
-                published: None,
-                last_modified: None,
-                cvss: None,
-                severity_bucket: "unknown".to_string(),
-                kev: true,
-                short_desc: kev_notes.get(&id).cloned().unwrap_or_else(|| "KEV-listed vulnerability (details not in current NVD modified feed).".to_string()),
-                vendor: kev_vendor.get(&id).cloned(),
-                product: kev_product.get(&id).cloned(),
-                refs,
-            });
-        }
+    digest::stamp_content_hashes(&mut items)?;
+    let out = out.unwrap_or(input_path);
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
+    }
+    codex::write_items(&out, &items, &output)?;
+    log_ok!("enrich wrote {} items to {}", items.len(), out.display());
+    Ok(())
+}
+
+fn stats_cmd(input_path: PathBuf, top: usize, json: bool) -> Result<()> {
+    watchdog::phase("stats: reading items");
+    let items = codex::read_items(&input_path)?;
+
+    let summary = stats::compute(&items, top);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        stats::print_table(&summary);
     }
+    Ok(())
+}
 
-    // Write output
-    if let Some(parent) = out_path.parent() {
+fn migrate_cmd(
+    input_path: PathBuf,
+    from: Option<u32>,
+    to: u32,
+    out: Option<PathBuf>,
+    output: codex::OutputOptions,
+) -> Result<()> {
+    watchdog::phase("migrate: reading items");
+    let (version, mut items) = codex::migrate(&input_path, from, to)?;
+    // Upgrades can rewrite fields (v2: typed refs), so hashes are recomputed
+    digest::stamp_content_hashes(&mut items)?;
+    let out = out.unwrap_or(input_path);
+    if let Some(parent) = out.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create output dir: {}", parent.display()))?;
     }
+    codex::write_items(&out, &items, &output)?;
+    log_ok!("migrate wrote {} items to {} (schema_version {} -> {})", items.len(), out.display(), version, to);
+    Ok(())
+}
+
+fn validate_cmd(input: Option<PathBuf>, print_schema: bool, max_errors: usize, json: bool) -> Result<()> {
+    if print_schema {
+        print!("{}", inspect::CANONICAL_SCHEMA);
+        return Ok(());
+    }
+    let Some(path) = input else {
+        anyhow::bail!("Nothing to validate: pass --input");
+    };
+
+    watchdog::phase("validate: checking schema");
+    let report = inspect::inspect_file("canonical", &path, max_errors)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        inspect::print_report(&report);
+    }
 
-    let payload = serde_json::to_string_pretty(&items)?;
-    fs::write(&out_path, payload)
-        .with_context(|| format!("Failed to write output: {}", out_path.display()))?;
+    if report.anomaly_count > 0 {
+        let msg = format!("validate found {} schema violations", report.anomaly_count);
+        return Err(errors::Failure::new("validation_failed", msg).path(&path).details(&report.anomalies).into());
+    }
+    Ok(())
+}
 
-    let now: DateTime<Utc> = Utc::now();
-    eprintln!(
-        "[OK] normalize wrote {} items to {} at {}",
-        items.len(),
-        out_path.display(),
-        now.to_rfc3339(),
+fn lint_cmd(input_path: PathBuf, rules_dir: Option<PathBuf>, no_builtin: bool, format: lint::LintFormat) -> Result<()> {
+    let custom = rules_dir.as_deref().map(lint::load_rules).transpose()?.unwrap_or_default();
+    let rules = lint::resolve_rules(custom, !no_builtin);
+
+    watchdog::phase("lint: reading items");
+    let items = codex::read_items(&input_path)?;
+    let findings = lint::run(&items, &rules)?;
+
+    match format {
+        lint::LintFormat::Text => lint::print_text(&findings, &rules, items.len()),
+        lint::LintFormat::Sarif => {
+            println!("{}", serde_json::to_string_pretty(&lint::to_sarif(&findings, &rules, &input_path))?)
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.level == "error").count();
+    if errors > 0 {
+        let msg = format!("lint found {} error-level findings", errors);
+        let failing: Vec<&lint::Finding> = findings.iter().filter(|f| f.level == "error").collect();
+        return Err(errors::Failure::new("lint_failed", msg).path(&input_path).details(failing).into());
+    }
+    Ok(())
+}
+
+fn check_cmd(
+    input_path: PathBuf,
+    sbom: PathBuf,
+    fail_on: String,
+    fail_on_kev: bool,
+    ci: check::CiFormat,
+    json: bool,
+) -> Result<()> {
+    let scored = &severity::policy().buckets;
+    if fail_on != "none" && diff::severity_rank(&fail_on) == 0 {
+        let names: Vec<&str> = scored.iter().map(|b| b.name.as_str()).collect();
+        anyhow::bail!("--fail-on must be one of {}, none (got '{}')", names.join(", "), fail_on);
+    }
+    let components = check::load_components(&sbom)?;
+
+    watchdog::phase("check: reading items");
+    let items = codex::read_items(&input_path)?;
+    let findings = check::run(&items, &components, &fail_on, fail_on_kev);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else {
+        check::report(&findings, components.len(), &sbom, ci)?;
+    }
+
+    let violations: Vec<&check::Finding> = findings.iter().filter(|f| f.violation).collect();
+    if !violations.is_empty() {
+        let msg = format!("check found {} violations in {}", violations.len(), sbom.display());
+        return Err(errors::Failure::new("check_failed", msg).path(&sbom).details(violations).into());
+    }
+    Ok(())
+}
+
+fn gate_cmd(
+    input_path: PathBuf,
+    policy: PathBuf,
+    as_of: Option<chrono::NaiveDate>,
+    ci: check::CiFormat,
+    json: bool,
+) -> Result<()> {
+    let rules = gate::load(&policy)?;
+
+    watchdog::phase("gate: reading items");
+    let items = codex::read_items(&input_path)?;
+    let as_of = as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let results = gate::evaluate(&items, &rules, as_of)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        gate::report(&results, &policy, ci);
+    }
+
+    let code = gate::exit_code(&results);
+    if code != 0 {
+        let failed: Vec<&gate::RuleResult> = results.iter().filter(|r| r.failed).collect();
+        let names: Vec<&str> = failed.iter().map(|r| r.rule.as_str()).collect();
+        let msg = format!("gate failed {} of {} rules: {}", failed.len(), results.len(), names.join(", "));
+        return Err(errors::Failure::new("gate_failed", msg).path(&policy).details(failed).exit_code(code).into());
+    }
+    Ok(())
+}
+
+fn vex_cmd(products_path: PathBuf, input_path: PathBuf, out: PathBuf, author: String, shareable: bool) -> Result<()> {
+    let products = vex::load_products(&products_path)?;
+
+    watchdog::phase("vex: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    if shareable {
+        redact::shareable(&mut items);
+    }
+
+    let (doc, statements) = vex::build(&items, &products, &author);
+    fs::write(&out, serde_json::to_string_pretty(&doc)?)
+        .with_context(|| format!("Failed to write output: {}", out.display()))?;
+    log_ok!(
+        "vex wrote {} statements for {} products to {}",
+        statements,
+        products.len(),
+        out.display()
     );
+    Ok(())
+}
 
+fn bench_cmd(kev: Option<PathBuf>, nvd: Vec<PathBuf>, iterations: usize, json: bool) -> Result<()> {
+    watchdog::phase("bench");
+    let report = bench::run(kev.as_deref(), &nvd, iterations)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        bench::print_table(&report);
+    }
     Ok(())
 }
 
-#[derive(Debug, Serialize)]
-struct TrendSummary {
-    window: String,               // "7d" or "30d"
-    generated_at: String,         // ISO
-    total_items: usize,
-    kev_items: usize,
-    by_severity: HashMap<String, usize>,
-    top_vendors: Vec<(String, usize)>,
-    top_products: Vec<(String, usize)>,
+fn inspect_cmd(kev: Option<PathBuf>, nvd: Option<PathBuf>, max_errors: usize, json: bool) -> Result<()> {
+    let inputs: Vec<(&str, PathBuf)> = [("kev", kev), ("nvd", nvd)]
+        .into_iter()
+        .filter_map(|(src, p)| p.map(|p| (src, p)))
+        .collect();
+    if inputs.is_empty() {
+        anyhow::bail!("Nothing to inspect: pass --kev and/or --nvd");
+    }
+
+    let mut reports = Vec::new();
+    for (source, path) in &inputs {
+        reports.push(inspect::inspect_file(source, path, max_errors)?);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        reports.iter().for_each(inspect::print_report);
+    }
+
+    let total: usize = reports.iter().map(|r| r.anomaly_count).sum();
+    if total > 0 {
+        let msg = format!("inspect found {} structural anomalies", total);
+        return Err(errors::Failure::new("validation_failed", msg).details(&reports).into());
+    }
+    Ok(())
 }
 
-fn parse_iso_datetime(s: &str) -> Option<DateTime<Utc>> {
-    let s = s.trim();
+fn export_cmd(
+    input_path: PathBuf,
+    format: export::ExportFormat,
+    out: PathBuf,
+    csv: export::CsvOptions,
+    template: Option<PathBuf>,
+    shareable: bool,
+) -> Result<()> {
+    watchdog::phase("export: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    if shareable {
+        redact::shareable(&mut items);
+    }
 
-    // 1) RFC3339 with timezone (preferred)
-    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-        return Some(dt.with_timezone(&Utc));
+    watchdog::phase("export: writing");
+    let written = match format {
+        export::ExportFormat::MdCards => export::write_md_cards(&items, &out, template.as_deref())?,
+        export::ExportFormat::Hugo | export::ExportFormat::Zola => {
+            export::write_site(&items, &out, format, template.as_deref())?
+        }
+        export::ExportFormat::Csv => export::write_csv(&items, &out, &csv.columns, &csv.list_delimiter)?,
+        export::ExportFormat::CyclonedxVdr => cyclonedx::write_vdr(&items, &out)?,
+        export::ExportFormat::Elasticsearch => unreachable!("handled by elasticsearch_export_cmd"),
+    };
+    let unit = match format {
+        export::ExportFormat::Csv => "rows",
+        export::ExportFormat::CyclonedxVdr => "vulnerabilities",
+        _ => "pages",
+    };
+    log_ok!("export wrote {} {} to {}", written, unit, out.display());
+    if format == export::ExportFormat::Zola {
+        log_ok!("export: declare taxonomies in config.toml: {}", export::TAXONOMIES.join(", "));
     }
+    Ok(())
+}
 
-    // 2) Naive ISO8601 without timezone -> assume UTC
-    // Try with fractional seconds first, then without.
-    use chrono::NaiveDateTime;
+fn elasticsearch_export_cmd(input_path: PathBuf, opts: &elastic::EsOptions, shareable: bool) -> Result<()> {
+    watchdog::phase("export: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    if shareable {
+        redact::shareable(&mut items);
+    }
 
-    let fmts = [
-        "%Y-%m-%dT%H:%M:%S%.f", // supports .123, .123456, etc.
-        "%Y-%m-%dT%H:%M:%S",
-    ];
+    watchdog::phase("export: bulk indexing");
+    let stats = elastic::export(&mut items, opts)?;
+    let target = match opts.out {
+        Some(out) => out.display().to_string(),
+        None => format!("{} index {}", opts.url.unwrap_or_default(), opts.index),
+    };
+    log_ok!(
+        "export: {} indexed, {} deleted, {} unchanged -> {}",
+        stats.indexed,
+        stats.deleted,
+        stats.unchanged,
+        target
+    );
+    Ok(())
+}
+
+fn train_dict_cmd(inputs: Vec<PathBuf>, out: PathBuf, max_size: usize) -> Result<()> {
+    watchdog::phase("train-dict: sampling items");
+    let (dict, samples) = archive::train_dict(&inputs, max_size)?;
+    fs::write(&out, &dict).with_context(|| format!("Failed to write dictionary: {}", out.display()))?;
+    log_ok!("train-dict wrote {} bytes to {} from {} items", dict.len(), out.display(), samples);
+    Ok(())
+}
+
+fn compress_cmd(input_path: PathBuf, out: PathBuf, dict_path: Option<PathBuf>, level: i32) -> Result<()> {
+    let dict = dict_path
+        .as_ref()
+        .map(|p| fs::read(p).with_context(|| format!("Failed to read dictionary: {}", p.display())))
+        .transpose()?;
+
+    watchdog::phase("compress: writing");
+    let (bytes_in, bytes_out) = archive::compress_file(&input_path, &out, dict.as_deref(), level)?;
+    log_ok!(
+        "compress wrote {} ({} -> {} bytes, {:.1}x)",
+        out.display(),
+        bytes_in,
+        bytes_out,
+        bytes_in as f64 / bytes_out.max(1) as f64
+    );
+    Ok(())
+}
+
+fn fixtures_cmd(outdir: PathBuf, spec: fixtures::FixtureSpec) -> Result<()> {
+    let written = fixtures::write_fixtures(&outdir, &spec)?;
+    for path in &written {
+        log_ok!("fixtures wrote {}", path);
+    }
+    Ok(())
+}
+
+fn mount_cmd(input_path: PathBuf, dir: PathBuf, allow_other: bool) -> Result<()> {
+    watchdog::phase("mount: reading items");
+    let items = codex::read_items(&input_path)?;
+    if !dir.is_dir() {
+        anyhow::bail!("Mount point is not a directory: {}", dir.display());
+    }
+    watchdog::phase("mount: serving");
+    fusefs::mount(&items, &input_path, &dir, allow_other)
+}
+
+fn serve_cmd(
+    input_path: PathBuf,
+    listen: String,
+    reload: Option<u64>,
+    grpc_listen: Option<String>,
+    snapshot_store: Option<PathBuf>,
+) -> Result<()> {
+    if reload == Some(0) {
+        anyhow::bail!("--reload must be at least 1 second");
+    }
+    watchdog::phase("serve: loading items");
+    let reload = reload.map(std::time::Duration::from_secs);
+    serve::serve(&input_path, &listen, reload, grpc_listen.as_deref(), snapshot_store.as_deref())
+}
+
+fn notify_cmd(config: PathBuf, snapshots: Option<(PathBuf, PathBuf)>, delta: Option<PathBuf>, dry_run: bool) -> Result<()> {
+    let targets = notify::load(&config)?;
+    watchdog::phase("notify: reading changes");
+    let changes = match (snapshots, delta) {
+        (Some((old, new)), _) => report::from_snapshots(&codex::read_items(&old)?, codex::read_items(&new)?, &old.display().to_string())?,
+        (None, Some(delta)) => report::from_delta(&delta)?,
+        (None, None) => anyhow::bail!("notify needs --old/--new or --delta"),
+    };
+    watchdog::phase("notify: posting");
+    let outcome = notify::send(&targets, &changes, dry_run)?;
+    if outcome.failed > 0 {
+        return Err(errors::Failure::new(
+            "notify_failed",
+            format!("{} of {} notify targets failed", outcome.failed, outcome.failed + outcome.sent),
+        )
+        .path(&config)
+        .into());
+    }
+    Ok(())
+}
+
+/// normalize's merged feeds before enrichment: the items and the records --lenient skipped.
+type MergedSources = (Vec<CanonicalItem>, Vec<lenient::SkippedRecord>);
+
+/// Set during --all-profiles: the last profile's feed options (as a key) and what they merged
+/// into, so the next profile with the same feeds skips parsing them. None outside such a run.
+static SHARED_SOURCES: std::sync::Mutex<Option<(String, Option<MergedSources>)>> = std::sync::Mutex::new(None);
+
+fn shared_sources_get(key: &str) -> Option<MergedSources> {
+    match &*SHARED_SOURCES.lock().unwrap_or_else(|e| e.into_inner()) {
+        Some((k, merged)) if k == key => merged.clone(),
+        _ => None,
+    }
+}
+
+fn shared_sources_put(key: &str, merged: &MergedSources) {
+    let mut shared = SHARED_SOURCES.lock().unwrap_or_else(|e| e.into_inner());
+    if shared.is_some() {
+        *shared = Some((key.to_string(), Some(merged.clone())));
+    }
+}
 
-    for fmt in fmts {
-        if let Ok(ndt) = NaiveDateTime::parse_from_str(s, fmt) {
-            return Some(DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
+/// `argv` with --all-profiles swapped for --profile NAME.
+fn with_profile<S: AsRef<std::ffi::OsStr>>(argv: &[S], name: &str) -> Vec<OsString> {
+    let kept = argv.iter().map(|a| a.as_ref().to_os_string()).filter(|a| a != "--all-profiles");
+    kept.chain(["--profile".into(), name.into()]).collect()
+}
+
+/// normalize for every [profile.<name>], built by `args_for`; `first` when the worker
+/// threads aren't configured yet. Profiles whose feed options match reuse the previous
+/// profile's merged sources instead of parsing the feeds again.
+fn all_profiles_cmd(
+    config: &config::Config,
+    first: bool,
+    mut args_for: impl FnMut(&str) -> Result<NormalizeArgs>,
+) -> Result<()> {
+    let names = config.profiles()?;
+    if names.is_empty() {
+        anyhow::bail!("--all-profiles: the config file has no [profile.<name>] sections");
+    }
+    // Every profile's options are checked before any feed is read
+    let runs: Vec<(String, NormalizeArgs)> =
+        names.into_iter().map(|name| Ok((name.clone(), args_for(&name)?))).collect::<Result<_>>()?;
+    *SHARED_SOURCES.lock().unwrap_or_else(|e| e.into_inner()) = Some((String::new(), None));
+    let total = runs.len();
+    let result = runs.into_iter().enumerate().try_for_each(|(i, (name, mut args))| {
+        // The global thread pool can only be configured once per process
+        if i > 0 || !first {
+            args.threads = 0;
         }
+        log_ok!("profile {} ({}/{})", name, i + 1, total);
+        normalize_cmd(args).with_context(|| format!("profile {}", name))
+    });
+    *SHARED_SOURCES.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    result
+}
+
+/// The normalize arguments after `daemon --`, with the config's [normalize] filled in.
+fn daemon_normalize_args<S: AsRef<std::ffi::OsStr>>(
+    args: &[S],
+    config: Option<&config::Config>,
+) -> Result<NormalizeArgs> {
+    let mut command = NormalizeCli::command();
+    command.build();
+    let args: Vec<OsString> = args.iter().map(|a| a.as_ref().to_os_string()).collect();
+    let mut argv: Vec<OsString> = std::iter::once("normalize".into()).chain(args.iter().cloned()).collect();
+    if let Some(config) = config {
+        let extra = config.subcommand_args(&command, &args)?;
+        argv.extend(extra);
+    }
+    let parsed = NormalizeCli::try_parse_from(argv).map(|c| c.args).map_err(|e| {
+        anyhow::anyhow!("Invalid normalize arguments after --: {}", e.to_string().trim().trim_start_matches("error: "))
+    })?;
+    if parsed.profile.is_some() && config.is_none() {
+        anyhow::bail!("--profile needs a --config file with [profile.<name>] sections");
     }
+    Ok(parsed)
+}
 
-    None
+fn daemon_cmd(schedule: daemon::Schedule, normalize_args: Vec<String>, config: Option<config::Config>) -> Result<()> {
+    // Bad arguments fail at startup rather than on every cycle
+    daemon_normalize_args(&normalize_args, config.as_ref())?;
+
+    let mut first = true;
+    daemon::run(&schedule, || {
+        let mut args = daemon_normalize_args(&normalize_args, config.as_ref())?;
+        if args.all_profiles {
+            let config =
+                config.as_ref().context("--all-profiles needs a --config file with [profile.<name>] sections")?;
+            let first = std::mem::take(&mut first);
+            return all_profiles_cmd(config, first, |name| {
+                daemon_normalize_args(&with_profile(&normalize_args, name), Some(config))
+            });
+        }
+        // The global thread pool can only be configured once per process
+        if !std::mem::take(&mut first) {
+            args.threads = 0;
+        }
+        normalize_cmd(args)
+    })
 }
 
-fn pick_item_time(item: &CanonicalItem) -> Option<DateTime<Utc>> {
-    if let Some(p) = &item.published {
-        if let Some(dt) = parse_iso_datetime(p) {
-            return Some(dt);
+type ServiceOptions = (String, bool, Option<PathBuf>, u64);
+
+fn daemon_install_cmd(
+    args: DaemonArgs,
+    config: Option<&Path>,
+    kind: service::Kind,
+    (name, user, working_dir, restart_secs): ServiceOptions,
+    start: bool,
+    print: bool,
+) -> Result<()> {
+    let interval = args.interval.context("--interval is required")?;
+    let cwd = std::env::current_dir().with_context(|| "Failed to read the current directory")?;
+    let working_dir = match working_dir {
+        Some(dir) => fs::canonicalize(&dir).with_context(|| format!("No such directory: {}", dir.display()))?,
+        None => cwd.clone(),
+    };
+    let mut argv = Vec::new();
+    if let Some(config) = config {
+        // Relative to where install ran, which needn't be the working directory
+        let config = fs::canonicalize(config).with_context(|| format!("Failed to resolve {}", config.display()))?;
+        argv.extend(["--config".to_string(), config.to_string_lossy().to_string()]);
+    }
+    argv.extend(args.argv(interval));
+    // Bad normalize arguments fail here rather than in a restart loop
+    daemon_normalize_args(&args.normalize, config.map(config::Config::load).transpose()?.as_ref())?;
+
+    let exe = std::env::current_exe().with_context(|| "Failed to locate the core binary")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    let spec = service::Spec { name, exe, args: argv, working_dir, restart_secs, user };
+    if print {
+        print!("{}", service::render(kind, &spec));
+        return Ok(());
+    }
+    service::install(kind, &spec, start)?;
+    Ok(())
+}
+
+fn replay_cmd(bundle: PathBuf, record: bool, keep: Option<PathBuf>) -> Result<()> {
+    let mut manifest = replay::load(&bundle)?;
+    let out_dir = keep
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("bastion-replay-{}", std::process::id())));
+
+    watchdog::phase("replay: running bundle");
+    let result = replay::replay(&bundle, &mut manifest, &out_dir, record);
+    if keep.is_none() {
+        let _ = fs::remove_dir_all(&out_dir);
+    }
+    let mismatches = result?;
+
+    let outputs: usize = manifest.runs.iter().map(|r| r.outputs.len()).sum();
+    if record {
+        replay::save(&bundle, &manifest)?;
+        let path = bundle.join(replay::MANIFEST);
+        log_ok!("replay recorded {} outputs from {} runs into {}", outputs, manifest.runs.len(), path.display());
+        return Ok(());
+    }
+    if !mismatches.is_empty() {
+        for m in &mismatches {
+            log_fail!(
+                "{}: {} expected {} got {}",
+                m.run,
+                m.path,
+                m.expected.as_deref().unwrap_or("(not recorded)"),
+                m.actual.as_deref().unwrap_or("(missing)"),
+            );
         }
+        let msg = format!("{} of {} replay outputs differ from {}", mismatches.len(), outputs, bundle.display());
+        return Err(errors::Failure::new("replay_mismatch", msg).path(&bundle).details(&mismatches).into());
+    }
+    log_ok!("replay reproduced {} outputs from {} runs", outputs, manifest.runs.len());
+    Ok(())
+}
+
+fn verify_cmd(
+    input_path: PathBuf,
+    pubkeys: Vec<PathBuf>,
+    keyring: Option<PathBuf>,
+    manifest_path: Option<PathBuf>,
+) -> Result<()> {
+    let manifest_path = manifest_path.unwrap_or_else(|| manifest::manifest_path(&input_path));
+    let mut keys: Vec<sign::TrustedKey> = pubkeys.into_iter().map(sign::TrustedKey::any_time).collect();
+    if let Some(path) = &keyring {
+        keys.extend(sign::load_keyring(path)?);
     }
-    if let Some(m) = &item.last_modified {
-        if let Some(dt) = parse_iso_datetime(m) {
-            return Some(dt);
+
+    // Manifest first: its digests are only worth checking once it is trusted
+    watchdog::phase("verify: checking signatures");
+    for file in [&manifest_path, &input_path] {
+        let verified = sign::verify(file, &keys)?;
+        log_ok!(
+            "{} signed by {} ({})",
+            file.display(),
+            verified.key.display(),
+            verified.trusted_comment
+        );
+    }
+
+    watchdog::phase("verify: checking digests");
+    let manifest = manifest::load(&manifest_path)?;
+    let mismatches = manifest::check(&manifest, &input_path)?;
+    if !mismatches.is_empty() {
+        for m in &mismatches {
+            log_fail!(
+                "{}: manifest sha256 {} got {}",
+                m.path,
+                m.expected.as_deref().unwrap_or("(not recorded)"),
+                m.actual.as_deref().unwrap_or("(missing)"),
+            );
         }
+        let msg = format!("{} file(s) differ from {}", mismatches.len(), manifest_path.display());
+        return Err(errors::Failure::new("digest_mismatch", msg).path(&manifest_path).details(&mismatches).into());
     }
-    None
+    log_ok!(
+        "{} matches its manifest ({} items, {} sidecars)",
+        input_path.display(),
+        manifest.output.items,
+        manifest.sidecars.len()
+    );
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct TrendSummary {
+    window: String,               // "7d" or "30d"
+    generated_at: String,         // ISO
+    total_items: usize,
+    kev_items: usize,
+    by_severity: HashMap<String, usize>,
+    top_vendors: Vec<(String, usize)>,
+    top_products: Vec<(String, usize)>,
 }
 
-fn top_n_counts(map: &HashMap<String, usize>, n: usize) -> Vec<(String, usize)> {
-    let mut v: Vec<(String, usize)> = map.iter().map(|(k, c)| (k.clone(), *c)).collect();
-    v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-    v.truncate(n);
-    v
+fn pick_item_time(item: &CanonicalItem) -> Option<DateTime<Utc>> {
+    if let Some(p) = &item.published
+        && let Some(dt) = parse_iso_datetime(p)
+    {
+        return Some(dt);
+    }
+    if let Some(m) = &item.last_modified
+        && let Some(dt) = parse_iso_datetime(m)
+    {
+        return Some(dt);
+    }
+    None
 }
 
-fn derive_cmd(input_path: PathBuf, outdir: PathBuf, cvss_threshold: f64) -> Result<()> {
-    let bytes = fs::read(&input_path)
-        .with_context(|| format!("Failed to read input: {}", input_path.display()))?;
-    let items: Vec<CanonicalItem> = serde_json::from_slice(&bytes)
-        .with_context(|| "Failed to parse canonical items.json")?;
+fn derive_cmd(input_path: PathBuf, outdir: PathBuf, cvss_threshold: f64, shareable: bool) -> Result<()> {
+    watchdog::phase("derive: reading items");
+    let mut items = codex::read_items(&input_path)?;
+    if shareable {
+        redact::shareable(&mut items);
+    }
 
     fs::create_dir_all(&outdir)
         .with_context(|| format!("Failed to create outdir: {}", outdir.display()))?;
@@ -417,8 +2891,12 @@ fn derive_cmd(input_path: PathBuf, outdir: PathBuf, cvss_threshold: f64) -> Resu
     // Priority filter
     let priority: Vec<CanonicalItem> = items
         .iter()
+        .filter(|i| {
+            i.kev
+                || i.cvss.unwrap_or(0.0) >= cvss_threshold
+                || i.observed_exploitation.as_ref().is_some_and(|o| o.observed)
+        })
         .cloned()
-        .filter(|i| i.kev || i.cvss.unwrap_or(0.0) >= cvss_threshold)
         .collect();
 
     let priority_path = outdir.join("priority_items.json");
@@ -446,15 +2924,15 @@ fn derive_cmd(input_path: PathBuf, outdir: PathBuf, cvss_threshold: f64) -> Resu
 
             *by_sev.entry(item.severity_bucket.clone()).or_insert(0) += 1;
 
-            if let Some(v) = &item.vendor {
-                if !v.trim().is_empty() {
-                    *vendor_counts.entry(v.trim().to_string()).or_insert(0) += 1;
-                }
+            if let Some(v) = &item.vendor
+                && !v.trim().is_empty()
+            {
+                *vendor_counts.entry(v.trim().to_string()).or_insert(0) += 1;
             }
-            if let Some(p) = &item.product {
-                if !p.trim().is_empty() {
-                    *product_counts.entry(p.trim().to_string()).or_insert(0) += 1;
-                }
+            if let Some(p) = &item.product
+                && !p.trim().is_empty()
+            {
+                *product_counts.entry(p.trim().to_string()).or_insert(0) += 1;
             }
         }
 
@@ -472,8 +2950,8 @@ fn derive_cmd(input_path: PathBuf, outdir: PathBuf, cvss_threshold: f64) -> Resu
         fs::write(out_path, serde_json::to_string_pretty(&summary)?)?;
     }
 
-    eprintln!(
-        "[OK] derive wrote {} priority items and trend summaries to {}",
+    log_ok!(
+        "derive wrote {} priority items and trend summaries to {}",
         priority.len(),
         outdir.display()
     );
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{codex, digest, input};
+
+/* -------------------- Run manifest -------------------- */
+/*
+normalize writes items.manifest.json next to its output: what went in, what
+came out, and what produced it.
+
+  {
+    "tool": "bastion-core", "version": "0.1.0", "schema_version": 2,
+    "generated_at": "2025-06-01T06:15:00Z",
+    "command": ["core", "normalize", "--kev", "kev.json", ...],
+    "inputs": [ { "role": "kev", "path": "kev.json", "bytes": 1234, "sha256": "..." }, ... ],
+    "output": { "path": "items.json", "bytes": 5678, "sha256": "...", "items": 3030 },
+    "sidecars": [ { "path": "items.overflow.json", ... } ]
+  }
+
+Hashes are over the raw bytes as stored (a .gz input is hashed compressed).
+Directory inputs (CSAF, cvelist, MSRC) get one hash over their sorted file list
+and contents. Remote (sftp://, s3://, ...) inputs are listed without a hash.
+
+normalize sorts items by ID and refs by kind and URL, so identical inputs and
+options give a byte-identical output and the same output hash; only
+generated_at and the command line differ between such runs.
+*/
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDigest {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputDigest {
+    pub role: String, // normalize flag the file came in through: kev, nvd, csaf, ...
+    #[serde(flatten)]
+    pub file: FileDigest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputDigest {
+    #[serde(flatten)]
+    pub file: FileDigest,
+    pub items: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub tool: String,
+    pub version: String,
+    pub schema_version: u32,
+    pub generated_at: String,
+    pub command: Vec<String>,
+    pub inputs: Vec<InputDigest>,
+    pub output: OutputDigest,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<FileDigest>,
+}
+
+/// items.json -> items.manifest.json
+pub fn manifest_path(out_path: &Path) -> PathBuf {
+    let stem = codex::output_stem(out_path);
+    out_path.with_file_name(format!("{}.manifest.json", stem))
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Digest of a local file or directory tree, recorded under `shown` (the path as given).
+pub fn digest_path(path: &Path, shown: &str) -> Result<FileDigest> {
+    if input::is_remote(path) {
+        return Ok(FileDigest { path: shown.to_string(), bytes: None, sha256: None });
+    }
+    if !path.is_dir() {
+        let (sha256, bytes) = digest::sha256_file(path)?;
+        return Ok(FileDigest { path: shown.to_string(), bytes: Some(bytes), sha256: Some(sha256) });
+    }
+    // "<relative path>\0<file sha256>\n" per file, in sorted order
+    let mut files = Vec::new();
+    walk(path, &mut files)?;
+    let mut tree = Sha256::new();
+    let mut total = 0u64;
+    for file in &files {
+        let (sha256, bytes) = digest::sha256_file(file)?;
+        let rel = file.strip_prefix(path).unwrap_or(file);
+        tree.update(rel.to_string_lossy().as_bytes());
+        tree.update(b"\0");
+        tree.update(sha256.as_bytes());
+        tree.update(b"\n");
+        total += bytes;
+    }
+    Ok(FileDigest { path: shown.to_string(), bytes: Some(total), sha256: Some(digest::hex(&tree.finalize())) })
+}
+
+pub fn input(role: &str, path: &Path) -> Result<InputDigest> {
+    Ok(InputDigest { role: role.to_string(), file: digest_path(path, &path.display().to_string())? })
+}
+
+/// Hash what was written and put the manifest next to `written`. `shown` is the
+/// output path as the user will see it (the remote URL for staged uploads).
+pub fn write(
+    written: &Path,
+    shown: &Path,
+    items: usize,
+    inputs: Vec<InputDigest>,
+    sidecars: &[(PathBuf, PathBuf)],
+) -> Result<PathBuf> {
+    let manifest = Manifest {
+        tool: "bastion-core".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: codex::SCHEMA_VERSION,
+        generated_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        command: std::env::args().collect(),
+        inputs,
+        output: OutputDigest { file: digest_path(written, &shown.display().to_string())?, items },
+        sidecars: sidecars
+            .iter()
+            .map(|(local, shown)| digest_path(local, &shown.display().to_string()))
+            .collect::<Result<_>>()?,
+    };
+    let path = manifest_path(written);
+    fs::write(&path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .with_context(|| format!("Failed to write manifest: {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn load(path: &Path) -> Result<Manifest> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse manifest: {}", path.display()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    pub path: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>, // None: file missing
+}
+
+/// Re-hash `output` and the sidecars the manifest lists (looked up by file
+/// name next to `output`, so a moved or downloaded copy checks the same).
+pub fn check(manifest: &Manifest, output: &Path) -> Result<Vec<Mismatch>> {
+    let mut expected = vec![(output.to_path_buf(), &manifest.output.file)];
+    for sidecar in &manifest.sidecars {
+        let name = Path::new(&sidecar.path).file_name().unwrap_or_default();
+        expected.push((output.with_file_name(name), sidecar));
+    }
+
+    let mut mismatches = Vec::new();
+    for (path, recorded) in expected {
+        let actual = if path.is_file() { Some(digest::sha256_file(&path)?.0) } else { None };
+        if actual.is_none() || actual != recorded.sha256 {
+            mismatches.push(Mismatch { path: path.display().to_string(), expected: recorded.sha256.clone(), actual });
+        }
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::CanonicalItem;
+
+/* -------------------- Combining outputs -------------------- */
+/*
+`merge a.json b.json c.json --out merged.json` unions canonical outputs, e.g.
+per-region pipelines into one master feed. An ID found in several inputs is a
+conflict, settled per --prefer:
+
+  newest    the copy with the newest last_modified; ties (and copies without
+            one) go to the input named first
+  priority  the copy from the input named first, whatever its dates
+
+The winning copy is kept whole; fields aren't mixed across inputs, so an item
+never carries an override or an embargo its own pipeline didn't set. Overrides
+and enrichment that should apply to the master feed belong in a normalize run
+over it (--merge-into).
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Prefer {
+    /// Newest last_modified wins; ties go to the earlier input
+    Newest,
+    /// Earlier input wins
+    Priority,
+}
+
+pub struct MergeSummary {
+    pub read: usize,
+    pub conflicts: usize,
+    /// Conflicts won by a later input
+    pub replaced: usize,
+}
+
+/// Union `inputs` (in priority order) by ID; see the module comment.
+pub fn merge(inputs: Vec<Vec<CanonicalItem>>, prefer: Prefer) -> (Vec<CanonicalItem>, MergeSummary) {
+    let mut summary = MergeSummary { read: 0, conflicts: 0, replaced: 0 };
+    let mut slot: HashMap<String, usize> = HashMap::new();
+    let mut merged: Vec<CanonicalItem> = Vec::new();
+    for items in inputs {
+        summary.read += items.len();
+        for item in items {
+            match slot.get(&item.id) {
+                Some(&idx) => {
+                    summary.conflicts += 1;
+                    // ISO8601 timestamps compare lexicographically; a missing one is oldest
+                    if prefer == Prefer::Newest && item.last_modified > merged[idx].last_modified {
+                        summary.replaced += 1;
+                        merged[idx] = item;
+                    }
+                }
+                None => {
+                    slot.insert(item.id.clone(), merged.len());
+                    merged.push(item);
+                }
+            }
+        }
+    }
+    (merged, summary)
+}
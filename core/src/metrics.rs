@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use crate::{log_ok, log_warn, severity, CanonicalItem};
+
+/* -------------------- Prometheus metrics -------------------- */
+/*
+GET /metrics in the Prometheus text format, from `serve` (on its own port) and
+from `daemon --metrics-listen HOST:PORT` (a listener that answers nothing else):
+
+  bastion_items                                 items in the dataset
+  bastion_items_by_severity{severity="high"}    per bucket, every bucket of the policy
+  bastion_kev_items                             KEV-listed items
+
+daemon, about its cycles:
+
+  bastion_last_success_timestamp_seconds        end of the last good cycle (0: none yet)
+  bastion_cycles_total                          cycles run
+  bastion_cycle_failures_total                  cycles that failed, for any reason
+  bastion_fetch_errors_total                    --fetch-cmd failures among those
+  bastion_normalize_duration_seconds            normalize time of the last cycle
+  bastion_cycle_duration_seconds                fetch + normalize of the last cycle
+
+serve, about the snapshot it answers from:
+
+  bastion_snapshot_loaded_timestamp_seconds     when the current snapshot was loaded
+  bastion_source_modified_timestamp_seconds     the file's modification time
+  bastion_reloads_total / bastion_reload_failures_total
+
+The dataset gauges follow the last output normalize wrote (daemon) or the
+snapshot being served. Alert on staleness with e.g.
+`time() - bastion_last_success_timestamp_seconds > 2 * 6 * 3600`.
+*/
+
+/// Counts behind the dataset gauges.
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub items: usize,
+    pub kev: usize,
+    pub by_severity: BTreeMap<String, usize>,
+}
+
+impl Dataset {
+    pub fn of(items: &[CanonicalItem]) -> Self {
+        let mut d = Dataset { items: items.len(), ..Default::default() };
+        for name in severity::names() {
+            d.by_severity.insert(name.to_string(), 0);
+        }
+        for item in items {
+            d.kev += item.kev as usize;
+            *d.by_severity.entry(item.severity_bucket.clone()).or_default() += 1;
+        }
+        d
+    }
+}
+
+#[derive(Default)]
+struct DaemonState {
+    dataset: Option<Dataset>,
+    cycles: u64,
+    failures: u64,
+    fetch_errors: u64,
+    last_success: Option<i64>,
+    normalize_seconds: Option<f64>,
+    cycle_seconds: Option<f64>,
+}
+
+static DAEMON: Mutex<Option<DaemonState>> = Mutex::new(None);
+
+/// Latest output of this process (normalize calls this after writing).
+pub fn set_dataset(dataset: Dataset) {
+    if let Ok(mut g) = DAEMON.lock()
+        && let Some(state) = g.as_mut()
+    {
+        state.dataset = Some(dataset);
+    }
+}
+
+pub struct Cycle {
+    pub ok: bool,
+    pub fetch_failed: bool,
+    pub normalize: Option<Duration>,
+    pub total: Duration,
+}
+
+/// Count one daemon cycle.
+pub fn record_cycle(cycle: &Cycle) {
+    let Ok(mut g) = DAEMON.lock() else { return };
+    let state = g.get_or_insert_with(DaemonState::default);
+    state.cycles += 1;
+    if cycle.ok {
+        state.last_success = Some(Utc::now().timestamp());
+    } else {
+        state.failures += 1;
+    }
+    state.fetch_errors += cycle.fetch_failed as u64;
+    if let Some(d) = cycle.normalize {
+        state.normalize_seconds = Some(d.as_secs_f64());
+    }
+    state.cycle_seconds = Some(cycle.total.as_secs_f64());
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+pub fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    metric(out, name, "gauge", help, &[(String::new(), value)]);
+}
+
+pub fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    metric(out, name, "counter", help, &[(String::new(), value as f64)]);
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+pub fn render_dataset(out: &mut String, d: &Dataset) {
+    gauge(out, "bastion_items", "Items in the dataset", d.items as f64);
+    let samples: Vec<(String, f64)> =
+        d.by_severity.iter().map(|(b, n)| (format!("{{severity=\"{}\"}}", escape(b)), *n as f64)).collect();
+    metric(out, "bastion_items_by_severity", "gauge", "Items per severity bucket", &samples);
+    gauge(out, "bastion_kev_items", "KEV-listed items", d.kev as f64);
+}
+
+/// The daemon's /metrics body.
+pub fn render_daemon() -> String {
+    let mut out = String::new();
+    let g = DAEMON.lock();
+    let Some(state) = g.as_ref().ok().and_then(|g| g.as_ref()) else { return out };
+    if let Some(d) = &state.dataset {
+        render_dataset(&mut out, d);
+    }
+    gauge(
+        &mut out,
+        "bastion_last_success_timestamp_seconds",
+        "Unix time the last successful cycle ended (0 before the first)",
+        state.last_success.unwrap_or(0) as f64,
+    );
+    counter(&mut out, "bastion_cycles_total", "Refresh cycles run", state.cycles);
+    counter(&mut out, "bastion_cycle_failures_total", "Refresh cycles that failed", state.failures);
+    counter(&mut out, "bastion_fetch_errors_total", "Failed --fetch-cmd runs", state.fetch_errors);
+    if let Some(s) = state.normalize_seconds {
+        gauge(&mut out, "bastion_normalize_duration_seconds", "Normalize time of the last cycle", s);
+    }
+    if let Some(s) = state.cycle_seconds {
+        gauge(&mut out, "bastion_cycle_duration_seconds", "Fetch plus normalize time of the last cycle", s);
+    }
+    out
+}
+
+/// Answer GET /metrics at `listen` from a background thread (daemon mode).
+pub fn listen(listen: &str) -> Result<()> {
+    // Registered before the first cycle, so the counters show zero rather than nothing
+    if let Ok(mut g) = DAEMON.lock() {
+        g.get_or_insert_with(DaemonState::default);
+    }
+    let listener = TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    log_ok!("daemon: metrics on http://{}/metrics", listener.local_addr()?);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            if let Err(e) = answer(stream) {
+                log_warn!("metrics: request failed: {:#}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+fn answer(mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are skipped, up to a small cap
+    for _ in 0..100 {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+    let (status, body) = match (method, target.split('?').next()) {
+        ("GET" | "HEAD", Some("/metrics")) => ("200 OK", render_daemon()),
+        ("GET" | "HEAD", _) => ("404 Not Found", "only /metrics here\n".to_string()),
+        _ => ("405 Method Not Allowed", "only GET /metrics\n".to_string()),
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        CONTENT_TYPE,
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()?;
+    Ok(())
+}
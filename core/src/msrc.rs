@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::{refs, severity, CanonicalItem};
+
+/* -------------------- MSRC CVRF parsing -------------------- */
+/*
+Microsoft publishes one CVRF document per Patch Tuesday
+(api.msrc.microsoft.com/cvrf/v3.0/cvrf/<yyyy-Mmm>, requested as JSON).
+NVD often lags these by days, so we pull vendor severity and KBs straight from MSRC.
+
+We target:
+- DocumentTracking.Identification.ID.Value           (e.g. "2024-Jan")
+- ProductTree.FullProductName[] { ProductID, Value }
+- Vulnerability[].CVE / Title.Value
+- Vulnerability[].Threats[] (Type 3 == severity)
+- Vulnerability[].CVSSScoreSets[].BaseScore
+- Vulnerability[].Remediations[] (Description.Value holds the KB number)
+- Vulnerability[].ProductStatuses[] (Type 3 == known affected)
+*/
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfDoc {
+    #[serde(default)]
+    document_tracking: Option<CvrfTracking>,
+    #[serde(default)]
+    product_tree: Option<CvrfProductTree>,
+    #[serde(default)]
+    vulnerability: Vec<CvrfVuln>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfTracking {
+    #[serde(default)]
+    identification: Option<CvrfIdentification>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfIdentification {
+    #[serde(default, rename = "ID")]
+    id: Option<CvrfValue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfValue {
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfProductTree {
+    #[serde(default)]
+    full_product_name: Vec<CvrfProductName>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfProductName {
+    #[serde(rename = "ProductID")]
+    product_id: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfVuln {
+    #[serde(default, rename = "CVE")]
+    cve: Option<String>,
+    #[serde(default)]
+    title: Option<CvrfValue>,
+    #[serde(default)]
+    threats: Vec<CvrfThreat>,
+    #[serde(default, rename = "CVSSScoreSets")]
+    cvss_score_sets: Vec<CvrfScoreSet>,
+    #[serde(default)]
+    remediations: Vec<CvrfRemediation>,
+    #[serde(default)]
+    product_statuses: Vec<CvrfProductStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfThreat {
+    #[serde(default, rename = "Type")]
+    kind: Option<u32>,
+    #[serde(default)]
+    description: Option<CvrfValue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfScoreSet {
+    #[serde(default)]
+    base_score: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfRemediation {
+    #[serde(default)]
+    description: Option<CvrfValue>,
+    #[serde(default, rename = "URL")]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrfProductStatus {
+    #[serde(default, rename = "ProductID")]
+    product_id: Vec<String>,
+    #[serde(default, rename = "Type")]
+    kind: Option<u32>,
+}
+
+const THREAT_SEVERITY: u32 = 3;
+const STATUS_KNOWN_AFFECTED: u32 = 3;
+
+/// Microsoft's view of a CVE from the MSRC CVRF feed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MsrcInfo {
+    pub release: Option<String>,         // CVRF document id, e.g. "2024-Jan"
+    pub severity: Option<String>,        // Critical|Important|Moderate|Low
+    pub cvss: Option<f64>,               // highest MSRC base score across products
+    pub kb_articles: Vec<String>,        // "KB5034122"
+    pub affected_products: Vec<String>,
+    #[serde(skip)]
+    title: Option<String>,
+    #[serde(skip)]
+    kb_urls: Vec<String>,
+}
+
+fn severity_rank(s: &str) -> u8 {
+    match s.to_ascii_lowercase().as_str() {
+        "critical" => 4,
+        "important" => 3,
+        "moderate" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+fn parse_doc(bytes: &[u8], out: &mut HashMap<String, MsrcInfo>) -> Result<()> {
+    let doc: CvrfDoc = serde_json::from_slice(bytes).with_context(|| "Failed to parse MSRC CVRF JSON")?;
+
+    let release = doc
+        .document_tracking
+        .and_then(|t| t.identification)
+        .and_then(|i| i.id)
+        .and_then(|v| v.value);
+    let products: HashMap<String, String> = doc
+        .product_tree
+        .map(|t| t.full_product_name)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| p.value.map(|v| (p.product_id, v)))
+        .collect();
+
+    for v in doc.vulnerability {
+        let Some(cve) = v.cve.map(|c| c.trim().to_string()) else { continue; };
+
+        let severity = v
+            .threats
+            .iter()
+            .filter(|t| t.kind == Some(THREAT_SEVERITY))
+            .filter_map(|t| t.description.as_ref().and_then(|d| d.value.clone()))
+            .max_by_key(|s| severity_rank(s));
+        let cvss = v
+            .cvss_score_sets
+            .iter()
+            .filter_map(|s| s.base_score)
+            .fold(None, |acc: Option<f64>, s| Some(acc.map_or(s, |a| a.max(s))));
+
+        let mut kbs: BTreeSet<String> = BTreeSet::new();
+        let mut kb_urls: BTreeSet<String> = BTreeSet::new();
+        for r in &v.remediations {
+            let Some(desc) = r.description.as_ref().and_then(|d| d.value.as_deref()) else { continue; };
+            let desc = desc.trim();
+            if !desc.is_empty() && desc.chars().all(|c| c.is_ascii_digit()) {
+                kbs.insert(format!("KB{}", desc));
+                if let Some(u) = &r.url {
+                    kb_urls.insert(u.trim().to_string());
+                }
+            }
+        }
+
+        let affected: BTreeSet<String> = v
+            .product_statuses
+            .iter()
+            .filter(|s| s.kind == Some(STATUS_KNOWN_AFFECTED))
+            .flat_map(|s| s.product_id.iter())
+            .filter_map(|id| products.get(id).cloned())
+            .collect();
+
+        out.insert(cve, MsrcInfo {
+            release: release.clone(),
+            severity,
+            cvss,
+            kb_articles: kbs.into_iter().collect(),
+            affected_products: affected.into_iter().collect(),
+            title: v.title.and_then(|t| t.value),
+            kb_urls: kb_urls.into_iter().collect(),
+        });
+    }
+    Ok(())
+}
+
+fn walk(path: &Path, out: &mut HashMap<String, MsrcInfo>) -> Result<()> {
+    if path.is_file() {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read MSRC document: {}", path.display()))?;
+        return parse_doc(&bytes, out);
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(path)
+        .with_context(|| format!("Failed to read MSRC directory: {}", path.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() || p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    // Oldest release first so later months' revisions win
+    entries.sort();
+    for p in entries {
+        walk(&p, out)?;
+    }
+    Ok(())
+}
+
+/// Merge MSRC CVRF data (a document or a directory of them) into matching items.
+/// Returns the number of items enriched.
+pub fn merge_msrc(path: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut records: HashMap<String, MsrcInfo> = HashMap::new();
+    walk(path, &mut records)?;
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let Some(mut info) = records.remove(&item.id) else { continue; };
+
+        if item.title.is_none() {
+            item.title = info.title.take();
+        }
+        if item.cvss.is_none() && info.cvss.is_some() {
+            item.cvss = info.cvss;
+            item.severity_bucket = severity::bucket(info.cvss, item.kev);
+        }
+        if item.vendor.is_none() {
+            item.vendor = Some("Microsoft".to_string());
+        }
+        if item.product.is_none() {
+            item.product = info.affected_products.first().cloned();
+        }
+        // KB articles are the update itself
+        for u in info.kb_urls.drain(..) {
+            let key = refs::url_key(&u);
+            if !item.refs.iter().any(|r| refs::url_key(&r.url) == key) {
+                let kb = refs::Reference { url: u, kind: refs::RefKind::Patch, source: "msrc".to_string(), dead: None };
+                item.refs.push(kb);
+            }
+        }
+        if !item.sources.iter().any(|s| s == "msrc") {
+            item.sources.push("msrc".to_string());
+        }
+        item.msrc = Some(info);
+        merged += 1;
+    }
+
+    Ok(merged)
+}
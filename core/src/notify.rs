@@ -0,0 +1,329 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::{
+    http, input, log_ok, log_warn, query, redact,
+    report::{Changes, Entry},
+    severity, CanonicalItem,
+};
+
+/* -------------------- Change notifications -------------------- */
+/*
+Pushes new high-priority items to on-call: items newly on the KEV list and
+items that newly reached a severity, optionally narrowed by a query filter.
+Targets come from notify.json:
+
+  { "targets": [
+      { "name": "on-call", "format": "slack", "url_env": "BASTION_ONCALL_SLACK",
+        "kev": true, "severity": "critical",
+        "filter": "vendor in [\"Microsoft\", \"Fortinet\"]" },
+      { "name": "soar", "format": "json", "url": "https://soar.internal/hooks/bastion",
+        "severity": null, "shareable": true, "limit": 100 } ] }
+
+  url | url_env  webhook URL, or the environment variable holding it
+  format       "json" (default) or "slack" (incoming-webhook message)
+  kev          alert on items newly on the KEV list (default true)
+  severity     alert on items newly at or above this bucket (default
+               "critical"; null turns it off)
+  filter       query expression (see query.rs) an alerted item must also match
+  limit        items per message (default 20); counts still cover all
+  shareable    leave embargoed items out, for hooks outside the team
+
+"json" posts {"target", "since", "generated_at", "count", "text", "items"},
+with the watchlist digest's per-item fields plus "reasons" (["kev", "critical"]).
+
+It runs after a diff, either standalone
+
+  core notify --config notify.json --delta items.delta.json   (or --old/--new)
+
+or inside normalize with --notify notify.json next to --baseline/--since-state,
+which is how `daemon` alerts every cycle. A target with nothing new is skipped.
+A failing hook is reported and doesn't stop the others; normalize only warns,
+the notify subcommand exits non-zero afterwards.
+
+Posting goes through http.rs (proxy, CA bundle, retries). URL and body reach
+curl as a config on stdin rather than on the command line, so a webhook
+secret doesn't show up in the process list.
+*/
+
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetFormat {
+    #[default]
+    Json,
+    Slack,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotifyFile {
+    targets: Vec<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Target {
+    pub name: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    url_env: Option<String>,
+    #[serde(default)]
+    format: TargetFormat,
+    #[serde(default = "yes")]
+    kev: bool,
+    #[serde(default = "default_severity")]
+    severity: Option<String>,
+    #[serde(default)]
+    filter: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    shareable: bool,
+    #[serde(skip)]
+    expr: Option<query::Expr>,
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn default_severity() -> Option<String> {
+    Some("critical".to_string())
+}
+
+#[derive(Serialize)]
+struct AlertItem<'a> {
+    id: &'a str,
+    title: &'a str,
+    severity: &'a str,
+    cvss: Option<f64>,
+    kev: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kev_due_date: Option<&'a str>,
+    vendor: Option<&'a str>,
+    product: Option<&'a str>,
+    url: String,
+    reasons: Vec<String>,
+}
+
+struct Alert<'a> {
+    item: &'a CanonicalItem,
+    reasons: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Outcome {
+    pub sent: usize,
+    pub failed: usize,
+}
+
+/// Read and check notify.json: a URL per target, known severities, parseable filters.
+pub fn load(path: &Path) -> Result<Vec<Target>> {
+    let bytes = input::read_input(path).with_context(|| format!("Failed to read notify config: {}", path.display()))?;
+    let file: NotifyFile =
+        serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse notify config: {}", path.display()))?;
+    let mut targets = file.targets;
+    for t in &mut targets {
+        if t.url.is_some() == t.url_env.is_some() {
+            anyhow::bail!("notify target '{}': set exactly one of url and url_env", t.name);
+        }
+        if let Some(s) = &t.severity
+            && severity::rank(s) == 0
+        {
+            anyhow::bail!("notify target '{}': unknown severity '{}' (known: {})", t.name, s, severity::names().join(", "));
+        }
+        if let Some(f) = &t.filter {
+            t.expr = Some(query::parse(f).with_context(|| format!("notify target '{}': invalid filter", t.name))?);
+        }
+    }
+    Ok(targets)
+}
+
+fn detail_url(item: &CanonicalItem) -> String {
+    format!("https://nvd.nist.gov/vuln/detail/{}", item.id)
+}
+
+impl Target {
+    fn url(&self) -> Result<String> {
+        match (&self.url, &self.url_env) {
+            (Some(url), _) => Ok(url.clone()),
+            (None, Some(var)) => std::env::var(var)
+                .with_context(|| format!("notify target '{}': {} is not set", self.name, var)),
+            (None, None) => anyhow::bail!("notify target '{}' has no url", self.name),
+        }
+    }
+
+    /// Why `e` should alert this target; empty if it shouldn't.
+    fn reasons(&self, e: &Entry) -> Result<Vec<String>> {
+        let Some(item) = &e.now else { return Ok(Vec::new()) };
+        let mut reasons = Vec::new();
+        if self.kev && e.new_kev() {
+            reasons.push("kev".to_string());
+        }
+        if let Some(s) = &self.severity
+            && e.newly_severe(severity::rank(s))
+        {
+            reasons.push(s.clone());
+        }
+        if reasons.is_empty() || (self.shareable && redact::is_embargoed(item, Utc::now())) {
+            return Ok(Vec::new());
+        }
+        if let Some(expr) = &self.expr
+            && !query::eval(expr, &serde_json::to_value(item)?)
+        {
+            return Ok(Vec::new());
+        }
+        Ok(reasons)
+    }
+
+    fn alerts<'a>(&self, changes: &'a Changes) -> Result<Vec<Alert<'a>>> {
+        let mut alerts = Vec::new();
+        for e in &changes.entries {
+            let reasons = self.reasons(e)?;
+            if let Some(item) = &e.now
+                && !reasons.is_empty()
+            {
+                alerts.push(Alert { item, reasons });
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+// "4 new alerts: 3 KEV, 2 critical"
+fn summary(alerts: &[Alert]) -> String {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for reason in alerts.iter().flat_map(|a| &a.reasons) {
+        match counts.iter_mut().find(|(r, _)| r == reason) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((reason.clone(), 1)),
+        }
+    }
+    let parts: Vec<String> =
+        counts.iter().map(|(r, n)| format!("{} {}", n, if r == "kev" { "KEV" } else { r.as_str() })).collect();
+    let noun = if alerts.len() == 1 { "alert" } else { "alerts" };
+    format!("{} new {}: {}", alerts.len(), noun, parts.join(", "))
+}
+
+fn json_payload(target: &Target, alerts: &[Alert], changes: &Changes) -> Value {
+    let limit = target.limit.unwrap_or(DEFAULT_LIMIT);
+    let items: Vec<AlertItem> = alerts
+        .iter()
+        .take(limit)
+        .map(|a| AlertItem {
+            id: &a.item.id,
+            title: a.item.title.as_deref().unwrap_or(&a.item.short_desc),
+            severity: &a.item.severity_bucket,
+            cvss: a.item.cvss,
+            kev: a.item.kev,
+            kev_due_date: a.item.kev_due_date.as_deref(),
+            vendor: a.item.vendor.as_deref(),
+            product: a.item.product.as_deref(),
+            url: detail_url(a.item),
+            reasons: a.reasons.clone(),
+        })
+        .collect();
+    json!({
+        "target": target.name,
+        "since": changes.since,
+        "generated_at": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "count": alerts.len(),
+        "text": summary(alerts),
+        "items": items,
+    })
+}
+
+// Slack mrkdwn only needs these three escaped
+fn mrkdwn(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn clip(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", s[..cut].trim_end()),
+        None => s.to_string(),
+    }
+}
+
+fn slack_payload(target: &Target, alerts: &[Alert], changes: &Changes) -> Value {
+    let limit = target.limit.unwrap_or(DEFAULT_LIMIT);
+    let text = summary(alerts);
+    let mut blocks = vec![json!({ "type": "header", "text": { "type": "plain_text", "text": clip(&text, 140) } })];
+    for a in alerts.iter().take(limit) {
+        let i = a.item;
+        let subject = [i.vendor.as_deref(), i.product.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        let mut line = format!("*<{}|{}>*", detail_url(i), i.id);
+        if !subject.is_empty() {
+            line.push_str(&format!(" {}", mrkdwn(&subject)));
+        }
+        line.push_str(&format!(": {}", mrkdwn(&clip(i.title.as_deref().unwrap_or(&i.short_desc), 200))));
+        let score = i.cvss.map(|c| format!("CVSS {}", c)).unwrap_or_else(|| "CVSS n/a".to_string());
+        let mut facts = vec![score, i.severity_bucket.clone()];
+        if i.kev {
+            facts.push(match &i.kev_due_date {
+                Some(due) => format!("KEV, due {}", due),
+                None => "KEV".to_string(),
+            });
+        }
+        line.push_str(&format!("\n{}", mrkdwn(&facts.join(" · "))));
+        blocks.push(json!({ "type": "section", "text": { "type": "mrkdwn", "text": line } }));
+    }
+    let mut context = format!("since {}", mrkdwn(&changes.since));
+    if alerts.len() > limit {
+        context = format!("… and {} more · {}", alerts.len() - limit, context);
+    }
+    blocks.push(json!({ "type": "context", "elements": [{ "type": "mrkdwn", "text": context }] }));
+    json!({ "text": text, "blocks": blocks })
+}
+
+fn post(target: &Target, payload: &Value) -> Result<()> {
+    let url = target.url()?;
+    // URL and body go in a curl config on stdin (see http.rs)
+    let config = format!(
+        "url = {}\ndata-binary = {}\n",
+        http::config_quote(&url),
+        http::config_quote(&serde_json::to_string(payload)?)
+    );
+    let args = ["--fail-with-body", "--max-time", "30", "-X", "POST", "-H", "Content-Type: application/json"];
+    let response = http::send_retrying(&format!("notify {}", target.name), &args, &config)?;
+    if !response.ok() {
+        anyhow::bail!("{} (curl exit {})", response.detail(), response.code);
+    }
+    Ok(())
+}
+
+/// Send each target its alerts from `changes`. With `dry_run` the payloads
+/// are printed on stdout instead.
+pub fn send(targets: &[Target], changes: &Changes, dry_run: bool) -> Result<Outcome> {
+    let mut outcome = Outcome::default();
+    for target in targets {
+        let alerts = target.alerts(changes)?;
+        if alerts.is_empty() {
+            log_ok!("notify {}: nothing new", target.name);
+            continue;
+        }
+        let payload = match target.format {
+            TargetFormat::Json => json_payload(target, &alerts, changes),
+            TargetFormat::Slack => slack_payload(target, &alerts, changes),
+        };
+        if dry_run {
+            println!("{}", serde_json::to_string_pretty(&json!({ "target": target.name, "payload": payload }))?);
+            continue;
+        }
+        match post(target, &payload) {
+            Ok(()) => {
+                outcome.sent += 1;
+                log_ok!("notify {}: posted {}", target.name, summary(&alerts));
+            }
+            Err(e) => {
+                outcome.failed += 1;
+                log_warn!("notify {}: webhook failed: {:#}", target.name, e);
+            }
+        }
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,167 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::BufRead,
+    path::Path,
+};
+
+use crate::{
+    input,
+    refs::{self, RefKind},
+    CanonicalItem,
+};
+
+/* -------------------- Nuclei template coverage -------------------- */
+/*
+An item with a projectdiscovery nuclei template can be checked from outside
+right away. --nuclei-templates takes either
+
+- a nuclei-templates checkout: every *.yaml under it is read for its top-level
+  `id:` and the `cve-id:` entries of its classification block (a string, a
+  comma list or a YAML list), so templates named apache-log4j-rce count too
+- the checkout's cves.json index (JSON lines, or an array):
+  { "ID": "CVE-2021-44228", "file_path": "http/cves/2021/CVE-2021-44228.yaml", ... }
+
+Items a template covers get has_nuclei_template and one ref per template,
+kind "detection", source "nuclei", linking the template on GitHub (so the
+template ID is the file name). `enrich --nuclei-templates` replaces what an
+earlier index added.
+*/
+
+const TEMPLATES_LINK: &str = "https://github.com/projectdiscovery/nuclei-templates/blob/main/";
+const SOURCE: &str = "nuclei";
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(default)]
+    file_path: Option<String>,
+}
+
+// CVE ID -> template paths relative to the checkout
+type Coverage = HashMap<String, BTreeSet<String>>;
+
+fn cve(s: &str) -> Option<String> {
+    let s = s.trim().trim_matches(['"', '\'']).to_uppercase();
+    s.starts_with("CVE-").then_some(s)
+}
+
+// id and cve-id values of one template, read line by line (no YAML parser here)
+fn template_cves(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut in_list = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if in_list {
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                out.extend(cve(item));
+                continue;
+            }
+            in_list = false;
+        }
+        if let Some(id) = line.strip_prefix("id:") {
+            out.extend(cve(id));
+        } else if let Some(value) = trimmed.strip_prefix("cve-id:") {
+            let value = value.trim().trim_start_matches('[').trim_end_matches(']');
+            in_list = value.is_empty();
+            out.extend(value.split(',').filter_map(cve));
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn walk_dir(root: &Path, dir: &Path, out: &mut Coverage) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read nuclei templates: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            walk_dir(root, &path, out)?;
+        } else if path.extension().is_some_and(|e| e == "yaml" || e == "yml") {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read nuclei template: {}", path.display()))?;
+            let rel = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            for id in template_cves(&text) {
+                out.entry(id).or_default().insert(rel.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_index(path: &Path, out: &mut Coverage) -> Result<()> {
+    let bytes = input::read_input(path)?;
+    let entries: Vec<IndexEntry> = match serde_json::from_slice(&bytes) {
+        Ok(entries) => entries,
+        Err(_) => {
+            let mut entries = Vec::new();
+            for (n, line) in bytes.lines().enumerate() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    let entry = serde_json::from_str(&line)
+                        .with_context(|| format!("{}: invalid nuclei index entry on line {}", path.display(), n + 1))?;
+                    entries.push(entry);
+                }
+            }
+            entries
+        }
+    };
+    if entries.is_empty() {
+        bail!("{} lists no nuclei templates", path.display());
+    }
+    for entry in entries {
+        let Some(id) = cve(&entry.id) else { continue };
+        let file = entry.file_path.unwrap_or_else(|| format!("{}.yaml", entry.id.trim()));
+        out.entry(id).or_default().insert(file.trim_start_matches("./").to_string());
+    }
+    Ok(())
+}
+
+/// Remove the template refs and flag an earlier merge added.
+pub fn clear_nuclei(items: &mut [CanonicalItem]) {
+    for item in items.iter_mut() {
+        item.refs.retain(|r| !(r.source == SOURCE && r.kind == RefKind::Detection));
+        item.sources.retain(|s| s != SOURCE);
+        item.has_nuclei_template = false;
+    }
+}
+
+/// Flag items covered by a nuclei template and link the templates. Returns the number flagged.
+pub fn merge_nuclei(path: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut coverage = Coverage::new();
+    if path.is_dir() {
+        walk_dir(path, path, &mut coverage)?;
+    } else {
+        parse_index(path, &mut coverage)?;
+    }
+
+    let mut flagged = 0usize;
+    for item in items.iter_mut() {
+        let Some(templates) = coverage.remove(&item.id) else { continue };
+        for file in templates {
+            let url = format!("{}{}", TEMPLATES_LINK, file);
+            let key = refs::url_key(&url);
+            if !item.refs.iter().any(|r| refs::url_key(&r.url) == key) {
+                let source = SOURCE.to_string();
+                item.refs.push(refs::Reference { url, kind: RefKind::Detection, source, dead: None });
+            }
+        }
+        if !item.sources.iter().any(|s| s == SOURCE) {
+            item.sources.push(SOURCE.to_string());
+        }
+        item.has_nuclei_template = true;
+        flagged += 1;
+    }
+    Ok(flagged)
+}
@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cpe, cvss, input, log_ok, refs,
+    source::{PartialItem, Source},
+    stream,
+};
+
+/* -------------------- NVD parsing (minimal, tolerant) -------------------- */
+/*
+NVD 2.0 feed format can evolve; we parse only what we need.
+
+We target:
+- vulnerabilities[].cve.id
+- vulnerabilities[].cve.published
+- vulnerabilities[].cve.lastModified
+- vulnerabilities[].cve.descriptions[] { lang, value }: the first of the
+  preferred languages (normalize --lang es,en; default en) present, else the
+  first non-empty one; --all-descriptions also keeps every variant by language
+- vulnerabilities[].cve.metrics.* (extract best available baseScore)
+- vulnerabilities[].cve.references[] { url }
+
+The vulnerabilities array is streamed record by record (see stream.rs),
+so there is no NvdRoot holding the whole feed. Records are converted in
+parallel batches of NVD_CHUNK and each batch goes straight to the Normalizer
+(for_each_item), so at most one batch of records is held besides the merged
+items. Repeated CVEs across files (yearly + modified) keep the newest
+lastModified: in the Normalizer (newest_wins), or in items() when called directly.
+*/
+
+// Records per parallel batch while streaming NVD
+const NVD_CHUNK: usize = 4096;
+
+#[derive(Debug, Deserialize)]
+struct NvdVulnWrap {
+    cve: NvdCve,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdCve {
+    id: String,
+    #[serde(default)]
+    published: Option<String>,
+    #[serde(default, rename = "lastModified")]
+    last_modified: Option<String>,
+    #[serde(default)]
+    descriptions: Vec<NvdLangValue>,
+    #[serde(default)]
+    references: Vec<NvdRef>,
+    #[serde(default)]
+    metrics: Option<serde_json::Value>,
+    #[serde(default)]
+    weaknesses: Vec<NvdWeakness>,
+    #[serde(default)]
+    configurations: Vec<cpe::NvdConfiguration>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdWeakness {
+    #[serde(default)]
+    description: Vec<NvdLangValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdLangValue {
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdRef {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// One or more NVD 2.0 feed files (yearly + modified), read in order.
+pub struct NvdSource {
+    paths: Vec<PathBuf>,
+    feeds: Vec<(String, Vec<u8>)>,
+    langs: Vec<String>,
+    all_descriptions: bool,
+}
+
+impl NvdSource {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        NvdSource { paths, feeds: Vec::new(), langs: vec!["en".to_string()], all_descriptions: false }
+    }
+
+    /// Feed files already in memory as (name, bytes), plain or gzip, read after any paths.
+    pub fn from_bytes(feeds: Vec<(String, Vec<u8>)>) -> Self {
+        NvdSource { feeds, ..NvdSource::new(Vec::new()) }
+    }
+
+    /// Description languages to prefer, in order; `all` also keeps every variant.
+    pub fn languages(mut self, langs: Vec<String>, all: bool) -> Self {
+        if !langs.is_empty() {
+            self.langs = langs;
+        }
+        self.all_descriptions = all;
+        self
+    }
+}
+
+// "es" also matches a regional tag such as "es-MX"
+fn lang_matches(lang: Option<&str>, want: &str) -> bool {
+    lang.is_some_and(|l| {
+        let primary = l.split('-').next().unwrap_or(l);
+        l.eq_ignore_ascii_case(want) || primary.eq_ignore_ascii_case(want)
+    })
+}
+
+fn pick_description(descs: &[NvdLangValue], langs: &[String]) -> Option<String> {
+    // preferred languages in order
+    for want in langs {
+        for d in descs {
+            if lang_matches(d.lang.as_deref(), want)
+                && let Some(v) = &d.value
+                && !v.trim().is_empty()
+            {
+                return Some(v.trim().to_string());
+            }
+        }
+    }
+    // fallback: first non-empty
+    for d in descs {
+        if let Some(v) = &d.value
+            && !v.trim().is_empty()
+        {
+            return Some(v.trim().to_string());
+        }
+    }
+    None
+}
+
+/// Every non-empty description by language tag; the first one per tag wins.
+fn all_descriptions_by_lang(descs: &[NvdLangValue]) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    for d in descs {
+        if let (Some(lang), Some(v)) = (&d.lang, &d.value)
+            && !v.trim().is_empty()
+        {
+            out.entry(lang.trim().to_lowercase()).or_insert_with(|| v.trim().to_string());
+        }
+    }
+    out
+}
+
+fn extract_best_cvss(metrics: &Option<serde_json::Value>) -> Option<cvss::CvssDetails> {
+    let m = metrics.as_ref()?;
+
+    // Try common NVD metric structures in preferred order (v3.1, v3.0, v2)
+    // We look for something like:
+    // metrics.cvssMetricV31[0].cvssData.baseScore
+    // metrics.cvssMetricV30[0].cvssData.baseScore
+    // metrics.cvssMetricV2[0].cvssData.baseScore
+    let candidates = ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"];
+
+    for key in candidates {
+        if let Some(arr) = m.get(key).and_then(|v| v.as_array())
+            && let Some(details) = arr.iter().find_map(cvss::from_nvd_entry)
+        {
+            return Some(details);
+        }
+    }
+
+    None
+}
+
+fn to_partial(cve: NvdCve, langs: &[String], all_descriptions: bool) -> PartialItem {
+    let id = cve.id.trim().to_string();
+
+    let mut refs: Vec<refs::Reference> = cve.references.iter()
+        .filter_map(|r| r.url.as_deref().map(|u| (u.trim(), &r.tags)))
+        .filter(|(u, _)| !u.is_empty())
+        .map(|(u, tags)| refs::Reference::new(u, tags, "nvd"))
+        .collect();
+
+    // Always include the NVD detail page as a ref
+    refs.push(refs::Reference::new(&format!("https://nvd.nist.gov/vuln/detail/{}", id), &[], "nvd"));
+
+    // NVD-CWE-Other / NVD-CWE-noinfo are placeholders, not weaknesses
+    let cwes: Vec<String> = cve.weaknesses.iter()
+        .flat_map(|w| w.description.iter())
+        .filter_map(|d| d.value.as_deref().map(str::trim))
+        .filter(|v| v.starts_with("CWE-"))
+        .map(str::to_string)
+        .collect();
+
+    PartialItem {
+        id,
+        published: cve.published,
+        last_modified: cve.last_modified,
+        cvss_details: extract_best_cvss(&cve.metrics),
+        // Later sources (KEV notes) may describe what NVD doesn't
+        description: pick_description(&cve.descriptions, langs),
+        descriptions: if all_descriptions { all_descriptions_by_lang(&cve.descriptions) } else { BTreeMap::new() },
+        placeholder: Some("No description available.".to_string()),
+        refs,
+        cwes,
+        affected: cpe::extract_affected(&cve.configurations),
+        ..Default::default()
+    }
+}
+
+/// Collapse duplicate CVE IDs (yearly + modified feeds overlap), keeping the
+/// newest lastModified. Ties go to the later file. First-seen position is kept.
+fn dedupe_newest(items: &mut Vec<PartialItem>) {
+    let mut slot: HashMap<String, usize> = HashMap::new();
+    let mut out: Vec<PartialItem> = Vec::with_capacity(items.len());
+    for item in items.drain(..) {
+        match slot.get(&item.id) {
+            Some(&idx) => {
+                // ISO8601 timestamps in one feed format compare lexicographically
+                if item.last_modified >= out[idx].last_modified {
+                    out[idx] = item;
+                }
+            }
+            None => {
+                slot.insert(item.id.clone(), out.len());
+                out.push(item);
+            }
+        }
+    }
+    *items = out;
+}
+
+impl Source for NvdSource {
+    fn name(&self) -> &str {
+        "nvd"
+    }
+
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.paths.clone()
+    }
+
+    fn newest_wins(&self) -> bool {
+        true
+    }
+
+    fn for_each_item(&self, f: &mut dyn FnMut(PartialItem) -> Result<()>) -> Result<()> {
+        // Stream records into chunks, convert each chunk in parallel and hand it on.
+        // par_drain().collect() keeps input order, so output is identical to a serial run.
+        let mut chunk: Vec<NvdCve> = Vec::with_capacity(NVD_CHUNK);
+        let convert = |chunk: &mut Vec<NvdCve>| -> Vec<PartialItem> {
+            chunk.par_drain(..).map(|cve| to_partial(cve, &self.langs, self.all_descriptions)).collect()
+        };
+        let mut on_record = |wrap: NvdVulnWrap| {
+            chunk.push(wrap.cve);
+            if chunk.len() == NVD_CHUNK {
+                convert(&mut chunk).into_iter().try_for_each(&mut *f)?;
+            }
+            Ok(())
+        };
+        for path in &self.paths {
+            stream::for_each_record(path, "vulnerabilities", "nvd", "/cve/id", &mut on_record)
+                .with_context(|| format!("Failed to parse NVD JSON: {}", path.display()))?;
+        }
+        for (name, bytes) in &self.feeds {
+            let reader = input::open_bytes(bytes, name)?;
+            stream::for_each_record_in(reader, Path::new(name), "vulnerabilities", "nvd", "/cve/id", &mut on_record)
+                .with_context(|| format!("Failed to parse NVD JSON: {}", name))?;
+        }
+        convert(&mut chunk).into_iter().try_for_each(f)
+    }
+
+    fn items(&self) -> Result<Vec<PartialItem>> {
+        let mut items: Vec<PartialItem> = Vec::new();
+        self.for_each_item(&mut |item| {
+            items.push(item);
+            Ok(())
+        })?;
+        let files = self.paths.len() + self.feeds.len();
+        if files > 1 {
+            let before = items.len();
+            dedupe_newest(&mut items);
+            log_ok!(
+                "nvd: {} files, {} records, {} unique CVEs",
+                files,
+                before,
+                items.len()
+            );
+        }
+        Ok(items)
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::errors::Failure;
+
+/* -------------------- Object storage targets -------------------- */
+/*
+A daemon in a container has no disk worth keeping; it can push straight to a
+bucket instead of leaving that to a sync sidecar. Inputs, normalize --out and
+--latest take
+
+  s3://bucket/path/items.json
+  gs://bucket/path/items.json            (feature "gcs")
+  az://account/container/path/items.json (feature "azure")
+
+Transfers shell out to the provider CLI, like sftp:// does to OpenSSH, so
+credentials come from wherever the CLI finds them (env, profiles, instance and
+workload identity) and nothing here handles secrets:
+
+  s3  aws s3 cp       AWS_PROFILE, AWS_REGION; AWS_ENDPOINT_URL for MinIO / R2 / Ceph
+  gs  gcloud storage cp
+  az  az storage blob upload / download; --auth-mode login unless
+      AZURE_STORAGE_KEY, _SAS_TOKEN or _CONNECTION_STRING is set
+
+BASTION_AWS, BASTION_GCLOUD and BASTION_AZ override the binaries. Large files
+go up as multipart / parallel composite / block uploads, split by the CLI
+(aws: above s3.multipart_threshold, 8 MB unless ~/.aws/config says otherwise).
+
+--sse aes256|kms asks for server-side encryption: SSE-S3 or SSE-KMS on S3, with
+--sse-kms-key naming the KMS key (bucket default otherwise). On GCS, kms needs
+--sse-kms-key (a CMEK key name); on Azure it names an encryption scope. aes256
+is the storage default on both.
+
+A finished upload replaces an object in one step, so readers see the previous
+version or the new one, never a partial file. --latest is promoted last, after
+the output and every sidecar are in place, as a copy (buckets have no links).
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Store {
+    S3,
+    Gcs,
+    Azure,
+}
+
+const SCHEMES: [(&str, Store); 3] = [("s3://", Store::S3), ("gs://", Store::Gcs), ("az://", Store::Azure)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sse {
+    Aes256,
+    Kms,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Encryption {
+    pub sse: Option<Sse>,
+    pub kms_key: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ObjectUrl {
+    pub store: Store,
+    pub account: Option<String>, // Azure storage account
+    pub bucket: String,          // bucket, or Azure container
+    pub key: String,
+}
+
+impl ObjectUrl {
+    fn url(&self) -> String {
+        match self.store {
+            Store::S3 => format!("s3://{}/{}", self.bucket, self.key),
+            Store::Gcs => format!("gs://{}/{}", self.bucket, self.key),
+            Store::Azure => {
+                format!("az://{}/{}/{}", self.account.as_deref().unwrap_or_default(), self.bucket, self.key)
+            }
+        }
+    }
+}
+
+pub fn is_object(path: &Path) -> bool {
+    let s = path.to_string_lossy();
+    SCHEMES.iter().any(|(scheme, _)| s.starts_with(scheme))
+}
+
+pub fn parse(path: &Path) -> Result<ObjectUrl> {
+    let s = path.to_string_lossy();
+    let Some((rest, store)) = SCHEMES.iter().find_map(|(scheme, store)| Some((s.strip_prefix(scheme)?, *store)))
+    else {
+        bail!("Not an object storage URL: {}", s);
+    };
+    match store {
+        Store::Gcs if !cfg!(feature = "gcs") => bail!("gs:// targets need a build with the gcs feature: {}", s),
+        Store::Azure if !cfg!(feature = "azure") => bail!("az:// targets need a build with the azure feature: {}", s),
+        _ => {}
+    }
+    let (account, rest) = match store {
+        Store::Azure => {
+            let (account, rest) =
+                rest.split_once('/').with_context(|| format!("az:// target has no container: {}", s))?;
+            (Some(account.to_string()), rest)
+        }
+        _ => (None, rest),
+    };
+    let (bucket, key) = rest.split_once('/').with_context(|| format!("object storage target has no key: {}", s))?;
+    if bucket.is_empty() || key.is_empty() || key.ends_with('/') || account.as_deref() == Some("") {
+        bail!("object storage target needs a bucket and an object key: {}", s);
+    }
+    Ok(ObjectUrl { store, account, bucket: bucket.to_string(), key: key.to_string() })
+}
+
+fn tool(store: Store) -> String {
+    let (var, default) = match store {
+        Store::S3 => ("BASTION_AWS", "aws"),
+        Store::Gcs => ("BASTION_GCLOUD", "gcloud"),
+        Store::Azure => ("BASTION_AZ", "az"),
+    };
+    std::env::var(var).unwrap_or_else(|_| default.to_string())
+}
+
+fn azure_args(target: &ObjectUrl, cmd: &mut Command) {
+    cmd.args(["--account-name", target.account.as_deref().unwrap_or_default()]);
+    cmd.args(["--container-name", &target.bucket, "--name", &target.key, "--only-show-errors"]);
+    let keyed = ["AZURE_STORAGE_KEY", "AZURE_STORAGE_SAS_TOKEN", "AZURE_STORAGE_CONNECTION_STRING"];
+    if !keyed.iter().any(|v| std::env::var_os(v).is_some()) {
+        cmd.args(["--auth-mode", "login"]);
+    }
+}
+
+fn run(mut cmd: Command, what: &str, url: &Path) -> Result<()> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let out = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to run {} for {}", program, url.display()))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(anyhow::Error::new(
+            Failure::new("remote_failed", format!("{} failed for {} ({}): {}", what, url.display(), out.status, stderr))
+                .path(url),
+        ));
+    }
+    Ok(())
+}
+
+/// Download an object into `local`.
+pub fn fetch(url: &Path, local: &Path) -> Result<()> {
+    let target = parse(url)?;
+    let mut cmd = Command::new(tool(target.store));
+    match target.store {
+        Store::S3 => {
+            cmd.args(["s3", "cp", "--only-show-errors", &target.url()]).arg(local);
+        }
+        Store::Gcs => {
+            cmd.args(["storage", "cp", "--no-user-output-enabled", &target.url()]).arg(local);
+        }
+        Store::Azure => {
+            cmd.args(["storage", "blob", "download", "--overwrite"]).arg("--file").arg(local);
+            azure_args(&target, &mut cmd);
+        }
+    }
+    run(cmd, "download", url)
+}
+
+/// Upload `local` to the object at `url`, replacing it.
+pub fn upload(local: &Path, url: &Path, enc: &Encryption) -> Result<()> {
+    let target = parse(url)?;
+    let mut cmd = Command::new(tool(target.store));
+    match target.store {
+        Store::S3 => {
+            cmd.args(["s3", "cp", "--only-show-errors"]).arg(local).arg(target.url());
+            match enc.sse {
+                Some(Sse::Aes256) => {
+                    cmd.args(["--sse", "AES256"]);
+                }
+                Some(Sse::Kms) => {
+                    cmd.args(["--sse", "aws:kms"]);
+                    if let Some(key) = &enc.kms_key {
+                        cmd.args(["--sse-kms-key-id", key]);
+                    }
+                }
+                None => {}
+            }
+        }
+        Store::Gcs => {
+            cmd.args(["storage", "cp", "--no-user-output-enabled"]).arg(local).arg(target.url());
+            if enc.sse == Some(Sse::Kms) {
+                let key = enc.kms_key.as_deref().context("--sse kms on gs:// needs --sse-kms-key (a CMEK key name)")?;
+                cmd.arg(format!("--encryption-key={}", key));
+            }
+        }
+        Store::Azure => {
+            cmd.args(["storage", "blob", "upload", "--overwrite"]).arg("--file").arg(local);
+            azure_args(&target, &mut cmd);
+            if enc.sse == Some(Sse::Kms) {
+                let key =
+                    enc.kms_key.as_deref().context("--sse kms on az:// needs --sse-kms-key (an encryption scope)")?;
+                cmd.args(["--encryption-scope", key]);
+            }
+        }
+    }
+    run(cmd, "upload", url)
+}
+
+/// Local file name for an object URL (the last key segment).
+pub fn file_name(url: &Path) -> Result<PathBuf> {
+    let target = parse(url)?;
+    Ok(PathBuf::from(target.key.rsplit('/').next().unwrap_or(&target.key)))
+}
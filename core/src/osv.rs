@@ -0,0 +1,215 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::Read, path::Path};
+
+use crate::{
+    input,
+    versions::{self, AffectedVersions, VersionRange},
+    CanonicalItem,
+};
+
+/* -------------------- OSV package ranges -------------------- */
+/*
+OSV (osv.dev, GitHub advisories, PyPA, Go, RustSec, ...) describes affected
+packages by ecosystem, name and version events:
+
+  { "id": "GHSA-...", "aliases": ["CVE-..."], "withdrawn": ...,
+    "affected": [{ "package": { "ecosystem", "name", "purl" },
+                   "ranges": [{ "type": "SEMVER"|"ECOSYSTEM"|"GIT", "events": [
+                     { "introduced": "0" }, { "fixed": "1.2.3" } ] }],
+                   "versions": ["1.0.0", ...] }] }
+
+--osv takes a directory of record files (searched recursively), a zip of them
+(osv.dev's per-ecosystem all.zip) or a single record / array of records. A
+record applies to the CVE it is (id) or aliases; each affected package becomes
+one affected_versions entry with source "osv" (see versions.rs). Events in a
+range read in order: introduced opens a run (gte), fixed closes it (lt),
+last_affected closes it inclusively (lte); limit is ignored. Withdrawn records
+and GIT ranges are skipped.
+*/
+
+#[derive(Debug, Deserialize)]
+struct OsvRecord {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    withdrawn: Option<String>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvAffected {
+    #[serde(default)]
+    package: Option<OsvPackage>,
+    #[serde(default)]
+    ranges: Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvPackage {
+    ecosystem: String,
+    name: String,
+    #[serde(default)]
+    purl: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvEvent {
+    #[serde(default)]
+    introduced: Option<String>,
+    #[serde(default)]
+    fixed: Option<String>,
+    #[serde(default)]
+    last_affected: Option<String>,
+}
+
+fn ranges_of(range: &OsvRange) -> Vec<VersionRange> {
+    let mut out = Vec::new();
+    let mut open: Option<VersionRange> = None;
+    for event in &range.events {
+        if let Some(v) = &event.introduced {
+            out.extend(open.take());
+            open = Some(VersionRange { gte: Some(v.clone()).filter(|v| v != "0"), ..Default::default() });
+        } else if let Some(v) = &event.fixed {
+            let mut r = open.take().unwrap_or_default();
+            r.lt = Some(v.clone());
+            out.push(r);
+        } else if let Some(v) = &event.last_affected {
+            let mut r = open.take().unwrap_or_default();
+            r.lte = Some(v.clone());
+            out.push(r);
+        }
+    }
+    out.extend(open);
+    out
+}
+
+fn entries(rec: &OsvRecord) -> Vec<AffectedVersions> {
+    let mut out = Vec::new();
+    for affected in &rec.affected {
+        let Some(pkg) = &affected.package else { continue };
+        let mut ranges = Vec::new();
+        let mut ecosystem_ordered = false;
+        for range in affected.ranges.iter().filter(|r| r.kind != "GIT") {
+            ecosystem_ordered |= range.kind == "ECOSYSTEM";
+            ranges.extend(ranges_of(range));
+        }
+        if ranges.is_empty() && affected.versions.is_empty() {
+            continue;
+        }
+        let fallback = if ecosystem_ordered { "ecosystem" } else { "opaque" };
+        let entry = AffectedVersions {
+            ecosystem: pkg.ecosystem.clone(),
+            product: pkg.name.clone(),
+            purl: pkg.purl.clone(),
+            scheme: versions::scheme(&ranges, &affected.versions, fallback),
+            ranges,
+            versions: affected.versions.clone(),
+            source: "osv".to_string(),
+            advisory: Some(rec.id.clone()),
+        };
+        if !out.contains(&entry) {
+            out.push(entry);
+        }
+    }
+    out
+}
+
+fn cve_ids(rec: &OsvRecord) -> impl Iterator<Item = &str> {
+    std::iter::once(&rec.id).chain(&rec.aliases).map(|s| s.trim()).filter(|s| s.starts_with("CVE-"))
+}
+
+// A file holds one record or an array of them; anything else (an index, a
+// schema) is skipped
+fn parse_records(bytes: &[u8], out: &mut Vec<OsvRecord>) {
+    if let Ok(rec) = serde_json::from_slice::<OsvRecord>(bytes) {
+        out.push(rec);
+    } else if let Ok(recs) = serde_json::from_slice::<Vec<OsvRecord>>(bytes) {
+        out.extend(recs);
+    }
+}
+
+fn walk_dir(dir: &Path, out: &mut Vec<OsvRecord>) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read OSV directory: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "json") {
+            let bytes = fs::read(&path).with_context(|| format!("Failed to read OSV record: {}", path.display()))?;
+            parse_records(&bytes, out);
+        }
+    }
+    Ok(())
+}
+
+fn walk_zip(path: &Path, out: &mut Vec<OsvRecord>) -> Result<()> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open OSV zip: {}", path.display()))?;
+    let mut archive =
+        zip::ZipArchive::new(file).with_context(|| format!("Failed to read OSV zip: {}", path.display()))?;
+    for idx in 0..archive.len() {
+        let mut entry = archive.by_index(idx)?;
+        if !entry.is_file() || !entry.name()?.ends_with(".json") {
+            continue;
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        parse_records(&bytes, out);
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Vec<OsvRecord>> {
+    let mut records = Vec::new();
+    if path.is_dir() {
+        walk_dir(path, &mut records)?;
+    } else if path.extension().is_some_and(|e| e == "zip") {
+        walk_zip(path, &mut records)?;
+    } else {
+        let bytes = input::read_input(path)?;
+        parse_records(&bytes, &mut records);
+        if records.is_empty() {
+            bail!("{} is not an OSV record or array of records", path.display());
+        }
+    }
+    Ok(records)
+}
+
+/// Add OSV package ranges to the items the records alias. Returns the number of items that gained any.
+pub fn merge_osv(path: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut by_cve: HashMap<String, Vec<AffectedVersions>> = HashMap::new();
+    for rec in load(path)?.iter().filter(|r| r.withdrawn.is_none()) {
+        let found = entries(rec);
+        if found.is_empty() {
+            continue;
+        }
+        for id in cve_ids(rec) {
+            by_cve.entry(id.to_string()).or_default().extend(found.iter().cloned());
+        }
+    }
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let Some(found) = by_cve.remove(&item.id) else { continue };
+        // The feed's ranges replace OSV entries kept from --merge-into
+        item.affected_versions.retain(|a| a.source != "osv");
+        item.affected_versions.extend(found);
+        merged += 1;
+    }
+    Ok(merged)
+}
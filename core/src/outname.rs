@@ -0,0 +1,117 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/* -------------------- Output name templates -------------------- */
+/*
+Scheduled runs name their own artifacts instead of relying on wrapper scripts:
+
+  normalize --out "snapshots/items-{date}-{source_hash_short}.json" --latest snapshots/items-latest.json
+
+  {date}               2025-06-01 (UTC)
+  {datetime}           20250601T061500Z
+  {source_hash}        sha256 over the raw KEV + NVD input files, in argument order
+  {source_hash_short}  its first 12 hex digits
+
+Same inputs, same source hash: a rerun on unchanged feeds overwrites its own
+artifact instead of adding a new one. --latest then points a stable name at the
+result, as a relative symlink or, with --latest-mode copy, a full copy for
+shares and tools that don't follow links. Both are replaced atomically.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LatestMode {
+    Symlink,
+    Copy,
+}
+
+pub fn is_template(path: &Path) -> bool {
+    path.to_string_lossy().contains('{')
+}
+
+fn source_hash(inputs: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for path in inputs {
+        if crate::input::is_remote(path) {
+            bail!("{{source_hash}} needs local inputs, got {}", path.display());
+        }
+        crate::digest::update_from_file(&mut hasher, path)?;
+    }
+    Ok(crate::digest::hex(&hasher.finalize()))
+}
+
+/// Expand placeholders in `template`. `inputs` are only read when a source hash is asked for.
+pub fn expand(template: &Path, now: DateTime<Utc>, inputs: &[PathBuf]) -> Result<PathBuf> {
+    let text = template.to_string_lossy();
+    let mut out = String::new();
+    let mut hash: Option<String> = None;
+    let mut rest = text.as_ref();
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .with_context(|| format!("Unclosed placeholder in output template: {}", text))?;
+        let name = &rest[start + 1..start + end];
+        match name {
+            "date" => out.push_str(&now.format("%Y-%m-%d").to_string()),
+            "datetime" => out.push_str(&now.format("%Y%m%dT%H%M%SZ").to_string()),
+            "source_hash" | "source_hash_short" => {
+                if hash.is_none() {
+                    hash = Some(source_hash(inputs)?);
+                }
+                let h = hash.as_deref().unwrap_or_default();
+                out.push_str(if name == "source_hash" { h } else { &h[..12] });
+            }
+            other => bail!(
+                "Unknown placeholder {{{}}} in output template (use date, datetime, source_hash, source_hash_short)",
+                other
+            ),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(PathBuf::from(out))
+}
+
+/// Point `latest` at `target`, replacing whatever was there.
+pub fn update_latest(latest: &Path, target: &Path, mode: LatestMode) -> Result<()> {
+    if let Some(parent) = latest.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let name = latest.file_name().with_context(|| format!("--latest has no file name: {}", latest.display()))?;
+    let tmp = latest.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    let _ = fs::remove_file(&tmp);
+
+    match mode {
+        LatestMode::Copy => {
+            fs::copy(target, &tmp).with_context(|| format!("Failed to copy {} to {}", target.display(), tmp.display()))?;
+        }
+        LatestMode::Symlink => {
+            // Relative when both live in the same directory, so the pair can be moved together
+            let same_dir = latest.parent().map(Path::new) == target.parent().map(Path::new);
+            let link_target = match (same_dir, target.file_name()) {
+                (true, Some(file)) => PathBuf::from(file),
+                _ => fs::canonicalize(target).with_context(|| format!("Failed to resolve {}", target.display()))?,
+            };
+            symlink(&link_target, &tmp)?;
+        }
+    }
+    fs::rename(&tmp, latest).with_context(|| format!("Failed to update {}", latest.display()))
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link).with_context(|| format!("Failed to create symlink {}", link.display()))
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+        .with_context(|| format!("Failed to create symlink {} (try --latest-mode copy)", link.display()))
+}
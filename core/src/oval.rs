@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+
+use crate::{distro::PackageStatus, xml};
+
+/* -------------------- Vendor OVAL streams -------------------- */
+/*
+Red Hat (security.access.redhat.com/data/oval/v2/, one stream per release) and
+SUSE (ftp.suse.com/pub/projects/security/oval/) publish OVAL 5 definitions that
+fleet tooling evaluates directly. distro.rs folds them into distro_status like
+the other trackers; this file only reads them. Pass uncompressed XML or .xml.gz
+(Red Hat ships .bz2: decompress it first).
+
+What is read from each <definition>:
+- CVEs: metadata/reference[source=CVE] and metadata/advisory/cve
+- releases: metadata/affected/platform ("Red Hat Enterprise Linux 9"), keyed as
+  rhel:9, sles:15-sp5, sled:15-sp5, slem:5.5, opensuse:leap-15.5; any other
+  platform becomes <first word>:<rest>, lowercased
+- packages: each criterion's test, through its object (package name) and state.
+  An evr/version "less than" state is the fixed version (status fixed); a test
+  with no state only checks the package is installed, which in a vulnerability
+  definition with no fix means vulnerable. Signature-key and other checks are
+  skipped, as are negated criteria.
+
+Criteria are a tree, and SUSE streams cover several platforms per definition
+with a different fixed version on each: a criterion "<platform> is installed"
+scopes its sibling criteria to that platform. Packages outside any platform
+scope apply to every platform the metadata lists.
+*/
+
+#[derive(Debug)]
+struct Test {
+    package: Option<String>,
+    // Some(fixed version) for a "less than" state, None without a state;
+    // tests with other states aren't kept
+    fixed: Option<String>,
+}
+
+const SLUGS: [(&str, &str, &str); 6] = [
+    ("Red Hat Enterprise Linux ", "rhel", ""),
+    ("SUSE Linux Enterprise Server ", "sles", ""),
+    ("SUSE Linux Enterprise Desktop ", "sled", ""),
+    ("SUSE Linux Enterprise Micro ", "slem", ""),
+    ("openSUSE Leap ", "opensuse", "leap-"),
+    ("openSUSE Tumbleweed", "opensuse", "tumbleweed"),
+];
+
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' {
+            out.push(c);
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_matches('-').to_string()
+}
+
+/// distro_status key for an OVAL platform name (see the module comment).
+pub fn release_key(platform: &str) -> String {
+    for (prefix, distro, release) in SLUGS {
+        if let Some(rest) = platform.strip_prefix(prefix) {
+            return format!("{}:{}{}", distro, release, slug(rest));
+        }
+    }
+    let (distro, rest) = platform.trim().split_once(' ').unwrap_or((platform.trim(), ""));
+    let distro: String = distro.to_lowercase().chars().filter(char::is_ascii_alphabetic).collect();
+    let release = slug(rest);
+    let distro = if distro.is_empty() { "oval" } else { &distro };
+    format!("{}:{}", distro, if release.is_empty() { "any" } else { &release })
+}
+
+fn index_tests(root: &xml::Element) -> HashMap<String, Test> {
+    let by_id = |section: &str| -> HashMap<&str, &xml::Element> {
+        root.child(section)
+            .map(|s| s.children.iter().filter_map(|e| Some((e.attr("id")?, e))).collect())
+            .unwrap_or_default()
+    };
+    let (objects, states) = (by_id("objects"), by_id("states"));
+    let mut tests = HashMap::new();
+    for test in root.child("tests").map(|t| t.children.as_slice()).unwrap_or_default() {
+        let Some(id) = test.attr("id") else { continue };
+        let object = test.child("object").and_then(|o| objects.get(o.attr("object_ref")?));
+        let package = object.and_then(|o| o.child("name")).map(|n| n.text.trim().to_string());
+        let fixed = match test.child("state").and_then(|s| s.attr("state_ref")) {
+            None => None,
+            Some(state_ref) => {
+                let state = states.get(state_ref);
+                let bound = state.and_then(|s| s.child("evr").or_else(|| s.child("version")));
+                match bound {
+                    Some(b) if b.attr("operation") == Some("less than") => Some(b.text.trim().to_string()),
+                    _ => continue,
+                }
+            }
+        };
+        tests.insert(id.to_string(), Test { package, fixed });
+    }
+    tests
+}
+
+struct Walk<'a> {
+    tests: &'a HashMap<String, Test>,
+    platforms: &'a [String],
+    vulnerability: bool,
+    // (platform, package status), platform None for "every platform"
+    out: Vec<(Option<String>, PackageStatus)>,
+}
+
+impl Walk<'_> {
+    fn platform_of(&self, criterion: &xml::Element) -> Option<String> {
+        let comment = criterion.attr("comment")?.strip_suffix(" is installed")?;
+        self.platforms.iter().find(|p| p.as_str() == comment).cloned()
+    }
+
+    fn criteria(&mut self, node: &xml::Element, scope: Option<String>) {
+        let criterions = node.children_named("criterion");
+        let scope = criterions.filter_map(|c| self.platform_of(c)).next().or(scope);
+        for child in &node.children {
+            match child.name.as_str() {
+                "criteria" => self.criteria(child, scope.clone()),
+                "criterion" if child.attr("negate") != Some("true") && self.platform_of(child).is_none() => {
+                    let Some(test) = child.attr("test_ref").and_then(|t| self.tests.get(t)) else { continue };
+                    let Some(package) = test.package.clone() else { continue };
+                    let (status, fixed_version) = match &test.fixed {
+                        Some(fixed) => ("fixed", Some(fixed.clone())),
+                        None if self.vulnerability => ("vulnerable", None),
+                        None => continue,
+                    };
+                    let status = PackageStatus { package, status: status.to_string(), fixed_version };
+                    self.out.push((scope.clone(), status));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// (CVE, distro_status key, package status) for every package criterion in an OVAL document.
+pub fn parse_oval(bytes: &[u8]) -> Result<Vec<(String, String, PackageStatus)>> {
+    let text = std::str::from_utf8(bytes).context("OVAL document is not UTF-8")?;
+    let root = xml::parse(text).context("Failed to parse OVAL XML")?;
+    let tests = index_tests(&root);
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    for def in root.child("definitions").into_iter().flat_map(|d| d.children_named("definition")) {
+        let Some(meta) = def.child("metadata") else { continue };
+        let mut cves: Vec<String> = meta
+            .children_named("reference")
+            .filter(|r| r.attr("source") == Some("CVE"))
+            .filter_map(|r| r.attr("ref_id"))
+            .chain(meta.child("advisory").into_iter().flat_map(|a| a.children_named("cve")).map(|c| c.text.trim()))
+            .filter(|c| c.starts_with("CVE-"))
+            .map(str::to_string)
+            .collect();
+        cves.sort();
+        cves.dedup();
+        let platforms: Vec<String> = meta
+            .children_named("affected")
+            .flat_map(|a| a.children_named("platform"))
+            .map(|p| p.text.trim().to_string())
+            .collect();
+        if cves.is_empty() || platforms.is_empty() {
+            continue;
+        }
+        let Some(criteria) = def.child("criteria") else { continue };
+        let vulnerability = def.attr("class") == Some("vulnerability");
+        let mut walk = Walk { tests: &tests, platforms: &platforms, vulnerability, out: Vec::new() };
+        walk.criteria(criteria, None);
+        for (scope, status) in walk.out {
+            let keys = match &scope {
+                Some(platform) => vec![release_key(platform)],
+                None => platforms.iter().map(|p| release_key(p)).collect(),
+            };
+            for key in keys {
+                for cve in &cves {
+                    // Streams repeat a package across definitions (one per advisory)
+                    let seen_key = (cve.clone(), key.clone(), status.package.clone(), status.fixed_version.clone());
+                    if seen.insert(seen_key) {
+                        out.push((cve.clone(), key.clone(), status.clone()));
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RHEL: &str = include_str!("../tests/fixtures/oval/rhel-9.xml");
+    const SUSE: &str = include_str!("../tests/fixtures/oval/suse.xml");
+
+    // (CVE, release key, package, status, fixed version)
+    type Row = (String, String, String, String, Option<String>);
+
+    fn rows(src: &str) -> Vec<Row> {
+        let parsed = parse_oval(src.as_bytes()).unwrap();
+        parsed.into_iter().map(|(cve, key, s)| (cve, key, s.package, s.status, s.fixed_version)).collect()
+    }
+
+    fn row(cve: &str, key: &str, package: &str, fixed: Option<&str>) -> Row {
+        let status = if fixed.is_some() { "fixed" } else { "vulnerable" };
+        (cve.into(), key.into(), package.into(), status.into(), fixed.map(str::to_string))
+    }
+
+    #[test]
+    fn reads_red_hat_streams() {
+        assert_eq!(rows(RHEL), [
+            row("CVE-2099-1001", "rhel:9", "openssl", Some("1:3.0.7-1.el9")),
+            row("CVE-2099-1002", "rhel:9", "openssl", Some("1:3.0.7-1.el9")),
+            row("CVE-2099-1003", "rhel:9", "libxml2", None),
+        ]);
+    }
+
+    #[test]
+    fn scopes_suse_criteria_to_their_platform() {
+        assert_eq!(rows(SUSE), [
+            row("CVE-2099-1010", "sles:15-sp5", "curl", Some("0:8.0.1-150400.5.23.1")),
+            row("CVE-2099-1010", "sles:15-sp6", "curl", Some("0:8.6.0-150600.16.1")),
+            row("CVE-2099-1010", "sles:15-sp5", "libcurl4", None),
+            row("CVE-2099-1010", "sles:15-sp6", "libcurl4", None),
+            row("CVE-2099-1010", "opensuse:leap-15.5", "libcurl4", None),
+        ]);
+    }
+
+    #[test]
+    fn release_keys() {
+        for (platform, key) in [
+            ("Red Hat Enterprise Linux 9", "rhel:9"),
+            ("SUSE Linux Enterprise Server 15 SP5", "sles:15-sp5"),
+            ("SUSE Linux Enterprise Micro 5.5", "slem:5.5"),
+            ("openSUSE Leap 15.5", "opensuse:leap-15.5"),
+            ("openSUSE Tumbleweed", "opensuse:tumbleweed"),
+            ("Ubuntu 22.04 LTS", "ubuntu:22.04-lts"),
+            ("Fedora", "fedora:any"),
+            ("", "oval:any"),
+        ] {
+            assert_eq!(release_key(platform), key, "{:?}", platform);
+        }
+    }
+
+    #[test]
+    fn malformed_streams_are_errors() {
+        assert!(parse_oval(b"\xff\xfe<oval_definitions/>").unwrap_err().to_string().contains("not UTF-8"));
+        let truncated = &RHEL[..RHEL.find("<tests>").unwrap()];
+        let err = format!("{:#}", parse_oval(truncated.as_bytes()).unwrap_err());
+        assert!(err.starts_with("Failed to parse OVAL XML: line "), "{}", err);
+        // Well-formed but not OVAL: nothing to read
+        assert!(parse_oval(b"<html><body/></html>").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::CanonicalItem;
+
+/* -------------------- KEV remediation deadlines -------------------- */
+/*
+Lists KEV items whose BOD 22-01 due date (kev_due_date) has passed as of a given
+day, or falls within the next N days, grouped by vendor for compliance reporting.
+Vendors sort by name, items by due date then ID, so reports are reproducible.
+
+KEV items without a parseable due date are counted, not listed: they need a
+re-normalize against a current catalog rather than remediation.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OverdueFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OverdueItem {
+    pub id: String,
+    pub product: Option<String>,
+    pub due_date: String,
+    pub days_overdue: i64, // negative: days left until the deadline
+    pub status: &'static str, // overdue|due_soon
+    pub ransomware_known: Option<bool>,
+    pub required_action: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VendorGroup {
+    pub vendor: String,
+    pub items: Vec<OverdueItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OverdueReport {
+    pub as_of: String,
+    pub within_days: i64,
+    pub kev_items: usize,
+    pub overdue: usize,
+    pub due_soon: usize,
+    pub missing_due_date: usize,
+    pub vendors: Vec<VendorGroup>,
+}
+
+pub fn compute(items: &[CanonicalItem], as_of: NaiveDate, within_days: i64) -> OverdueReport {
+    let mut report = OverdueReport {
+        as_of: as_of.to_string(),
+        within_days,
+        kev_items: 0,
+        overdue: 0,
+        due_soon: 0,
+        missing_due_date: 0,
+        vendors: Vec::new(),
+    };
+    let mut by_vendor: BTreeMap<String, Vec<OverdueItem>> = BTreeMap::new();
+
+    for item in items.iter().filter(|i| i.kev) {
+        report.kev_items += 1;
+        let Some(due) = item
+            .kev_due_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+        else {
+            report.missing_due_date += 1;
+            continue;
+        };
+
+        let days_overdue = (as_of - due).num_days();
+        let status = if days_overdue > 0 {
+            report.overdue += 1;
+            "overdue"
+        } else if -days_overdue <= within_days {
+            report.due_soon += 1;
+            "due_soon"
+        } else {
+            continue;
+        };
+
+        let vendor = item.vendor.clone().filter(|v| !v.trim().is_empty()).unwrap_or_else(|| "unknown".into());
+        by_vendor.entry(vendor).or_default().push(OverdueItem {
+            id: item.id.clone(),
+            product: item.product.clone(),
+            due_date: due.to_string(),
+            days_overdue,
+            status,
+            ransomware_known: item.ransomware_known,
+            required_action: item.kev_required_action.clone(),
+        });
+    }
+
+    for (vendor, mut items) in by_vendor {
+        items.sort_by(|a, b| a.due_date.cmp(&b.due_date).then_with(|| a.id.cmp(&b.id)));
+        report.vendors.push(VendorGroup { vendor, items });
+    }
+    report
+}
+
+pub fn render_markdown(r: &OverdueReport) -> String {
+    let mut out = Vec::new();
+    out.push(format!("# KEV remediation deadlines as of {}", r.as_of));
+    out.push(String::new());
+    out.push(format!("- KEV items: {}", r.kev_items));
+    out.push(format!("- Overdue: {}", r.overdue));
+    out.push(format!("- Due within {} days: {}", r.within_days, r.due_soon));
+    if r.missing_due_date > 0 {
+        out.push(format!("- Without a due date: {}", r.missing_due_date));
+    }
+
+    for group in &r.vendors {
+        out.push(String::new());
+        out.push(format!("## {} ({})", group.vendor, group.items.len()));
+        out.push(String::new());
+        out.push("| ID | Product | Due | Status | Ransomware |".to_string());
+        out.push("|---|---|---|---|---|".to_string());
+        for i in &group.items {
+            let status = if i.days_overdue > 0 {
+                format!("{} days overdue", i.days_overdue)
+            } else if i.days_overdue == 0 {
+                "due today".to_string()
+            } else {
+                format!("due in {} days", -i.days_overdue)
+            };
+            let ransomware = match i.ransomware_known {
+                Some(true) => "known",
+                Some(false) => "unknown",
+                None => "",
+            };
+            out.push(format!(
+                "| {} | {} | {} | {} | {} |",
+                i.id,
+                i.product.as_deref().unwrap_or("").replace('|', "\\|"),
+                i.due_date,
+                status,
+                ransomware,
+            ));
+        }
+    }
+
+    out.push(String::new());
+    out.join("\n")
+}
@@ -0,0 +1,245 @@
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{input, log_warn, severity, CanonicalItem};
+
+/* -------------------- Local overrides -------------------- */
+/*
+Internal assessments that disagree with the feeds, applied last in normalize so
+they win over NVD/KEV. TOML (or JSON of the same shape):
+
+  [[override]]
+  id = "CVE-2024-1234"
+  severity_bucket = "low"            # also: cvss, vendor, product, title, short_desc
+  reason = "Vulnerable module is not shipped in our builds"
+  author = "appsec"
+  expires = "2026-01-01"             # optional; ignored (with a warning) from that day
+
+  [[override]]
+  id = "CVE-2023-9999"
+  suppress = true                    # dropped from the output entirely
+  reason = "Vendor-confirmed false positive"
+
+  [[override]]
+  id = "CVE-2025-0001"
+  note = "Tracked in SEC-123"        # appended to internal_notes
+
+Every changed field is recorded in item.overrides with its original value, so
+consumers can tell pinned values from feed data. normalize restores those
+originals on items kept from --merge-into before applying the current file, so
+reapplying is a no-op and deleted rules stop applying. Pinning cvss without a
+bucket re-derives the bucket under the active severity policy.
+
+internal_notes and override reasons stay internal: --shareable strips them.
+*/
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverrideRule {
+    pub id: String,
+    #[serde(default)]
+    pub severity_bucket: Option<String>,
+    #[serde(default)]
+    pub cvss: Option<f64>,
+    #[serde(default)]
+    pub vendor: Option<String>,
+    #[serde(default)]
+    pub product: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub short_desc: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub suppress: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub expires: Option<String>, // YYYY-MM-DD
+}
+
+#[derive(Debug, Deserialize)]
+struct OverrideFile {
+    #[serde(default, rename = "override")]
+    rules: Vec<OverrideRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AppliedOverride {
+    pub field: String,
+    pub original: Value,
+    pub value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub source: String, // overrides file it came from
+}
+
+#[derive(Debug, Default)]
+pub struct OverrideStats {
+    pub applied: usize,
+    pub suppressed: Vec<String>,
+    pub expired: usize,
+    pub unmatched: Vec<String>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<OverrideRule>> {
+    let bytes = input::read_input(path)?;
+    let text = String::from_utf8(bytes).with_context(|| format!("Overrides file is not UTF-8: {}", path.display()))?;
+    let doc = if text.trim_start().starts_with('{') {
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse overrides JSON: {}", path.display()))?
+    } else {
+        crate::toml::parse(&text).with_context(|| format!("Failed to parse overrides TOML: {}", path.display()))?
+    };
+    let file: OverrideFile =
+        serde_json::from_value(doc).with_context(|| format!("Invalid overrides file: {}", path.display()))?;
+
+    let buckets = severity::names();
+    let mut seen = HashSet::new();
+    for rule in &file.rules {
+        if !seen.insert(rule.id.as_str()) {
+            bail!("{}: more than one [[override]] for {}", path.display(), rule.id);
+        }
+        if let Some(b) = &rule.severity_bucket
+            && !buckets.contains(&b.as_str())
+        {
+            bail!("{}: {} severity_bucket \"{}\" is not one of {}", path.display(), rule.id, b, buckets.join("|"));
+        }
+        if let Some(cvss) = rule.cvss
+            && !(0.0..=10.0).contains(&cvss)
+        {
+            bail!("{}: {} cvss {} is outside 0-10", path.display(), rule.id, cvss);
+        }
+        if let Some(day) = &rule.expires {
+            NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                .with_context(|| format!("{}: {} expires \"{}\" is not YYYY-MM-DD", path.display(), rule.id, day))?;
+        }
+    }
+    Ok(file.rules)
+}
+
+/// Put back whatever an earlier run pinned and drop its notes.
+pub fn revert_all(items: &mut [CanonicalItem]) {
+    for item in items.iter_mut() {
+        for o in std::mem::take(&mut item.overrides).into_iter().rev() {
+            let _ = set_field(item, &o.field, o.original);
+        }
+        item.internal_notes.clear();
+    }
+}
+
+fn get_field(item: &CanonicalItem, field: &str) -> Value {
+    match field {
+        "severity_bucket" => item.severity_bucket.clone().into(),
+        "cvss" => item.cvss.into(),
+        "vendor" => item.vendor.clone().into(),
+        "product" => item.product.clone().into(),
+        "title" => item.title.clone().into(),
+        "short_desc" => item.short_desc.clone().into(),
+        _ => Value::Null,
+    }
+}
+
+fn set_field(item: &mut CanonicalItem, field: &str, value: Value) -> Result<()> {
+    let s = || value.as_str().map(str::to_string);
+    match field {
+        "severity_bucket" => item.severity_bucket = s().unwrap_or_default(),
+        "cvss" => item.cvss = value.as_f64(),
+        "vendor" => item.vendor = s(),
+        "product" => item.product = s(),
+        "title" => item.title = s(),
+        "short_desc" => item.short_desc = s().unwrap_or_default(),
+        other => bail!("cannot override field {}", other),
+    }
+    Ok(())
+}
+
+/// Apply `rules` to `items` (suppressed items are removed). Call revert_all first.
+pub fn apply(items: &mut Vec<CanonicalItem>, rules: &[OverrideRule], source: &Path) -> Result<OverrideStats> {
+    let today = Utc::now().date_naive();
+    let source = source.display().to_string();
+    let mut stats = OverrideStats::default();
+
+    let index: HashMap<String, usize> = items.iter().enumerate().map(|(n, i)| (i.id.clone(), n)).collect();
+
+    let mut suppress: HashSet<&str> = HashSet::new();
+    for rule in rules {
+        let expired = rule
+            .expires
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .is_some_and(|d| d <= today);
+        if expired {
+            log_warn!("override for {} expired on {}; ignored", rule.id, rule.expires.as_deref().unwrap_or(""));
+            stats.expired += 1;
+            continue;
+        }
+        let Some(item) = index.get(&rule.id).map(|&n| &mut items[n]) else {
+            stats.unmatched.push(rule.id.clone());
+            continue;
+        };
+        if rule.suppress {
+            suppress.insert(rule.id.as_str());
+            continue;
+        }
+
+        let mut pins: Vec<(&str, Value)> = Vec::new();
+        if let Some(v) = rule.cvss {
+            pins.push(("cvss", v.into()));
+            if rule.severity_bucket.is_none() {
+                pins.push(("severity_bucket", severity::bucket(Some(v), item.kev).into()));
+            }
+        }
+        for (field, v) in [
+            ("severity_bucket", &rule.severity_bucket),
+            ("vendor", &rule.vendor),
+            ("product", &rule.product),
+            ("title", &rule.title),
+            ("short_desc", &rule.short_desc),
+        ] {
+            if let Some(v) = v {
+                pins.push((field, v.clone().into()));
+            }
+        }
+
+        for (field, value) in pins {
+            let original = get_field(item, field);
+            if original == value {
+                continue;
+            }
+            set_field(item, field, value.clone())?;
+            item.overrides.push(AppliedOverride {
+                field: field.to_string(),
+                original,
+                value,
+                reason: rule.reason.clone(),
+                author: rule.author.clone(),
+                source: source.clone(),
+            });
+        }
+        if let Some(note) = &rule.note {
+            item.internal_notes.push(match &rule.author {
+                Some(author) => format!("{} ({})", note, author),
+                None => note.clone(),
+            });
+        }
+        stats.applied += 1;
+    }
+
+    if !suppress.is_empty() {
+        items.retain(|i| !suppress.contains(i.id.as_str()));
+        stats.suppressed = suppress.into_iter().map(str::to_string).collect();
+        stats.suppressed.sort();
+    }
+    Ok(stats)
+}
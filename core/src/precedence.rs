@@ -0,0 +1,76 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/* -------------------- Merge precedence -------------------- */
+/*
+By default the first source that has a scalar wins (NVD, then KEV, then
+--source feeds; see source.rs). A MergePolicy reorders that, globally and per
+field:
+
+  normalize --source-priority kev,nvd --field-priority short_desc=acme,nvd ...
+
+or in the config file:
+
+  [normalize]
+  source-priority = "kev,nvd"
+  field-priority = ["short_desc=acme,nvd", "cvss=csaf,nvd"]
+
+A field's own list replaces the global one. Sources a list leaves out rank
+after every listed source, in source order among themselves, and a source only
+takes over a field another one already set when it ranks higher.
+
+Fields: published, last_modified, cvss (with cvss_details), short_desc, vendor,
+product. Besides the Normalizer's sources, `csaf` may be ranked for cvss: the
+vendor's score (first advisory that has one) then replaces the merged cvss
+(cvss_details keeps the feed's vector). Overrides still pin over everything.
+
+With a policy the Normalizer records which source set each of these fields in
+item.provenance, so normalize --with-provenance shows the real winner.
+*/
+
+pub const FIELDS: [&str; 6] = ["published", "last_modified", "cvss", "short_desc", "vendor", "product"];
+
+#[derive(Debug, Clone, Default)]
+pub struct MergePolicy {
+    default: Vec<String>,
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+fn source_list(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+impl MergePolicy {
+    /// From --source-priority LIST and --field-priority FIELD=LIST values; None when both are unset.
+    pub fn parse(default: Option<&str>, fields: &[String]) -> Result<Option<Self>> {
+        if default.is_none() && fields.is_empty() {
+            return Ok(None);
+        }
+        let mut policy = MergePolicy { default: default.map(source_list).unwrap_or_default(), ..Default::default() };
+        for spec in fields {
+            let (field, list) = spec
+                .split_once('=')
+                .with_context(|| format!("invalid field priority '{}': expected FIELD=SOURCE,...", spec))?;
+            let field = field.trim();
+            if !FIELDS.contains(&field) {
+                bail!("unknown field '{}' in field priority (fields: {})", field, FIELDS.join(", "));
+            }
+            policy.fields.insert(field.to_string(), source_list(list));
+        }
+        Ok(Some(policy))
+    }
+
+    /// Position of `source` in the list that applies to `field`; None when it isn't listed.
+    pub fn rank(&self, field: &str, source: &str) -> Option<usize> {
+        self.fields.get(field).unwrap_or(&self.default).iter().position(|s| s == source)
+    }
+
+    /// Whether `challenger` should replace the value `holder` set (None: unknown source).
+    pub fn prefers(&self, field: &str, challenger: &str, holder: Option<&str>) -> bool {
+        match (self.rank(field, challenger), holder.and_then(|h| self.rank(field, h))) {
+            (Some(c), Some(h)) => c < h,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, io::BufRead, path::Path};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- Priority scoring -------------------- */
+/*
+Composite triage priority, because raw CVSS alone isn't how anyone triages.
+Each signal is scaled to 0..1 and weighted:
+
+  cvss     cvss / 10 (unscored items contribute 0)
+  epss     FIRST EPSS probability, from --epss or else the item's stored `epss` (0 when absent)
+  kev      KEV-listed, or SSVC exploitation "active"
+  exploit  public exploit code or sensor-observed exploitation; SSVC "poc" counts half
+
+  priority_score = min(100, 100 * criticality * sum(w * signal) / sum(w))
+
+criticality comes from the first asset rule matching the item's vendor (and
+product, when given), case-insensitively; otherwise default_criticality. The
+tier is the first whose min_score the score reaches, so list them highest first.
+Defaults follow SSVC's deployer outcomes:
+
+  { "weights": { "cvss": 0.35, "epss": 0.25, "kev": 0.25, "exploit": 0.15 },
+    "assets": [ { "vendor": "Microsoft", "product": "Exchange Server", "criticality": 1.5 },
+                { "vendor": "Acme", "criticality": 0.5 } ],
+    "default_criticality": 1.0,
+    "tiers": [ { "name": "act", "min_score": 70 }, { "name": "attend", "min_score": 50 },
+               { "name": "track*", "min_score": 25 }, { "name": "track", "min_score": 0 } ] }
+
+Keys left out of a policy file keep these defaults.
+
+--epss also stores each item's probability as `epss` (none when FIRST has no
+score for it), so scoring again without --epss reuses the last one, and runs
+archived in the snapshot store keep its history (`snapshot epss-trend`).
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    pub cvss: f64,
+    pub epss: f64,
+    pub kev: f64,
+    pub exploit: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights { cvss: 0.35, epss: 0.25, kev: 0.25, exploit: 0.15 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetRule {
+    pub vendor: String,
+    #[serde(default)]
+    pub product: Option<String>,
+    pub criticality: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tier {
+    pub name: String,
+    pub min_score: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PriorityPolicy {
+    pub weights: Weights,
+    pub assets: Vec<AssetRule>,
+    pub default_criticality: f64,
+    pub tiers: Vec<Tier>,
+}
+
+impl Default for PriorityPolicy {
+    fn default() -> Self {
+        let tier = |name: &str, min_score: f64| Tier { name: name.to_string(), min_score };
+        PriorityPolicy {
+            weights: Weights::default(),
+            assets: Vec::new(),
+            default_criticality: 1.0,
+            tiers: vec![tier("act", 70.0), tier("attend", 50.0), tier("track*", 25.0), tier("track", 0.0)],
+        }
+    }
+}
+
+impl PriorityPolicy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = input::read_input(path)?;
+        let policy: PriorityPolicy = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse priority policy: {}", path.display()))?;
+        let w = &policy.weights;
+        if [w.cvss, w.epss, w.kev, w.exploit].iter().any(|v| *v < 0.0) || w.cvss + w.epss + w.kev + w.exploit <= 0.0 {
+            bail!("Priority policy {}: weights must be non-negative and not all zero", path.display());
+        }
+        if policy.tiers.is_empty() {
+            bail!("Priority policy {}: no tiers", path.display());
+        }
+        Ok(policy)
+    }
+
+    fn criticality(&self, item: &CanonicalItem) -> f64 {
+        let eq = |a: &str, b: Option<&String>| b.is_some_and(|b| b.trim().eq_ignore_ascii_case(a.trim()));
+        self.assets
+            .iter()
+            .find(|r| eq(&r.vendor, item.vendor.as_ref()) && r.product.as_ref().is_none_or(|p| eq(p, item.product.as_ref())))
+            .map_or(self.default_criticality, |r| r.criticality)
+    }
+
+    fn tier(&self, score: f64) -> String {
+        self.tiers
+            .iter()
+            .find(|t| score >= t.min_score)
+            .or(self.tiers.last())
+            .map(|t| t.name.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// FIRST's daily epss_scores-YYYY-MM-DD.csv(.gz): a "#model_version:..." comment,
+/// then cve,epss,percentile.
+pub fn load_epss(path: &Path) -> Result<HashMap<String, f64>> {
+    let reader = std::io::BufReader::new(input::open_input(path)?);
+    let mut scores = HashMap::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read EPSS scores: {}", path.display()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("cve,") {
+            continue;
+        }
+        let mut cols = line.split(',');
+        let (Some(cve), Some(epss)) = (cols.next(), cols.next()) else {
+            bail!("{}:{}: expected cve,epss,percentile", path.display(), n + 1);
+        };
+        let epss: f64 = epss
+            .trim()
+            .parse()
+            .with_context(|| format!("{}:{}: invalid EPSS score", path.display(), n + 1))?;
+        scores.insert(cve.trim().to_string(), epss.clamp(0.0, 1.0));
+    }
+    Ok(scores)
+}
+
+fn exploitation_signals(item: &CanonicalItem) -> (f64, f64) {
+    let ssvc = item.ssvc.as_ref().and_then(|s| s.exploitation.as_deref()).unwrap_or("");
+    let kev = if item.kev || ssvc.eq_ignore_ascii_case("active") { 1.0 } else { 0.0 };
+    let observed = item.observed_exploitation.as_ref().is_some_and(|o| o.observed);
+    let exploit = if item.exploit_public || observed {
+        1.0
+    } else if ssvc.eq_ignore_ascii_case("poc") {
+        0.5
+    } else {
+        0.0
+    };
+    (kev, exploit)
+}
+
+/// Set `priority_score` / `priority_tier` on every item, and `epss` from `epss`
+/// when given (else the stored one is used). Returns counts per tier, in policy order.
+pub fn score(
+    items: &mut [CanonicalItem],
+    policy: &PriorityPolicy,
+    epss: Option<&HashMap<String, f64>>,
+) -> Vec<(String, usize)> {
+    let w = &policy.weights;
+    let total = w.cvss + w.epss + w.kev + w.exploit;
+    let mut counts: Vec<(String, usize)> = policy.tiers.iter().map(|t| (t.name.clone(), 0)).collect();
+
+    for item in items.iter_mut() {
+        let (kev, exploit) = exploitation_signals(item);
+        let cvss = item.cvss.unwrap_or(0.0).clamp(0.0, 10.0) / 10.0;
+        if let Some(scores) = epss {
+            item.epss = scores.get(&item.id).copied();
+        }
+        let epss = item.epss.unwrap_or(0.0);
+        let raw = (w.cvss * cvss + w.epss * epss + w.kev * kev + w.exploit * exploit) / total;
+
+        let score = (100.0 * policy.criticality(item) * raw).clamp(0.0, 100.0);
+        let score = (score * 10.0).round() / 10.0;
+        let tier = policy.tier(score);
+        if let Some(c) = counts.iter_mut().find(|(name, _)| *name == tier) {
+            c.1 += 1;
+        }
+        item.priority_score = Some(score);
+        item.priority_tier = Some(tier);
+    }
+    counts
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use crate::CanonicalItem;
+
+/* -------------------- Per-field provenance -------------------- */
+/*
+normalize --with-provenance records, for every populated field, which input
+last set it and how fresh that input was:
+
+  "provenance": {
+    "cvss":        { "source": "nvd", "as_of": "2025-05-30T14:15:22.140" },
+    "vendor":      { "source": "kev", "as_of": "2025-06-01T17:54:30.000Z" },
+    "ssvc":        { "source": "vulnrichment", "as_of": "2025-06-02T06:00:11+00:00" },
+    "title":       { "source": "overrides", "as_of": "2025-05-20T09:12:03+00:00" }
+  }
+
+as_of is the record's lastModified for NVD fields, the catalog's dateReleased
+for KEV fields, and the input file's modification time for everything else.
+Derived fields (cwe_categories, fix_refs, tags, ...) name the pass that computed
+them and carry no as_of.
+
+NVD and KEV fields are attributed as items are built. After that, every pass is
+bracketed by a per-field hash of each item, and fields whose value changed are
+credited to that pass; a field a pass empties loses its entry. Items kept from
+--merge-into keep the provenance they were written with. Without the flag any
+stored provenance is dropped, so it never describes a run that didn't track it.
+
+The map is audit metadata and is left out of content_hash (see digest.rs).
+*/
+
+// Identity and bookkeeping, not data that comes from a source
+const UNTRACKED: &[&str] = &["id", "sources", "provenance", "overrides", "truncated", "content_hash"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldSource {
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<String>,
+}
+
+fn is_empty(v: &Value) -> bool {
+    match v {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
+}
+
+// Populated, tracked fields with a hash of their value
+fn field_hashes(item: &CanonicalItem) -> Vec<(String, u64)> {
+    let Ok(Value::Object(fields)) = serde_json::to_value(item) else { return Vec::new() };
+    fields
+        .into_iter()
+        .filter(|(k, v)| !UNTRACKED.contains(&k.as_str()) && !is_empty(v))
+        .map(|(k, v)| {
+            let mut h = DefaultHasher::new();
+            v.to_string().hash(&mut h);
+            (k, h.finish())
+        })
+        .collect()
+}
+
+/// Modification time of the newest of `paths` (RFC 3339), for inputs without their own timestamp.
+pub fn input_time<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<String> {
+    paths
+        .into_iter()
+        .filter_map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .max()
+        .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+}
+
+/// Attribute the fields normalize filled from NVD and KEV. `desc_from_kev` tells
+/// which items fell back to the KEV note for their description. Items from
+/// neither (normalize --source feeds only) credit their first source; fields a
+/// --source feed adds to an NVD/KEV item are credited to those. Entries the
+/// Normalizer recorded under a merge policy (see precedence.rs) are kept.
+pub fn seed(items: &mut [CanonicalItem], kev_as_of: Option<&str>, desc_from_kev: impl Fn(&CanonicalItem) -> bool) {
+    let kev = FieldSource { source: "kev".to_string(), as_of: kev_as_of.map(str::to_string) };
+    for item in items.iter_mut() {
+        let has = |name: &str| item.sources.iter().any(|s| s == name);
+        let feed_only = !has("nvd") && !has("kev");
+        let kev_only = has("kev") && !has("nvd");
+        let first = if feed_only { item.sources.first().cloned().unwrap_or_default() } else { "nvd".to_string() };
+        let primary = FieldSource { source: first, as_of: item.last_modified.clone() };
+        let kev_desc = desc_from_kev(item);
+        let recorded = std::mem::take(&mut item.provenance);
+        item.provenance = field_hashes(item)
+            .into_iter()
+            .map(|(field, _)| {
+                let kev_field = matches!(
+                    field.as_str(),
+                    "kev" | "kev_date_added" | "kev_due_date" | "kev_required_action" | "ransomware_known" | "vendor" | "product"
+                );
+                let from_kev = kev_only
+                    || (!feed_only
+                        && (kev_field
+                            || (field == "short_desc" && kev_desc)
+                            || (field == "severity_bucket" && item.cvss.is_none() && item.kev)));
+                let source = match recorded.get(&field) {
+                    Some(r) if r.source == "nvd" => FieldSource { as_of: item.last_modified.clone(), ..r.clone() },
+                    Some(r) if r.source == "kev" => kev.clone(),
+                    Some(r) => r.clone(),
+                    None if from_kev => kev.clone(),
+                    None => primary.clone(),
+                };
+                (field, source)
+            })
+            .collect();
+    }
+}
+
+/// Field hashes per item as of the last pass.
+pub struct Tracker {
+    snapshot: HashMap<String, Vec<(String, u64)>>,
+}
+
+impl Tracker {
+    pub fn new(items: &[CanonicalItem]) -> Self {
+        let snapshot = items.par_iter().map(|i| (i.id.clone(), field_hashes(i))).collect();
+        Tracker { snapshot }
+    }
+
+    /// Credit every field that changed since the last pass to `source`.
+    pub fn stage(&mut self, items: &mut [CanonicalItem], source: &str, as_of: Option<String>) {
+        let credit = FieldSource { source: source.to_string(), as_of };
+        let now: Vec<Vec<(String, u64)>> = items.par_iter().map(field_hashes).collect();
+        for (item, fields) in items.iter_mut().zip(now) {
+            let before = self.snapshot.get(&item.id);
+            let old = |field: &str| before.and_then(|b| b.iter().find(|(f, _)| f == field)).map(|(_, h)| *h);
+            for (field, hash) in &fields {
+                if old(field) != Some(*hash) {
+                    item.provenance.insert(field.clone(), credit.clone());
+                }
+            }
+            for (field, _) in before.into_iter().flatten() {
+                if !fields.iter().any(|(f, _)| f == field) {
+                    item.provenance.remove(field);
+                }
+            }
+            self.snapshot.insert(item.id.clone(), fields);
+        }
+    }
+
+    /// After --merge-into: kept items bring their stored provenance (or, if the
+    /// prior file had none, are credited to `source`), then tracking restarts.
+    pub fn rebase(&mut self, items: &mut [CanonicalItem], source: &str, as_of: Option<String>) {
+        let credit = FieldSource { source: source.to_string(), as_of };
+        for item in items.iter_mut().filter(|i| i.provenance.is_empty()) {
+            item.provenance = field_hashes(item).into_iter().map(|(f, _)| (f, credit.clone())).collect();
+        }
+        *self = Tracker::new(items);
+    }
+}
+
+/// normalize's pass hook: a no-op unless --with-provenance is on.
+pub fn stage(tracker: &mut Option<Tracker>, items: &mut [CanonicalItem], source: &str, as_of: Option<String>) {
+    if let Some(t) = tracker {
+        t.stage(items, source, as_of);
+    }
+}
@@ -0,0 +1,408 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+/* -------------------- Query expressions over canonical items -------------------- */
+/*
+Small filter language so consumers stop hand-rolling jq:
+
+  kev == true && severity_bucket in ["critical","high"] && published > 2024-01-01
+  vendor contains "micro" || !(cwes in ["CWE-79"])
+  msrc.severity == "Critical"
+
+- fields: any item field, dotted paths into nested objects
+- operators: == != > >= < <=, `in [..]`, `contains` (substring, case-insensitive,
+  or array membership), && || ! and parentheses; a bare field tests truthiness
+- literals: "strings", numbers, true/false/null, bare dates (2024-01-01)
+Ordering on strings is lexicographic, which is chronological for ISO8601 timestamps.
+A missing field never matches a comparison.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QueryFormat {
+    Json,
+    Jsonl,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Lit(Value),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(src: &str) -> Result<Vec<Tok>> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut toks = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        if let Some(op) = ["&&", "||", "==", "!=", ">=", "<="].into_iter().find(|op| *op == two) {
+            toks.push(Tok::Op(op));
+            i += 2;
+            continue;
+        }
+        match c {
+            '(' => toks.push(Tok::LParen),
+            ')' => toks.push(Tok::RParen),
+            '[' => toks.push(Tok::LBracket),
+            ']' => toks.push(Tok::RBracket),
+            ',' => toks.push(Tok::Comma),
+            '>' => toks.push(Tok::Op(">")),
+            '<' => toks.push(Tok::Op("<")),
+            '!' => toks.push(Tok::Op("!")),
+            '"' | '\'' => {
+                let quote = c;
+                let mut s = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        i += 1;
+                    }
+                    s.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    bail!("Unterminated string literal in query");
+                }
+                toks.push(Tok::Lit(Value::String(s)));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || ":.-+".contains(chars[i])) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                // Bare dates/timestamps stay strings so they compare against ISO8601 fields
+                let lit = match word.parse::<f64>() {
+                    Ok(n) => serde_json::Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null),
+                    Err(_) if c == '-' => bail!("Expected a number after '-' in query, found '{}'", word),
+                    Err(_) => Value::String(word),
+                };
+                toks.push(Tok::Lit(lit));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                toks.push(match word.as_str() {
+                    "true" => Tok::Lit(Value::Bool(true)),
+                    "false" => Tok::Lit(Value::Bool(false)),
+                    "null" => Tok::Lit(Value::Null),
+                    "in" => Tok::Op("in"),
+                    "contains" => Tok::Op("contains"),
+                    "and" => Tok::Op("&&"),
+                    "or" => Tok::Op("||"),
+                    "not" => Tok::Op("!"),
+                    _ => Tok::Ident(word),
+                });
+                continue;
+            }
+            other => bail!("Unexpected character '{}' in query", other),
+        }
+        i += 1;
+    }
+    Ok(toks)
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Truthy(String),
+    Cmp(String, &'static str, Value),
+}
+
+struct Parser {
+    toks: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let t = self.toks.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat_op("||") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while self.eat_op("&&") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_op("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        match self.next() {
+            Some(Tok::LParen) => {
+                let e = self.or()?;
+                match self.next() {
+                    Some(Tok::RParen) => Ok(e),
+                    _ => bail!("Expected ')' in query"),
+                }
+            }
+            Some(Tok::Ident(field)) => match self.peek() {
+                Some(Tok::Op(op)) if !matches!(*op, "&&" | "||" | "!") => {
+                    let op = *op;
+                    self.pos += 1;
+                    Ok(Expr::Cmp(field, op, self.literal()?))
+                }
+                _ => Ok(Expr::Truthy(field)),
+            },
+            other => bail!("Expected a field name or '(' in query, found {:?}", other),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        match self.next() {
+            Some(Tok::Lit(v)) => Ok(v),
+            // Unquoted words on the right-hand side are taken as strings
+            Some(Tok::Ident(w)) => Ok(Value::String(w)),
+            Some(Tok::LBracket) => {
+                let mut list = Vec::new();
+                if matches!(self.peek(), Some(Tok::RBracket)) {
+                    self.pos += 1;
+                    return Ok(Value::Array(list));
+                }
+                loop {
+                    list.push(self.literal()?);
+                    match self.next() {
+                        Some(Tok::Comma) => continue,
+                        Some(Tok::RBracket) => break,
+                        _ => bail!("Expected ',' or ']' in list literal"),
+                    }
+                }
+                Ok(Value::Array(list))
+            }
+            other => bail!("Expected a literal in query, found {:?}", other),
+        }
+    }
+}
+
+pub fn parse(src: &str) -> Result<Expr> {
+    let mut p = Parser { toks: tokenize(src)?, pos: 0 };
+    let expr = p.or()?;
+    if p.pos < p.toks.len() {
+        return Err(anyhow!("Unexpected trailing input in query: {:?}", &p.toks[p.pos..]));
+    }
+    Ok(expr)
+}
+
+/// Resolve "a.b.c" into a nested value.
+pub fn lookup<'a>(item: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(item, |v, key| v.get(key))
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => Some(x.as_str().cmp(y.as_str())),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn equals(a: &Value, b: &Value) -> bool {
+    compare(a, b) == Some(Ordering::Equal)
+}
+
+fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        Value::Number(_) => true,
+    }
+}
+
+pub fn eval(expr: &Expr, item: &Value) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, item) && eval(b, item),
+        Expr::Or(a, b) => eval(a, item) || eval(b, item),
+        Expr::Not(e) => !eval(e, item),
+        Expr::Truthy(field) => lookup(item, field).is_some_and(truthy),
+        Expr::Cmp(field, op, rhs) => {
+            let lhs = lookup(item, field).unwrap_or(&Value::Null);
+            match *op {
+                "==" => equals(lhs, rhs),
+                "!=" => !equals(lhs, rhs),
+                ">" => compare(lhs, rhs) == Some(Ordering::Greater),
+                ">=" => matches!(compare(lhs, rhs), Some(Ordering::Greater | Ordering::Equal)),
+                "<" => compare(lhs, rhs) == Some(Ordering::Less),
+                "<=" => matches!(compare(lhs, rhs), Some(Ordering::Less | Ordering::Equal)),
+                "in" => {
+                    let Value::Array(options) = rhs else { return false; };
+                    match lhs {
+                        Value::Array(values) => values.iter().any(|v| options.iter().any(|o| equals(v, o))),
+                        v => options.iter().any(|o| equals(v, o)),
+                    }
+                }
+                "contains" => match (lhs, rhs) {
+                    (Value::String(s), Value::String(needle)) => s.to_lowercase().contains(&needle.to_lowercase()),
+                    (Value::Array(values), needle) => values.iter().any(|v| equals(v, needle)),
+                    _ => false,
+                },
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Sort key spec: "cvss" (ascending) or "-cvss" / "cvss:desc" (descending). Missing sorts last.
+pub fn sort_values(values: &mut [Value], spec: &str) {
+    let (field, desc) = match spec.strip_prefix('-') {
+        Some(f) => (f, true),
+        None => match spec.strip_suffix(":desc") {
+            Some(f) => (f, true),
+            None => (spec.strip_suffix(":asc").unwrap_or(spec), false),
+        },
+    };
+    values.sort_by(|a, b| {
+        let (x, y) = (lookup(a, field).filter(|v| !v.is_null()), lookup(b, field).filter(|v| !v.is_null()));
+        match (x, y) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(x), Some(y)) => {
+                let ord = compare(x, y).unwrap_or(Ordering::Equal);
+                if desc { ord.reverse() } else { ord }
+            }
+        }
+    });
+}
+
+/// Keep only the listed (possibly dotted) fields; keys are the paths as given.
+pub fn project(value: &Value, fields: &[String]) -> Value {
+    let mut out = Map::new();
+    for f in fields {
+        out.insert(f.clone(), lookup(value, f).cloned().unwrap_or(Value::Null));
+    }
+    Value::Object(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn matches(src: &str, item: &Value) -> bool {
+        eval(&parse(src).unwrap(), item)
+    }
+
+    fn item() -> Value {
+        json!({
+            "id": "CVE-2024-0001",
+            "kev": true,
+            "cvss": 9.8,
+            "severity_bucket": "critical",
+            "published": "2024-03-05T10:00:00.000",
+            "vendor": "Microsoft",
+            "cwes": ["CWE-79", "CWE-89"],
+            "msrc": { "severity": "Critical" },
+            "title": null
+        })
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let item = item();
+        // kev || (false && ...) rather than (kev || false) && ...
+        assert!(matches("kev == true || cvss < 1 && vendor == \"nobody\"", &item));
+        assert!(!matches("(kev == true || cvss < 1) && vendor == \"nobody\"", &item));
+        assert!(matches("kev and cvss > 9 or title", &item));
+    }
+
+    #[test]
+    fn not_applies_to_the_nearest_term() {
+        let item = item();
+        assert!(!matches("!kev || cvss < 1", &item));
+        assert!(matches("not (kev && cvss < 1)", &item));
+        assert!(matches("!title", &item));
+        assert!(matches("!!kev", &item));
+    }
+
+    #[test]
+    fn in_lists_and_contains() {
+        let item = item();
+        assert!(matches("severity_bucket in [\"critical\", \"high\"]", &item));
+        assert!(!matches("severity_bucket in []", &item));
+        assert!(matches("cwes in [\"CWE-89\"]", &item));
+        assert!(matches("cvss in [7.5, 9.8]", &item));
+        assert!(matches("vendor contains \"SOFT\"", &item));
+        assert!(matches("cwes contains \"CWE-79\"", &item));
+        assert!(!matches("cwes contains \"CWE-7\"", &item));
+        assert!(matches("msrc.severity == Critical", &item));
+        assert!(!matches("missing.field > 0 || missing contains \"x\" || missing in [1]", &item));
+    }
+
+    #[test]
+    fn quoting_and_escapes() {
+        let item = json!({ "short_desc": "say \"hi\" and it's done\\" });
+        assert!(matches(r#"short_desc contains "\"hi\"""#, &item));
+        assert!(matches(r#"short_desc contains 'it\'s'"#, &item));
+        assert!(matches(r#"short_desc contains 'done\\'"#, &item));
+        assert!(parse("short_desc == \"open").is_err());
+    }
+
+    #[test]
+    fn dates_compare_as_iso8601_strings() {
+        let item = item();
+        assert!(matches("published > 2024-01-01", &item));
+        assert!(matches("published < 2024-03-05T11:00:00", &item));
+        assert!(!matches("published >= 2025-01-01", &item));
+        assert_eq!(tokenize("2024-01-01").unwrap(), vec![Tok::Lit(json!("2024-01-01"))]);
+        assert_eq!(tokenize("-2.5").unwrap(), vec![Tok::Lit(json!(-2.5))]);
+    }
+
+    #[test]
+    fn malformed_queries_are_errors() {
+        let bad = ["cvss > -", "cvss > -x", "kev == true kev", "kev )", "(kev", "cvss >", "&& kev"];
+        for src in bad.into_iter().chain(["kev == [1 2]", "a # b"]) {
+            assert!(parse(src).is_err(), "{} should not parse", src);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{log_debug, log_ok, parse_iso_datetime, CanonicalItem};
+
+/* -------------------- Redaction for shareable outputs -------------------- */
+/*
+Every command that produces something meant to leave the team (exports, feeds,
+derive for the public brief) takes --shareable and routes items through here.
+Internal outputs (normalize, query, stats) keep everything.
+
+Rules:
+- embargoed_until in the future -> item dropped
+- unparseable embargoed_until    -> item dropped (fail closed)
+- internal_notes, override reason/author -> removed (pinned values stay, see overrides.rs)
+*/
+
+/// Timestamps, or a bare date meaning midnight UTC.
+fn parse_until(ts: &str) -> Option<DateTime<Utc>> {
+    parse_iso_datetime(ts).or_else(|| {
+        let day = NaiveDate::parse_from_str(ts.trim(), "%Y-%m-%d").ok()?;
+        Some(day.and_hms_opt(0, 0, 0)?.and_utc())
+    })
+}
+
+pub fn is_embargoed(item: &CanonicalItem, now: DateTime<Utc>) -> bool {
+    match item.embargoed_until.as_deref() {
+        None => false,
+        Some(ts) => parse_until(ts).is_none_or(|until| until > now),
+    }
+}
+
+/// Drop embargoed items and internal annotations. Returns how many items were withheld.
+pub fn shareable(items: &mut Vec<CanonicalItem>) -> usize {
+    let now = Utc::now();
+    let before = items.len();
+    items.retain(|i| {
+        let embargoed = is_embargoed(i, now);
+        if embargoed {
+            log_debug!("redaction: dropped {} (embargoed until {})", i.id, i.embargoed_until.as_deref().unwrap_or(""));
+        }
+        !embargoed
+    });
+    for item in items.iter_mut() {
+        item.internal_notes.clear();
+        for o in item.overrides.iter_mut() {
+            o.reason = None;
+            o.author = None;
+        }
+    }
+    let withheld = before - items.len();
+    if withheld > 0 {
+        log_ok!("redaction withheld {} embargoed items", withheld);
+    }
+    withheld
+}
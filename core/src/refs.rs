@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::CanonicalItem;
+
+/* -------------------- Typed references -------------------- */
+/*
+item.refs entries carry what the link is and which feed contributed it:
+
+  { "url": "https://github.com/o/r/commit/abc123", "kind": "patch", "source": "nvd" }
+
+kind comes from NVD's reference tags when there are any (Patch, Vendor Advisory,
+Exploit, ...) and from the URL otherwise: commit/PR links are patches, Exploit-DB,
+Packet Storm and Metasploit modules are exploits, vendor and GHSA advisory pages
+are vendor advisories, nuclei templates are detections (see nuclei.rs),
+mailing-list archives are articles. A link with several tags takes the most
+actionable one. normalize orders refs by kind (patches, vendor advisories,
+exploits, mitigations, detections, other advisories, then the rest) and
+by URL within a kind, so consumers can surface the useful links first,
+--max-item-bytes truncation drops the least useful ones, and the order doesn't
+depend on which feed listed a link first.
+
+Links are deduplicated by url_key(), so http:// and https://, a trailing slash,
+host case and a default port don't make two entries of one page. The first
+feed's entry is kept, with the https:// spelling if any duplicate had it.
+normalize --check-refs marks or drops links that no longer resolve (see
+linkcheck.rs); `dead` says why.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefKind {
+    Patch,
+    VendorAdvisory,
+    Exploit,
+    Mitigation,
+    Detection,
+    Advisory,
+    Issue,
+    Article,
+    Other,
+}
+
+impl RefKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RefKind::Patch => "patch",
+            RefKind::VendorAdvisory => "vendor-advisory",
+            RefKind::Exploit => "exploit",
+            RefKind::Mitigation => "mitigation",
+            RefKind::Detection => "detection",
+            RefKind::Advisory => "advisory",
+            RefKind::Issue => "issue",
+            RefKind::Article => "article",
+            RefKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reference {
+    pub url: String,
+    pub kind: RefKind,
+    pub source: String, // feed that contributed the link: nvd, kev, msrc, internal, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead: Option<String>, // "HTTP 404", "host not found": set by normalize --check-refs
+}
+
+impl Reference {
+    /// Classify `url` from its NVD tags, falling back to the URL itself.
+    pub fn new(url: &str, tags: &[String], source: &str) -> Self {
+        let kind = tags.iter().filter_map(|t| kind_from_tag(t)).min().unwrap_or_else(|| classify_url(url));
+        Reference { url: url.to_string(), kind, source: source.to_string(), dead: None }
+    }
+}
+
+/// What two spellings of one link have in common: no scheme (http and https
+/// match), lowercase host without a default port, no trailing slash, empty query
+/// or empty fragment. Non-http(s) strings are their own key.
+pub fn url_key(url: &str) -> String {
+    let url = url.trim();
+    let lower = |n: usize| url.get(..n).map(str::to_ascii_lowercase);
+    let rest = match (lower(7).as_deref(), lower(8).as_deref()) {
+        (_, Some("https://")) => &url[8..],
+        (Some("http://"), _) => &url[7..],
+        _ => return url.to_string(),
+    };
+    let split = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, tail) = rest.split_at(split);
+    let host = authority.to_ascii_lowercase();
+    let host = host.strip_suffix(":443").or_else(|| host.strip_suffix(":80")).unwrap_or(&host);
+    let tail = tail.trim_end_matches('#').trim_end_matches('?');
+    let cut = tail.find(['?', '#']).unwrap_or(tail.len());
+    let (path, suffix) = tail.split_at(cut);
+    format!("{}{}{}", host, path.trim_end_matches('/'), suffix)
+}
+
+/// Dedupe `refs` by url_key (first entry wins, upgraded to https:// if a duplicate has it).
+pub fn dedup_refs(refs: &mut Vec<Reference>) {
+    let mut slot: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<Reference> = Vec::with_capacity(refs.len());
+    for r in refs.drain(..) {
+        match slot.entry(url_key(&r.url)) {
+            Entry::Occupied(e) => {
+                let first = &mut kept[*e.get()];
+                if first.url.get(..7).is_some_and(|s| s.eq_ignore_ascii_case("http://"))
+                    && r.url.get(..8).is_some_and(|s| s.eq_ignore_ascii_case("https://"))
+                {
+                    first.url = r.url;
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert(kept.len());
+                kept.push(r);
+            }
+        }
+    }
+    *refs = kept;
+}
+
+fn kind_from_tag(tag: &str) -> Option<RefKind> {
+    Some(match tag.trim() {
+        "Exploit" => RefKind::Exploit,
+        "Patch" => RefKind::Patch,
+        "Vendor Advisory" | "Release Notes" => RefKind::VendorAdvisory,
+        "Mitigation" => RefKind::Mitigation,
+        "Third Party Advisory" | "VDB Entry" | "US Government Resource" => RefKind::Advisory,
+        "Issue Tracking" => RefKind::Issue,
+        "Mailing List" | "Press/Media Coverage" | "Technical Description" => RefKind::Article,
+        // Product, Permissions Required, Broken Link, Not Applicable, ...: say nothing about the link
+        _ => return None,
+    })
+}
+
+/// URL heuristics for links without (useful) tags.
+pub fn classify_url(url: &str) -> RefKind {
+    if is_fix_ref(url) {
+        return RefKind::Patch;
+    }
+    let lower = url.to_ascii_lowercase();
+    let rest = lower.strip_prefix("https://").or_else(|| lower.strip_prefix("http://")).unwrap_or(&lower);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("").trim_start_matches("www.");
+    let path = &rest[rest.find('/').unwrap_or(rest.len())..];
+
+    let host_is = |h: &str| host == h || host.ends_with(&format!(".{}", h));
+    if host_is("exploit-db.com")
+        || host_is("packetstormsecurity.com")
+        || host_is("packetstorm.news")
+        || (host == "github.com" && path.starts_with("/rapid7/metasploit-framework/"))
+    {
+        return RefKind::Exploit;
+    }
+    if host == "github.com" && path.starts_with("/projectdiscovery/nuclei-templates/") {
+        return RefKind::Detection;
+    }
+    if (host == "github.com" && (path.contains("/security/advisories/") || path.starts_with("/advisories/ghsa-")))
+        || host_is("msrc.microsoft.com")
+        || host_is("support.microsoft.com")
+        || host_is("fortiguard.com")
+        || host_is("tools.cisco.com")
+        || host_is("sec.cloudapps.cisco.com")
+        || host.starts_with("security.")
+        || host.starts_with("psirt.")
+        || path.contains("/security-advisor")
+        || path.contains("/securityadvisor")
+        || path.contains("/security/advisor")
+    {
+        return RefKind::VendorAdvisory;
+    }
+    if host_is("nvd.nist.gov") || host_is("cve.org") || host_is("cve.mitre.org") || host_is("cisa.gov") || host_is("kb.cert.org") {
+        return RefKind::Advisory;
+    }
+    if (host == "github.com" && path.contains("/issues/"))
+        || (host.contains("gitlab") && path.contains("/-/issues/"))
+        || host.starts_with("bugzilla.")
+        || host.starts_with("bugs.")
+        || host.starts_with("issues.")
+        || path.contains("show_bug.cgi")
+    {
+        return RefKind::Issue;
+    }
+    if host_is("seclists.org") || host_is("openwall.com") || host.starts_with("lists.") || host.starts_with("mail.") {
+        return RefKind::Article;
+    }
+    RefKind::Other
+}
+
+/// Dedupe every item's refs (see dedup_refs) and order them by kind, then URL.
+pub fn order_refs(items: &mut [CanonicalItem]) {
+    for item in items.iter_mut() {
+        dedup_refs(&mut item.refs);
+        item.refs.sort_by(|a, b| (a.kind, &a.url).cmp(&(b.kind, &b.url)));
+    }
+}
+
+/// Add `url` unless the item already links it (in any spelling url_key equates).
+pub fn push_ref(refs: &mut Vec<Reference>, url: &str, source: &str) {
+    let key = url_key(url);
+    if !refs.iter().any(|r| url_key(&r.url) == key) {
+        refs.push(Reference::new(url, &[], source));
+    }
+}
+
+/// http(s) links embedded in free text (KEV notes: "https://a ; https://b"), first occurrence order.
+pub fn extract_urls<'a>(texts: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for text in texts {
+        for token in text.split(|c: char| c.is_whitespace() || c == ';') {
+            let url = token.trim_end_matches(['.', ',', ')', ']', '>']).trim_start_matches(['(', '[', '<']);
+            if (url.starts_with("https://") || url.starts_with("http://")) && !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/* -------------------- Fix reference mining -------------------- */
+/*
+Picks commit and pull/merge-request links out of item.refs into item.fix_refs,
+for patch tooling that wants code changes separate from advisories. refs keeps
+everything; fix_refs is a subset in the same order.
+
+Recognised:
+  github.com/<owner>/<repo>/commit/<sha>    github.com/<owner>/<repo>/pull/<n>
+  <gitlab host>/<group>/.../-/commit/<sha>  <gitlab host>/<group>/.../-/merge_requests/<n>
+"gitlab host" is gitlab.com or any host whose name contains "gitlab".
+*/
+
+fn is_hex_sha(s: &str) -> bool {
+    (7..=40).contains(&s.len()) && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+pub fn is_fix_ref(url: &str) -> bool {
+    let Some(rest) = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) else {
+        return false;
+    };
+    // Drop query/fragment ("#diff-...", "?w=1") and a trailing "/files" or ".patch"
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let segs: Vec<&str> = rest.trim_end_matches('/').split('/').collect();
+    let Some((host, path)) = segs.split_first() else { return false; };
+    let host = host.to_ascii_lowercase();
+    let tail = |s: &str| s.trim_end_matches(".patch").trim_end_matches(".diff").to_string();
+
+    if host == "github.com" || host == "www.github.com" {
+        // owner/repo/commit/<sha>[/...] or owner/repo/pull/<n>[/files|/commits/...]
+        return match path {
+            [_, _, "commit", sha, ..] => is_hex_sha(&tail(sha)),
+            [_, _, "pull", n, ..] => is_number(&tail(n)),
+            _ => false,
+        };
+    }
+    if host.contains("gitlab") {
+        let Some(dash) = path.iter().position(|s| *s == "-") else { return false; };
+        return match &path[dash + 1..] {
+            ["commit", sha, ..] => is_hex_sha(&tail(sha)),
+            ["merge_requests", n, ..] => is_number(&tail(n)),
+            _ => false,
+        } && dash >= 2; // at least group/project before "/-/"
+    }
+    false
+}
+
+/// Fill fix_refs on every item. Returns how many items have at least one.
+pub fn mine_fix_refs(items: &mut [CanonicalItem]) -> usize {
+    let mut found = 0;
+    for item in items.iter_mut() {
+        item.fix_refs = item.refs.iter().filter(|r| is_fix_ref(&r.url)).map(|r| r.url.clone()).collect();
+        if !item.fix_refs.is_empty() {
+            found += 1;
+        }
+    }
+    found
+}
@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{digest, errors::Failure, http, objstore};
+
+/* -------------------- SFTP targets -------------------- */
+/*
+Some regulated environments only move files over SSH bastions. Inputs and
+normalize --out may be given as
+
+  sftp://[user@]host[:port]/absolute/path/items.json
+
+Transfers shell out to the OpenSSH `sftp` client in batch mode, so keys, agents,
+known_hosts and ProxyJump all come from the operator's ssh config; nothing here
+prompts. BASTION_SFTP overrides the client binary (e.g. a wrapper that adds -F).
+
+Inputs are fetched to a temp file before parsing; outputs are written locally
+(sidecars included) and uploaded once complete, so the remote never sees a
+partial document.
+
+s3://, gs:// and az:// targets go through the same functions; objstore.rs
+does the transfers. http(s):// works for inputs only, downloaded by http.rs.
+*/
+
+const SCHEME: &str = "sftp://";
+
+pub struct SftpTarget {
+    pub authority: String, // [user@]host
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+pub fn is_remote(path: &Path) -> bool {
+    path.to_string_lossy().starts_with(SCHEME) || objstore::is_object(path) || http::is_url(path)
+}
+
+// Last path segment of a remote URL
+fn file_name(url: &Path) -> Result<PathBuf> {
+    if objstore::is_object(url) {
+        return objstore::file_name(url);
+    }
+    if http::is_url(url) {
+        let s = url.to_string_lossy();
+        let path = s.split(['?', '#']).next().unwrap_or(&s);
+        let name = path.rsplit('/').next().filter(|n| !n.is_empty() && !n.contains(':')).unwrap_or("download");
+        return Ok(PathBuf::from(name));
+    }
+    let target = parse(url)?;
+    Path::new(&target.path)
+        .file_name()
+        .map(PathBuf::from)
+        .with_context(|| format!("sftp target has no file name: {}", url.display()))
+}
+
+pub fn parse(path: &Path) -> Result<SftpTarget> {
+    let s = path.to_string_lossy();
+    let rest = s
+        .strip_prefix(SCHEME)
+        .with_context(|| format!("Not an sftp:// target: {}", s))?;
+    let (host_part, remote_path) = rest
+        .split_once('/')
+        .with_context(|| format!("sftp target has no path: {}", s))?;
+    if remote_path.is_empty() {
+        anyhow::bail!("sftp target has no path: {}", s);
+    }
+
+    // user@host:port; IPv6 hosts need brackets, as in URLs
+    let (authority, port) = match host_part.rsplit_once(':') {
+        Some((a, p)) if !p.contains(']') => {
+            let port = p.parse().with_context(|| format!("Invalid port in sftp target: {}", s))?;
+            (a.to_string(), Some(port))
+        }
+        _ => (host_part.to_string(), None),
+    };
+    if authority.is_empty() || authority.ends_with('@') {
+        anyhow::bail!("sftp target has no host: {}", s);
+    }
+    Ok(SftpTarget { authority, port, path: format!("/{}", remote_path) })
+}
+
+fn quote(path: &str) -> String {
+    // sftp batch files accept double-quoted arguments with backslash escapes
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn run_batch(target: &SftpTarget, url: &Path, commands: &str) -> Result<()> {
+    let client = std::env::var("BASTION_SFTP").unwrap_or_else(|_| "sftp".to_string());
+    let mut cmd = Command::new(&client);
+    cmd.args(["-q", "-o", "BatchMode=yes", "-b", "-"]);
+    if let Some(port) = target.port {
+        cmd.args(["-P", &port.to_string()]);
+    }
+    cmd.arg(&target.authority)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {} for {}", client, url.display()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(commands.as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(anyhow::Error::new(Failure::new(
+            "remote_failed",
+            format!("sftp transfer failed for {} ({}): {}", url.display(), out.status, stderr),
+        )
+        .path(url)));
+    }
+    Ok(())
+}
+
+/// Download a remote input into the temp dir and return the local copy.
+pub fn fetch(url: &Path) -> Result<PathBuf> {
+    let name = file_name(url).map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let key = &digest::sha256_hex(url.to_string_lossy().as_bytes())[..16];
+    let local = std::env::temp_dir().join(format!("bastion-remote-{}-{}", key, name));
+    if objstore::is_object(url) {
+        objstore::fetch(url, &local)?;
+        return Ok(local);
+    }
+    if http::is_url(url) {
+        http::download(&url.to_string_lossy(), &local)?;
+        return Ok(local);
+    }
+
+    let target = parse(url)?;
+    let commands = format!("get {} {}\n", quote(&target.path), quote(&local.to_string_lossy()));
+    run_batch(&target, url, &commands)?;
+    Ok(local)
+}
+
+/// Upload `files` as (local, remote URL) pairs in order: sftp in one session,
+/// each via a temporary name and a rename so readers never see a partial file;
+/// objects one by one, with `enc` (see objstore.rs).
+pub fn upload(files: &[(PathBuf, PathBuf)], enc: &objstore::Encryption) -> Result<()> {
+    if let Some((_, url)) = files.iter().find(|(_, url)| http::is_url(url)) {
+        anyhow::bail!("http(s):// is for inputs only, not outputs: {}", url.display());
+    }
+    let (objects, files): (Vec<_>, Vec<_>) = files.iter().cloned().partition(|(_, url)| objstore::is_object(url));
+    for (local, url) in &objects {
+        objstore::upload(local, url, enc)?;
+    }
+    let Some((_, first)) = files.first() else { return Ok(()) };
+    let target = parse(first)?;
+
+    let mut commands = String::new();
+    for (local, url) in &files {
+        let remote = parse(url)?;
+        if remote.authority != target.authority || remote.port != target.port {
+            anyhow::bail!("sftp uploads in one run must share a host: {}", url.display());
+        }
+        let partial = format!("{}.partial", remote.path);
+        commands.push_str(&format!("put {} {}\n", quote(&local.to_string_lossy()), quote(&partial)));
+        // '-' prefix: a missing previous version is fine
+        commands.push_str(&format!("-rm {}\n", quote(&remote.path)));
+        commands.push_str(&format!("rename {} {}\n", quote(&partial), quote(&remote.path)));
+    }
+    run_batch(&target, first, &commands)
+}
+
+/// Local staging path for a remote output.
+pub fn staging_path(url: &Path) -> Result<PathBuf> {
+    if http::is_url(url) {
+        anyhow::bail!("http(s):// is for inputs only, not outputs: {}", url.display());
+    }
+    let name = file_name(url)?;
+    let key = &digest::sha256_hex(url.to_string_lossy().as_bytes())[..16];
+    let dir = std::env::temp_dir().join(format!("bastion-remote-out-{}", key));
+    // Start empty so a sidecar left by an earlier run is not uploaded again
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create staging dir: {}", dir.display()))?;
+    Ok(dir.join(name))
+}
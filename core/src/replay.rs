@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{digest, errors::Failure};
+
+/* -------------------- Replay harness -------------------- */
+/*
+A replay bundle is a directory of archived inputs plus replay.json:
+
+  { "recorded_with": "0.1.0",
+    "runs": [ { "name": "weekly normalize",
+                "args": ["normalize", "--kev", "kev.json.gz", "--nvd", "nvd.json.gz",
+                         "--out", "$OUT/items.json"],
+                "outputs": [ { "path": "items.json", "sha256": "..." } ] },
+              { "name": "diff vs last week",
+                "args": ["diff", "--old", "prev.json", "--new", "$OUT/items.json"],
+                "stdout": "diff.md",
+                "outputs": [ { "path": "diff.md", "sha256": "..." } ] } ] }
+
+`args` is everything after the binary name, so global flags (--severity-policy
+...) belong there too. Runs execute in order with the bundle as working
+directory, so relative input paths resolve inside it; $OUT expands to a scratch
+directory. Commands that report on stdout name a file under $OUT to capture it
+in. Every listed output must then hash to the recorded sha256.
+
+  replay --bundle DIR            verify the current binary reproduces the outputs
+  replay --bundle DIR --record   (re)write the sha256 values from this binary
+
+Run it with a candidate build before rolling it into a pipeline: any mismatch
+fails with code "replay_mismatch" and --keep leaves the outputs for diffing.
+*/
+
+pub const MANIFEST: &str = "replay.json";
+const OUT_VAR: &str = "$OUT";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub recorded_with: Option<String>,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Run {
+    pub name: String,
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>, // capture stdout into $OUT/<file>
+    pub outputs: Vec<ExpectedOutput>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpectedOutput {
+    pub path: String, // relative to $OUT
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    pub run: String,
+    pub path: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>, // None: output was not written
+}
+
+pub fn load(bundle: &Path) -> Result<Manifest> {
+    let path = bundle.join(MANIFEST);
+    let text = fs::read_to_string(&path).with_context(|| format!("Failed to read replay manifest: {}", path.display()))?;
+    let manifest: Manifest = serde_json::from_str(&text)
+        .with_context(|| Failure::new("invalid_json", "Failed to parse replay manifest").path(&path))?;
+    if manifest.runs.is_empty() {
+        anyhow::bail!("Replay manifest has no runs: {}", path.display());
+    }
+    Ok(manifest)
+}
+
+fn run_one(run: &Run, bundle: &Path, out_dir: &Path) -> Result<()> {
+    let exe = std::env::current_exe().with_context(|| "Failed to locate the running binary")?;
+    let out = out_dir.to_string_lossy();
+    let args: Vec<String> = run.args.iter().map(|a| a.replace(OUT_VAR, &out)).collect();
+
+    let mut cmd = Command::new(&exe);
+    cmd.args(&args).current_dir(bundle);
+    if let Some(name) = &run.stdout {
+        let path = out_dir.join(name);
+        let file = fs::File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        cmd.stdout(file);
+    }
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to run replay step \"{}\"", run.name))?;
+    if !status.success() {
+        anyhow::bail!("Replay step \"{}\" failed ({})", run.name, status);
+    }
+    Ok(())
+}
+
+/// Execute every run and check (or, when `record`, fill in) the output hashes.
+pub fn replay(bundle: &Path, manifest: &mut Manifest, out_dir: &Path, record: bool) -> Result<Vec<Mismatch>> {
+    fs::create_dir_all(out_dir).with_context(|| format!("Failed to create replay output dir: {}", out_dir.display()))?;
+    // Runs execute inside the bundle, so a relative --keep must not resolve there
+    let out_dir = &fs::canonicalize(out_dir)?;
+    let mut mismatches = Vec::new();
+
+    for run in manifest.runs.iter_mut() {
+        run_one(run, bundle, out_dir)?;
+        for output in run.outputs.iter_mut() {
+            let path: PathBuf = out_dir.join(&output.path);
+            let actual = fs::read(&path).ok().map(|b| digest::sha256_hex(&b));
+            if record {
+                output.sha256 = Some(
+                    actual.with_context(|| format!("Replay step \"{}\" did not write {}", run.name, output.path))?,
+                );
+                continue;
+            }
+            if output.sha256.is_none() || actual != output.sha256 {
+                mismatches.push(Mismatch {
+                    run: run.name.clone(),
+                    path: output.path.clone(),
+                    expected: output.sha256.clone(),
+                    actual,
+                });
+            }
+        }
+    }
+
+    if record {
+        manifest.recorded_with = Some(env!("CARGO_PKG_VERSION").to_string());
+    }
+    Ok(mismatches)
+}
+
+pub fn save(bundle: &Path, manifest: &Manifest) -> Result<()> {
+    let path = bundle.join(MANIFEST);
+    fs::write(&path, serde_json::to_string_pretty(manifest)? + "\n")
+        .with_context(|| format!("Failed to write replay manifest: {}", path.display()))
+}
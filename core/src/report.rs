@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::ValueEnum;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    codex,
+    delta::{self, ChangeKind, Seen},
+    digest, html, query, redact, severity, CanonicalItem,
+};
+
+/* -------------------- Daily digest -------------------- */
+/*
+`report` turns what changed between two runs into Markdown that can be pasted
+into chat as-is: new KEV entries, items that newly reached --severity (default
+critical), and CVSS changes of at least --min-cvss-change points. The input is
+either two snapshots (--old/--new) or a delta file written by normalize
+(--baseline/--since-state, see delta.rs).
+
+The layout comes from a template (--template replaces the built-in one).
+Placeholders are {{name}}:
+
+  date, since, added_count, changed_count, removed_count,
+  new_kev_count, new_severe_count, cvss_change_count, severity,
+  new_kev, new_severe, cvss_changes, added, removed   (Markdown bullet lists)
+
+Lists show at most --limit entries, then "... and N more". An empty list
+renders as "- none". With --shareable, embargoed items are left out.
+
+--format html renders the added and changed items as a browsable page instead
+(see html.rs); with --input it renders a whole snapshot. --filter takes a query
+expression (see query.rs) and narrows either format to matching items; removed
+items have nothing to match against and are dropped by it.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Chat-ready digest from --template
+    Markdown,
+    /// Self-contained page with sortable tables (see html.rs)
+    Html,
+}
+
+const DEFAULT_TEMPLATE: &str = "\
+# Vulnerability digest {{date}}
+
+{{added_count}} added, {{changed_count}} changed, {{removed_count}} removed since {{since}}.
+
+## New KEV entries ({{new_kev_count}})
+{{new_kev}}
+
+## Newly {{severity}} ({{new_severe_count}})
+{{new_severe}}
+
+## Notable CVSS changes ({{cvss_change_count}})
+{{cvss_changes}}
+";
+
+/// One item that differs between the runs: `was` is None for additions, `now` for removals.
+pub struct Entry {
+    pub id: String,
+    pub was: Option<Seen>,
+    pub now: Option<CanonicalItem>,
+}
+
+impl Entry {
+    /// On the KEV list now but not before (or newly added as KEV).
+    pub fn new_kev(&self) -> bool {
+        self.now.as_ref().is_some_and(|i| i.kev) && self.was.as_ref().is_none_or(|w| !w.kev)
+    }
+
+    /// At or above severity rank `threshold` now but not before.
+    pub fn newly_severe(&self, threshold: u8) -> bool {
+        self.now.as_ref().is_some_and(|i| severity::rank(&i.severity_bucket) >= threshold)
+            && self.was.as_ref().is_none_or(|w| severity::rank(&w.severity_bucket) < threshold)
+    }
+}
+
+pub struct Changes {
+    pub since: String,
+    pub entries: Vec<Entry>,
+}
+
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    pub filter: Option<String>,
+    pub title: Option<String>,
+    pub template: Option<PathBuf>,
+    pub severity: String,
+    pub min_cvss_change: f64,
+    pub limit: usize,
+}
+
+pub fn from_snapshots(old: &[CanonicalItem], new: Vec<CanonicalItem>, since: &str) -> Result<Changes> {
+    let old_by_id: HashMap<&str, &CanonicalItem> = old.iter().map(|i| (i.id.as_str(), i)).collect();
+    let new_ids: HashSet<String> = new.iter().map(|i| i.id.clone()).collect();
+
+    // Recomputed rather than trusting stored content_hash, which edited files may carry stale
+    let mut entries = Vec::new();
+    for item in new {
+        let was = match old_by_id.get(item.id.as_str()) {
+            Some(o) => Some(Seen { content_hash: digest::item_content_hash(o)?, ..Seen::of(o)? }),
+            None => None,
+        };
+        if let Some(w) = &was
+            && w.content_hash == digest::item_content_hash(&item)?
+        {
+            continue;
+        }
+        entries.push(Entry { id: item.id.clone(), was, now: Some(item) });
+    }
+    for o in old.iter().filter(|o| !new_ids.contains(&o.id)) {
+        entries.push(Entry { id: o.id.clone(), was: Some(Seen::of(o)?), now: None });
+    }
+    entries.sort_by_cached_key(|e| codex::id_key(&e.id));
+    Ok(Changes { since: since.to_string(), entries })
+}
+
+pub fn from_delta(path: &Path) -> Result<Changes> {
+    let file = delta::read(path)?;
+    let since = match &file.since_generated_at {
+        Some(at) => format!("{} ({})", file.since, at),
+        None => file.since,
+    };
+    let entries = file
+        .changes
+        .into_iter()
+        .map(|c| Entry {
+            id: c.id,
+            was: if c.change == ChangeKind::Added { None } else { c.was },
+            now: if c.change == ChangeKind::Removed { None } else { c.item },
+        })
+        .collect();
+    Ok(Changes { since, entries })
+}
+
+/// --shareable: leave embargoed items out of the digest entirely.
+pub fn withhold_embargoed(changes: &mut Changes) -> usize {
+    let now = Utc::now();
+    let before = changes.entries.len();
+    changes.entries.retain(|e| !e.now.as_ref().is_some_and(|i| redact::is_embargoed(i, now)));
+    before - changes.entries.len()
+}
+
+/// --filter: keep entries whose current item matches.
+pub fn filter(changes: &mut Changes, expr: &query::Expr) -> Result<()> {
+    let mut kept = Vec::with_capacity(changes.entries.len());
+    for e in changes.entries.drain(..) {
+        if let Some(item) = &e.now
+            && query::eval(expr, &serde_json::to_value(item)?)
+        {
+            kept.push(e);
+        }
+    }
+    changes.entries = kept;
+    Ok(())
+}
+
+pub fn render_html(changes: &Changes, title: &str) -> String {
+    let items: Vec<&CanonicalItem> = changes.entries.iter().filter_map(|e| e.now.as_ref()).collect();
+    let change: BTreeMap<String, &'static str> = changes
+        .entries
+        .iter()
+        .filter(|e| e.now.is_some())
+        .map(|e| (e.id.clone(), if e.was.is_some() { "changed" } else { "added" }))
+        .collect();
+    let source = format!("changes since {}", changes.since);
+    html::render(&items, &html::Page { title, source: &source, change: Some(&change) })
+}
+
+fn score(s: Option<f64>) -> String {
+    s.map(|v| v.to_string()).unwrap_or_else(|| "n/a".to_string())
+}
+
+// "- CVE-2025-1234 Acme Widget: title (CVSS 9.8, critical)"
+fn item_line(item: &CanonicalItem, extra: &str) -> String {
+    let subject = [item.vendor.as_deref(), item.product.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+    let text = item.title.clone().unwrap_or_else(|| {
+        let desc = item.short_desc.trim();
+        match desc.char_indices().nth(140) {
+            Some((cut, _)) => format!("{}…", desc[..cut].trim_end()),
+            None => desc.to_string(),
+        }
+    });
+    let mut line = format!("- **{}**", item.id);
+    if !subject.is_empty() {
+        line.push_str(&format!(" {}", subject));
+    }
+    if !text.is_empty() {
+        line.push_str(&format!(": {}", text));
+    }
+    line.push_str(&format!(" (CVSS {}, {}{})", score(item.cvss), item.severity_bucket, extra));
+    line
+}
+
+fn bullet_list(mut lines: Vec<String>, limit: usize) -> String {
+    if lines.is_empty() {
+        return "- none".to_string();
+    }
+    let more = lines.len().saturating_sub(limit);
+    lines.truncate(limit);
+    if more > 0 {
+        lines.push(format!("- ... and {} more", more));
+    }
+    lines.join("\n")
+}
+
+pub fn load_template(template: Option<&Path>) -> Result<String> {
+    match template {
+        Some(p) => fs::read_to_string(p).with_context(|| format!("Failed to read template: {}", p.display())),
+        None => Ok(DEFAULT_TEMPLATE.to_string()),
+    }
+}
+
+pub fn render(changes: &Changes, opts: &ReportOptions, template: &str) -> String {
+    let threshold = severity::rank(&opts.severity);
+    let mut new_kev = Vec::new();
+    let mut new_severe = Vec::new();
+    let mut cvss_changes = Vec::new();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let (mut added_count, mut changed_count) = (0, 0);
+
+    for e in &changes.entries {
+        let Some(item) = &e.now else {
+            removed.push(format!("- {}", e.id));
+            continue;
+        };
+        match &e.was {
+            None => {
+                added_count += 1;
+                added.push(item_line(item, ""));
+            }
+            Some(_) => changed_count += 1,
+        }
+        let was = e.was.as_ref();
+
+        if e.new_kev() {
+            let due = item.kev_due_date.as_ref().map(|d| format!("; due {}", d)).unwrap_or_default();
+            new_kev.push(item_line(item, &due));
+        }
+        if e.newly_severe(threshold) {
+            let from = was.map(|w| format!("; was {}", w.severity_bucket)).unwrap_or_default();
+            new_severe.push(item_line(item, &from));
+        }
+        if let Some(w) = was
+            && let (Some(old), Some(new)) = (w.cvss, item.cvss)
+            && (new - old).abs() >= opts.min_cvss_change
+        {
+            cvss_changes.push(format!(
+                "- **{}**: {} → {} ({} → {})",
+                item.id, old, new, w.severity_bucket, item.severity_bucket
+            ));
+        }
+    }
+
+    let vars: [(&str, String); 14] = [
+        ("date", Utc::now().format("%Y-%m-%d").to_string()),
+        ("since", changes.since.clone()),
+        ("added_count", added_count.to_string()),
+        ("changed_count", changed_count.to_string()),
+        ("removed_count", removed.len().to_string()),
+        ("new_kev_count", new_kev.len().to_string()),
+        ("new_severe_count", new_severe.len().to_string()),
+        ("cvss_change_count", cvss_changes.len().to_string()),
+        ("severity", opts.severity.clone()),
+        ("new_kev", bullet_list(new_kev, opts.limit)),
+        ("new_severe", bullet_list(new_severe, opts.limit)),
+        ("cvss_changes", bullet_list(cvss_changes, opts.limit)),
+        ("added", bullet_list(added, opts.limit)),
+        ("removed", bullet_list(removed, opts.limit)),
+    ];
+    let mut out = template.to_string();
+    for (name, value) in &vars {
+        out = out.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    out
+}
@@ -0,0 +1,367 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::{digest, input, severity, CanonicalItem};
+
+/* -------------------- Full-text search -------------------- */
+/*
+`index --input items.json --out items.index.zst` builds an inverted index over
+each item's title + description, vendor, product, ref URLs and ID; `search`
+ranks matches with BM25:
+
+  search --index items.index.zst 'confluence ognl' --kev
+  search --index items.index.zst '"remote code execution" vendor:microsoft -edge'
+
+Query syntax:
+- words must all match (in any field); stems match, so "injected" finds "injection"
+- "quoted phrases" must appear in that order within one field
+- field:word or field:"phrase" limits to desc, vendor, product, refs or id
+- -word / -"phrase" excludes items matching it
+
+Words are lowercased alphanumeric runs, stemmed by a small suffix stripper
+(English plurals, -ed/-ing/-ion/-er/-ly and a final e); tokens with digits stay
+as they are, so versions and IDs match exactly. Vendor and product hits weigh
+twice a description hit, refs half.
+
+The index is serde JSON, zstd-compressed; it holds the fields search prints, so
+searching doesn't read items.json. It is a snapshot: rebuild it after each
+normalize (the index records which items.json it came from).
+*/
+
+pub const INDEX_VERSION: u32 = 1;
+
+const FIELDS: [&str; 5] = ["desc", "vendor", "product", "refs", "id"];
+const BOOST: [f64; 5] = [1.0, 2.0, 2.0, 0.5, 2.0];
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SearchFormat {
+    /// Score, ID, severity and description, one result per line
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Doc {
+    pub id: String,
+    pub kev: bool,
+    pub severity_bucket: String,
+    pub cvss: Option<f64>,
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub short_desc: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Posting {
+    doc: u32,
+    field: u8,
+    positions: Vec<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub index_version: u32,
+    pub generated_at: String,
+    pub source: String,
+    pub source_sha256: String,
+    pub docs: Vec<Doc>,
+    // Tokens per field of each doc, and on average, for length normalization
+    lens: Vec<[u32; 5]>,
+    avg_lens: [f64; 5],
+    postings: BTreeMap<String, Vec<Posting>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hit<'a> {
+    pub score: f64,
+    #[serde(flatten)]
+    pub doc: &'a Doc,
+}
+
+/// Which items a search may return, besides matching the query.
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    pub kev: bool,
+    /// At least this severe (see severity.rs)
+    pub min_severity: Option<String>,
+    /// Case-insensitive vendor name
+    pub vendor: Option<String>,
+}
+
+/* ---- tokens ---- */
+
+fn stem(word: &str) -> String {
+    if word.len() <= 3 || word.bytes().any(|b| b.is_ascii_digit()) {
+        return word.to_string();
+    }
+    let mut w = word.to_string();
+    for (suffix, replace) in [
+        ("ies", "y"),
+        ("ied", "y"),
+        ("ations", ""),
+        ("ation", ""),
+        ("ions", ""),
+        ("ion", ""),
+        ("ings", ""),
+        ("ing", ""),
+        ("ers", ""),
+        ("er", ""),
+        ("edly", ""),
+        ("ed", ""),
+        ("ly", ""),
+        ("es", ""),
+        ("s", ""),
+    ] {
+        if let Some(base) = w.strip_suffix(suffix)
+            && base.len() >= 3
+            // access, status, analysis: not plurals
+            && !(suffix == "s" && (base.ends_with('s') || base.ends_with('u') || base.ends_with('i')))
+        {
+            w = format!("{}{}", base, replace);
+            break;
+        }
+    }
+    if w.len() > 3 && w.ends_with('e') {
+        w.pop();
+    }
+    w
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).map(|t| stem(&t.to_lowercase()))
+}
+
+fn field_text(item: &CanonicalItem, field: usize) -> String {
+    match field {
+        0 => format!("{} {}", item.title.as_deref().unwrap_or(""), item.short_desc),
+        1 => item.vendor.clone().unwrap_or_default(),
+        2 => item.product.clone().unwrap_or_default(),
+        3 => item.refs.iter().map(|r| r.url.as_str()).collect::<Vec<_>>().join(" "),
+        _ => item.id.clone(),
+    }
+}
+
+/* ---- building ---- */
+
+pub fn build(items: &[CanonicalItem], source: &Path) -> Result<SearchIndex> {
+    let (source_sha256, _) = digest::sha256_file(source)?;
+    let mut postings: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+    let mut docs = Vec::with_capacity(items.len());
+    let mut doc_lens = Vec::with_capacity(items.len());
+    let mut totals = [0u64; 5];
+    for (n, item) in items.iter().enumerate() {
+        let mut lens = [0u32; 5];
+        for (field, len) in lens.iter_mut().enumerate() {
+            let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+            for (pos, tok) in tokens(&field_text(item, field)).enumerate() {
+                positions.entry(tok).or_default().push(pos as u32);
+                *len += 1;
+            }
+            totals[field] += *len as u64;
+            for (tok, positions) in positions {
+                postings.entry(tok).or_default().push(Posting { doc: n as u32, field: field as u8, positions });
+            }
+        }
+        docs.push(Doc {
+            id: item.id.clone(),
+            kev: item.kev,
+            severity_bucket: item.severity_bucket.clone(),
+            cvss: item.cvss,
+            vendor: item.vendor.clone(),
+            product: item.product.clone(),
+            short_desc: item.short_desc.clone(),
+        });
+        doc_lens.push(lens);
+    }
+    for list in postings.values_mut() {
+        list.sort_by_key(|p| (p.doc, p.field));
+    }
+    let avg_lens = totals.map(|t| if docs.is_empty() { 0.0 } else { t as f64 / docs.len() as f64 });
+    Ok(SearchIndex {
+        index_version: INDEX_VERSION,
+        generated_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        source: source.display().to_string(),
+        source_sha256,
+        docs,
+        lens: doc_lens,
+        avg_lens,
+        postings,
+    })
+}
+
+pub fn write(index: &SearchIndex, path: &Path) -> Result<()> {
+    let json = serde_json::to_vec(index)?;
+    let bytes = zstd::encode_all(json.as_slice(), 0)?;
+    let tmp = path.with_extension("partial");
+    fs::write(&tmp, bytes).with_context(|| format!("Failed to write index: {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to move index into place: {}", path.display()))
+}
+
+pub fn load(path: &Path) -> Result<SearchIndex> {
+    let bytes = input::read_input(path).with_context(|| format!("Failed to read index: {}", path.display()))?;
+    let index: SearchIndex =
+        serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse index: {}", path.display()))?;
+    if index.index_version != INDEX_VERSION {
+        bail!(
+            "{} is index version {}, this binary reads version {}; rebuild it with `index`",
+            path.display(),
+            index.index_version,
+            INDEX_VERSION
+        );
+    }
+    Ok(index)
+}
+
+/* ---- querying ---- */
+
+#[derive(Debug)]
+struct Clause {
+    terms: Vec<String>,
+    field: Option<usize>,
+    exclude: bool,
+}
+
+fn parse_query(q: &str) -> Result<Vec<Clause>> {
+    let mut clauses = Vec::new();
+    let mut rest = q.trim_start();
+    while !rest.is_empty() {
+        let exclude = rest.starts_with('-');
+        if exclude {
+            rest = &rest[1..];
+        }
+        let mut field = None;
+        if let Some((name, after)) = rest.split_once(':')
+            && let Some(f) = FIELDS.iter().position(|f| *f == name)
+        {
+            field = Some(f);
+            rest = after;
+        }
+        let text;
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let Some(end) = quoted.find('"') else { bail!("unterminated phrase in query: {}", q) };
+            text = &quoted[..end];
+            rest = &quoted[end + 1..];
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            text = &rest[..end];
+            rest = &rest[end..];
+        }
+        let terms: Vec<String> = tokens(text).collect();
+        if !terms.is_empty() {
+            clauses.push(Clause { terms, field, exclude });
+        }
+        rest = rest.trim_start();
+    }
+    if !clauses.iter().any(|c| !c.exclude) {
+        bail!("query has no words to search for: {}", q);
+    }
+    Ok(clauses)
+}
+
+impl SearchIndex {
+    fn idf(&self, term: &str) -> f64 {
+        let n = self.docs.len() as f64;
+        let mut df = 0;
+        let mut last = None;
+        for p in self.postings.get(term).into_iter().flatten() {
+            if last != Some(p.doc) {
+                df += 1;
+                last = Some(p.doc);
+            }
+        }
+        let df = df as f64;
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    /// Occurrences of the clause per (doc, field).
+    fn matches(&self, clause: &Clause) -> HashMap<(u32, u8), u32> {
+        let mut out = HashMap::new();
+        let Some(first) = self.postings.get(&clause.terms[0]) else { return out };
+        let rest: Vec<HashMap<(u32, u8), &Vec<u32>>> = clause.terms[1..]
+            .iter()
+            .map(|t| {
+                self.postings.get(t).into_iter().flatten().map(|p| ((p.doc, p.field), &p.positions)).collect()
+            })
+            .collect();
+        for p in first {
+            if clause.field.is_some_and(|f| f != p.field as usize) {
+                continue;
+            }
+            let key = (p.doc, p.field);
+            // Each following term at the next position
+            let count = p
+                .positions
+                .iter()
+                .filter(|&&start| {
+                    rest.iter().enumerate().all(|(i, m)| {
+                        m.get(&key).is_some_and(|pos| pos.binary_search(&(start + 1 + i as u32)).is_ok())
+                    })
+                })
+                .count();
+            if count > 0 {
+                out.insert(key, count as u32);
+            }
+        }
+        out
+    }
+
+    pub fn search(&self, query: &str, filter: &SearchFilter, limit: usize) -> Result<Vec<Hit<'_>>> {
+        let clauses = parse_query(query)?;
+        if let Some(name) = &filter.min_severity
+            && !severity::names().contains(&name.as_str())
+        {
+            bail!("unknown severity '{}' (buckets are: {})", name, severity::names().join(", "));
+        }
+        let min_rank = filter.min_severity.as_deref().map(severity::rank);
+        let mut scores: Option<HashMap<u32, f64>> = None;
+        let mut excluded: HashSet<u32> = HashSet::new();
+        for clause in &clauses {
+            let found = self.matches(clause);
+            if clause.exclude {
+                excluded.extend(found.keys().map(|(doc, _)| *doc));
+                continue;
+            }
+            let idf: f64 = clause.terms.iter().map(|t| self.idf(t)).sum();
+            let mut clause_scores: HashMap<u32, f64> = HashMap::new();
+            for ((doc, field), tf) in found {
+                let f = field as usize;
+                let len = self.lens[doc as usize][f] as f64;
+                let norm = 1.0 - B + B * len / self.avg_lens[f].max(1.0);
+                let tf = tf as f64;
+                *clause_scores.entry(doc).or_default() += BOOST[f] * idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+            // Every clause must match
+            scores = Some(match scores {
+                None => clause_scores,
+                Some(prev) => {
+                    prev.into_iter().filter_map(|(doc, s)| clause_scores.get(&doc).map(|c| (doc, s + c))).collect()
+                }
+            });
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(doc, _)| !excluded.contains(doc))
+            .map(|(doc, score)| Hit { score, doc: &self.docs[doc as usize] })
+            .filter(|h| !filter.kev || h.doc.kev)
+            .filter(|h| min_rank.is_none_or(|r| severity::rank(&h.doc.severity_bucket) >= r))
+            .filter(|h| {
+                filter.vendor.as_deref().is_none_or(|v| h.doc.vendor.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(v)))
+            })
+            .collect();
+        // Ties by ID, so results are stable across runs
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.doc.id.cmp(&b.doc.id)));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
@@ -0,0 +1,460 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{codex, grpc, log_ok, log_warn, metrics, query, remote, snapshot, stats, watchdog, CanonicalItem};
+
+/* -------------------- Read-only HTTP API -------------------- */
+/*
+`serve --input items.json` answers queries from an in-memory index so internal
+tools don't each copy and parse the file:
+
+  GET /items/CVE-2024-3400        one item (404 if unknown)
+  GET /items?severity=critical,high&kev=true&vendor=fortinet&limit=50
+  GET /stats                      same document as `stats --json`
+  GET /healthz                    item count, source, load time
+  GET /metrics                    Prometheus text format (see metrics.rs)
+  POST /-/reload                  re-read the file now (used by `daemon --notify-serve`)
+
+/items parameters, all optional and ANDed:
+  severity  comma-separated buckets       kev       true|false
+  vendor    exact, case-insensitive       product   exact, case-insensitive
+  tag       item carries this tag         cwe       e.g. CWE-79
+  since     last_modified >= this (ISO8601 prefix, e.g. 2025-06-01)
+  filter    query expression (see query.rs), URL-encoded
+  sort      field, -field or field:desc, as for `query --sort`
+  limit     page size, default 100, at most 1000       offset   default 0
+The answer is {"total", "offset", "limit", "items"}; items are in canonical ID
+order unless sorted. Unknown parameters are a 400 so typos don't silently
+match everything. Errors are {"error": {"code", "message"}}.
+
+It is a deliberately small HTTP/1.1 server on std only: GET and HEAD, one
+request per connection, no TLS or auth; the one POST only re-reads the file
+it already serves. Bind it to localhost (the default) or put it behind the
+proxy that already terminates TLS for internal tools.
+
+--grpc-listen HOST:PORT adds the same queries over gRPC (proto/bastion.proto,
+see grpc.rs) on a second port, answered from the same index; with
+--snapshot-store DIR its Diff call compares that index against archived runs.
+
+--reload SECS checks the file's modification time every SECS seconds (sftp://
+inputs are simply re-fetched) and swaps in the new snapshot once it parsed; a
+file that fails to load is reported and the previous snapshot keeps serving.
+*/
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+pub const STATS_TOP: usize = 10;
+
+/// One loaded snapshot; REST and gRPC answer from the same one.
+pub struct Index {
+    pub items: Vec<CanonicalItem>,
+    pub by_id: HashMap<String, usize>,
+    stats: Value,
+    dataset: metrics::Dataset,
+    loaded_at: String,
+    modified: Option<SystemTime>,
+}
+
+// For /metrics: reloads since start, by --reload or POST /-/reload
+static RELOADS: AtomicU64 = AtomicU64::new(0);
+static RELOAD_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn load(path: &Path) -> Result<Index> {
+    let modified = modified(path);
+    Index::new(codex::read_items(path)?, modified)
+}
+
+impl Index {
+    /// `modified`: the source file's mtime, for --reload.
+    pub(crate) fn new(items: Vec<CanonicalItem>, modified: Option<SystemTime>) -> Result<Self> {
+        let by_id = items.iter().enumerate().map(|(i, item)| (item.id.clone(), i)).collect();
+        let stats = serde_json::to_value(stats::compute(&items, STATS_TOP))?;
+        let dataset = metrics::Dataset::of(&items);
+        Ok(Index { items, by_id, stats, dataset, loaded_at: Utc::now().to_rfc3339(), modified })
+    }
+}
+
+pub type Shared = Arc<RwLock<Arc<Index>>>;
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        let mut body = serde_json::to_vec_pretty(value).unwrap_or_default();
+        body.push(b'\n');
+        Response { status, content_type: "application/json", body }
+    }
+
+    fn error(status: u16, code: &str, message: impl Into<String>) -> Self {
+        Response::json(status, &json!({ "error": { "code": code, "message": message.into() } }))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// %XX and '+' decoding for query strings; invalid escapes are kept as-is.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn parse_query(qs: &str) -> Vec<(String, String)> {
+    qs.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect()
+}
+
+fn eq_ci(field: &Option<String>, want: &str) -> bool {
+    field.as_deref().is_some_and(|f| f.eq_ignore_ascii_case(want))
+}
+
+/// The /items filters, shared with gRPC QueryItems (grpc.rs); None matches everything.
+#[derive(Default)]
+pub struct Selection {
+    pub severities: Option<Vec<String>>,
+    pub kev: Option<bool>,
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub tag: Option<String>,
+    pub cwe: Option<String>,
+    pub since: Option<String>,
+    pub filter: Option<query::Expr>,
+    pub sort: Option<String>,
+}
+
+/// A page of matches: the items themselves, or as JSON when a filter or sort was given.
+pub enum Selected<'a> {
+    Items(Vec<&'a CanonicalItem>),
+    Values(Vec<Value>),
+}
+
+impl Selection {
+    fn typed_match(&self, item: &CanonicalItem) -> bool {
+        self.severities.as_ref().is_none_or(|s| s.contains(&item.severity_bucket))
+            && self.kev.is_none_or(|k| item.kev == k)
+            && self.vendor.as_deref().is_none_or(|v| eq_ci(&item.vendor, v))
+            && self.product.as_deref().is_none_or(|p| eq_ci(&item.product, p))
+            && self.tag.as_deref().is_none_or(|t| item.tags.iter().any(|x| x == t))
+            && self.cwe.as_deref().is_none_or(|c| item.cwes.iter().any(|x| x.eq_ignore_ascii_case(c)))
+            && self.since.as_deref().is_none_or(|s| item.last_modified.as_deref().is_some_and(|m| m >= s))
+    }
+
+    /// Total matches and the `limit` of them after `offset`.
+    pub fn select<'a>(&self, index: &'a Index, offset: usize, limit: usize) -> (usize, Selected<'a>) {
+        // Typed checks first; the filter expression needs each candidate as JSON
+        if self.filter.is_none() && self.sort.is_none() {
+            let mut page = Vec::new();
+            let mut total = 0usize;
+            for item in index.items.iter().filter(|i| self.typed_match(i)) {
+                if total >= offset && page.len() < limit {
+                    page.push(item);
+                }
+                total += 1;
+            }
+            return (total, Selected::Items(page));
+        }
+        let mut matched: Vec<Value> = Vec::new();
+        for item in index.items.iter().filter(|i| self.typed_match(i)) {
+            let Ok(value) = serde_json::to_value(item) else { continue };
+            if self.filter.as_ref().is_none_or(|e| query::eval(e, &value)) {
+                matched.push(value);
+            }
+        }
+        if let Some(spec) = &self.sort {
+            query::sort_values(&mut matched, spec);
+        }
+        let total = matched.len();
+        (total, Selected::Values(matched.into_iter().skip(offset).take(limit).collect()))
+    }
+}
+
+fn list_items(index: &Index, params: &[(String, String)]) -> Response {
+    let mut sel = Selection::default();
+    let (mut limit, mut offset) = (DEFAULT_LIMIT, 0usize);
+
+    for (key, value) in params {
+        match key.as_str() {
+            "severity" => sel.severities = Some(value.split(',').map(|s| s.trim().to_string()).collect()),
+            "kev" => match value.as_str() {
+                "true" => sel.kev = Some(true),
+                "false" => sel.kev = Some(false),
+                _ => return Response::error(400, "bad_request", format!("kev must be true or false (got '{}')", value)),
+            },
+            "vendor" => sel.vendor = Some(value.clone()),
+            "product" => sel.product = Some(value.clone()),
+            "tag" => sel.tag = Some(value.clone()),
+            "cwe" => sel.cwe = Some(value.clone()),
+            "since" => sel.since = Some(value.clone()),
+            "filter" => match query::parse(value) {
+                Ok(expr) => sel.filter = Some(expr),
+                Err(e) => return Response::error(400, "bad_request", format!("Invalid filter: {:#}", e)),
+            },
+            "sort" => sel.sort = Some(value.clone()),
+            "limit" | "offset" => {
+                let Ok(n) = value.parse::<usize>() else {
+                    return Response::error(400, "bad_request", format!("{} must be a non-negative integer", key));
+                };
+                if key == "limit" {
+                    limit = n.min(MAX_LIMIT);
+                } else {
+                    offset = n;
+                }
+            }
+            _ => return Response::error(400, "bad_request", format!("Unknown parameter '{}'", key)),
+        }
+    }
+
+    let body = match sel.select(index, offset, limit) {
+        (total, Selected::Items(items)) => json!({ "total": total, "offset": offset, "limit": limit, "items": items }),
+        (total, Selected::Values(items)) => json!({ "total": total, "offset": offset, "limit": limit, "items": items }),
+    };
+    Response::json(200, &body)
+}
+
+fn route(index: &Index, source: &Path, target: &str) -> Response {
+    let (path, qs) = target.split_once('?').unwrap_or((target, ""));
+    let params = parse_query(qs);
+    let path = path.trim_end_matches('/');
+
+    match path {
+        "/healthz" => Response::json(
+            200,
+            &json!({
+                "status": "ok",
+                "items": index.items.len(),
+                "source": source.display().to_string(),
+                "loaded_at": index.loaded_at,
+                "source_modified": index.modified.map(|t| DateTime::<Utc>::from(t).to_rfc3339()),
+            }),
+        ),
+        "/stats" => Response::json(200, &index.stats),
+        "/metrics" => {
+            Response { status: 200, content_type: metrics::CONTENT_TYPE, body: render_metrics(index).into_bytes() }
+        }
+        "/items" => list_items(index, &params),
+        _ => match path.strip_prefix("/items/") {
+            Some(id) => {
+                let id = percent_decode(id).to_ascii_uppercase();
+                match index.by_id.get(&id) {
+                    Some(&i) => Response::json(200, &index.items[i]),
+                    None => Response::error(404, "not_found", format!("No item {}", id)),
+                }
+            }
+            None => Response::error(404, "not_found", format!("No route for {}", path)),
+        },
+    }
+}
+
+fn render_metrics(index: &Index) -> String {
+    let mut out = String::new();
+    metrics::render_dataset(&mut out, &index.dataset);
+    let loaded = DateTime::parse_from_rfc3339(&index.loaded_at).map_or(0, |t| t.timestamp());
+    let modified = index.modified.map_or(0, |t| DateTime::<Utc>::from(t).timestamp());
+    metrics::gauge(
+        &mut out,
+        "bastion_snapshot_loaded_timestamp_seconds",
+        "Unix time the served snapshot was loaded",
+        loaded as f64,
+    );
+    metrics::gauge(
+        &mut out,
+        "bastion_source_modified_timestamp_seconds",
+        "Modification time of the served file (0: unknown)",
+        modified as f64,
+    );
+    let reloads = RELOADS.load(Ordering::Relaxed);
+    metrics::counter(&mut out, "bastion_reloads_total", "Snapshots swapped in since start", reloads);
+    metrics::counter(
+        &mut out,
+        "bastion_reload_failures_total",
+        "Reloads that failed and kept the old snapshot",
+        RELOAD_FAILURES.load(Ordering::Relaxed),
+    );
+    out
+}
+
+fn handle(mut stream: TcpStream, shared: &Shared, source: &Path) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are read (to stay within the size cap) but not used
+    let mut read = request_line.len();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        read += line.len();
+        if read > MAX_REQUEST_BYTES {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+    let response = if read > MAX_REQUEST_BYTES {
+        Response::error(413, "too_large", "Request headers too large")
+    } else if method == "POST" && target.split('?').next() == Some("/-/reload") {
+        reload(shared, source)
+    } else if method != "GET" && method != "HEAD" {
+        Response::error(405, "method_not_allowed", "Only GET and HEAD are supported (and POST /-/reload)")
+    } else {
+        let index = shared.read().map(|g| Arc::clone(&g)).map_err(|_| anyhow::anyhow!("index lock poisoned"))?;
+        route(&index, source, target)
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// POST /-/reload: swap in the file as it is now; on failure the old snapshot stays.
+fn reload(shared: &Shared, source: &Path) -> Response {
+    match load(source) {
+        Ok(index) => {
+            let count = index.items.len();
+            if let Ok(mut g) = shared.write() {
+                *g = Arc::new(index);
+            }
+            RELOADS.fetch_add(1, Ordering::Relaxed);
+            log_ok!("serve reloaded {} items from {} on request", count, source.display());
+            Response::json(200, &json!({ "status": "reloaded", "items": count }))
+        }
+        Err(e) => {
+            RELOAD_FAILURES.fetch_add(1, Ordering::Relaxed);
+            log_warn!("serve kept the previous snapshot: reload of {} failed: {:#}", source.display(), e);
+            Response::error(500, "reload_failed", format!("{:#}", e))
+        }
+    }
+}
+
+fn watch(shared: Shared, source: PathBuf, every: Duration) {
+    // Last version tried, so a broken file is reported once rather than every tick
+    let mut tried = shared.read().ok().and_then(|g| g.modified);
+    loop {
+        thread::sleep(every);
+        let now = modified(&source);
+        if !remote::is_remote(&source) && now == tried {
+            continue;
+        }
+        tried = now;
+        match load(&source) {
+            Ok(index) => {
+                let count = index.items.len();
+                if let Ok(mut g) = shared.write() {
+                    *g = Arc::new(index);
+                }
+                RELOADS.fetch_add(1, Ordering::Relaxed);
+                log_ok!("serve reloaded {} items from {}", count, source.display());
+            }
+            Err(e) => {
+                RELOAD_FAILURES.fetch_add(1, Ordering::Relaxed);
+                log_warn!("serve kept the previous snapshot: reload of {} failed: {:#}", source.display(), e);
+            }
+        }
+    }
+}
+
+pub fn serve(
+    source: &Path,
+    listen: &str,
+    reload: Option<Duration>,
+    grpc_listen: Option<&str>,
+    snapshot_store: Option<&Path>,
+) -> Result<()> {
+    let index = load(source)?;
+    let count = index.items.len();
+    let shared: Shared = Arc::new(RwLock::new(Arc::new(index)));
+
+    let listener = TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    watchdog::phase("serve: listening");
+    log_ok!("serve: {} items from {} on http://{}", count, source.display(), listener.local_addr()?);
+
+    if let Some(addr) = grpc_listen {
+        let store = snapshot_store.map(snapshot::Store::open).transpose()?;
+        grpc::listen(addr, Arc::clone(&shared), store)?;
+    }
+
+    if let Some(every) = reload {
+        let (shared, source) = (Arc::clone(&shared), source.to_path_buf());
+        thread::spawn(move || watch(shared, source, every));
+    }
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let (shared, source) = (Arc::clone(&shared), source.to_path_buf());
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &shared, &source) {
+                log_warn!("serve: request failed: {:#}", e);
+            }
+        });
+    }
+    Ok(())
+}
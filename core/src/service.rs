@@ -0,0 +1,247 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{html, log_ok};
+
+/* -------------------- Service registration -------------------- */
+/*
+`daemon install --interval 6h -- <normalize args>` registers the same daemon
+with the system supervisor, so it starts at boot and comes back after a crash
+or a --max-failures exit:
+
+  systemd  /etc/systemd/system/<name>.service (with --user:
+           ~/.config/systemd/user/<name>.service), Restart=on-failure after
+           --restart-sec; then systemctl daemon-reload and enable --now
+  windows  a Task Scheduler task (<name>, running as SYSTEM at boot, restart on
+           failure every minute at least), registered from an XML definition
+           saved under %ProgramData%\bastion-codex. Windows services must answer
+           the service control manager, which a console program doesn't, so a
+           boot task stands in for one
+
+ExecStart / the task's command is this binary's absolute path with --config (as
+an absolute path, if one was given) and the daemon flags; the working
+directory is the current one (or --working-dir), so relative paths among the
+normalize arguments keep pointing where they did at install time. Other
+global flags aren't carried over; put them in the config file.
+
+--print writes the unit or task XML to stdout instead of installing it, for
+review or a configuration management tool. --no-start registers without
+starting. BASTION_SYSTEMCTL and BASTION_SCHTASKS override the binaries.
+
+To remove: systemctl disable --now <name> and delete the unit file, or
+schtasks /Delete /TN <name> /F.
+*/
+
+pub const DEFAULT_NAME: &str = "bastion-codex";
+pub const DEFAULT_RESTART_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Systemd,
+    Windows,
+}
+
+impl Kind {
+    pub fn native() -> Kind {
+        if cfg!(windows) { Kind::Windows } else { Kind::Systemd }
+    }
+}
+
+#[derive(Debug)]
+pub struct Spec {
+    pub name: String,
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub restart_secs: u64,
+    pub user: bool, // systemd user unit instead of a system one
+}
+
+fn tool(var: &str, default: &str) -> String {
+    std::env::var(var).unwrap_or_else(|_| default.to_string())
+}
+
+// systemd command lines: C-style double quotes; % and $ are specifiers / variables
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    let plain = !escaped.is_empty() && !escaped.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c));
+    if plain {
+        escaped
+    } else {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+// CommandLineToArgvW rules: backslashes are literal unless they precede a quote
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                out.push_str(&"\\".repeat(backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            c => {
+                out.push_str(&"\\".repeat(backslashes));
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    out.push_str(&"\\".repeat(backslashes * 2));
+    out.push('"');
+    out
+}
+
+pub fn systemd_unit(spec: &Spec) -> String {
+    let exec: Vec<String> =
+        std::iter::once(spec.exe.to_string_lossy().to_string()).chain(spec.args.iter().cloned()).collect();
+    let exec: Vec<String> = exec.iter().map(|a| systemd_quote(a)).collect();
+    let wanted_by = if spec.user { "default.target" } else { "multi-user.target" };
+    let mut unit = String::new();
+    unit.push_str("[Unit]\n");
+    unit.push_str(&format!("Description=Bastion Codex refresh daemon ({})\n", spec.name));
+    unit.push_str("Wants=network-online.target\nAfter=network-online.target\n\n");
+    unit.push_str("[Service]\nType=simple\n");
+    unit.push_str(&format!("WorkingDirectory={}\n", systemd_quote(&spec.working_dir.to_string_lossy())));
+    unit.push_str(&format!("ExecStart={}\n", exec.join(" ")));
+    unit.push_str(&format!("Restart=on-failure\nRestartSec={}\n\n", spec.restart_secs));
+    unit.push_str(&format!("[Install]\nWantedBy={}\n", wanted_by));
+    unit
+}
+
+pub fn task_xml(spec: &Spec) -> String {
+    let args: Vec<String> = spec.args.iter().map(|a| windows_quote(a)).collect();
+    // Task Scheduler won't restart more often than once a minute
+    let restart = spec.restart_secs.max(60);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Bastion Codex refresh daemon ({name})</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <BootTrigger>
+      <Enabled>true</Enabled>
+    </BootTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>S-1-5-18</UserId>
+      <RunLevel>HighestAvailable</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT{restart}S</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{exe}</Command>
+      <Arguments>{args}</Arguments>
+      <WorkingDirectory>{dir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        name = html::escape(&spec.name),
+        restart = restart,
+        exe = html::escape(&spec.exe.to_string_lossy()),
+        args = html::escape(&args.join(" ")),
+        dir = html::escape(&spec.working_dir.to_string_lossy()),
+    )
+}
+
+pub fn render(kind: Kind, spec: &Spec) -> String {
+    match kind {
+        Kind::Systemd => systemd_unit(spec),
+        Kind::Windows => task_xml(spec),
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        bail!("{} {} failed ({})", program, args.join(" "), status);
+    }
+    Ok(())
+}
+
+fn unit_dir(user: bool) -> Result<PathBuf> {
+    if !user {
+        return Ok(PathBuf::from("/etc/systemd/system"));
+    }
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = std::env::var_os("HOME").context("--user needs HOME or XDG_CONFIG_HOME")?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+    Ok(config.join("systemd").join("user"))
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Write the unit / task definition and register it; `start` also starts it now.
+/// Returns where the definition was saved.
+pub fn install(kind: Kind, spec: &Spec, start: bool) -> Result<PathBuf> {
+    if spec.name.is_empty() || spec.name.contains(['/', '\\', ' ']) {
+        bail!("service name must be non-empty without slashes or spaces: '{}'", spec.name);
+    }
+    match kind {
+        Kind::Systemd => {
+            let path = unit_dir(spec.user)?.join(format!("{}.service", spec.name));
+            write(&path, systemd_unit(spec).as_bytes())?;
+            let systemctl = tool("BASTION_SYSTEMCTL", "systemctl");
+            let scope: &[&str] = if spec.user { &["--user"] } else { &[] };
+            run(&systemctl, &[scope, &["daemon-reload"]].concat())?;
+            let enable: &[&str] = if start { &["enable", "--now"] } else { &["enable"] };
+            run(&systemctl, &[scope, enable, &[&spec.name]].concat())?;
+            log_ok!("installed systemd unit {} ({})", spec.name, path.display());
+            Ok(path)
+        }
+        Kind::Windows => {
+            let data = std::env::var_os("ProgramData").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+            let path = data.join(DEFAULT_NAME).join(format!("{}.xml", spec.name));
+            // schtasks reads the definition as UTF-16 (with a byte order mark)
+            let bytes: Vec<u8> =
+                std::iter::once(0xFEFF).chain(task_xml(spec).encode_utf16()).flat_map(u16::to_le_bytes).collect();
+            write(&path, &bytes)?;
+            let schtasks = tool("BASTION_SCHTASKS", "schtasks");
+            let xml = path.to_string_lossy();
+            run(&schtasks, &["/Create", "/TN", &spec.name, "/XML", &xml, "/F"])?;
+            if start {
+                run(&schtasks, &["/Run", "/TN", &spec.name])?;
+            }
+            log_ok!("installed scheduled task {} ({})", spec.name, path.display());
+            Ok(path)
+        }
+    }
+}
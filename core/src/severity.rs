@@ -0,0 +1,185 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{path::Path, sync::OnceLock};
+
+use crate::input;
+
+/* -------------------- Severity policy -------------------- */
+/*
+severity_bucket is assigned by a policy: an ordered list of buckets, most severe
+first, each with a minimum CVSS and optionally "KEV only". The first bucket an
+item qualifies for wins; items without a score get `unscored`. The default is the
+standard CVSS v3 scale (critical 9.0 / high 7.0 / medium 4.0 / low).
+
+Set once per run from --severity-policy (JSON file) or --severity-thresholds:
+
+  { "buckets": [ { "name": "urgent",   "min_cvss": 9.0, "kev": true },
+                 { "name": "critical", "min_cvss": 9.0 },
+                 { "name": "high",     "min_cvss": 7.0 },
+                 { "name": "medium",   "min_cvss": 4.0 },
+                 { "name": "low",      "min_cvss": 0.0 } ],
+    "unscored": "unknown" }
+
+  --severity-thresholds urgent=9.0+kev,critical=9.0,high=7.0,medium=4.0,low=0
+
+A bucket that an earlier one always shadows (low=0,critical=9.0) is rejected.
+
+Ranks (diff escalations, watchlist/check thresholds, ordering) follow the list,
+so custom names compare correctly. Only buckets are configurable; normalize has
+to run with the same policy as the commands reading its output.
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bucket {
+    pub name: String,
+    pub min_cvss: f64,
+    #[serde(default)]
+    pub kev: bool, // only KEV-listed items qualify
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeverityPolicy {
+    pub buckets: Vec<Bucket>,
+    #[serde(default = "default_unscored")]
+    pub unscored: String,
+}
+
+fn default_unscored() -> String {
+    "unknown".to_string()
+}
+
+impl SeverityPolicy {
+    pub fn standard() -> Self {
+        let b = |name: &str, min_cvss: f64| Bucket { name: name.to_string(), min_cvss, kev: false };
+        SeverityPolicy {
+            buckets: vec![b("critical", 9.0), b("high", 7.0), b("medium", 4.0), b("low", 0.0)],
+            unscored: default_unscored(),
+        }
+    }
+
+    fn checked(self, origin: &str) -> Result<Self> {
+        if self.buckets.is_empty() {
+            bail!("Severity policy from {} defines no buckets", origin);
+        }
+        let mut names: Vec<&str> = self.buckets.iter().map(|b| b.name.as_str()).collect();
+        names.push(&self.unscored);
+        names.sort();
+        if let Some(w) = names.windows(2).find(|w| w[0] == w[1]) {
+            bail!("Severity policy from {} names bucket '{}' twice", origin, w[0]);
+        }
+        // First match wins, so a bucket behind a broader one with no higher min_cvss never gets used
+        for (i, later) in self.buckets.iter().enumerate() {
+            if let Some(earlier) =
+                self.buckets[..i].iter().find(|b| b.min_cvss <= later.min_cvss && (later.kev || !b.kev))
+            {
+                bail!(
+                    "Severity policy from {}: bucket '{}' (min_cvss {}) can never match, '{}' (min_cvss {}) \
+                     comes first; list buckets most severe first",
+                    origin,
+                    later.name,
+                    later.min_cvss,
+                    earlier.name,
+                    earlier.min_cvss
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = input::read_input(path)?;
+        let policy: SeverityPolicy = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse severity policy: {}", path.display()))?;
+        policy.checked(&path.display().to_string())
+    }
+
+    /// "urgent=9.0+kev,critical=9.0,high=7.0,medium=4.0,low=0"
+    pub fn parse_thresholds(spec: &str) -> Result<Self> {
+        let buckets = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|entry| {
+                let (name, rest) = entry
+                    .split_once('=')
+                    .with_context(|| format!("Severity threshold '{}' is not name=min_cvss", entry))?;
+                let (min, kev) = match rest.strip_suffix("+kev") {
+                    Some(min) => (min, true),
+                    None => (rest, false),
+                };
+                let min_cvss = min
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Severity threshold '{}' has no numeric CVSS", entry))?;
+                Ok(Bucket { name: name.trim().to_string(), min_cvss, kev })
+            })
+            .collect::<Result<_>>()?;
+        SeverityPolicy { buckets, unscored: default_unscored() }.checked("--severity-thresholds")
+    }
+}
+
+static POLICY: OnceLock<SeverityPolicy> = OnceLock::new();
+
+pub fn set_policy(policy: SeverityPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> &'static SeverityPolicy {
+    POLICY.get_or_init(SeverityPolicy::standard)
+}
+
+/// Bucket for a score under the active policy.
+pub fn bucket(cvss: Option<f64>, kev: bool) -> String {
+    let p = policy();
+    let Some(score) = cvss else { return p.unscored.clone(); };
+    p.buckets
+        .iter()
+        .find(|b| score >= b.min_cvss && (kev || !b.kev))
+        .map_or_else(|| p.unscored.clone(), |b| b.name.clone())
+}
+
+/// Standard CVSS v3 rating, for formats that only accept those names (CycloneDX).
+pub fn standard_bucket(cvss: Option<f64>) -> String {
+    match cvss {
+        None => "unknown".to_string(),
+        Some(s) if s >= 9.0 => "critical".to_string(),
+        Some(s) if s >= 7.0 => "high".to_string(),
+        Some(s) if s >= 4.0 => "medium".to_string(),
+        Some(_) => "low".to_string(),
+    }
+}
+
+/// Higher is more severe; the unscored bucket and unknown names are 0.
+pub fn rank(name: &str) -> u8 {
+    let buckets = &policy().buckets;
+    buckets
+        .iter()
+        .position(|b| b.name == name)
+        .map_or(0, |i| (buckets.len() - i) as u8)
+}
+
+/// Bucket names, most severe first, unscored last.
+pub fn names() -> Vec<&'static str> {
+    let p = policy();
+    p.buckets.iter().map(|b| b.name.as_str()).chain([p.unscored.as_str()]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_must_run_most_severe_first() {
+        let names = |spec: &str| -> Vec<String> {
+            SeverityPolicy::parse_thresholds(spec).unwrap().buckets.into_iter().map(|b| b.name).collect()
+        };
+        assert_eq!(names("urgent=9.0+kev,critical=9.0,high=7.0,medium=4.0,low=0").len(), 5);
+        // A KEV-only bucket may sit below a broader one with a higher threshold
+        assert_eq!(names("critical=9.0,urgent=7.0+kev,high=7.0,low=0"), ["critical", "urgent", "high", "low"]);
+
+        for spec in ["low=0,critical=9.0", "high=7.0,medium=7.0", "high=7.0,urgent=9.0+kev", "a=1+kev,b=2+kev"] {
+            let err = SeverityPolicy::parse_thresholds(spec).unwrap_err().to_string();
+            assert!(err.contains("can never match"), "{}: {}", spec, err);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::errors::Failure;
+
+/* -------------------- Detached signatures -------------------- */
+/*
+normalize --sign-key KEY signs the output and its run manifest with minisign,
+leaving items.json.minisig and items.manifest.json.minisig next to them (and
+uploading them with sftp:// outputs). Consumers can check either file with
+stock minisign, or run
+
+  core verify --input items.json --pubkey bastion.pub
+
+which checks both signatures and then the manifest's output/sidecar digests
+against the files on disk, so a swapped file and a swapped manifest both fail.
+
+Signing shells out to the `minisign` CLI; BASTION_MINISIGN overrides the
+binary. An encrypted secret key reads its password from
+BASTION_MINISIGN_PASSWORD when set, otherwise minisign prompts on the terminal.
+verify takes --pubkey more than once so a rotation can accept the old and new
+key for a while; a signature from any of them passes.
+
+For rotations that outlive the overlap, --keyring FILE lists the trusted keys
+with the window each was in use (relative paths are against the file):
+
+  { "keys": [
+      { "pubkey": "2024.pub", "not_after": "2025-06-30" },
+      { "pubkey": "2025.pub", "not_before": "2025-06-01" } ] }
+
+Bounds are inclusive; a date alone means the start (not_before) or end
+(not_after) of that day, UTC. The time checked is the one normalize puts at
+the end of the signed trusted comment, so it can't be changed without breaking
+the signature: old snapshots keep verifying against a retired key, and a
+retired key can't sign new ones. A signature without that timestamp fails any
+key that has a window. Plain --pubkey keys have none.
+*/
+
+fn minisign() -> String {
+    std::env::var("BASTION_MINISIGN").unwrap_or_else(|_| "minisign".to_string())
+}
+
+/// items.json -> items.json.minisig (also for sftp:// URLs)
+pub fn signature_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.minisig", path.display()))
+}
+
+/// Sign `files` with one minisign run (one password prompt). The trusted
+/// comment is signed too and shown by `verify`.
+pub fn sign(files: &[&Path], key: &Path, trusted_comment: &str) -> Result<Vec<PathBuf>> {
+    let tool = minisign();
+    let password = std::env::var("BASTION_MINISIGN_PASSWORD").ok();
+    let mut cmd = Command::new(&tool);
+    cmd.arg("-S").arg("-s").arg(key).args(["-t", trusted_comment]).arg("-m").args(files);
+    cmd.stdin(if password.is_some() { Stdio::piped() } else { Stdio::inherit() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {} to sign with {}", tool, key.display()))?;
+    if let (Some(mut stdin), Some(password)) = (child.stdin.take(), &password) {
+        stdin.write_all(format!("{}\n", password).as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(anyhow::Error::new(Failure::new(
+            "sign_failed",
+            format!("minisign could not sign with {} ({}): {}", key.display(), out.status, stderr),
+        )
+        .path(key)));
+    }
+
+    let sigs: Vec<PathBuf> = files.iter().map(|f| signature_path(f)).collect();
+    if let Some(missing) = sigs.iter().find(|s| !s.is_file()) {
+        anyhow::bail!("minisign reported success but wrote no signature: {}", missing.display());
+    }
+    Ok(sigs)
+}
+
+/// A trusted public key and when it was in use; None bounds don't constrain.
+#[derive(Debug, Clone)]
+pub struct TrustedKey {
+    pub path: PathBuf,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl TrustedKey {
+    /// A --pubkey key, valid at any time.
+    pub fn any_time(path: PathBuf) -> Self {
+        TrustedKey { path, not_before: None, not_after: None }
+    }
+
+    fn window(&self) -> String {
+        let at = |t: DateTime<Utc>| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        match (self.not_before, self.not_after) {
+            (Some(from), Some(to)) => format!("{} to {}", at(from), at(to)),
+            (Some(from), None) => format!("from {}", at(from)),
+            (None, Some(to)) => format!("until {}", at(to)),
+            (None, None) => "any time".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Keyring {
+    keys: Vec<KeyringEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyringEntry {
+    pubkey: PathBuf,
+    #[serde(default)]
+    not_before: Option<String>,
+    #[serde(default)]
+    not_after: Option<String>,
+}
+
+// "2025-06-01" is that whole day: its first second for not_before, its last for not_after
+fn bound(s: &str, end_of_day: bool) -> Result<DateTime<Utc>> {
+    if let Ok(day) = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d") {
+        let time = if end_of_day { day.and_hms_opt(23, 59, 59) } else { day.and_hms_opt(0, 0, 0) };
+        return Ok(DateTime::from_naive_utc_and_offset(time.context("invalid date")?, Utc));
+    }
+    crate::parse_iso_datetime(s).with_context(|| format!("Invalid date '{}': use YYYY-MM-DD or an ISO timestamp", s))
+}
+
+/// Read a --keyring file.
+pub fn load_keyring(path: &Path) -> Result<Vec<TrustedKey>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read keyring {}", path.display()))?;
+    let keyring: Keyring =
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse keyring {}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    keyring
+        .keys
+        .into_iter()
+        .map(|entry| {
+            let at = |s: &Option<String>, end| s.as_deref().map(|s| bound(s, end)).transpose();
+            let key = TrustedKey {
+                path: dir.join(&entry.pubkey),
+                not_before: at(&entry.not_before, false)?,
+                not_after: at(&entry.not_after, true)?,
+            };
+            if let (Some(from), Some(to)) = (key.not_before, key.not_after)
+                && from > to
+            {
+                anyhow::bail!("{}: {} has not_before after not_after", path.display(), entry.pubkey.display());
+            }
+            Ok(key)
+        })
+        .collect()
+}
+
+/// When the signature was made: the timestamp normalize ends the trusted comment with.
+fn signed_at(trusted_comment: &str) -> Option<DateTime<Utc>> {
+    trusted_comment.split_whitespace().next_back().and_then(crate::parse_iso_datetime)
+}
+
+pub struct Verified {
+    pub key: PathBuf,
+    pub trusted_comment: String,
+}
+
+/// Check `file` against its .minisig with each of `keys` in turn; the first
+/// key that verifies and was valid when the signature was made wins.
+pub fn verify(file: &Path, keys: &[TrustedKey]) -> Result<Verified> {
+    let sig = signature_path(file);
+    if !sig.is_file() {
+        return Err(Failure::new("signature_missing", format!("No signature next to {}: expected {}", file.display(), sig.display()))
+            .path(file)
+            .into());
+    }
+
+    let tool = minisign();
+    let mut errors = Vec::new();
+    let mut outside_window = false;
+    for trusted in keys {
+        let key = &trusted.path;
+        // -Q: print only the trusted comment on success
+        let out = Command::new(&tool)
+            .arg("-V")
+            .arg("-Q")
+            .arg("-p")
+            .arg(key)
+            .arg("-m")
+            .arg(file)
+            .arg("-x")
+            .arg(&sig)
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("Failed to run {} to verify {}", tool, file.display()))?;
+        if out.status.success() {
+            let trusted_comment = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            if trusted.not_before.is_none() && trusted.not_after.is_none() {
+                return Ok(Verified { key: key.clone(), trusted_comment });
+            }
+            match signed_at(&trusted_comment) {
+                Some(at)
+                    if trusted.not_before.is_none_or(|from| at >= from)
+                        && trusted.not_after.is_none_or(|to| at <= to) =>
+                {
+                    return Ok(Verified { key: key.clone(), trusted_comment });
+                }
+                Some(at) => errors.push(format!(
+                    "{}: signed at {}, outside the key's window ({})",
+                    key.display(),
+                    at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    trusted.window()
+                )),
+                None => errors.push(format!(
+                    "{}: the trusted comment has no signing time to check the key's window ({}) against",
+                    key.display(),
+                    trusted.window()
+                )),
+            }
+            outside_window = true;
+            continue;
+        }
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        errors.push(format!("{}: {}", key.display(), stderr));
+    }
+    // A good signature from a key used outside its window is its own failure
+    let code = if outside_window { "key_outside_window" } else { "signature_invalid" };
+    Err(Failure::new(
+        code,
+        format!("{} is not signed by any trusted key ({})", file.display(), errors.join("; ")),
+    )
+    .path(file)
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyring_dates_cover_whole_days() {
+        let dir = std::env::temp_dir().join(format!("bastion-codex-keyring-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ring = dir.join("keyring.json");
+        let text = r#"{"keys": [{"pubkey": "old.pub", "not_after": "2025-06-30"},
+            {"pubkey": "new.pub", "not_before": "2025-06-01T12:00:00Z"}]}"#;
+        fs::write(&ring, text).unwrap();
+        let keys = load_keyring(&ring).unwrap();
+        assert_eq!(keys[0].path, dir.join("old.pub"));
+        assert_eq!(keys[0].not_before, None);
+        assert_eq!(keys[0].not_after.unwrap().to_rfc3339(), "2025-06-30T23:59:59+00:00");
+        assert_eq!(keys[1].not_before.unwrap().to_rfc3339(), "2025-06-01T12:00:00+00:00");
+
+        fs::write(&ring, r#"{"keys": [{"pubkey": "k.pub", "not_before": "2025-07-01", "not_after": "2025-06-30"}]}"#)
+            .unwrap();
+        assert!(load_keyring(&ring).is_err());
+        fs::write(&ring, r#"{"keys": [{"pubkey": "k.pub", "not_befor": "2025-07-01"}]}"#).unwrap();
+        assert!(load_keyring(&ring).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn signing_time_is_the_last_word_of_the_trusted_comment() {
+        let at = signed_at("bastion-core 0.1.0 items.json (21 items) 2025-06-30T10:00:00Z").unwrap();
+        assert_eq!(at.to_rfc3339(), "2025-06-30T10:00:00+00:00");
+        assert!(signed_at("timestamp:1719741600 file:items.json").is_none());
+        assert!(signed_at("").is_none());
+    }
+}
@@ -0,0 +1,453 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{digest, CanonicalItem};
+
+/* -------------------- Snapshot history store -------------------- */
+/*
+Instead of keeping dated copies of items.json around, each run can be archived
+into a store that keeps every distinct version of an item exactly once:
+
+  data/snapshots/
+    objects/3f/9a0c...e1.json.zst       one item version (compact JSON), named by
+                                        the sha256 of those bytes
+    runs/20250601T061500Z.json.zst      {"taken_at", "label", "items": {id: sha256}}
+    runs.jsonl                          one line per run: taken_at, file, label,
+                                        items, new_objects
+
+A day's run adds a ~10 MB run file and objects only for the items that changed
+(hundreds, not the ~300k of a full snapshot).
+
+  snapshot add --input items.json       archive an output (normalize --snapshot-store
+                                        does this after every run)
+  snapshot list                         the archived runs
+  snapshot show --id CVE-... --as-of 2025-03-01
+                                        the item as the last run on or before that
+                                        date (end of day UTC) had it
+  snapshot history --id CVE-... [--field severity_bucket]
+                                        each run where the item (or the field) changed
+  snapshot epss-trend --id CVE-...      the item's EPSS score (stored by score/enrich
+                                        --epss) at each run where it moved
+  query --store DIR --as-of 2025-03-01 --filter 'kev == true'
+                                        the whole run as of that date, through the
+                                        usual query filter / projection / sort
+  snapshot prune --daily-days 90 --weekly-days 730
+                                        keep the newest run per day, then per ISO week,
+                                        drop older runs and the objects only they used
+
+A run's time is when it was taken: normalize's own clock, or for `add` the
+generated_at of the output's manifest (so old copies can be backfilled in any
+order), its modification time without one, or --taken-at. history compares
+whole items field by field; content_hash is left out since it changes with
+every other field.
+*/
+
+pub const STORE_VERSION: u32 = 1;
+
+/// One line of runs.jsonl.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub taken_at: String,
+    pub file: String,
+    #[serde(default)]
+    pub label: String,
+    pub items: usize,
+    pub new_objects: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Run {
+    store_version: u32,
+    taken_at: String,
+    label: String,
+    items: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub was: Value,
+    pub now: Value,
+}
+
+/// A run in which the item differs from the run before it.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub taken_at: String,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// The EPSS score of an item from `taken_at` on; None: the run had no score for it.
+#[derive(Debug, Serialize)]
+pub struct EpssPoint {
+    pub taken_at: String,
+    pub epss: Option<f64>,
+}
+
+pub struct Store {
+    dir: PathBuf,
+}
+
+/// "2025-03-01" means the end of that day; anything else is a timestamp.
+pub fn parse_as_of(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(day) = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d") {
+        let end = day.and_hms_opt(23, 59, 59).context("invalid date")?;
+        return Ok(DateTime::from_naive_utc_and_offset(end, Utc));
+    }
+    crate::parse_iso_datetime(s).with_context(|| format!("Invalid date '{}': use YYYY-MM-DD or an ISO timestamp", s))
+}
+
+/// When the output at `path` was produced: its manifest's generated_at, else its mtime.
+pub fn output_time(path: &Path) -> Result<DateTime<Utc>> {
+    let manifest = crate::manifest::manifest_path(path);
+    if manifest.exists()
+        && let Some(at) = crate::manifest::load(&manifest).ok().and_then(|m| crate::parse_iso_datetime(&m.generated_at))
+    {
+        return Ok(at);
+    }
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to read modification time: {}", path.display()))?;
+    Ok(modified.into())
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("partial");
+    fs::write(&tmp, bytes).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to move {} into place", path.display()))
+}
+
+fn read_zstd(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    zstd::decode_all(bytes.as_slice()).with_context(|| format!("Failed to decompress {}", path.display()))
+}
+
+impl Store {
+    /// Open the store at `dir`, creating it if needed.
+    pub fn open(dir: &Path) -> Result<Self> {
+        for sub in ["objects", "runs"] {
+            let path = dir.join(sub);
+            fs::create_dir_all(&path).with_context(|| format!("Failed to create snapshot store: {}", path.display()))?;
+        }
+        Ok(Store { dir: dir.to_path_buf() })
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.dir.join("objects").join(&hash[..2]).join(format!("{}.json.zst", &hash[2..]))
+    }
+
+    /// Archive `items` as the run taken at `taken_at`.
+    pub fn add(&self, items: &[CanonicalItem], taken_at: DateTime<Utc>, label: &str) -> Result<RunInfo> {
+        let stamp = taken_at.format("%Y%m%dT%H%M%SZ").to_string();
+        let file = format!("{}.json.zst", stamp);
+        let run_path = self.dir.join("runs").join(&file);
+        if run_path.exists() {
+            bail!("{} already has a run taken at {}", self.dir.display(), stamp);
+        }
+
+        let mut ids = BTreeMap::new();
+        let mut new_objects = 0;
+        for item in items {
+            let bytes = serde_json::to_vec(item)?;
+            let hash = digest::sha256_hex(&bytes);
+            let path = self.object_path(&hash);
+            if !path.exists() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                write_atomic(&path, &zstd::encode_all(bytes.as_slice(), 0)?)?;
+                new_objects += 1;
+            }
+            ids.insert(item.id.clone(), hash);
+        }
+
+        let taken = taken_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let run = Run { store_version: STORE_VERSION, taken_at: taken.clone(), label: label.to_string(), items: ids };
+        write_atomic(&run_path, &zstd::encode_all(serde_json::to_vec(&run)?.as_slice(), 0)?)?;
+
+        // The index line last: a run only counts once its objects and file are in place
+        let info = RunInfo { taken_at: taken, file, label: label.to_string(), items: items.len(), new_objects };
+        let index = self.dir.join("runs.jsonl");
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index)
+            .with_context(|| format!("Failed to open {}", index.display()))?;
+        writeln!(f, "{}", serde_json::to_string(&info)?)?;
+        f.sync_all()?;
+        Ok(info)
+    }
+
+    /// Archived runs, oldest first.
+    pub fn runs(&self) -> Result<Vec<RunInfo>> {
+        let index = self.dir.join("runs.jsonl");
+        if !index.exists() {
+            return Ok(Vec::new());
+        }
+        let text = fs::read_to_string(&index).with_context(|| format!("Failed to read {}", index.display()))?;
+        let mut runs = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(n, l)| {
+                serde_json::from_str::<RunInfo>(l)
+                    .with_context(|| format!("Invalid run on line {} of {}", n + 1, index.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Backfilled runs are appended out of order; RFC 3339 in UTC sorts as text
+        runs.sort_by(|a, b| a.taken_at.cmp(&b.taken_at));
+        Ok(runs)
+    }
+
+    fn load_run(&self, info: &RunInfo) -> Result<Run> {
+        let path = self.dir.join("runs").join(&info.file);
+        let run: Run = serde_json::from_slice(&read_zstd(&path)?)
+            .with_context(|| format!("Failed to parse run: {}", path.display()))?;
+        if run.store_version != STORE_VERSION {
+            bail!(
+                "{} is store version {}, this binary reads version {}",
+                path.display(),
+                run.store_version,
+                STORE_VERSION
+            );
+        }
+        Ok(run)
+    }
+
+    /// The run's items: ID -> object hash.
+    pub fn run_items(&self, info: &RunInfo) -> Result<BTreeMap<String, String>> {
+        Ok(self.load_run(info)?.items)
+    }
+
+    /// One archived item version.
+    pub fn object(&self, hash: &str) -> Result<CanonicalItem> {
+        let path = self.object_path(hash);
+        serde_json::from_slice(&read_zstd(&path)?).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// The last run taken at or before `at`. None: no run that early.
+    pub fn run_as_of(&self, at: DateTime<Utc>) -> Result<Option<RunInfo>> {
+        let at = at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        Ok(self.runs()?.into_iter().rfind(|r| r.taken_at <= at))
+    }
+
+    /// Every item of a run, in ID order.
+    pub fn items(&self, info: &RunInfo) -> Result<Vec<CanonicalItem>> {
+        self.load_run(info)?.items.values().map(|hash| self.object(hash)).collect()
+    }
+
+    /// The item `id` as the last run at or before `at` had it. None: no run that early.
+    pub fn as_of(&self, id: &str, at: DateTime<Utc>) -> Result<Option<(RunInfo, Option<CanonicalItem>)>> {
+        let Some(info) = self.run_as_of(at)? else { return Ok(None) };
+        let run = self.load_run(&info)?;
+        let item = run.items.get(id).map(|hash| self.object(hash)).transpose()?;
+        Ok(Some((info, item)))
+    }
+
+    /// Runs in which `id` appeared, disappeared or changed; with `fields`, only
+    /// changes touching one of them (appearing and disappearing always count).
+    pub fn history(&self, id: &str, fields: &[String]) -> Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        let mut prev: Option<(String, Value)> = None;
+        for info in self.runs()? {
+            let run = self.load_run(&info)?;
+            let hash = run.items.get(id);
+            match (hash, &prev) {
+                (None, None) => continue,
+                (None, Some(_)) => {
+                    changes.push(Change { taken_at: info.taken_at, change: ChangeKind::Removed, fields: Vec::new() });
+                    prev = None;
+                }
+                (Some(h), Some((p, _))) if h == p => continue,
+                (Some(h), _) => {
+                    let mut now = serde_json::to_value(self.object(h)?)?;
+                    if let Some(map) = now.as_object_mut() {
+                        map.remove("content_hash");
+                    }
+                    match &prev {
+                        None => changes.push(Change {
+                            taken_at: info.taken_at,
+                            change: ChangeKind::Added,
+                            fields: Vec::new(),
+                        }),
+                        Some((_, was)) => {
+                            let diff = diff_fields(was, &now, fields);
+                            if !diff.is_empty() {
+                                let change = ChangeKind::Changed;
+                                changes.push(Change { taken_at: info.taken_at, change, fields: diff });
+                            }
+                        }
+                    }
+                    prev = Some((h.clone(), now));
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// `id`'s epss in the first run that has the item and each later one where it
+    /// moved; runs without the item are skipped.
+    pub fn epss_trend(&self, id: &str) -> Result<Vec<EpssPoint>> {
+        let mut points: Vec<EpssPoint> = Vec::new();
+        let mut prev_hash: Option<String> = None;
+        for info in self.runs()? {
+            let run = self.load_run(&info)?;
+            let Some(hash) = run.items.get(id) else { continue };
+            if prev_hash.as_ref() == Some(hash) {
+                continue;
+            }
+            let epss = self.object(hash)?.epss;
+            if points.last().is_none_or(|p| p.epss != epss) {
+                points.push(EpssPoint { taken_at: info.taken_at, epss });
+            }
+            prev_hash = Some(hash.clone());
+        }
+        Ok(points)
+    }
+
+    /// Drop `runs` from the store, then every object no remaining run refers to.
+    pub fn prune(&self, runs: &[RunInfo]) -> Result<PruneStats> {
+        let dropped: HashSet<&str> = runs.iter().map(|r| r.file.as_str()).collect();
+        let kept: Vec<RunInfo> = self.runs()?.into_iter().filter(|r| !dropped.contains(r.file.as_str())).collect();
+
+        // The index first: an interrupted prune leaves unlisted files, never listed runs without them
+        let mut index = String::new();
+        for info in &kept {
+            index.push_str(&serde_json::to_string(info)?);
+            index.push('\n');
+        }
+        write_atomic(&self.dir.join("runs.jsonl"), index.as_bytes())?;
+        for file in &dropped {
+            let path = self.dir.join("runs").join(file);
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+
+        let mut live: HashSet<String> = HashSet::new();
+        for info in &kept {
+            live.extend(self.load_run(info)?.items.into_values());
+        }
+        let mut objects = 0;
+        let objects_dir = self.dir.join("objects");
+        for shard in fs::read_dir(&objects_dir).with_context(|| format!("Failed to list {}", objects_dir.display()))? {
+            let shard = shard?.path();
+            let Some(prefix) = shard.file_name().and_then(|n| n.to_str()).map(str::to_string) else { continue };
+            for entry in fs::read_dir(&shard).with_context(|| format!("Failed to list {}", shard.display()))? {
+                let path = entry?.path();
+                let Some(rest) = path.file_name().and_then(|n| n.to_str()?.strip_suffix(".json.zst")) else {
+                    continue;
+                };
+                if !live.contains(&format!("{}{}", prefix, rest)) {
+                    fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+                    objects += 1;
+                }
+            }
+            if fs::read_dir(&shard)?.next().is_none() {
+                fs::remove_dir(&shard).with_context(|| format!("Failed to remove {}", shard.display()))?;
+            }
+        }
+        Ok(PruneStats { runs: dropped.len(), objects })
+    }
+}
+
+/// What Store::prune removed.
+#[derive(Debug)]
+pub struct PruneStats {
+    pub runs: usize,
+    pub objects: usize,
+}
+
+/// Runs a retention policy drops, with the reason: the newest run per day is kept for
+/// `daily_days`, then the newest per ISO week until `weekly_days`, older ones go.
+/// The same policy as the orchestrator's data/history retention.
+pub fn plan_retention(
+    runs: &[RunInfo],
+    now: DateTime<Utc>,
+    daily_days: i64,
+    weekly_days: i64,
+) -> Vec<(RunInfo, String)> {
+    let mut kept_buckets: HashSet<String> = HashSet::new();
+    let mut prune = Vec::new();
+    // Newest first, so the first run seen in a bucket is the one kept
+    for info in runs.iter().rev() {
+        let Ok(taken) = DateTime::parse_from_rfc3339(&info.taken_at) else { continue };
+        let taken = taken.with_timezone(&Utc);
+        let age_days = (now - taken).num_days();
+        let (bucket, reason) = if age_days < daily_days {
+            (format!("day:{}", taken.date_naive()), "superseded by a newer run the same day".to_string())
+        } else if age_days < weekly_days {
+            let week = taken.iso_week();
+            (format!("week:{}-W{:02}", week.year(), week.week()), "superseded by a newer run the same week".to_string())
+        } else {
+            prune.push((info.clone(), format!("older than {} days", weekly_days)));
+            continue;
+        };
+        if !kept_buckets.insert(bucket) {
+            prune.push((info.clone(), reason));
+        }
+    }
+    prune.reverse();
+    prune
+}
+
+/// Top-level fields that differ between two versions of an item, in field order.
+fn diff_fields(was: &Value, now: &Value, only: &[String]) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let (was, now) = (was.as_object().unwrap_or(&empty), now.as_object().unwrap_or(&empty));
+    let mut keys: Vec<&String> = now.keys().collect();
+    keys.extend(was.keys().filter(|k| !now.contains_key(*k)));
+    keys.into_iter()
+        .filter(|k| only.is_empty() || only.contains(k))
+        .filter_map(|k| {
+            let (a, b) = (was.get(k).cloned().unwrap_or(Value::Null), now.get(k).cloned().unwrap_or(Value::Null));
+            (a != b).then(|| FieldChange { field: k.clone(), was: a, now: b })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(taken_at: &str) -> RunInfo {
+        let file = format!("{}.json.zst", taken_at);
+        RunInfo { taken_at: taken_at.to_string(), file, label: String::new(), items: 0, new_objects: 0 }
+    }
+
+    #[test]
+    fn retention_keeps_newest_per_day_then_week() {
+        let now = crate::parse_iso_datetime("2026-10-14T12:00:00Z").unwrap();
+        let runs: Vec<RunInfo> = [
+            "2024-01-01T00:00:00Z", // past weekly_days
+            "2026-03-02T01:00:00Z", // same ISO week as the next one
+            "2026-03-04T01:00:00Z",
+            "2026-10-13T01:00:00Z", // same day as the next one
+            "2026-10-13T02:00:00Z",
+            "2026-10-14T01:00:00Z",
+        ]
+        .into_iter()
+        .map(run)
+        .collect();
+        let dropped: Vec<String> = plan_retention(&runs, now, 90, 730).into_iter().map(|(r, _)| r.taken_at).collect();
+        assert_eq!(dropped, ["2024-01-01T00:00:00Z", "2026-03-02T01:00:00Z", "2026-10-13T01:00:00Z"]);
+    }
+}
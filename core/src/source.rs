@@ -0,0 +1,365 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use crate::{cpe, cvss, log_ok, logging, precedence, provenance, refs, severity, CanonicalItem};
+
+/* -------------------- Feed sources + merge -------------------- */
+/*
+A Source turns one feed into PartialItems: whatever that feed knows about each
+CVE, nothing more. The Normalizer merges the sources it was given by ID into
+CanonicalItems, in the order the sources were added:
+
+  let nvd = nvd::NvdSource::new(nvd_paths);
+  let kev = kev::KevSource::load(kev_path)?;
+  let items = Normalizer::new().source(&nvd).source(&kev).normalize()?;
+
+Merge rules, per ID:
+- sources lists every source that produced the ID, in source order
+- scalars (dates, CVSS, vendor, product, description) come from the first
+  source that has them, unless a MergePolicy ranks the sources otherwise (see
+  precedence.rs); list fields are concatenated, refs deduplicated by URL
+  (first wins) and CWEs sorted
+- a `kev` listing marks the item KEV and copies the BOD 22-01 fields
+- short_desc is the first description, else the first placeholder (a source's
+  "we know of it but can't describe it" text), else a generic one
+- severity_bucket follows from CVSS and KEV (see severity.rs)
+- a source whose newest_wins() is true (NVD: yearly and modified feeds overlap)
+  may repeat an ID; the record with the newest last_modified replaces the
+  earlier one, ties going to the later record. That is exact while the source
+  is the item's only contributor so far, i.e. when it is added first, as
+  normalize does; after another source's record the repeat is merged instead
+
+Sources hand records over one at a time through for_each_item, which by default
+walks items(); NVD streams its feeds chunk by chunk (see nvd.rs), so the merge
+never holds a whole feed's records next to the merged items.
+
+Output order is first appearance across sources; normalize sorts by ID later.
+Enrichment passes (cvelist, msrc, distro trackers, ...) fill gaps on existing
+items rather than contributing records, so they stay merge_* functions over the
+item list, run after the Normalizer.
+
+A Registry maps kinds to constructors so sources can be named on the command
+line as KIND:ARG (`normalize --source exec:acme=./acme-feed --jsonl`). The
+builtin kinds each sit behind a cargo feature of the same name, all on by
+default:
+
+  nvd:PATH_OR_GLOB    NVD 2.0 feed files (nvd.rs)
+  kev:PATH            CISA KEV catalog (kev.rs)
+  exec:NAME=COMMAND   PartialItems as JSON lines from a subprocess (exec.rs)
+
+exec is the way to plug in a proprietary feed without touching this crate; a
+Rust tool embedding the library can instead implement Source and register() it
+under its own kind.
+*/
+
+const NO_DESCRIPTION: &str = "No description available.";
+
+pub trait Source {
+    /// Name recorded in `sources` ("nvd", "kev").
+    fn name(&self) -> &str;
+    fn items(&self) -> Result<Vec<PartialItem>>;
+    /// Pass each record to `f` as it is produced; what the Normalizer calls.
+    fn for_each_item(&self, f: &mut dyn FnMut(PartialItem) -> Result<()>) -> Result<()> {
+        self.items()?.into_iter().try_for_each(f)
+    }
+    /// Repeated IDs keep the newest last_modified instead of merging (see the module comment).
+    fn newest_wins(&self) -> bool {
+        false
+    }
+    /// Files the source reads, listed in the run manifest.
+    fn inputs(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// What one source knows about one CVE. Only `id` is required in JSON.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialItem {
+    pub id: String,
+    pub published: Option<String>,
+    pub last_modified: Option<String>,
+    pub cvss_details: Option<cvss::CvssDetails>,
+    pub description: Option<String>,
+    /// description per language tag, when a source keeps them all
+    pub descriptions: BTreeMap<String, String>,
+    /// short_desc when no source describes the item
+    pub placeholder: Option<String>,
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub refs: Vec<refs::Reference>,
+    pub cwes: Vec<String>,
+    pub affected: Vec<cpe::AffectedCpe>,
+    pub kev: Option<KevListing>,
+}
+
+/// KEV's BOD 22-01 operational fields, copied onto every KEV-listed item.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KevListing {
+    pub date_added: Option<String>,
+    pub due_date: Option<String>,
+    pub required_action: Option<String>,
+    pub ransomware_known: Option<bool>,
+}
+
+impl KevListing {
+    pub fn apply(&self, item: &mut CanonicalItem) {
+        item.kev_date_added = self.date_added.clone();
+        item.kev_due_date = self.due_date.clone();
+        item.kev_required_action = self.required_action.clone();
+        item.ransomware_known = self.ransomware_known;
+    }
+}
+
+fn pick<T>(mine: &mut Option<T>, theirs: Option<T>, take: bool) {
+    if theirs.is_some() && (take || mine.is_none()) {
+        *mine = theirs;
+    }
+}
+
+impl PartialItem {
+    /// Whether the merge-policy field (precedence::FIELDS) is set.
+    fn has(&self, field: &str) -> bool {
+        match field {
+            "published" => self.published.is_some(),
+            "last_modified" => self.last_modified.is_some(),
+            "cvss" => self.cvss_details.is_some(),
+            "short_desc" => self.description.is_some(),
+            "vendor" => self.vendor.is_some(),
+            "product" => self.product.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Merge `other` in; scalars already set are kept unless their field is in `take`.
+    fn absorb(&mut self, other: PartialItem, take: &[&str]) {
+        let take = |field| take.contains(&field);
+        pick(&mut self.published, other.published, take("published"));
+        pick(&mut self.last_modified, other.last_modified, take("last_modified"));
+        pick(&mut self.cvss_details, other.cvss_details, take("cvss"));
+        pick(&mut self.description, other.description, take("short_desc"));
+        self.placeholder = self.placeholder.take().or(other.placeholder);
+        for (lang, text) in other.descriptions {
+            self.descriptions.entry(lang).or_insert(text);
+        }
+        pick(&mut self.vendor, other.vendor, take("vendor"));
+        pick(&mut self.product, other.product, take("product"));
+        self.refs.extend(other.refs);
+        self.cwes.extend(other.cwes);
+        self.affected.extend(other.affected);
+        self.kev = self.kev.take().or(other.kev);
+    }
+
+    fn finish(self, sources: Vec<String>) -> CanonicalItem {
+        let PartialItem { mut refs, mut cwes, .. } = self;
+        refs::dedup_refs(&mut refs);
+        cwes.sort();
+        cwes.dedup();
+
+        let cvss = self.cvss_details.as_ref().map(|d| d.base_score);
+        let is_kev = self.kev.is_some();
+        let short_desc = self.description.or(self.placeholder).unwrap_or_else(|| NO_DESCRIPTION.to_string());
+        let mut item = CanonicalItem {
+            id: self.id,
+            sources,
+            published: self.published,
+            last_modified: self.last_modified,
+            cvss,
+            cvss_details: self.cvss_details,
+            severity_bucket: severity::bucket(cvss, is_kev),
+            kev: is_kev,
+            short_desc,
+            descriptions: self.descriptions,
+            vendor: self.vendor,
+            product: self.product,
+            refs,
+            cwes,
+            affected: self.affected,
+            ..Default::default()
+        };
+        if let Some(listing) = &self.kev {
+            listing.apply(&mut item);
+        }
+        item
+    }
+}
+
+/// Merges Sources into canonical items; see the module comment for the rules.
+#[derive(Default)]
+pub struct Normalizer<'a> {
+    sources: Vec<&'a dyn Source>,
+    policy: Option<&'a precedence::MergePolicy>,
+}
+
+// One merged ID: what is known so far, who contributed, and which source (by
+// index) set each policy field
+struct Slot {
+    item: PartialItem,
+    sources: Vec<String>,
+    set_by: [Option<usize>; precedence::FIELDS.len()],
+}
+
+impl<'a> Normalizer<'a> {
+    pub fn new() -> Self {
+        Normalizer::default()
+    }
+
+    /// Add a source; earlier sources win for scalar fields.
+    pub fn source(mut self, source: &'a dyn Source) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Rank scalar fields by `policy` instead of source order.
+    pub fn policy(mut self, policy: &'a precedence::MergePolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn normalize(&self) -> Result<Vec<CanonicalItem>> {
+        let mut slot: HashMap<String, usize> = HashMap::new();
+        let mut merged: Vec<Slot> = Vec::new();
+        let mut records = 0;
+        // Fields a ranked source took over from an earlier one, per field
+        let mut overturned = [0usize; precedence::FIELDS.len()];
+        for (n, source) in self.sources.iter().enumerate() {
+            let name = source.name();
+            let started = logging::Timer::start();
+            let (mut count, mut replaced) = (0usize, 0usize);
+            source.for_each_item(&mut |partial| {
+                count += 1;
+                let set = |item: &PartialItem| precedence::FIELDS.map(|f| item.has(f).then_some(n));
+                match slot.get(&partial.id) {
+                    Some(&idx) if source.newest_wins() && merged[idx].sources == [name] => {
+                        // ISO8601 timestamps in one feed format compare lexicographically
+                        let m = &mut merged[idx];
+                        if partial.last_modified >= m.item.last_modified {
+                            m.set_by = set(&partial);
+                            m.item = partial;
+                        }
+                        replaced += 1;
+                    }
+                    Some(&idx) => {
+                        let m = &mut merged[idx];
+                        if !m.sources.iter().any(|s| s == name) {
+                            m.sources.push(name.to_string());
+                        }
+                        let mut take = Vec::new();
+                        if let Some(policy) = self.policy {
+                            for (f, field) in precedence::FIELDS.into_iter().enumerate() {
+                                let holder = m.set_by[f].map(|i| self.sources[i].name());
+                                if let Some(holder) = holder
+                                    && partial.has(field)
+                                    && policy.prefers(field, name, Some(holder))
+                                {
+                                    take.push(field);
+                                    overturned[f] += 1;
+                                }
+                            }
+                        }
+                        for (f, field) in precedence::FIELDS.into_iter().enumerate() {
+                            if partial.has(field) && (m.set_by[f].is_none() || take.contains(&field)) {
+                                m.set_by[f] = Some(n);
+                            }
+                        }
+                        m.item.absorb(partial, &take);
+                    }
+                    None => {
+                        slot.insert(partial.id.clone(), merged.len());
+                        let set_by = set(&partial);
+                        merged.push(Slot { item: partial, sources: vec![name.to_string()], set_by });
+                    }
+                }
+                Ok(())
+            })?;
+            log_ok!("source {}: {} records in {:.1}s", name, count, started.secs());
+            if replaced > 0 {
+                log_ok!("source {}: {} repeated IDs, newest last_modified kept", name, replaced);
+            }
+            records += count;
+        }
+        log_ok!("sources merged: {} records from {} sources into {} items", records, self.sources.len(), merged.len());
+        if self.policy.is_some() {
+            let summary: Vec<String> = precedence::FIELDS
+                .iter()
+                .zip(overturned)
+                .filter(|(_, n)| *n > 0)
+                .map(|(field, n)| format!("{} {}", field, n))
+                .collect();
+            if !summary.is_empty() {
+                log_ok!("merge policy: later sources won {}", summary.join(", "));
+            }
+        }
+        Ok(merged
+            .into_iter()
+            .map(|m| {
+                let mut item = m.item.finish(m.sources);
+                // Read back by provenance::seed and by passes that rank against the merge (csaf)
+                if self.policy.is_some() {
+                    for (field, n) in precedence::FIELDS.iter().zip(m.set_by) {
+                        if let Some(n) = n {
+                            let source = self.sources[n].name().to_string();
+                            item.provenance.insert(field.to_string(), provenance::FieldSource { source, as_of: None });
+                        }
+                    }
+                }
+                item
+            })
+            .collect())
+    }
+}
+
+type Factory = Box<dyn Fn(&str) -> Result<Box<dyn Source>> + Send + Sync>;
+
+/// Source constructors by kind, for KIND:ARG specs.
+#[derive(Default)]
+pub struct Registry {
+    factories: BTreeMap<String, Factory>,
+}
+
+impl Registry {
+    /// The kinds compiled into this build (see the module comment).
+    pub fn builtin() -> Self {
+        #[allow(unused_mut)] // with every builtin feature disabled
+        let mut registry = Registry::default();
+        #[cfg(feature = "nvd")]
+        registry.register("nvd", |arg| {
+            let paths = crate::input::expand_globs(&[PathBuf::from(arg)])?;
+            Ok(Box::new(crate::nvd::NvdSource::new(paths)))
+        });
+        #[cfg(feature = "kev")]
+        registry.register("kev", |arg| Ok(Box::new(crate::kev::KevSource::load(std::path::Path::new(arg))?)));
+        #[cfg(feature = "exec")]
+        registry.register("exec", |arg| Ok(Box::new(crate::exec::ExecSource::parse(arg)?)));
+        registry
+    }
+
+    /// Add or replace the constructor for `kind`; it receives the ARG part of the spec.
+    pub fn register(
+        &mut self,
+        kind: &str,
+        factory: impl Fn(&str) -> Result<Box<dyn Source>> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(kind.to_string(), Box::new(factory));
+    }
+
+    pub fn kinds(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    /// Build a source from "KIND:ARG".
+    pub fn create(&self, spec: &str) -> Result<Box<dyn Source>> {
+        let Some((kind, arg)) = spec.split_once(':') else {
+            bail!("invalid source '{}': expected KIND:ARG (kinds: {})", spec, self.kinds().join(", "));
+        };
+        match self.factories.get(kind.trim()) {
+            Some(factory) => factory(arg),
+            None => bail!("unknown source kind '{}' (known: {})", kind, self.kinds().join(", ")),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{top_n_counts, CanonicalItem};
+
+/* -------------------- Summary analytics -------------------- */
+/*
+Whole-file counts for dashboards and for sanity-checking a refresh (a feed that
+silently lost half its records shows up immediately in total/by_month).
+Unlike derive's trend windows these ignore the current date.
+*/
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub kev: usize,
+    pub non_kev: usize,
+    pub by_severity: BTreeMap<String, usize>,
+    pub with_cvss: usize,
+    pub avg_cvss: Option<f64>,
+    pub top_vendors: Vec<(String, usize)>,
+    pub top_products: Vec<(String, usize)>,
+    pub by_month: BTreeMap<String, usize>, // "2024-06" -> items published that month
+    pub no_published: usize,
+}
+
+pub fn compute(items: &[CanonicalItem], top: usize) -> Stats {
+    let mut by_severity: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_month: BTreeMap<String, usize> = BTreeMap::new();
+    let mut vendors: HashMap<String, usize> = HashMap::new();
+    let mut products: HashMap<String, usize> = HashMap::new();
+    let (mut kev, mut no_published, mut with_cvss, mut cvss_sum) = (0usize, 0usize, 0usize, 0f64);
+
+    for item in items {
+        if item.kev {
+            kev += 1;
+        }
+        *by_severity.entry(item.severity_bucket.clone()).or_insert(0) += 1;
+        if let Some(c) = item.cvss {
+            with_cvss += 1;
+            cvss_sum += c;
+        }
+        match item.published.as_deref().and_then(|p| p.get(..7)) {
+            Some(month) => *by_month.entry(month.to_string()).or_insert(0) += 1,
+            None => no_published += 1,
+        }
+        if let Some(v) = item.vendor.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            *vendors.entry(v.to_string()).or_insert(0) += 1;
+        }
+        if let Some(p) = item.product.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            *products.entry(p.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    Stats {
+        total: items.len(),
+        kev,
+        non_kev: items.len() - kev,
+        by_severity,
+        with_cvss,
+        avg_cvss: (with_cvss > 0).then(|| cvss_sum / with_cvss as f64),
+        top_vendors: top_n_counts(&vendors, top),
+        top_products: top_n_counts(&products, top),
+        by_month,
+        no_published,
+    }
+}
+
+fn pct(n: usize, total: usize) -> String {
+    if total == 0 { "-".to_string() } else { format!("{:.1}%", n as f64 * 100.0 / total as f64) }
+}
+
+pub fn print_table(s: &Stats) {
+    println!("Items           {:>8}", s.total);
+    println!("  KEV           {:>8}  {:>6}", s.kev, pct(s.kev, s.total));
+    println!("  non-KEV       {:>8}  {:>6}", s.non_kev, pct(s.non_kev, s.total));
+    println!(
+        "Average CVSS    {:>8}  (over {} scored items)",
+        s.avg_cvss.map_or("n/a".to_string(), |a| format!("{:.2}", a)),
+        s.with_cvss
+    );
+
+    println!();
+    println!("By severity");
+    let order = crate::severity::names();
+    for &sev in &order {
+        let n = s.by_severity.get(sev).copied().unwrap_or(0);
+        println!("  {:<12}  {:>8}  {:>6}", sev, n, pct(n, s.total));
+    }
+    // Anything outside the policy's buckets points at a normalization bug (or a policy mismatch)
+    for (sev, n) in s.by_severity.iter().filter(|(k, _)| !order.contains(&k.as_str())) {
+        println!("  {:<12}  {:>8}  {:>6}  (non-standard bucket)", sev, n, pct(*n, s.total));
+    }
+
+    for (title, rows) in [("Top vendors", &s.top_vendors), ("Top products", &s.top_products)] {
+        println!();
+        println!("{}", title);
+        for (name, n) in rows {
+            println!("  {:<32}  {:>8}", name, n);
+        }
+    }
+
+    println!();
+    println!("Published per month");
+    for (month, n) in &s.by_month {
+        println!("  {:<12}  {:>8}", month, n);
+    }
+    if s.no_published > 0 {
+        println!("  {:<12}  {:>8}", "(none)", s.no_published);
+    }
+}
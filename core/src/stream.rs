@@ -0,0 +1,452 @@
+use anyhow::{bail, Context, Result};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Read},
+    marker::PhantomData,
+    path::Path,
+};
+
+use crate::{errors::Failure, input, lenient, logging};
+
+/* -------------------- Streaming array parsing -------------------- */
+/*
+Full NVD year feeds are hundreds of MB; deserializing a whole NvdRoot keeps every
+record alive at once. Instead we walk the top-level object, skip every key except
+the target array, and hand each element to a callback as soon as it is parsed.
+Peak memory is then one record plus whatever the callback keeps.
+
+With --lenient, for_each_record doesn't hand the file to serde at all: a scan
+that only follows brackets and quotes finds where each element of the array
+starts and ends, and each element is parsed on its own. A record with broken
+syntax (a missing comma, a bad literal) is then reported and skipped like one
+of the wrong shape, and the next one reads as usual. The scan can't recover
+from a record whose brackets or quotes don't balance: everything after it
+would be taken as part of it, so the file ending inside the array (as a
+truncated download does) still fails the run. The root's other keys must be
+valid JSON too.
+*/
+
+struct ArraySeed<'f, T, F> {
+    f: &'f mut F,
+    count: &'f mut usize,
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T, F> DeserializeSeed<'de> for &mut ArraySeed<'_, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T, F> Visitor<'de> for &mut ArraySeed<'_, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(elem) = seq.next_element::<T>()? {
+            (self.f)(elem).map_err(de::Error::custom)?;
+            *self.count += 1;
+        }
+        Ok(())
+    }
+}
+
+// Receives the root object's other keys (see for_each_in_root)
+type MetaFn<'m> = &'m mut dyn FnMut(&str, Value) -> Result<()>;
+
+struct RootVisitor<'a, 'f, 'm, T, F> {
+    field: &'a str,
+    seed: ArraySeed<'f, T, F>,
+    // The root's other keys, for callers that want them (else skipped unparsed)
+    meta: Option<MetaFn<'m>>,
+}
+
+impl<'de, T, F> Visitor<'de> for RootVisitor<'_, '_, '_, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an object with a \"{}\" array", self.field)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<usize, A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.field {
+                map.next_value_seed(&mut self.seed)?;
+            } else if let Some(meta) = &mut self.meta {
+                meta(&key, map.next_value()?).map_err(de::Error::custom)?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(*self.seed.count)
+    }
+
+    // A bare top-level array; only reached through for_each_in_root
+    fn visit_seq<A: SeqAccess<'de>>(mut self, seq: A) -> Result<usize, A::Error> {
+        (&mut self.seed).visit_seq(seq)?;
+        Ok(*self.seed.count)
+    }
+}
+
+/// Stream every element of the top-level array `field` in the JSON file at `path`
+/// (gzip/zstd inputs are decompressed on the fly).
+/// Returns the number of elements visited. Errors from `f` abort the parse.
+pub fn for_each_in_array<T, F>(path: &Path, field: &str, f: F) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    for_each_in_reader(input::open_input(path)?, path, field, f)
+}
+
+/// for_each_in_array over an input that is already open (see input::open_bytes);
+/// `path` only names it in messages.
+pub fn for_each_in_reader<T, F>(reader: impl Read, path: &Path, field: &str, f: F) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    drive(reader, path, field, None, f)
+}
+
+/// for_each_in_reader that also takes a bare top-level array of records (codex v1
+/// files) and passes the root object's other keys to `meta` as they are reached,
+/// so a schema_version ahead of the array is known before its first record.
+pub fn for_each_in_root<T, F>(
+    reader: impl Read,
+    path: &Path,
+    field: &str,
+    meta: MetaFn<'_>,
+    f: F,
+) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    drive(reader, path, field, Some(meta), f)
+}
+
+// With `meta`, the root may be an array as well as an object
+fn drive<T, F>(
+    reader: impl Read,
+    path: &Path,
+    field: &str,
+    meta: Option<MetaFn<'_>>,
+    mut f: F,
+) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+    let mut progress = logging::Progress::new(name);
+    let mut f = |record: T| {
+        progress.tick();
+        f(record)
+    };
+
+    // Records fully handled so far; on error this is the index of the failing one
+    let mut done = 0;
+    let any = meta.is_some();
+    let visitor = RootVisitor {
+        field,
+        seed: ArraySeed { f: &mut f, count: &mut done, _marker: PhantomData },
+        meta,
+    };
+    let parsed = if any {
+        de::Deserializer::deserialize_any(&mut de, visitor)
+    } else {
+        de::Deserializer::deserialize_map(&mut de, visitor)
+    };
+    let parsed = parsed.and_then(|count| de.end().map(|_| count));
+    parsed.map_err(|e| {
+        let msg = format!("Parse stopped after {} \"{}\" records in {}", done, field, path.display());
+        anyhow::Error::new(e).context(Failure::new("invalid_record", msg).path(path).record(done))
+    })
+}
+
+/// for_each_in_array, except that with --lenient a record that doesn't parse as
+/// JSON or doesn't fit `T` is reported (see lenient.rs) and skipped instead of
+/// aborting. `id_pointer` locates the CVE ID in a record for the report.
+pub fn for_each_record<T, F>(path: &Path, field: &str, source: &str, id_pointer: &str, f: F) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    for_each_record_in(input::open_input(path)?, path, field, source, id_pointer, f)
+}
+
+/// for_each_record over an input that is already open.
+pub fn for_each_record_in<T, F>(
+    reader: impl Read,
+    path: &Path,
+    field: &str,
+    source: &str,
+    id_pointer: &str,
+    mut f: F,
+) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    if !lenient::enabled() {
+        return for_each_in_reader(reader, path, field, f);
+    }
+    for_each_resynced(reader, path, field, (source, id_pointer), &mut |_, _| Ok(()), &mut f)
+}
+
+/// The --lenient reading behind for_each_record, for callers that also want the
+/// root object's other keys (parsed).
+pub fn for_each_resynced<T, F>(
+    reader: impl Read,
+    path: &Path,
+    field: &str,
+    (source, id_pointer): (&str, &str),
+    meta: MetaFn<'_>,
+    mut f: F,
+) -> Result<usize>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    let file = path.display().to_string();
+    let name = path.file_name().map_or_else(|| file.clone(), |n| n.to_string_lossy().into_owned());
+    let mut progress = logging::Progress::new(name);
+    let mut index = 0;
+    let scanned = split_root(
+        reader,
+        field,
+        &mut |key, raw| {
+            let value = serde_json::from_slice(raw).with_context(|| format!("Invalid \"{}\" value", key))?;
+            meta(key, value)
+        },
+        &mut |raw| {
+            index += 1;
+            progress.tick();
+            let record: Value = match serde_json::from_slice(raw.trim_ascii_start()) {
+                Ok(record) => record,
+                Err(e) => {
+                    lenient::skip_broken(source, &file, index - 1, raw, e);
+                    return Ok(());
+                }
+            };
+            match T::deserialize(&record) {
+                Ok(parsed) => f(parsed),
+                Err(e) => {
+                    lenient::skip(source, &file, index - 1, &record, id_pointer, e);
+                    Ok(())
+                }
+            }
+        },
+    );
+    scanned.map(|_| index).map_err(|e| {
+        let msg = format!("Parse stopped after {} \"{}\" records in {}", index, field, path.display());
+        e.context(Failure::new("invalid_record", msg).path(path).record(index))
+    })
+}
+
+/// Walk a root object by brackets and quotes only: each element of its `field`
+/// array goes to `on_record` and every other key's value to `on_meta`, as raw
+/// bytes, without checking either is valid JSON.
+fn split_root(
+    reader: impl Read,
+    field: &str,
+    on_meta: &mut dyn FnMut(&str, &[u8]) -> Result<()>,
+    on_record: &mut dyn FnMut(&[u8]) -> Result<()>,
+) -> Result<()> {
+    let mut reader = BufReader::with_capacity(1 << 16, reader);
+    let mut depth = 0usize;
+    let (mut in_string, mut escaped) = (false, false);
+    let (mut key, mut reading_key) = (Vec::new(), false);
+    // After `"field":`, until its '['
+    let mut array_next = false;
+    let mut in_array = false;
+    let mut record = Vec::new();
+    // Another key's value, being read
+    let mut value: Option<Vec<u8>> = None;
+    let mut done = false;
+
+    let key_str = |key: &[u8]| String::from_utf8_lossy(key).into_owned();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let n = buf.len();
+        for &b in buf {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_string = false;
+                }
+                if reading_key {
+                    // The closing quote ends the key; escapes stay as written
+                    if in_string {
+                        key.push(b);
+                    } else {
+                        reading_key = false;
+                    }
+                } else if in_array {
+                    record.push(b);
+                } else if let Some(v) = value.as_mut() {
+                    v.push(b);
+                }
+                continue;
+            }
+            if in_array {
+                match b {
+                    b'[' | b'{' => depth += 1,
+                    b']' if depth == 2 => {
+                        if !record.trim_ascii().is_empty() {
+                            on_record(&record)?;
+                        }
+                        record.clear();
+                        in_array = false;
+                        depth = 1;
+                        continue;
+                    }
+                    b'}' if depth == 2 => bail!("\"{}\" array closed with '}}'", field),
+                    b']' | b'}' => depth -= 1,
+                    b',' if depth == 2 => {
+                        on_record(&record)?;
+                        record.clear();
+                        continue;
+                    }
+                    b'"' => in_string = true,
+                    _ => {}
+                }
+                record.push(b);
+                continue;
+            }
+            if let Some(v) = value.as_mut() {
+                match b {
+                    b',' | b'}' if depth == 1 => {
+                        on_meta(&key_str(&key), v)?;
+                        value = None;
+                    }
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' => depth = depth.checked_sub(1).context("unbalanced brackets in the root object")?,
+                    b'"' => in_string = true,
+                    _ => {}
+                }
+                if let Some(v) = value.as_mut() {
+                    v.push(b);
+                    continue;
+                }
+            }
+            if b.is_ascii_whitespace() {
+                continue;
+            }
+            match (depth, b) {
+                _ if done => bail!("trailing characters after the root object"),
+                (0, b'{') => depth = 1,
+                (0, _) => bail!("expected an object with a \"{}\" array", field),
+                (1, b'[') if array_next => {
+                    array_next = false;
+                    in_array = true;
+                    depth = 2;
+                }
+                (1, _) if array_next => bail!("\"{}\" is not an array", field),
+                (1, b'"') => {
+                    in_string = true;
+                    reading_key = true;
+                    key.clear();
+                }
+                (1, b':') if key == field.as_bytes() => array_next = true,
+                (1, b':') => value = Some(Vec::new()),
+                (1, b',') => {}
+                (1, b'}') => {
+                    depth = 0;
+                    done = true;
+                }
+                _ => bail!("unexpected '{}' in the root object", b as char),
+            }
+        }
+        reader.consume(n);
+    }
+    if in_array {
+        bail!(
+            "the file ends inside the \"{}\" array: truncated, or a record's brackets or quotes don't balance",
+            field
+        );
+    }
+    if !done {
+        bail!("the file ends inside the root object");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Record {
+        id: String,
+    }
+
+    fn resync(json: &str) -> Result<(Vec<String>, Option<Value>)> {
+        let (mut ids, mut version) = (Vec::new(), None);
+        let mut meta = |key: &str, value: Value| {
+            if key == "version" {
+                version = Some(value);
+            }
+            Ok(())
+        };
+        let path = Path::new("test.json");
+        for_each_resynced(json.as_bytes(), path, "records", ("test", "/id"), &mut meta, |r: Record| {
+            ids.push(r.id);
+            Ok(())
+        })?;
+        Ok((ids, version))
+    }
+
+    #[test]
+    fn lenient_scan_skips_broken_records_and_reads_on() {
+        let json = r#"{"version": {"major": 2, "note": "a } in a string"},
+            "records": [{"id": "CVE-2099-0001"}, {"id" "CVE-2099-0002"}, {"id": 3},
+                        {"id": "CVE-2099-0004", "text": "brackets ]} and \"quotes\" in strings"}],
+            "after": [1, [2]]}"#;
+        let (ids, version) = resync(json).unwrap();
+        assert_eq!(ids, ["CVE-2099-0001", "CVE-2099-0004"]);
+        // Only this test skips records, so the list is its own
+        let skipped: Vec<String> = lenient::take().into_iter().map(|s| s.id.unwrap_or_default()).collect();
+        // The broken record is named by the CVE ID in its text; the misshapen one has none to read
+        assert_eq!(skipped, ["CVE-2099-0002", ""]);
+        assert_eq!(version.unwrap()["note"], "a } in a string");
+    }
+
+    #[test]
+    fn lenient_scan_fails_where_it_cannot_resync() {
+        assert!(resync(r#"{"records": [{"id": "CVE-2099-0001"}, {"id": "CVE-2099-0002""#).is_err());
+        assert!(resync(r#"{"records": [{"id": "CVE-2099-0001"}}"#).is_err());
+        assert!(resync(r#"{"records": {"id": "CVE-2099-0001"}}"#).is_err());
+        assert!(resync(r#"{"version": [1, 2}, "records": []}"#).is_err());
+        assert!(resync(r#"{"records": []} {}"#).is_err());
+        let (ids, _) = resync(r#"{"records": []}"#).unwrap();
+        assert!(ids.is_empty());
+    }
+}
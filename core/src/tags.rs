@@ -0,0 +1,130 @@
+use anyhow::{bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- Keyword tagging -------------------- */
+/*
+Routing labels computed once in the feed instead of in every consumer. A rules
+file (TOML, or JSON of the same shape) maps patterns to tags:
+
+  [[rule]]
+  tag = "vpn-appliance"
+  pattern = "fortinet|pulse secure|globalprotect"   # regex, case-insensitive
+  fields = ["vendor", "product"]                    # default: title, short_desc, vendor, product
+
+  [[rule]]
+  tag = "deser"
+  keywords = ["deserialization", "deserialize"]     # plain substrings, case-insensitive
+
+A rule needs a pattern, keywords, or both (either one matching is enough).
+Several rules may share a tag. A rule's fields can also include "cwes"
+(matches each CWE id). Matching runs on the final item, after vendor
+normalization and overrides. With --tag-rules, item.tags is recomputed from
+scratch, so rules removed from the file drop their tags on merge-into items too.
+*/
+
+const DEFAULT_FIELDS: &[&str] = &["title", "short_desc", "vendor", "product"];
+const FIELDS: &[&str] = &["title", "short_desc", "vendor", "product", "cwes"];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    tag: String,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    fields: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleSpec>,
+}
+
+pub struct TagRule {
+    tag: String,
+    matcher: Regex,
+    fields: Vec<String>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<TagRule>> {
+    let bytes = input::read_input(path)?;
+    let text = String::from_utf8(bytes).with_context(|| format!("Tag rules file is not UTF-8: {}", path.display()))?;
+    let doc: Value = if text.trim_start().starts_with('{') {
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse tag rules JSON: {}", path.display()))?
+    } else {
+        crate::toml::parse(&text).with_context(|| format!("Failed to parse tag rules TOML: {}", path.display()))?
+    };
+    let file: RulesFile =
+        serde_json::from_value(doc).with_context(|| format!("Invalid tag rules file: {}", path.display()))?;
+
+    let mut rules = Vec::new();
+    for (n, spec) in file.rules.into_iter().enumerate() {
+        let tag = spec.tag.trim().to_string();
+        if tag.is_empty() {
+            bail!("{}: rule {} has an empty tag", path.display(), n + 1);
+        }
+        // Keywords become escaped alternatives next to the pattern
+        let mut alternatives: Vec<String> = spec.keywords.iter().filter(|k| !k.is_empty()).map(|k| regex::escape(k)).collect();
+        if let Some(p) = &spec.pattern {
+            alternatives.push(format!("(?:{})", p));
+        }
+        if alternatives.is_empty() {
+            bail!("{}: rule for tag \"{}\" needs a pattern or keywords", path.display(), tag);
+        }
+        let matcher = RegexBuilder::new(&alternatives.join("|"))
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("{}: invalid pattern for tag \"{}\"", path.display(), tag))?;
+
+        let fields = if spec.fields.is_empty() {
+            DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect()
+        } else {
+            spec.fields
+        };
+        if let Some(bad) = fields.iter().find(|f| !FIELDS.contains(&f.as_str())) {
+            bail!("{}: tag \"{}\" field \"{}\" is not one of {}", path.display(), tag, bad, FIELDS.join("|"));
+        }
+        rules.push(TagRule { tag, matcher, fields });
+    }
+    Ok(rules)
+}
+
+fn field_matches(item: &CanonicalItem, field: &str, re: &Regex) -> bool {
+    let hit = |s: &Option<String>| s.as_deref().is_some_and(|s| re.is_match(s));
+    match field {
+        "title" => hit(&item.title),
+        "short_desc" => re.is_match(&item.short_desc),
+        "vendor" => hit(&item.vendor),
+        "product" => hit(&item.product),
+        "cwes" => item.cwes.iter().any(|c| re.is_match(c)),
+        _ => false,
+    }
+}
+
+/// Recompute item.tags on every item. Returns tag -> number of items carrying it.
+pub fn apply(items: &mut [CanonicalItem], rules: &[TagRule]) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = rules.iter().map(|r| (r.tag.clone(), 0)).collect();
+    for item in items.iter_mut() {
+        let tags: BTreeSet<&str> = rules
+            .iter()
+            .filter(|r| r.fields.iter().any(|f| field_matches(item, f, &r.matcher)))
+            .map(|r| r.tag.as_str())
+            .collect();
+        for tag in &tags {
+            *counts.entry(tag.to_string()).or_default() += 1;
+        }
+        item.tags = tags.into_iter().map(str::to_string).collect();
+    }
+    counts
+}
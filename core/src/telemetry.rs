@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+    path::Path,
+};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- Exploitation telemetry enrichment -------------------- */
+/*
+KEV is a binary, curated flag. Sensor data says whether a CVE is being scanned
+for or exploited right now, and how much of it is exposed.
+
+Inputs are exports (fetching stays outside the core):
+- GreyNoise: GNQL results, either { "data": [ ... ] } or one JSON object per line.
+  Each record: ip, classification (malicious|benign|unknown), last_seen, cve: [...]
+- Shodan: `shodan download` output (JSON lines, usually .json.gz). Each banner
+  carries vulns: { "CVE-...": { ... } }; we count distinct ip_str per CVE.
+
+`observed` is true when GreyNoise saw at least one malicious IP targeting the CVE.
+Shodan host counts measure exposure, not exploitation, and never set it alone.
+*/
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ObservedExploitation {
+    pub observed: bool,
+    pub greynoise_ips: usize,            // distinct IPs tagged with the CVE
+    pub greynoise_malicious: usize,      // of which classified malicious
+    pub greynoise_last_seen: Option<String>,
+    pub shodan_hosts: usize,             // distinct exposed hosts in the export
+}
+
+#[derive(Debug, Deserialize)]
+struct GreyNoiseRecord {
+    #[serde(default)]
+    ip: Option<String>,
+    #[serde(default)]
+    classification: Option<String>,
+    #[serde(default)]
+    last_seen: Option<String>,
+    #[serde(default, alias = "cves")]
+    cve: Vec<String>,
+}
+
+#[derive(Default)]
+struct GnAgg {
+    ips: HashSet<String>,
+    malicious: HashSet<String>,
+    last_seen: Option<String>,
+}
+
+/// Objects from either a `{ "data": [...] }` document or JSON lines.
+fn for_each_record(path: &Path, mut f: impl FnMut(Value) -> Result<()>) -> Result<()> {
+    let bytes = input::read_input(path)?;
+    if let Ok(Value::Object(mut doc)) = serde_json::from_slice::<Value>(&bytes) {
+        if let Some(Value::Array(data)) = doc.remove("data") {
+            return data.into_iter().try_for_each(f);
+        }
+        return f(Value::Object(doc));
+    }
+    for (n, line) in bytes.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let v = serde_json::from_str(&line).with_context(|| format!("{}: invalid JSON on line {}", path.display(), n + 1))?;
+        f(v)?;
+    }
+    Ok(())
+}
+
+fn parse_greynoise(path: &Path) -> Result<HashMap<String, GnAgg>> {
+    let mut out: HashMap<String, GnAgg> = HashMap::new();
+    for_each_record(path, |v| {
+        let Ok(rec) = serde_json::from_value::<GreyNoiseRecord>(v) else { return Ok(()); };
+        let ip = rec.ip.unwrap_or_default();
+        let malicious = rec.classification.as_deref() == Some("malicious");
+        for cve in rec.cve.iter().map(|c| c.trim().to_uppercase()).filter(|c| c.starts_with("CVE-")) {
+            let agg = out.entry(cve).or_default();
+            agg.ips.insert(ip.clone());
+            if malicious {
+                agg.malicious.insert(ip.clone());
+            }
+            if rec.last_seen > agg.last_seen {
+                agg.last_seen = rec.last_seen.clone();
+            }
+        }
+        Ok(())
+    })
+    .with_context(|| format!("Failed to parse GreyNoise export: {}", path.display()))?;
+    Ok(out)
+}
+
+fn parse_shodan(path: &Path) -> Result<HashMap<String, HashSet<String>>> {
+    let mut out: HashMap<String, HashSet<String>> = HashMap::new();
+    for_each_record(path, |banner| {
+        let host = banner
+            .get("ip_str")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| banner.get("ip").map(|v| v.to_string()))
+            .unwrap_or_default();
+        if let Some(vulns) = banner.get("vulns").and_then(|v| v.as_object()) {
+            for cve in vulns.keys().map(|c| c.trim().to_uppercase()).filter(|c| c.starts_with("CVE-")) {
+                out.entry(cve).or_default().insert(host.clone());
+            }
+        }
+        Ok(())
+    })
+    .with_context(|| format!("Failed to parse Shodan export: {}", path.display()))?;
+    Ok(out)
+}
+
+/// Attach `observed_exploitation` from GreyNoise/Shodan exports.
+/// Returns the number of items with observed malicious activity.
+pub fn merge_telemetry(greynoise: Option<&Path>, shodan: Option<&Path>, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut gn = greynoise.map(parse_greynoise).transpose()?.unwrap_or_default();
+    let mut sh = shodan.map(parse_shodan).transpose()?.unwrap_or_default();
+
+    let mut observed = 0usize;
+    for item in items.iter_mut() {
+        let g = gn.remove(&item.id);
+        let s = sh.remove(&item.id);
+        if g.is_none() && s.is_none() {
+            continue;
+        }
+        let mut obs = item.observed_exploitation.take().unwrap_or_default();
+        if let Some(g) = g {
+            obs.greynoise_ips = g.ips.len();
+            obs.greynoise_malicious = g.malicious.len();
+            obs.greynoise_last_seen = g.last_seen;
+            if !item.sources.iter().any(|s| s == "greynoise") {
+                item.sources.push("greynoise".to_string());
+            }
+        }
+        if let Some(s) = s {
+            obs.shodan_hosts = s.len();
+            if !item.sources.iter().any(|s| s == "shodan") {
+                item.sources.push("shodan".to_string());
+            }
+        }
+        obs.observed = obs.greynoise_malicious > 0;
+        if obs.observed {
+            observed += 1;
+        }
+        item.observed_exploitation = Some(obs);
+    }
+    Ok(observed)
+}
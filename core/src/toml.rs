@@ -0,0 +1,559 @@
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+/* -------------------- Minimal TOML reader -------------------- */
+/*
+Hand-edited config (overrides, policies) reads better as TOML, but the dependency
+set has no TOML crate. This covers what such files use and converts to a
+serde_json::Value, so callers deserialize with the same structs as their JSON form:
+
+- comments, [table], [a.b], [[array.of.tables]], dotted and quoted keys
+- "basic" strings with escapes, 'literal' strings, """/''' multi-line strings
+- integers (incl. _ separators, 0x/0o/0b), floats, inf/nan, booleans
+- arrays (multi-line, trailing comma) and { inline = "tables" }
+- dates/times are kept as their string form
+
+Redefining a key is an error, as in TOML proper.
+*/
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+fn err<T>(p: &Parser, msg: impl AsRef<str>) -> Result<T> {
+    bail!("line {}: {}", p.line, msg.as_ref())
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.src[self.pos..].starts_with(s) {
+            for _ in s.chars() {
+                self.bump();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    // Spaces and tabs only
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    // Whitespace, newlines and comments (inside arrays)
+    fn skip_all(&mut self) {
+        loop {
+            self.skip_ws();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                }
+                Some('\r') if self.src[self.pos..].starts_with("\r\n") => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_ws();
+        self.skip_comment();
+        self.eat("\r");
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => err(self, format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn key_part(&mut self) -> Result<String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return err(self, "expected a key");
+                }
+                Ok(self.src[start..self.pos].to_string())
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Vec<String>> {
+        let mut parts = vec![self.key_part()?];
+        loop {
+            self.skip_ws();
+            if !self.eat(".") {
+                return Ok(parts);
+            }
+            parts.push(self.key_part()?);
+        }
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let Some(c) = self.bump() else { return err(self, "unterminated escape") };
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '"' => '"',
+            '\\' => '\\',
+            'u' | 'U' => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let hex: String = (0..len).filter_map(|_| self.bump()).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => ch,
+                    None => return err(self, format!("invalid unicode escape \\{}{}", c, hex)),
+                }
+            }
+            other => return err(self, format!("invalid escape \\{}", other)),
+        })
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        let multi = self.eat("\"\"\"");
+        if !multi {
+            self.bump();
+        } else {
+            // A newline right after the opening delimiter is trimmed
+            self.eat("\r");
+            self.eat("\n");
+        }
+        let mut out = String::new();
+        loop {
+            if multi && self.eat("\"\"\"") {
+                return Ok(out);
+            }
+            if !multi && self.peek() == Some('\n') {
+                return err(self, "newline in string");
+            }
+            match self.bump() {
+                None => return err(self, "unterminated string"),
+                Some('"') if !multi => return Ok(out),
+                Some('\\') => {
+                    if multi && matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) {
+                        // Line-ending backslash: drop the newline and leading whitespace
+                        while matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) {
+                            self.bump();
+                        }
+                    } else {
+                        out.push(self.escape()?);
+                    }
+                }
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        let multi = self.eat("'''");
+        if !multi {
+            self.bump();
+        } else {
+            self.eat("\r");
+            self.eat("\n");
+        }
+        let mut out = String::new();
+        loop {
+            if multi && self.eat("'''") {
+                return Ok(out);
+            }
+            if !multi && self.peek() == Some('\n') {
+                return err(self, "newline in string");
+            }
+            match self.bump() {
+                None => return err(self, "unterminated string"),
+                Some('\'') if !multi => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.bump(); // [
+        let mut items = Vec::new();
+        loop {
+            self.skip_all();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_all();
+            if self.eat(",") {
+                continue;
+            }
+            self.skip_all();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            return err(self, "expected ',' or ']' in array");
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.bump(); // {
+        let mut table = Map::new();
+        self.skip_ws();
+        if self.eat("}") {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_ws();
+            if !self.eat("=") {
+                return err(self, "expected '=' in inline table");
+            }
+            self.skip_ws();
+            let value = self.value()?;
+            insert(self, &mut table, &key, value)?;
+            self.skip_ws();
+            if self.eat("}") {
+                return Ok(Value::Object(table));
+            }
+            if !self.eat(",") {
+                return err(self, "expected ',' or '}' in inline table");
+            }
+        }
+    }
+
+    fn scalar(&mut self) -> Result<Value> {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(',' | ']' | '}' | '#' | '\n' | '\r' | '\t') => break,
+                Some(' ') if !self.space_in_datetime(start) => break,
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+        let raw = self.src[start..self.pos].trim();
+        match raw {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => {
+                // JSON has no infinities; keep the spelling
+                return Ok(Value::String(raw.to_string()));
+            }
+            "" => return err(self, "expected a value"),
+            _ => {}
+        }
+        let digits = raw.replace('_', "");
+        let (sign, unsigned) = match digits.strip_prefix('-') {
+            Some(rest) => (-1i64, rest.to_string()),
+            None => (1, digits.trim_start_matches('+').to_string()),
+        };
+        for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+            if let Some(rest) = unsigned.strip_prefix(prefix) {
+                return match i64::from_str_radix(rest, radix) {
+                    Ok(n) => Ok(Value::from(sign * n)),
+                    Err(_) => err(self, format!("invalid integer '{}'", raw)),
+                };
+            }
+        }
+        if let Ok(n) = digits.parse::<i64>() {
+            return Ok(Value::from(n));
+        }
+        let looks_float = digits.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+        if looks_float && let Ok(f) = digits.parse::<f64>() {
+            return Ok(Value::from(f));
+        }
+        // Offset/local date-times, dates, times
+        if raw.starts_with(|c: char| c.is_ascii_digit()) && raw.contains([':', '-']) {
+            return Ok(Value::String(raw.to_string()));
+        }
+        err(self, format!("invalid value '{}'", raw))
+    }
+
+    // "1979-05-27 07:32:00": a space may separate date and time
+    fn space_in_datetime(&self, start: usize) -> bool {
+        let so_far = &self.src[start..self.pos];
+        let next = self.src[self.pos + 1..].chars().next();
+        so_far.len() == 10 && so_far.as_bytes()[4] == b'-' && next.is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            _ => self.scalar(),
+        }
+    }
+}
+
+fn insert(p: &Parser, table: &mut Map<String, Value>, key: &[String], value: Value) -> Result<()> {
+    let (last, parents) = key.split_last().expect("keys have at least one part");
+    let mut cur = table;
+    for part in parents {
+        let entry = cur.entry(part.clone()).or_insert_with(|| Value::Object(Map::new()));
+        let Value::Object(next) = entry else { return err(p, format!("key '{}' is not a table", part)) };
+        cur = next;
+    }
+    if cur.contains_key(last) {
+        return err(p, format!("duplicate key '{}'", key.join(".")));
+    }
+    cur.insert(last.clone(), value);
+    Ok(())
+}
+
+// Walk to the table a [header] or [[header]] names, creating it as needed
+fn open_table<'t>(
+    p: &Parser,
+    root: &'t mut Map<String, Value>,
+    path: &[String],
+    array: bool,
+) -> Result<&'t mut Map<String, Value>> {
+    let mut cur = root;
+    for (i, part) in path.iter().enumerate() {
+        let last = i + 1 == path.len();
+        let entry = cur.entry(part.clone()).or_insert_with(|| {
+            if last && array { Value::Array(Vec::new()) } else { Value::Object(Map::new()) }
+        });
+        if last && array {
+            let Value::Array(list) = entry else { return err(p, format!("'{}' is not an array of tables", path.join("."))) };
+            list.push(Value::Object(Map::new()));
+        }
+        cur = match entry {
+            Value::Object(t) => t,
+            // Intermediate [[x]] segments refer to the latest element
+            Value::Array(list) => match list.last_mut() {
+                Some(Value::Object(t)) => t,
+                _ => return err(p, format!("'{}' is not a table", part)),
+            },
+            _ => return err(p, format!("'{}' is not a table", part)),
+        };
+    }
+    Ok(cur)
+}
+
+pub fn parse(src: &str) -> Result<Value> {
+    let mut p = Parser { src, pos: 0, line: 1 };
+    let mut root = Map::new();
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        p.skip_all();
+        let Some(c) = p.peek() else { break };
+        if c == '[' {
+            let array = p.eat("[[");
+            if !array {
+                p.bump();
+            }
+            let path = p.key()?;
+            p.skip_ws();
+            if !p.eat(if array { "]]" } else { "]" }) {
+                return err(&p, "unterminated table header");
+            }
+            open_table(&p, &mut root, &path, array)?;
+            current = path;
+            p.end_of_line()?;
+            continue;
+        }
+
+        let key = p.key()?;
+        p.skip_ws();
+        if !p.eat("=") {
+            return err(&p, format!("expected '=' after key '{}'", key.join(".")));
+        }
+        p.skip_ws();
+        let value = p.value()?;
+        // Re-walking the header path without appending lands on the latest [[element]]
+        let table = if current.is_empty() {
+            &mut root
+        } else {
+            open_table(&p, &mut root, &current, false)?
+        };
+        insert(&p, table, &key, value)?;
+        p.end_of_line()?;
+    }
+    Ok(Value::Object(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strings_and_escapes() {
+        let doc = parse(
+            r#"
+basic = "tab\there \"quoted\" back\\slash \u00e9 \U0001F600"
+literal = 'C:\path\no escapes'
+"quoted key" = 1
+'single quoted' = 2
+"#,
+        )
+        .unwrap();
+        assert_eq!(doc["basic"], "tab\there \"quoted\" back\\slash \u{e9} \u{1F600}");
+        assert_eq!(doc["literal"], "C:\\path\\no escapes");
+        assert_eq!(doc["quoted key"], 1);
+        assert_eq!(doc["single quoted"], 2);
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let doc = parse(
+            "a = \"\"\"\nfirst\nsecond\"\"\"\n\
+             b = \"\"\"joined \\\n      here\"\"\"\n\
+             c = '''\nraw \\n kept\n'''\n",
+        )
+        .unwrap();
+        assert_eq!(doc["a"], "first\nsecond");
+        assert_eq!(doc["b"], "joined here");
+        assert_eq!(doc["c"], "raw \\n kept\n");
+    }
+
+    #[test]
+    fn numbers_booleans_and_arrays() {
+        let doc = parse(
+            r#"
+n = 1_000
+hex = 0xff
+neg = -0b101
+f = 6.5e-1
+t = true
+list = [
+  1, # a comment
+  "two",
+  [3],
+]
+nan = nan
+"#,
+        )
+        .unwrap();
+        assert_eq!(doc["n"], 1000);
+        assert_eq!(doc["hex"], 255);
+        assert_eq!(doc["neg"], -5);
+        assert_eq!(doc["f"], 0.65);
+        assert_eq!(doc["t"], true);
+        assert_eq!(doc["list"], json!([1, "two", [3]]));
+        assert_eq!(doc["nan"], "nan");
+    }
+
+    #[test]
+    fn tables_and_dotted_keys() {
+        let doc = parse(
+            r#"
+top.level = "x"
+site."a.b".c = 1
+
+[server]
+listen = "127.0.0.1:8080"
+limits = { max = 10, nested = { deep = true }, "odd key" = [] }
+
+[server.tls]
+cert = "c.pem"
+"#,
+        )
+        .unwrap();
+        assert_eq!(doc["top"]["level"], "x");
+        assert_eq!(doc["site"]["a.b"]["c"], 1);
+        assert_eq!(doc["server"]["listen"], "127.0.0.1:8080");
+        assert_eq!(doc["server"]["limits"], json!({ "max": 10, "nested": { "deep": true }, "odd key": [] }));
+        assert_eq!(doc["server"]["tls"]["cert"], "c.pem");
+    }
+
+    #[test]
+    fn arrays_of_tables() {
+        let doc = parse(
+            r#"
+[[rule]]
+id = "CVE-2099-0001"
+[rule.set]
+severity = "high"
+
+[[rule]]
+id = "CVE-2099-0002"
+tags = ["a"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc["rule"],
+            json!([{ "id": "CVE-2099-0001", "set": { "severity": "high" } }, { "id": "CVE-2099-0002", "tags": ["a"] }])
+        );
+    }
+
+    #[test]
+    fn datetimes_stay_strings() {
+        let doc = parse(
+            "spaced = 1979-05-27 07:32:00Z\n\
+             t = 1979-05-27T07:32:00-07:00\n\
+             day = 1979-05-27\n\
+             time = 07:32:00\n\
+             arr = [1979-05-27 07:32:00, 2000-01-01]\n\
+             after = 1979-05-27 # a date, then a comment\n",
+        )
+        .unwrap();
+        assert_eq!(doc["spaced"], "1979-05-27 07:32:00Z");
+        assert_eq!(doc["t"], "1979-05-27T07:32:00-07:00");
+        assert_eq!(doc["day"], "1979-05-27");
+        assert_eq!(doc["time"], "07:32:00");
+        assert_eq!(doc["arr"], json!(["1979-05-27 07:32:00", "2000-01-01"]));
+        assert_eq!(doc["after"], "1979-05-27");
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let fails = |src: &str, msg: &str| {
+            let e = parse(src).unwrap_err().to_string();
+            assert!(e.contains(msg), "{:?}: {}", src, e);
+        };
+        fails("a = 1\na = 2\n", "line 2: duplicate key 'a'");
+        fails("a = \"open\n", "newline in string");
+        fails("a = \"\"\"never closed", "unterminated string");
+        fails("a = \"\\q\"", "invalid escape \\q");
+        fails("a = \"\\uZZZZ\"", "invalid unicode escape");
+        fails("a = [1 2]", "expected ',' or ']' in array");
+        fails("a = { b = 1 c = 2 }", "expected ',' or '}' in inline table");
+        fails("[table\nx = 1", "unterminated table header");
+        fails("a = 1 b = 2", "unexpected 'b' after value");
+        fails("key", "expected '=' after key 'key'");
+        fails("a = \n", "expected a value");
+        fails("a = nope", "invalid value 'nope'");
+        fails("a = 0xZZ", "invalid integer '0xZZ'");
+        fails("a = 1\n[a.b]\n", "'a' is not a table");
+        fails("[x]\n[[x]]\n", "'x' is not an array of tables");
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{codex, snapshot, CanonicalItem};
+
+/* -------------------- Change trends -------------------- */
+/*
+Re-triage triggers over a window of runs, the ones a plain diff buries among
+thousands of description edits:
+
+  cvss jumps     the score rose by at least --min-cvss-jump (default 2.0)
+  kev added      the item was not KEV-listed at the start of the window, now is
+  newly scored   the item had no CVSS score (bucket "unknown"), now has one
+
+`trends --store DIR --window 30d` walks every run in the snapshot store (see
+snapshot.rs) from the last one before the window up to --until (default: now),
+and compares each item against how it looked at the start of the window, so
+5.0 -> 6.5 -> 8.0 over two runs still counts as one jump. Items first seen
+inside the window start from their first version. Each trigger is reported
+once per item, with the run it first fired in (`at`) and the values then.
+
+`trends --old a.json --new b.json` does the same for two snapshots, with no
+runs in between. Lists are in canonical ID order.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TrendsFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CvssJump {
+    pub id: String,
+    pub at: String,
+    pub old: f64,
+    pub new: f64,
+    pub old_bucket: String,
+    pub new_bucket: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KevAdded {
+    pub id: String,
+    pub at: String,
+    pub kev_date_added: Option<String>,
+    pub cvss: Option<f64>,
+    pub severity_bucket: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewlyScored {
+    pub id: String,
+    pub at: String,
+    pub cvss: f64,
+    pub severity_bucket: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrendReport {
+    pub since: String,
+    pub until: String,
+    pub runs: usize,
+    pub min_cvss_jump: f64,
+    pub cvss_jumps: Vec<CvssJump>,
+    pub kev_added: Vec<KevAdded>,
+    pub newly_scored: Vec<NewlyScored>,
+}
+
+/// What the triggers compare against: the item at the start of the window.
+struct Base {
+    cvss: Option<f64>,
+    bucket: String,
+    kev: bool,
+}
+
+impl Base {
+    fn of(item: &CanonicalItem) -> Self {
+        Base { cvss: item.cvss, bucket: item.severity_bucket.clone(), kev: item.kev }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Trigger {
+    Cvss,
+    Kev,
+    Scored,
+}
+
+struct Tracker {
+    min_cvss_jump: f64,
+    fired: HashSet<(String, Trigger)>,
+    report: TrendReport,
+}
+
+impl Tracker {
+    fn new(since: String, until: String, min_cvss_jump: f64) -> Self {
+        let report = TrendReport {
+            since,
+            until,
+            runs: 0,
+            min_cvss_jump,
+            cvss_jumps: Vec::new(),
+            kev_added: Vec::new(),
+            newly_scored: Vec::new(),
+        };
+        Tracker { min_cvss_jump, fired: HashSet::new(), report }
+    }
+
+    fn fire(&mut self, id: &str, trigger: Trigger) -> bool {
+        self.fired.insert((id.to_string(), trigger))
+    }
+
+    fn check(&mut self, base: &Base, now: &CanonicalItem, at: &str) {
+        if let (Some(old), Some(new)) = (base.cvss, now.cvss)
+            && new - old >= self.min_cvss_jump
+            && self.fire(&now.id, Trigger::Cvss)
+        {
+            self.report.cvss_jumps.push(CvssJump {
+                id: now.id.clone(),
+                at: at.to_string(),
+                old,
+                new,
+                old_bucket: base.bucket.clone(),
+                new_bucket: now.severity_bucket.clone(),
+            });
+        }
+        if !base.kev && now.kev && self.fire(&now.id, Trigger::Kev) {
+            self.report.kev_added.push(KevAdded {
+                id: now.id.clone(),
+                at: at.to_string(),
+                kev_date_added: now.kev_date_added.clone(),
+                cvss: now.cvss,
+                severity_bucket: now.severity_bucket.clone(),
+            });
+        }
+        if base.cvss.is_none()
+            && let Some(cvss) = now.cvss
+            && self.fire(&now.id, Trigger::Scored)
+        {
+            self.report.newly_scored.push(NewlyScored {
+                id: now.id.clone(),
+                at: at.to_string(),
+                cvss,
+                severity_bucket: now.severity_bucket.clone(),
+            });
+        }
+    }
+
+    fn finish(mut self) -> TrendReport {
+        let r = &mut self.report;
+        r.cvss_jumps.sort_by_cached_key(|c| codex::id_key(&c.id));
+        r.kev_added.sort_by_cached_key(|c| codex::id_key(&c.id));
+        r.newly_scored.sort_by_cached_key(|c| codex::id_key(&c.id));
+        self.report
+    }
+}
+
+/// Triggers between two snapshots.
+pub fn from_snapshots(
+    old: &[CanonicalItem],
+    new: &[CanonicalItem],
+    labels: (&str, &str),
+    min_cvss_jump: f64,
+) -> TrendReport {
+    let old_by_id: HashMap<&str, &CanonicalItem> = old.iter().map(|i| (i.id.as_str(), i)).collect();
+    let mut tracker = Tracker::new(labels.0.to_string(), labels.1.to_string(), min_cvss_jump);
+    tracker.report.runs = 2;
+    for item in new {
+        if let Some(o) = old_by_id.get(item.id.as_str()) {
+            tracker.check(&Base::of(o), item, labels.1);
+        }
+    }
+    tracker.finish()
+}
+
+/// Triggers over the store's runs between `since` and `until`.
+pub fn from_store(
+    store: &snapshot::Store,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    min_cvss_jump: f64,
+) -> Result<TrendReport> {
+    let stamp = |t: DateTime<Utc>| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let (since, until) = (stamp(since), stamp(until));
+    let runs: Vec<_> = store.runs()?.into_iter().filter(|r| r.taken_at <= until).collect();
+    // The window starts from the last run before it, or its first run if there is none
+    let start = runs.iter().rposition(|r| r.taken_at < since).unwrap_or(0);
+    let mut tracker = Tracker::new(since, until, min_cvss_jump);
+    let Some(first) = runs.get(start) else { return Ok(tracker.finish()) };
+    tracker.report.runs = runs.len() - start;
+
+    let base_hashes = store.run_items(first)?;
+    let mut prev = base_hashes.clone();
+    // Start-of-window versions, loaded only for items that change
+    let mut bases: BTreeMap<String, Base> = BTreeMap::new();
+    for run in &runs[start + 1..] {
+        let current = store.run_items(run)?;
+        for (id, hash) in &current {
+            if prev.get(id) == Some(hash) {
+                continue;
+            }
+            let now = store.object(hash)?;
+            if !bases.contains_key(id) {
+                let base = match base_hashes.get(id) {
+                    Some(h) => Base::of(&store.object(h)?),
+                    None => Base::of(&now),
+                };
+                bases.insert(id.clone(), base);
+            }
+            tracker.check(&bases[id], &now, &run.taken_at);
+        }
+        prev = current;
+    }
+    Ok(tracker.finish())
+}
+
+fn score(s: Option<f64>) -> String {
+    s.map(|v| v.to_string()).unwrap_or_else(|| "n/a".to_string())
+}
+
+pub fn render_markdown(r: &TrendReport) -> String {
+    let mut out = Vec::new();
+    out.push("# Codex trends".to_string());
+    out.push(String::new());
+    out.push(format!("- Window: {} → {} ({} runs)", r.since, r.until, r.runs));
+    out.push(format!("- CVSS jumps (≥ {}): {}", r.min_cvss_jump, r.cvss_jumps.len()));
+    out.push(format!("- Newly KEV-listed: {}", r.kev_added.len()));
+    out.push(format!("- Newly scored: {}", r.newly_scored.len()));
+
+    let mut list = |title: &str, lines: Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        out.push(String::new());
+        out.push(format!("## {}", title));
+        out.extend(lines);
+    };
+
+    list(
+        "Newly KEV-listed",
+        r.kev_added
+            .iter()
+            .map(|k| {
+                let added = k.kev_date_added.as_deref().map(|d| format!(", added {}", d)).unwrap_or_default();
+                format!("- {}: {} ({}{}) — {}", k.id, k.severity_bucket, score(k.cvss), added, k.at)
+            })
+            .collect(),
+    );
+    list(
+        "CVSS jumps",
+        r.cvss_jumps
+            .iter()
+            .map(|c| format!("- {}: {} → {} ({} → {}) — {}", c.id, c.old, c.new, c.old_bucket, c.new_bucket, c.at))
+            .collect(),
+    );
+    list(
+        "Newly scored",
+        r.newly_scored.iter().map(|n| format!("- {}: {} ({}) — {}", n.id, n.cvss, n.severity_bucket, n.at)).collect(),
+    );
+
+    out.push(String::new());
+    out.join("\n")
+}
@@ -0,0 +1,662 @@
+use anyhow::Result;
+
+use crate::CanonicalItem;
+
+#[cfg(unix)]
+use anyhow::{Context, bail};
+#[cfg(unix)]
+use std::io::{Read, Write};
+
+/* -------------------- Terminal browser -------------------- */
+/*
+`tui --input items.json` browses a snapshot without leaving the terminal: a list
+on top, the selected item's detail below (description, CVSS breakdown, KEV
+fields, refs).
+
+  j/k, arrows     move            PgUp/PgDn, g/G   page, first/last
+  /               text search (ID, vendor, product, title, description)
+  f               filter expression, as for `query --filter`
+  s / r           cycle sort key (ID, published, CVSS, severity, KEV added) / reverse
+  K               KEV only
+  u/d             scroll the detail pane
+  y               copy the CVE ID (OSC 52: works over ssh, in terminals that allow it)
+  o 1-9           open that ref in the browser (xdg-open / open)
+  Esc on empty    clear search and filter;  q  quit
+
+It draws with plain ANSI escapes in raw mode (termios), no curses. Unix only.
+*/
+
+#[cfg(unix)]
+mod term {
+    use anyhow::{Result, bail};
+    use std::io::Write;
+
+    /// Raw mode on the alternate screen until dropped (also on panic unwind).
+    pub struct Raw {
+        saved: libc::termios,
+    }
+
+    impl Raw {
+        pub fn enter() -> Result<Self> {
+            // SAFETY: termios is plain data; tcgetattr fills it or fails.
+            let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+                bail!("tui needs a terminal on stdin");
+            }
+            let mut raw = saved;
+            // SAFETY: cfmakeraw/tcsetattr only touch the struct and the tty.
+            unsafe {
+                libc::cfmakeraw(&mut raw);
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw);
+            }
+            let mut out = std::io::stdout();
+            let _ = write!(out, "\x1b[?1049h\x1b[?25l");
+            let _ = out.flush();
+            Ok(Raw { saved })
+        }
+    }
+
+    impl Drop for Raw {
+        fn drop(&mut self) {
+            let mut out = std::io::stdout();
+            let _ = write!(out, "\x1b[0m\x1b[?25h\x1b[?1049l");
+            let _ = out.flush();
+            // SAFETY: restores the attributes read in enter().
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved) };
+        }
+    }
+
+    /// (columns, rows)
+    pub fn size() -> (usize, usize) {
+        // SAFETY: TIOCGWINSZ writes a winsize into ws.
+        let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) } == 0;
+        if ok && ws.ws_col > 0 && ws.ws_row > 0 { (ws.ws_col as usize, ws.ws_row as usize) } else { (80, 24) }
+    }
+
+    /// Whether stdin has input within `ms` milliseconds.
+    pub fn ready(ms: i32) -> bool {
+        let mut fd = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+        // SAFETY: one valid pollfd.
+        unsafe { libc::poll(&mut fd, 1, ms) > 0 }
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, PartialEq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Backspace,
+    Esc,
+}
+
+#[cfg(unix)]
+fn read_key() -> Result<Option<Key>> {
+    let mut stdin = std::io::stdin().lock();
+    let mut byte = [0u8; 1];
+    let mut next = |stdin: &mut std::io::StdinLock| -> Result<u8> {
+        stdin.read_exact(&mut byte).context("Failed to read the terminal")?;
+        Ok(byte[0])
+    };
+    Ok(Some(match next(&mut stdin)? {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        // A lone ESC is the key; one followed at once by more bytes is a sequence
+        0x1b if !term::ready(30) => Key::Esc,
+        0x1b => {
+            let intro = next(&mut stdin)?;
+            let code = next(&mut stdin)?;
+            match (intro, code) {
+                (b'[' | b'O', b'A') => Key::Up,
+                (b'[' | b'O', b'B') => Key::Down,
+                (b'[' | b'O', b'H') => Key::Home,
+                (b'[' | b'O', b'F') => Key::End,
+                (b'[', b'1'..=b'8') => {
+                    // ESC [ n ~
+                    let mut n = code;
+                    while n != b'~' && term::ready(30) {
+                        n = next(&mut stdin)?;
+                    }
+                    match code {
+                        b'5' => Key::PageUp,
+                        b'6' => Key::PageDown,
+                        b'1' | b'7' => Key::Home,
+                        b'4' | b'8' => Key::End,
+                        _ => return Ok(None),
+                    }
+                }
+                _ => return Ok(None),
+            }
+        }
+        b if b < 0x20 => return Ok(None),
+        b => {
+            // UTF-8: the lead byte says how many follow
+            let len = match b {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut buf = vec![b];
+            for _ in 1..len {
+                buf.push(next(&mut stdin)?);
+            }
+            match String::from_utf8(buf).ok().and_then(|s| s.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => return Ok(None),
+            }
+        }
+    }))
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    Id,
+    Published,
+    Cvss,
+    Severity,
+    KevAdded,
+}
+
+#[cfg(unix)]
+impl SortKey {
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Id => "id",
+            SortKey::Published => "published",
+            SortKey::Cvss => "cvss",
+            SortKey::Severity => "severity",
+            SortKey::KevAdded => "kev added",
+        }
+    }
+
+    fn next(self) -> SortKey {
+        match self {
+            SortKey::Id => SortKey::Published,
+            SortKey::Published => SortKey::Cvss,
+            SortKey::Cvss => SortKey::Severity,
+            SortKey::Severity => SortKey::KevAdded,
+            SortKey::KevAdded => SortKey::Id,
+        }
+    }
+}
+
+#[cfg(unix)]
+#[derive(PartialEq)]
+enum Prompt {
+    None,
+    Search,
+    Filter,
+    Open,
+}
+
+#[cfg(unix)]
+struct App<'a> {
+    items: &'a [CanonicalItem],
+    // items as JSON for filter expressions, built on first use
+    values: Option<Vec<serde_json::Value>>,
+    view: Vec<usize>,
+    cursor: usize,
+    top: usize,
+    detail_scroll: usize,
+    search: String,
+    filter: String,
+    kev_only: bool,
+    sort: SortKey,
+    reverse: bool,
+    prompt: Prompt,
+    input: String,
+    status: String,
+}
+
+#[cfg(unix)]
+impl<'a> App<'a> {
+    fn new(items: &'a [CanonicalItem]) -> Self {
+        let mut app = App {
+            items,
+            values: None,
+            view: Vec::new(),
+            cursor: 0,
+            top: 0,
+            detail_scroll: 0,
+            search: String::new(),
+            filter: String::new(),
+            kev_only: false,
+            sort: SortKey::Id,
+            reverse: false,
+            prompt: Prompt::None,
+            input: String::new(),
+            status: String::new(),
+        };
+        app.refresh();
+        app
+    }
+
+    fn selected(&self) -> Option<&'a CanonicalItem> {
+        self.view.get(self.cursor).map(|&i| &self.items[i])
+    }
+
+    /// Rebuild the view from search, filter, KEV and sort, keeping the selection if it survives.
+    fn refresh(&mut self) {
+        let keep = self.selected().map(|i| i.id.clone());
+        let expr = match self.filter.trim() {
+            "" => None,
+            text => match crate::query::parse(text) {
+                Ok(e) => Some(e),
+                Err(e) => {
+                    self.status = format!("filter: {:#}", e);
+                    self.filter.clear();
+                    None
+                }
+            },
+        };
+        if expr.is_some() && self.values.is_none() {
+            self.values = Some(self.items.iter().map(|i| serde_json::to_value(i).unwrap_or_default()).collect());
+        }
+        let needle = self.search.to_lowercase();
+        let items = self.items;
+        let mut view: Vec<usize> = (0..items.len())
+            .filter(|&i| {
+                let item = &items[i];
+                (!self.kev_only || item.kev)
+                    && (needle.is_empty()
+                        || [
+                            Some(&item.id),
+                            item.vendor.as_ref(),
+                            item.product.as_ref(),
+                            item.title.as_ref(),
+                            Some(&item.short_desc),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .any(|s| s.to_lowercase().contains(&needle)))
+                    && expr.as_ref().is_none_or(|e| self.values.as_ref().is_some_and(|v| crate::query::eval(e, &v[i])))
+            })
+            .collect();
+
+        // Dates and scores sort newest/highest first; missing ones last
+        let key = |i: &usize| -> (bool, String) {
+            let item = &items[*i];
+            match self.sort {
+                SortKey::Id => (false, String::new()),
+                SortKey::Published => (item.published.is_none(), item.published.clone().unwrap_or_default()),
+                SortKey::Cvss => (item.cvss.is_none(), format!("{:06.2}", item.cvss.unwrap_or(0.0))),
+                SortKey::Severity => (false, format!("{:03}", crate::severity::rank(&item.severity_bucket))),
+                SortKey::KevAdded => (item.kev_date_added.is_none(), item.kev_date_added.clone().unwrap_or_default()),
+            }
+        };
+        if self.sort == SortKey::Id {
+            view.sort_by_cached_key(|&i| crate::codex::id_key(&items[i].id));
+        } else {
+            view.sort_by_cached_key(|i| {
+                let (missing, k) = key(i);
+                (missing, std::cmp::Reverse(k), crate::codex::id_key(&items[*i].id))
+            });
+        }
+        if self.reverse {
+            view.reverse();
+        }
+
+        self.view = view;
+        self.cursor = keep.and_then(|id| self.view.iter().position(|&i| items[i].id == id)).unwrap_or(0);
+        self.detail_scroll = 0;
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        let cursor = cursor.min(self.view.len().saturating_sub(1));
+        if cursor != self.cursor {
+            self.detail_scroll = 0;
+        }
+        self.cursor = cursor;
+    }
+}
+
+#[cfg(unix)]
+fn truncate(s: &str, width: usize) -> String {
+    let s: String = s.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    if s.chars().count() <= width {
+        return s;
+    }
+    let mut out: String = s.chars().take(width.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+#[cfg(unix)]
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(unix)]
+fn severity_color(bucket: &str) -> &'static str {
+    match bucket {
+        "critical" => "\x1b[1;31m",
+        "high" => "\x1b[33m",
+        "medium" => "\x1b[36m",
+        _ => "",
+    }
+}
+
+#[cfg(unix)]
+fn detail_lines(item: &CanonicalItem, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let head = match &item.title {
+        Some(t) => format!("\x1b[1m{}\x1b[0m  {}", item.id, t),
+        None => format!("\x1b[1m{}\x1b[0m", item.id),
+    };
+    lines.push(head);
+    let who = [item.vendor.as_deref(), item.product.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" / ");
+    lines.push(format!(
+        "{}{}\x1b[0m  cvss {}  published {}  modified {}{}",
+        severity_color(&item.severity_bucket),
+        item.severity_bucket,
+        item.cvss.map_or("-".to_string(), |c| format!("{:.1}", c)),
+        item.published.as_deref().unwrap_or("-"),
+        item.last_modified.as_deref().unwrap_or("-"),
+        if who.is_empty() { String::new() } else { format!("  {}", who) }
+    ));
+    if let Some(c) = &item.cvss_details {
+        let mut parts = vec![format!("CVSS {}", c.version)];
+        for (label, v) in [
+            ("AV", &c.attack_vector),
+            ("AC", &c.attack_complexity),
+            ("PR", &c.privileges_required),
+            ("UI", &c.user_interaction),
+            ("S", &c.scope),
+            ("Au", &c.authentication),
+            ("C", &c.confidentiality_impact),
+            ("I", &c.integrity_impact),
+            ("A", &c.availability_impact),
+        ] {
+            if let Some(v) = v {
+                parts.push(format!("{}:{}", label, v.to_lowercase()));
+            }
+        }
+        if let Some(src) = &c.source {
+            parts.push(format!("({})", src));
+        }
+        lines.push(parts.join("  "));
+        if let Some(v) = &c.vector {
+            lines.push(format!("\x1b[2m{}\x1b[0m", v));
+        }
+    }
+    if item.kev {
+        let mut kev = vec!["\x1b[1;31mKEV\x1b[0m".to_string()];
+        if let Some(d) = &item.kev_date_added {
+            kev.push(format!("added {}", d));
+        }
+        if let Some(d) = &item.kev_due_date {
+            kev.push(format!("due {}", d));
+        }
+        match item.ransomware_known {
+            Some(true) => kev.push("ransomware: known".to_string()),
+            Some(false) => kev.push("ransomware: unknown".to_string()),
+            None => {}
+        }
+        lines.push(kev.join("  "));
+        if let Some(action) = &item.kev_required_action {
+            lines.extend(wrap(&format!("Required action: {}", action), width));
+        }
+    }
+    lines.push(String::new());
+    lines.extend(wrap(&item.short_desc, width));
+    if !item.refs.is_empty() {
+        lines.push(String::new());
+        for (n, r) in item.refs.iter().enumerate() {
+            let num = if n < 9 { format!("{}", n + 1) } else { " ".to_string() };
+            lines.push(format!("{} {:<14} {}", num, r.kind.as_str(), r.url));
+        }
+    }
+    lines
+}
+
+#[cfg(unix)]
+fn draw(app: &mut App, out: &mut impl Write) -> Result<()> {
+    let (cols, rows) = term::size();
+    let list_rows = ((rows.saturating_sub(2)) * 45 / 100).max(3);
+    let detail_rows = rows.saturating_sub(list_rows + 2);
+
+    if app.cursor < app.top {
+        app.top = app.cursor;
+    } else if app.cursor >= app.top + list_rows {
+        app.top = app.cursor + 1 - list_rows;
+    }
+
+    let mut frame = String::from("\x1b[H");
+    for row in 0..list_rows {
+        frame.push_str("\x1b[2K");
+        if let Some(&i) = app.view.get(app.top + row) {
+            let item = &app.items[i];
+            let who = item.vendor.as_deref().or(item.product.as_deref()).unwrap_or("");
+            let line = format!(
+                "{:<16} {:<8} {:>4} {:<3} {:<18} {}",
+                truncate(&item.id, 16),
+                truncate(&item.severity_bucket, 8),
+                item.cvss.map_or(String::new(), |c| format!("{:.1}", c)),
+                if item.kev { "KEV" } else { "" },
+                truncate(who, 18),
+                item.title.as_deref().unwrap_or(&item.short_desc)
+            );
+            let line = truncate(&line, cols);
+            if app.top + row == app.cursor {
+                frame.push_str(&format!("\x1b[7m{:<width$}\x1b[0m", line, width = cols));
+            } else {
+                frame.push_str(&format!("{}{}\x1b[0m", severity_color(&item.severity_bucket), line));
+            }
+        }
+        frame.push_str("\r\n");
+    }
+
+    let mut filters = vec![format!("sort: {}{}", app.sort.label(), if app.reverse { " (reversed)" } else { "" })];
+    if !app.search.is_empty() {
+        filters.push(format!("search: {}", app.search));
+    }
+    if !app.filter.is_empty() {
+        filters.push(format!("filter: {}", app.filter));
+    }
+    if app.kev_only {
+        filters.push("KEV only".to_string());
+    }
+    let bar = format!(
+        " {}/{} of {}  {} ",
+        if app.view.is_empty() { 0 } else { app.cursor + 1 },
+        app.view.len(),
+        app.items.len(),
+        filters.join("  ")
+    );
+    frame.push_str(&format!("\x1b[2K\x1b[1;44m{:<width$}\x1b[0m\r\n", truncate(&bar, cols), width = cols));
+
+    let detail = app.selected().map(|i| detail_lines(i, cols.max(20))).unwrap_or_default();
+    app.detail_scroll = app.detail_scroll.min(detail.len().saturating_sub(1));
+    for row in 0..detail_rows {
+        frame.push_str("\x1b[2K");
+        if let Some(line) = detail.get(app.detail_scroll + row) {
+            // Escapes don't take columns, so only plain lines are cut to width
+            if line.contains('\x1b') {
+                frame.push_str(line);
+            } else {
+                frame.push_str(&truncate(line, cols));
+            }
+            frame.push_str("\x1b[0m");
+        }
+        frame.push_str("\r\n");
+    }
+
+    frame.push_str("\x1b[2K");
+    let footer = match app.prompt {
+        Prompt::Search => format!("/{}", app.input),
+        Prompt::Filter => format!("filter: {}", app.input),
+        Prompt::Open => "open ref 1-9: ".to_string(),
+        Prompt::None if !app.status.is_empty() => app.status.clone(),
+        Prompt::None => {
+            "j/k move  / search  f filter  s sort  r reverse  K kev  y copy id  o open ref  q quit".to_string()
+        }
+    };
+    frame.push_str(&format!("\x1b[2m{}\x1b[0m", truncate(&footer, cols)));
+    out.write_all(frame.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_osc52(text: &str, out: &mut impl Write) -> Result<()> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut b64 = String::new();
+    for chunk in text.as_bytes().chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                b64.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                b64.push('=');
+            }
+        }
+    }
+    write!(out, "\x1b]52;c;{}\x07", b64)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn open_url(url: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    std::process::Command::new(opener)
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("{} not available", opener))?;
+    Ok(())
+}
+
+/// Browse `items` until the user quits.
+#[cfg(unix)]
+pub fn run(items: &[CanonicalItem]) -> Result<()> {
+    if items.is_empty() {
+        bail!("nothing to browse: the snapshot has no items");
+    }
+    let mut app = App::new(items);
+    let _raw = term::Raw::enter()?;
+    let mut out = std::io::stdout();
+    let mut size = term::size();
+    draw(&mut app, &mut out)?;
+    loop {
+        // Redraw on resize as well as on input
+        if !term::ready(250) {
+            if term::size() != size {
+                size = term::size();
+                draw(&mut app, &mut out)?;
+            }
+            continue;
+        }
+        let Some(key) = read_key()? else { continue };
+        if app.prompt != Prompt::None {
+            match (&app.prompt, key) {
+                (Prompt::Open, Key::Char(c @ '1'..='9')) => {
+                    app.prompt = Prompt::None;
+                    let n = c as usize - '1' as usize;
+                    app.status = match app.selected().and_then(|i| i.refs.get(n)) {
+                        Some(r) => match open_url(&r.url) {
+                            Ok(()) => format!("opened {}", r.url),
+                            Err(e) => format!("{:#}", e),
+                        },
+                        None => format!("no ref {}", n + 1),
+                    };
+                }
+                (_, Key::Esc) | (Prompt::Open, _) => app.prompt = Prompt::None,
+                (_, Key::Enter) => {
+                    let text = std::mem::take(&mut app.input);
+                    match std::mem::replace(&mut app.prompt, Prompt::None) {
+                        Prompt::Search => app.search = text,
+                        Prompt::Filter => app.filter = text,
+                        _ => {}
+                    }
+                    app.refresh();
+                }
+                (_, Key::Backspace) => {
+                    app.input.pop();
+                }
+                (_, Key::Char(c)) => app.input.push(c),
+                _ => {}
+            }
+            draw(&mut app, &mut out)?;
+            continue;
+        }
+
+        app.status.clear();
+        let page = (term::size().1.saturating_sub(2) * 45 / 100).max(3);
+        match key {
+            Key::Char('q') => break,
+            Key::Char('j') | Key::Down => app.move_to(app.cursor + 1),
+            Key::Char('k') | Key::Up => app.move_to(app.cursor.saturating_sub(1)),
+            Key::PageDown => app.move_to(app.cursor + page),
+            Key::PageUp => app.move_to(app.cursor.saturating_sub(page)),
+            Key::Char('g') | Key::Home => app.move_to(0),
+            Key::Char('G') | Key::End => app.move_to(usize::MAX),
+            Key::Char('d') => app.detail_scroll += 1,
+            Key::Char('u') => app.detail_scroll = app.detail_scroll.saturating_sub(1),
+            Key::Char('/') => {
+                app.input = app.search.clone();
+                app.prompt = Prompt::Search;
+            }
+            Key::Char('f') => {
+                app.input = app.filter.clone();
+                app.prompt = Prompt::Filter;
+            }
+            Key::Char('o') => app.prompt = Prompt::Open,
+            Key::Char('s') => {
+                app.sort = app.sort.next();
+                app.refresh();
+            }
+            Key::Char('r') => {
+                app.reverse = !app.reverse;
+                app.refresh();
+            }
+            Key::Char('K') => {
+                app.kev_only = !app.kev_only;
+                app.refresh();
+            }
+            Key::Char('y') => {
+                if let Some(item) = app.selected() {
+                    copy_osc52(&item.id, &mut out)?;
+                    app.status = format!("copied {}", item.id);
+                }
+            }
+            Key::Esc if !app.search.is_empty() || !app.filter.is_empty() => {
+                app.search.clear();
+                app.filter.clear();
+                app.refresh();
+            }
+            _ => {}
+        }
+        draw(&mut app, &mut out)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_items: &[CanonicalItem]) -> Result<()> {
+    anyhow::bail!("tui is only supported on Unix terminals")
+}
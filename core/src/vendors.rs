@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{input, CanonicalItem};
+
+/* -------------------- Vendor / product normalization -------------------- */
+/*
+KEV's vendorProject/product are free text: "Microsoft" and "Microsoft Corporation",
+"VMware" and "VMware Inc." land in different groups in every by-vendor report.
+This pass maps them onto one display name. The bundled dictionary lives in
+core/mappings/vendor_aliases.json; --vendor-aliases adds a file of the same shape
+on top (its entries win):
+
+  {
+    "vendors":  { "<canonical>": { "cpe": "<cpe vendor token>", "aliases": ["..."] } },
+    "products": { "<canonical vendor>": { "<canonical product>": ["alias", ...] } }
+  }
+
+Matching ignores case, punctuation, spacing and corporate suffixes (Inc., Corp.,
+Ltd, GmbH, ...), so "Red Hat, Inc." finds "Red Hat" without listing it. Spellings
+the dictionary doesn't know are still grouped by that key; the most common
+spelling in the run names the group.
+
+With --cpe-dictionary (NVD CPE dictionary, JSON from the 2.0 API or the XML feed)
+every resulting vendor is checked against the dictionary's vendor tokens; the
+ones that don't match are reported along with the token their items' CPEs use,
+which is usually the alias entry to add.
+*/
+
+const BUNDLED_ALIASES: &str = include_str!("../mappings/vendor_aliases.json");
+
+const CORPORATE_SUFFIXES: &[&str] = &[
+    "inc", "incorporated", "corp", "corporation", "co", "company", "ltd", "limited", "llc", "gmbh", "ag", "sa",
+    "se", "plc", "bv", "nv", "oy", "ab", "pty",
+];
+
+#[derive(Debug, Deserialize)]
+struct VendorEntry {
+    #[serde(default)]
+    cpe: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    vendors: BTreeMap<String, VendorEntry>,
+    #[serde(default)]
+    products: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Default)]
+pub struct VendorAliases {
+    vendors: HashMap<String, String>,                 // vendor key -> canonical vendor
+    cpe: HashMap<String, String>,                     // canonical vendor -> CPE vendor token
+    products: HashMap<(String, String), String>,      // (canonical vendor, product key) -> canonical product
+}
+
+// Lowercase alphanumerics only, leading "the" and trailing corporate suffixes dropped
+fn vendor_key(s: &str) -> String {
+    let lower = s.to_lowercase();
+    let mut tokens: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).collect();
+    if tokens.len() > 1 && tokens[0] == "the" {
+        tokens.remove(0);
+    }
+    while tokens.len() > 1 && tokens.last().is_some_and(|t| CORPORATE_SUFFIXES.contains(t)) {
+        tokens.pop();
+    }
+    tokens.concat()
+}
+
+// Product names keep their suffixes but lose a leading vendor name ("Microsoft Exchange")
+fn product_key(vendor: &str, s: &str) -> String {
+    let key: String = s.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+    let vendor = vendor_key(vendor);
+    match key.strip_prefix(vendor.as_str()) {
+        Some(rest) if !rest.is_empty() && !vendor.is_empty() => rest.to_string(),
+        _ => key,
+    }
+}
+
+impl VendorAliases {
+    fn add_file(&mut self, file: AliasFile) {
+        for (canonical, entry) in file.vendors {
+            self.vendors.insert(vendor_key(&canonical), canonical.clone());
+            for alias in &entry.aliases {
+                self.vendors.insert(vendor_key(alias), canonical.clone());
+            }
+            if let Some(token) = entry.cpe {
+                self.cpe.insert(canonical, token.trim().to_lowercase());
+            }
+        }
+        for (vendor, products) in file.products {
+            for (canonical, aliases) in products {
+                for name in std::iter::once(&canonical).chain(&aliases) {
+                    self.products.insert((vendor.clone(), product_key(&vendor, name)), canonical.clone());
+                }
+            }
+        }
+    }
+
+    pub fn bundled() -> Result<Self> {
+        let file: AliasFile = serde_json::from_str(BUNDLED_ALIASES).context("Bundled vendor aliases are invalid")?;
+        let mut aliases = Self::default();
+        aliases.add_file(file);
+        Ok(aliases)
+    }
+
+    /// Layer a user alias file over what is loaded already.
+    pub fn extend(&mut self, path: &Path) -> Result<()> {
+        let bytes = input::read_input(path)?;
+        let file: AliasFile = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse vendor aliases: {}", path.display()))?;
+        self.add_file(file);
+        Ok(())
+    }
+
+    fn cpe_token(&self, vendor: &str) -> String {
+        match self.cpe.get(vendor) {
+            Some(token) => token.clone(),
+            None => vendor.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("_"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NormalizeStats {
+    pub vendors_changed: usize,
+    pub products_changed: usize,
+    pub vendor_groups: usize, // distinct vendors afterwards
+}
+
+// Most common spelling per key. Ties prefer mixed case over SHOUTING, then the shorter
+// spelling, then the lexicographically smallest so runs are stable
+fn pick_spellings(counts: HashMap<(String, String), HashMap<String, usize>>) -> HashMap<(String, String), String> {
+    let rank = |(s, n): &(String, usize)| (*n, s.chars().any(char::is_lowercase), std::cmp::Reverse(s.len()));
+    counts
+        .into_iter()
+        .map(|(key, spellings)| {
+            let best = spellings
+                .into_iter()
+                .max_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| b.0.cmp(&a.0)))
+                .map(|(s, _)| s)
+                .unwrap_or_default();
+            (key, best)
+        })
+        .collect()
+}
+
+/// Rewrite item.vendor / item.product to canonical names.
+pub fn normalize(items: &mut [CanonicalItem], aliases: &VendorAliases) -> NormalizeStats {
+    let mut stats = NormalizeStats::default();
+
+    // Vendors: dictionary first, then the run's own most common spelling
+    let mut vendor_counts: HashMap<(String, String), HashMap<String, usize>> = HashMap::new();
+    for item in items.iter() {
+        if let Some(v) = item.vendor.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            let key = vendor_key(v);
+            if !aliases.vendors.contains_key(&key) {
+                *vendor_counts.entry((String::new(), key)).or_default().entry(v.to_string()).or_default() += 1;
+            }
+        }
+    }
+    let vendor_spellings = pick_spellings(vendor_counts);
+
+    for item in items.iter_mut() {
+        let Some(v) = item.vendor.as_deref().map(str::trim).filter(|v| !v.is_empty()) else { continue };
+        let key = vendor_key(v);
+        let canonical = match aliases.vendors.get(&key) {
+            Some(c) => c.clone(),
+            None => vendor_spellings.get(&(String::new(), key)).cloned().unwrap_or_else(|| v.to_string()),
+        };
+        if item.vendor.as_deref() != Some(canonical.as_str()) {
+            item.vendor = Some(canonical);
+            stats.vendors_changed += 1;
+        }
+    }
+
+    // Products, within their (now canonical) vendor
+    let mut product_counts: HashMap<(String, String), HashMap<String, usize>> = HashMap::new();
+    for item in items.iter() {
+        let vendor = item.vendor.clone().unwrap_or_default();
+        if let Some(p) = item.product.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            let key = (vendor.clone(), product_key(&vendor, p));
+            if !aliases.products.contains_key(&key) {
+                *product_counts.entry(key).or_default().entry(p.to_string()).or_default() += 1;
+            }
+        }
+    }
+    let product_spellings = pick_spellings(product_counts);
+
+    for item in items.iter_mut() {
+        let vendor = item.vendor.clone().unwrap_or_default();
+        let Some(p) = item.product.as_deref().map(str::trim).filter(|p| !p.is_empty()) else { continue };
+        let key = (vendor.clone(), product_key(&vendor, p));
+        let canonical = aliases
+            .products
+            .get(&key)
+            .or_else(|| product_spellings.get(&key))
+            .cloned()
+            .unwrap_or_else(|| p.to_string());
+        if item.product.as_deref() != Some(canonical.as_str()) {
+            item.product = Some(canonical);
+            stats.products_changed += 1;
+        }
+    }
+
+    stats.vendor_groups = items.iter().filter_map(|i| i.vendor.as_deref()).collect::<HashSet<_>>().len();
+    stats
+}
+
+/* -------------------- CPE dictionary cross-check -------------------- */
+
+// Vendor field of a cpe:2.3:part:vendor:... or cpe:/part:vendor:... name
+fn cpe_vendor(name: &str) -> Option<&str> {
+    let rest = name.strip_prefix("cpe:2.3:").or_else(|| name.strip_prefix("cpe:/"))?;
+    let mut fields = rest.splitn(3, ':');
+    fields.next()?; // part
+    fields.next().filter(|v| !v.is_empty() && *v != "*" && *v != "-")
+}
+
+/// Vendor tokens (by key) from an NVD CPE dictionary, any format that spells out CPE names.
+pub fn load_cpe_vendors(path: &Path) -> Result<HashSet<String>> {
+    let reader = BufReader::new(input::open_input(path)?);
+    let mut vendors = HashSet::new();
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("Failed to read CPE dictionary: {}", path.display()))?;
+        let mut rest = line.as_str();
+        while let Some(at) = rest.find("cpe:") {
+            rest = &rest[at..];
+            let end = rest.find(|c: char| c == '"' || c == '<' || c.is_whitespace()).unwrap_or(rest.len());
+            if let Some(v) = cpe_vendor(&rest[..end]) {
+                vendors.insert(vendor_key(&v.replace('\\', "")));
+            }
+            rest = &rest[end.max(4)..];
+        }
+    }
+    Ok(vendors)
+}
+
+#[derive(Debug)]
+pub struct UnknownVendor {
+    pub vendor: String,
+    pub items: usize,
+    pub cpe_hint: Option<String>, // most common CPE vendor token on its items
+}
+
+/// Vendors whose CPE token isn't in the dictionary, most items first.
+pub fn cross_check(items: &[CanonicalItem], aliases: &VendorAliases, cpe_vendors: &HashSet<String>) -> Vec<UnknownVendor> {
+    let mut by_vendor: BTreeMap<&str, (usize, HashMap<&str, usize>)> = BTreeMap::new();
+    for item in items {
+        let Some(vendor) = item.vendor.as_deref() else { continue };
+        let entry = by_vendor.entry(vendor).or_default();
+        entry.0 += 1;
+        for cpe in &item.affected {
+            if let Some(token) = cpe_vendor(&cpe.cpe23_uri) {
+                *entry.1.entry(token).or_default() += 1;
+            }
+        }
+    }
+
+    let mut unknown: Vec<UnknownVendor> = by_vendor
+        .into_iter()
+        .filter(|(vendor, _)| !cpe_vendors.contains(&vendor_key(&aliases.cpe_token(vendor))))
+        .map(|(vendor, (count, tokens))| UnknownVendor {
+            vendor: vendor.to_string(),
+            items: count,
+            cpe_hint: tokens
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(t, _)| t.to_string()),
+        })
+        .collect();
+    unknown.sort_by(|a, b| b.items.cmp(&a.items).then_with(|| a.vendor.cmp(&b.vendor)));
+    unknown
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{cpe::AffectedCpe, CanonicalItem};
+
+/* -------------------- Structured affected versions -------------------- */
+/*
+item.affected keeps NVD's cpeMatch entries as they are; affected_versions is
+the same knowledge (plus OSV's, see osv.rs) shaped for a package matcher, so a
+scanner compares versions without parsing CPE names:
+
+  { "ecosystem": "cpe", "product": "apache:log4j", "scheme": "semver",
+    "ranges": [{ "gte": "2.0.1", "lt": "2.15.0" }], "versions": [], "source": "nvd" }
+
+- ecosystem + product is the key: "cpe" and vendor:product from the CPE name,
+  "cna" and the CNA's vendor:product (cvelist.rs), or the OSV ecosystem
+  ("PyPI", "npm", "Debian:12") and package name
+- ranges are OR-ed; within one, every bound given must hold. A range with no
+  bounds at all means every version. OSV's introduced "0" becomes no lower bound
+- versions lists exact affected versions (a CPE naming one, OSV's versions[])
+- scheme says how to compare: "semver" when every version and bound is dotted
+  numbers (optional leading v, -pre/+build suffixes), "ecosystem" for OSV
+  ECOSYSTEM ranges (that ecosystem's own ordering), "opaque" otherwise. GIT
+  ranges name commits, not versions, and are skipped
+
+Only vulnerable CPEs count; one entry per product key and source. derive()
+rebuilds the nvd entries from item.affected and keeps the rest, so it can run
+again after anything that changes item.affected.
+*/
+
+/// Version bounds; a missing bound doesn't constrain.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VersionRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<String>,
+}
+
+impl VersionRange {
+    fn bounds(&self) -> impl Iterator<Item = &str> {
+        [&self.gt, &self.gte, &self.lt, &self.lte].into_iter().flatten().map(String::as_str)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AffectedVersions {
+    pub ecosystem: String,              // "cpe", or the OSV ecosystem
+    pub product: String,                // vendor:product for cpe, else the package name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,           // OSV package.purl
+    pub scheme: String,                 // semver|ecosystem|opaque
+    #[serde(default)]
+    pub ranges: Vec<VersionRange>,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    pub source: String,                 // nvd|osv|cvelist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisory: Option<String>,       // OSV record ID ("GHSA-...")
+}
+
+fn is_numeric_segments(s: &str) -> bool {
+    !s.is_empty() && s.split('.').all(|seg| !seg.is_empty() && seg.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// 1.2.3, v2.0, 1.0.0-rc.1, 3.1+build: dotted numbers with optional pre-release/build suffixes.
+pub fn is_semverish(v: &str) -> bool {
+    let v = v.strip_prefix('v').unwrap_or(v);
+    let core = v.split(['-', '+']).next().unwrap_or("");
+    is_numeric_segments(core) && core.split('.').count() <= 4
+}
+
+/// semver when everything given compares that way, else `fallback`.
+pub fn scheme(ranges: &[VersionRange], versions: &[String], fallback: &str) -> String {
+    let mut all = ranges.iter().flat_map(VersionRange::bounds).chain(versions.iter().map(String::as_str));
+    if all.all(is_semverish) { "semver" } else { fallback }.to_string()
+}
+
+// CPE 2.3 formatted string fields: split on unescaped ':', escapes removed
+fn cpe_fields(uri: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = uri.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+fn given(field: Option<&String>) -> Option<&str> {
+    field.map(String::as_str).filter(|v| !v.is_empty() && *v != "*" && *v != "-")
+}
+
+/// One entry per vulnerable vendor:product in `affected`.
+pub fn from_cpes(affected: &[AffectedCpe]) -> Vec<AffectedVersions> {
+    let mut by_product: BTreeMap<String, (Vec<VersionRange>, Vec<String>)> = BTreeMap::new();
+    for cpe in affected.iter().filter(|a| a.vulnerable) {
+        // cpe:2.3:part:vendor:product:version:update:...
+        let fields = cpe_fields(&cpe.cpe23_uri);
+        let (Some(vendor), Some(product)) = (given(fields.get(3)), given(fields.get(4))) else { continue };
+        let (ranges, versions) = by_product.entry(format!("{}:{}", vendor, product)).or_default();
+        if let Some(version) = given(fields.get(5)) {
+            let version = match given(fields.get(6)) {
+                Some(update) => format!("{}-{}", version, update),
+                None => version.to_string(),
+            };
+            versions.push(version);
+            continue;
+        }
+        ranges.push(VersionRange {
+            gt: cpe.version_start_excluding.clone(),
+            gte: cpe.version_start_including.clone(),
+            lt: cpe.version_end_excluding.clone(),
+            lte: cpe.version_end_including.clone(),
+        });
+    }
+    by_product
+        .into_iter()
+        .map(|(product, (mut ranges, mut versions))| {
+            ranges.sort();
+            ranges.dedup();
+            // An unbounded range already covers every listed version
+            if ranges.contains(&VersionRange::default()) {
+                ranges = vec![VersionRange::default()];
+                versions.clear();
+            }
+            versions.sort();
+            versions.dedup();
+            AffectedVersions {
+                ecosystem: "cpe".to_string(),
+                product,
+                scheme: scheme(&ranges, &versions, "opaque"),
+                ranges,
+                versions,
+                source: "nvd".to_string(),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Rebuild the nvd entries of item.affected_versions from item.affected. Returns items with any entry.
+pub fn derive(items: &mut [CanonicalItem]) -> usize {
+    let mut with = 0;
+    for item in items.iter_mut() {
+        let others = std::mem::take(&mut item.affected_versions).into_iter().filter(|a| a.source != "nvd");
+        let mut entries = from_cpes(&item.affected);
+        entries.extend(others);
+        item.affected_versions = entries;
+        if !item.affected_versions.is_empty() {
+            with += 1;
+        }
+    }
+    with
+}
@@ -0,0 +1,135 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{digest, input, CanonicalItem};
+
+/* -------------------- OpenVEX generation -------------------- */
+/*
+Joins a caller-supplied product list against the codex and emits OpenVEX v0.2.0
+statements (https://github.com/openvex/spec).
+
+products.txt, one product per line (# comments allowed):
+  pkg:deb/debian/openssl@3.0.11
+  Microsoft Exchange Server 2019
+  pkg:npm/lodash@4.17.21 | not_affected:vulnerable_code_not_present
+
+Matching, case-insensitive:
+- purl: package name vs distro package names and item.product
+- anything else: equals item.product or "vendor product", or names an MSRC
+  affected product
+
+Matches default to under_investigation; "| status[:justification]" overrides it.
+*/
+
+pub const STATUSES: [&str; 4] = ["not_affected", "affected", "fixed", "under_investigation"];
+
+pub struct Product {
+    pub id: String,
+    pub status: String,
+    pub justification: Option<String>,
+}
+
+pub fn load_products(path: &Path) -> Result<Vec<Product>> {
+    let text = String::from_utf8(input::read_input(path)?)
+        .with_context(|| format!("Product list is not UTF-8: {}", path.display()))?;
+
+    let mut products = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (id, spec) = match line.split_once('|') {
+            Some((id, spec)) => (id.trim(), spec.trim()),
+            None => (line, "under_investigation"),
+        };
+        let (status, justification) = match spec.split_once(':') {
+            Some((s, j)) => (s.trim(), Some(j.trim().to_string())),
+            None => (spec, None),
+        };
+        if !STATUSES.contains(&status) {
+            bail!("{}:{}: unknown VEX status '{}' (expected one of {})", path.display(), n + 1, status, STATUSES.join(", "));
+        }
+        products.push(Product { id: id.to_string(), status: status.to_string(), justification });
+    }
+    Ok(products)
+}
+
+/// "pkg:deb/debian/openssl@3.0.11?arch=amd64" -> "openssl"
+fn purl_name(purl: &str) -> Option<&str> {
+    let rest = purl.strip_prefix("pkg:")?;
+    let path = rest.split(['@', '?', '#']).next()?;
+    path.rsplit('/').next().filter(|n| !n.is_empty())
+}
+
+pub fn matches(product: &Product, item: &CanonicalItem) -> bool {
+    let eq = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+
+    if let Some(name) = purl_name(&product.id) {
+        return item.distro_status.values().flat_map(|d| d.packages.iter()).any(|p| eq(&p.package, name))
+            || item.product.as_deref().is_some_and(|p| eq(p, name));
+    }
+
+    let id = product.id.as_str();
+    if item.product.as_deref().is_some_and(|p| eq(p, id)) {
+        return true;
+    }
+    if let (Some(v), Some(p)) = (&item.vendor, &item.product)
+        && eq(&format!("{} {}", v.trim(), p.trim()), id)
+    {
+        return true;
+    }
+    item.msrc.as_ref().is_some_and(|m| m.affected_products.iter().any(|p| eq(p, id)))
+}
+
+/// Build the OpenVEX document. Statements are grouped per (CVE, status) and sorted,
+/// and the document @id is a hash of them, so identical inputs give identical ids.
+pub fn build(items: &[CanonicalItem], products: &[Product], author: &str) -> (Value, usize) {
+    // (cve, status, justification) -> product ids
+    let mut grouped: BTreeMap<(String, String, Option<String>), Vec<String>> = BTreeMap::new();
+    for item in items {
+        for p in products.iter().filter(|p| matches(p, item)) {
+            grouped
+                .entry((item.id.clone(), p.status.clone(), p.justification.clone()))
+                .or_default()
+                .push(p.id.clone());
+        }
+    }
+
+    let statements: Vec<Value> = grouped
+        .into_iter()
+        .map(|((cve, status, justification), mut ids)| {
+            ids.sort();
+            ids.dedup();
+            let mut st = json!({
+                "vulnerability": { "name": cve },
+                "products": ids.iter().map(|id| json!({ "@id": id })).collect::<Vec<_>>(),
+                "status": status,
+            });
+            if status == "not_affected" {
+                match justification {
+                    Some(j) => st["justification"] = json!(j),
+                    None => st["impact_statement"] = json!("Declared not affected in the product list"),
+                }
+            }
+            if status == "affected" {
+                st["action_statement"] = json!("Review vendor guidance and remediate");
+            }
+            st
+        })
+        .collect();
+
+    let count = statements.len();
+    let fingerprint = digest::sha256_hex(&serde_json::to_vec(&statements).unwrap_or_default());
+    let doc = json!({
+        "@context": "https://openvex.dev/ns/v0.2.0",
+        "@id": format!("https://openvex.dev/docs/public/bastion-codex-{}", &fingerprint[..16]),
+        "author": author,
+        "timestamp": Utc::now().to_rfc3339(),
+        "version": 1,
+        "statements": statements,
+    });
+    (doc, count)
+}
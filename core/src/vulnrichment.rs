@@ -0,0 +1,147 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+use crate::{cvelist, severity, CanonicalItem};
+
+/* -------------------- CISA Vulnrichment (ADP / SSVC) -------------------- */
+/*
+github.com/cisagov/vulnrichment ships CVE JSON 5.x records (same layout as cvelistV5)
+whose "CISA-ADP" container carries SSVC decision points and, sometimes, a CVSS score.
+
+We target:
+- containers.adp[] where providerMetadata.shortName == "CISA-ADP"
+- adp.metrics[].other { type: "ssvc", content: { timestamp, options: [ { "<decision>": "<value>" } ] } }
+- adp.metrics[].cvssV3_1 / cvssV3_0 baseScore
+*/
+
+#[derive(Debug, Deserialize)]
+struct AdpRecord {
+    #[serde(rename = "cveMetadata")]
+    cve_metadata: AdpMetadata,
+    #[serde(default)]
+    containers: Option<AdpContainers>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdpMetadata {
+    #[serde(rename = "cveId")]
+    cve_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdpContainers {
+    #[serde(default)]
+    adp: Vec<AdpContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdpContainer {
+    #[serde(default, rename = "providerMetadata")]
+    provider_metadata: Option<AdpProvider>,
+    #[serde(default)]
+    metrics: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdpProvider {
+    #[serde(default, rename = "shortName")]
+    short_name: Option<String>,
+}
+
+/// SSVC decision points assigned by CISA.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Ssvc {
+    pub exploitation: Option<String>,     // none|poc|active
+    pub automatable: Option<String>,      // no|yes
+    pub technical_impact: Option<String>, // partial|total
+    pub timestamp: Option<String>,
+    pub provider: String,                 // "CISA-ADP"
+}
+
+struct AdpInfo {
+    ssvc: Option<Ssvc>,
+    cvss: Option<f64>,
+}
+
+fn parse_ssvc(content: &serde_json::Value, provider: &str) -> Ssvc {
+    let mut ssvc = Ssvc {
+        timestamp: content.get("timestamp").and_then(|v| v.as_str()).map(str::to_string),
+        provider: provider.to_string(),
+        ..Default::default()
+    };
+    let options = content.get("options").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    for opt in options {
+        let Some(obj) = opt.as_object() else { continue; };
+        for (k, v) in obj {
+            let v = v.as_str().map(|s| s.trim().to_ascii_lowercase());
+            match k.to_ascii_lowercase().as_str() {
+                "exploitation" => ssvc.exploitation = v,
+                "automatable" => ssvc.automatable = v,
+                "technical impact" => ssvc.technical_impact = v,
+                _ => {}
+            }
+        }
+    }
+    ssvc
+}
+
+fn parse_record(bytes: &[u8]) -> Option<(String, AdpInfo)> {
+    let rec: AdpRecord = serde_json::from_slice(bytes).ok()?;
+    let mut info = AdpInfo { ssvc: None, cvss: None };
+
+    for adp in rec.containers?.adp {
+        let provider = adp.provider_metadata.and_then(|p| p.short_name).unwrap_or_default();
+        if provider != "CISA-ADP" {
+            continue;
+        }
+        for m in &adp.metrics {
+            if let Some(other) = m.get("other")
+                && other.get("type").and_then(|v| v.as_str()) == Some("ssvc")
+                && let Some(content) = other.get("content")
+            {
+                info.ssvc = Some(parse_ssvc(content, &provider));
+            }
+            for key in ["cvssV3_1", "cvssV3_0"] {
+                if info.cvss.is_none() {
+                    info.cvss = m.get(key).and_then(|v| v.get("baseScore")).and_then(|v| v.as_f64());
+                }
+            }
+        }
+    }
+
+    if info.ssvc.is_none() && info.cvss.is_none() {
+        return None;
+    }
+    Some((rec.cve_metadata.cve_id.trim().to_string(), info))
+}
+
+/// Attach CISA SSVC decision points (and gap-fill CVSS) from a vulnrichment checkout or zip.
+/// Returns the number of items enriched.
+pub fn merge_vulnrichment(path: &Path, items: &mut [CanonicalItem]) -> Result<usize> {
+    let mut records: HashMap<String, AdpInfo> = HashMap::new();
+    cvelist::for_each_record(path, &mut |bytes| {
+        if let Some((id, info)) = parse_record(bytes) {
+            records.insert(id, info);
+        }
+    })?;
+
+    let mut merged = 0usize;
+    for item in items.iter_mut() {
+        let Some(info) = records.remove(&item.id) else { continue; };
+
+        if info.ssvc.is_some() {
+            item.ssvc = info.ssvc;
+        }
+        if item.cvss.is_none() && info.cvss.is_some() {
+            item.cvss = info.cvss;
+            item.severity_bucket = severity::bucket(info.cvss, item.kev);
+        }
+        if !item.sources.iter().any(|s| s == "vulnrichment") {
+            item.sources.push("vulnrichment".to_string());
+        }
+        merged += 1;
+    }
+
+    Ok(merged)
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{log_debug, log_warn};
+
+/* -------------------- Run limits + watchdog -------------------- */
+/*
+A malformed feed can send parsing into pathological memory growth. Rather than
+letting the host OOM-kill us (or a hung run block the next cron slot), a watchdog
+thread checks wall time and RSS and exits with a diagnostic.
+
+Exit codes: 124 = timeout, 125 = memory limit.
+*/
+
+pub const EXIT_TIMEOUT: i32 = 124;
+pub const EXIT_MEMORY: i32 = 125;
+
+// The current phase and when it began (set on the first phase() call)
+static PHASE: Mutex<(&'static str, Option<Instant>)> = Mutex::new(("startup", None));
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunLimits {
+    pub timeout: Option<Duration>,
+    pub max_rss_mb: Option<u64>,
+    pub max_open_files: Option<u64>,
+}
+
+/// Record what the run is doing, reported if the watchdog fires.
+/// With --verbose, also logs how long the previous phase took.
+pub fn phase(name: &'static str) {
+    if let Ok(mut p) = PHASE.lock() {
+        let now = Instant::now();
+        if let (prev, Some(started)) = *p {
+            log_debug!("{} took {:.2}s", prev, (now - started).as_secs_f64());
+        }
+        *p = (name, Some(now));
+    }
+}
+
+fn current_phase() -> &'static str {
+    PHASE.lock().map(|p| p.0).unwrap_or("unknown")
+}
+
+/// Resident set size in MB (Linux /proc only).
+fn rss_mb() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096 / (1024 * 1024))
+}
+
+#[cfg(unix)]
+fn set_open_files_limit(n: u64) -> Result<()> {
+    let mut lim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    // SAFETY: getrlimit/setrlimit only read/write the struct we pass in.
+    unsafe {
+        if libc::getrlimit(libc::RLIMIT_NOFILE, &mut lim) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        lim.rlim_cur = (n as libc::rlim_t).min(lim.rlim_max);
+        if libc::setrlimit(libc::RLIMIT_NOFILE, &lim) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_open_files_limit(_n: u64) -> Result<()> {
+    log_warn!("--max-open-files is not supported on this platform; ignoring");
+    Ok(())
+}
+
+fn abort(code: i32, started: Instant, reason: String) -> ! {
+    eprintln!("[FATAL] watchdog: {}", reason);
+    eprintln!("  phase:   {}", current_phase());
+    eprintln!("  elapsed: {:.1}s", started.elapsed().as_secs_f64());
+    if let Some(rss) = rss_mb() {
+        eprintln!("  rss:     {} MB", rss);
+    }
+    eprintln!("  No output was finalized; inputs are left untouched.");
+    std::process::exit(code);
+}
+
+/// Apply limits and start the watchdog thread (no-op if no limits are set).
+pub fn install(limits: RunLimits) -> Result<()> {
+    if let Some(n) = limits.max_open_files {
+        set_open_files_limit(n)?;
+    }
+    if limits.timeout.is_none() && limits.max_rss_mb.is_none() {
+        return Ok(());
+    }
+    if limits.max_rss_mb.is_some() && rss_mb().is_none() {
+        log_warn!("--max-rss-mb needs /proc/self/statm; memory watchdog disabled");
+    }
+
+    let started = Instant::now();
+    thread::Builder::new()
+        .name("watchdog".to_string())
+        .spawn(move || loop {
+            thread::sleep(Duration::from_millis(250));
+            if let Some(t) = limits.timeout
+                && started.elapsed() > t
+            {
+                abort(EXIT_TIMEOUT, started, format!("run exceeded --timeout of {}s", t.as_secs()));
+            }
+            if let Some(max) = limits.max_rss_mb
+                && let Some(rss) = rss_mb()
+                && rss > max
+            {
+                abort(EXIT_MEMORY, started, format!("rss {} MB exceeded --max-rss-mb {}", rss, max));
+            }
+        })?;
+    Ok(())
+}
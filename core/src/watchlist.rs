@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{diff::severity_rank, input, CanonicalItem};
+
+/* -------------------- Watchlist-scoped feeds -------------------- */
+/*
+Each product team keeps a watchlist (vendors/products/CWEs/keywords plus filters).
+For every watchlist we write:
+
+  <slug>.feed.json    JSON Feed 1.1 (https://jsonfeed.org/version/1.1)
+  <slug>.digest.json  webhook payload; posting is left to the orchestrator
+
+With --old, only items that are new or changed since that snapshot are included,
+so subscribers see movement rather than the whole codex every run.
+
+watchlists.json:
+  { "watchlists": [ { "name": "Payments", "vendors": ["Microsoft"],
+                      "products": [], "cwes": [], "keywords": ["exchange"],
+                      "min_severity": "high", "kev_only": false,
+                      "webhook": "https://hooks.example/..." } ] }
+
+A watchlist with no vendors/products/cwes/keywords matches every item;
+the filters still apply.
+*/
+
+#[derive(Debug, Deserialize)]
+struct WatchlistFile {
+    watchlists: Vec<Watchlist>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Watchlist {
+    pub name: String,
+    #[serde(default)]
+    vendors: Vec<String>,
+    #[serde(default)]
+    products: Vec<String>,
+    #[serde(default)]
+    cwes: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    min_severity: Option<String>,
+    #[serde(default)]
+    kev_only: bool,
+    #[serde(default)]
+    webhook: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DigestItem<'a> {
+    id: &'a str,
+    title: &'a str,
+    severity: &'a str,
+    cvss: Option<f64>,
+    kev: bool,
+    vendor: Option<&'a str>,
+    product: Option<&'a str>,
+    url: String,
+}
+
+pub fn load_watchlists(path: &Path) -> Result<Vec<Watchlist>> {
+    let bytes = input::read_input(path).with_context(|| format!("Failed to read watchlists: {}", path.display()))?;
+    let file: WatchlistFile = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Failed to parse watchlists: {}", path.display()))?;
+    Ok(file.watchlists)
+}
+
+fn contains_ci(list: &[String], value: Option<&str>) -> bool {
+    let Some(v) = value else { return false; };
+    list.iter().any(|x| x.trim().eq_ignore_ascii_case(v.trim()))
+}
+
+impl Watchlist {
+    pub fn matches(&self, item: &CanonicalItem) -> bool {
+        if self.kev_only && !item.kev {
+            return false;
+        }
+        if let Some(min) = &self.min_severity
+            && severity_rank(&item.severity_bucket) < severity_rank(min)
+        {
+            return false;
+        }
+
+        let unscoped = self.vendors.is_empty() && self.products.is_empty() && self.cwes.is_empty() && self.keywords.is_empty();
+        if unscoped {
+            return true;
+        }
+
+        let text = format!("{} {}", item.title.as_deref().unwrap_or(""), item.short_desc).to_lowercase();
+        contains_ci(&self.vendors, item.vendor.as_deref())
+            || contains_ci(&self.products, item.product.as_deref())
+            || item.cwes.iter().any(|c| contains_ci(&self.cwes, Some(c)))
+            || self.keywords.iter().any(|k| !k.trim().is_empty() && text.contains(&k.trim().to_lowercase()))
+    }
+
+    pub fn slug(&self) -> String {
+        let mut slug = String::new();
+        for c in self.name.trim().to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c);
+            } else if !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_matches('-').to_string();
+        if slug.is_empty() { "watchlist".to_string() } else { slug }
+    }
+}
+
+fn detail_url(item: &CanonicalItem) -> String {
+    format!("https://nvd.nist.gov/vuln/detail/{}", item.id)
+}
+
+/// Changed = different content hash (or last_modified, for snapshots without hashes).
+fn changed_since<'a>(items: &'a [CanonicalItem], old: &[CanonicalItem]) -> Vec<&'a CanonicalItem> {
+    let old_by_id: HashMap<&str, &CanonicalItem> = old.iter().map(|i| (i.id.as_str(), i)).collect();
+    items
+        .iter()
+        .filter(|i| match old_by_id.get(i.id.as_str()) {
+            None => true,
+            Some(o) if !o.content_hash.is_empty() && !i.content_hash.is_empty() => o.content_hash != i.content_hash,
+            Some(o) => o.last_modified != i.last_modified || o.kev != i.kev || o.cvss != i.cvss,
+        })
+        .collect()
+}
+
+fn json_feed(wl: &Watchlist, items: &[&CanonicalItem]) -> Value {
+    let entries: Vec<Value> = items
+        .iter()
+        .map(|i| {
+            let mut tags = vec![i.severity_bucket.clone()];
+            if i.kev {
+                tags.push("kev".to_string());
+            }
+            tags.extend(i.cwes.iter().cloned());
+            json!({
+                "id": i.id,
+                "url": detail_url(i),
+                "title": match &i.title { Some(t) => format!("{}: {}", i.id, t), None => i.id.clone() },
+                "content_text": i.short_desc,
+                "date_published": i.published,
+                "date_modified": i.last_modified,
+                "tags": tags,
+            })
+        })
+        .collect();
+
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": format!("Bastion Codex – {}", wl.name),
+        "description": format!("Vulnerabilities matching the {} watchlist", wl.name),
+        "items": entries,
+    })
+}
+
+fn digest(wl: &Watchlist, items: &[&CanonicalItem], generated_at: &str) -> Value {
+    let kev = items.iter().filter(|i| i.kev).count();
+    let digest_items: Vec<DigestItem> = items
+        .iter()
+        .map(|i| DigestItem {
+            id: &i.id,
+            title: i.title.as_deref().unwrap_or(&i.short_desc),
+            severity: &i.severity_bucket,
+            cvss: i.cvss,
+            kev: i.kev,
+            vendor: i.vendor.as_deref(),
+            product: i.product.as_deref(),
+            url: detail_url(i),
+        })
+        .collect();
+
+    json!({
+        "watchlist": wl.name,
+        "webhook": wl.webhook,
+        "generated_at": generated_at,
+        "count": items.len(),
+        "text": format!("{}: {} new/changed vulnerabilities ({} KEV)", wl.name, items.len(), kev),
+        "items": digest_items,
+    })
+}
+
+/// Write feed + digest per watchlist. Returns (watchlist name, item count) pairs.
+pub fn write_feeds(
+    items: &[CanonicalItem],
+    old: Option<&[CanonicalItem]>,
+    watchlists: &[Watchlist],
+    outdir: &Path,
+    limit: usize,
+) -> Result<Vec<(String, usize)>> {
+    fs::create_dir_all(outdir).with_context(|| format!("Failed to create outdir: {}", outdir.display()))?;
+
+    let mut candidates: Vec<&CanonicalItem> = match old {
+        Some(old) => changed_since(items, old),
+        None => items.iter().collect(),
+    };
+    // Newest first; ID as tie-break keeps output stable
+    candidates.sort_by(|a, b| b.last_modified.cmp(&a.last_modified).then_with(|| a.id.cmp(&b.id)));
+
+    let generated_at = Utc::now().to_rfc3339();
+    let mut summary = Vec::new();
+    for wl in watchlists {
+        let matched: Vec<&CanonicalItem> = candidates.iter().copied().filter(|i| wl.matches(i)).take(limit).collect();
+        let slug = wl.slug();
+
+        let feed_path = outdir.join(format!("{}.feed.json", slug));
+        fs::write(&feed_path, serde_json::to_string_pretty(&json_feed(wl, &matched))?)
+            .with_context(|| format!("Failed to write feed: {}", feed_path.display()))?;
+        let digest_path = outdir.join(format!("{}.digest.json", slug));
+        fs::write(&digest_path, serde_json::to_string_pretty(&digest(wl, &matched, &generated_at))?)
+            .with_context(|| format!("Failed to write digest: {}", digest_path.display()))?;
+
+        summary.push((wl.name.clone(), matched.len()));
+    }
+    Ok(summary)
+}
@@ -0,0 +1,322 @@
+use anyhow::{bail, Result};
+
+/* -------------------- Minimal XML reader -------------------- */
+/*
+Vendor OVAL streams (see oval.rs) are XML, and the dependency set has no XML
+crate. This reads a whole document into an Element tree, enough for data files:
+
+- elements, attributes ('single' or "double" quoted), self-closing tags
+- text with the five predefined entities and &#N; / &#xH; references, CDATA
+- the XML declaration, processing instructions, comments and DOCTYPE are skipped
+
+Namespace prefixes are dropped from element and attribute names (red-def:rpminfo_test
+is rpminfo_test), since OVAL producers disagree on prefixes, not on local names.
+Whitespace-only text is not kept. No DTDs, no custom entities, no validation.
+Elements nest at most MAX_DEPTH deep; malformed input is an error with a line.
+*/
+
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// First direct child called `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Direct children called `name`.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Every element called `name` below this one, in document order.
+    pub fn descendants<'a>(&'a self, name: &str) -> Vec<&'a Element> {
+        let mut out = Vec::new();
+        let mut stack: Vec<&Element> = self.children.iter().rev().collect();
+        while let Some(e) = stack.pop() {
+            if e.name == name {
+                out.push(e);
+            }
+            stack.extend(e.children.iter().rev());
+        }
+        out
+    }
+}
+
+fn local(name: &str) -> String {
+    name.rsplit(':').next().unwrap_or(name).to_string()
+}
+
+fn unescape(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let Some(end) = rest.find(';').filter(|&e| e <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|h| u32::from_str_radix(h, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|n| n.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn line(&self) -> usize {
+        self.src[..self.pos].matches('\n').count() + 1
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<()> {
+        match self.rest().find(end) {
+            Some(at) => {
+                self.pos += at + end.len();
+                Ok(())
+            }
+            None => bail!("line {}: unterminated markup (no {})", self.line(), end),
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    fn name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '=')).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    // Declarations, comments and processing instructions between elements
+    fn skip_misc(&mut self) -> Result<bool> {
+        let rest = self.rest();
+        if rest.starts_with("<?") {
+            self.skip_past("?>")?;
+        } else if rest.starts_with("<!--") {
+            self.skip_past("-->")?;
+        } else if rest.starts_with("<!DOCTYPE") {
+            // An internal subset can hold '>'; it ends at "]>"
+            match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => self.skip_past("]>")?,
+                _ => self.skip_past(">")?,
+            }
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn attrs(&mut self) -> Result<(Vec<(String, String)>, bool)> {
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_ws();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok((attrs, true));
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                return Ok((attrs, false));
+            }
+            let name = self.name();
+            if name.is_empty() {
+                bail!("line {}: malformed tag", self.line());
+            }
+            self.skip_ws();
+            if !self.rest().starts_with('=') {
+                bail!("line {}: attribute {} has no value", self.line(), name);
+            }
+            self.pos += 1;
+            self.skip_ws();
+            let Some(quote) = self.rest().chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                bail!("line {}: attribute {} is not quoted", self.line(), name);
+            };
+            let value = &self.rest()[1..];
+            let Some(end) = value.find(quote) else { bail!("line {}: unterminated attribute {}", self.line(), name) };
+            attrs.push((local(name), unescape(&value[..end])));
+            self.pos += end + 2;
+        }
+    }
+
+    // At '<' of a start tag; `depth` counts the elements open around it
+    fn element(&mut self, depth: usize) -> Result<Element> {
+        if depth >= MAX_DEPTH {
+            bail!("line {}: elements nested over {} deep", self.line(), MAX_DEPTH);
+        }
+        self.pos += 1;
+        let raw = self.name();
+        let mut el = Element { name: local(raw), ..Default::default() };
+        let (attrs, empty) = self.attrs()?;
+        el.attrs = attrs;
+        if empty {
+            return Ok(el);
+        }
+        loop {
+            let rest = self.rest();
+            let Some(lt) = rest.find('<') else { bail!("line {}: <{}> is never closed", self.line(), raw) };
+            el.text.push_str(&unescape(&rest[..lt]));
+            self.pos += lt;
+            let rest = self.rest();
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let Some(end) = cdata.find("]]>") else { bail!("line {}: unterminated CDATA", self.line()) };
+                el.text.push_str(&cdata[..end]);
+                self.pos += "<![CDATA[".len() + end + 3;
+            } else if let Some(close) = rest.strip_prefix("</") {
+                let Some(end) = close.find('>') else { bail!("line {}: unterminated </{}>", self.line(), raw) };
+                if close[..end].trim() != raw {
+                    bail!("line {}: </{}> closes <{}>", self.line(), close[..end].trim(), raw);
+                }
+                self.pos += 2 + end + 1;
+                break;
+            } else if !self.skip_misc()? {
+                el.children.push(self.element(depth + 1)?);
+            }
+        }
+        if el.text.trim().is_empty() {
+            el.text.clear();
+        }
+        Ok(el)
+    }
+}
+
+/// The document's root element.
+pub fn parse(src: &str) -> Result<Element> {
+    let mut p = Parser { src: src.strip_prefix('\u{feff}').unwrap_or(src), pos: 0 };
+    loop {
+        p.skip_ws();
+        if p.rest().is_empty() {
+            bail!("no root element");
+        }
+        if !p.skip_misc()? {
+            break;
+        }
+    }
+    if !p.rest().starts_with('<') {
+        bail!("line {}: text before the root element", p.line());
+    }
+    p.element(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_text_entities_and_cdata() {
+        let doc = parse(concat!(
+            "\u{feff}<?xml version=\"1.0\"?>\n<!DOCTYPE r [<!ENTITY x \"y\">]>\n<!-- intro -->",
+            "<r a='1 &lt; 2' b=\"&quot;q&quot;\">",
+            "  <t>a &amp; b &#65;&#x42; &bogus; & &#xZZ;</t><c><![CDATA[<not> &amp; markup]]></c>",
+            "<?pi inside?><!-- c --><e/><e x=\"2\" /><n>  </n>",
+            "</r>",
+        ))
+        .unwrap();
+        assert_eq!(doc.name, "r");
+        assert_eq!((doc.attr("a"), doc.attr("b")), (Some("1 < 2"), Some("\"q\"")));
+        assert_eq!(doc.child("t").unwrap().text, "a & b AB &bogus; & &#xZZ;");
+        assert_eq!(doc.child("c").unwrap().text, "<not> &amp; markup");
+        assert_eq!(doc.children_named("e").count(), 2);
+        assert_eq!(doc.children_named("e").nth(1).unwrap().attr("x"), Some("2"));
+        assert_eq!(doc.child("n").unwrap().text, ""); // whitespace only
+        assert_eq!(doc.text, "");
+    }
+
+    #[test]
+    fn drops_namespace_prefixes() {
+        let doc = parse(concat!(
+            "<oval_definitions xmlns=\"urn:d\" xmlns:red-def=\"urn:l\">",
+            "<tests><red-def:rpminfo_test red-def:id=\"t1\"><red-def:object object_ref=\"o1\"/></red-def:rpminfo_test>",
+            "<other:rpminfo_test id=\"t2\"/></tests></oval_definitions>",
+        ))
+        .unwrap();
+        assert_eq!(doc.attr("red-def"), Some("urn:l"));
+        let tests = doc.descendants("rpminfo_test");
+        assert_eq!(tests.len(), 2);
+        assert_eq!((tests[0].attr("id"), tests[1].attr("id")), (Some("t1"), Some("t2")));
+        assert_eq!(tests[0].child("object").unwrap().attr("object_ref"), Some("o1"));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for (src, message) in [
+            ("", "no root element"),
+            ("<!-- only a comment -->", "no root element"),
+            ("text <r/>", "text before the root element"),
+            ("<r>", "<r> is never closed"),
+            ("<r><a></b></r>", "</b> closes <a>"),
+            ("<r><a></a", "unterminated </a>"),
+            ("<r a=1/>", "attribute a is not quoted"),
+            ("<r a/>", "attribute a has no value"),
+            ("<r a=\"1/>", "unterminated attribute a"),
+            ("<r><![CDATA[x</r>", "unterminated CDATA"),
+            ("<r><!-- x</r>", "unterminated markup (no -->)"),
+            ("<r\n><", "line 2: malformed tag"),
+        ] {
+            let err = parse(src).expect_err(src).to_string();
+            assert!(err.contains(message), "{:?}: {}", src, err);
+        }
+        let deep = "<a>".repeat(MAX_DEPTH + 1);
+        assert!(parse(&deep).unwrap_err().to_string().contains("nested over"));
+    }
+
+    // Every truncation of a document is an error, never a panic
+    #[test]
+    fn truncated_input_does_not_panic() {
+        let src = "<?xml version=\"1.0\"?><r a='&amp;é'><!-- c --><b x=\"1\"/><t>&#233;t<![CDATA[é]]></t></r>";
+        for (at, _) in src.char_indices().skip(1) {
+            assert!(parse(&src[..at]).is_err(), "{:?}", &src[..at]);
+        }
+        assert!(parse(src).is_ok());
+    }
+}
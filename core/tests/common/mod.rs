@@ -0,0 +1,29 @@
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bastion-codex-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn normalize(dir: &Path, args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_core"))
+        .current_dir(dir)
+        .arg("normalize")
+        .args(args)
+        .output()
+        .expect("run core normalize");
+    assert!(output.status.success(), "normalize failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+pub fn item(path: &Path, id: &str) -> Value {
+    let codex: Value = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    let items = codex["items"].as_array().unwrap();
+    items.iter().find(|i| i["id"] == id).cloned().expect("item present")
+}
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "nvd", feature = "kev", feature = "io"))]
+
+mod common;
+
+use common::{item, normalize, scratch};
+use serde_json::Value;
+use std::fs;
+
+const NVD: &str = r#"{"format": "NVD_CVE", "version": "2.0", "vulnerabilities": [{"cve": {
+  "id": "CVE-2099-10001", "published": "2099-01-04T09:15:00.000", "lastModified": "2099-01-04T09:15:00.000",
+  "descriptions": [{"lang": "en", "value": "Synthetic vulnerability CVE-2099-10001 in FortiOS."}],
+  "metrics": {"cvssMetricV31": [{"source": "nvd@nist.gov", "type": "Primary",
+    "cvssData": {"version": "3.1", "baseScore": 7.2, "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"}}]},
+  "references": [{"url": "https://example.invalid/CVE-2099-10001/ref/0"}]}}]}"#;
+
+const KEV_LISTED: &str = r#"{"title": "Synthetic KEV Catalog", "catalogVersion": "2099.01.01",
+  "dateReleased": "2099-01-01T00:00:00.000Z", "count": 1, "vulnerabilities": [{"cveID": "CVE-2099-10001",
+  "vendorProject": "Fortinet", "product": "FortiOS", "vulnerabilityName": "FortiOS synthetic flaw",
+  "dateAdded": "2099-01-05", "dueDate": "2099-01-26", "requiredAction": "Apply updates.",
+  "knownRansomwareCampaignUse": "Unknown", "shortDescription": "Synthetic KEV entry.", "notes": ""}]}"#;
+
+const KEV_EMPTY: &str = r#"{"title": "Synthetic KEV Catalog", "catalogVersion": "2099.02.01",
+  "dateReleased": "2099-02-01T00:00:00.000Z", "count": 0, "vulnerabilities": []}"#;
+
+const EXPLOITDB: &str = "id,file,description,date_published,author,type,platform,port,codes\n\
+  51234,exploits/hardware/remote/51234.py,FortiOS synthetic RCE,2099-01-10,anon,remote,hardware,443,CVE-2099-10001\n";
+
+// The CVE's lastModified doesn't move between runs, so the prior item is the one
+// kept; this run's enrichment and KEV catalog must still reach it.
+#[test]
+fn fresh_enrichment_survives_merge_into() {
+    let dir = scratch("merge-into");
+    fs::write(dir.join("nvd.json"), NVD).unwrap();
+    fs::write(dir.join("kev-listed.json"), KEV_LISTED).unwrap();
+    fs::write(dir.join("kev-empty.json"), KEV_EMPTY).unwrap();
+    fs::write(dir.join("edb.csv"), EXPLOITDB).unwrap();
+
+    normalize(&dir, &["--nvd", "nvd.json", "--kev", "kev-listed.json", "--out", "prior.json"]);
+    let prior = item(&dir.join("prior.json"), "CVE-2099-10001");
+    assert_eq!(prior["kev"], true);
+    assert_eq!(prior["exploit_public"], false);
+
+    let args = ["--nvd", "nvd.json", "--kev", "kev-empty.json", "--exploitdb", "edb.csv"];
+    normalize(&dir, &[&args[..], &["--merge-into", "prior.json", "--out", "merged.json"]].concat());
+    let merged = item(&dir.join("merged.json"), "CVE-2099-10001");
+    assert_eq!(merged["exploit_public"], true);
+    assert_eq!(merged["exploit_refs"][0], "https://www.exploit-db.com/exploits/51234");
+    // Delisted from KEV since the prior run
+    assert_eq!(merged["kev"], false);
+    assert_eq!(merged["kev_due_date"], Value::Null);
+    assert!(!merged["sources"].as_array().unwrap().iter().any(|s| s == "kev"));
+    assert_eq!(merged["sources"].as_array().unwrap().iter().filter(|s| *s == "exploitdb").count(), 1);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn cna_record(title: &str) -> String {
+    format!(
+        r#"{{"dataType": "CVE_RECORD", "cveMetadata": {{"cveId": "CVE-2099-10001", "state": "PUBLISHED"}},
+  "containers": {{"cna": {{"title": "{}", "descriptions": [{{"lang": "en", "value": "Synthetic."}}]}}}}}}"#,
+        title
+    )
+}
+
+// cvelist is the only title source, so a CNA retitle must reach the kept item
+#[test]
+fn cna_title_updates_kept_items() {
+    let dir = scratch("merge-into-title");
+    fs::write(dir.join("nvd.json"), NVD).unwrap();
+    fs::create_dir_all(dir.join("cvelist")).unwrap();
+    let record = dir.join("cvelist").join("CVE-2099-10001.json");
+
+    fs::write(&record, cna_record("FortiOS heap overflow")).unwrap();
+    normalize(&dir, &["--nvd", "nvd.json", "--cvelist", "cvelist", "--out", "prior.json"]);
+    assert_eq!(item(&dir.join("prior.json"), "CVE-2099-10001")["title"], "FortiOS heap overflow");
+
+    fs::write(&record, cna_record("FortiOS SSL-VPN heap overflow")).unwrap();
+    let args = ["--nvd", "nvd.json", "--cvelist", "cvelist", "--merge-into", "prior.json", "--out", "merged.json"];
+    normalize(&dir, &args);
+    assert_eq!(item(&dir.join("merged.json"), "CVE-2099-10001")["title"], "FortiOS SSL-VPN heap overflow");
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,63 @@
+#![cfg(all(feature = "nvd", feature = "kev", feature = "io"))]
+
+mod common;
+
+use common::{item, normalize, scratch};
+use serde_json::Value;
+use std::fs;
+
+// One NVD record with `refs` reference URLs
+fn nvd(id: &str, refs: usize) -> String {
+    let refs: Vec<String> =
+        (0..refs).map(|n| format!(r#"{{"url": "https://example.invalid/{}/ref/{}"}}"#, id, n)).collect();
+    format!(
+        r#"{{"format": "NVD_CVE", "version": "2.0", "vulnerabilities": [{{"cve": {{
+  "id": "{id}", "published": "2099-01-04T09:15:00.000", "lastModified": "2099-01-04T09:15:00.000",
+  "descriptions": [{{"lang": "en", "value": "Synthetic vulnerability {id}."}}],
+  "references": [{}]}}}}]}}"#,
+        refs.join(", ")
+    )
+}
+
+fn sidecar(path: &std::path::Path) -> Vec<Value> {
+    serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn sidecar_is_removed_when_nothing_overflows() {
+    let dir = scratch("sidecar-stale");
+    fs::write(dir.join("nvd.json"), nvd("CVE-2099-30001", 40)).unwrap();
+    let args = ["--nvd", "nvd.json", "--out", "items.json", "--truncate", "sidecar"];
+
+    normalize(&dir, &[&args[..], &["--max-item-bytes", "2000"]].concat());
+    assert_eq!(sidecar(&dir.join("items.overflow.json")).len(), 1);
+
+    normalize(&dir, &[&args[..], &["--max-item-bytes", "100000"]].concat());
+    assert!(!dir.join("items.overflow.json").exists());
+    assert!(!dir.join("items.overflow.json.tmp").exists());
+    assert_eq!(item(&dir.join("items.json"), "CVE-2099-30001").get("truncated"), None);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+// The big record is only in the prior output; its overflow must follow it into the new sidecar
+#[test]
+fn kept_items_carry_their_overflow() {
+    let dir = scratch("sidecar-merge");
+    fs::write(dir.join("big.json"), nvd("CVE-2099-30001", 40)).unwrap();
+    fs::write(dir.join("small.json"), nvd("CVE-2099-30002", 1)).unwrap();
+    let limits = ["--max-item-bytes", "2000", "--truncate", "sidecar"];
+
+    normalize(&dir, &[&limits[..], &["--nvd", "big.json", "--out", "prior.json"]].concat());
+    let prior = sidecar(&dir.join("prior.overflow.json"));
+
+    let merge = ["--nvd", "small.json", "--merge-into", "prior.json", "--out", "merged.json"];
+    normalize(&dir, &[&limits[..], &merge[..]].concat());
+    let kept = item(&dir.join("merged.json"), "CVE-2099-30001");
+    assert_eq!(kept["truncated"], serde_json::json!(["refs"]));
+    let merged = sidecar(&dir.join("merged.overflow.json"));
+    assert_eq!(merged, prior);
+    assert!(!merged[0]["fields"]["refs"].as_array().unwrap().is_empty());
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,46 @@
+#![cfg(all(feature = "nvd", feature = "kev", feature = "io"))]
+
+mod common;
+
+use common::{item, normalize, scratch};
+use serde_json::Value;
+use std::fs;
+
+const NVD: &str = r#"{"format": "NVD_CVE", "version": "2.0", "vulnerabilities": [{"cve": {
+  "id": "CVE-2099-20001", "published": "2099-01-04T09:15:00.000", "lastModified": "2099-01-04T09:15:00.000",
+  "descriptions": [{"lang": "en", "value": "Synthetic vulnerability CVE-2099-20001 in foo."}],
+  "metrics": {"cvssMetricV31": [{"source": "nvd@nist.gov", "type": "Primary",
+    "cvssData": {"version": "3.1", "baseScore": 5.0, "vectorString": "CVSS:3.1/AV:N/AC:H/PR:L/UI:N/S:U/C:L/I:L/A:L"}}]},
+  "references": [{"url": "https://example.invalid/CVE-2099-20001/ref/0"}]}}]}"#;
+
+const CSAF: &str = r#"{"document": {"category": "csaf_security_advisory", "csaf_version": "2.0",
+  "publisher": {"category": "vendor", "name": "Example Vendor"},
+  "tracking": {"id": "EXA-2099-0001", "current_release_date": "2099-01-05T00:00:00Z"}},
+  "vulnerabilities": [{"cve": "CVE-2099-20001", "product_status": {"fixed": ["foo-1"]},
+  "scores": [{"cvss_v3": {"baseScore": 6.5, "version": "3.1"}, "products": ["foo-1"]}]}]}"#;
+
+// --source-priority decides between NVD's and the vendor's CVSS whether or not
+// provenance is written, and without --with-provenance none is
+#[test]
+fn source_priority_ranks_csaf_cvss() {
+    let dir = scratch("source-priority");
+    fs::write(dir.join("nvd.json"), NVD).unwrap();
+    fs::create_dir_all(dir.join("csaf")).unwrap();
+    fs::write(dir.join("csaf").join("exa-2099-0001.json"), CSAF).unwrap();
+
+    for (priority, cvss) in [("nvd,csaf", 5.0), ("csaf,nvd", 6.5)] {
+        let args = ["--nvd", "nvd.json", "--csaf", "csaf", "--source-priority", priority];
+        normalize(&dir, &[&args[..], &["--out", "plain.json"]].concat());
+        let plain = item(&dir.join("plain.json"), "CVE-2099-20001");
+        assert_eq!(plain["cvss"], cvss, "{}", priority);
+        assert_eq!(plain.get("provenance"), None, "{}", priority);
+
+        normalize(&dir, &[&args[..], &["--with-provenance", "--out", "tracked.json"]].concat());
+        let tracked = item(&dir.join("tracked.json"), "CVE-2099-20001");
+        assert_eq!(tracked["cvss"], cvss, "{}", priority);
+        let owner = if priority == "nvd,csaf" { "nvd" } else { "csaf" };
+        assert_eq!(tracked["provenance"]["cvss"]["source"], Value::from(owner), "{}", priority);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -3,10 +3,12 @@ from __future__ import annotations
 import hashlib
 import json
 import os
+import random
+import time
 from dataclasses import dataclass
 from datetime import datetime, timezone
 from pathlib import Path
-from typing import Any, Dict, Optional
+from typing import Any, Dict, List, Optional
 
 import requests
 
@@ -20,6 +22,63 @@ class DownloadResult:
     fetched_at_iso: str
 
 
+USER_AGENT = "BastionCodex/0.1 (+local ingestion)"
+
+# Same transient set and defaults as core's http.rs
+RETRY_STATUSES = {408, 429, 500, 502, 503, 504}
+DEFAULT_RETRIES = 3
+DEFAULT_RETRY_DELAY_MS = 1000
+MAX_DELAY_MS = 60_000
+
+
+@dataclass
+class Settings:
+    """
+    Egress settings for every fetch, set once from the command line (ti_run.py
+    --proxy / --cacert / --http-retries / --http-retry-delay-ms) and passed on to
+    `core` too. Without --proxy, requests honours HTTPS_PROXY / NO_PROXY itself;
+    without --cacert, REQUESTS_CA_BUNDLE.
+    """
+
+    proxy: Optional[str] = None
+    cacert: Optional[str] = None
+    retries: int = DEFAULT_RETRIES
+    retry_delay_ms: int = DEFAULT_RETRY_DELAY_MS
+
+    def core_args(self) -> List[str]:
+        """The same settings as `core` global flags."""
+        args = ["--http-retries", str(self.retries), "--http-retry-delay-ms", str(self.retry_delay_ms)]
+        if self.proxy:
+            args += ["--proxy", self.proxy]
+        if self.cacert:
+            args += ["--cacert", self.cacert]
+        return args
+
+
+SETTINGS = Settings()
+
+
+def configure(settings: Settings) -> None:
+    global SETTINGS
+    SETTINGS = settings
+
+
+def new_session(user_agent: str = USER_AGENT) -> requests.Session:
+    session = requests.Session()
+    session.headers["User-Agent"] = user_agent
+    if SETTINGS.proxy:
+        session.proxies = {"http": SETTINGS.proxy, "https": SETTINGS.proxy}
+    if SETTINGS.cacert:
+        session.verify = SETTINGS.cacert
+    return session
+
+
+def backoff(attempt: int) -> float:
+    """Seconds before retry `attempt`: 1s, 2s, 4s, ... at most 60s, plus up to a quarter of jitter."""
+    base = min(SETTINGS.retry_delay_ms * 2 ** min(attempt, 16), MAX_DELAY_MS)
+    return (base + random.uniform(0, base / 4)) / 1000
+
+
 def utc_now_iso() -> str:
     return datetime.now(timezone.utc).replace(microsecond=0).isoformat()
 
@@ -32,33 +91,127 @@ def sha256_file(path: Path) -> str:
     return h.hexdigest()
 
 
+def _validator(r: requests.Response) -> str | None:
+    """What an If-Range can carry: a strong ETag, else Last-Modified."""
+    etag = r.headers.get("ETag", "").strip()
+    if etag and not etag.startswith("W/"):
+        return etag
+    return r.headers.get("Last-Modified", "").strip() or None
+
+
 def download_to_path(
     url: str,
     dest_path: Path,
     timeout_s: int = 60,
-    user_agent: str = "BastionCodex/0.1 (+local ingestion)",
+    user_agent: str = USER_AGENT,
 ) -> DownloadResult:
+    """
+    Download into <dest>.partial and rename once complete. Transient failures are
+    retried with backoff, resuming the partial with a Range request; the partial's
+    ETag / Last-Modified (<dest>.partial.validator) goes along as If-Range, so a
+    file regenerated upstream since is fetched anew instead of spliced. A partial
+    without a validator is only resumed within this call.
+    """
     dest_path.parent.mkdir(parents=True, exist_ok=True)
+    partial = dest_path.with_name(dest_path.name + ".partial")
+    validator_path = dest_path.with_name(dest_path.name + ".partial.validator")
 
-    headers = {"User-Agent": user_agent}
-    with requests.get(url, headers=headers, stream=True, timeout=timeout_s) as r:
-        r.raise_for_status()
-        bytes_written = 0
-        with dest_path.open("wb") as f:
-            for chunk in r.iter_content(chunk_size=1024 * 1024):
-                if chunk:
-                    f.write(chunk)
-                    bytes_written += len(chunk)
+    def discard() -> None:
+        partial.unlink(missing_ok=True)
+        validator_path.unlink(missing_ok=True)
+
+    stored = validator_path.read_text(encoding="utf-8").strip() if validator_path.exists() else ""
+    if not stored:
+        # From an older run, or a server that sent no validator: nothing to check it against
+        discard()
+    stored_validator = stored or None
+
+    session = new_session(user_agent)
+    attempt = 0
+    while True:
+        have = partial.stat().st_size if partial.exists() else 0
+        headers = {}
+        if have:
+            headers["Range"] = f"bytes={have}-"
+            if stored_validator:
+                headers["If-Range"] = stored_validator
+        reason = ""
+        try:
+            with session.get(url, headers=headers, stream=True, timeout=timeout_s) as r:
+                if r.status_code == 416 and have:
+                    # The partial doesn't fit the file (any more): start over
+                    discard()
+                    stored_validator = None
+                    continue
+                if r.status_code in RETRY_STATUSES:
+                    reason = f"HTTP {r.status_code}"
+                else:
+                    r.raise_for_status()
+                    current = _validator(r)
+                    # 206 resumes; 200 is the whole file (no range support, or it changed)
+                    resumed = r.status_code == 206 and have > 0
+                    if resumed and stored_validator and current and current != stored_validator:
+                        print(f"[WARN] download {url}: changed since the partial was saved; starting over")
+                        discard()
+                        stored_validator = None
+                        continue
+                    if not resumed:
+                        discard()
+                        stored_validator = current
+                        if current:
+                            validator_path.write_text(current, encoding="utf-8")
+                    with partial.open("ab" if resumed else "wb") as f:
+                        for chunk in r.iter_content(chunk_size=64 * 1024):
+                            if chunk:
+                                f.write(chunk)
+                    partial.replace(dest_path)
+                    validator_path.unlink(missing_ok=True)
+                    break
+        except (requests.ConnectionError, requests.Timeout, requests.exceptions.ChunkedEncodingError) as e:
+            reason = type(e).__name__
+        if attempt >= SETTINGS.retries:
+            raise RuntimeError(f"download failed for {url}: {reason}")
+        wait = backoff(attempt)
+        attempt += 1
+        have = partial.stat().st_size if partial.exists() else 0
+        resuming = f"resuming at {have} bytes, " if have else ""
+        print(f"[WARN] download {url}: {reason}; {resuming}retry {attempt}/{SETTINGS.retries} in {wait:.1f}s")
+        time.sleep(wait)
 
     return DownloadResult(
         url=url,
         path=dest_path,
         sha256=sha256_file(dest_path),
-        bytes_written=bytes_written,
+        bytes_written=dest_path.stat().st_size,
         fetched_at_iso=utc_now_iso(),
     )
 
 
 def write_json(path: Path, obj: Any) -> None:
     path.parent.mkdir(parents=True, exist_ok=True)
-    path.write_text(json.dumps(obj, indent=2, sort_keys=True), encoding="utf-8")
\ No newline at end of file
+    path.write_text(json.dumps(obj, indent=2, sort_keys=True), encoding="utf-8")
+
+
+def post_json(
+    url: str,
+    payload: Any,
+    timeout_s: int = 30,
+    user_agent: str = USER_AGENT,
+) -> int:
+    session = new_session(user_agent)
+    for attempt in range(SETTINGS.retries + 1):
+        try:
+            r = session.post(url, json=payload, timeout=timeout_s)
+        except (requests.ConnectionError, requests.Timeout) as e:
+            if attempt >= SETTINGS.retries:
+                raise
+            reason = type(e).__name__
+        else:
+            if r.status_code not in RETRY_STATUSES or attempt >= SETTINGS.retries:
+                r.raise_for_status()
+                return r.status_code
+            reason = f"HTTP {r.status_code}"
+        wait = backoff(attempt)
+        print(f"[WARN] POST {url}: {reason}; retry {attempt + 1}/{SETTINGS.retries} in {wait:.1f}s")
+        time.sleep(wait)
+    raise AssertionError("unreachable")
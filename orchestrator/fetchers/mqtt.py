@@ -0,0 +1,74 @@
+from __future__ import annotations
+
+import socket
+import struct
+from typing import Optional
+
+# Minimal MQTT 3.1.1 publisher (CONNECT / PUBLISH QoS 0 / DISCONNECT) so the
+# orchestrator can bridge alerts into OT messaging without a client library.
+# Plain TCP only; put a TLS-terminating bridge in front of brokers that require it.
+
+
+def _remaining_length(n: int) -> bytes:
+    out = bytearray()
+    while True:
+        byte, n = n % 128, n // 128
+        out.append(byte | (0x80 if n else 0))
+        if not n:
+            return bytes(out)
+
+
+def _str(s: str) -> bytes:
+    b = s.encode("utf-8")
+    return struct.pack("!H", len(b)) + b
+
+
+def _packet(kind: int, body: bytes) -> bytes:
+    return bytes([kind]) + _remaining_length(len(body)) + body
+
+
+class MqttPublisher:
+    def __init__(
+        self,
+        broker: str,
+        client_id: str = "bastion-codex",
+        username: Optional[str] = None,
+        password: Optional[str] = None,
+        timeout_s: int = 30,
+    ) -> None:
+        host, _, port = broker.partition(":")
+        self.sock = socket.create_connection((host, int(port or 1883)), timeout=timeout_s)
+
+        flags = 0x02  # clean session
+        payload = _str(client_id)
+        if username:
+            flags |= 0x80
+            payload += _str(username)
+            if password:
+                flags |= 0x40
+                payload += _str(password)
+        variable = _str("MQTT") + bytes([0x04, flags]) + struct.pack("!H", 60)
+        self.sock.sendall(_packet(0x10, variable + payload))
+
+        connack = self.sock.recv(4)
+        if len(connack) < 4 or connack[0] != 0x20:
+            raise ConnectionError(f"MQTT broker {broker} sent no CONNACK")
+        if connack[3] != 0:
+            raise ConnectionError(f"MQTT broker {broker} refused connection (code {connack[3]})")
+
+    def publish(self, topic: str, payload: bytes, retain: bool = False) -> None:
+        if any(c in topic for c in "+#"):
+            raise ValueError(f"MQTT topic may not contain wildcards: {topic}")
+        self.sock.sendall(_packet(0x30 | (0x01 if retain else 0), _str(topic) + payload))
+
+    def close(self) -> None:
+        try:
+            self.sock.sendall(_packet(0xE0, b""))
+        finally:
+            self.sock.close()
+
+    def __enter__(self) -> "MqttPublisher":
+        return self
+
+    def __exit__(self, *exc) -> None:
+        self.close()
@@ -1,12 +1,17 @@
 from __future__ import annotations
 
 import gzip
+import json
 import os
 import shutil
+import time
+from datetime import datetime, timedelta, timezone
 from pathlib import Path
-from typing import Dict
+from typing import Dict, List
 
-from .http import DownloadResult, download_to_path, sha256_file
+import requests
+
+from .http import DownloadResult, download_to_path, new_session, sha256_file, utc_now_iso
 
 
 # NVD JSON 2.0 Modified feed (gz)
@@ -43,4 +48,167 @@ def fetch_nvd_modified(raw_dir: Path) -> Dict:
         "sha256_json": sha256_file(json_path),
         "bytes_json": json_path.stat().st_size,
         "fetched_at": res_gz.fetched_at_iso,
-    }
\ No newline at end of file
+    }
+
+# NVD API 2.0 (NIST is winding down the static feeds)
+DEFAULT_NVD_API_URL = "https://services.nvd.nist.gov/rest/json/cves/2.0"
+
+# API maximum page size and lastModStartDate/lastModEndDate span
+RESULTS_PER_PAGE = 2000
+MAX_WINDOW_DAYS = 120
+
+# Documented limits: 5 requests per rolling 30 s without a key, 50 with one
+RATE_WINDOW_S = 30.0
+RATE_PUBLIC = 5
+RATE_WITH_KEY = 50
+
+# First pull without state or --nvd-since: same span as the modified feed
+DEFAULT_LOOKBACK_DAYS = 8
+
+# 403 is what NVD answers when the rate limit is exceeded
+RETRY_STATUSES = {403, 429, 500, 502, 503, 504}
+MAX_RETRIES = 6
+
+
+class _RateLimiter:
+    def __init__(self, limit: int, window_s: float = RATE_WINDOW_S) -> None:
+        self.limit = limit
+        self.window_s = window_s
+        self.sent: List[float] = []
+
+    def wait(self) -> None:
+        now = time.monotonic()
+        self.sent = [t for t in self.sent if now - t < self.window_s]
+        if len(self.sent) >= self.limit:
+            time.sleep(self.window_s - (now - self.sent[0]))
+        self.sent.append(time.monotonic())
+
+
+def _api_time(dt: datetime) -> str:
+    return dt.astimezone(timezone.utc).strftime("%Y-%m-%dT%H:%M:%S.000+00:00")
+
+
+def _parse_since(since: str) -> datetime:
+    dt = datetime.fromisoformat(since.replace("Z", "+00:00"))
+    return dt if dt.tzinfo else dt.replace(tzinfo=timezone.utc)
+
+
+def _windows(start: datetime, end: datetime) -> List[tuple[datetime, datetime]]:
+    out = []
+    while start < end:
+        stop = min(start + timedelta(days=MAX_WINDOW_DAYS), end)
+        out.append((start, stop))
+        start = stop
+    return out
+
+
+def _get_page(session: requests.Session, url: str, params: Dict, limiter: _RateLimiter) -> Dict:
+    for attempt in range(MAX_RETRIES):
+        limiter.wait()
+        try:
+            r = session.get(url, params=params, timeout=120)
+        except (requests.ConnectionError, requests.Timeout) as e:
+            reason, retry_after = type(e).__name__, ""
+        else:
+            if r.status_code not in RETRY_STATUSES:
+                r.raise_for_status()
+                return r.json()
+            reason, retry_after = str(r.status_code), r.headers.get("Retry-After", "")
+        delay = float(retry_after) if retry_after.isdigit() else min(6 * 2 ** attempt, 120)
+        print(f"[WARN] nvd api: {reason} at startIndex {params['startIndex']}, retrying in {delay:.0f}s")
+        time.sleep(delay)
+    # Last try: whatever goes wrong now is raised
+    limiter.wait()
+    r = session.get(url, params=params, timeout=120)
+    r.raise_for_status()
+    return r.json()
+
+
+def fetch_nvd_api(raw_dir: Path, api_key: str | None = None, since: str | None = None) -> Dict:
+    """
+    Pull CVEs modified since the last pull from the NVD API 2.0, paging through
+    startIndex/resultsPerPage in lastModStartDate windows of at most 120 days and
+    staying under the documented rate limits (backing off on 403/429/5xx).
+
+    The window starts at `since` (ISO-8601), else where the previous pull ended
+    (nvd_api_state.json), else 8 days ago. A key (--api-key / NVD_API_KEY) raises
+    the limit from 5 to 50 requests per 30 s.
+
+    The output has the feed's shape, so normalize reads it like nvd_modified.json.
+    Duplicates across pages or windows keep the newest lastModified.
+
+    Writes:
+      - data/raw/nvd_api.json
+      - data/raw/nvd_api_state.json  ({"last_mod_end": ISO}, only after a complete pull)
+    """
+    url = os.environ.get("BASTION_NVD_API_URL", DEFAULT_NVD_API_URL).strip()
+    api_key = api_key or os.environ.get("NVD_API_KEY") or None
+    json_path = raw_dir / "nvd_api.json"
+    state_path = raw_dir / "nvd_api_state.json"
+    state = json.loads(state_path.read_text(encoding="utf-8")) if state_path.exists() else {}
+
+    end = datetime.now(timezone.utc).replace(microsecond=0)
+    if since:
+        start = _parse_since(since)
+    elif state.get("last_mod_end"):
+        start = _parse_since(state["last_mod_end"])
+    else:
+        start = end - timedelta(days=DEFAULT_LOOKBACK_DAYS)
+
+    session = new_session()
+    if api_key:
+        session.headers["apiKey"] = api_key
+    limiter = _RateLimiter(RATE_WITH_KEY if api_key else RATE_PUBLIC)
+
+    by_id: Dict[str, Dict] = {}
+    pages = 0
+    for win_start, win_end in _windows(start, end):
+        index = 0
+        while True:
+            params = {
+                "lastModStartDate": _api_time(win_start),
+                "lastModEndDate": _api_time(win_end),
+                "startIndex": index,
+                "resultsPerPage": RESULTS_PER_PAGE,
+            }
+            page = _get_page(session, url, params, limiter)
+            pages += 1
+            vulns = page.get("vulnerabilities") or []
+            for v in vulns:
+                cve = v.get("cve") or {}
+                cve_id = cve.get("id")
+                prev = by_id.get(cve_id)
+                # ISO-8601 timestamps from one API compare correctly as strings
+                if cve_id and (prev is None or cve.get("lastModified", "") >= prev["cve"].get("lastModified", "")):
+                    by_id[cve_id] = v
+            index += len(vulns)
+            if not vulns or index >= int(page.get("totalResults", 0)):
+                break
+
+    vulnerabilities = [by_id[k] for k in sorted(by_id)]
+    feed = {
+        "format": "NVD_CVE",
+        "version": "2.0",
+        "timestamp": _api_time(end),
+        "totalResults": len(vulnerabilities),
+        "vulnerabilities": vulnerabilities,
+    }
+    raw_dir.mkdir(parents=True, exist_ok=True)
+    tmp = json_path.with_suffix(".json.partial")
+    tmp.write_text(json.dumps(feed), encoding="utf-8")
+    tmp.replace(json_path)
+    state_path.write_text(json.dumps({"last_mod_end": end.isoformat()}, indent=2), encoding="utf-8")
+
+    return {
+        "name": "nvd_api",
+        "source": "nvd",
+        "url": url,
+        "window_start": start.isoformat(),
+        "window_end": end.isoformat(),
+        "pages": pages,
+        "records": len(vulnerabilities),
+        "path_json": str(json_path),
+        "sha256_json": sha256_file(json_path),
+        "bytes_json": json_path.stat().st_size,
+        "fetched_at": utc_now_iso(),
+    }
@@ -0,0 +1,111 @@
+from __future__ import annotations
+
+import json
+import os
+import zipfile
+from pathlib import Path
+from typing import Dict, List
+
+from .http import download_to_path, utc_now_iso, write_json
+
+
+# Public HTTP front of the gs://osv-vulnerabilities bucket
+DEFAULT_OSV_BUCKET_URL = "https://osv-vulnerabilities.storage.googleapis.com"
+
+DEFAULT_OSV_ECOSYSTEMS = ["PyPI", "npm", "Go", "crates.io", "Maven"]
+
+# Advisories downloaded between sync_state.json saves
+CHECKPOINT_EVERY = 500
+
+
+def _changed_since(index_path: Path, since: str | None) -> List[tuple[str, str]]:
+    """
+    Parse <ecosystem>/modified_id.csv ("<modified ISO>,<id>" rows, newest first)
+    and return the rows modified after `since`. Stops reading at the first older row.
+    """
+    changed: List[tuple[str, str]] = []
+    with index_path.open("r", encoding="utf-8") as f:
+        for line in f:
+            line = line.strip()
+            if not line:
+                continue
+            modified, _, vuln_id = line.partition(",")
+            # ISO-8601 UTC timestamps compare correctly as strings
+            if since is not None and modified <= since:
+                break
+            changed.append((modified, vuln_id))
+    return changed
+
+
+def _seed_from_archive(base: str, eco: str, eco_dir: Path) -> int:
+    """
+    First sync of an ecosystem: its <ecosystem>/all.zip (the archive `core normalize
+    --osv` also reads) in one download, unpacked next to the per-advisory files later
+    syncs write. Returns the number of advisories.
+    """
+    archive = eco_dir / "all.zip"
+    download_to_path(f"{base}/{eco}/all.zip", archive)
+    with zipfile.ZipFile(archive) as z:
+        # Flat <id>.json members only; anything with a path component is not an advisory
+        names = [n for n in z.namelist() if n.endswith(".json") and Path(n).name == n]
+        for name in names:
+            z.extract(name, eco_dir)
+    archive.unlink()
+    return len(names)
+
+
+def fetch_osv_incremental(raw_dir: Path, ecosystems: List[str] | None = None) -> Dict:
+    """
+    Incrementally mirror OSV advisories per ecosystem using the bucket's
+    modified_id.csv index: only advisories modified since the last sync are
+    downloaded. The first sync of an ecosystem unpacks its all.zip instead.
+    Updates go oldest first and the state is saved every CHECKPOINT_EVERY
+    advisories, so an interrupted sync resumes about where it stopped.
+
+    Writes:
+      - data/raw/osv/<ecosystem>/<id>.json
+      - data/raw/osv/sync_state.json  ({ecosystem: newest modified timestamp seen})
+    """
+    base = os.environ.get("BASTION_OSV_BUCKET_URL", DEFAULT_OSV_BUCKET_URL).rstrip("/")
+    ecosystems = ecosystems or DEFAULT_OSV_ECOSYSTEMS
+
+    osv_dir = raw_dir / "osv"
+    state_path = osv_dir / "sync_state.json"
+    state: Dict[str, str] = json.loads(state_path.read_text(encoding="utf-8")) if state_path.exists() else {}
+
+    per_ecosystem: Dict[str, int] = {}
+    for eco in ecosystems:
+        eco_dir = osv_dir / eco
+        index_path = eco_dir / "modified_id.csv"
+        download_to_path(f"{base}/{eco}/modified_id.csv", index_path)
+
+        since = state.get(eco)
+        changed = _changed_since(index_path, since)
+        if since is None and changed:
+            # The index was fetched first: the archive holds at least what it lists
+            per_ecosystem[eco] = _seed_from_archive(base, eco, eco_dir)
+            state[eco] = changed[0][0]
+            write_json(state_path, state)
+            continue
+
+        pending = list(reversed(changed))
+        saved = 0
+        for n, (modified, vuln_id) in enumerate(pending, 1):
+            download_to_path(f"{base}/{eco}/{vuln_id}.json", eco_dir / f"{vuln_id}.json")
+            # A checkpoint must cover every row up to its timestamp, so not inside a run of equal ones
+            following = pending[n][0] if n < len(pending) else None
+            if following != modified and (n - saved >= CHECKPOINT_EVERY or following is None):
+                state[eco] = modified
+                write_json(state_path, state)
+                saved = n
+        per_ecosystem[eco] = len(changed)
+        write_json(state_path, state)
+
+    return {
+        "name": "osv",
+        "source": "osv",
+        "url": base,
+        "path": str(osv_dir),
+        "downloaded": per_ecosystem,
+        "fetched_at": utc_now_iso(),
+    }
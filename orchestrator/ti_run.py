@@ -6,9 +6,12 @@ import subprocess
 from pathlib import Path
 from typing import Dict, List
 
-from fetchers.http import write_json, utc_now_iso
+from fetchers import http
+from fetchers.http import post_json, write_json, utc_now_iso
 from fetchers.kev import fetch_kev
-from fetchers.nvd import fetch_nvd_modified
+from fetchers.mqtt import MqttPublisher
+from fetchers.nvd import fetch_nvd_api, fetch_nvd_modified
+from fetchers.osv import fetch_osv_incremental
 
 import json
 from datetime import datetime, timezone
@@ -18,13 +21,20 @@ import os
 import shutil
 
 
-def run_fetch(root: Path) -> Dict:
+def run_fetch(root: Path, nvd_api: bool = False, api_key: str | None = None, nvd_since: str | None = None) -> Dict:
     raw_dir = root / "data" / "raw"
     raw_dir.mkdir(parents=True, exist_ok=True)
 
     artifacts: List[Dict] = []
     artifacts.append(fetch_kev(raw_dir))
-    artifacts.append(fetch_nvd_modified(raw_dir))
+    if nvd_api:
+        artifacts.append(fetch_nvd_api(raw_dir, api_key, nvd_since))
+    else:
+        artifacts.append(fetch_nvd_modified(raw_dir))
+    # OSV is opt-in: BASTION_OSV_ECOSYSTEMS=PyPI,npm,...
+    osv_ecosystems = [e.strip() for e in os.environ.get("BASTION_OSV_ECOSYSTEMS", "").split(",") if e.strip()]
+    if osv_ecosystems:
+        artifacts.append(fetch_osv_incremental(raw_dir, osv_ecosystems))
 
     meta = {
         "project": "bastion-codex",
@@ -42,13 +52,26 @@ def run_rust(root: Path, args: List[str]) -> None:
     """
     Run the Rust truth engine via cargo.
     """
-    cmd = ["cargo", "run", "--quiet", "--manifest-path", str(root / "core" / "Cargo.toml"), "--"] + args
+    manifest = str(root / "core" / "Cargo.toml")
+    cmd = ["cargo", "run", "--quiet", "--manifest-path", manifest, "--"]
+    cmd += http.SETTINGS.core_args() + SEVERITY_ARGS + args
     subprocess.run(cmd, cwd=str(root), check=True)
 
 
-def run_weekly(root: Path) -> None:
+RETENTION_DAILY_DAYS = 90    # keep newest snapshot per day for this long
+RETENTION_WEEKLY_DAYS = 730  # then newest per ISO week; older snapshots are pruned
+
+
+def run_weekly(
+    root: Path,
+    nvd_api: bool = False,
+    api_key: str | None = None,
+    nvd_since: str | None = None,
+    daily_days: int = RETENTION_DAILY_DAYS,
+    weekly_days: int = RETENTION_WEEKLY_DAYS,
+) -> None:
     # 1) Fetch
-    meta = run_fetch(root)
+    meta = run_fetch(root, nvd_api, api_key, nvd_since)
     print(f"[OK] Wrote: {root / 'data' / 'raw' / 'meta.json'}")
     for a in meta["artifacts"]:
         if a["name"] == "kev":
@@ -63,8 +86,9 @@ def run_weekly(root: Path) -> None:
         [
             "normalize",
             "--kev", "data/raw/kev.json",
-            "--nvd", "data/raw/nvd_modified.json",
+            "--nvd", "data/raw/nvd_api.json" if nvd_api else "data/raw/nvd_modified.json",
             "--out", "data/normalized/items.json",
+            "--snapshot-store", SNAPSHOT_STORE,
         ],
     )
 
@@ -76,6 +100,7 @@ def run_weekly(root: Path) -> None:
             "derive",
             "--input", "data/normalized/items.json",
             "--outdir", "data/derived",
+            "--shareable",  # the brief is exported to Obsidian/blog
         ],
     )
 
@@ -91,6 +116,7 @@ def run_weekly(root: Path) -> None:
     
     # Compute deltas if we have at least 2 snapshots to compare against
     delta = None
+    transitions = None
     snaps = list_snapshots(root)
     if len(snaps) >= 2:
         prev = snaps[-2]
@@ -99,18 +125,39 @@ def run_weekly(root: Path) -> None:
         cur_7d = json.loads((cur / "trends_7d.json").read_text(encoding="utf-8"))
         delta = compute_deltas(prev_7d, cur_7d)
         print("[OK] Computed week-over-week deltas.")
+        prev_idx_path = prev / "severity_index.json"
+        cur_idx_path = cur / "severity_index.json"
+        if prev_idx_path.exists() and cur_idx_path.exists():
+            transitions = compute_severity_transitions(
+                json.loads(prev_idx_path.read_text(encoding="utf-8")),
+                json.loads(cur_idx_path.read_text(encoding="utf-8")),
+            )
+            (root / "data" / "derived" / "severity_transitions.json").write_text(
+                json.dumps(transitions, indent=2), encoding="utf-8"
+            )
+            print(f"[OK] Severity transitions: {len(transitions['escalations'])} escalations, "
+                  f"{len(transitions['downgrades'])} downgrades.")
     else:
         print("[INFO] Not enough history for week-over-week deltas yet.")
         
+    # Watched IDs (embargoed/RESERVED CVEs) that gained details this run
+    watch_ids = root / "data" / "watch" / "ids.txt"
+    if watch_ids.exists():
+        check_watched_ids(root, watch_ids, os.environ.get("BASTION_WATCH_WEBHOOK"))
+
     ## 4) Generate weekly brief
-    brief_path = generate_weekly_markdown(root, delta=delta)
+    brief_path = generate_weekly_markdown(root, delta=delta, transitions=transitions)
     print(f"  - {brief_path.relative_to(root)}")
 
     ## Optional: export to Obsidian vault if env var set
     export_to_obsidian(root, brief_path)
     export_to_astro_blog(root, brief_path)
 
-def generate_weekly_markdown(root: Path, delta: dict | None = None) -> Path:
+    ## 5) Enforce snapshot retention
+    prune_history(root, daily_days, weekly_days)
+    prune_snapshot_store(root, daily_days, weekly_days)
+
+def generate_weekly_markdown(root: Path, delta: dict | None = None, transitions: dict | None = None) -> Path:
     derived_dir = root / "data" / "derived"
     briefs_dir = root / "data" / "briefs"
     briefs_dir.mkdir(parents=True, exist_ok=True)
@@ -162,6 +209,21 @@ def generate_weekly_markdown(root: Path, delta: dict | None = None) -> Path:
         lines.append("- Delta tracking is warming up. This section will populate after at least two weekly snapshots exist.")
         lines.append("")
         
+    if transitions and (transitions["escalations"] or transitions["downgrades"]):
+        lines.append("## Severity Escalations")
+        esc = transitions["escalations"]
+        if esc:
+            lines.append(f"- {len(esc)} existing CVEs were re-scored into a higher severity bucket:")
+            for e in esc[:15]:
+                lines.append(f"  - {e['id']}: {e['old']} → {e['new']}")
+            if len(esc) > 15:
+                lines.append(f"  - ... and {len(esc) - 15} more (see data/derived/severity_transitions.json)")
+        else:
+            lines.append("- No existing CVEs moved into a higher severity bucket.")
+        if transitions["downgrades"]:
+            lines.append(f"- {len(transitions['downgrades'])} CVEs were re-scored downward.")
+        lines.append("")
+
     lines.append("## Defender Takeaways")
 
     if sev_count("critical") > 50:
@@ -235,6 +297,16 @@ def snapshot_state(root: Path) -> Path:
             raise FileNotFoundError(f"Missing derived artifact for snapshot: {src}")
         shutil.copyfile(src, hist_root / name)
 
+    # Compact id -> severity bucket map so the next run can spot re-scored CVEs
+    items_path = root / "data" / "normalized" / "items.json"
+    if items_path.exists():
+        items = json.loads(items_path.read_text(encoding="utf-8"))
+        if isinstance(items, dict):  # versioned envelope
+            items = items.get("items", [])
+        index = {i["id"]: i.get("severity_bucket", "unknown") for i in items if i.get("id")}
+        (hist_root / "severity_index.json").write_text(json.dumps(index, sort_keys=True), encoding="utf-8")
+        required = required + ["severity_index.json"]
+
     meta = {
         "snapshot_day": day,
         "snapshot_stamp": stamp,
@@ -261,6 +333,229 @@ def list_snapshots(root: Path) -> list[Path]:
     snaps.sort(key=lambda p: p.as_posix())
     return snaps
 
+def plan_retention(snaps: list[Path], now: datetime, daily_days: int, weekly_days: int) -> list[tuple[Path, str]]:
+    """
+    Return (snapshot, reason) for every snapshot the policy drops. Snapshots are
+    judged by their stamp directory name; unparseable names are never pruned.
+    """
+    kept_buckets: set[str] = set()
+    prune: list[tuple[Path, str]] = []
+    # Newest first, so the first snapshot seen in a bucket is the one kept
+    for snap in sorted(snaps, key=lambda p: p.name, reverse=True):
+        try:
+            taken = datetime.strptime(snap.name, "%Y-%m-%dT%H-%M-%SZ").replace(tzinfo=timezone.utc)
+        except ValueError:
+            continue
+        age_days = (now - taken).days
+        if age_days < daily_days:
+            bucket, reason = f"day:{taken.date()}", "superseded by a newer snapshot the same day"
+        elif age_days < weekly_days:
+            year, week, _ = taken.isocalendar()
+            bucket, reason = f"week:{year}-W{week:02d}", "superseded by a newer snapshot the same week"
+        else:
+            prune.append((snap, f"older than {weekly_days} days"))
+            continue
+        if bucket in kept_buckets:
+            prune.append((snap, reason))
+        else:
+            kept_buckets.add(bucket)
+    return prune
+
+def prune_history(
+    root: Path,
+    daily_days: int = RETENTION_DAILY_DAYS,
+    weekly_days: int = RETENTION_WEEKLY_DAYS,
+    dry_run: bool = False,
+) -> list[Path]:
+    """
+    Apply the snapshot retention policy to data/history. Every pruned snapshot is
+    appended to data/history/retention_log.jsonl so there is an audit trail.
+    """
+    now = datetime.now(timezone.utc)
+    plan = plan_retention(list_snapshots(root), now, daily_days, weekly_days)
+    if not plan:
+        print("[OK] Retention: nothing to prune.")
+        return []
+
+    log_path = root / "data" / "history" / "retention_log.jsonl"
+    with log_path.open("a", encoding="utf-8") as log:
+        for snap, reason in plan:
+            rel = snap.relative_to(root).as_posix()
+            if dry_run:
+                print(f"  [DRY-RUN] would prune {rel} ({reason})")
+                continue
+            shutil.rmtree(snap)
+            # Drop the day directory once its last snapshot is gone
+            if not any(snap.parent.iterdir()):
+                snap.parent.rmdir()
+            log.write(json.dumps({"pruned_at": now.isoformat(), "snapshot": rel, "reason": reason}) + "\n")
+
+    verb = "Would prune" if dry_run else "Pruned"
+    print(f"[OK] Retention: {verb} {len(plan)} snapshots (daily {daily_days}d, weekly {weekly_days}d).")
+    return [snap for snap, _ in plan]
+
+SNAPSHOT_STORE = "data/snapshots"  # normalize --snapshot-store, read by `core query --as-of`
+
+def prune_snapshot_store(root: Path, daily_days: int, weekly_days: int, dry_run: bool = False) -> None:
+    """
+    The same retention policy for the snapshot store: `core snapshot prune` drops the
+    runs and then the item versions no kept run refers to.
+    """
+    if not (root / SNAPSHOT_STORE / "runs.jsonl").exists():
+        return
+    args = ["snapshot", "--store", SNAPSHOT_STORE, "prune"]
+    args += ["--daily-days", str(daily_days), "--weekly-days", str(weekly_days)]
+    run_rust(root, args + (["--dry-run"] if dry_run else []))
+
+def query_as_of(root: Path, as_of: str, cve: str | None, expr: str | None) -> None:
+    """
+    What the weekly runs knew on `as_of`: `core query --as-of` over the snapshot store,
+    optionally narrowed to one CVE and/or a filter expression.
+    """
+    filters = [f'id == "{cve.strip().upper()}"'] if cve else []
+    if expr:
+        filters.append(f"({expr})")
+    args = ["query", "--store", SNAPSHOT_STORE, "--as-of", as_of]
+    if filters:
+        args += ["--filter", " && ".join(filters)]
+    run_rust(root, args)
+
+def post_watchlist_digests(feeds_dir: Path) -> None:
+    """
+    POST each <slug>.digest.json written by `bastion-core feeds` to its watchlist webhook.
+    Empty digests and watchlists without a webhook are skipped; one failing hook
+    does not stop the others.
+    """
+    digests = sorted(feeds_dir.glob("*.digest.json"))
+    if not digests:
+        print(f"[INFO] No digests found in {feeds_dir}")
+        return
+
+    for path in digests:
+        digest = json.loads(path.read_text(encoding="utf-8"))
+        url = digest.pop("webhook", None)
+        name = digest.get("watchlist", path.stem)
+        if not url:
+            continue
+        if not digest.get("count"):
+            print(f"[INFO] {name}: nothing new, skipping webhook")
+            continue
+        try:
+            post_json(url, digest)
+            print(f"[OK] {name}: posted {digest['count']} items")
+        except Exception as e:
+            print(f"[WARN] {name}: webhook failed: {e}")
+
+DEFAULT_MQTT_TOPIC = "bastion/{watchlist}/{vendor}/{severity}"
+
+def topic_segment(value) -> str:
+    """Lowercase slug safe for one MQTT topic level (no '/', '+', '#')."""
+    slug = re.sub(r"[^a-z0-9._-]+", "-", str(value or "unknown").lower()).strip("-")
+    return slug or "unknown"
+
+def publish_digests_mqtt(feeds_dir: Path, broker: str, topic_template: str = DEFAULT_MQTT_TOPIC) -> None:
+    """
+    Publish each item of every non-empty <slug>.digest.json to an MQTT broker, one
+    message per item. The topic is rendered from {watchlist}, {vendor}, {product},
+    {severity} and {id}; each placeholder becomes a single topic level.
+    Credentials come from BASTION_MQTT_USERNAME / BASTION_MQTT_PASSWORD.
+    """
+    digests = sorted(feeds_dir.glob("*.digest.json"))
+    if not digests:
+        print(f"[INFO] No digests found in {feeds_dir}")
+        return
+
+    published = 0
+    with MqttPublisher(
+        broker,
+        username=os.environ.get("BASTION_MQTT_USERNAME"),
+        password=os.environ.get("BASTION_MQTT_PASSWORD"),
+    ) as mqtt:
+        for path in digests:
+            digest = json.loads(path.read_text(encoding="utf-8"))
+            name = digest.get("watchlist", path.stem)
+            for item in digest.get("items", []):
+                topic = topic_template.format(
+                    watchlist=topic_segment(name),
+                    vendor=topic_segment(item.get("vendor")),
+                    product=topic_segment(item.get("product")),
+                    severity=topic_segment(item.get("severity")),
+                    id=topic_segment(item.get("id")),
+                )
+                payload = dict(item, watchlist=name, generated_at=digest.get("generated_at"))
+                mqtt.publish(topic, json.dumps(payload, sort_keys=True).encode("utf-8"))
+                published += 1
+    print(f"[OK] Published {published} items to MQTT broker {broker}")
+
+WATCH_STATUS_RANK = {"absent": 0, "published": 1, "scored": 2}
+
+def watch_status(item: dict | None) -> str:
+    """
+    absent    - not in the codex yet (RESERVED / embargoed)
+    published - NVD/CVE-list has a record with a description
+    scored    - a CVSS score is available
+    """
+    if item is None:
+        return "absent"
+    if item.get("cvss") is not None:
+        return "scored"
+    return "published"
+
+
+def check_watched_ids(root: Path, ids_path: Path, webhook: str | None = None) -> list[dict]:
+    """
+    Compare watched CVE IDs against the current codex and report any that moved
+    forward (absent -> published -> scored) since the last check.
+    State lives in data/watch/state.json so each transition alerts once.
+    """
+    wanted = []
+    for line in ids_path.read_text(encoding="utf-8").splitlines():
+        line = line.split("#", 1)[0].strip().upper()
+        if line:
+            wanted.append(line)
+
+    items = json.loads((root / "data" / "normalized" / "items.json").read_text(encoding="utf-8"))
+    if isinstance(items, dict):  # versioned envelope
+        items = items.get("items", [])
+    by_id = {i.get("id"): i for i in items}
+
+    state_path = root / "data" / "watch" / "state.json"
+    state = json.loads(state_path.read_text(encoding="utf-8")) if state_path.exists() else {}
+
+    alerts = []
+    for cve in wanted:
+        item = by_id.get(cve)
+        new = watch_status(item)
+        old = state.get(cve, {}).get("status", "absent")
+        if WATCH_STATUS_RANK[new] > WATCH_STATUS_RANK.get(old, 0):
+            alerts.append({
+                "id": cve,
+                "old": old,
+                "new": new,
+                "cvss": item.get("cvss") if item else None,
+                "severity": item.get("severity_bucket") if item else None,
+                "short_desc": item.get("short_desc") if item else None,
+            })
+        state[cve] = {"status": new, "checked_at": utc_now_iso()}
+
+    write_json(state_path, state)
+
+    for a in alerts:
+        score = f" (CVSS {a['cvss']}, {a['severity']})" if a["cvss"] is not None else ""
+        print(f"[ALERT] {a['id']}: {a['old']} -> {a['new']}{score}")
+    if not alerts:
+        print(f"[OK] {len(wanted)} watched IDs checked, no changes.")
+
+    if alerts and webhook:
+        try:
+            post_json(webhook, {
+                "text": f"{len(alerts)} watched CVEs have new details",
+                "items": alerts,
+            })
+        except Exception as e:
+            print(f"[WARN] watch webhook failed: {e}")
+    return alerts
+
 # Compute percentage change with safe handling of division by zero
 def pct_change(new: int, old: int) -> float | None:
     if old == 0:
@@ -314,6 +609,54 @@ def compute_deltas(prev_7d: dict, cur_7d: dict) -> dict:
 
     return out
 
+SEVERITY_RANK = {"unknown": 0, "low": 1, "medium": 2, "high": 3, "critical": 4}
+# `core` global flags for a custom policy; set with SEVERITY_RANK by configure_severity()
+SEVERITY_ARGS: List[str] = []
+
+def configure_severity(policy: str | None, thresholds: str | None) -> None:
+    """
+    Run `core` under a custom severity policy and rank buckets by it too, as core does:
+    buckets are listed most severe first, the unscored bucket ranks 0.
+    """
+    global SEVERITY_RANK, SEVERITY_ARGS
+    if policy:
+        doc = json.loads(Path(policy).read_text(encoding="utf-8"))
+        names = [b["name"] for b in doc.get("buckets", [])]
+        unscored = doc.get("unscored", "unknown")
+        SEVERITY_ARGS = ["--severity-policy", str(Path(policy).resolve())]
+    elif thresholds:
+        names = [e.split("=", 1)[0].strip() for e in thresholds.split(",") if e.strip()]
+        unscored = "unknown"
+        SEVERITY_ARGS = ["--severity-thresholds", thresholds]
+    else:
+        return
+    SEVERITY_RANK = {name: len(names) - i for i, name in enumerate(names)}
+    SEVERITY_RANK[unscored] = 0
+
+def compute_severity_transitions(prev_index: dict, cur_index: dict) -> dict:
+    """
+    Find CVEs present in both snapshots whose severity bucket changed.
+    Escalations (e.g. medium -> critical after reanalysis) are alert-worthy on their
+    own: they are not "new" items, so new-item tracking misses them.
+    unknown -> scored is treated as an escalation too.
+    """
+    escalations = []
+    downgrades = []
+    for cve, new in cur_index.items():
+        old = prev_index.get(cve)
+        if old is None or old == new:
+            continue
+        entry = {"id": cve, "old": old, "new": new}
+        if SEVERITY_RANK.get(new, 0) > SEVERITY_RANK.get(old, 0):
+            escalations.append(entry)
+        else:
+            downgrades.append(entry)
+
+    # Biggest jumps first, then by ID for stable output
+    escalations.sort(key=lambda e: (-(SEVERITY_RANK.get(e["new"], 0) - SEVERITY_RANK.get(e["old"], 0)), e["id"]))
+    downgrades.sort(key=lambda e: e["id"])
+    return {"escalations": escalations, "downgrades": downgrades}
+
 # Optional: export to Obsidian vault if env var set
 def export_to_obsidian(root: Path, brief_path: Path) -> None:
     """
@@ -432,20 +775,79 @@ def main() -> None:
     parser.add_argument("--root", default=".", help="Repo root (default: current directory)")
     parser.add_argument("--fetch", action="store_true", help="Fetch and cache raw feeds (KEV + NVD modified)")
     parser.add_argument("--weekly", action="store_true", help="Run full weekly pipeline (fetch + normalize + derive)")
+    parser.add_argument("--nvd-api", action="store_true", help="With --fetch/--weekly, pull NVD from the API 2.0 instead of the modified feed")
+    parser.add_argument("--api-key", help="NVD API key for --nvd-api (default: $NVD_API_KEY); raises the rate limit tenfold")
+    parser.add_argument("--nvd-since", metavar="ISO", help="With --nvd-api, pull changes since this time instead of since the last pull")
+    parser.add_argument("--as-of", metavar="YYYY-MM-DD", help="Query the snapshot store as of a date (core query --as-of)")
+    parser.add_argument("--cve", help="CVE ID to look up with --as-of")
+    parser.add_argument("--filter", metavar="EXPR", help="core query filter expression for --as-of")
+    parser.add_argument("--check-watched", metavar="FILE", help="Alert when watched CVE IDs (one per line) get details or a score")
+    parser.add_argument("--fetch-osv", metavar="ECOSYSTEMS", help="Incrementally sync OSV advisories (comma-separated, e.g. PyPI,npm)")
+    parser.add_argument("--post-digests", metavar="DIR", help="POST watchlist digests from `bastion-core feeds` to their webhooks")
+    parser.add_argument("--prune-history", action="store_true", help="Apply the snapshot retention policy to data/history and data/snapshots")
+    parser.add_argument("--retention-daily-days", type=int, default=RETENTION_DAILY_DAYS, help="Keep one snapshot per day for this many days")
+    parser.add_argument("--retention-weekly-days", type=int, default=RETENTION_WEEKLY_DAYS, help="Then one per week up to this age; older are pruned")
+    parser.add_argument("--dry-run", action="store_true", help="With --prune-history, list what would be pruned")
+    parser.add_argument("--publish-mqtt", metavar="DIR", help="Publish watchlist digest items from `bastion-core feeds` to MQTT")
+    parser.add_argument("--mqtt-broker", metavar="HOST[:PORT]", help="MQTT broker for --publish-mqtt (default port 1883)")
+    parser.add_argument("--mqtt-topic", default=DEFAULT_MQTT_TOPIC, help="Topic template: {watchlist} {vendor} {product} {severity} {id}")
+    parser.add_argument("--proxy", metavar="URL", help="Proxy for every fetch and `core` run (default: HTTPS_PROXY / NO_PROXY)")
+    parser.add_argument("--cacert", metavar="FILE", help="CA bundle (PEM) for TLS, e.g. a corporate inspecting proxy's root")
+    parser.add_argument("--http-retries", type=int, default=http.DEFAULT_RETRIES, help="Retries for transient HTTP failures (no answer, 408, 429, 5xx)")
+    parser.add_argument("--http-retry-delay-ms", type=int, default=http.DEFAULT_RETRY_DELAY_MS, help="First retry delay; each further retry waits twice as long (at most 60s)")
+    parser.add_argument("--severity-policy", metavar="FILE", help="Severity policy JSON for `core` runs and severity transitions")
+    parser.add_argument("--severity-thresholds", metavar="SPEC", help="Inline policy instead, e.g. urgent=9.0+kev,critical=9.0,high=7.0,medium=4.0,low=0")
 
     args = parser.parse_args()
     root = Path(args.root).resolve()
+    http.configure(http.Settings(args.proxy, args.cacert, args.http_retries, args.http_retry_delay_ms))
+    if args.severity_policy and args.severity_thresholds:
+        parser.error("--severity-policy and --severity-thresholds are mutually exclusive")
+    configure_severity(args.severity_policy, args.severity_thresholds)
 
     if args.weekly:
-        run_weekly(root)
+        retention = (args.retention_daily_days, args.retention_weekly_days)
+        run_weekly(root, args.nvd_api, args.api_key, args.nvd_since, *retention)
+        return
+
+    if args.as_of:
+        query_as_of(root, args.as_of, args.cve, args.filter)
+        return
+
+    if args.check_watched:
+        check_watched_ids(root, Path(args.check_watched), os.environ.get("BASTION_WATCH_WEBHOOK"))
+        return
+
+    if args.post_digests:
+        post_watchlist_digests(root / args.post_digests)
+        return
+
+    if args.prune_history:
+        prune_history(root, args.retention_daily_days, args.retention_weekly_days, args.dry_run)
+        prune_snapshot_store(root, args.retention_daily_days, args.retention_weekly_days, args.dry_run)
+        return
+
+    if args.publish_mqtt:
+        if not args.mqtt_broker:
+            parser.error("--publish-mqtt requires --mqtt-broker")
+        publish_digests_mqtt(root / args.publish_mqtt, args.mqtt_broker, args.mqtt_topic)
+        return
+
+    if args.fetch_osv:
+        ecosystems = [e.strip() for e in args.fetch_osv.split(",") if e.strip()]
+        res = fetch_osv_incremental(root / "data" / "raw", ecosystems)
+        for eco, n in res["downloaded"].items():
+            print(f"[OK] osv {eco}: {n} advisories new or modified since last sync")
         return
 
     if args.fetch:
-        meta = run_fetch(root)
+        meta = run_fetch(root, args.nvd_api, args.api_key, args.nvd_since)
         print(f"[OK] Wrote: {root / 'data' / 'raw' / 'meta.json'}")
         for a in meta["artifacts"]:
             if a["name"] == "kev":
                 print(f"  - KEV: {a['path']} ({a['bytes']} bytes)")
+            elif a["name"] == "osv":
+                print(f"  - OSV: {a['path']} ({sum(a['downloaded'].values())} advisories updated)")
             else:
                 print(f"  - NVD: {a['path_json']} ({a['bytes_json']} bytes)")
         return
@@ -453,6 +855,14 @@ def main() -> None:
     print("Nothing to do. Try:")
     print("  python orchestrator\\ti_run.py --weekly")
     print("  python orchestrator\\ti_run.py --fetch")
+    print("  python orchestrator\\ti_run.py --fetch --nvd-api --nvd-since 2024-06-01T00:00:00Z")
+    print("  python orchestrator\\ti_run.py --as-of 2024-06-01 --cve CVE-2024-1234")
+    print("  python orchestrator\\ti_run.py --as-of 2024-06-01 --filter 'kev == true'")
+    print("  python orchestrator\\ti_run.py --fetch-osv PyPI,npm")
+    print("  python orchestrator\\ti_run.py --post-digests data/feeds")
+    print("  python orchestrator\\ti_run.py --prune-history --dry-run")
+    print("  python orchestrator\\ti_run.py --publish-mqtt data/feeds --mqtt-broker broker.local:1883")
+    print("  python orchestrator\\ti_run.py --check-watched data/watch/ids.txt")
 
 
 if __name__ == "__main__":